@@ -25,6 +25,8 @@ bitflags::bitflags! {
         const FREE = 0b0000_0001;
         /// File has tags to scan for
         const HAS_TAGS = 0b0000_0010;
+        /// Waits for a sysop - not listed until it is approved
+        const HELD = 0b0000_0100;
         /// PW protected - it's in the metadata
         const PASSWORD = 0b0001_0000;
         /// File can't be deleted
@@ -64,6 +66,9 @@ impl FileHeader {
     pub fn is_deleted(&self) -> bool {
         self.attribute.contains(FileAttributes::DELETED)
     }
+    pub fn is_held(&self) -> bool {
+        self.attribute.contains(FileAttributes::HELD)
+    }
 
     pub fn set_free(&mut self, free: bool) {
         if free {
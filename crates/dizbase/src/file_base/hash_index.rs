@@ -0,0 +1,158 @@
+//! One index over several file bases, so that an upload can be checked against every
+//! area of a board and not just the directory it lands in. Each base keeps the hashes
+//! in its own database; this only gathers them.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use super::FileBase;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedFile {
+    /// Name of the area the file is listed in.
+    pub area: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    pub hash: u64,
+}
+
+impl IndexedFile {
+    /// How a duplicate names its original, see `MetadataType::Duplicate`.
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.area, self.name)
+    }
+}
+
+/// What an upload clashes with. The same contents weigh more than the same name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Duplicate<'a> {
+    Contents(&'a IndexedFile),
+    Name(&'a IndexedFile),
+}
+
+impl<'a> Duplicate<'a> {
+    pub fn original(&self) -> &'a IndexedFile {
+        match self {
+            Duplicate::Contents(file) | Duplicate::Name(file) => file,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct HashIndex {
+    files: Vec<IndexedFile>,
+    by_hash: HashMap<u64, Vec<usize>>,
+    by_name: HashMap<String, Vec<usize>>,
+}
+
+impl HashIndex {
+    pub fn add_base(&mut self, area: &str, base: &mut FileBase) -> crate::Result<()> {
+        for (header, hash) in base.hashes()? {
+            let path = base.full_path(&header);
+            self.add(IndexedFile {
+                area: area.to_string(),
+                path,
+                name: header.name,
+                size: header.size,
+                hash,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn add(&mut self, file: IndexedFile) {
+        // Every empty file hashes the same; that says nothing about what's in it.
+        if file.size > 0 {
+            self.by_hash.entry(file.hash).or_default().push(self.files.len());
+        }
+        self.by_name.entry(file.name.to_ascii_uppercase()).or_default().push(self.files.len());
+        self.files.push(file);
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Looks for anything already on the board with this name, ignoring case the way
+    /// DOS does.
+    pub fn find_name(&self, name: &str) -> Option<&IndexedFile> {
+        self.by_name.get(&name.to_ascii_uppercase())?.first().map(|index| &self.files[*index])
+    }
+
+    pub fn find_hash(&self, hash: u64) -> Option<&IndexedFile> {
+        self.by_hash.get(&hash)?.first().map(|index| &self.files[*index])
+    }
+
+    /// Checks a new file, which must not be in the index itself yet.
+    pub fn find(&self, name: &str, size: u64, hash: u64) -> Option<Duplicate<'_>> {
+        if size > 0
+            && let Some(file) = self.find_hash(hash)
+        {
+            return Some(Duplicate::Contents(file));
+        }
+        self.find_name(name).map(Duplicate::Name)
+    }
+
+    /// Every set of files with the same contents, each set in the order the areas were added.
+    pub fn duplicates(&self) -> Vec<Vec<&IndexedFile>> {
+        let mut groups: Vec<Vec<&IndexedFile>> = self
+            .by_hash
+            .values()
+            .filter(|indices| indices.len() > 1)
+            .map(|indices| indices.iter().map(|index| &self.files[*index]).collect())
+            .collect();
+        groups.sort_by_key(|group| group[0].path.clone());
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn area(root: &TempDir, name: &str, files: &[(&str, &[u8])]) -> FileBase {
+        let dir = root.path().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        FileBase::open(&dir, dir.join("dir")).unwrap()
+    }
+
+    #[test]
+    fn test_the_same_contents_are_found_in_another_area() {
+        let root = TempDir::new().unwrap();
+        let mut index = HashIndex::default();
+        index
+            .add_base("Games", &mut area(&root, "games", &[("DOOM.ZIP", b"doom"), ("EMPTY.TXT", b"")]))
+            .unwrap();
+        index
+            .add_base("Uploads", &mut area(&root, "uploads", &[("DOOM2.ZIP", b"doom"), ("NOTES.TXT", b"")]))
+            .unwrap();
+
+        let groups = index.duplicates();
+        assert_eq!(groups.len(), 1);
+        let names: Vec<String> = groups[0].iter().map(|file| file.describe()).collect();
+        assert_eq!(names, vec!["Games: DOOM.ZIP", "Uploads: DOOM2.ZIP"]);
+    }
+
+    #[test]
+    fn test_a_new_file_is_checked_by_contents_then_name() {
+        let root = TempDir::new().unwrap();
+        let mut index = HashIndex::default();
+        index.add_base("Games", &mut area(&root, "games", &[("DOOM.ZIP", b"doom")])).unwrap();
+
+        let doom = FileBase::get_hash(&root.path().join("games/DOOM.ZIP")).unwrap();
+        assert!(matches!(index.find("OTHER.ZIP", 4, doom), Some(Duplicate::Contents(file)) if file.name == "DOOM.ZIP"));
+        assert!(matches!(index.find("doom.zip", 3, 1), Some(Duplicate::Name(_))));
+        assert_eq!(index.find("NEW.ZIP", 3, 1), None);
+        // An empty upload is only compared by name.
+        assert_eq!(index.find("NEW.ZIP", 0, doom), None);
+    }
+}
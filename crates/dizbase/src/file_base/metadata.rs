@@ -1,3 +1,5 @@
+use chrono::{DateTime, Utc};
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MetadataType {
     /// Unknown meta data type
@@ -14,6 +16,12 @@ pub enum MetadataType {
     FileID,
     /// Sauce (128 bytes)
     Sauce,
+    /// What the upload check made of the file, see `file_base_scanner::verify`.
+    Verification,
+    /// When the file was uploaded, unix time in milliseconds (i64, little endian).
+    Uploaded,
+    /// An upload whose name or contents were already on the board, as `area: file`.
+    Duplicate,
 }
 
 impl MetadataType {
@@ -25,6 +33,9 @@ impl MetadataType {
             3 => MetadataType::Tags,
             4 => MetadataType::FileID,
             5 => MetadataType::Sauce,
+            6 => MetadataType::Verification,
+            7 => MetadataType::Uploaded,
+            8 => MetadataType::Duplicate,
             _ => MetadataType::Unknown(data),
         }
     }
@@ -38,6 +49,9 @@ impl MetadataType {
             MetadataType::Tags => 3,
             MetadataType::FileID => 4,
             MetadataType::Sauce => 5,
+            MetadataType::Verification => 6,
+            MetadataType::Uploaded => 7,
+            MetadataType::Duplicate => 8,
         }
     }
 }
@@ -55,4 +69,34 @@ impl MetadataHeader {
     pub fn get_type(&self) -> MetadataType {
         self.metadata_type
     }
+
+    pub fn uploaded(time: DateTime<Utc>) -> Self {
+        Self::new(MetadataType::Uploaded, time.timestamp_millis().to_le_bytes().to_vec())
+    }
+
+    pub fn upload_time(&self) -> Option<DateTime<Utc>> {
+        if self.metadata_type != MetadataType::Uploaded {
+            return None;
+        }
+        let millis = i64::from_le_bytes(self.data.as_slice().try_into().ok()?);
+        DateTime::from_timestamp_millis(millis)
+    }
+
+    pub fn tags(tags: &[String]) -> Self {
+        Self::new(MetadataType::Tags, tags.join(",").into_bytes())
+    }
+
+    pub fn tag_list(&self) -> Option<Vec<String>> {
+        if self.metadata_type != MetadataType::Tags {
+            return None;
+        }
+        Some(super::tags::parse_tags(&String::from_utf8_lossy(&self.data)))
+    }
+
+    pub fn hash(&self) -> Option<u64> {
+        if self.metadata_type != MetadataType::Hash {
+            return None;
+        }
+        Some(u64::from_le_bytes(self.data.as_slice().try_into().ok()?))
+    }
 }
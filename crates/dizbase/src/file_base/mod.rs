@@ -20,11 +20,13 @@ use self::{
 };
 
 pub mod file_header;
+pub mod hash_index;
 pub mod metadata;
 pub mod pattern;
+pub mod tags;
 
 /// Bumped whenever the schema changes so that `migrate` knows what to apply.
-const SCHEMA_VERSION: i32 = 1;
+const SCHEMA_VERSION: i32 = 2;
 
 /// Holds the file base's own files, out of the way of anything a user can upload.
 pub const STATE_DIR: &str = ".icy";
@@ -60,6 +62,9 @@ pub struct FileBase {
     reserved_names: HashSet<String>,
     name_map: HashMap<String, usize>,
     file_headers: Vec<FileHeader>,
+    /// Uploads waiting for a sysop. They stay out of `file_headers`, so nothing that
+    /// lists, searches or downloads through the base gets to see them.
+    held: Vec<FileHeader>,
 }
 
 impl Deref for FileBase {
@@ -104,6 +109,7 @@ impl FileBase {
             reserved_names: Self::reserved_names(&meta_data_path),
             name_map: HashMap::new(),
             file_headers: Vec::new(),
+            held: Vec::new(),
         };
         res.load_headers()?;
         if let Err(err) = res.scan_path() {
@@ -136,6 +142,17 @@ impl FileBase {
         if version >= SCHEMA_VERSION {
             return Ok(());
         }
+        if version < 1 {
+            Self::create_tables(connection)?;
+        }
+        if version < 2 {
+            Self::create_tag_table(connection)?;
+        }
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(())
+    }
+
+    fn create_tables(connection: &Connection) -> crate::Result<()> {
         connection.execute_batch(
             "CREATE TABLE IF NOT EXISTS files (
                 id         INTEGER PRIMARY KEY,
@@ -154,7 +171,6 @@ impl FileBase {
             );
             CREATE INDEX IF NOT EXISTS metadata_file_id ON metadata(file_id);",
         )?;
-        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
         Ok(())
     }
 
@@ -175,8 +191,13 @@ impl FileBase {
 
         self.file_headers.clear();
         self.name_map.clear();
+        self.held.clear();
         for header in rows {
             let header = header?;
+            if header.is_held() {
+                self.held.push(header);
+                continue;
+            }
             self.name_map.insert(header.name.clone(), self.file_headers.len());
             self.file_headers.push(header);
         }
@@ -203,7 +224,7 @@ impl FileBase {
                 log::warn!("Skipping file with a non utf-8 name: {}", path.display());
                 continue;
             };
-            if self.name_map.contains_key(file_name) || self.reserved_names.contains(file_name) {
+            if self.contains_name(file_name) || self.reserved_names.contains(file_name) {
                 continue;
             }
             let (date, size) = Self::file_stats(&path);
@@ -247,42 +268,119 @@ impl FileBase {
             .ok_or_else(|| FileBaseError::FileNotFound(file_name.to_string()).into())
     }
 
+    /// Whether a file of that name is listed, held uploads included.
+    pub fn contains_name(&self, name: &str) -> bool {
+        self.name_map.contains_key(name) || self.held.iter().any(|header| header.name == name)
+    }
+
     pub fn add_file(&mut self, path: &Path, metadata: Vec<MetadataHeader>) -> crate::Result<()> {
+        let header = self.insert_file(path, metadata, FileAttributes::NONE)?;
+        self.name_map.insert(header.name.clone(), self.file_headers.len());
+        self.file_headers.push(header);
+        Ok(())
+    }
+
+    /// Adds an upload that only shows up once `approve` is called for it. The time it
+    /// came in is recorded next to whatever the caller passes, usually the uploader.
+    pub fn hold_file(&mut self, path: &Path, mut metadata: Vec<MetadataHeader>) -> crate::Result<()> {
+        if !metadata.iter().any(|header| header.get_type() == MetadataType::Uploaded) {
+            metadata.push(MetadataHeader::uploaded(Utc::now()));
+        }
+        let header = self.insert_file(path, metadata, FileAttributes::HELD)?;
+        self.held.push(header);
+        Ok(())
+    }
+
+    fn insert_file(&mut self, path: &Path, mut metadata: Vec<MetadataHeader>, attribute: FileAttributes) -> crate::Result<FileHeader> {
         let file_name = path
             .file_name()
             .and_then(|n| n.to_str())
             .ok_or_else(|| FileBaseError::FileNotFound(path.display().to_string()))?
             .to_string();
-        if self.name_map.contains_key(&file_name) {
+        if self.contains_name(&file_name) {
             return Err(FileBaseError::FileAlreadyExists(file_name).into());
         }
         let (date, size) = Self::file_stats(path);
 
         let transaction = self.connection.transaction()?;
         transaction.execute(
-            "INSERT INTO files (name, date, size, scanned) VALUES (?1, ?2, ?3, 1)",
-            params![file_name, date.timestamp_millis(), size as i64],
+            "INSERT INTO files (name, date, size, attribute, scanned) VALUES (?1, ?2, ?3, ?4, 1)",
+            params![file_name, date.timestamp_millis(), size as i64, attribute.bits() as i64],
         )?;
         let id = transaction.last_insert_rowid();
+        Self::store_tags(&transaction, id, &mut metadata)?;
         Self::insert_metadata(&transaction, id, &metadata)?;
         transaction.commit()?;
 
-        self.name_map.insert(file_name.clone(), self.file_headers.len());
-        self.file_headers.push(FileHeader {
+        Ok(FileHeader {
             id,
             name: file_name,
             date,
             size,
             dl_counter: 0,
-            attribute: FileAttributes::NONE,
-        });
+            attribute,
+        })
+    }
+
+    /// The uploads waiting for a sysop, oldest first.
+    pub fn held(&self) -> &[FileHeader] {
+        &self.held
+    }
+
+    fn held_index(&self, name: &str) -> crate::Result<usize> {
+        self.held
+            .iter()
+            .position(|header| header.name == name)
+            .ok_or_else(|| FileBaseError::FileNotFound(name.to_string()).into())
+    }
+
+    pub fn held_metadata(&mut self, name: &str) -> crate::Result<Vec<MetadataHeader>> {
+        let header = &self.held[self.held_index(name)?];
+        let (id, path) = (header.id, self.full_path(header));
+        self.metadata_of(id, &path)
+    }
+
+    /// Lists a held upload like any other file.
+    pub fn approve(&mut self, name: &str) -> crate::Result<()> {
+        let index = self.held_index(name)?;
+        let mut header = self.held.remove(index);
+        header.attribute.remove(FileAttributes::HELD);
+        self.connection.execute(
+            "UPDATE files SET attribute = ?2 WHERE id = ?1",
+            params![header.id, header.attribute.bits() as i64],
+        )?;
+        self.name_map.insert(header.name.clone(), self.file_headers.len());
+        self.file_headers.push(header);
         Ok(())
     }
 
+    /// Forgets a held upload and hands back its metadata, so that the caller can delete
+    /// the file or add it to another base. The file itself is left where it is.
+    pub fn take_held(&mut self, name: &str) -> crate::Result<Vec<MetadataHeader>> {
+        let metadata = self.held_metadata(name)?;
+        let index = self.held_index(name)?;
+        let header = self.held.remove(index);
+        self.connection.execute("DELETE FROM files WHERE id = ?1", params![header.id])?;
+        Ok(metadata)
+    }
+
     /// Reads the stored metadata, deriving it from the file itself the first time it is asked for.
     pub fn read_metadata(&mut self, path: &Path) -> crate::Result<Vec<MetadataHeader>> {
         let index = self.header_index(path)?;
         let id = self.file_headers[index].id;
+        self.metadata_of(id, path)
+    }
+
+    fn metadata_of(&mut self, id: i64, path: &Path) -> crate::Result<Vec<MetadataHeader>> {
+        let mut metadata = self.stored_metadata_of(id, path)?;
+        let tags = self.tags_of(id)?;
+        if !tags.is_empty() {
+            metadata.push(MetadataHeader::tags(&tags));
+        }
+        Ok(metadata)
+    }
+
+    fn stored_metadata_of(&mut self, id: i64, path: &Path) -> crate::Result<Vec<MetadataHeader>> {
         let scanned: bool = self
             .connection
             .query_row("SELECT scanned FROM files WHERE id = ?1", params![id], |row| row.get(0))
@@ -306,11 +404,13 @@ impl FileBase {
         Ok(result)
     }
 
-    pub fn write_metadata(&mut self, path: &Path, metadata: Vec<MetadataHeader>) -> crate::Result<()> {
+    /// Tags are only replaced when `metadata` carries a `Tags` entry.
+    pub fn write_metadata(&mut self, path: &Path, mut metadata: Vec<MetadataHeader>) -> crate::Result<()> {
         let index = self.header_index(path)?;
         let id = self.file_headers[index].id;
 
         let transaction = self.connection.transaction()?;
+        Self::store_tags(&transaction, id, &mut metadata)?;
         transaction.execute("DELETE FROM metadata WHERE file_id = ?1", params![id])?;
         Self::insert_metadata(&transaction, id, &metadata)?;
         transaction.execute("UPDATE files SET scanned = 1 WHERE id = ?1", params![id])?;
@@ -436,10 +536,49 @@ impl FileBase {
         Ok(())
     }
 
+    /// The content hash of every file the base knows about, held uploads included.
+    /// Files that were never scanned are scanned now; ones missing from disk are left out.
+    pub fn hashes(&mut self) -> crate::Result<Vec<(FileHeader, u64)>> {
+        let mut stored = HashMap::new();
+        {
+            let mut statement = self.connection.prepare("SELECT file_id, data FROM metadata WHERE type = ?1")?;
+            let rows = statement.query_map(params![MetadataType::Hash.to_data() as i64], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?;
+            for row in rows {
+                let (id, data) = row?;
+                if let Ok(bytes) = data.as_slice().try_into() {
+                    stored.insert(id, u64::from_le_bytes(bytes));
+                }
+            }
+        }
+
+        let headers: Vec<FileHeader> = self.file_headers.iter().chain(self.held.iter()).cloned().collect();
+        let mut result = Vec::with_capacity(headers.len());
+        for header in headers {
+            if let Some(hash) = stored.get(&header.id) {
+                result.push((header, *hash));
+                continue;
+            }
+            let path = self.full_path(&header);
+            if !path.exists() {
+                continue;
+            }
+            if let Some(hash) = self.metadata_of(header.id, &path)?.iter().find_map(MetadataHeader::hash) {
+                result.push((header, hash));
+            }
+        }
+        Ok(result)
+    }
+
     pub fn get_hash(path: &Path) -> crate::Result<u64> {
         let data = fs::read(path)?;
-        let hash = XxHash3_64::oneshot(&data);
-        Ok(hash)
+        Ok(Self::hash_bytes(&data))
+    }
+
+    /// The same hash for contents that are not a file of their own, like an archive member.
+    pub fn hash_bytes(data: &[u8]) -> u64 {
+        XxHash3_64::oneshot(data)
     }
 
     pub fn full_path(&self, entry: &FileHeader) -> PathBuf {
@@ -661,6 +800,51 @@ mod tests {
         assert_eq!(second[0].dl_counter, 3);
     }
 
+    #[test]
+    fn test_a_held_upload_is_not_listed_until_approved() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "ALPHA.TXT", b"alpha");
+        let mut base = base(&dir);
+        write(&dir, "NEW.ZIP", b"new");
+        base.hold_file(
+            &dir.path().join("NEW.ZIP"),
+            vec![MetadataHeader::new(MetadataType::Uploader, b"caller".to_vec())],
+        )
+        .unwrap();
+        assert_eq!(names(&base), vec!["ALPHA.TXT"]);
+        assert_eq!(base.held().len(), 1);
+
+        // Neither a reopen nor the directory scan lets it slip in.
+        drop(base);
+        let mut base = self::base(&dir);
+        assert_eq!(names(&base), vec!["ALPHA.TXT"]);
+        let metadata = base.held_metadata("NEW.ZIP").unwrap();
+        assert!(metadata.iter().any(|m| m.get_type() == MetadataType::Uploader));
+        assert!(metadata.iter().any(|m| m.upload_time().is_some()));
+
+        base.approve("NEW.ZIP").unwrap();
+        assert!(base.held().is_empty());
+        drop(base);
+        assert_eq!(names(&self::base(&dir)), vec!["ALPHA.TXT", "NEW.ZIP"]);
+    }
+
+    #[test]
+    fn test_a_taken_upload_leaves_the_base() {
+        let dir = TempDir::new().unwrap();
+        write(&dir, "NEW.ZIP", b"new");
+        let path = dir.path().join("NEW.ZIP");
+        let mut base = FileBase::open(dir.path(), dir.path().join("dir")).unwrap();
+        base.remove_file(&path).unwrap();
+        base.hold_file(&path, Vec::new()).unwrap();
+        assert!(base.add_file(&path, Vec::new()).is_err());
+
+        let metadata = base.take_held("NEW.ZIP").unwrap();
+        assert!(metadata.iter().any(|m| m.get_type() == MetadataType::Uploaded));
+        assert!(base.held().is_empty());
+        assert!(base.approve("NEW.ZIP").is_err());
+        assert!(path.exists());
+    }
+
     /// Two nodes hold the same area open at once, which the old format could not survive.
     #[test]
     fn test_two_open_bases_can_both_write() {
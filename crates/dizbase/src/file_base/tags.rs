@@ -0,0 +1,191 @@
+//! Tags are kept in a table of their own, indexed by tag, so a search for a tag
+//! does not have to read the metadata of every file in the area. To everything
+//! that reads metadata they still look like a `MetadataType::Tags` entry.
+
+use std::{collections::HashSet, path::Path};
+
+use rusqlite::{Connection, params, params_from_iter};
+
+use super::{
+    FileBase,
+    file_header::FileAttributes,
+    metadata::{MetadataHeader, MetadataType},
+};
+
+/// More than that is a description, not a set of tags.
+pub const MAX_TAGS: usize = 16;
+pub const MAX_TAG_LEN: usize = 24;
+
+/// `#Demo`, `demo` and `DEMO` are the same tag. Anything but letters, digits and
+/// `-_.+` is dropped, a tag that is left empty is no tag.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag: String = tag
+        .trim_start_matches('#')
+        .chars()
+        .filter(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | '.' | '+'))
+        .flat_map(char::to_lowercase)
+        .take(MAX_TAG_LEN)
+        .collect();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Splits on spaces and commas, so both `#demo #1995` and the stored `demo,1995` work.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for tag in text.split(|ch: char| ch.is_whitespace() || ch == ',').filter_map(normalize_tag) {
+        if !tags.contains(&tag) && tags.len() < MAX_TAGS {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+impl FileBase {
+    pub(super) fn create_tag_table(connection: &Connection) -> crate::Result<()> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tags (
+                file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+                tag     TEXT    NOT NULL,
+                PRIMARY KEY (file_id, tag)
+            );
+            CREATE INDEX IF NOT EXISTS tags_tag ON tags(tag);",
+        )?;
+        // Tags written before the table existed were kept with the rest of the metadata.
+        let stored: Vec<(i64, Vec<u8>)> = {
+            let mut statement = connection.prepare("SELECT file_id, data FROM metadata WHERE type = ?1")?;
+            statement
+                .query_map(params![MetadataType::Tags.to_data() as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()?
+        };
+        for (id, data) in stored {
+            Self::insert_tags(connection, id, &parse_tags(&String::from_utf8_lossy(&data)))?;
+        }
+        connection.execute("DELETE FROM metadata WHERE type = ?1", params![MetadataType::Tags.to_data() as i64])?;
+        Ok(())
+    }
+
+    fn insert_tags(connection: &Connection, id: i64, tags: &[String]) -> crate::Result<()> {
+        let mut statement = connection.prepare("INSERT OR IGNORE INTO tags (file_id, tag) VALUES (?1, ?2)")?;
+        for tag in tags {
+            statement.execute(params![id, tag])?;
+        }
+        Ok(())
+    }
+
+    /// Moves a `Tags` entry out of metadata that is about to be stored into the tag table.
+    /// Answers whether there was one, so a caller that passes none keeps the tags it had.
+    pub(super) fn store_tags(connection: &Connection, id: i64, metadata: &mut Vec<MetadataHeader>) -> crate::Result<bool> {
+        let tags: Vec<String> = metadata.iter().filter_map(MetadataHeader::tag_list).flatten().collect();
+        let had_tags = metadata.iter().any(|header| header.get_type() == MetadataType::Tags);
+        metadata.retain(|header| header.get_type() != MetadataType::Tags);
+        if had_tags {
+            connection.execute("DELETE FROM tags WHERE file_id = ?1", params![id])?;
+            Self::insert_tags(connection, id, &tags)?;
+        }
+        Ok(had_tags)
+    }
+
+    pub(super) fn tags_of(&self, id: i64) -> crate::Result<Vec<String>> {
+        let mut statement = self.connection.prepare("SELECT tag FROM tags WHERE file_id = ?1 ORDER BY tag")?;
+        let tags = statement.query_map(params![id], |row| row.get(0))?.collect::<Result<_, _>>()?;
+        Ok(tags)
+    }
+
+    pub fn tags(&self, path: &Path) -> crate::Result<Vec<String>> {
+        self.tags_of(self.file_headers[self.header_index(path)?].id)
+    }
+
+    /// Replaces the tags of a file. They are normalized on the way in.
+    pub fn set_tags(&mut self, path: &Path, tags: &[String]) -> crate::Result<()> {
+        let id = self.file_headers[self.header_index(path)?].id;
+        let tags = parse_tags(&tags.join(","));
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM tags WHERE file_id = ?1", params![id])?;
+        Self::insert_tags(&transaction, id, &tags)?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// The ids of the files that carry every one of `tags`.
+    pub fn tagged(&self, tags: &[String]) -> crate::Result<HashSet<i64>> {
+        let tags = parse_tags(&tags.join(","));
+        if tags.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let placeholders = vec!["?"; tags.len()].join(", ");
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT file_id FROM tags WHERE tag IN ({placeholders}) GROUP BY file_id HAVING COUNT(*) = {}",
+            tags.len()
+        ))?;
+        let ids = statement
+            .query_map(params_from_iter(tags.iter()), |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(ids)
+    }
+
+    /// Every tag of the listed files with how many files carry it, most used first.
+    /// Uploads that wait for a sysop do not count.
+    pub fn tag_cloud(&self) -> crate::Result<Vec<(String, usize)>> {
+        let mut statement = self.connection.prepare(
+            "SELECT tag, COUNT(*) FROM tags JOIN files ON files.id = tags.file_id
+             WHERE (files.attribute & ?1) = 0 GROUP BY tag ORDER BY COUNT(*) DESC, tag",
+        )?;
+        let cloud = statement
+            .query_map(params![FileAttributes::HELD.bits() as i64], |row| {
+                Ok((row.get(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(cloud)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn base_with(dir: &TempDir, names: &[&str]) -> FileBase {
+        for name in names {
+            fs::write(dir.path().join(name), name.as_bytes()).unwrap();
+        }
+        FileBase::open(dir.path(), dir.path().join("dir")).unwrap()
+    }
+
+    fn tags(text: &str) -> Vec<String> {
+        parse_tags(text)
+    }
+
+    #[test]
+    fn test_tags_are_normalized() {
+        assert_eq!(tags("#Demo #1995, demo  c64!"), vec!["demo", "1995", "c64"]);
+        assert_eq!(normalize_tag("#"), None);
+    }
+
+    #[test]
+    fn test_files_are_found_by_all_of_their_tags() {
+        let dir = TempDir::new().unwrap();
+        let mut base = base_with(&dir, &["A.ZIP", "B.ZIP", "C.ZIP"]);
+        base.set_tags(&dir.path().join("A.ZIP"), &tags("demo 1995")).unwrap();
+        base.set_tags(&dir.path().join("B.ZIP"), &tags("demo 1996")).unwrap();
+        let id = |name: &str| base.iter().find(|header| header.name == name).unwrap().id;
+
+        assert_eq!(base.tagged(&tags("#DEMO")).unwrap(), HashSet::from([id("A.ZIP"), id("B.ZIP")]));
+        assert_eq!(base.tagged(&tags("#demo #1995")).unwrap(), HashSet::from([id("A.ZIP")]));
+        assert_eq!(base.tag_cloud().unwrap()[0], ("demo".to_string(), 2));
+    }
+
+    #[test]
+    fn test_tags_show_up_as_metadata_and_survive_a_rescan() {
+        let dir = TempDir::new().unwrap();
+        let mut base = base_with(&dir, &[]);
+        let path = dir.path().join("NEW.ZIP");
+        fs::write(&path, b"new").unwrap();
+        base.add_file(&path, vec![MetadataHeader::tags(&tags("#demo"))]).unwrap();
+
+        base.rescan(&path, true).unwrap();
+        let metadata = base.read_metadata(&path).unwrap();
+        assert_eq!(metadata.iter().find_map(MetadataHeader::tag_list), Some(tags("demo")));
+        assert_eq!(base.tags(&path).unwrap(), tags("demo"));
+    }
+}
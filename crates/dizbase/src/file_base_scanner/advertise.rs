@@ -0,0 +1,202 @@
+use std::{
+    fs,
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use unarc_rs::unified::ArchiveFormat;
+use zip::write::SimpleFileOptions;
+
+/// What a board adds to the archives it hands out: the inverse of what the
+/// fingerprints in `repack` take away.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Advertisement {
+    /// Added under its own name, unless the archive already has a member by that name.
+    pub file: PathBuf,
+    /// Replaces the archive comment, which most unzippers print on opening.
+    pub comment: String,
+    /// Appended to the `FILE_ID.DIZ` the archive carries. An archive without
+    /// one does not get one, the description is the uploader's to write.
+    pub file_id_line: String,
+}
+
+impl Advertisement {
+    pub fn is_empty(&self) -> bool {
+        self.file.as_os_str().is_empty() && self.comment.is_empty() && self.file_id_line.is_empty()
+    }
+}
+
+/// Only zips are touched. Everything else would have to be converted first,
+/// and that is `repack`'s business.
+pub fn is_zip(path: &Path) -> bool {
+    ArchiveFormat::from_path(path) == Some(ArchiveFormat::Zip)
+}
+
+/// Writes `source` to `target` with the advertisement added and answers whether
+/// it did. The members already in the archive are copied without being
+/// unpacked, so they come out byte for byte as they went in.
+pub fn advertise_zip(source: &Path, target: &Path, ad: &Advertisement) -> crate::Result<bool> {
+    if !is_zip(source) || ad.is_empty() {
+        return Ok(false);
+    }
+    let mut target = fs::File::create(target)?;
+    write_advertised(source, &mut target, ad)?;
+    Ok(true)
+}
+
+/// The same for a file that stays where it is. The new archive is moved over
+/// the old one in one step, and keeps its date so the board does not list it
+/// as new.
+pub fn advertise_in_place(path: &Path, ad: &Advertisement) -> crate::Result<bool> {
+    if !is_zip(path) || ad.is_empty() {
+        return Ok(false);
+    }
+    let metadata = fs::metadata(path)?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let temporary = tempfile::NamedTempFile::new_in(directory)?;
+    write_advertised(path, temporary.as_file(), ad)?;
+
+    if let Ok(modified) = metadata.modified() {
+        let _ = temporary.as_file().set_modified(modified);
+    }
+    let _ = temporary.as_file().set_permissions(metadata.permissions());
+    temporary.persist(path)?;
+    Ok(true)
+}
+
+fn write_advertised<W: Write + Seek>(source: &Path, target: W, ad: &Advertisement) -> crate::Result<()> {
+    let mut archive = zip::ZipArchive::new(BufReader::new(fs::File::open(source)?))?;
+    let ad_name = ad.file.file_name().map(|name| name.to_string_lossy().to_string());
+    let mut zip = zip::ZipWriter::new(BufWriter::new(target));
+    let mut has_ad = false;
+
+    for index in 0..archive.len() {
+        let name = archive.by_index_raw(index)?.name().to_string();
+        if ad_name.as_ref().is_some_and(|ad_name| name.eq_ignore_ascii_case(ad_name)) {
+            has_ad = true;
+        }
+        if !ad.file_id_line.is_empty() && name.eq_ignore_ascii_case("FILE_ID.DIZ") {
+            let mut member = archive.by_index(index)?;
+            let mut text = Vec::new();
+            member.read_to_end(&mut text)?;
+            let options = SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .last_modified_time(member.last_modified().unwrap_or_default());
+            drop(member);
+            zip.start_file(name, options)?;
+            zip.write_all(&with_line(text, &ad.file_id_line))?;
+            continue;
+        }
+        zip.raw_copy_file(archive.by_index_raw(index)?)?;
+    }
+
+    if let Some(ad_name) = ad_name
+        && !has_ad
+    {
+        let contents = fs::read(&ad.file)?;
+        zip.start_file(ad_name, SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated))?;
+        zip.write_all(&contents)?;
+    }
+    if ad.comment.is_empty() {
+        zip.set_raw_comment(archive.comment().into());
+    } else {
+        zip.set_comment(ad.comment.clone());
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+/// Adds the line below the description, once. A description that already ends
+/// in it came through here before.
+fn with_line(mut text: Vec<u8>, line: &str) -> Vec<u8> {
+    while text.last().is_some_and(|byte| matches!(byte, b'\r' | b'\n' | b' ' | b'\t' | 0x1A)) {
+        text.pop();
+    }
+    if text.ends_with(line.as_bytes()) {
+        return text;
+    }
+    if !text.is_empty() {
+        text.extend_from_slice(b"\r\n");
+    }
+    text.extend_from_slice(line.as_bytes());
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_base::FileBase;
+    use tempfile::TempDir;
+
+    fn zip_with(path: &Path, members: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, contents) in members {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn member(path: &Path, name: &str) -> Option<Vec<u8>> {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        let mut member = archive.by_name(name).ok()?;
+        let mut contents = Vec::new();
+        member.read_to_end(&mut contents).unwrap();
+        Some(contents)
+    }
+
+    fn ad(directory: &TempDir) -> Advertisement {
+        let file = directory.path().join("MYBBS.NFO");
+        fs::write(&file, b"Call my board").unwrap();
+        Advertisement {
+            file,
+            comment: "Downloaded from My BBS".to_string(),
+            file_id_line: "-= My BBS =-".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_the_copy_carries_the_ad_and_the_original_stays_as_it_was() {
+        let directory = TempDir::new().unwrap();
+        let source = directory.path().join("GAME.ZIP");
+        zip_with(&source, &[("GAME.EXE", b"game"), ("FILE_ID.DIZ", b"A game\r\n\x1a")]);
+        let hash = FileBase::get_hash(&source).unwrap();
+        let target = directory.path().join("copy.zip");
+
+        assert!(advertise_zip(&source, &target, &ad(&directory)).unwrap());
+
+        assert_eq!(member(&target, "GAME.EXE").unwrap(), b"game");
+        assert_eq!(member(&target, "MYBBS.NFO").unwrap(), b"Call my board");
+        assert_eq!(member(&target, "FILE_ID.DIZ").unwrap(), b"A game\r\n-= My BBS =-");
+        let archive = zip::ZipArchive::new(fs::File::open(&target).unwrap()).unwrap();
+        assert_eq!(archive.comment(), b"Downloaded from My BBS");
+        assert_eq!(FileBase::get_hash(&source).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_an_archive_is_only_advertised_once() {
+        let directory = TempDir::new().unwrap();
+        let path = directory.path().join("GAME.ZIP");
+        zip_with(&path, &[("GAME.EXE", b"game"), ("file_id.diz", b"A game")]);
+        let ad = ad(&directory);
+
+        assert!(advertise_in_place(&path, &ad).unwrap());
+        let once = fs::read(&path).unwrap();
+        assert!(advertise_in_place(&path, &ad).unwrap());
+
+        let archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 3);
+        assert_eq!(member(&path, "file_id.diz").unwrap(), b"A game\r\n-= My BBS =-");
+        assert_eq!(fs::read(&path).unwrap().len(), once.len());
+    }
+
+    #[test]
+    fn test_anything_but_a_zip_is_left_alone() {
+        let directory = TempDir::new().unwrap();
+        let path = directory.path().join("GAME.LHA");
+        fs::write(&path, b"not touched").unwrap();
+
+        assert!(!advertise_in_place(&path, &ad(&directory)).unwrap());
+        assert_eq!(fs::read(&path).unwrap(), b"not touched");
+    }
+}
@@ -8,9 +8,11 @@ use crate::file_base::{
     FileBase,
     metadata::{MetadataHeader, MetadataType},
 };
+pub mod advertise;
 pub mod repack;
 
 pub mod bbstro_fingerprint;
+pub mod verify;
 
 pub fn scan_file(path: &Path) -> crate::Result<Vec<MetadataHeader>> {
     let mut info = Vec::new();
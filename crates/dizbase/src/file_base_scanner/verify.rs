@@ -0,0 +1,120 @@
+use std::{fs, io::BufReader, path::Path};
+
+use unarc_rs::unified::{ArchiveFormat, UnifiedArchive};
+
+use crate::file_base::metadata::{MetadataHeader, MetadataType};
+
+/// What the upload check made of a file. Stored as `MetadataType::Verification`:
+/// one status byte, followed by the reason a failed file was turned down.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    Failed(String),
+}
+
+impl Verdict {
+    pub fn is_passed(&self) -> bool {
+        matches!(self, Verdict::Passed)
+    }
+
+    pub fn to_metadata(&self) -> MetadataHeader {
+        let data = match self {
+            Verdict::Passed => vec![0],
+            Verdict::Failed(reason) => {
+                let mut data = vec![1];
+                data.extend_from_slice(reason.as_bytes());
+                data
+            }
+        };
+        MetadataHeader::new(MetadataType::Verification, data)
+    }
+
+    pub fn from_metadata(metadata: &[MetadataHeader]) -> Option<Self> {
+        let data = &metadata.iter().find(|m| m.metadata_type == MetadataType::Verification)?.data;
+        match data.first()? {
+            0 => Some(Verdict::Passed),
+            _ => Some(Verdict::Failed(String::from_utf8_lossy(&data[1..]).to_string())),
+        }
+    }
+}
+
+/// Unpacks every member of an archive without writing anything, the way
+/// `PKUNZIP -t` checks an upload. Files that aren't archives pass untouched.
+/// A member is held in memory while it is checked, so one that unpacks to more
+/// than `max_size` bytes fails the archive, as it does for `read_member`.
+pub fn test_extract(path: &Path, max_size: u64) -> crate::Result<()> {
+    let Some(format) = ArchiveFormat::from_path(path) else {
+        return Ok(());
+    };
+    let mut archive = UnifiedArchive::open_with_format(BufReader::new(fs::File::open(path)?), format)?;
+    while let Some(entry) = archive.next_entry()? {
+        if entry.original_size() > max_size {
+            return Err(format!("{} unpacks to {} bytes, more than {max_size}", entry.file_name(), entry.original_size()).into());
+        }
+        match archive.read(&entry) {
+            Ok(data) if data.len() as u64 > max_size => {
+                return Err(format!("{} unpacked to more than {max_size} bytes", entry.file_name()).into());
+            }
+            Ok(_) => {}
+            Err(err) => return Err(format!("{}: {err}", entry.file_name()).into()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    #[test]
+    fn test_a_verdict_survives_the_metadata() {
+        for verdict in [Verdict::Passed, Verdict::Failed("clamscan: Eicar-Signature FOUND".to_string())] {
+            assert_eq!(Verdict::from_metadata(&[verdict.to_metadata()]), Some(verdict));
+        }
+        assert_eq!(Verdict::from_metadata(&[]), None);
+    }
+
+    #[test]
+    fn test_a_sound_archive_extracts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("GOOD.ZIP");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("README.TXT", SimpleFileOptions::default()).unwrap();
+        zip.write_all(b"hello there").unwrap();
+        zip.finish().unwrap();
+        assert!(test_extract(&path, 1024).is_ok());
+    }
+
+    #[test]
+    fn test_a_member_bigger_than_the_limit_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BOMB.ZIP");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("ZEROS.TXT", SimpleFileOptions::default()).unwrap();
+        zip.write_all(&vec![0; 4 * 1024 * 1024]).unwrap();
+        zip.finish().unwrap();
+
+        assert!(test_extract(&path, 1024 * 1024).is_err());
+        assert!(test_extract(&path, 4 * 1024 * 1024).is_ok());
+    }
+
+    #[test]
+    fn test_a_truncated_archive_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BAD.ZIP");
+        fs::write(&path, b"PK\x03\x04 this is not really a zip").unwrap();
+        assert!(test_extract(&path, 1024).is_err());
+    }
+
+    #[test]
+    fn test_a_plain_file_is_not_an_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("NOTES.TXT");
+        fs::write(&path, b"just text").unwrap();
+        assert!(test_extract(&path, 1024).is_ok());
+    }
+}
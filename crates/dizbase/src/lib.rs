@@ -1,4 +1,4 @@
-use std::{fs, io::BufReader};
+use std::{fs, io::BufReader, path::Path};
 
 use unarc_rs::{
     date_time::DosDateTime,
@@ -12,6 +12,9 @@ mod macros;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+/// What a member may unpack to when it is read or tested, it is held in memory.
+pub const MAX_EXTRACT_SIZE: u64 = 32 * 1024 * 1024;
+
 pub struct FileInfo {
     pub name: String,
     pub size: u64,
@@ -19,6 +22,27 @@ pub struct FileInfo {
     pub date: DosDateTime,
 }
 
+impl FileInfo {
+    /// How much smaller packing made the member, in percent, the way ARC and
+    /// PKZIP listed it.
+    pub fn ratio(&self) -> u64 {
+        if self.size == 0 || self.compressed_size >= self.size {
+            return 0;
+        }
+        100 - self.compressed_size * 100 / self.size
+    }
+}
+
+/// Members a caller can read on the terminal: text and ANSI, judged by the
+/// extension because that is all a listing knows.
+pub fn is_text_member(name: &str) -> bool {
+    const TEXT: [&str; 14] = ["TXT", "ANS", "ASC", "NFO", "DIZ", "DOC", "ME", "1ST", "PCB", "LOG", "BBS", "SDI", "XB", "MD"];
+    let Some((_, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    TEXT.iter().any(|text| text.eq_ignore_ascii_case(extension))
+}
+
 pub fn scan_file_contents(path: &std::path::PathBuf) -> crate::Result<Vec<FileInfo>> {
     let Some(format) = ArchiveFormat::from_path(path) else {
         return Err(format!("Unsupported archive format: {}", path.display()).into());
@@ -37,3 +61,72 @@ pub fn scan_file_contents(path: &std::path::PathBuf) -> crate::Result<Vec<FileIn
 
     Ok(info)
 }
+
+/// Unpacks one member, found by the name `scan_file_contents` listed it under.
+/// A member that unpacks to more than `max_size` bytes is refused before it is
+/// read, a few kilobytes of archive can claim gigabytes.
+pub fn read_member(path: &Path, name: &str, max_size: u64) -> crate::Result<Option<Vec<u8>>> {
+    let Some(format) = ArchiveFormat::from_path(path) else {
+        return Err(format!("Unsupported archive format: {}", path.display()).into());
+    };
+    let mut archive = UnifiedArchive::open_with_format(BufReader::new(fs::File::open(path)?), format)?;
+    while let Some(entry) = archive.next_entry()? {
+        if entry.file_name() == name {
+            if entry.original_size() > max_size {
+                return Err(format!("{name} unpacks to {} bytes, more than {max_size}", entry.original_size()).into());
+            }
+            let data = archive.read(&entry)?;
+            if data.len() as u64 > max_size {
+                return Err(format!("{name} unpacked to more than {max_size} bytes").into());
+            }
+            return Ok(Some(data));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn test_one_member_can_be_read_out_of_an_archive() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let path = directory.path().join("DEMO.ZIP");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, contents) in [("DEMO.EXE", b"binary".as_slice()), ("README.TXT", b"read me".as_slice())] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let listed = scan_file_contents(&path.to_path_buf()).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(read_member(&path, &listed[1].name, 1024).unwrap().unwrap(), b"read me");
+        assert_eq!(read_member(&path, "MISSING.TXT", 1024).unwrap(), None);
+    }
+
+    #[test]
+    fn test_a_member_bigger_than_the_limit_is_not_unpacked() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let path = directory.path().join("BOMB.ZIP");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("ZEROS.TXT", SimpleFileOptions::default()).unwrap();
+        zip.write_all(&vec![0; 4 * 1024 * 1024]).unwrap();
+        zip.finish().unwrap();
+        assert!(fs::metadata(&path).unwrap().len() < 64 * 1024);
+
+        assert!(read_member(&path, "ZEROS.TXT", 1024 * 1024).is_err());
+        assert_eq!(read_member(&path, "ZEROS.TXT", 4 * 1024 * 1024).unwrap().unwrap().len(), 4 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_text_members_are_told_by_their_extension() {
+        assert!(is_text_member("README.TXT"));
+        assert!(is_text_member("docs/intro.ans"));
+        assert!(!is_text_member("DEMO.EXE"));
+        assert!(!is_text_member("README"));
+    }
+}
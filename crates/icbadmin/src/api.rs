@@ -46,6 +46,7 @@ pub fn router(state: AppState) -> Router {
             "/api/conferences/{index}",
             get(api_get_conference).put(api_update_conference).delete(api_delete_conference),
         )
+        .route("/api/uploads/pending", get(api_list_pending_uploads).post(api_resolve_pending_upload))
         .layer(middleware::from_fn(security_headers))
         .with_state(state)
 }
@@ -1119,3 +1120,31 @@ async fn api_delete_conference(
         Err(e) => json_error(&e),
     }
 }
+
+async fn api_list_pending_uploads(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if authenticate(&state, &headers).is_none() {
+        return unauthorized();
+    }
+    match state.backend.list_pending_uploads().await {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => json_error(&e),
+    }
+}
+
+async fn api_resolve_pending_upload(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(body): Json<PendingUploadActionDto>,
+) -> Response {
+    let Some(principal) = authenticate(&state, &headers) else {
+        return unauthorized();
+    };
+    if !check_csrf(&principal, csrf_header(&headers).as_deref()) {
+        return forbidden("missing or invalid CSRF token");
+    }
+    match state.backend.resolve_pending_upload(&body, &actor(&principal, addr)).await {
+        Ok(list) => Json(list).into_response(),
+        Err(e) => json_error(&e),
+    }
+}
@@ -723,3 +723,45 @@ pub struct ConferenceResponse {
     pub file: String,
     pub fingerprint: String,
 }
+
+/// An upload `hold_uploads` kept back. `path` is the directory it waits in and
+/// identifies it together with `name`.
+#[derive(Serialize, Debug)]
+pub struct PendingUploadDto {
+    pub conference: usize,
+    pub location: String,
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub uploader: String,
+    pub uploaded: Option<String>,
+    pub description: String,
+    /// The file already on the board this one duplicates, when `duplicate_uploads` flagged it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UploadLocationDto {
+    pub conference: usize,
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PendingUploadListResponse {
+    pub uploads: Vec<PendingUploadDto>,
+    /// Where `move` can send an upload.
+    pub locations: Vec<UploadLocationDto>,
+}
+
+/// `action` is `approve`, `delete` or `move`; `target` is the path of an upload
+/// location and only read by `move`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PendingUploadActionDto {
+    pub action: String,
+    pub path: String,
+    pub name: String,
+    #[serde(default)]
+    pub target: String,
+}
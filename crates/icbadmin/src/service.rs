@@ -11,6 +11,7 @@ use icy_board_engine::{
         IcyBoard, IcyBoardSerializer,
         conferences::{Conference, ConferenceBase, ConferenceType},
         icb_config::{DisplayNewsBehavior, IcbConfig, PasswordStorageMethod},
+        pending_uploads::{find_pending, pending_uploads, upload_locations},
         security_expr::SecurityExpression,
         user_base::Password,
     },
@@ -86,6 +87,9 @@ pub trait AdminBackend: Send + Sync {
     async fn create_conference(&self, patch: &ConferenceDto, fingerprint: &str, actor: &str) -> Result<ApplyResultDto>;
     async fn update_conference(&self, index: usize, patch: &ConferenceDto, fingerprint: &str, actor: &str) -> Result<ApplyResultDto>;
     async fn delete_conference(&self, index: usize, fingerprint: &str, actor: &str) -> Result<ApplyResultDto>;
+
+    async fn list_pending_uploads(&self) -> Result<PendingUploadListResponse>;
+    async fn resolve_pending_upload(&self, request: &PendingUploadActionDto, actor: &str) -> Result<PendingUploadListResponse>;
 }
 
 // ---------------------------------------------------------------- live backend
@@ -599,6 +603,76 @@ impl AdminBackend for LiveAdminBackend {
         })
         .await
     }
+    async fn list_pending_uploads(&self) -> Result<PendingUploadListResponse> {
+        let board = self.board.lock().await;
+        Ok(pending_upload_list(&board))
+    }
+
+    async fn resolve_pending_upload(&self, request: &PendingUploadActionDto, actor: &str) -> Result<PendingUploadListResponse> {
+        let board = self.board.lock().await;
+        let upload = find_pending(&board, Path::new(&request.path), &request.name)
+            .ok_or_else(|| AdminError::Missing(format!("{} is not waiting for approval in {}", request.name, request.path)))?;
+        let result = match request.action.as_str() {
+            "approve" => upload.approve(),
+            "delete" => upload.delete(),
+            "move" => {
+                let target = upload_locations(&board)
+                    .into_iter()
+                    .find(|location| location.path == Path::new(&request.target))
+                    .ok_or_else(|| AdminError::Validation(vec![format!("{} is not an upload location", request.target)]))?;
+                upload.move_to(&target)
+            }
+            other => {
+                return Err(AdminError::Validation(vec![format!(
+                    "unknown action '{other}', expected approve, delete or move"
+                )]));
+            }
+        };
+        result.map_err(|e| AdminError::Save(e.to_string()))?;
+
+        backup::append_audit(
+            &self.root_path,
+            &serde_json::json!({
+                "time": chrono::Utc::now().to_rfc3339(),
+                "actor": actor,
+                "action": format!("{}_upload", request.action),
+                "mode": "live",
+                "file": upload.path().display().to_string(),
+                "target": request.target,
+                "uploader": upload.uploader,
+            }),
+        );
+        Ok(pending_upload_list(&board))
+    }
+}
+
+// ---------------------------------------------------------------- pending uploads
+
+fn pending_upload_list(board: &IcyBoard) -> PendingUploadListResponse {
+    PendingUploadListResponse {
+        uploads: pending_uploads(board)
+            .into_iter()
+            .map(|upload| PendingUploadDto {
+                conference: upload.location.conference,
+                location: upload.location.name.clone(),
+                path: path_string(&upload.location.path),
+                name: upload.name,
+                size: upload.size,
+                uploader: upload.uploader,
+                uploaded: upload.uploaded.map(|time| time.to_rfc3339()),
+                description: upload.description,
+                duplicate_of: upload.duplicate_of,
+            })
+            .collect(),
+        locations: upload_locations(board)
+            .into_iter()
+            .map(|location| UploadLocationDto {
+                conference: location.conference,
+                name: location.name,
+                path: path_string(&location.path),
+            })
+            .collect(),
+    }
 }
 
 // ---------------------------------------------------------------- overview helpers
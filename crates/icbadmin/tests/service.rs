@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use icbadmin::{
     check_bind_address,
-    dto::GeneralSettingsDto,
+    dto::{GeneralSettingsDto, PendingUploadActionDto},
     error::AdminError,
     service::{AdminBackend, LiveAdminBackend},
 };
@@ -13,6 +13,7 @@ use icy_board_engine::icy_board::{
     conferences::{Conference, ConferenceBase},
     icb_config::IcbConfig,
     lock::LOCK_FILE_NAME,
+    pending_uploads::upload_locations,
 };
 use tokio::sync::Mutex;
 
@@ -532,3 +533,63 @@ async fn live_conference_paths_are_relative() {
     assert_eq!(reloaded[0].news_file, PathBuf::from("main/news.pcb"));
     assert!(running_board.lock().await.conferences[0].intro_file.is_absolute());
 }
+
+/// Puts `name` into the first conference's upload location the way an upload with
+/// `hold_uploads` set would.
+async fn hold_upload(f: &Fixture, name: &str) -> PathBuf {
+    let uploads = f.backend.root_path().join("uploads");
+    fs::create_dir_all(&uploads).unwrap();
+    let mut board = f.board.lock().await;
+    board.conferences[0].pub_upload_location = uploads.clone();
+    let location = upload_locations(&board).into_iter().find(|l| l.path == uploads).unwrap();
+    let mut base = location.open().unwrap();
+    fs::write(uploads.join(name), b"upload").unwrap();
+    base.hold_file(&uploads.join(name), Vec::new()).unwrap();
+    uploads
+}
+
+fn action(action: &str, path: &std::path::Path, name: &str) -> PendingUploadActionDto {
+    PendingUploadActionDto {
+        action: action.to_string(),
+        path: path.display().to_string(),
+        name: name.to_string(),
+        target: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn a_held_upload_is_listed_and_can_be_approved() {
+    let (f, _) = conference_fixture().await;
+    let uploads = hold_upload(&f, "NEW.ZIP").await;
+
+    let list = f.backend.list_pending_uploads().await.unwrap();
+    assert_eq!(list.uploads.len(), 1);
+    assert_eq!(list.uploads[0].name, "NEW.ZIP");
+    assert!(list.uploads[0].uploaded.is_some());
+    assert!(list.locations.iter().any(|l| l.path == uploads.display().to_string()));
+
+    let list = f.backend.resolve_pending_upload(&action("approve", &uploads, "NEW.ZIP"), "test").await.unwrap();
+    assert!(list.uploads.is_empty());
+    let audit = fs::read_to_string(f.backend.root_path().join(icbadmin::backup::AUDIT_LOG_NAME)).unwrap();
+    assert!(audit.contains("approve_upload"));
+}
+
+#[tokio::test]
+async fn only_queued_uploads_can_be_resolved() {
+    let (f, _) = conference_fixture().await;
+    let uploads = hold_upload(&f, "NEW.ZIP").await;
+
+    let err = f
+        .backend
+        .resolve_pending_upload(&action("delete", &uploads, "OTHER.ZIP"), "test")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AdminError::Missing(_)));
+    let err = f
+        .backend
+        .resolve_pending_upload(&action("bless", &uploads, "NEW.ZIP"), "test")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, AdminError::Validation(_)));
+    assert!(uploads.join("NEW.ZIP").exists());
+}
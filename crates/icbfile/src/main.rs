@@ -5,19 +5,28 @@ use std::{
 };
 
 use argh::FromArgs;
+use bstr::BString;
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
 use dizbase::{
-    file_base::{FileBase, file_header::FileHeader},
+    file_base::{FileBase, file_header::FileHeader, hash_index::HashIndex, metadata::MetadataType, tags::parse_tags},
     file_base_scanner::{
         bbstro_fingerprint::FingerprintData,
         repack::{RepackOptions, Repacked, repack_file},
         scan_file,
+        verify::Verdict,
     },
 };
-use icy_board_engine::icy_board::{IcyBoardSerializer, file_directory::DirectoryList};
+use icy_board_engine::icy_board::{
+    IcyBoardSerializer,
+    file_directory::{DirectoryList, FileDirectory},
+};
+use jamjam::jam::{JamMessage, JamMessageBase};
 
 mod listing;
+mod newfiles;
 
 use listing::{Entry, format_files_bbs, parse_files_bbs, parse_pcboard_dir};
+use newfiles::{FeedFormat, NewFile, NewFilesReport};
 
 type Res<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -44,6 +53,10 @@ enum Command {
     Set(Set),
     Repack(Repack),
     Fingerprints(Fingerprints),
+    Pending(Pending),
+    Approve(Approve),
+    Dupes(Dupes),
+    NewFiles(NewFiles),
 }
 
 #[derive(FromArgs)]
@@ -158,7 +171,7 @@ struct Export {
 
 #[derive(FromArgs)]
 #[argh(subcommand, name = "set")]
-/// change the description or the flags of a single file
+/// change the description, the tags or the flags of a single file
 struct Set {
     #[argh(positional)]
     /// a file directory, or a file_areas.toml together with --area
@@ -176,6 +189,10 @@ struct Set {
     /// the new description
     desc: Option<String>,
 
+    #[argh(option, short = 't')]
+    /// the new tags, like "#demo #1995"; an empty string removes them
+    tags: Option<String>,
+
     #[argh(option)]
     /// download costs no time: true or false
     free: Option<bool>,
@@ -223,6 +240,90 @@ struct Fingerprints {
     output: PathBuf,
 }
 
+#[derive(FromArgs)]
+#[argh(subcommand, name = "pending")]
+/// list the uploads of an area that wait for a sysop
+struct Pending {
+    #[argh(positional)]
+    /// a file directory, or a file_areas.toml together with --area
+    target: PathBuf,
+
+    #[argh(option, short = 'a')]
+    /// area name or index when the target is a file_areas.toml
+    area: Option<String>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "approve")]
+/// approve held uploads of an area, or throw them away
+struct Approve {
+    #[argh(positional)]
+    /// a file directory, or a file_areas.toml together with --area
+    target: PathBuf,
+
+    #[argh(positional)]
+    /// the held files, all of them when none is given
+    files: Vec<String>,
+
+    #[argh(option, short = 'a')]
+    /// area name or index when the target is a file_areas.toml
+    area: Option<String>,
+
+    #[argh(switch)]
+    /// delete the files instead of approving them
+    delete: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "dupes")]
+/// report files that are in more than one place, compared by contents
+struct Dupes {
+    #[argh(positional)]
+    /// one or more file_areas.toml, usually one per conference
+    areas: Vec<PathBuf>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "newfiles")]
+/// report the files added to the areas since a date, as a bulletin, a list or a feed
+struct NewFiles {
+    #[argh(positional)]
+    /// one or more file_areas.toml, usually one per conference
+    areas: Vec<PathBuf>,
+
+    #[argh(option, short = 's')]
+    /// the first day to report, as YYYY-MM-DD
+    since: Option<String>,
+
+    #[argh(option, default = "7")]
+    /// how many days back to report when --since is not given
+    days: u32,
+
+    #[argh(option, short = 'f', default = "FeedFormat::Text")]
+    /// ansi, text, rss or json
+    format: FeedFormat,
+
+    #[argh(option, short = 'o')]
+    /// write to this file instead of stdout, ansi and text are encoded as cp437
+    output: Option<PathBuf>,
+
+    #[argh(option, default = "String::from(\"New files\")")]
+    /// the title of the report
+    title: String,
+
+    #[argh(option, default = "String::new()")]
+    /// where the board is reached, the link of the rss feed
+    link: String,
+
+    #[argh(option)]
+    /// also post the text list to this jam message base, given without extension
+    post: Option<PathBuf>,
+
+    #[argh(option, default = "String::from(\"Sysop\")")]
+    /// who the posted message is from
+    from: String,
+}
+
 enum Format {
     Auto,
     PcBoard,
@@ -282,6 +383,10 @@ fn run(cli: Cli) -> Res<()> {
         Command::Set(cmd) => set(&cmd),
         Command::Repack(cmd) => repack(&cmd),
         Command::Fingerprints(cmd) => fingerprints(&cmd),
+        Command::Pending(cmd) => pending(open(&cmd.target, &cmd.area)?),
+        Command::Approve(cmd) => approve(&cmd),
+        Command::Dupes(cmd) => dupes(&cmd),
+        Command::NewFiles(cmd) => newfiles(&cmd),
     }
 }
 
@@ -302,8 +407,12 @@ fn open(target: &Path, area: &Option<String>) -> Res<FileBase> {
     let Some(selector) = area else {
         return Err(format!("{} is an area list, pick one of its areas with --area", target.display()).into());
     };
-    let directory = select_area(&list, selector)?;
-    let base = target.parent().unwrap_or(Path::new("."));
+    open_area(target, select_area(&list, selector)?)
+}
+
+/// Opens an area of the list at `list_path`, whose paths are relative to the list.
+fn open_area(list_path: &Path, directory: &FileDirectory) -> Res<FileBase> {
+    let base = list_path.parent().unwrap_or(Path::new("."));
     let path = resolve(base, &directory.path);
     let metadata_path = if directory.metadata_path.as_os_str().is_empty() {
         path.join("dir")
@@ -313,7 +422,7 @@ fn open(target: &Path, area: &Option<String>) -> Res<FileBase> {
     FileBase::open(&path, metadata_path)
 }
 
-fn select_area<'a>(list: &'a DirectoryList, selector: &str) -> Res<&'a icy_board_engine::icy_board::file_directory::FileDirectory> {
+fn select_area<'a>(list: &'a DirectoryList, selector: &str) -> Res<&'a FileDirectory> {
     if let Ok(index) = selector.parse::<usize>() {
         return list
             .get(index)
@@ -356,6 +465,12 @@ fn list(mut base: FileBase, long: bool) -> Res<()> {
         for line in description.lines() {
             println!("             | {}", line);
         }
+        if long {
+            let tags = base.tags(&path)?;
+            if !tags.is_empty() {
+                println!("             # {}", tags.join(" "));
+            }
+        }
     }
     println!("\n{} file(s)", headers.len());
     Ok(())
@@ -552,6 +667,9 @@ fn set(cmd: &Set) -> Res<()> {
     if let Some(description) = &cmd.desc {
         base.set_description(&path, description)?;
     }
+    if let Some(tags) = &cmd.tags {
+        base.set_tags(&path, &parse_tags(tags))?;
+    }
     if let Some(free) = cmd.free
         && let Some(header) = base.iter_mut().find(|header| header.name == cmd.file)
     {
@@ -671,3 +789,188 @@ fn fingerprints(cmd: &Fingerprints) -> Res<()> {
     println!("wrote the fingerprints of {} to {}", cmd.input.display(), cmd.output.display());
     Ok(())
 }
+
+fn pending(mut base: FileBase) -> Res<()> {
+    let held: Vec<FileHeader> = base.held().to_vec();
+    for header in &held {
+        let metadata = base.held_metadata(&header.name)?;
+        let text = |metadata_type: MetadataType| {
+            metadata
+                .iter()
+                .find(|m| m.get_type() == metadata_type)
+                .map(|m| String::from_utf8_lossy(&m.data).to_string())
+                .unwrap_or_default()
+        };
+        let uploaded = metadata
+            .iter()
+            .find_map(|m| m.upload_time())
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!("{:<12} {:>10}  {}  {}", header.name, header.size, uploaded, text(MetadataType::Uploader));
+        if let Some(Verdict::Failed(reason)) = Verdict::from_metadata(&metadata) {
+            println!("             ! {}", reason);
+        }
+        let original = text(MetadataType::Duplicate);
+        if !original.is_empty() {
+            println!("             = duplicate of {}", original);
+        }
+        for line in text(MetadataType::FileID).lines() {
+            println!("             | {}", line);
+        }
+    }
+    println!("\n{} upload(s) waiting", held.len());
+    Ok(())
+}
+
+fn approve(cmd: &Approve) -> Res<()> {
+    let mut base = open(&cmd.target, &cmd.area)?;
+    let names: Vec<String> = if cmd.files.is_empty() {
+        base.held().iter().map(|header| header.name.clone()).collect()
+    } else {
+        cmd.files.clone()
+    };
+    for name in &names {
+        if cmd.delete {
+            base.take_held(name)?;
+            let path = base.dir().join(name);
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+            println!("deleted {}", name);
+        } else {
+            base.approve(name)?;
+            println!("approved {}", name);
+        }
+    }
+    if names.is_empty() {
+        println!("no upload waiting");
+    }
+    Ok(())
+}
+
+fn dupes(cmd: &Dupes) -> Res<()> {
+    if cmd.areas.is_empty() {
+        return Err("name at least one file_areas.toml".into());
+    }
+    let mut index = HashIndex::default();
+    for list_path in &cmd.areas {
+        let list = DirectoryList::load(list_path).map_err(|err| format!("can't read {}: {}", list_path.display(), err))?;
+        for directory in list.iter() {
+            let mut base = match open_area(list_path, directory) {
+                Ok(base) => base,
+                Err(err) => {
+                    eprintln!("{}: {}", directory.name, err);
+                    continue;
+                }
+            };
+            index.add_base(&directory.name, &mut base)?;
+        }
+    }
+
+    let groups = index.duplicates();
+    for group in &groups {
+        println!("{:016X}  {} bytes", group[0].hash, group[0].size);
+        for file in group {
+            println!("  {:<35} {}", file.area, file.path.display());
+        }
+    }
+    let extra: usize = groups.iter().map(|group| group.len() - 1).sum();
+    println!("\n{} file(s) indexed, {} duplicate(s) in {} set(s)", index.len(), extra, groups.len());
+    Ok(())
+}
+
+fn newfiles(cmd: &NewFiles) -> Res<()> {
+    if cmd.areas.is_empty() {
+        return Err("name at least one file_areas.toml".into());
+    }
+    let since = match &cmd.since {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|err| format!("can't read the date '{}': {}", date, err))?
+            .and_time(NaiveTime::MIN)
+            .and_utc(),
+        None => Utc::now() - Duration::days(cmd.days.into()),
+    };
+
+    let mut files = Vec::new();
+    for list_path in &cmd.areas {
+        let list = DirectoryList::load(list_path).map_err(|err| format!("can't read {}: {}", list_path.display(), err))?;
+        for directory in list.iter() {
+            let mut base = match open_area(list_path, directory) {
+                Ok(base) => base,
+                Err(err) => {
+                    eprintln!("{}: {}", directory.name, err);
+                    continue;
+                }
+            };
+            files.extend(new_files_in(&directory.name, &mut base, since)?);
+        }
+    }
+    let report = NewFilesReport {
+        title: cmd.title.clone(),
+        since,
+        files,
+    };
+
+    let text = report.render(&cmd.format, &cmd.link);
+    match &cmd.output {
+        Some(path) => {
+            match cmd.format {
+                FeedFormat::Ansi | FeedFormat::Text => codepages::tables::write_cp437(path, &text)?,
+                FeedFormat::Rss | FeedFormat::Json => fs::write(path, &text)?,
+            }
+            eprintln!("wrote {} new file(s) to {}", report.files.len(), path.display());
+        }
+        None => print!("{}", text),
+    }
+
+    if let Some(path) = &cmd.post {
+        if report.files.is_empty() {
+            eprintln!("no new files, nothing posted");
+        } else {
+            post(path, &cmd.from, &report.heading(), &report.to_text())?;
+            eprintln!("posted to {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// The files of an area dated `since` or later, the way `N` picks them, newest first.
+fn new_files_in(area: &str, base: &mut FileBase, since: DateTime<Utc>) -> Res<Vec<NewFile>> {
+    let headers: Vec<FileHeader> = base.iter().filter(|header| header.date >= since).cloned().collect();
+    let mut files = Vec::new();
+    for header in headers {
+        let path = base.full_path(&header);
+        let uploader = base
+            .read_metadata(&path)?
+            .iter()
+            .find(|m| m.metadata_type == MetadataType::Uploader)
+            .map(|m| String::from_utf8_lossy(&m.data).to_string());
+        files.push(NewFile {
+            area: area.to_string(),
+            description: base.description(&path)?.unwrap_or_default(),
+            tags: base.tags(&path)?,
+            name: header.name,
+            size: header.size,
+            date: header.date,
+            uploader,
+        });
+    }
+    files.sort_by(|a, b| b.date.cmp(&a.date));
+    Ok(files)
+}
+
+fn post(path: &Path, from: &str, subject: &str, text: &str) -> Res<()> {
+    let mut base = if path.with_extension("jhr").exists() {
+        JamMessageBase::open(path)?
+    } else {
+        JamMessageBase::create(path)?
+    };
+    let message = JamMessage::default()
+        .with_from(BString::from(from))
+        .with_to(BString::from("All"))
+        .with_subject(BString::from(subject))
+        .with_date_time(Utc::now())
+        .with_text(BString::from(text));
+    base.write_message(&message)?;
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! The "new files this week" report: what came into the areas since a date,
+//! rendered as an ANSI bulletin, a plain DIR style list, an RSS feed or JSON.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt::Write as _;
+
+/// One file that is new since the report's date.
+#[derive(Debug, Serialize)]
+pub struct NewFile {
+    pub area: String,
+    pub name: String,
+    pub size: u64,
+    pub date: DateTime<Utc>,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uploader: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewFilesReport {
+    pub title: String,
+    pub since: DateTime<Utc>,
+    /// Grouped by area in the order of the area list, newest first within an area.
+    pub files: Vec<NewFile>,
+}
+
+pub enum FeedFormat {
+    Ansi,
+    Text,
+    Rss,
+    Json,
+}
+
+impl std::str::FromStr for FeedFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ansi" | "ans" => Ok(FeedFormat::Ansi),
+            "text" | "txt" | "dir" => Ok(FeedFormat::Text),
+            "rss" => Ok(FeedFormat::Rss),
+            "json" => Ok(FeedFormat::Json),
+            _ => Err(format!("unknown format '{}', expected ansi, text, rss or json", s)),
+        }
+    }
+}
+
+impl NewFilesReport {
+    pub fn render(&self, format: &FeedFormat, link: &str) -> String {
+        match format {
+            FeedFormat::Ansi => self.to_ansi(),
+            FeedFormat::Text => self.to_text(),
+            FeedFormat::Rss => self.to_rss(link),
+            FeedFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default() + "\n",
+        }
+    }
+
+    fn areas(&self) -> impl Iterator<Item = (&str, &[NewFile])> {
+        self.files.chunk_by(|a, b| a.area == b.area).map(|files| (files[0].area.as_str(), files))
+    }
+
+    pub fn heading(&self) -> String {
+        format!("{} since {}", self.title, self.since.format("%Y-%m-%d"))
+    }
+
+    /// The layout of a PCBoard DIR file, with the area names above their files,
+    /// so it reads the same in a message as it does in a listing.
+    pub fn to_text(&self) -> String {
+        let mut result = String::new();
+        let heading = self.heading();
+        let _ = writeln!(result, "{}\n{}", heading, "=".repeat(heading.chars().count()));
+        for (area, files) in self.areas() {
+            let _ = writeln!(result, "\n{}\n{}", area, "-".repeat(area.chars().count()));
+            for file in files {
+                let _ = write!(result, "{:<12} {:>8}  {}  ", file.name, file.size, file.date.format("%m-%d-%y"));
+                write_description(&mut result, file, &format!("{}| ", " ".repeat(31)));
+            }
+        }
+        let _ = writeln!(result, "\n{} new file(s)", self.files.len());
+        result
+    }
+
+    /// A bulletin in the colours of the board's own listings, to be written as cp437.
+    pub fn to_ansi(&self) -> String {
+        let mut result = String::from("\x1b[0m\x1b[2J");
+        let _ = writeln!(result, "\x1b[1;37m{}\x1b[0m", self.heading());
+        for (area, files) in self.areas() {
+            let _ = writeln!(result, "\n\x1b[1;33m{}\x1b[0m", area);
+            let _ = writeln!(result, "\x1b[0;36m{}\x1b[0m", "\u{2500}".repeat(area.chars().count()));
+            for file in files {
+                let _ = write!(
+                    result,
+                    "\x1b[1;33m{:<12} \x1b[0;32m{:>8}  \x1b[1;32m{}  \x1b[0;37m",
+                    file.name,
+                    file.size,
+                    file.date.format("%m-%d-%y")
+                );
+                write_description(&mut result, file, &format!("{}\x1b[0;36m| \x1b[0;37m", " ".repeat(31)));
+            }
+        }
+        let _ = writeln!(result, "\n\x1b[1;37m{}\x1b[0m new file(s)", self.files.len());
+        result.replace('\n', "\r\n")
+    }
+
+    /// RSS 2.0, one item per file. `link` is where the board is reached, readers
+    /// that insist on a link get that.
+    pub fn to_rss(&self, link: &str) -> String {
+        let mut result = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+        let _ = writeln!(result, "  <title>{}</title>", xml_escape(&self.title));
+        let _ = writeln!(result, "  <link>{}</link>", xml_escape(link));
+        let _ = writeln!(result, "  <description>{}</description>", xml_escape(&self.heading()));
+        let _ = writeln!(result, "  <lastBuildDate>{}</lastBuildDate>", Utc::now().to_rfc2822());
+        for file in &self.files {
+            result.push_str("  <item>\n");
+            let _ = writeln!(result, "    <title>{}</title>", xml_escape(&format!("{}: {}", file.area, file.name)));
+            if !link.is_empty() {
+                let _ = writeln!(result, "    <link>{}</link>", xml_escape(link));
+            }
+            let _ = writeln!(result, "    <description>{}</description>", xml_escape(&plain(&file.description)));
+            let _ = writeln!(result, "    <category>{}</category>", xml_escape(&file.area));
+            for tag in &file.tags {
+                let _ = writeln!(result, "    <category>{}</category>", xml_escape(tag));
+            }
+            let _ = writeln!(
+                result,
+                "    <guid isPermaLink=\"false\">{}</guid>",
+                xml_escape(&format!("{}/{}/{}", file.area, file.name, file.date.timestamp()))
+            );
+            let _ = writeln!(result, "    <pubDate>{}</pubDate>", file.date.to_rfc2822());
+            result.push_str("  </item>\n");
+        }
+        result.push_str("</channel>\n</rss>\n");
+        result
+    }
+}
+
+fn write_description(result: &mut String, file: &NewFile, continuation: &str) {
+    let description = plain(&file.description);
+    let mut lines = description.lines();
+    let _ = writeln!(result, "{}", lines.next().unwrap_or_default());
+    for line in lines {
+        let _ = writeln!(result, "{}{}", continuation, line);
+    }
+    if let Some(uploader) = &file.uploader {
+        let _ = writeln!(result, "{}Uploaded by: {}", continuation, uploader);
+    }
+}
+
+/// A `FILE_ID.DIZ` may come with colours and cursor moves of its own, which
+/// would wreck a report that is not displayed on the board.
+fn plain(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\x1b' => {
+                if chars.peek() == Some(&'[') {
+                    chars.next();
+                    for ch in chars.by_ref() {
+                        if ch.is_ascii_alphabetic() {
+                            break;
+                        }
+                    }
+                }
+            }
+            '\r' | '\x1a' => {}
+            '\n' | '\t' => result.push(ch),
+            ch if ch.is_control() => {}
+            ch => result.push(ch),
+        }
+    }
+    result.trim_end().to_string()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn report() -> NewFilesReport {
+        let date = Utc.with_ymd_and_hms(1995, 3, 2, 12, 0, 0).unwrap();
+        NewFilesReport {
+            title: "New files".to_string(),
+            since: Utc.with_ymd_and_hms(1995, 3, 1, 0, 0, 0).unwrap(),
+            files: vec![
+                NewFile {
+                    area: "Demos".to_string(),
+                    name: "SECOND.ZIP".to_string(),
+                    size: 1024,
+                    date,
+                    description: "\x1b[1;31mSecond Reality\x1b[0m\r\nby Future Crew".to_string(),
+                    uploader: Some("Sysop".to_string()),
+                    tags: vec!["demo".to_string(), "1995".to_string()],
+                },
+                NewFile {
+                    area: "Tools & Utils".to_string(),
+                    name: "PKZ204G.EXE".to_string(),
+                    size: 202574,
+                    date,
+                    description: "PKZIP 2.04g".to_string(),
+                    uploader: None,
+                    tags: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_the_text_list_uses_the_dir_columns() {
+        let text = report().to_text();
+        assert!(text.starts_with("New files since 1995-03-01\n"));
+        assert!(text.contains("\nDemos\n-----\nSECOND.ZIP       1024  03-02-95  Second Reality\n"));
+        assert!(text.contains(&format!("{}| by Future Crew\n", " ".repeat(31))));
+        assert!(text.contains(&format!("{}| Uploaded by: Sysop\n", " ".repeat(31))));
+        assert!(text.ends_with("\n2 new file(s)\n"));
+    }
+
+    #[test]
+    fn test_the_feed_is_escaped_and_carries_the_tags() {
+        let rss = report().to_rss("telnet://bbs.example.com");
+        assert!(rss.contains("<title>Tools &amp; Utils: PKZ204G.EXE</title>"));
+        assert!(rss.contains("<category>demo</category>"));
+        assert!(rss.contains("<description>Second Reality\nby Future Crew</description>"));
+        assert!(rss.contains("<pubDate>Thu, 2 Mar 1995 12:00:00 +0000</pubDate>"));
+    }
+
+    #[test]
+    fn test_the_json_leaves_out_what_a_file_does_not_have() {
+        let json: serde_json::Value = serde_json::from_str(&report().render(&FeedFormat::Json, "")).unwrap();
+        assert_eq!(json["files"][0]["tags"][1], "1995");
+        assert!(json["files"][1].get("uploader").is_none());
+    }
+}
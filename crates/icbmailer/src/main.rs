@@ -1,4 +1,5 @@
 use std::{
+    collections::HashMap,
     fs,
     path::{Path, PathBuf},
     process::exit,
@@ -10,15 +11,18 @@ use icy_board_engine::{
     icy_board::{
         IcyBoard, IcyBoardSerializer,
         ftn::{
-            FtnConfig, FtnLink,
+            FtnLink,
             bundle::{is_bundle, unpack},
+            freq::FreqResolver,
             packet::Packet,
+            tic::process_tics,
             toss::{TossReport, TossTarget, scan_outbound, toss_inbound},
         },
         message_area::MessageArea,
+        qwknet::{QwkHub, scan_reply, toss_packet},
     },
 };
-use icy_net::binkp::{BinkpIdentity, PollRequest};
+use icy_net::binkp::{BinkpIdentity, FileRequests, NoFileRequests, PollRequest};
 
 mod zconnect_experiment;
 
@@ -38,6 +42,7 @@ struct Cli {
 enum Command {
     Links(Links),
     Poll(Poll),
+    Qwk(Qwk),
     Scan(Scan),
     Show(Show),
     Toss(Toss),
@@ -73,6 +78,23 @@ struct Poll {
     verbose: bool,
 }
 
+#[derive(FromArgs)]
+#[argh(subcommand, name = "qwk")]
+/// toss the packets of the QWK hubs and leave a reply with the mail written here
+struct Qwk {
+    #[argh(positional)]
+    /// path/file name of the icyboard.toml configuration file
+    config: PathBuf,
+
+    #[argh(positional)]
+    /// the hub to exchange mail with, every hub when left out
+    hub: Option<String>,
+
+    #[argh(switch, short = 'v')]
+    /// report what the exchange is doing
+    verbose: bool,
+}
+
 #[derive(FromArgs)]
 #[argh(subcommand, name = "show")]
 /// list what is inside a packet or a mail bundle
@@ -88,7 +110,7 @@ struct Show {
 
 #[derive(FromArgs)]
 #[argh(subcommand, name = "toss")]
-/// read the mail waiting in the inbound into the message bases
+/// read the mail waiting in the inbound into the message bases, and the file echos into the file areas
 struct Toss {
     #[argh(positional)]
     /// path/file name of the icyboard.toml configuration file
@@ -131,6 +153,13 @@ async fn main() {
             Ok(mut board) => poll_links(&mut board, arguments.address.as_deref(), arguments.keep).await,
             Err(err) => Err(err),
         },
+        Command::Qwk(arguments) => match read_board(&arguments.config) {
+            Ok(board) => {
+                set_up_logging(arguments.verbose || board.ftn.options.verbose_log);
+                exchange_qwk(&board, arguments.hub.as_deref())
+            }
+            Err(err) => Err(err),
+        },
         Command::Show(arguments) => {
             set_up_logging(false);
             show(&arguments.file, arguments.text)
@@ -168,14 +197,20 @@ fn set_up_logging(verbose: bool) {
 }
 
 fn load(config: &Path) -> Res<IcyBoard> {
-    let mut board = IcyBoard::load(&config)?;
-    board.resolve_paths();
+    let board = read_board(config)?;
     if !board.ftn.is_configured() {
         return Err(format!("{} lists no ftn address, so there is nothing to introduce this board as", config.display()).into());
     }
     Ok(board)
 }
 
+/// A board in a QWK network only needs the board itself, not an ftn address.
+fn read_board(config: &Path) -> Res<IcyBoard> {
+    let mut board = IcyBoard::load(&config)?;
+    board.resolve_paths();
+    Ok(board)
+}
+
 fn list_links(config: &Path) -> Res<()> {
     let board = load(config)?;
     for link in &board.ftn.links {
@@ -215,7 +250,7 @@ async fn poll_links(board: &mut IcyBoard, address: Option<&str>, keep: bool) ->
     let mut failed = 0;
     let mut received = false;
     for link in &selected {
-        match poll_link(&board.ftn, &identity_for(board, link)?, link, keep).await {
+        match poll_link(board, &identity_for(board, link)?, link, keep).await {
             Ok(files) => received |= files,
             Err(err) => {
                 eprintln!("{}: {}", link.to_5d(), err);
@@ -234,7 +269,8 @@ async fn poll_links(board: &mut IcyBoard, address: Option<&str>, keep: bool) ->
 
 /// Answers whether the call brought anything back, which is what decides
 /// whether there is a point in tossing afterwards.
-async fn poll_link(ftn: &FtnConfig, identity: &BinkpIdentity, link: &FtnLink, keep: bool) -> Res<bool> {
+async fn poll_link(board: &IcyBoard, identity: &BinkpIdentity, link: &FtnLink, keep: bool) -> Res<bool> {
+    let ftn = &board.ftn;
     let outbound = outbound_files(&ftn.outbound_for(link))?;
     println!(
         "Calling {} at {}:{} with {} file(s) to hand over",
@@ -254,7 +290,13 @@ async fn poll_link(ftn: &FtnConfig, identity: &BinkpIdentity, link: &FtnLink, ke
         inbound: ftn.inbound.clone(),
         ..Default::default()
     };
-    let result = icy_net::binkp::poll(&request).await?;
+    // A board that published nothing answers every request with nothing.
+    let mut requests: Box<dyn FileRequests> = if ftn.freq.is_enabled() {
+        Box::new(FreqResolver::new(&ftn.freq, link).with_metadata_paths(file_area_metadata_paths(board)))
+    } else {
+        Box::new(NoFileRequests)
+    };
+    let result = icy_net::binkp::poll_with_requests(&request, requests.as_mut()).await?;
 
     println!(
         "  {} answered, running {}{}",
@@ -278,6 +320,9 @@ async fn poll_link(ftn: &FtnConfig, identity: &BinkpIdentity, link: &FtnLink, ke
     for path in &result.batch.skipped {
         println!("  held back for the next call: {}", path.display());
     }
+    for path in &result.batch.requested {
+        println!("  requested by the link: {}", path.display());
+    }
     Ok(!result.batch.received.is_empty())
 }
 
@@ -310,6 +355,23 @@ fn toss(board: &mut IcyBoard) -> Res<()> {
         println!("  left in the inbound, {}: {}", file.display(), err);
     }
     register_new_areas(board, &report)?;
+
+    let report = process_tics(&board.ftn, &file_echo_areas(board), &chrono::Local::now().naive_local())?;
+    if report.imported > 0 || report.forwarded > 0 || report.waiting > 0 || !report.unknown.is_empty() || !report.failed.is_empty() {
+        println!(
+            "{} file(s) placed, {} replaced, {} handed on to downlinks",
+            report.imported, report.replaced, report.forwarded
+        );
+    }
+    if report.waiting > 0 {
+        println!("  {} tic(s) wait for their file", report.waiting);
+    }
+    for (tag, count) in &report.unknown {
+        println!("  {} file(s) arrived for {}, which no file area carries", count, tag);
+    }
+    for (file, err) in &report.failed {
+        println!("  left in the inbound, {}: {}", file.display(), err);
+    }
     Ok(())
 }
 
@@ -364,6 +426,44 @@ fn echo_areas(board: &IcyBoard) -> Vec<(String, PathBuf)> {
     areas
 }
 
+/// The directories whose file base keeps its data somewhere else than next to the files.
+fn file_area_metadata_paths(board: &IcyBoard) -> HashMap<PathBuf, PathBuf> {
+    let mut paths = HashMap::new();
+    for conference in board.conferences.iter() {
+        let Some(list) = &conference.directories else {
+            continue;
+        };
+        for directory in list.iter() {
+            if !directory.metadata_path.as_os_str().is_empty() {
+                paths.insert(directory.path.clone(), directory.metadata_path.clone());
+            }
+        }
+    }
+    paths
+}
+
+/// The file areas fed by a file echo, with the metadata path their file base uses.
+fn file_echo_areas(board: &IcyBoard) -> Vec<(String, PathBuf, PathBuf)> {
+    let mut areas = Vec::new();
+    for conference in board.conferences.iter() {
+        let Some(list) = &conference.directories else {
+            continue;
+        };
+        for directory in list.iter() {
+            if directory.ftn_area_tag.is_empty() {
+                continue;
+            }
+            let metadata_path = if directory.metadata_path.as_os_str().is_empty() {
+                directory.path.join("dir")
+            } else {
+                directory.metadata_path.clone()
+            };
+            areas.push((directory.ftn_area_tag.clone(), directory.path.clone(), metadata_path));
+        }
+    }
+    areas
+}
+
 fn identity_for(board: &IcyBoard, link: &FtnLink) -> Res<BinkpIdentity> {
     let Some(aka) = board.ftn.aka_for(link) else {
         return Err(format!("No address of this board belongs to the network of {}", link.to_5d()).into());
@@ -393,6 +493,77 @@ fn outbound_files(directory: &Path) -> Res<Vec<PathBuf>> {
     Ok(files)
 }
 
+fn exchange_qwk(board: &IcyBoard, hub: Option<&str>) -> Res<()> {
+    let node_id = board.config.qwk_settings.bbs_id.trim().to_ascii_uppercase();
+    if node_id.is_empty() {
+        return Err("A QWK network knows a board by its bbs_id, which the qwk settings leave empty".into());
+    }
+    let selected: Vec<&QwkHub> = board
+        .config
+        .qwk_settings
+        .hubs
+        .iter()
+        .filter(|candidate| hub.is_none_or(|wanted| candidate.name.eq_ignore_ascii_case(wanted)))
+        .collect();
+    if selected.is_empty() {
+        return Err(match hub {
+            Some(wanted) => format!("No QWK hub named {} is configured", wanted).into(),
+            None => "No QWK hubs configured".into(),
+        });
+    }
+
+    let mut failed = 0;
+    for hub in selected {
+        if let Err(err) = exchange_with(hub, &qwk_areas(board, hub), &node_id) {
+            eprintln!("{}: {}", hub.name, err);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} of the hubs could not be served", failed).into());
+    }
+    Ok(())
+}
+
+fn exchange_with(hub: &QwkHub, areas: &[(u16, PathBuf)], node_id: &str) -> Res<()> {
+    if let Some(packet) = hub.fetch()? {
+        let report = toss_packet(&packet, areas, node_id)?;
+        println!(
+            "{}: {} message(s) tossed, {} duplicate(s) and {} looped message(s) dropped",
+            hub.name, report.imported, report.duplicates, report.looped
+        );
+        for (conference, count) in &report.unknown {
+            println!("  {} message(s) arrived for conference {}, which no area carries", count, conference);
+        }
+        fs::remove_file(&packet)?;
+    }
+
+    let report = scan_reply(hub, areas, node_id)?;
+    if report.waiting {
+        println!("{}: the last reply was not picked up yet, nothing added", hub.name);
+    } else if let Some(reply) = &report.reply {
+        println!("{}: {} message(s) packed into {}", hub.name, report.exported, reply.display());
+    }
+    Ok(())
+}
+
+/// The areas that take part in a QWK network, told apart by their conference
+/// number. An area without one is not offered to any hub.
+fn qwk_areas(board: &IcyBoard, hub: &QwkHub) -> Vec<(u16, PathBuf)> {
+    let mut areas = Vec::new();
+    for conference in board.conferences.iter() {
+        let Some(list) = &conference.areas else {
+            continue;
+        };
+        for area in list.iter() {
+            if area.qwk_conference_number != 0 && hub.carries(area.qwk_conference_number) {
+                areas.push((area.qwk_conference_number, area.path.clone()));
+            }
+        }
+    }
+    areas
+}
+
 fn show(file: &Path, with_text: bool) -> Res<()> {
     let name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
     if !is_bundle(name) {
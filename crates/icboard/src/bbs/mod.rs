@@ -170,7 +170,7 @@ pub async fn handle_client(
 }
 
 #[async_recursion(?Send)]
-pub async fn internal_handle_client(mut state: IcyBoardState, login_options: Option<LoginOptions>, stuffed_chars: &str) -> Res<()> {
+pub async fn internal_handle_client(mut state: IcyBoardState, mut login_options: Option<LoginOptions>, stuffed_chars: &str) -> Res<()> {
     let mut logged_in = false;
     let mut local = false;
 
@@ -234,12 +234,19 @@ pub async fn internal_handle_client(mut state: IcyBoardState, login_options: Opt
         log::warn!("Terminal detection failed: {err}");
     }
 
-    if let Some(login_options) = &login_options
-        && let Some(ppe) = &login_options.ppe
+    if let Some(login_options) = &mut login_options
+        && let Some(ppe) = &mut login_options.ppe
     {
+        cmd.state.ppe_debugger = ppe.debugger.take();
+        cmd.state.ppe_profiler = ppe.profiler.take();
         if let Err(err) = cmd.state.run_ppe(&ppe.ppe, None).await {
             log::error!("error running PPE: {}", err);
         };
+        if let Some(profiler) = cmd.state.ppe_profiler.take()
+            && let Err(err) = profiler.save_for(&ppe.ppe)
+        {
+            log::error!("can't write the profile of {}: {}", ppe.ppe.display(), err);
+        }
         cmd.state.new_line().await?;
         if local {
             cmd.state.println(TerminalTarget::Both, &icy_board_tui::get_text("run_ppe_completed")).await?;
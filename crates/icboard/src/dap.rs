@@ -0,0 +1,344 @@
+//! The Debug Adapter Protocol end of `--debug-ppe`. An editor connects over TCP,
+//! its requests go to the PPE's VM and the VM's stops come back as events. There
+//! is one thread, the PPE.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicI64, Ordering},
+};
+
+use icy_board_engine::{
+    Res,
+    vm::{DebugClient, DebugEvent, DebugFrame, DebugRequest, DebugVariable, PpeDebugger, StopReason, debug_channel},
+};
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{
+        TcpListener,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::{Mutex, mpsc, oneshot},
+};
+
+pub const DEFAULT_DEBUG_PORT: u16 = 4711;
+
+const THREAD_ID: i64 = 1;
+
+/// Waits for the editor to connect and answers it from then on. The debugger
+/// that comes back goes to the PPE, which waits for the editor's breakpoints
+/// before it runs.
+///
+/// A connection that closes before its first request only checked whether the
+/// port is open, so the next one is waited for.
+pub async fn listen(port: u16) -> Res<PpeDebugger> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    println!("Waiting for a debugger on port {port}...");
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let (reader, writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        let Ok(Some(first)) = read_message(&mut reader).await else {
+            continue;
+        };
+        log::info!("Debugger connected from {peer}");
+        let (debugger, client) = debug_channel();
+        tokio::spawn(async move {
+            if let Err(err) = serve(reader, writer, client, first).await {
+                log::error!("Debug adapter stopped: {err}");
+            }
+        });
+        return Ok(debugger);
+    }
+}
+
+struct Writer {
+    stream: Mutex<OwnedWriteHalf>,
+    seq: AtomicI64,
+}
+
+impl Writer {
+    async fn send(&self, mut message: Value) -> Res<()> {
+        message["seq"] = json!(self.seq.fetch_add(1, Ordering::Relaxed));
+        let body = message.to_string();
+        let mut stream = self.stream.lock().await;
+        stream.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    async fn event(&self, event: &str, body: Value) -> Res<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body })).await
+    }
+
+    async fn respond(&self, request: &Value, body: Result<Value, String>) -> Res<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": body.is_ok(),
+        });
+        match body {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response).await
+    }
+}
+
+async fn serve(mut reader: BufReader<OwnedReadHalf>, writer: OwnedWriteHalf, client: DebugClient, first: Value) -> Res<()> {
+    let DebugClient { requests, events } = client;
+    let writer = Arc::new(Writer {
+        stream: Mutex::new(writer),
+        seq: AtomicI64::new(1),
+    });
+    tokio::spawn(forward_events(events, writer.clone()));
+
+    let mut stop_on_entry = false;
+    let mut next = Some(first);
+    loop {
+        let request = match next.take() {
+            Some(request) => request,
+            None => match read_message(&mut reader).await? {
+                Some(request) => request,
+                None => break,
+            },
+        };
+        let command = request["command"].as_str().unwrap_or_default().to_string();
+        let arguments = &request["arguments"];
+        let result = match command.as_str() {
+            "initialize" => {
+                writer
+                    .respond(
+                        &request,
+                        Ok(json!({
+                            "supportsConfigurationDoneRequest": true,
+                            "supportsEvaluateForHovers": true,
+                            "supportsTerminateRequest": true,
+                        })),
+                    )
+                    .await?;
+                writer.event("initialized", json!({})).await?;
+                continue;
+            }
+            // The PPE is the one icboard was started with.
+            "launch" | "attach" => {
+                stop_on_entry = arguments["stopOnEntry"].as_bool().unwrap_or(false);
+                Ok(json!({}))
+            }
+            "setBreakpoints" => {
+                let file = arguments["source"]["path"].as_str().unwrap_or_default().into();
+                let lines: Vec<usize> = arguments["breakpoints"]
+                    .as_array()
+                    .map(|breakpoints| breakpoints.iter().filter_map(|bp| bp["line"].as_u64()).map(|line| line as usize).collect())
+                    .unwrap_or_default();
+                let (reply, answer) = oneshot::channel();
+                let _ = requests.send(DebugRequest::SetBreakpoints { file, lines, reply });
+                answer_later(writer.clone(), request, answer, |verified| {
+                    json!({ "breakpoints": verified.into_iter().map(|line| match line {
+                        Some(line) => json!({ "verified": true, "line": line }),
+                        None => json!({ "verified": false, "message": "No code on this line" }),
+                    }).collect::<Vec<_>>() })
+                });
+                continue;
+            }
+            "setExceptionBreakpoints" => Ok(json!({ "breakpoints": [] })),
+            "configurationDone" => {
+                let _ = requests.send(DebugRequest::ConfigurationDone { stop_on_entry });
+                Ok(json!({}))
+            }
+            "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "PPE" }] })),
+            "stackTrace" => {
+                let (reply, answer) = oneshot::channel();
+                let _ = requests.send(DebugRequest::StackTrace(reply));
+                answer_later(
+                    writer.clone(),
+                    request,
+                    answer,
+                    |frames: Vec<DebugFrame>| json!({ "stackFrames": frames.iter().map(frame_json).collect::<Vec<_>>(), "totalFrames": frames.len() }),
+                );
+                continue;
+            }
+            "scopes" => {
+                let (reply, answer) = oneshot::channel();
+                let frame = arguments["frameId"].as_u64().unwrap_or(0) as usize;
+                let _ = requests.send(DebugRequest::Scopes { frame, reply });
+                answer_later(writer.clone(), request, answer, |scopes| {
+                    json!({ "scopes": scopes.iter().map(|scope| json!({
+                        "name": scope.name,
+                        "variablesReference": scope.reference,
+                        "expensive": false,
+                    })).collect::<Vec<_>>() })
+                });
+                continue;
+            }
+            "variables" => {
+                let (reply, answer) = oneshot::channel();
+                let _ = requests.send(DebugRequest::Variables {
+                    reference: arguments["variablesReference"].as_u64().unwrap_or(0) as usize,
+                    start: arguments["start"].as_u64().unwrap_or(0) as usize,
+                    count: arguments["count"].as_u64().unwrap_or(0) as usize,
+                    reply,
+                });
+                answer_later(
+                    writer.clone(),
+                    request,
+                    answer,
+                    |variables: Vec<DebugVariable>| json!({ "variables": variables.iter().map(variable_json).collect::<Vec<_>>() }),
+                );
+                continue;
+            }
+            "evaluate" => {
+                let (reply, answer) = oneshot::channel();
+                let _ = requests.send(DebugRequest::Evaluate {
+                    expression: arguments["expression"].as_str().unwrap_or_default().to_string(),
+                    frame: arguments["frameId"].as_u64().unwrap_or(0) as usize,
+                    reply,
+                });
+                let writer = writer.clone();
+                tokio::spawn(async move {
+                    let body = match answer.await {
+                        Ok(Ok(variable)) => Ok(json!({
+                            "result": variable.value,
+                            "type": variable.type_name,
+                            "variablesReference": variable.reference,
+                            "indexedVariables": variable.indexed,
+                        })),
+                        Ok(Err(message)) => Err(message),
+                        Err(_) => Err("the PPE has ended".to_string()),
+                    };
+                    let _ = writer.respond(&request, body).await;
+                });
+                continue;
+            }
+            "continue" => {
+                let _ = requests.send(DebugRequest::Continue);
+                Ok(json!({ "allThreadsContinued": true }))
+            }
+            "next" => {
+                let _ = requests.send(DebugRequest::StepOver);
+                Ok(json!({}))
+            }
+            "stepIn" => {
+                let _ = requests.send(DebugRequest::StepIn);
+                Ok(json!({}))
+            }
+            "stepOut" => {
+                let _ = requests.send(DebugRequest::StepOut);
+                Ok(json!({}))
+            }
+            "pause" => {
+                let _ = requests.send(DebugRequest::Pause);
+                Ok(json!({}))
+            }
+            "disconnect" | "terminate" => {
+                let _ = requests.send(DebugRequest::Disconnect);
+                writer.respond(&request, Ok(json!({}))).await?;
+                if command == "disconnect" {
+                    break;
+                }
+                continue;
+            }
+            _ => Err(format!("{command} is not supported")),
+        };
+        writer.respond(&request, result).await?;
+    }
+    Ok(())
+}
+
+/// Queries are answered by the VM, which only does so while the PPE is stopped.
+/// The answer is sent when it comes, so reading the next request does not wait for it.
+fn answer_later<T: Send + 'static>(writer: Arc<Writer>, request: Value, answer: oneshot::Receiver<T>, to_json: impl FnOnce(T) -> Value + Send + 'static) {
+    tokio::spawn(async move {
+        let body = answer.await.map(to_json).map_err(|_| "the PPE has ended".to_string());
+        let _ = writer.respond(&request, body).await;
+    });
+}
+
+async fn forward_events(mut events: mpsc::UnboundedReceiver<DebugEvent>, writer: Arc<Writer>) {
+    while let Some(event) = events.recv().await {
+        let result = match event {
+            DebugEvent::Stopped(reason) => {
+                let mut body = json!({
+                    "reason": match &reason {
+                        StopReason::Entry => "entry",
+                        StopReason::Breakpoint => "breakpoint",
+                        StopReason::Step => "step",
+                        StopReason::Pause => "pause",
+                        StopReason::Exception(_) => "exception",
+                    },
+                    "threadId": THREAD_ID,
+                    "allThreadsStopped": true,
+                });
+                if let StopReason::Exception(message) = reason {
+                    body["description"] = json!("Runtime error");
+                    body["text"] = json!(message);
+                }
+                writer.event("stopped", body).await
+            }
+            DebugEvent::Exited => {
+                let _ = writer.event("exited", json!({ "exitCode": 0 })).await;
+                writer.event("terminated", json!({})).await
+            }
+        };
+        if result.is_err() {
+            break;
+        }
+    }
+}
+
+fn frame_json(frame: &DebugFrame) -> Value {
+    let mut result = json!({
+        "id": frame.id,
+        "name": frame.name,
+        "line": frame.line,
+        "column": if frame.line > 0 { 1 } else { 0 },
+    });
+    if let Some(file) = &frame.file {
+        result["source"] = json!({
+            "name": file.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+            "path": file,
+        });
+    }
+    result
+}
+
+fn variable_json(variable: &DebugVariable) -> Value {
+    let mut result = json!({
+        "name": variable.name,
+        "value": variable.value,
+        "type": variable.type_name,
+        "variablesReference": variable.reference,
+    });
+    if variable.indexed > 0 {
+        result["indexedVariables"] = json!(variable.indexed);
+    }
+    result
+}
+
+/// One message: `Content-Length` headers, an empty line, then that many bytes of JSON.
+async fn read_message(reader: &mut BufReader<OwnedReadHalf>) -> Res<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("Content-Length")
+        {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let mut body = vec![0; length.unwrap_or_default()];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
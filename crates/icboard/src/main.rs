@@ -19,6 +19,7 @@ use crossterm::{
 use icy_board_engine::{
     Res,
     icy_board::{IcyBoard, bbs::BBS, lock::BoardLock, state::PPEExecute},
+    vm::{ProfileMode, Profiler},
 };
 
 use node_monitoring_screen::{NodeMonitoringScreenMessage, WebAdminInfo};
@@ -35,6 +36,7 @@ const WEB_ADMIN_TOKEN_ENV: &str = "ICBADMIN_TOKEN";
 
 pub mod bbs;
 mod call_wait_screen;
+mod dap;
 mod event_scheduler;
 pub mod menu_runner;
 mod node_monitoring_screen;
@@ -62,6 +64,18 @@ struct Cli {
     /// execute PPE file
     ppe: Option<PathBuf>,
 
+    #[argh(option)]
+    /// execute PPE file under a Debug Adapter Protocol debugger (compile it with pplc --debug)
+    debug_ppe: Option<PathBuf>,
+
+    #[argh(option)]
+    /// execute PPE file and write a profile next to it, NAME.profile and NAME.folded for flame graphs
+    profile_ppe: Option<PathBuf>,
+
+    #[argh(option, default = "dap::DEFAULT_DEBUG_PORT")]
+    /// port the debugger connects to (default 4711)
+    debug_port: u16,
+
     #[argh(option)]
     /// run PPE with user login: "first;last;PWRD:password;PPE:file.ppe;param1;param2;..."
     runppe: Option<String>,
@@ -148,6 +162,32 @@ async fn start_icy_board(arguments: &Cli, file: PathBuf) -> Res<()> {
                 return Ok(());
             }
 
+            if let Some(ppe) = arguments.debug_ppe.as_ref().or(arguments.profile_ppe.as_ref()) {
+                let debugger = match &arguments.debug_ppe {
+                    Some(_) => Some(dap::listen(arguments.debug_port).await?),
+                    None => None,
+                };
+                let _terminal = init_terminal()?;
+                stdout().execute(Clear(crossterm::terminal::ClearType::All)).unwrap();
+                let execute = PPEExecute {
+                    ppe: ppe.clone(),
+                    user_name: None,
+                    password: None,
+                    args: Vec::new(),
+                    debugger,
+                    profiler: arguments.profile_ppe.is_some().then(|| Profiler::new(ProfileMode::Profile)),
+                };
+                let result = match Tui::local_mode(&board, &bbs, false, Some(execute), stuffed).await {
+                    Ok(mut tui) => tui.run(&mut bbs, &board).await,
+                    Err(err) => Err(err),
+                };
+                restore_terminal()?;
+                if let Err(err) = &result {
+                    log::error!("while running {}: {}", ppe.display(), err);
+                }
+                return result;
+            }
+
             // Handle /runppe parameter
             if let Some(runppe_params) = &arguments.runppe {
                 match handle_runppe(runppe_params).await {
@@ -427,6 +467,8 @@ where
                     user_name: name_opt,
                     password: pw_opt,
                     args: params_opt.unwrap_or_default(),
+                    debugger: None,
+                    profiler: None,
                 }),
                 stuffed_chars,
             )
@@ -236,6 +236,8 @@ pub fn test_ppe_output_with_input<P: Fn(&mut IcyBoard)>(source: &str, input: &st
             user_name: None,
             password: None,
             args: Vec::new(),
+            debugger: None,
+            profiler: None,
         }),
         true,
     )
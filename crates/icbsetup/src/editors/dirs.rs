@@ -9,7 +9,7 @@ use icy_board_engine::{
     Res,
     icy_board::{
         IcyBoard, IcyBoardSerializer,
-        file_directory::{DirectoryList, FileDirectory, SortDirection, SortOrder},
+        file_directory::{Advertise, DirectoryList, FileDirectory, SortDirection, SortOrder},
         security_expr::SecurityExpression,
         user_base::Password,
     },
@@ -320,6 +320,55 @@ impl<'a> Page for DirsEditor<'a> {
                                     },
                                 ),
                             ),
+                            ConfigEntry::Item(
+                                ListItem::new(
+                                    get_text("dirs_edit_advertise"),
+                                    ListValue::ComboBox(ComboBox {
+                                        cur_value: ComboBoxValue::new(format!("{:?}", item.advertise), format!("{:?}", item.advertise)),
+                                        selected_item: 0,
+                                        is_edit_open: false,
+                                        first_item: 0,
+                                        values: Advertise::iter()
+                                            .map(|x| ComboBoxValue::new(format!("{:?}", x), format!("{:?}", x)))
+                                            .collect::<Vec<ComboBoxValue>>(),
+                                    }),
+                                )
+                                .with_label_width(16)
+                                .with_update_combobox_value(
+                                    &|(i, list): &(usize, Arc<Mutex<DirectoryList>>), value: &ComboBox| {
+                                        let advertise = match value.cur_value.value.as_str() {
+                                            "Upload" => Advertise::Upload,
+                                            "Download" => Advertise::Download,
+                                            _ => Advertise::Off,
+                                        };
+                                        list.lock().unwrap()[*i].advertise = advertise;
+                                    },
+                                ),
+                            ),
+                            ConfigEntry::Item(
+                                ListItem::new(get_text("dirs_edit_ad_file"), ListValue::Path(item.ad_file.clone()))
+                                    .with_label_width(16)
+                                    .with_update_path_value(&|(i, list): &(usize, Arc<Mutex<DirectoryList>>), value: PathBuf| {
+                                        list.lock().unwrap()[*i].ad_file = value;
+                                    }),
+                            ),
+                            ConfigEntry::Item(
+                                ListItem::new(get_text("dirs_edit_ad_comment"), ListValue::Text(60, TextFlags::None, item.ad_comment.clone()))
+                                    .with_label_width(16)
+                                    .with_update_text_value(&|(i, list): &(usize, Arc<Mutex<DirectoryList>>), value: String| {
+                                        list.lock().unwrap()[*i].ad_comment = value;
+                                    }),
+                            ),
+                            ConfigEntry::Item(
+                                ListItem::new(
+                                    get_text("dirs_edit_ad_file_id_line"),
+                                    ListValue::Text(45, TextFlags::None, item.ad_file_id_line.clone()),
+                                )
+                                .with_label_width(16)
+                                .with_update_text_value(&|(i, list): &(usize, Arc<Mutex<DirectoryList>>), value: String| {
+                                    list.lock().unwrap()[*i].ad_file_id_line = value;
+                                }),
+                            ),
                         ],
                     });
                 } else {
@@ -273,6 +273,7 @@ impl<'a> Page for DoorEditor<'a> {
                                 door_type: DoorType::BBSlink,
                                 path: "".to_string(),
                                 drop_file: Default::default(),
+                                permissions: None,
                             });
                         }
                         self.insert_table.content_length += 1;
@@ -46,9 +46,10 @@ use icy_board_engine::{
         PCBoardRecordImporter,
         accounting_cfg::AccountingConfig,
         commands::CommandType,
-        doors::DoorList,
+        doors::{DoorList, DropFile},
         icb_config::{
-            AccountingOptions, ConfigSwitches, EventOptions, FileTransferOptions, LimitOptions, MessageOptions, SystemControlOptions, UserCommandLevels,
+            AccountingOptions, ConfigSwitches, DuplicateUploads, EditorInfoFile, EventOptions, FileTransferOptions, LimitOptions, MessageOptions,
+            SystemControlOptions, UserCommandLevels,
         },
         lookup_case_insensitive,
         security_expr::SecurityExpression,
@@ -636,6 +637,10 @@ impl PCBoardImporter {
                 default_scan_all_selected_confs_at_login: self.data.scan_all,
                 force_comments_to_main: self.data.force_main,
                 update_last_read_pointer: self.data.last_read_update,
+                // PCBOARD.DAT has no editor hook, external editors were doors.
+                external_editor: String::new(),
+                external_editor_info: EditorInfoFile::default(),
+                external_editor_drop_file: DropFile::None,
             },
             file_transfer: FileTransferOptions {
                 verify_files_uploaded: self.data.test_uploads,
@@ -643,12 +648,18 @@ impl PCBoardImporter {
                 upload_descr_lines: self.data.num_ul_desc_lines as u8,
                 // PCBoard listed whatever the DIR file held, so an import keeps it.
                 strip_colors_in_descriptions: false,
+                show_thumbnails: false,
+                ask_upload_tags: false,
                 disallow_batch_uploads: self.data.no_batch_up,
                 promote_to_batch_transfers: self.data.promote_batch,
                 upload_credit_time: self.data.upload_credit.max(0) as u32,
                 upload_credit_bytes: self.data.byte_credit.max(0) as u32,
                 disable_drive_size_check: self.data.disable_drive_check,
                 stop_uploads_free_space: self.data.stop_free_space.max(0) as u32,
+                upload_checkers: Vec::new(),
+                quarantine_path: PathBuf::new(),
+                hold_uploads: false,
+                duplicate_uploads: DuplicateUploads::Allow,
             },
             system_control: SystemControlOptions {
                 disable_ns_logon: self.data.disable_quick,
@@ -686,6 +697,8 @@ impl PCBoardImporter {
                 sysop_start: IcbTime::parse(&self.data.sysop_start),
                 sysop_stop: IcbTime::parse(&self.data.sysop_stop),
                 max_number_upload_descr_lines: self.data.num_ul_desc_lines as u16,
+                ppe_instruction_budget: 0,
+                ppe_idle_timeout: 0,
             },
             options: BoardOptions {
                 give_user_password_to_doors: false,
@@ -1,10 +1,10 @@
 use std::sync::{Arc, Mutex};
 
 use crossterm::event::KeyEvent;
-use icy_board_engine::icy_board::IcyBoard;
+use icy_board_engine::icy_board::{IcyBoard, icb_config::DuplicateUploads};
 use icy_board_tui::{
     cfg_entry_bool, cfg_entry_u32,
-    config_menu::{ConfigEntry, ConfigMenu, ResultState},
+    config_menu::{ConfigEntry, ConfigMenu, ListItem, ListValue, ResultState, Value},
     get_text,
     icbconfigmenu::ICBConfigMenuUI,
     tab_page::{Page, PageMessage},
@@ -27,7 +27,32 @@ impl FileTransfers {
                 cfg_entry_u32!("upload_credit_bytes", label_with, 0, 10000, file_transfer, upload_credit_bytes, lock),
                 cfg_entry_bool!("display_uploader", label_with, file_transfer, display_uploader, lock),
                 cfg_entry_bool!("strip_colors_in_descriptions", label_with, file_transfer, strip_colors_in_descriptions, lock),
+                cfg_entry_bool!("show_thumbnails", label_with, file_transfer, show_thumbnails, lock),
+                cfg_entry_bool!("ask_upload_tags", label_with, file_transfer, ask_upload_tags, lock),
                 cfg_entry_bool!("verify_files_uploaded", label_with, file_transfer, verify_files_uploaded, lock),
+                cfg_entry_bool!("hold_uploads", label_with, file_transfer, hold_uploads, lock),
+                ConfigEntry::Item(
+                    ListItem::new(
+                        get_text("duplicate_uploads"),
+                        ListValue::ValueList(
+                            duplicate_uploads_value(lock.config.file_transfer.duplicate_uploads).to_string(),
+                            vec![Value::new("Allow", "allow"), Value::new("Flag", "flag"), Value::new("Reject", "reject")],
+                        ),
+                    )
+                    .with_status(get_text("duplicate_uploads-status"))
+                    .with_help(get_text("duplicate_uploads-help"))
+                    .with_label_width(label_with)
+                    .with_update_value(Box::new(|board: &Arc<Mutex<IcyBoard>>, value: &ListValue| {
+                        let ListValue::ValueList(val, _) = value else {
+                            return;
+                        };
+                        board.lock().unwrap().config.file_transfer.duplicate_uploads = match val.as_str() {
+                            "flag" => DuplicateUploads::Flag,
+                            "reject" => DuplicateUploads::Reject,
+                            _ => DuplicateUploads::Allow,
+                        };
+                    })),
+                ),
                 cfg_entry_bool!("disable_drive_size_check", label_with, file_transfer, disable_drive_size_check, lock),
                 ConfigEntry::Separator,
                 cfg_entry_u32!("stop_uploads_free_space", 41, 0, 1024 * 1024, file_transfer, stop_uploads_free_space, lock),
@@ -41,6 +66,14 @@ impl FileTransfers {
     }
 }
 
+fn duplicate_uploads_value(duplicates: DuplicateUploads) -> &'static str {
+    match duplicates {
+        DuplicateUploads::Allow => "allow",
+        DuplicateUploads::Flag => "flag",
+        DuplicateUploads::Reject => "reject",
+    }
+}
+
 impl Page for FileTransfers {
     fn render(&mut self, frame: &mut ratatui::Frame, disp_area: ratatui::prelude::Rect) {
         self.menu.render(frame, disp_area)
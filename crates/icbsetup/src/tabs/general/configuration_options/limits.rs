@@ -3,7 +3,7 @@ use std::sync::{Arc, Mutex};
 use crossterm::event::KeyEvent;
 use icy_board_engine::icy_board::IcyBoard;
 use icy_board_tui::{
-    cfg_entry_u8, cfg_entry_u16,
+    cfg_entry_u8, cfg_entry_u16, cfg_entry_u32,
     config_menu::{ConfigEntry, ConfigMenu, ResultState},
     get_text,
     icbconfigmenu::ICBConfigMenuUI,
@@ -25,6 +25,9 @@ impl Limits {
                 cfg_entry_u16!("password_expire_days", label_width, 0, 10000, limits, password_expire_days, lock),
                 cfg_entry_u16!("password_expire_warn_days", label_width, 0, 10000, limits, password_expire_warn_days, lock),
                 cfg_entry_u8!("min_pwd_length", label_width, 0, 10000, limits, min_pwd_length, lock),
+                ConfigEntry::Separator,
+                cfg_entry_u32!("ppe_instruction_budget", label_width, 0, u32::MAX, limits, ppe_instruction_budget, lock),
+                cfg_entry_u16!("ppe_idle_timeout", label_width, 0, 3600, limits, ppe_idle_timeout, lock),
             ];
             ConfigMenu { obj: icy_board.clone(), entry }
         };
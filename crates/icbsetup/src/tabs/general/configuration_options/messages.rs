@@ -3,7 +3,7 @@ use std::sync::{Arc, Mutex};
 use crossterm::event::KeyEvent;
 use icy_board_engine::icy_board::IcyBoard;
 use icy_board_tui::{
-    cfg_entry_bool, cfg_entry_u16,
+    cfg_entry_bool, cfg_entry_text, cfg_entry_u16,
     config_menu::{ConfigEntry, ConfigMenu, ResultState},
     get_text,
     icbconfigmenu::ICBConfigMenuUI,
@@ -38,6 +38,8 @@ impl Messages {
                 cfg_entry_bool!("prompt_to_read_mail", label_width, message, prompt_to_read_mail, lock),
                 cfg_entry_bool!("force_comments_to_main", label_width, message, force_comments_to_main, lock),
                 cfg_entry_bool!("update_last_read_pointer", label_width, message, update_last_read_pointer, lock),
+                ConfigEntry::Separator,
+                cfg_entry_text!("message_external_editor", label_width, 30, message, external_editor, lock),
             ];
             ConfigMenu { obj: icy_board.clone(), entry }
         };
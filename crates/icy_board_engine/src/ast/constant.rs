@@ -43,7 +43,7 @@ impl BuiltinConst {
 }
 
 pub const STACK_LIMIT: i32 = 6022 + 1024;
-pub const BUILTIN_CONSTS: [BuiltinConst; 184] = [
+pub const BUILTIN_CONSTS: [BuiltinConst; 194] = [
     BuiltinConst { name: "TRUE", value: 0x01 },
     BuiltinConst { name: "FALSE", value: 0x00 },
     BuiltinConst {
@@ -507,6 +507,7 @@ pub const BUILTIN_CONSTS: [BuiltinConst; 184] = [
         value: 6,
     },
     BuiltinConst { name: "ERR_STACK", value: 7 },
+    BuiltinConst { name: "ERR_DENIED", value: 8 },
     BuiltinConst {
         name: "ERR_KIND_NONE",
         value: 0,
@@ -535,6 +536,27 @@ pub const BUILTIN_CONSTS: [BuiltinConst; 184] = [
         name: "ERR_KIND_SOUND",
         value: 6,
     },
+    BuiltinConst {
+        name: "ERR_KIND_SANDBOX",
+        value: 7,
+    },
+    BuiltinConst {
+        name: "ERR_KIND_JSON",
+        value: 8,
+    },
+    BuiltinConst {
+        name: "ERR_KIND_WEB",
+        value: 9,
+    },
+    BuiltinConst { name: "JSON_NULL", value: 0 },
+    BuiltinConst {
+        name: "JSON_BOOLEAN",
+        value: 1,
+    },
+    BuiltinConst { name: "JSON_NUMBER", value: 2 },
+    BuiltinConst { name: "JSON_STRING", value: 3 },
+    BuiltinConst { name: "JSON_ARRAY", value: 4 },
+    BuiltinConst { name: "JSON_OBJECT", value: 5 },
     BuiltinConst { name: "FONT_ALL", value: -1 },
 ];
 
@@ -5,7 +5,7 @@ use crate::{
     parser::lexer::{Spanned, Token},
 };
 
-use super::{AstVisitorMut, Constant, Expression, Statement, constant::NumberFormat};
+use super::{AstNode, AstVisitorMut, Constant, Expression, Statement, constant::NumberFormat};
 #[derive(Debug, PartialEq, Clone)]
 pub struct DimensionSpecifier {
     dimension_token: Spanned<Token>,
@@ -816,6 +816,115 @@ impl EnumDeclarationAstNode {
     }
 }
 
+/// One declaration inside `MODULE ... ENDMODULE`, with the `PUBLIC` or `PRIVATE` it was given.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModuleMember {
+    visibility_token: Option<Spanned<Token>>,
+    node: AstNode,
+}
+
+impl ModuleMember {
+    pub fn new(visibility_token: Option<Spanned<Token>>, node: AstNode) -> Self {
+        Self { visibility_token, node }
+    }
+
+    pub fn get_visibility_token(&self) -> Option<&Spanned<Token>> {
+        self.visibility_token.as_ref()
+    }
+
+    /// A member is private unless it is marked `PUBLIC`.
+    pub fn is_public(&self) -> bool {
+        matches!(self.visibility_token, Some(Spanned { token: Token::Public, .. }))
+    }
+
+    pub fn get_node(&self) -> &AstNode {
+        &self.node
+    }
+
+    /// The names the member brings into the module, with the tokens that declare them.
+    pub fn get_declared_tokens(&self) -> Vec<&Spanned<Token>> {
+        match &self.node {
+            AstNode::Function(function) => vec![function.get_identifier_token()],
+            AstNode::Procedure(procedure) => vec![procedure.get_identifier_token()],
+            AstNode::FunctionDeclaration(declaration) => vec![declaration.get_identifier_token()],
+            AstNode::ProcedureDeclaration(declaration) => vec![declaration.get_identifier_token()],
+            AstNode::TopLevelStatement(Statement::VariableDeclaration(declaration)) => {
+                declaration.get_variables().iter().map(|variable| variable.get_identifier_token()).collect()
+            }
+            AstNode::TopLevelStatement(Statement::ConstDeclaration(declaration)) => vec![declaration.get_identifier_token()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// `MODULE name ... ENDMODULE` - routines and globals that other code reaches as `name.member`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ModuleAstNode {
+    module_token: Spanned<Token>,
+    identifier_token: Spanned<Token>,
+    members: Vec<ModuleMember>,
+    endmodule_token: Spanned<Token>,
+}
+
+impl ModuleAstNode {
+    pub fn new(module_token: Spanned<Token>, identifier_token: Spanned<Token>, members: Vec<ModuleMember>, endmodule_token: Spanned<Token>) -> Self {
+        Self {
+            module_token,
+            identifier_token,
+            members,
+            endmodule_token,
+        }
+    }
+
+    pub fn get_module_token(&self) -> &Spanned<Token> {
+        &self.module_token
+    }
+    pub fn get_identifier_token(&self) -> &Spanned<Token> {
+        &self.identifier_token
+    }
+    pub fn get_identifier(&self) -> &unicase::Ascii<String> {
+        let Token::Identifier(id) = &self.identifier_token.token else {
+            panic!("Expected identifier token")
+        };
+        id
+    }
+    pub fn get_members(&self) -> &[ModuleMember] {
+        &self.members
+    }
+    pub fn get_endmodule_token(&self) -> &Spanned<Token> {
+        &self.endmodule_token
+    }
+}
+
+/// `IMPORT name` - lets the file reach the public members of module `name`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ImportAstNode {
+    import_token: Spanned<Token>,
+    identifier_token: Spanned<Token>,
+}
+
+impl ImportAstNode {
+    pub fn new(import_token: Spanned<Token>, identifier_token: Spanned<Token>) -> Self {
+        Self {
+            import_token,
+            identifier_token,
+        }
+    }
+
+    pub fn get_import_token(&self) -> &Spanned<Token> {
+        &self.import_token
+    }
+    pub fn get_identifier_token(&self) -> &Spanned<Token> {
+        &self.identifier_token
+    }
+    pub fn get_identifier(&self) -> &unicase::Ascii<String> {
+        let Token::Identifier(id) = &self.identifier_token.token else {
+            panic!("Expected identifier token")
+        };
+        id
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct FunctionParameterSpecifier {
     function_token: Spanned<Token>,
@@ -48,6 +48,8 @@ pub enum AstNode {
     FunctionDeclaration(FunctionDeclarationAstNode),
     TypeDeclaration(TypeDeclarationAstNode),
     EnumDeclaration(EnumDeclarationAstNode),
+    Module(ModuleAstNode),
+    Import(ImportAstNode),
     Main(BlockStatement),
 }
 
@@ -61,6 +63,8 @@ impl AstNode {
             AstNode::FunctionDeclaration(f) => visitor.visit_function_declaration(f),
             AstNode::TypeDeclaration(t) => visitor.visit_type_declaration(t),
             AstNode::EnumDeclaration(e) => visitor.visit_enum_declaration(e),
+            AstNode::Module(m) => visitor.visit_module(m),
+            AstNode::Import(i) => visitor.visit_import(i),
             AstNode::Main(m) => visitor.visit_main(m),
         }
     }
@@ -75,6 +79,8 @@ impl AstNode {
             AstNode::FunctionDeclaration(f) => visitor.visit_function_declaration(f),
             AstNode::TypeDeclaration(t) => AstNode::TypeDeclaration(t.clone()),
             AstNode::EnumDeclaration(e) => visitor.visit_enum_declaration(e),
+            AstNode::Module(m) => visitor.visit_module(m),
+            AstNode::Import(i) => visitor.visit_import(i),
             AstNode::Main(m) => AstNode::Main(visitor.visit_block(m)),
         }
     }
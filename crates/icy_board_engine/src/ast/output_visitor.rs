@@ -482,6 +482,61 @@ impl AstVisitor<()> for OutputVisitor {
         self.eol();
     }
 
+    fn visit_for_each_statement(&mut self, for_each_stmt: &super::ForEachStatement) {
+        self.output_keyword("For");
+        self.output.push(' ');
+        self.output_keyword("Each");
+        self.output.push(' ');
+        self.output(for_each_stmt.get_identifier());
+        self.output.push(' ');
+        self.output_keyword("In");
+        self.output.push(' ');
+        for_each_stmt.get_collection_expr().visit(self);
+        self.eol();
+
+        self.indent += 1;
+        self.output_statements(for_each_stmt.get_statements());
+        self.indent -= 1;
+
+        self.indent();
+        self.output_keyword("Next");
+        self.eol();
+    }
+
+    fn visit_try_statement(&mut self, try_stmt: &super::TryStatement) {
+        self.output_keyword("Try");
+        self.eol();
+
+        self.indent += 1;
+        self.output_statements(try_stmt.get_statements());
+        self.indent -= 1;
+
+        self.indent();
+        self.output_keyword("Catch");
+        if let Some(id) = try_stmt.get_catch_identifier() {
+            self.output.push(' ');
+            self.output(id);
+        }
+        self.eol();
+
+        self.indent += 1;
+        self.output_statements(try_stmt.get_catch_statements());
+        self.indent -= 1;
+
+        self.indent();
+        self.output_keyword("EndTry");
+        self.eol();
+    }
+
+    fn visit_try_marker_statement(&mut self, marker: &super::TryMarkerStatement) {
+        if let Some(label) = marker.get_catch_label() {
+            self.output.push_str("<try> ");
+            self.output(label);
+        } else {
+            self.output.push_str("<end try>");
+        }
+    }
+
     fn visit_break_statement(&mut self, _break_stmt: &super::BreakStatement) {
         self.output_keyword("Break");
     }
@@ -648,6 +703,34 @@ impl AstVisitor<()> for OutputVisitor {
         self.output_keyword("EndEnum");
     }
 
+    fn visit_module(&mut self, module: &super::ModuleAstNode) {
+        self.output_keyword("Module");
+        self.output.push(' ');
+        self.output(module.get_identifier());
+        self.eol();
+        self.indent += 1;
+        for member in module.get_members() {
+            self.indent();
+            if member.get_visibility_token().is_some() {
+                self.output_keyword(if member.is_public() { "Public " } else { "Private " });
+            }
+            member.get_node().visit(self);
+            // Routines end their own last line.
+            if !matches!(member.get_node(), super::AstNode::Function(_) | super::AstNode::Procedure(_)) {
+                self.eol();
+            }
+        }
+        self.indent -= 1;
+        self.indent();
+        self.output_keyword("EndModule");
+    }
+
+    fn visit_import(&mut self, import: &super::ImportAstNode) {
+        self.output_keyword("Import");
+        self.output.push(' ');
+        self.output(import.get_identifier());
+    }
+
     fn visit_procedure_declaration(&mut self, proc_decl: &super::ProcedureDeclarationAstNode) {
         self.output_keyword("Declare Procedure ");
         self.output_function(proc_decl.get_identifier());
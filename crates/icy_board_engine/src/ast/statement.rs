@@ -27,6 +27,7 @@ pub enum Statement {
     Loop(LoopStatement),
 
     For(ForStatement),
+    ForEach(ForEachStatement),
     Break(BreakStatement),
     Continue(ContinueStatement),
     Gosub(GosubStatement),
@@ -34,6 +35,8 @@ pub enum Statement {
     Let(LetStatement),
     Goto(GotoStatement),
     OnError(OnErrorStatement),
+    Try(TryStatement),
+    TryMarker(TryMarkerStatement),
     Label(LabelStatement),
     Call(ProcedureCallStatement),
     PredifinedCall(PredefinedCallStatement),
@@ -57,6 +60,7 @@ impl Statement {
             Statement::RepeatUntil(r) => r.get_repeat_token().span.start..r.condition.get_span().end,
             Statement::Loop(l) => l.get_loop_token().span.start..l.get_endloop_token().span.end,
             Statement::For(f) => f.get_for_token().span.clone(),
+            Statement::ForEach(f) => f.get_for_token().span.clone(),
             Statement::Break(b) => b.get_break_token().span.clone(),
             Statement::Continue(c) => c.get_continue_token().span.clone(),
             Statement::Gosub(g) => g.get_gosub_token().span.clone(),
@@ -64,6 +68,8 @@ impl Statement {
             Statement::Let(l) => l.get_identifier_token().span.clone(),
             Statement::Goto(g) => g.get_goto_token().span.clone(),
             Statement::OnError(o) => o.get_on_error_token().span.clone(),
+            Statement::Try(t) => t.get_try_token().span.start..t.get_endtry_token().span.end,
+            Statement::TryMarker(t) => t.get_span(),
             Statement::Label(l) => l.get_label_token().span.clone(),
             Statement::Call(c) => c.get_identifier_token().span.clone(),
             Statement::PredifinedCall(p) => p.identifier_token.span.clone(),
@@ -87,6 +93,7 @@ impl Statement {
             Statement::Loop(s) => visitor.visit_loop_statement(s),
 
             Statement::For(s) => visitor.visit_for_statement(s),
+            Statement::ForEach(s) => visitor.visit_for_each_statement(s),
             Statement::Break(s) => visitor.visit_break_statement(s),
             Statement::Continue(s) => visitor.visit_continue_statement(s),
             Statement::Gosub(s) => visitor.visit_gosub_statement(s),
@@ -94,6 +101,8 @@ impl Statement {
             Statement::Let(s) => visitor.visit_let_statement(s),
             Statement::Goto(s) => visitor.visit_goto_statement(s),
             Statement::OnError(s) => visitor.visit_on_error_statement(s),
+            Statement::Try(s) => visitor.visit_try_statement(s),
+            Statement::TryMarker(s) => visitor.visit_try_marker_statement(s),
             Statement::Label(s) => visitor.visit_label_statement(s),
             Statement::Call(s) => visitor.visit_procedure_call_statement(s),
             Statement::PredifinedCall(s) => visitor.visit_predefined_call_statement(s),
@@ -117,6 +126,7 @@ impl Statement {
             Statement::RepeatUntil(s) => visitor.visit_repeat_until_statement(s),
             Statement::Loop(s) => visitor.visit_loop_statement(s),
             Statement::For(s) => visitor.visit_for_statement(s),
+            Statement::ForEach(s) => visitor.visit_for_each_statement(s),
             Statement::Break(s) => visitor.visit_break_statement(s),
             Statement::Continue(s) => visitor.visit_continue_statement(s),
             Statement::Gosub(s) => visitor.visit_gosub_statement(s),
@@ -124,6 +134,8 @@ impl Statement {
             Statement::Let(s) => visitor.visit_let_statement(s),
             Statement::Goto(s) => visitor.visit_goto_statement(s),
             Statement::OnError(s) => visitor.visit_on_error_statement(s),
+            Statement::Try(s) => visitor.visit_try_statement(s),
+            Statement::TryMarker(s) => visitor.visit_try_marker_statement(s),
             Statement::Label(s) => visitor.visit_label_statement(s),
             Statement::Call(s) => visitor.visit_procedure_call_statement(s),
             Statement::PredifinedCall(s) => visitor.visit_predefined_call_statement(s),
@@ -247,6 +259,12 @@ impl Statement {
                 }
                 true
             }
+            (Statement::ForEach(f1), Statement::ForEach(f2)) => {
+                f1.get_identifier() == f2.get_identifier()
+                    && f1.get_collection_expr().is_similar(f2.get_collection_expr())
+                    && f1.get_statements().len() == f2.get_statements().len()
+                    && f1.get_statements().iter().zip(f2.get_statements().iter()).all(|(s1, s2)| s1.is_similar(s2))
+            }
             (Statement::Let(l1), Statement::Let(l2)) => {
                 l1.get_identifier() == l2.get_identifier()
                     && l1.get_value_expression().is_similar(l2.get_value_expression())
@@ -254,6 +272,18 @@ impl Statement {
             }
             (Statement::Goto(g1), Statement::Goto(g2)) => g1.get_label() == g2.get_label(),
             (Statement::OnError(o1), Statement::OnError(o2)) => o1 == o2,
+            (Statement::Try(t1), Statement::Try(t2)) => {
+                t1.get_catch_identifier() == t2.get_catch_identifier()
+                    && t1.get_statements().len() == t2.get_statements().len()
+                    && t1.get_statements().iter().zip(t2.get_statements().iter()).all(|(s1, s2)| s1.is_similar(s2))
+                    && t1.get_catch_statements().len() == t2.get_catch_statements().len()
+                    && t1
+                        .get_catch_statements()
+                        .iter()
+                        .zip(t2.get_catch_statements().iter())
+                        .all(|(s1, s2)| s1.is_similar(s2))
+            }
+            (Statement::TryMarker(t1), Statement::TryMarker(t2)) => t1.get_catch_label() == t2.get_catch_label(),
             (Statement::Gosub(g1), Statement::Gosub(g2)) => g1.get_label() == g2.get_label(),
             (Statement::Label(g1), Statement::Label(g2)) => g1.get_label() == g2.get_label(),
             (Statement::Call(c1), Statement::Call(c2)) => {
@@ -1171,6 +1201,128 @@ impl ForStatement {
     }
 }
 
+/// `FOR EACH item IN collection ... NEXT`, walking the items of a list or the keys of a map.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForEachStatement {
+    for_token: Spanned<Token>,
+    each_token: Spanned<Token>,
+    identifier_token: Spanned<Token>,
+    in_token: Spanned<Token>,
+    collection_expr: Box<Expression>,
+    statements: Vec<Statement>,
+    next_token: Spanned<Token>,
+    next_identifier_token: Option<Spanned<Token>>,
+}
+
+impl ForEachStatement {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        for_token: Spanned<Token>,
+        each_token: Spanned<Token>,
+        identifier_token: Spanned<Token>,
+        in_token: Spanned<Token>,
+        collection_expr: Expression,
+        statements: Vec<Statement>,
+        next_token: Spanned<Token>,
+        next_identifier_token: Option<Spanned<Token>>,
+    ) -> Self {
+        Self {
+            for_token,
+            each_token,
+            identifier_token,
+            in_token,
+            collection_expr: Box::new(collection_expr),
+            statements,
+            next_token,
+            next_identifier_token,
+        }
+    }
+
+    pub fn empty(variable_name: unicase::Ascii<String>, collection_expr: Expression, statements: Vec<Statement>) -> Self {
+        Self {
+            for_token: Spanned::create_empty(Token::For),
+            each_token: Spanned::create_empty(Token::Identifier(unicase::Ascii::new("EACH".to_string()))),
+            identifier_token: Spanned::create_empty(Token::Identifier(variable_name)),
+            in_token: Spanned::create_empty(Token::Identifier(unicase::Ascii::new("IN".to_string()))),
+            collection_expr: Box::new(collection_expr),
+            statements,
+            next_token: Spanned::create_empty(Token::Next),
+            next_identifier_token: None,
+        }
+    }
+
+    pub fn get_for_token(&self) -> &Spanned<Token> {
+        &self.for_token
+    }
+
+    pub fn get_each_token(&self) -> &Spanned<Token> {
+        &self.each_token
+    }
+
+    pub fn get_identifier_token(&self) -> &Spanned<Token> {
+        &self.identifier_token
+    }
+
+    /// Returns the name of the loop variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the identifier token isn't an identifier.
+    pub fn get_identifier(&self) -> &unicase::Ascii<String> {
+        if let Token::Identifier(id) = &self.identifier_token.token {
+            return id;
+        }
+        panic!("Expected identifier token")
+    }
+
+    pub fn set_identifier(&mut self, new_id: unicase::Ascii<String>) {
+        if let Token::Identifier(id) = &mut self.identifier_token.token {
+            *id = new_id;
+        }
+    }
+
+    pub fn get_in_token(&self) -> &Spanned<Token> {
+        &self.in_token
+    }
+
+    pub fn get_collection_expr(&self) -> &Expression {
+        &self.collection_expr
+    }
+
+    pub fn get_collection_expr_mut(&mut self) -> &mut Expression {
+        &mut self.collection_expr
+    }
+
+    pub fn get_statements(&self) -> &Vec<Statement> {
+        &self.statements
+    }
+
+    pub fn get_statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+
+    pub fn get_next_token(&self) -> &Spanned<Token> {
+        &self.next_token
+    }
+
+    pub fn get_next_identifier_token(&self) -> Option<&Spanned<Token>> {
+        self.next_identifier_token.as_ref()
+    }
+
+    pub fn get_next_identifier(&self) -> Option<&unicase::Ascii<String>> {
+        if let Some(ni) = &self.next_identifier_token
+            && let Token::Identifier(id) = &ni.token
+        {
+            return Some(id);
+        }
+        None
+    }
+
+    pub fn create_empty_statement(variable_name: unicase::Ascii<String>, collection_expr: Expression, statements: Vec<Statement>) -> Statement {
+        Statement::ForEach(ForEachStatement::empty(variable_name, collection_expr, statements))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum CaseSpecifier {
     Expression(Box<Expression>),
@@ -1542,6 +1694,160 @@ impl OnErrorStatement {
     }
 }
 
+/// `TRY ... CATCH [e] ... ENDTRY`. An operation that fails inside the block leaves it for
+/// the CATCH part, with the error in `e` when one is named.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TryStatement {
+    try_token: Spanned<Token>,
+    statements: Vec<Statement>,
+    catch_token: Spanned<Token>,
+    catch_identifier_token: Option<Spanned<Token>>,
+    catch_statements: Vec<Statement>,
+    endtry_token: Spanned<Token>,
+}
+
+impl TryStatement {
+    pub fn new(
+        try_token: Spanned<Token>,
+        statements: Vec<Statement>,
+        catch_token: Spanned<Token>,
+        catch_identifier_token: Option<Spanned<Token>>,
+        catch_statements: Vec<Statement>,
+        endtry_token: Spanned<Token>,
+    ) -> Self {
+        Self {
+            try_token,
+            statements,
+            catch_token,
+            catch_identifier_token,
+            catch_statements,
+            endtry_token,
+        }
+    }
+
+    pub fn empty(statements: Vec<Statement>, catch_identifier: Option<unicase::Ascii<String>>, catch_statements: Vec<Statement>) -> Self {
+        Self {
+            try_token: Spanned::create_empty(Token::Try),
+            statements,
+            catch_token: Spanned::create_empty(Token::Catch),
+            catch_identifier_token: catch_identifier.map(|id| Spanned::create_empty(Token::Identifier(id))),
+            catch_statements,
+            endtry_token: Spanned::create_empty(Token::EndTry),
+        }
+    }
+
+    pub fn get_try_token(&self) -> &Spanned<Token> {
+        &self.try_token
+    }
+
+    pub fn get_statements(&self) -> &Vec<Statement> {
+        &self.statements
+    }
+
+    pub fn get_statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.statements
+    }
+
+    pub fn get_catch_token(&self) -> &Spanned<Token> {
+        &self.catch_token
+    }
+
+    pub fn get_catch_identifier_token(&self) -> Option<&Spanned<Token>> {
+        self.catch_identifier_token.as_ref()
+    }
+
+    /// The variable the error is put in, `None` for a bare `CATCH`.
+    pub fn get_catch_identifier(&self) -> Option<&unicase::Ascii<String>> {
+        if let Some(token) = &self.catch_identifier_token
+            && let Token::Identifier(id) = &token.token
+        {
+            return Some(id);
+        }
+        None
+    }
+
+    pub fn set_catch_identifier(&mut self, new_id: unicase::Ascii<String>) {
+        if let Some(token) = &mut self.catch_identifier_token
+            && let Token::Identifier(id) = &mut token.token
+        {
+            *id = new_id;
+        }
+    }
+
+    pub fn get_catch_statements(&self) -> &Vec<Statement> {
+        &self.catch_statements
+    }
+
+    pub fn get_catch_statements_mut(&mut self) -> &mut Vec<Statement> {
+        &mut self.catch_statements
+    }
+
+    pub fn get_endtry_token(&self) -> &Spanned<Token> {
+        &self.endtry_token
+    }
+
+    pub fn create_empty_statement(statements: Vec<Statement>, catch_identifier: Option<unicase::Ascii<String>>, catch_statements: Vec<Statement>) -> Statement {
+        Statement::Try(TryStatement::empty(statements, catch_identifier, catch_statements))
+    }
+}
+
+/// What a `TRY` block is lowered to: `<try> label` arms the CATCH part at the label,
+/// `<end try>` disarms it again where the block ends.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TryMarkerStatement {
+    /// The label of the CATCH part, `None` for `<end try>`.
+    catch_label_token: Option<Spanned<Token>>,
+    /// The `TRY` the marker was lowered from.
+    span: core::ops::Range<usize>,
+}
+
+impl TryMarkerStatement {
+    pub fn new(catch_label_token: Option<Spanned<Token>>, span: core::ops::Range<usize>) -> Self {
+        Self { catch_label_token, span }
+    }
+
+    pub fn begin(catch_label: unicase::Ascii<String>) -> Self {
+        Self::new(Some(Spanned::create_empty(Token::Identifier(catch_label))), 0..0)
+    }
+
+    pub fn end() -> Self {
+        Self::new(None, 0..0)
+    }
+
+    pub fn get_span(&self) -> core::ops::Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn get_catch_label_token(&self) -> Option<&Spanned<Token>> {
+        self.catch_label_token.as_ref()
+    }
+
+    pub fn get_catch_label(&self) -> Option<&unicase::Ascii<String>> {
+        if let Some(token) = &self.catch_label_token
+            && let Token::Identifier(id) = &token.token
+        {
+            return Some(id);
+        }
+        None
+    }
+
+    pub fn set_catch_label(&mut self, new_id: unicase::Ascii<String>) {
+        if let Some(token) = &mut self.catch_label_token
+            && let Token::Identifier(id) = &mut token.token
+        {
+            *id = new_id;
+        }
+    }
+
+    pub fn create_begin_statement(catch_label: unicase::Ascii<String>) -> Statement {
+        Statement::TryMarker(TryMarkerStatement::begin(catch_label))
+    }
+
+    pub fn create_end_statement() -> Statement {
+        Statement::TryMarker(TryMarkerStatement::end())
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct LabelStatement {
     label_token: Spanned<Token>,
@@ -1,6 +1,6 @@
-use super::{AstNode, AstVisitor, AstVisitorMut};
+use super::{AstNode, AstVisitor, AstVisitorMut, Statement};
 use crate::executable::LAST_PPL_LANGUAGE_VERSION;
-use std::{fmt, path::PathBuf};
+use std::{fmt, ops::Range, path::PathBuf};
 
 #[derive(Debug)]
 pub struct Ast {
@@ -31,6 +31,35 @@ impl Ast {
     pub fn visit_mut<V: AstVisitorMut>(&self, visitor: &mut V) -> Self {
         visitor.visit_ast(self)
     }
+
+    /// Where a dependency's source has more than it shares with the packages
+    /// using it: anything but modules, types, enums, imports and comments.
+    pub fn unshared_spans(&self) -> Vec<Range<usize>> {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node {
+                AstNode::Module(_) | AstNode::TypeDeclaration(_) | AstNode::EnumDeclaration(_) | AstNode::Import(_) => None,
+                AstNode::TopLevelStatement(Statement::Comment(_)) => None,
+                AstNode::TopLevelStatement(statement) => Some(statement.get_span()),
+                AstNode::Function(function) => Some(function.get_identifier_token().span.clone()),
+                AstNode::Procedure(procedure) => Some(procedure.get_identifier_token().span.clone()),
+                AstNode::FunctionDeclaration(function) => Some(function.get_identifier_token().span.clone()),
+                AstNode::ProcedureDeclaration(procedure) => Some(procedure.get_identifier_token().span.clone()),
+                AstNode::Main(block) => Some(block.get_span()),
+            })
+            .collect()
+    }
+
+    /// Drops everything `unshared_spans` points at, so a dependency adds its
+    /// modules and types to a PPE and nothing that would run or take up space.
+    pub fn retain_shared(&mut self) {
+        self.nodes.retain(|node| {
+            matches!(
+                node,
+                AstNode::Module(_) | AstNode::TypeDeclaration(_) | AstNode::EnumDeclaration(_) | AstNode::Import(_)
+            )
+        });
+    }
 }
 
 impl Default for Ast {
@@ -2,12 +2,13 @@ use crate::parser::lexer::{Spanned, Token};
 
 use super::{
     ArrayInitializerExpression, Ast, AstNode, BinaryExpression, BlockStatement, BreakStatement, CaseBlock, CaseSpecifier, CommentAstNode,
-    ConstDeclarationStatement, ConstantExpression, ContinueStatement, ElseBlock, ElseIfBlock, EnumDeclarationAstNode, Expression, ForStatement,
-    FunctionCallExpression, FunctionDeclarationAstNode, FunctionImplementation, GosubStatement, GotoStatement, IdentifierExpression, IfStatement,
-    IfThenStatement, IndexerExpression, LabelStatement, LetStatement, LoopStatement, MemberCallStatement, MemberReferenceExpression, OnErrorStatement,
-    ParameterSpecifier, ParensExpression, PredefinedCallStatement, ProcedureCallStatement, ProcedureDeclarationAstNode, ProcedureImplementation,
-    RecordLiteralExpression, RepeatUntilStatement, ReturnStatement, SelectStatement, Statement, TypeDeclarationAstNode, UnaryExpression,
-    VariableDeclarationStatement, VariableSpecifier, WhileDoStatement, WhileStatement,
+    ConstDeclarationStatement, ConstantExpression, ContinueStatement, ElseBlock, ElseIfBlock, EnumDeclarationAstNode, Expression, ForEachStatement,
+    ForStatement, FunctionCallExpression, FunctionDeclarationAstNode, FunctionImplementation, GosubStatement, GotoStatement, IdentifierExpression, IfStatement,
+    IfThenStatement, ImportAstNode, IndexerExpression, LabelStatement, LetStatement, LoopStatement, MemberCallStatement, MemberReferenceExpression,
+    ModuleAstNode, ModuleMember, OnErrorStatement, ParameterSpecifier, ParensExpression, PredefinedCallStatement, ProcedureCallStatement,
+    ProcedureDeclarationAstNode, ProcedureImplementation, RecordLiteralExpression, RepeatUntilStatement, ReturnStatement, SelectStatement, Statement,
+    TryMarkerStatement, TryStatement, TypeDeclarationAstNode, UnaryExpression, VariableDeclarationStatement, VariableSpecifier, WhileDoStatement,
+    WhileStatement,
 };
 
 #[allow(unused_variables)]
@@ -102,6 +103,10 @@ pub trait AstVisitor<T: Default>: Sized {
         walk_for_stmt(self, for_stmt);
         T::default()
     }
+    fn visit_for_each_statement(&mut self, for_each_stmt: &ForEachStatement) -> T {
+        walk_for_each_stmt(self, for_each_stmt);
+        T::default()
+    }
     fn visit_break_statement(&mut self, break_stmt: &BreakStatement) -> T {
         T::default()
     }
@@ -125,6 +130,13 @@ pub trait AstVisitor<T: Default>: Sized {
     fn visit_on_error_statement(&mut self, on_error: &OnErrorStatement) -> T {
         T::default()
     }
+    fn visit_try_statement(&mut self, try_stmt: &TryStatement) -> T {
+        walk_try_stmt(self, try_stmt);
+        T::default()
+    }
+    fn visit_try_marker_statement(&mut self, marker: &TryMarkerStatement) -> T {
+        T::default()
+    }
     fn visit_label_statement(&mut self, label: &LabelStatement) -> T {
         T::default()
     }
@@ -171,6 +183,13 @@ pub trait AstVisitor<T: Default>: Sized {
     fn visit_enum_declaration(&mut self, enum_decl: &EnumDeclarationAstNode) -> T {
         T::default()
     }
+    fn visit_module(&mut self, module: &ModuleAstNode) -> T {
+        walk_module(self, module);
+        T::default()
+    }
+    fn visit_import(&mut self, import: &ImportAstNode) -> T {
+        T::default()
+    }
 
     // visit implementations
 
@@ -235,6 +254,12 @@ pub fn walk_program<T: Default, V: AstVisitor<T>>(visitor: &mut V, program: &Ast
     }
 }
 
+pub fn walk_module<T: Default, V: AstVisitor<T>>(visitor: &mut V, module: &ModuleAstNode) {
+    for member in module.get_members() {
+        member.get_node().visit(visitor);
+    }
+}
+
 pub fn walk_select_stmt<T: Default, V: AstVisitor<T>>(visitor: &mut V, select_stmt: &SelectStatement) {
     select_stmt.get_expression().visit(visitor);
 
@@ -288,6 +313,22 @@ pub fn walk_for_stmt<T: Default, V: AstVisitor<T>>(visitor: &mut V, for_stmt: &F
     }
 }
 
+pub fn walk_for_each_stmt<T: Default, V: AstVisitor<T>>(visitor: &mut V, for_each_stmt: &ForEachStatement) {
+    for_each_stmt.get_collection_expr().visit(visitor);
+    for stmt in for_each_stmt.get_statements() {
+        stmt.visit(visitor);
+    }
+}
+
+pub fn walk_try_stmt<T: Default, V: AstVisitor<T>>(visitor: &mut V, try_stmt: &TryStatement) {
+    for stmt in try_stmt.get_statements() {
+        stmt.visit(visitor);
+    }
+    for stmt in try_stmt.get_catch_statements() {
+        stmt.visit(visitor);
+    }
+}
+
 pub fn walk_return_stmt<T: Default, V: AstVisitor<T>>(visitor: &mut V, ret_stmt: &ReturnStatement) {
     if let Some(expr) = ret_stmt.get_expression() {
         expr.visit(visitor);
@@ -569,6 +610,25 @@ pub trait AstVisitorMut: Sized {
         ))
     }
 
+    fn visit_for_each_statement(&mut self, for_each_stmt: &ForEachStatement) -> Statement {
+        Statement::ForEach(ForEachStatement::new(
+            for_each_stmt.get_for_token().clone(),
+            for_each_stmt.get_each_token().clone(),
+            Spanned {
+                span: for_each_stmt.get_identifier_token().span.clone(),
+                token: Token::Identifier(self.visit_identifier(for_each_stmt.get_identifier())),
+            },
+            for_each_stmt.get_in_token().clone(),
+            for_each_stmt.get_collection_expr().visit_mut(self),
+            for_each_stmt.get_statements().iter().map(|stmt| stmt.visit_mut(self)).collect(),
+            for_each_stmt.get_next_token().clone(),
+            for_each_stmt.get_next_identifier_token().map(|ni| Spanned {
+                span: ni.span.clone(),
+                token: Token::Identifier(self.visit_identifier(for_each_stmt.get_next_identifier().unwrap())),
+            }),
+        ))
+    }
+
     fn visit_break_statement(&mut self, break_stmt: &BreakStatement) -> Statement {
         Statement::Break(break_stmt.clone())
     }
@@ -607,6 +667,22 @@ pub trait AstVisitorMut: Sized {
     fn visit_on_error_statement(&mut self, on_error: &OnErrorStatement) -> Statement {
         Statement::OnError(on_error.clone())
     }
+    fn visit_try_statement(&mut self, try_stmt: &TryStatement) -> Statement {
+        Statement::Try(TryStatement::new(
+            try_stmt.get_try_token().clone(),
+            try_stmt.get_statements().iter().map(|stmt| stmt.visit_mut(self)).collect(),
+            try_stmt.get_catch_token().clone(),
+            try_stmt.get_catch_identifier_token().map(|id| Spanned {
+                span: id.span.clone(),
+                token: Token::Identifier(self.visit_identifier(try_stmt.get_catch_identifier().unwrap())),
+            }),
+            try_stmt.get_catch_statements().iter().map(|stmt| stmt.visit_mut(self)).collect(),
+            try_stmt.get_endtry_token().clone(),
+        ))
+    }
+    fn visit_try_marker_statement(&mut self, marker: &TryMarkerStatement) -> Statement {
+        Statement::TryMarker(marker.clone())
+    }
     fn visit_label_statement(&mut self, label: &LabelStatement) -> Statement {
         Statement::Label(label.clone())
     }
@@ -682,6 +758,21 @@ pub trait AstVisitorMut: Sized {
     fn visit_enum_declaration(&mut self, enum_decl: &EnumDeclarationAstNode) -> AstNode {
         AstNode::EnumDeclaration(enum_decl.clone())
     }
+    fn visit_module(&mut self, module: &ModuleAstNode) -> AstNode {
+        AstNode::Module(ModuleAstNode::new(
+            module.get_module_token().clone(),
+            module.get_identifier_token().clone(),
+            module
+                .get_members()
+                .iter()
+                .map(|member| ModuleMember::new(member.get_visibility_token().cloned(), member.get_node().visit_mut(self)))
+                .collect(),
+            module.get_endmodule_token().clone(),
+        ))
+    }
+    fn visit_import(&mut self, import: &ImportAstNode) -> AstNode {
+        AstNode::Import(import.clone())
+    }
     fn visit_function_declaration(&mut self, func_decl: &FunctionDeclarationAstNode) -> AstNode {
         AstNode::FunctionDeclaration(FunctionDeclarationAstNode::new(
             func_decl.get_declare_token().clone(),
@@ -2,21 +2,68 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{
     ast::{
-        Ast, AstNode, AstVisitorMut, BinaryExpression, BlockStatement, CommentAstNode, ConstDeclarationStatement, Constant, ConstantExpression,
-        DimensionSpecifier, Expression, ForStatement, FunctionImplementation, GotoStatement, IdentifierExpression, IfStatement, LabelStatement, LetStatement,
-        MemberReferenceExpression, ParameterSpecifier, ProcedureImplementation, ReturnStatement, SelectStatement, Statement, VariableDeclarationStatement,
-        VariableSpecifier, const_expression, const_value_with_members, constant::NumberFormat,
+        Ast, AstNode, AstVisitor, AstVisitorMut, BinaryExpression, BlockStatement, CommentAstNode, ConstDeclarationStatement, Constant, ConstantExpression,
+        DimensionSpecifier, Expression, ForEachStatement, ForStatement, FunctionCallExpression, FunctionImplementation, GosubStatement, GotoStatement,
+        IdentifierExpression, IfStatement, LabelStatement, LetStatement, MemberReferenceExpression, ParameterSpecifier, ProcedureImplementation,
+        ReturnStatement, SelectStatement, Statement, TryMarkerStatement, TryStatement, VariableDeclarationStatement, VariableSpecifier, const_expression,
+        const_value_with_members, constant::NumberFormat,
     },
     decompiler::evaluation_visitor::{ConstantFolder, OptimizationVisitor},
-    executable::VariableValue,
+    executable::{VariableType, VariableValue},
     parser::{
         EnumDefinition,
         lexer::{Spanned, Token},
     },
 };
 
+/// The hidden copy of the collection a `FOR EACH` walks. It is declared without a
+/// type, the semantic pass gives it the type of what is copied into it.
+pub const EACH_COLLECTION_PREFIX: &str = "*(items";
+
+/// The TRY blocks around each label of a routine, outermost first. A TRY is known by
+/// where its keyword starts.
+#[derive(Default)]
+struct LabelTryScan {
+    open_tries: Vec<usize>,
+    labels: HashMap<unicase::Ascii<String>, Vec<usize>>,
+}
+
+impl LabelTryScan {
+    fn scan<'a>(statements: impl IntoIterator<Item = &'a Statement>) -> HashMap<unicase::Ascii<String>, Vec<usize>> {
+        let mut scan = Self::default();
+        for statement in statements {
+            statement.visit(&mut scan);
+        }
+        scan.labels
+    }
+}
+
+impl AstVisitor<()> for LabelTryScan {
+    fn visit_try_statement(&mut self, try_stmt: &TryStatement) {
+        self.open_tries.push(try_stmt.get_try_token().span.start);
+        for stmt in try_stmt.get_statements() {
+            stmt.visit(self);
+        }
+        self.open_tries.pop();
+        for stmt in try_stmt.get_catch_statements() {
+            stmt.visit(self);
+        }
+    }
+
+    fn visit_label_statement(&mut self, label: &LabelStatement) {
+        self.labels.insert(label.get_label().clone(), self.open_tries.clone());
+    }
+}
+
 pub struct AstTransformationVisitor {
-    continue_break_labels: Vec<(unicase::Ascii<String>, unicase::Ascii<String>)>,
+    /// The continue and break label of each enclosing loop, with the number of TRY blocks
+    /// that were open where the loop started.
+    continue_break_labels: Vec<(unicase::Ascii<String>, unicase::Ascii<String>, usize)>,
+    /// The TRY blocks the statement being rewritten is in, outermost first.
+    open_tries: Vec<usize>,
+    /// Where the labels of the current routine sit, see [`LabelTryScan`].
+    label_tries: HashMap<unicase::Ascii<String>, Vec<usize>>,
+    jumps_into_try: Vec<Spanned<Token>>,
     cur_function: Option<unicase::Ascii<String>>,
     optimize_output: bool,
     labels: usize,
@@ -31,6 +78,9 @@ impl AstTransformationVisitor {
     pub fn new(optimize_output: bool, enums: Vec<EnumDefinition>) -> Self {
         Self {
             continue_break_labels: Vec::new(),
+            open_tries: Vec::new(),
+            label_tries: HashMap::new(),
+            jumps_into_try: Vec::new(),
             cur_function: None,
             optimize_output,
             labels: 0,
@@ -47,6 +97,33 @@ impl AstTransformationVisitor {
         std::mem::take(&mut self.loop_counters)
     }
 
+    /// The GOTO and GOSUB labels of the file just transformed that lead into a TRY block
+    /// the jump is not in. The block would never be set up, so they are rejected.
+    pub fn take_jumps_into_try(&mut self) -> Vec<Spanned<Token>> {
+        std::mem::take(&mut self.jumps_into_try)
+    }
+
+    /// The number of TRY blocks open at a label, `None` if it lies in a block this
+    /// jump is not in. An unknown label is left to the semantic pass.
+    fn label_try_depth(&self, label: &unicase::Ascii<String>) -> Option<usize> {
+        match self.label_tries.get(label) {
+            Some(tries) => self.open_tries.starts_with(tries).then_some(tries.len()),
+            None => Some(self.open_tries.len()),
+        }
+    }
+
+    /// A jump first closes the TRY blocks it leaves, down to the number open where it lands.
+    fn leave_try_blocks(&self, target_try_depth: usize, jump: Statement) -> Statement {
+        if self.open_tries.len() == target_try_depth {
+            return jump;
+        }
+        let mut statements: Vec<Statement> = (target_try_depth..self.open_tries.len())
+            .map(|_| TryMarkerStatement::create_end_statement())
+            .collect();
+        statements.push(jump);
+        Statement::Block(BlockStatement::empty(statements))
+    }
+
     pub fn next_label(&mut self) -> unicase::Ascii<String> {
         let label = unicase::Ascii::new(format!("*(label{}", self.labels));
         self.labels += 1;
@@ -153,19 +230,98 @@ impl AstVisitorMut for AstTransformationVisitor {
         if self.continue_break_labels.is_empty() {
             return CommentAstNode::create_empty_statement("no continue block");
         }
-        let (continue_label, _) = self.continue_break_labels.last().unwrap();
-        GotoStatement::create_empty_statement(continue_label.clone())
+        let (continue_label, _, try_depth) = self.continue_break_labels.last().unwrap();
+        self.leave_try_blocks(*try_depth, GotoStatement::create_empty_statement(continue_label.clone()))
     }
     fn visit_break_statement(&mut self, _break_stmt: &crate::ast::BreakStatement) -> Statement {
         if self.continue_break_labels.is_empty() {
             return CommentAstNode::create_empty_statement("no break block");
         }
-        let (_, break_label) = self.continue_break_labels.last().unwrap();
-        GotoStatement::create_empty_statement(break_label.clone())
+        let (_, break_label, try_depth) = self.continue_break_labels.last().unwrap();
+        self.leave_try_blocks(*try_depth, GotoStatement::create_empty_statement(break_label.clone()))
+    }
+
+    /// TRY
+    ///   statements
+    /// CATCH e
+    ///   catch statements
+    /// ENDTRY
+    ///
+    /// becomes
+    ///
+    /// <try> catch_label
+    ///   statements
+    /// <end try>
+    /// GOTO end_label
+    /// :catch_label
+    ///   e = ERR()
+    ///   catch statements
+    /// :end_label
+    fn visit_try_statement(&mut self, try_stmt: &TryStatement) -> Statement {
+        let mut statements = Vec::new();
+
+        let catch_label = self.next_label();
+        let end_label = self.next_label();
+
+        statements.push(Statement::TryMarker(TryMarkerStatement::new(
+            Some(Spanned::create_empty(Token::Identifier(catch_label.clone()))),
+            try_stmt.get_try_token().span.clone(),
+        )));
+        self.open_tries.push(try_stmt.get_try_token().span.start);
+        statements.extend(try_stmt.get_statements().iter().map(|s| s.visit_mut(self)));
+        self.open_tries.pop();
+        statements.push(TryMarkerStatement::create_end_statement());
+        statements.push(GotoStatement::create_empty_statement(end_label.clone()));
+
+        statements.push(LabelStatement::create_empty_statement(catch_label));
+        if let Some(identifier_token) = try_stmt.get_catch_identifier_token() {
+            statements.push(Statement::Let(LetStatement::new(
+                None,
+                Spanned {
+                    span: identifier_token.span.clone(),
+                    token: Token::Identifier(self.visit_identifier(try_stmt.get_catch_identifier().unwrap())),
+                },
+                None,
+                Vec::new(),
+                None,
+                Vec::new(),
+                Spanned::new(Token::Eq, identifier_token.span.clone()),
+                FunctionCallExpression::create_empty_expression(
+                    IdentifierExpression::create_empty_expression(unicase::Ascii::new("ERR".to_string())),
+                    Vec::new(),
+                ),
+            )));
+        }
+        statements.extend(try_stmt.get_catch_statements().iter().map(|s| s.visit_mut(self)));
+        statements.push(LabelStatement::create_empty_statement(end_label));
+
+        Statement::Block(BlockStatement::empty(statements))
+    }
+
+    /// A GOTO out of a TRY block closes the blocks it leaves first, like BREAK does.
+    fn visit_goto_statement(&mut self, goto: &GotoStatement) -> Statement {
+        match self.label_try_depth(goto.get_label()) {
+            Some(depth) => self.leave_try_blocks(depth, Statement::Goto(goto.clone())),
+            None => {
+                self.jumps_into_try.push(goto.get_label_token().clone());
+                Statement::Goto(goto.clone())
+            }
+        }
+    }
+
+    /// A GOSUB comes back to where it was, only one into a TRY block is a problem.
+    fn visit_gosub_statement(&mut self, gosub: &GosubStatement) -> Statement {
+        if self.label_try_depth(gosub.get_label()).is_none() {
+            self.jumps_into_try.push(gosub.get_label_token().clone());
+        }
+        Statement::Gosub(gosub.clone())
     }
 
     fn visit_if_statement(&mut self, if_stmt: &IfStatement) -> Statement {
-        if matches!(if_stmt.get_statement(), Statement::Goto(_)) {
+        // A GOTO that has to close TRY blocks first is no longer a single jump.
+        if let Statement::Goto(goto) = if_stmt.get_statement()
+            && self.label_try_depth(goto.get_label()).is_none_or(|depth| depth == self.open_tries.len())
+        {
             return Statement::If(IfStatement::empty(
                 if_stmt.get_condition().visit_mut(self),
                 if_stmt.get_statement().visit_mut(self),
@@ -229,7 +385,8 @@ impl AstVisitorMut for AstTransformationVisitor {
         let continue_label = self.next_label();
         let break_label = self.next_label();
 
-        self.continue_break_labels.push((continue_label.clone(), break_label.clone()));
+        self.continue_break_labels
+            .push((continue_label.clone(), break_label.clone(), self.open_tries.len()));
 
         statements.push(LabelStatement::create_empty_statement(continue_label.clone()));
         statements.push(IfStatement::create_empty_statement(
@@ -249,7 +406,8 @@ impl AstVisitorMut for AstTransformationVisitor {
         let continue_label = self.next_label();
         let break_label = self.next_label();
 
-        self.continue_break_labels.push((continue_label.clone(), break_label.clone()));
+        self.continue_break_labels
+            .push((continue_label.clone(), break_label.clone(), self.open_tries.len()));
 
         statements.push(LabelStatement::create_empty_statement(continue_label.clone()));
         statements.push(IfStatement::create_empty_statement(
@@ -270,7 +428,8 @@ impl AstVisitorMut for AstTransformationVisitor {
         let continue_label = self.next_label();
         let break_label = self.next_label();
 
-        self.continue_break_labels.push((continue_label.clone(), break_label.clone()));
+        self.continue_break_labels
+            .push((continue_label.clone(), break_label.clone(), self.open_tries.len()));
 
         statements.push(LabelStatement::create_empty_statement(loop_label.clone()));
         statements.extend(repeat_until.get_statements().iter().map(|s| s.visit_mut(self)));
@@ -292,7 +451,8 @@ impl AstVisitorMut for AstTransformationVisitor {
         let continue_label = self.next_label();
         let break_label = self.next_label();
 
-        self.continue_break_labels.push((continue_label.clone(), break_label.clone()));
+        self.continue_break_labels
+            .push((continue_label.clone(), break_label.clone(), self.open_tries.len()));
 
         statements.push(LabelStatement::create_empty_statement(continue_label.clone()));
         statements.extend(loop_stmt.get_statements().iter().map(|s| s.visit_mut(self)));
@@ -363,7 +523,8 @@ impl AstVisitorMut for AstTransformationVisitor {
         ));
 
         // create loop
-        self.continue_break_labels.push((continue_label.clone(), break_label.clone()));
+        self.continue_break_labels
+            .push((continue_label.clone(), break_label.clone(), self.open_tries.len()));
         statements.push(LabelStatement::create_empty_statement(loop_label.clone()));
 
         let increment = if let Some(increment) = for_stmt.get_step_expr() {
@@ -423,6 +584,87 @@ impl AstVisitorMut for AstTransformationVisitor {
         Statement::Block(BlockStatement::empty(statements))
     }
 
+    /// `FOR EACH` counts through the collection with a hidden INTEGER of its own. The
+    /// collection is evaluated once, into a hidden copy the loop walks, so the loop
+    /// sees the items that were there when it started.
+    fn visit_for_each_statement(&mut self, for_each_stmt: &ForEachStatement) -> Statement {
+        let mut statements = Vec::new();
+
+        let loop_label = self.next_label();
+        let break_label = self.next_label();
+        let counter = unicase::Ascii::new(format!("*(each{}", self.labels));
+        let items = unicase::Ascii::new(format!("{EACH_COLLECTION_PREFIX}{}", self.labels));
+        self.labels += 1;
+        let counter_expr = IdentifierExpression::create_empty_expression(counter.clone());
+        let items_expr = IdentifierExpression::create_empty_expression(items.clone());
+
+        statements.push(VariableDeclarationStatement::create_empty_statement(
+            VariableType::Integer,
+            vec![VariableSpecifier::empty(counter.clone(), Vec::new())],
+        ));
+        statements.push(VariableDeclarationStatement::create_empty_statement(
+            VariableType::None,
+            vec![VariableSpecifier::empty(items.clone(), Vec::new())],
+        ));
+        statements.push(LetStatement::create_empty_statement(
+            items,
+            Token::Eq,
+            Vec::new(),
+            for_each_stmt.get_collection_expr().visit_mut(self),
+        ));
+        statements.push(LetStatement::create_empty_statement(
+            counter.clone(),
+            Token::Eq,
+            Vec::new(),
+            ConstantExpression::create_empty_expression(Constant::Integer(0, NumberFormat::Default)),
+        ));
+
+        self.continue_break_labels
+            .push((loop_label.clone(), break_label.clone(), self.open_tries.len()));
+        statements.push(LabelStatement::create_empty_statement(loop_label.clone()));
+        statements.push(IfStatement::create_empty_statement(
+            BinaryExpression::create_empty_expression(
+                crate::ast::BinOp::GreaterEq,
+                counter_expr.clone(),
+                FunctionCallExpression::create_empty_expression(
+                    IdentifierExpression::create_empty_expression(unicase::Ascii::new("LEN".to_string())),
+                    vec![items_expr.clone()],
+                ),
+            ),
+            GotoStatement::create_empty_statement(break_label.clone()),
+        ));
+        statements.push(Statement::Let(LetStatement::new(
+            None,
+            for_each_stmt.get_identifier_token().clone(),
+            None,
+            Vec::new(),
+            None,
+            Vec::new(),
+            Spanned::new(Token::Eq, for_each_stmt.get_in_token().span.clone()),
+            FunctionCallExpression::create_empty_expression(
+                IdentifierExpression::create_empty_expression(unicase::Ascii::new("<each item>".to_string())),
+                vec![items_expr, counter_expr.clone()],
+            ),
+        )));
+        statements.push(LetStatement::create_empty_statement(
+            counter,
+            Token::Eq,
+            Vec::new(),
+            BinaryExpression::create_empty_expression(
+                crate::ast::BinOp::Add,
+                counter_expr,
+                ConstantExpression::create_empty_expression(Constant::Integer(1, NumberFormat::Default)),
+            ),
+        ));
+
+        statements.extend(for_each_stmt.get_statements().iter().map(|s| s.visit_mut(self)));
+
+        statements.push(GotoStatement::create_empty_statement(loop_label));
+        statements.push(LabelStatement::create_empty_statement(break_label));
+        self.continue_break_labels.pop();
+        Statement::Block(BlockStatement::empty(statements))
+    }
+
     fn visit_let_statement(&mut self, let_stmt: &LetStatement) -> Statement {
         let mut val_expr = let_stmt.get_value_expression().visit_mut(self);
 
@@ -487,6 +729,7 @@ impl AstVisitorMut for AstTransformationVisitor {
 
     fn visit_function_implementation(&mut self, function: &FunctionImplementation) -> AstNode {
         self.cur_function = Some(function.get_identifier().clone());
+        let outer_labels = std::mem::replace(&mut self.label_tries, LabelTryScan::scan(function.get_statements()));
         self.local_constants = Some(HashMap::new());
         self.collect_local_bindings(function.get_parameters(), function.get_statements());
         self.collect_constants(function.get_statements(), true);
@@ -509,10 +752,12 @@ impl AstVisitorMut for AstTransformationVisitor {
         self.cur_function = None;
         self.local_constants = None;
         self.local_bindings = None;
+        self.label_tries = outer_labels;
         res
     }
 
     fn visit_procedure_implementation(&mut self, procedure: &ProcedureImplementation) -> AstNode {
+        let outer_labels = std::mem::replace(&mut self.label_tries, LabelTryScan::scan(procedure.get_statements()));
         self.local_constants = Some(HashMap::new());
         self.collect_local_bindings(procedure.get_parameters(), procedure.get_statements());
         self.collect_constants(procedure.get_statements(), true);
@@ -531,6 +776,7 @@ impl AstVisitorMut for AstTransformationVisitor {
         ));
         self.local_constants = None;
         self.local_bindings = None;
+        self.label_tries = outer_labels;
         res
     }
 
@@ -566,6 +812,11 @@ impl AstVisitorMut for AstTransformationVisitor {
                 _ => {}
             }
         }
+        self.label_tries = LabelTryScan::scan(program.nodes.iter().flat_map(|node| match node {
+            AstNode::TopLevelStatement(stmt) => std::slice::from_ref(stmt),
+            AstNode::Main(block) => block.get_statements().as_slice(),
+            _ => &[],
+        }));
 
         let mut new_program = Ast::new();
         new_program.file_name.clone_from(&program.file_name);
@@ -1,6 +1,6 @@
 use crate::{
     ast::AstVisitor,
-    executable::{PPEExpr, VariableType},
+    executable::{CollectionMember, FuncOpCode, PPEExpr, VariableType},
     semantic::SemanticInfo,
 };
 
@@ -65,6 +65,18 @@ impl AstVisitor<PPEExpr> for ExpressionCompiler<'_> {
         else {
             return PPEExpr::Value(0);
         };
+        if self
+            .compiler
+            .semantic_visitor
+            .type_registry
+            .get_collection(VariableType::UserData(*type_id))
+            .is_some()
+        {
+            let Some(member) = CollectionMember::from_name(member_reference_expression.get_identifier()) else {
+                return PPEExpr::Value(0);
+            };
+            return PPEExpr::Member(Box::new(base), member as usize);
+        }
         if let Some(member_id) = self
             .compiler
             .semantic_visitor
@@ -107,6 +119,11 @@ impl AstVisitor<PPEExpr> for ExpressionCompiler<'_> {
         };
 
         match function_type {
+            SemanticInfo::PredefinedFunc(FuncOpCode::JsonStr) => {
+                let mut arguments: Vec<PPEExpr> = arguments;
+                arguments.push(self.compiler.json_fields(call.get_expression().get_span().start));
+                PPEExpr::PredefinedFunctionCall(FuncOpCode::JsonText.get_definition(), arguments)
+            }
             SemanticInfo::PredefinedFunc(op_code) => {
                 PPEExpr::PredefinedFunctionCall(
                     op_code.get_definition(), // to de-alias aliases
@@ -148,7 +165,9 @@ impl AstVisitor<PPEExpr> for ExpressionCompiler<'_> {
             if var.value.get_type() == VariableType::Function {
                 return PPEExpr::FunctionCall(var.header.id, arguments);
             }
-            if var.header.dim as usize != arguments.len() {
+            let is_item =
+                var.header.dim == 0 && arguments.len() == 1 && self.compiler.semantic_visitor.type_registry.get_collection(var.header.variable_type).is_some();
+            if var.header.dim as usize != arguments.len() && !is_item {
                 log::error!("Invalid dimensions for function call: {}", indexer.get_identifier());
                 return PPEExpr::Value(0);
             }
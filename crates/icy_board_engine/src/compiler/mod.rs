@@ -2,18 +2,23 @@ pub use ast_transform::*;
 use workspace::Workspace;
 pub mod ast_transform;
 mod enum_lowering;
+pub mod module_lowering;
 pub mod optimizer;
 pub mod user_data;
 
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{Arc, Mutex},
 };
 use thiserror::Error;
 
 use crate::{
-    ast::{Ast, AstNode, Expression, OnErrorMode, Statement},
-    executable::{Executable, ExpressionNegator, OnErrorTarget, OpCode, PPECommand, PPEExpr, PPEScript, VariableType},
+    ast::{Ast, AstNode, Constant, Expression, OnErrorMode, Statement},
+    executable::{
+        DebugInfo, EntryType, Executable, ExpressionNegator, OnErrorTarget, OpCode, PPECommand, PPEExpr, PPEScript, TypeLayout, VariableName, VariableType,
+        line_of,
+    },
     parser::{
         ErrorReporter, UserTypeRegistry,
         lexer::{Spanned, Token},
@@ -102,6 +107,36 @@ pub enum CompilationErrorType {
     #[error("Record literals need runtime {0}")]
     RecordLiteralNeedsRuntime(u16),
 
+    #[error("'LIST OF' and 'MAP OF' need runtime {0}")]
+    CollectionNeedsRuntime(u16),
+
+    #[error("TRY needs runtime {0}")]
+    TryNeedsRuntime(u16),
+
+    #[error("Can't jump into a TRY block from outside of it ({0})")]
+    JumpIntoTry(String),
+
+    #[error("Module {0} is already defined")]
+    ModuleAlreadyDefined(String),
+
+    #[error("Module {0} not found")]
+    ModuleNotFound(String),
+
+    #[error("Module {0} is used without 'IMPORT {0}'")]
+    ModuleNotImported(String),
+
+    #[error("Module {0} has no member named {1}")]
+    ModuleMemberNotFound(String, String),
+
+    #[error("{1} is private to module {0}")]
+    ModuleMemberIsPrivate(String, String),
+
+    #[error("{0} has no member named {1}")]
+    CollectionMemberNotFound(String, String),
+
+    #[error("FOR EACH needs a LIST or MAP, got {0}")]
+    CollectionExpected(String),
+
     #[error("Unused variable ({0})")]
     UnusedVariable(String),
 
@@ -182,6 +217,12 @@ pub struct PPECompiler {
     label_lookup_table: HashMap<unicase::Ascii<String>, usize>,
 
     commands: PPEScript,
+
+    /// The files in the order they were compiled, `cur_file` indexes it.
+    files: Vec<PathBuf>,
+    cur_file: usize,
+    /// Byte offset of every statement with the file and source position it came from.
+    source_positions: Vec<(usize, usize, usize)>,
 }
 
 impl PPECompiler {
@@ -195,6 +236,9 @@ impl PPECompiler {
             label_lookup_table: HashMap::new(),
             runtime: workspace.runtime(),
             commands: PPEScript::default(),
+            files: Vec::new(),
+            cur_file: 0,
+            source_positions: Vec::new(),
         }
     }
 
@@ -209,13 +253,21 @@ impl PPECompiler {
     /// Panics if .
     pub fn compile(&mut self, asts: &[&Ast]) {
         let mut visted = Vec::new();
+        let lowered = module_lowering::lower_modules(asts, &self.semantic_visitor.errors);
         // One transformer for the whole package, so its generated labels stay unique across files.
         let mut transformer = AstTransformationVisitor::new(true, self.semantic_visitor.type_registry.enums());
-        for prg in asts {
+        for prg in &lowered {
             self.semantic_visitor.errors.lock().unwrap().set_file_name(&prg.file_name);
             let prg = prg.visit_mut(&mut transformer);
             // println!("{}", prg);
             self.semantic_visitor.set_loop_counters(transformer.take_loop_counters());
+            for label in transformer.take_jumps_into_try() {
+                self.semantic_visitor
+                    .errors
+                    .lock()
+                    .unwrap()
+                    .report_error(label.span.clone(), CompilationErrorType::JumpIntoTry(label.token.to_string()));
+            }
             prg.visit(&mut self.semantic_visitor);
             visted.push(prg);
         }
@@ -228,6 +280,8 @@ impl PPECompiler {
         self.lookup_table = self.semantic_visitor.generate_variable_table();
         for prg in visted {
             self.semantic_visitor.errors.lock().unwrap().set_file_name(&prg.file_name);
+            self.cur_file = self.files.len();
+            self.files.push(prg.file_name.clone());
             for d in &prg.nodes {
                 match d {
                     AstNode::Function(_func) => {}
@@ -237,6 +291,9 @@ impl PPECompiler {
                     // The layout is settled while parsing, nothing is emitted for it.
                     AstNode::TypeDeclaration(_type_decl) => {}
                     AstNode::EnumDeclaration(_enum_decl) => {}
+                    // Lowered into ordinary globals and routines before anything gets here.
+                    AstNode::Module(_module) => {}
+                    AstNode::Import(_import) => {}
                     AstNode::TopLevelStatement(stmt) => {
                         // may get transformed by the ast transformer.
                         if let Statement::Block(block) = stmt {
@@ -308,7 +365,12 @@ impl PPECompiler {
             }
             return;
         }
+        let span = stmt.get_span();
         if let Some(stmt) = self.compile_statement(stmt) {
+            // Statements the transformer made up have no place in the source.
+            if !span.is_empty() {
+                self.source_positions.push((self.cur_offset * 2, self.cur_file, span.start));
+            }
             self.commands.add_statement(&mut self.cur_offset, stmt);
         }
     }
@@ -334,6 +396,10 @@ impl PPECompiler {
                 };
                 Some(PPECommand::OnError(target))
             }
+            Statement::TryMarker(marker) => match marker.get_catch_label_token() {
+                Some(label_token) => Some(PPECommand::TryBegin(self.get_label_index(label_token))),
+                None => Some(PPECommand::PredefinedCall(OpCode::TryEnd.get_definition(), Vec::new())),
+            },
             Statement::Label(label) => {
                 self.set_label_offset(label.get_label_token());
                 None
@@ -368,7 +434,14 @@ impl PPECompiler {
                     }
                 }
 
-                if decl.header.dim != let_smt.get_arguments().len() as u8 {
+                // An item of a collection is set through its key, the way an array element is.
+                let collection_item = self
+                    .semantic_visitor
+                    .type_registry
+                    .get_collection(decl.header.variable_type)
+                    .map(|(_, element)| element);
+                let is_item = decl.header.dim == 0 && let_smt.get_arguments().len() == 1 && collection_item.is_some();
+                if decl.header.dim != let_smt.get_arguments().len() as u8 && !is_item {
                     log::error!("Invalid dimensions for variable: {var_name}");
                     return None;
                 }
@@ -377,9 +450,13 @@ impl PPECompiler {
                 } else {
                     decl.header.id
                 };
-                let variable_type = decl.header.variable_type;
+                let variable_type = if is_item {
+                    collection_item.unwrap_or(VariableType::None)
+                } else {
+                    decl.header.variable_type
+                };
                 let dim = decl.header.dim;
-                let variable = if dim == 0 {
+                let variable = if dim == 0 && !is_item {
                     PPEExpr::Value(decl_id)
                 } else {
                     let mut arguments = Vec::new();
@@ -422,6 +499,10 @@ impl PPECompiler {
                     let expr_buffer = self.comp_expr(arg);
                     arguments.push(expr_buffer);
                 }
+                if def.opcode == OpCode::JsonRead {
+                    arguments.push(self.json_fields(call_stmt.get_identifier_token().span.start));
+                    return Some(PPECommand::PredefinedCall(OpCode::JsonReadFields.get_definition(), arguments));
+                }
 
                 Some(PPECommand::PredefinedCall(
                     def.opcode.get_definition(), // to de-alias aliases
@@ -470,6 +551,8 @@ impl PPECompiler {
             Statement::RepeatUntil(_) => panic!("repeat until not allowed in output AST."),
             Statement::Loop(_) => panic!("loop not allowed in output AST."),
             Statement::For(_) => panic!("for not allowed in output AST."),
+            Statement::ForEach(_) => panic!("for each not allowed in output AST."),
+            Statement::Try(_) => panic!("try not allowed in output AST."),
             Statement::Select(_) => panic!("select not allowed in output AST."),
         }
     }
@@ -495,23 +578,20 @@ impl PPECompiler {
     pub fn create_executable(&self) -> Result<Executable, CompilationErrorType> {
         let mut variable_table = self.lookup_table.variable_table.clone();
         variable_table.set_version(self.runtime);
-        let user_types: Vec<Vec<VariableType>> = self
-            .semantic_visitor
-            .type_registry
+        let registry = &self.semantic_visitor.type_registry;
+        let stored_type = |field_type: VariableType| {
+            if registry.is_enum_type(field_type) {
+                VariableType::Integer
+            } else {
+                field_type
+            }
+        };
+        let user_types: Vec<TypeLayout> = registry
             .user_types()
             .iter()
-            .map(|definition| {
-                definition
-                    .fields
-                    .iter()
-                    .map(|(_, field_type)| {
-                        if self.semantic_visitor.type_registry.is_enum_type(*field_type) {
-                            VariableType::Integer
-                        } else {
-                            *field_type
-                        }
-                    })
-                    .collect()
+            .map(|definition| match definition.collection {
+                Some((kind, element)) => TypeLayout::Collection(kind, stored_type(element)),
+                None => TypeLayout::Record(definition.fields.iter().map(|(_, field_type)| stored_type(*field_type)).collect()),
             })
             .collect();
         variable_table.fill_in_records(&user_types);
@@ -523,6 +603,47 @@ impl PPECompiler {
         })
     }
 
+    /// Where the statements of `executable` came from and what its variables were called.
+    /// `sources` are the texts of the compiled files, in the order they were passed to `compile`.
+    pub fn create_debug_info(&self, executable: &Executable, sources: &[&str]) -> DebugInfo {
+        let mut info = DebugInfo::new(executable);
+        // A debugger opens them from wherever it runs.
+        info.files = self.files.iter().map(|file| std::fs::canonicalize(file).unwrap_or_else(|_| file.clone())).collect();
+        for (offset, file, position) in &self.source_positions {
+            let line = sources.get(*file).map_or(0, |source| line_of(source, *position));
+            info.add_line(*offset, *file, line);
+        }
+
+        let entries = executable.variable_table.get_entries();
+        // A function's result goes by the function's name, the decompiler puts that back by itself.
+        let results: Vec<usize> = entries
+            .iter()
+            .filter(|entry| entry.header.variable_type == VariableType::Function)
+            .map(|entry| unsafe { entry.value.data.function_value.return_var as usize })
+            .collect();
+        for entry in entries {
+            // The counter a FOR EACH keeps is no name anybody wrote.
+            if matches!(entry.get_type(), EntryType::Constant | EntryType::UserVariable)
+                || results.contains(&entry.header.id)
+                || entry.get_name().starts_with("*(")
+            {
+                continue;
+            }
+            info.variables.push(VariableName {
+                id: entry.header.id,
+                name: entry.get_name().clone(),
+            });
+        }
+        for definition in self.semantic_visitor.type_registry.user_types() {
+            info.types.push(TypeName {
+                id: definition.id as u8,
+                name: definition.name.to_string(),
+                fields: definition.fields.iter().map(|(name, _)| name.to_string()).collect(),
+            });
+        }
+        info
+    }
+
     fn comp_expr(&mut self, expr: &Expression) -> PPEExpr {
         expr.visit(&mut ExpressionCompiler { compiler: self })
     }
@@ -564,6 +685,13 @@ impl PPECompiler {
         }
     }
 
+    /// The constant holding the field names semantic analysis worked out for the
+    /// `JsonStr` or `JsonRead` whose name starts at `name_start`.
+    fn json_fields(&mut self, name_start: usize) -> PPEExpr {
+        let fields = self.semantic_visitor.json_fields.get(&name_start).cloned().unwrap_or_default();
+        PPEExpr::Value(self.lookup_table.lookup_constant(&Constant::String(fields)))
+    }
+
     fn lookup_variable_index(&self, get_identifier: &unicase::Ascii<String>) -> Option<usize> {
         self.lookup_table.lookup_variable_index(get_identifier)
     }
@@ -572,7 +700,7 @@ impl PPECompiler {
         let last = (self.commands.statements.len() as i32 - 1) as usize;
         for stmt in &mut self.commands.statements {
             match &mut stmt.command {
-                PPECommand::IfNot(_, idx) | PPECommand::Goto(idx) | PPECommand::Gosub(idx) => {
+                PPECommand::IfNot(_, idx) | PPECommand::Goto(idx) | PPECommand::Gosub(idx) | PPECommand::TryBegin(idx) => {
                     if let Some(label_descr) = self.label_table.get(*idx) {
                         if let Some(offset) = label_descr.offset {
                             *idx = offset * 2;
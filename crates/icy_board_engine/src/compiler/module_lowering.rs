@@ -0,0 +1,319 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    ast::{
+        Ast, AstNode, AstVisitor, AstVisitorMut, BinaryExpression, ConstDeclarationStatement, Expression, IdentifierExpression, LetStatement,
+        MemberCallStatement, MemberReferenceExpression, OnErrorMode, OnErrorStatement, ParameterSpecifier, ParensExpression, ProcedureCallStatement,
+        RecordLiteralExpression, RecordLiteralField, Statement, UnaryExpression, VariableDeclarationStatement,
+    },
+    parser::{
+        ErrorReporter,
+        lexer::{Spanned, Token},
+    },
+};
+
+use super::CompilationErrorType;
+
+/// The name a module member is compiled under. An identifier can't hold a dot,
+/// so it never clashes with a name of the program.
+pub fn mangled_name(module: &unicase::Ascii<String>, member: &unicase::Ascii<String>) -> unicase::Ascii<String> {
+    unicase::Ascii::new(format!("{module}.{member}"))
+}
+
+/// The members of a module, with whether each is public.
+struct ModuleDefinition {
+    members: HashMap<unicase::Ascii<String>, bool>,
+}
+
+/// Turns every `MODULE` into ordinary globals and routines and every `module.member`
+/// into the name the member got, so nothing after this has to know about modules.
+pub fn lower_modules(asts: &[&Ast], errors: &Arc<Mutex<ErrorReporter>>) -> Vec<Ast> {
+    let mut modules = HashMap::new();
+    for ast in asts {
+        errors.lock().unwrap().set_file_name(&ast.file_name);
+        for node in &ast.nodes {
+            let AstNode::Module(module) = node else {
+                continue;
+            };
+            if modules.contains_key(module.get_identifier()) {
+                errors.lock().unwrap().report_error(
+                    module.get_identifier_token().span.clone(),
+                    CompilationErrorType::ModuleAlreadyDefined(module.get_identifier().to_string()),
+                );
+                continue;
+            }
+            let mut members: HashMap<unicase::Ascii<String>, bool> = HashMap::new();
+            for member in module.get_members() {
+                for token in member.get_declared_tokens() {
+                    if let Token::Identifier(name) = &token.token {
+                        // A DECLARE and its routine are one member, either may say PUBLIC.
+                        *members.entry(name.clone()).or_default() |= member.is_public();
+                    }
+                }
+            }
+            modules.insert(module.get_identifier().clone(), ModuleDefinition { members });
+        }
+    }
+
+    asts.iter().map(|ast| lower_file(ast, &modules, errors)).collect()
+}
+
+fn lower_file(ast: &Ast, modules: &HashMap<unicase::Ascii<String>, ModuleDefinition>, errors: &Arc<Mutex<ErrorReporter>>) -> Ast {
+    errors.lock().unwrap().set_file_name(&ast.file_name);
+    let mut imports = Vec::new();
+    for node in &ast.nodes {
+        if let AstNode::Import(import) = node {
+            if modules.contains_key(import.get_identifier()) {
+                imports.push(import.get_identifier().clone());
+            } else {
+                errors.lock().unwrap().report_error(
+                    import.get_identifier_token().span.clone(),
+                    CompilationErrorType::ModuleNotFound(import.get_identifier().to_string()),
+                );
+            }
+        }
+    }
+
+    let mut lowered = Ast::new();
+    lowered.file_name.clone_from(&ast.file_name);
+    lowered.language_version = ast.language_version;
+    lowered.require_user_variables = ast.require_user_variables;
+    if modules.is_empty() {
+        lowered.nodes = ast.nodes.iter().filter(|node| !matches!(node, AstNode::Import(_))).cloned().collect();
+        return lowered;
+    }
+
+    let mut visitor = ModuleLoweringVisitor {
+        modules,
+        imports,
+        cur_module: None,
+        locals: HashSet::new(),
+        errors: errors.clone(),
+    };
+    for node in &ast.nodes {
+        match node {
+            AstNode::Import(_) => {}
+            AstNode::Module(module) => {
+                visitor.cur_module = Some(module.get_identifier().clone());
+                for member in module.get_members() {
+                    visitor.locals = routine_locals(member.get_node());
+                    lowered.nodes.push(member.get_node().visit_mut(&mut visitor));
+                }
+                visitor.cur_module = None;
+            }
+            _ => {
+                visitor.locals = routine_locals(node);
+                lowered.nodes.push(node.visit_mut(&mut visitor));
+            }
+        }
+    }
+    lowered
+}
+
+/// The parameters and locals of a routine, which hide the members of its module.
+fn routine_locals(node: &AstNode) -> HashSet<unicase::Ascii<String>> {
+    let (parameters, statements) = match node {
+        AstNode::Function(function) => (function.get_parameters(), function.get_statements()),
+        AstNode::Procedure(procedure) => (procedure.get_parameters(), procedure.get_statements()),
+        _ => return HashSet::new(),
+    };
+    let mut collector = LocalCollector::default();
+    for parameter in parameters {
+        match parameter {
+            ParameterSpecifier::Variable(variable) => {
+                if let Some(variable) = variable.get_variable() {
+                    collector.names.insert(variable.get_identifier().clone());
+                }
+            }
+            ParameterSpecifier::Function(function) => {
+                collector.names.insert(function.get_identifier().clone());
+            }
+            ParameterSpecifier::Procedure(procedure) => {
+                collector.names.insert(procedure.get_identifier().clone());
+            }
+        }
+    }
+    for statement in statements {
+        statement.visit(&mut collector);
+    }
+    collector.names
+}
+
+#[derive(Default)]
+struct LocalCollector {
+    names: HashSet<unicase::Ascii<String>>,
+}
+
+impl AstVisitor<()> for LocalCollector {
+    fn visit_variable_declaration_statement(&mut self, var_decl: &VariableDeclarationStatement) {
+        for variable in var_decl.get_variables() {
+            self.names.insert(variable.get_identifier().clone());
+        }
+    }
+
+    fn visit_const_declaration_statement(&mut self, const_decl: &ConstDeclarationStatement) {
+        self.names.insert(const_decl.get_identifier().clone());
+    }
+}
+
+struct ModuleLoweringVisitor<'a> {
+    modules: &'a HashMap<unicase::Ascii<String>, ModuleDefinition>,
+    imports: Vec<unicase::Ascii<String>>,
+    /// The module whose members are being lowered, they reach each other unqualified.
+    cur_module: Option<unicase::Ascii<String>>,
+    locals: HashSet<unicase::Ascii<String>>,
+    errors: Arc<Mutex<ErrorReporter>>,
+}
+
+impl ModuleLoweringVisitor<'_> {
+    /// The name `module.member` stands for, `None` when `module` isn't a module.
+    fn resolve(&mut self, module: &unicase::Ascii<String>, member_token: &Spanned<Token>) -> Option<unicase::Ascii<String>> {
+        if self.locals.contains(module) {
+            return None;
+        }
+        let definition = self.modules.get(module)?;
+        let Token::Identifier(member) = &member_token.token else {
+            return None;
+        };
+        let inside = self.cur_module.as_ref() == Some(module);
+        let error = match definition.members.get(member) {
+            None => Some(CompilationErrorType::ModuleMemberNotFound(module.to_string(), member.to_string())),
+            Some(_) if !inside && !self.imports.contains(module) => Some(CompilationErrorType::ModuleNotImported(module.to_string())),
+            Some(false) if !inside => Some(CompilationErrorType::ModuleMemberIsPrivate(module.to_string(), member.to_string())),
+            Some(_) => None,
+        };
+        if let Some(error) = error {
+            self.errors.lock().unwrap().report_error(member_token.span.clone(), error);
+        }
+        Some(mangled_name(module, member))
+    }
+
+    fn resolve_expression(&mut self, expression: &Expression, member_token: &Spanned<Token>) -> Option<Spanned<Token>> {
+        let Expression::Identifier(module) = expression else {
+            return None;
+        };
+        let mangled = self.resolve(module.get_identifier(), member_token)?;
+        Some(Spanned::new(Token::Identifier(mangled), member_token.span.clone()))
+    }
+}
+
+impl AstVisitorMut for ModuleLoweringVisitor<'_> {
+    fn visit_identifier(&mut self, id: &unicase::Ascii<String>) -> unicase::Ascii<String> {
+        if let Some(module) = &self.cur_module
+            && !self.locals.contains(id)
+            && self.modules[module].members.contains_key(id)
+        {
+            return mangled_name(module, id);
+        }
+        id.clone()
+    }
+
+    fn visit_member_reference_expression(&mut self, member_ref: &MemberReferenceExpression) -> Expression {
+        if let Some(identifier_token) = self.resolve_expression(member_ref.get_expression(), member_ref.get_identifier_token()) {
+            return Expression::Identifier(IdentifierExpression::new(identifier_token));
+        }
+        // A record field keeps its name, only the record may belong to a module.
+        Expression::MemberReference(MemberReferenceExpression::new(
+            member_ref.get_expression().visit_mut(self),
+            member_ref.get_dot_token().clone(),
+            member_ref.get_identifier_token().clone(),
+        ))
+    }
+
+    fn visit_record_literal_expression(&mut self, record: &RecordLiteralExpression) -> Expression {
+        Expression::RecordLiteral(RecordLiteralExpression::new(
+            record.get_type_token().clone(),
+            record.get_variable_type(),
+            record.get_lbrace_token().clone(),
+            record
+                .get_fields()
+                .iter()
+                .map(|field| RecordLiteralField::new(field.get_identifier_token().clone(), field.get_value().visit_mut(self)))
+                .collect(),
+            record.get_rbrace_token().clone(),
+        ))
+    }
+
+    // The language server reports against the lowered tree, so the operators keep their place.
+    fn visit_unary_expression(&mut self, unary: &UnaryExpression) -> Expression {
+        Expression::Unary(UnaryExpression::new(unary.get_op_token().clone(), unary.get_expression().visit_mut(self)))
+    }
+
+    fn visit_binary_expression(&mut self, binary: &BinaryExpression) -> Expression {
+        Expression::Binary(BinaryExpression::new(
+            binary.get_left_expression().visit_mut(self),
+            binary.get_op_token().clone(),
+            binary.get_right_expression().visit_mut(self),
+        ))
+    }
+
+    fn visit_parens_expression(&mut self, parens: &ParensExpression) -> Expression {
+        Expression::Parens(ParensExpression::new(
+            parens.get_lpar_token().clone(),
+            parens.get_expression().visit_mut(self),
+            parens.get_rpar_token().clone(),
+        ))
+    }
+
+    fn visit_let_statement(&mut self, let_stmt: &LetStatement) -> Statement {
+        // `module.member = value` reads as a record assignment until here.
+        if let_stmt.get_arguments().is_empty()
+            && let Some((member_token, fields)) = let_stmt.get_members().split_first()
+            && let Some(identifier_token) = self.resolve(let_stmt.get_identifier(), member_token)
+        {
+            return Statement::Let(LetStatement::new(
+                let_stmt.get_let_token().clone(),
+                Spanned::new(Token::Identifier(identifier_token), member_token.span.clone()),
+                None,
+                Vec::new(),
+                None,
+                fields.to_vec(),
+                let_stmt.get_eq_token().clone(),
+                let_stmt.get_value_expression().visit_mut(self),
+            ));
+        }
+        Statement::Let(LetStatement::new(
+            let_stmt.get_let_token().clone(),
+            Spanned {
+                span: let_stmt.get_identifier_token().span.clone(),
+                token: Token::Identifier(self.visit_identifier(let_stmt.get_identifier())),
+            },
+            let_stmt.get_lpar_token().clone(),
+            let_stmt.get_arguments().iter().map(|arg| arg.visit_mut(self)).collect(),
+            let_stmt.get_rpar_token().clone(),
+            let_stmt.get_members().clone(),
+            let_stmt.get_eq_token().clone(),
+            let_stmt.get_value_expression().visit_mut(self),
+        ))
+    }
+
+    fn visit_member_call_statement(&mut self, call: &MemberCallStatement) -> Statement {
+        // `module.procedure(...)` is a plain procedure call once the module is resolved.
+        if let Expression::FunctionCall(function_call) = call.get_expression()
+            && let Expression::MemberReference(member_ref) = function_call.get_expression()
+            && let Some(identifier_token) = self.resolve_expression(member_ref.get_expression(), member_ref.get_identifier_token())
+        {
+            return Statement::Call(ProcedureCallStatement::new(
+                identifier_token,
+                function_call.get_lpar_token().clone(),
+                function_call.get_arguments().iter().map(|arg| arg.visit_mut(self)).collect(),
+                function_call.get_rpar_token().clone(),
+            ));
+        }
+        Statement::MemberCall(MemberCallStatement::new(call.get_expression().visit_mut(self)))
+    }
+
+    fn visit_on_error_statement(&mut self, on_error: &OnErrorStatement) -> Statement {
+        let mut on_error = on_error.clone();
+        if on_error.get_mode() == OnErrorMode::Procedure
+            && let Some(target) = on_error.get_target()
+        {
+            let target = self.visit_identifier(target);
+            on_error.set_target(target);
+        }
+        Statement::OnError(on_error)
+    }
+}
@@ -98,7 +98,9 @@ fn remove_jumps_to_the_following_statement(statements: &mut Vec<Statement>) {
 fn remove_unreachable_statements(statements: &mut Vec<Statement>) {
     let mut reachable = true;
     statements.retain(|statement| {
-        if matches!(statement, Statement::Label(_)) {
+        // The end of a TRY block stays even behind a RETURN, it is how the decompiler
+        // finds where the block stops.
+        if matches!(statement, Statement::Label(_)) || matches!(statement, Statement::TryMarker(marker) if marker.get_catch_label().is_none()) {
             reachable = true;
         }
         // A declaration may sit in a part of the program that is never entered and the
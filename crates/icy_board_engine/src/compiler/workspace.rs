@@ -1,15 +1,18 @@
 use std::{
+    collections::BTreeMap,
     fs,
     path::{Path, PathBuf},
 };
 
 use semver::Version;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
     Res,
-    executable::{LAST_PPE_RUNTIME, LAST_PPL_LANGUAGE_VERSION},
+    executable::{LAST_PPE_RUNTIME, language_version_for_runtime},
     formatting::FormattingOptions,
+    vm::Permissions,
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -47,6 +50,22 @@ pub struct CompilerData {
     pub defines: Option<Vec<String>>,
 }
 
+/// A package whose modules the workspace imports, see `[dependencies]` in `ppl.toml`.
+#[derive(Default, Debug, Deserialize, Serialize)]
+pub struct Dependency {
+    /// The package directory relative to this `ppl.toml`, `vendor/<name>` when left out.
+    pub path: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum DependencyError {
+    #[error("Dependency {0} not found, there is no {1}")]
+    NotFound(String, String),
+
+    #[error("Dependency {0} depends on the package that uses it")]
+    Cycle(String),
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Workspace {
     #[serde(skip)]
@@ -58,7 +77,10 @@ pub struct Workspace {
     pub package: Package,
     pub compiler: Option<CompilerData>,
     pub data: Option<PackageData>,
+    pub dependencies: Option<BTreeMap<String, Dependency>>,
     formatting: Option<FormattingOptions>,
+    /// What the PPE needs to be allowed, `pplc` writes it next to the PPE.
+    pub permissions: Option<Permissions>,
 }
 impl Default for Workspace {
     fn default() -> Self {
@@ -72,7 +94,9 @@ impl Default for Workspace {
             },
             compiler: None,
             data: None,
+            dependencies: None,
             formatting: None,
+            permissions: None,
             hard_coded_files: None,
         }
     }
@@ -146,6 +170,51 @@ impl Workspace {
         files
     }
 
+    /// Every package the workspace depends on with its sources, its own dependencies
+    /// first. Each one is parsed with its own settings and compiled after `files()`, so
+    /// the program still starts in `main`.
+    pub fn dependency_packages(&self) -> Res<Vec<(Workspace, Vec<PathBuf>)>> {
+        let mut packages = Vec::new();
+        let mut chain = vec![self.file_name.canonicalize().unwrap_or_else(|_| self.file_name.clone())];
+        let mut loaded = Vec::new();
+        self.collect_dependency_packages(&mut chain, &mut loaded, &mut packages)?;
+        Ok(packages)
+    }
+
+    fn collect_dependency_packages(&self, chain: &mut Vec<PathBuf>, loaded: &mut Vec<PathBuf>, packages: &mut Vec<(Workspace, Vec<PathBuf>)>) -> Res<()> {
+        let Some(dependencies) = &self.dependencies else {
+            return Ok(());
+        };
+        let base_path = self.file_name.parent().unwrap_or(Path::new(""));
+        for (name, dependency) in dependencies {
+            let directory = match &dependency.path {
+                Some(path) => base_path.join(path),
+                None => base_path.join("vendor").join(name),
+            };
+            let manifest = directory.join("ppl.toml");
+            if !manifest.is_file() {
+                return Err(Box::new(DependencyError::NotFound(name.clone(), manifest.display().to_string())));
+            }
+            let manifest = manifest.canonicalize()?;
+            if chain.contains(&manifest) {
+                return Err(Box::new(DependencyError::Cycle(name.clone())));
+            }
+            // Two packages may share a dependency, its modules are only compiled once.
+            if loaded.contains(&manifest) {
+                continue;
+            }
+            loaded.push(manifest.clone());
+
+            let package = Workspace::load(&manifest)?;
+            chain.push(manifest);
+            package.collect_dependency_packages(chain, loaded, packages)?;
+            chain.pop();
+            let files = package.files();
+            packages.push((package, files));
+        }
+        Ok(())
+    }
+
     pub fn runtime(&self) -> u16 {
         self.package.runtime.unwrap_or(LAST_PPE_RUNTIME)
     }
@@ -156,7 +225,7 @@ impl Workspace {
         {
             return language_version;
         }
-        self.runtime().min(LAST_PPL_LANGUAGE_VERSION)
+        language_version_for_runtime(self.runtime())
     }
 
     /// Takes a language version the caller found elsewhere, for a workspace that
@@ -175,6 +244,7 @@ impl Workspace {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::executable::LAST_PPL_LANGUAGE_VERSION;
 
     #[test]
     fn default_runtime_and_language_are_independent() {
@@ -190,6 +260,13 @@ mod tests {
         assert_eq!(340, workspace.language_version());
     }
 
+    #[test]
+    fn a_format_only_runtime_keeps_the_language_before_it() {
+        let mut workspace = Workspace::default();
+        workspace.package.runtime = Some(403);
+        assert_eq!(400, workspace.language_version());
+    }
+
     #[test]
     fn a_default_language_version_is_taken_when_none_is_stated() {
         let mut workspace = Workspace::default();
@@ -205,6 +282,101 @@ mod tests {
         assert_eq!(350, workspace.language_version());
     }
 
+    fn package(root: &Path, directory: &str, manifest: &str, sources: &[&str]) {
+        let directory = root.join(directory);
+        fs::create_dir_all(directory.join("src")).unwrap();
+        fs::write(directory.join("ppl.toml"), manifest).unwrap();
+        for source in sources {
+            fs::write(directory.join("src").join(source), "").unwrap();
+        }
+    }
+
+    const PACKAGE: &str = "[package]\nname = \"test\"\nversion = \"0.1.0\"\n";
+
+    #[test]
+    fn dependencies_are_found_by_path_and_in_vendor() {
+        let root = tempfile::tempdir().unwrap();
+        package(
+            root.path(),
+            "app",
+            &format!("{PACKAGE}[dependencies]\nstrings = {{ path = \"../strings\" }}\ncommon = {{}}\n"),
+            &["main.pps"],
+        );
+        package(root.path(), "strings", PACKAGE, &["strings.pps"]);
+        package(root.path(), "app/vendor/common", PACKAGE, &["common.pps"]);
+
+        let workspace = Workspace::load(root.path().join("app/ppl.toml")).unwrap();
+        let names: Vec<_> = workspace
+            .dependency_packages()
+            .unwrap()
+            .iter()
+            .flat_map(|(_, files)| files)
+            .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["common.pps", "strings.pps"]);
+    }
+
+    #[test]
+    fn a_dependency_of_a_dependency_comes_first_and_only_once() {
+        let root = tempfile::tempdir().unwrap();
+        package(
+            root.path(),
+            "app",
+            &format!("{PACKAGE}[dependencies]\na = {{ path = \"../a\" }}\nb = {{ path = \"../b\" }}\n"),
+            &["main.pps"],
+        );
+        package(root.path(), "a", &format!("{PACKAGE}[dependencies]\nb = {{ path = \"../b\" }}\n"), &["a.pps"]);
+        package(root.path(), "b", PACKAGE, &["b.pps"]);
+
+        let workspace = Workspace::load(root.path().join("app/ppl.toml")).unwrap();
+        let names: Vec<_> = workspace
+            .dependency_packages()
+            .unwrap()
+            .iter()
+            .flat_map(|(_, files)| files)
+            .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["b.pps", "a.pps"]);
+    }
+
+    #[test]
+    fn a_dependency_comes_with_its_own_manifest() {
+        let root = tempfile::tempdir().unwrap();
+        package(
+            root.path(),
+            "app",
+            &format!("{PACKAGE}runtime = 400\n[dependencies]\nstrings = {{ path = \"../strings\" }}\n"),
+            &["main.pps"],
+        );
+        package(root.path(), "strings", &format!("{PACKAGE}runtime = 350\n"), &["strings.pps"]);
+
+        let workspace = Workspace::load(root.path().join("app/ppl.toml")).unwrap();
+        let packages = workspace.dependency_packages().unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].0.runtime(), 350);
+    }
+
+    #[test]
+    fn a_missing_dependency_is_an_error() {
+        let root = tempfile::tempdir().unwrap();
+        package(root.path(), "app", &format!("{PACKAGE}[dependencies]\ncommon = {{}}\n"), &["main.pps"]);
+
+        let workspace = Workspace::load(root.path().join("app/ppl.toml")).unwrap();
+        let error = workspace.dependency_packages().unwrap_err();
+        assert!(matches!(error.downcast_ref::<DependencyError>(), Some(DependencyError::NotFound(name, _)) if name == "common"));
+    }
+
+    #[test]
+    fn a_dependency_cycle_is_an_error() {
+        let root = tempfile::tempdir().unwrap();
+        package(root.path(), "a", &format!("{PACKAGE}[dependencies]\nb = {{ path = \"../b\" }}\n"), &["a.pps"]);
+        package(root.path(), "b", &format!("{PACKAGE}[dependencies]\na = {{ path = \"../a\" }}\n"), &["b.pps"]);
+
+        let workspace = Workspace::load(root.path().join("a/ppl.toml")).unwrap();
+        let error = workspace.dependency_packages().unwrap_err();
+        assert!(matches!(error.downcast_ref::<DependencyError>(), Some(DependencyError::Cycle(name)) if name == "a"));
+    }
+
     #[test]
     fn no_default_leaves_the_workspace_alone() {
         let mut workspace = Workspace::default();
@@ -11,13 +11,14 @@ use crate::{
         Ast, AstNode, BinOp, BinaryExpression, BlockStatement, CommentAstNode, Constant, ConstantExpression, Expression, FunctionCallExpression,
         FunctionDeclarationAstNode, FunctionImplementation, GosubStatement, GotoStatement, IdentifierExpression, IfStatement, IndexerExpression,
         LabelStatement, LetStatement, MemberCallStatement, MemberReferenceExpression, OnErrorMode, OnErrorStatement, ParameterSpecifier, ParensExpression,
-        PredefinedCallStatement, ProcedureCallStatement, ProcedureDeclarationAstNode, ProcedureImplementation, Statement, TypeDeclarationAstNode,
-        TypeFieldSpecifier, UnaryExpression, UnaryOp, VariableDeclarationStatement, VariableParameterSpecifier, VariableSpecifier, constant::NumberFormat,
+        PredefinedCallStatement, ProcedureCallStatement, ProcedureDeclarationAstNode, ProcedureImplementation, Statement, TryMarkerStatement,
+        TypeDeclarationAstNode, TypeFieldSpecifier, UnaryExpression, UnaryOp, VariableDeclarationStatement, VariableParameterSpecifier, VariableSpecifier,
+        constant::NumberFormat,
     },
     compiler::{user_data::UserDataEntry, workspace::Workspace},
     executable::{
-        DeserializationError, DeserializationErrorType, EntryType, Executable, OpCode, PPECommand, PPEExpr, PPEScript, PPEVisitor, StatementDefinition,
-        TableEntry, VariableType,
+        CollectionKind, CollectionMember, DebugInfo, DeserializationError, DeserializationErrorType, EntryType, Executable, FuncOpCode, OpCode, PPECommand,
+        PPEExpr, PPEScript, PPEVisitor, StatementDefinition, TableEntry, TypeLayout, VariableType,
     },
     parser::{
         ErrorReporter, UserTypeRegistry, is_user_declared_type,
@@ -52,11 +53,19 @@ fn user_field_name(index: usize) -> unicase::Ascii<String> {
 }
 
 /// The board objects, plus a stand-in declaration for every record the PPE carries.
+/// Collections keep their real names, those follow from the element type.
 fn build_type_registry(executable: &Executable) -> UserTypeRegistry {
     let registry = UserTypeRegistry::icy_board_registry();
-    for (i, fields) in executable.user_types.iter().enumerate() {
-        let fields = fields.iter().enumerate().map(|(j, t)| (user_field_name(j), *t)).collect();
-        registry.declare_user_type(user_type_name(i), fields);
+    for (i, layout) in executable.user_types.iter().enumerate() {
+        match layout {
+            TypeLayout::Record(fields) => {
+                let fields = fields.iter().enumerate().map(|(j, t)| (user_field_name(j), *t)).collect();
+                registry.declare_user_type(user_type_name(i), fields);
+            }
+            TypeLayout::Collection(kind, element) => {
+                registry.declare_collection(*kind, *element);
+            }
+        }
     }
     registry
 }
@@ -76,6 +85,7 @@ pub struct Decompiler {
     issues: Vec<DecompilerIssue>,
     optimize_output: bool,
     type_registry: UserTypeRegistry,
+    debug_info: Option<DebugInfo>,
 }
 
 impl Decompiler {
@@ -98,15 +108,21 @@ impl Decompiler {
             issues: Vec::new(),
             optimize_output,
             type_registry,
+            debug_info: None,
         })
     }
 
+    /// Names from `pplc --debug` are used instead of made up ones.
+    pub fn set_debug_info(&mut self, debug_info: DebugInfo) {
+        self.debug_info = Some(debug_info);
+    }
+
     fn analyze_labels(&mut self) -> HashMap<usize, usize> {
         let mut labels = HashSet::new();
 
         for statement in &self.script.statements {
             match statement.command {
-                PPECommand::Goto(label) | PPECommand::Gosub(label) | PPECommand::IfNot(_, label) => {
+                PPECommand::Goto(label) | PPECommand::Gosub(label) | PPECommand::IfNot(_, label) | PPECommand::TryBegin(label) => {
                     labels.insert(label);
                 }
                 PPECommand::OnError(target) => {
@@ -144,6 +160,9 @@ impl Decompiler {
         self.executable.variable_table.analyze_usage(&self.script);
         self.executable.variable_table.analyze_locals();
         self.executable.variable_table.generate_names();
+        if let Some(debug_info) = &self.debug_info {
+            self.executable.variable_table.restore_names(debug_info);
+        }
 
         let mut ast = Ast::default();
 
@@ -227,9 +246,13 @@ impl Decompiler {
         }
     }
 
-    /// The records the PPE declares, under invented names.
+    /// The records the PPE declares, under invented names. A collection is written
+    /// out where it is used, so it needs no declaration.
     fn generate_type_declarations(&self, ast: &mut Ast) {
-        for (i, fields) in self.executable.user_types.iter().enumerate() {
+        for (i, layout) in self.executable.user_types.iter().enumerate() {
+            let TypeLayout::Record(fields) = layout else {
+                continue;
+            };
             let fields = fields
                 .iter()
                 .enumerate()
@@ -274,7 +297,12 @@ impl Decompiler {
 
     /// The name and type of member `id` of whatever `base` evaluates to.
     fn resolve_member(&self, base: &PPEExpr, id: usize) -> Option<(unicase::Ascii<String>, VariableType)> {
-        let VariableType::UserData(type_id) = self.expression_type(base)? else {
+        let base_type = self.expression_type(base)?;
+        if self.type_registry.get_collection(base_type).is_some() {
+            let member = CollectionMember::from_id(id)?;
+            return Some((unicase::Ascii::new(member.name().to_string()), member.return_type()));
+        }
+        let VariableType::UserData(type_id) = base_type else {
             return None;
         };
         if self.type_registry.is_record_type(type_id) {
@@ -297,7 +325,12 @@ impl Decompiler {
     /// table can say. Only member access needs this.
     fn expression_type(&self, expr: &PPEExpr) -> Option<VariableType> {
         match expr {
-            PPEExpr::Value(id) | PPEExpr::Dim(id, _) => Some(self.executable.variable_table.try_get_entry(*id)?.header.variable_type),
+            PPEExpr::Value(id) => Some(self.executable.variable_table.try_get_entry(*id)?.header.variable_type),
+            PPEExpr::Dim(id, _) => {
+                let variable_type = self.executable.variable_table.try_get_entry(*id)?.header.variable_type;
+                // An item of a collection is read like an array element.
+                Some(self.type_registry.get_collection(variable_type).map_or(variable_type, |(_, element)| element))
+            }
             PPEExpr::Member(base, id) => self.resolve_member(base, *id).map(|(_, t)| t),
             PPEExpr::MemberFunctionCall(base, _, id) => {
                 // Codegen leaves the member reference in the base, so reach past it.
@@ -312,6 +345,12 @@ impl Decompiler {
                 let return_var = unsafe { entry.value.data.function_value.return_var } as usize;
                 Some(self.executable.variable_table.try_get_entry(return_var)?.header.variable_type)
             }
+            PPEExpr::PredefinedFunctionCall(def, args) if def.opcode == FuncOpCode::EachItem => {
+                match self.type_registry.get_collection(self.expression_type(args.first()?)?)? {
+                    (CollectionKind::List, element) => Some(element),
+                    (CollectionKind::Map, _) => Some(VariableType::String),
+                }
+            }
             PPEExpr::PredefinedFunctionCall(def, _) => Some(def.return_type),
             _ => None,
         }
@@ -450,6 +489,10 @@ impl Decompiler {
             PPEExpr::Dim(id, dims) => {
                 IndexerExpression::create_empty_expression(self.get_variable_name(*id), dims.iter().map(|e| self.decompile_expression(e)).collect())
             }
+            // The field names go back to being worked out when the source is compiled again.
+            PPEExpr::PredefinedFunctionCall(f, args) if f.opcode == FuncOpCode::JsonText => {
+                self.decompile_expression(&PPEExpr::PredefinedFunctionCall(FuncOpCode::JsonStr.get_definition(), args[..1].to_vec()))
+            }
             PPEExpr::PredefinedFunctionCall(f, args) => FunctionCallExpression::create_empty_expression(
                 IdentifierExpression::create_empty_expression(unicase::Ascii::new(f.name.to_string())),
                 args.iter()
@@ -499,6 +542,11 @@ impl Decompiler {
                 ProcedureCallStatement::create_empty_statement(self.get_variable_name(*p), args.iter().map(|e| self.decompile_expression(e)).collect())
             }
             PPECommand::MemberCall(expr) => MemberCallStatement::create_empty_statement(self.decompile_expression(expr)),
+            PPECommand::TryBegin(label) => TryMarkerStatement::create_begin_statement(self.get_label_name(*label)),
+            PPECommand::PredefinedCall(p, _) if p.opcode == OpCode::TryEnd => TryMarkerStatement::create_end_statement(),
+            PPECommand::PredefinedCall(p, args) if p.opcode == OpCode::JsonReadFields => {
+                self.decompile_statement(&PPECommand::PredefinedCall(OpCode::JsonRead.get_definition(), args[..2].to_vec()))
+            }
             PPECommand::PredefinedCall(p, args) => PredefinedCallStatement::create_empty_statement(
                 p,
                 args.iter()
@@ -737,8 +785,16 @@ fn generate_variable_declaration(var: &TableEntry, type_token: Spanned<Token>) -
 ///
 /// Panics if .
 pub fn decompile(executable: Executable, raw: bool, lang_version: u16) -> Res<(Ast, Vec<DecompilerIssue>)> {
+    decompile_with_debug_info(executable, raw, lang_version, None)
+}
+
+/// The same, with the original names where the debug information has them.
+pub fn decompile_with_debug_info(executable: Executable, raw: bool, lang_version: u16, debug_info: Option<DebugInfo>) -> Res<(Ast, Vec<DecompilerIssue>)> {
     match Decompiler::new(executable, !raw) {
         Ok(mut d) => {
+            if let Some(debug_info) = debug_info {
+                d.set_debug_info(debug_info);
+            }
             let mut ast = d.decompile()?;
             ast.language_version = lang_version;
 
@@ -858,6 +914,7 @@ impl PPEVisitor<()> for VariableConstantVisitor<'_> {
     fn visit_goto(&mut self, _label: &usize) {}
     fn visit_gosub(&mut self, _label: &usize) {}
     fn visit_on_error(&mut self, _target: &crate::executable::OnErrorTarget) {}
+    fn visit_try_begin(&mut self, _label: &usize) {}
     fn visit_end_func(&mut self) {}
     fn visit_end_proc(&mut self) {}
     fn visit_stop(&mut self) {}
@@ -0,0 +1,108 @@
+use crate::{
+    ast::{BinOp, Expression, ForEachStatement, Statement},
+    semantic::SemanticVisitor,
+};
+
+/// What the `<each item>` function is called in the decompiled tree.
+const EACH_ITEM: &str = "<each item>";
+
+pub fn scan_for_each(visitor: &SemanticVisitor, statements: &mut Vec<Statement>, lang_version: u16) {
+    // FOR EACH Header:
+    // LET LST001 = [COLLECTION]
+    // LET INT001 = 0
+    // :LABEL002
+    // IF (INT001 >= LEN(LST001)) GOTO LABEL001
+    // LET [VAR] = <each item>(LST001, INT001)
+    // LET INT001 = INT001 + 1
+    // ...
+    // GOTO LABEL002
+    // :LABEL001
+    if statements.len() < 8 {
+        return;
+    }
+    let mut i = 0;
+    while i + 7 < statements.len() {
+        if let Some((var_name, collection, loop_label, break_label)) = match_for_each_header(&statements[i..i + 6]) {
+            let end = (i + 6..statements.len() - 1).find(|&j| {
+                matches!(&statements[j], Statement::Goto(goto) if *goto.get_label() == loop_label)
+                    && matches!(&statements[j + 1], Statement::Label(label) if *label.get_label() == break_label)
+            });
+            if let Some(end) = end {
+                let mut for_block: Vec<Statement> = statements.drain(i..end + 2).collect();
+                // pop goto and break label, then the header
+                for_block.truncate(for_block.len() - 2);
+                for_block.drain(0..6);
+                super::optimize_block(visitor, &mut for_block, lang_version);
+                super::handle_break_continue(break_label, loop_label, &mut for_block);
+                statements.insert(i, ForEachStatement::create_empty_statement(var_name, collection, for_block));
+            }
+        }
+        i += 1;
+    }
+}
+
+fn match_for_each_header(header: &[Statement]) -> Option<(unicase::Ascii<String>, Expression, unicase::Ascii<String>, unicase::Ascii<String>)> {
+    let [
+        Statement::Let(copy),
+        Statement::Let(init),
+        Statement::Label(loop_label),
+        Statement::If(check),
+        Statement::Let(item),
+        Statement::Let(step),
+    ] = header
+    else {
+        return None;
+    };
+    let counter = init.get_identifier();
+    let items = copy.get_identifier();
+    if !copy.get_arguments().is_empty() || !copy.get_members().is_empty() {
+        return None;
+    }
+    if init.get_value_expression().to_string() != "0" || !init.get_arguments().is_empty() {
+        return None;
+    }
+
+    let Expression::Binary(condition) = check.get_condition() else {
+        return None;
+    };
+    let Statement::Goto(break_goto) = check.get_statement() else {
+        return None;
+    };
+    if condition.get_op() != BinOp::GreaterEq || !is_identifier(condition.get_left_expression(), counter) {
+        return None;
+    }
+    let Expression::FunctionCall(len) = condition.get_right_expression() else {
+        return None;
+    };
+    if !is_identifier(len.get_expression(), "LEN") || len.get_arguments().len() != 1 || !is_identifier(&len.get_arguments()[0], items) {
+        return None;
+    }
+
+    let Expression::FunctionCall(each_item) = item.get_value_expression() else {
+        return None;
+    };
+    let [collection, index] = each_item.get_arguments().as_slice() else {
+        return None;
+    };
+    if !is_identifier(each_item.get_expression(), EACH_ITEM) || !is_identifier(index, counter) || !is_identifier(collection, items) {
+        return None;
+    }
+    if !item.get_arguments().is_empty() || !item.get_members().is_empty() {
+        return None;
+    }
+
+    if step.get_identifier() != counter || step.get_value_expression().to_string() != format!("{counter} + 1") {
+        return None;
+    }
+
+    Some((
+        item.get_identifier().clone(),
+        copy.get_value_expression().clone(),
+        loop_label.get_label().clone(),
+        break_goto.get_label().clone(),
+    ))
+}
+
+fn is_identifier(expr: &Expression, name: &str) -> bool {
+    matches!(expr, Expression::Identifier(identifier) if identifier.get_identifier().eq_ignore_ascii_case(name))
+}
@@ -1,3 +1,4 @@
+use for_each::scan_for_each;
 use for_next::scan_for_next;
 use select_case::scan_select_statements;
 use unicase::Ascii;
@@ -5,6 +6,7 @@ use unicase::Ascii;
 use crate::{
     ast::{AstVisitorMut, BreakStatement, ContinueStatement, IfStatement, IfThenStatement, RenameVisitor},
     executable::OpCode,
+    parser::{FIRST_COLLECTION_LANGUAGE_VERSION, FIRST_TRY_LANGUAGE_VERSION},
     semantic::{ReferenceType, SemanticVisitor},
 };
 
@@ -12,12 +14,14 @@ use self::while_do::scan_do_while;
 
 use super::{Ast, Expression, Statement, rename_visitor::RenameScanVisitor};
 
+mod for_each;
 pub mod for_next;
 mod if_else;
 mod loop_endloop;
 mod remove_label_visitor;
 mod repeat_until;
 mod select_case;
+mod try_catch;
 mod unused_label_visitor;
 mod while_do;
 
@@ -32,6 +36,9 @@ fn _optimize_argument(arg: &mut Expression) {
 }
 
 pub fn optimize_loops(visitor: &SemanticVisitor, statements: &mut Vec<Statement>, lang_version: u16) {
+    if lang_version >= FIRST_COLLECTION_LANGUAGE_VERSION {
+        scan_for_each(visitor, statements, lang_version);
+    }
     scan_for_next(visitor, statements, lang_version);
     scan_do_while(visitor, statements, lang_version);
     if lang_version >= 350 {
@@ -42,6 +49,9 @@ pub fn optimize_loops(visitor: &SemanticVisitor, statements: &mut Vec<Statement>
 
 fn optimize_block(visitor: &SemanticVisitor, statements: &mut Vec<Statement>, lang_version: u16) {
     optimize_loops(visitor, statements, lang_version);
+    if lang_version >= FIRST_TRY_LANGUAGE_VERSION {
+        try_catch::scan_try_catch(visitor, statements, lang_version);
+    }
     optimize_ifs(visitor, statements, lang_version);
     if lang_version >= 200 {
         scan_select_statements(statements);
@@ -0,0 +1,151 @@
+use crate::{
+    ast::{Expression, Statement, TryStatement},
+    semantic::SemanticVisitor,
+};
+
+use super::{optimize_block, scan_label};
+
+/* Compiled Example:
+
+    <try> LABEL001
+    PRINT "Hello World!"
+    <end try>
+    GOTO LABEL002
+:LABEL001
+    LET E = ERR()
+    PRINTLN E.Code
+:LABEL002
+
+Was:
+TRY
+  PRINT "Hello World!"
+CATCH E
+  PRINTLN E.Code
+ENDTRY
+
+The GOTO is gone when nothing was caught into an empty CATCH. A BREAK, CONTINUE or GOTO
+leaving the block closes it with an `<end try>` of its own right before the jump.
+*/
+pub fn scan_try_catch(visitor: &SemanticVisitor, statements: &mut Vec<Statement>, lang_version: u16) {
+    let mut i = 0;
+    while i < statements.len() {
+        let Some(catch_label) = begin_label(&statements[i]) else {
+            i += 1;
+            continue;
+        };
+        let Some(catch_start) = scan_label(statements, i + 1, &catch_label) else {
+            i += 1;
+            continue;
+        };
+        let Some((close, end_label)) = match_close(statements, i, catch_start) else {
+            i += 1;
+            continue;
+        };
+        let catch_end = match &end_label {
+            Some(end_label) => scan_label(statements, catch_start + 1, end_label),
+            None => Some(catch_start + 1),
+        };
+        let Some(catch_end) = catch_end else {
+            i += 1;
+            continue;
+        };
+
+        let mut catch_statements: Vec<Statement> = statements.drain(catch_start + 1..catch_end).collect();
+        statements.drain(close..=catch_start);
+        let mut body: Vec<Statement> = statements.drain(i + 1..close).collect();
+        statements.remove(i);
+
+        remove_leave_markers(&mut body);
+        optimize_block(visitor, &mut body, lang_version);
+
+        let catch_identifier = take_catch_identifier(&mut catch_statements);
+        optimize_block(visitor, &mut catch_statements, lang_version);
+
+        statements.insert(i, TryStatement::create_empty_statement(body, catch_identifier, catch_statements));
+        i += 1;
+    }
+}
+
+fn begin_label(statement: &Statement) -> Option<unicase::Ascii<String>> {
+    if let Statement::TryMarker(marker) = statement {
+        return marker.get_catch_label().cloned();
+    }
+    None
+}
+
+fn is_end_marker(statement: &Statement) -> bool {
+    matches!(statement, Statement::TryMarker(marker) if marker.get_catch_label().is_none())
+}
+
+/// The `<end try>` the block falls through to, right before the catch label or with
+/// the jump over the CATCH part in between. Returns it with the label after the CATCH part.
+fn match_close(statements: &[Statement], begin: usize, catch_start: usize) -> Option<(usize, Option<unicase::Ascii<String>>)> {
+    if catch_start > begin + 1 && is_end_marker(&statements[catch_start - 1]) {
+        return Some((catch_start - 1, None));
+    }
+    if catch_start > begin + 2
+        && is_end_marker(&statements[catch_start - 2])
+        && let Statement::Goto(goto) = &statements[catch_start - 1]
+    {
+        return Some((catch_start - 2, Some(goto.get_label().clone())));
+    }
+    None
+}
+
+/// A jump out of the block closes the TRY blocks it leaves innermost first. Loops are
+/// rebuilt before this runs, so a run of `<end try>` one longer than the blocks still
+/// open inside it is a jump that leaves the block being rebuilt, and its marker is the last.
+fn remove_leave_markers(body: &mut Vec<Statement>) {
+    let mut open_catch_labels: Vec<unicase::Ascii<String>> = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        if let Some(label) = begin_label(&body[i]) {
+            open_catch_labels.push(label);
+            i += 1;
+            continue;
+        }
+        if let Statement::Label(label) = &body[i]
+            && open_catch_labels.last() == Some(label.get_label())
+        {
+            open_catch_labels.pop();
+            i += 1;
+            continue;
+        }
+        if !is_end_marker(&body[i]) || closes_inner_block(body, i, open_catch_labels.last()) {
+            i += 1;
+            continue;
+        }
+        let depth = open_catch_labels.len();
+        if i + depth < body.len() && (i..=i + depth).all(|j| is_end_marker(&body[j])) {
+            body.remove(i + depth);
+        }
+        i += depth + 1;
+    }
+}
+
+fn closes_inner_block(body: &[Statement], i: usize, catch_label: Option<&unicase::Ascii<String>>) -> bool {
+    let Some(catch_label) = catch_label else {
+        return false;
+    };
+    let is_catch_label = |statement: Option<&Statement>| matches!(statement, Some(Statement::Label(label)) if label.get_label() == catch_label);
+    is_catch_label(body.get(i + 1)) || (matches!(body.get(i + 1), Some(Statement::Goto(_))) && is_catch_label(body.get(i + 2)))
+}
+
+/// `LET E = ERR()` at the start of the CATCH part is what names the variable.
+fn take_catch_identifier(catch_statements: &mut Vec<Statement>) -> Option<unicase::Ascii<String>> {
+    let Some(Statement::Let(let_stmt)) = catch_statements.first() else {
+        return None;
+    };
+    if !let_stmt.get_arguments().is_empty() || !let_stmt.get_members().is_empty() {
+        return None;
+    }
+    let Expression::FunctionCall(call) = let_stmt.get_value_expression() else {
+        return None;
+    };
+    if !call.get_arguments().is_empty() || !matches!(call.get_expression(), Expression::Identifier(id) if id.get_identifier().eq_ignore_ascii_case("ERR")) {
+        return None;
+    }
+    let identifier = let_stmt.get_identifier().clone();
+    catch_statements.remove(0);
+    Some(identifier)
+}
@@ -28,4 +28,10 @@ impl AstVisitor<()> for UnusedLabelVisitor {
     fn visit_gosub_statement(&mut self, gosub: &crate::ast::GosubStatement) {
         self.used_labels.insert(unicase::Ascii::new(gosub.get_label().to_string()));
     }
+
+    fn visit_try_marker_statement(&mut self, marker: &crate::ast::TryMarkerStatement) {
+        if let Some(label) = marker.get_catch_label() {
+            self.used_labels.insert(label.clone());
+        }
+    }
 }
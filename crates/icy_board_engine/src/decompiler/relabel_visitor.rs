@@ -1,4 +1,4 @@
-use crate::ast::{Ast, AstVisitor, AstVisitorMut, GosubStatement, GotoStatement, LabelStatement};
+use crate::ast::{Ast, AstVisitor, AstVisitorMut, GosubStatement, GotoStatement, LabelStatement, Statement, TryMarkerStatement};
 use unicase::Ascii;
 
 struct GatherLabelVisitor {
@@ -39,6 +39,14 @@ impl AstVisitorMut for TransformLabelVisitor {
         GosubStatement::create_empty_statement(label)
     }
 
+    fn visit_try_marker_statement(&mut self, marker: &TryMarkerStatement) -> Statement {
+        let mut marker = marker.clone();
+        if let Some(label) = marker.get_catch_label() {
+            marker.set_catch_label(self.get_label(label));
+        }
+        Statement::TryMarker(marker)
+    }
+
     fn visit_label_statement(&mut self, label_stmt: &crate::ast::LabelStatement) -> crate::ast::Statement {
         let label = self.get_label(label_stmt.get_label());
         LabelStatement::create_empty_statement(label)
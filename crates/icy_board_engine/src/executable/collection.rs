@@ -0,0 +1,291 @@
+//! The values behind `LIST OF` and `MAP OF STRING TO`.
+//!
+//! The VM copies a value every time it reads one, so the items sit behind an `Arc`
+//! and are only copied once a collection that is shared gets written to.
+
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+
+use super::{VariableType, VariableValue};
+
+/// Which kind of collection a type stands for. The number is what the type table stores.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CollectionKind {
+    List = 1,
+    Map = 2,
+}
+
+impl CollectionKind {
+    pub fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            1 => Some(CollectionKind::List),
+            2 => Some(CollectionKind::Map),
+            _ => None,
+        }
+    }
+
+    /// The type name a collection of `element_name` is written as.
+    pub fn type_name(self, element_name: &str) -> String {
+        match self {
+            CollectionKind::List => format!("LIST OF {element_name}"),
+            CollectionKind::Map => format!("MAP OF STRING TO {element_name}"),
+        }
+    }
+}
+
+/// The members every collection has. The discriminant is the member id in the PPE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionMember {
+    Add = 0,
+    Insert = 1,
+    Remove = 2,
+    Contains = 3,
+    Clear = 4,
+}
+
+impl CollectionMember {
+    pub const ALL: [CollectionMember; 5] = [
+        CollectionMember::Add,
+        CollectionMember::Insert,
+        CollectionMember::Remove,
+        CollectionMember::Contains,
+        CollectionMember::Clear,
+    ];
+
+    pub fn from_id(id: usize) -> Option<Self> {
+        Self::ALL.get(id).copied()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|member| member.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CollectionMember::Add => "Add",
+            CollectionMember::Insert => "Insert",
+            CollectionMember::Remove => "Remove",
+            CollectionMember::Contains => "Contains",
+            CollectionMember::Clear => "Clear",
+        }
+    }
+
+    /// How many arguments the member takes, or `None` when that kind has no such member.
+    /// A map is only ever filled under a key, so it has no `Add`.
+    pub fn parameter_count(self, kind: CollectionKind) -> Option<usize> {
+        match (self, kind) {
+            (CollectionMember::Add, CollectionKind::List) => Some(1),
+            (CollectionMember::Add, CollectionKind::Map) => None,
+            (CollectionMember::Insert, _) => Some(2),
+            (CollectionMember::Remove | CollectionMember::Contains, _) => Some(1),
+            (CollectionMember::Clear, _) => Some(0),
+        }
+    }
+
+    pub fn return_type(self) -> VariableType {
+        match self {
+            CollectionMember::Contains => VariableType::Boolean,
+            _ => VariableType::None,
+        }
+    }
+
+    /// The members that leave the collection as it was.
+    pub fn is_read_only(self) -> bool {
+        self == CollectionMember::Contains
+    }
+
+    /// The signature shown for the member, `element` being the item type name.
+    pub fn signature(self, kind: CollectionKind, element: &str) -> String {
+        let index = match kind {
+            CollectionKind::List => "INTEGER index",
+            CollectionKind::Map => "STRING key",
+        };
+        match self {
+            CollectionMember::Add => format!("Add({element} item)"),
+            CollectionMember::Insert => format!("Insert({index}, {element} item)"),
+            CollectionMember::Remove => format!("Remove({index})"),
+            CollectionMember::Contains if kind == CollectionKind::List => format!("Contains({element} item) BOOLEAN"),
+            CollectionMember::Contains => format!("Contains({index}) BOOLEAN"),
+            CollectionMember::Clear => "Clear()".to_string(),
+        }
+    }
+}
+
+/// A `LIST OF` value. Items are numbered from 0, like the elements of an array.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PPLList {
+    /// What a new item starts out as. Every item is converted to its type.
+    pub element: Box<VariableValue>,
+    pub items: Arc<Vec<VariableValue>>,
+}
+
+impl PPLList {
+    pub fn new(element: VariableValue) -> Self {
+        Self {
+            element: Box::new(element),
+            items: Arc::new(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn get(&self, index: i32) -> Option<&VariableValue> {
+        usize::try_from(index).ok().and_then(|index| self.items.get(index))
+    }
+
+    pub fn get_mut(&mut self, index: i32) -> Option<&mut VariableValue> {
+        let index = usize::try_from(index).ok().filter(|index| *index < self.items.len())?;
+        Arc::make_mut(&mut self.items).get_mut(index)
+    }
+
+    pub fn add(&mut self, value: VariableValue) {
+        let value = value.convert_to(self.element.vtype);
+        Arc::make_mut(&mut self.items).push(value);
+    }
+
+    /// Puts `value` in front of item `index`; the length itself appends. False when
+    /// `index` is past that.
+    pub fn insert(&mut self, index: i32, value: VariableValue) -> bool {
+        let Some(index) = usize::try_from(index).ok().filter(|index| *index <= self.items.len()) else {
+            return false;
+        };
+        let value = value.convert_to(self.element.vtype);
+        Arc::make_mut(&mut self.items).insert(index, value);
+        true
+    }
+
+    pub fn remove(&mut self, index: i32) -> bool {
+        let Some(index) = usize::try_from(index).ok().filter(|index| *index < self.items.len()) else {
+            return false;
+        };
+        Arc::make_mut(&mut self.items).remove(index);
+        true
+    }
+
+    pub fn contains(&self, value: &VariableValue) -> bool {
+        let value = value.clone().convert_to(self.element.vtype);
+        self.items.iter().any(|item| *item == value)
+    }
+
+    pub fn clear(&mut self) {
+        self.items = Arc::new(Vec::new());
+    }
+}
+
+/// A `MAP OF STRING TO` value. Entries keep the order their keys were first set in,
+/// so walking a map gives the same order on every run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PPLMap {
+    /// What a missing entry reads as and a new one starts out as.
+    pub element: Box<VariableValue>,
+    pub entries: Arc<IndexMap<String, VariableValue>>,
+}
+
+impl PPLMap {
+    pub fn new(element: VariableValue) -> Self {
+        Self {
+            element: Box::new(element),
+            entries: Arc::new(IndexMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&VariableValue> {
+        self.entries.get(key)
+    }
+
+    /// The entry under `key`, made first if the map has none.
+    pub fn entry(&mut self, key: &str) -> &mut VariableValue {
+        if !self.entries.contains_key(key) {
+            let element = (*self.element).clone();
+            Arc::make_mut(&mut self.entries).insert(key.to_string(), element);
+        }
+        Arc::make_mut(&mut self.entries).get_mut(key).unwrap()
+    }
+
+    pub fn insert(&mut self, key: &str, value: VariableValue) {
+        let value = value.convert_to(self.element.vtype);
+        *self.entry(key) = value;
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        if !self.entries.contains_key(key) {
+            return false;
+        }
+        Arc::make_mut(&mut self.entries).shift_remove(key);
+        true
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn key_at(&self, index: i32) -> Option<&str> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| self.entries.get_index(index))
+            .map(|(key, _)| key.as_str())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries = Arc::new(IndexMap::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_copy_keeps_its_items_when_the_original_changes() {
+        let mut list = PPLList::new(VariableValue::new_int(0));
+        list.add(VariableValue::new_int(1));
+        let copy = list.clone();
+        list.add(VariableValue::new_int(2));
+        assert_eq!(1, copy.len());
+        assert_eq!(2, list.len());
+    }
+
+    #[test]
+    fn list_items_take_the_element_type() {
+        let mut list = PPLList::new(VariableValue::new_int(0));
+        list.add(VariableValue::new_string("42".to_string()));
+        assert_eq!(VariableType::Integer, list.get(0).unwrap().vtype);
+        assert!(list.contains(&VariableValue::new_int(42)));
+        assert!(!list.insert(3, VariableValue::new_int(1)));
+        assert!(list.insert(1, VariableValue::new_int(7)));
+        assert_eq!(7, list.get(1).unwrap().as_int());
+        assert!(list.remove(0));
+        assert!(!list.remove(-1));
+    }
+
+    #[test]
+    fn map_entries_keep_their_first_order() {
+        let mut map = PPLMap::new(VariableValue::new_int(0));
+        map.insert("b", VariableValue::new_int(1));
+        map.insert("a", VariableValue::new_int(2));
+        map.insert("b", VariableValue::new_int(3));
+        assert_eq!(Some("b"), map.key_at(0));
+        assert_eq!(Some("a"), map.key_at(1));
+        assert_eq!(3, map.get("b").unwrap().as_int());
+        assert!(map.remove("b"));
+        assert!(!map.contains("b"));
+        assert_eq!(0, map.entry("c").as_int());
+        assert_eq!(2, map.len());
+    }
+}
@@ -128,6 +128,8 @@ pub enum PPECommand {
     Let(Box<PPEExpr>, Box<PPEExpr>),
     MemberCall(Box<PPEExpr>),
     OnError(OnErrorTarget),
+    /// Arms a `TRY`, the label is where its CATCH part starts.
+    TryBegin(usize),
 }
 
 /// Where `ON ERROR` sends the program. The label is patched like a `GOTO` label is,
@@ -198,6 +200,10 @@ impl PPECommand {
                 vec.push(OpCode::GOSUB as i16);
                 vec.push(*pos as i16);
             }
+            PPECommand::TryBegin(pos) => {
+                vec.push(OpCode::TryBegin as i16);
+                vec.push(*pos as i16);
+            }
             PPECommand::OnError(target) => {
                 let (mode, value) = target.encode();
                 vec.push(OpCode::OnError as i16);
@@ -311,7 +317,7 @@ impl PPECommand {
         match self {
             PPECommand::End | PPECommand::Return | PPECommand::EndFunc | PPECommand::EndProc | PPECommand::Stop => 1,
 
-            PPECommand::Goto(_) | PPECommand::Gosub(_) => 2,
+            PPECommand::Goto(_) | PPECommand::Gosub(_) | PPECommand::TryBegin(_) => 2,
             PPECommand::OnError(_) => 3,
             PPECommand::IfNot(expr, _) => 1 + expr.get_size() + 2,
             PPECommand::ProcedureCall(_, args) => 3 + PPEExpr::count_size(args) + args.len(),
@@ -345,6 +351,7 @@ impl PPECommand {
             PPECommand::Goto(label) => visitor.visit_goto(label),
             PPECommand::Gosub(label) => visitor.visit_gosub(label),
             PPECommand::OnError(target) => visitor.visit_on_error(target),
+            PPECommand::TryBegin(label) => visitor.visit_try_begin(label),
             PPECommand::EndFunc => visitor.visit_end_func(),
             PPECommand::EndProc => visitor.visit_end_proc(),
             PPECommand::Stop => visitor.visit_stop(),
@@ -573,6 +580,7 @@ pub trait PPEVisitor<T>: Sized {
     fn visit_goto(&mut self, label: &usize) -> T;
     fn visit_gosub(&mut self, label: &usize) -> T;
     fn visit_on_error(&mut self, target: &OnErrorTarget) -> T;
+    fn visit_try_begin(&mut self, label: &usize) -> T;
     fn visit_end_func(&mut self) -> T;
     fn visit_end_proc(&mut self) -> T;
     fn visit_stop(&mut self) -> T;
@@ -769,6 +777,10 @@ impl PPEVisitor<Result<VariableValue, PPEError>> for PPEConstantValueVisitor<'_>
         todo!()
     }
 
+    fn visit_try_begin(&mut self, _label: &usize) -> Result<VariableValue, PPEError> {
+        todo!()
+    }
+
     fn visit_end_func(&mut self) -> Result<VariableValue, PPEError> {
         todo!()
     }
@@ -0,0 +1,242 @@
+//! What a PPE forgets about its source: the file and line every statement came
+//! from, and the names of the variables and routines. `pplc --debug` writes it
+//! next to the PPE, the VM uses it for its error messages and `ppld` for names.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Res;
+
+use super::Executable;
+
+pub const DEBUG_INFO_EXTENSION: &str = "dbg";
+
+const DEBUG_INFO_VERSION: u16 = 1;
+
+/// A statement that starts at `offset` (in bytes, like the disassembler shows
+/// it) and came from line `line` of `files[file]`. Lines count from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LineMapping(pub usize, pub usize, pub usize);
+
+impl LineMapping {
+    pub fn offset(&self) -> usize {
+        self.0
+    }
+
+    pub fn file(&self) -> usize {
+        self.1
+    }
+
+    pub fn line(&self) -> usize {
+        self.2
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VariableName {
+    pub id: usize,
+    pub name: String,
+}
+
+/// A record type the program declared. The PPE keeps only the field types.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeName {
+    pub id: u8,
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DebugInfo {
+    pub version: u16,
+    /// Of the script buffer, so information for an older build of the PPE is not believed.
+    pub code_hash: String,
+    pub files: Vec<PathBuf>,
+    /// Sorted by offset.
+    #[serde(default)]
+    pub lines: Vec<LineMapping>,
+    #[serde(default)]
+    pub variables: Vec<VariableName>,
+    #[serde(default)]
+    pub types: Vec<TypeName>,
+}
+
+impl DebugInfo {
+    pub fn new(executable: &Executable) -> Self {
+        Self {
+            version: DEBUG_INFO_VERSION,
+            code_hash: code_hash(&executable.script_buffer),
+            ..Default::default()
+        }
+    }
+
+    /// `GAME.PPE` keeps its debug information in `GAME.DBG`.
+    pub fn path_for(ppe_file: &Path) -> PathBuf {
+        let upper = ppe_file
+            .extension()
+            .is_some_and(|ext| ext.to_string_lossy().chars().all(|ch| ch.is_ascii_uppercase()));
+        ppe_file.with_extension(if upper {
+            DEBUG_INFO_EXTENSION.to_ascii_uppercase()
+        } else {
+            DEBUG_INFO_EXTENSION.to_string()
+        })
+    }
+
+    /// The debug information of a PPE, if there is some and it was written for this very code.
+    pub fn load_for(ppe_file: &Path, executable: &Executable) -> Option<Self> {
+        let path = Self::path_for(ppe_file);
+        if !path.exists() {
+            return None;
+        }
+        match Self::load(&path) {
+            Ok(info) if info.matches(executable) => Some(info),
+            Ok(_) => {
+                log::warn!("Ignoring {}, it was written for another build of {}", path.display(), ppe_file.display());
+                None
+            }
+            Err(err) => {
+                log::warn!("Can't read debug information {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> Res<Self> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Res<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn matches(&self, executable: &Executable) -> bool {
+        self.version == DEBUG_INFO_VERSION && self.code_hash == code_hash(&executable.script_buffer)
+    }
+
+    /// Notes where the statement at `offset` came from. Generated statements have
+    /// no place of their own and belong to the line before them, so a mapping is
+    /// only added when the line changes.
+    pub fn add_line(&mut self, offset: usize, file: usize, line: usize) {
+        if let Some(last) = self.lines.last() {
+            if last.file() == file && last.line() == line {
+                return;
+            }
+            if last.offset() == offset {
+                self.lines.pop();
+            }
+        }
+        self.lines.push(LineMapping(offset, file, line));
+    }
+
+    /// The file and line of the statement at byte `offset`.
+    pub fn location(&self, offset: usize) -> Option<(&Path, usize)> {
+        let idx = self.lines.partition_point(|mapping| mapping.offset() <= offset).checked_sub(1)?;
+        let mapping = &self.lines[idx];
+        Some((self.files.get(mapping.file())?.as_path(), mapping.line()))
+    }
+
+    /// `file:line` of the statement at byte `offset`, or the offset itself when that is all there is.
+    pub fn describe(&self, offset: usize) -> String {
+        match self.location(offset) {
+            Some((file, line)) => format!("{}:{}", file.display(), line),
+            None => format!("offset {offset:05X}"),
+        }
+    }
+
+    /// Whether a line starts with the statement at `offset`, which is where stepping stops.
+    pub fn is_line_start(&self, offset: usize) -> bool {
+        self.lines.binary_search_by_key(&offset, LineMapping::offset).is_ok()
+    }
+
+    /// Finds a source file the way an editor names it: absolute, while the
+    /// compiler may have seen it relative.
+    pub fn file_index(&self, path: &Path) -> Option<usize> {
+        if let Some(idx) = self.files.iter().position(|file| file == path) {
+            return Some(idx);
+        }
+        let canonical = fs::canonicalize(path).ok();
+        self.files
+            .iter()
+            .position(|file| canonical.is_some() && fs::canonicalize(file).ok() == canonical)
+            .or_else(|| self.files.iter().position(|file| file.is_relative() && path.ends_with(file)))
+    }
+
+    pub fn name_of(&self, id: usize) -> Option<&str> {
+        self.variables.iter().find(|var| var.id == id).map(|var| var.name.as_str())
+    }
+
+    pub fn type_name(&self, id: u8) -> Option<&TypeName> {
+        self.types.iter().find(|t| t.id == id)
+    }
+}
+
+pub fn code_hash(script_buffer: &[i16]) -> String {
+    let bytes: Vec<u8> = script_buffer.iter().flat_map(|word| word.to_le_bytes()).collect();
+    format!("{:x}", md5::compute(bytes))
+}
+
+/// Turns a byte position into a line number, counting from 1.
+pub fn line_of(source: &str, position: usize) -> usize {
+    let end = position.min(source.len());
+    source.as_bytes()[..end].iter().filter(|&&b| b == b'\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> DebugInfo {
+        let mut info = DebugInfo {
+            version: DEBUG_INFO_VERSION,
+            code_hash: code_hash(&[1, 2, 3]),
+            files: vec![PathBuf::from("main.pps"), PathBuf::from("lib.pps")],
+            ..Default::default()
+        };
+        info.add_line(0, 0, 3);
+        info.add_line(4, 0, 3);
+        info.add_line(10, 0, 5);
+        info.add_line(20, 1, 2);
+        info.variables.push(VariableName {
+            id: 7,
+            name: "UserName".to_string(),
+        });
+        info
+    }
+
+    #[test]
+    fn test_an_offset_belongs_to_the_last_line_before_it() {
+        let info = info();
+        assert_eq!(info.lines.len(), 3);
+        assert_eq!(info.location(6), Some((Path::new("main.pps"), 3)));
+        assert_eq!(info.location(10), Some((Path::new("main.pps"), 5)));
+        assert_eq!(info.describe(100), "lib.pps:2");
+        assert_eq!(info.name_of(7), Some("UserName"));
+        assert_eq!(info.name_of(8), None);
+    }
+
+    #[test]
+    fn test_it_survives_a_round_trip() {
+        let info = info();
+        let text = toml::to_string(&info).unwrap();
+        assert_eq!(toml::from_str::<DebugInfo>(&text).unwrap(), info);
+    }
+
+    #[test]
+    fn test_lines_are_counted_from_one() {
+        let source = "; comment\nPRINTLN 1\n\nPRINTLN 2";
+        assert_eq!(line_of(source, 0), 1);
+        assert_eq!(line_of(source, source.find("PRINTLN 2").unwrap()), 4);
+        assert_eq!(line_of(source, 1000), 4);
+    }
+
+    #[test]
+    fn test_the_file_sits_next_to_the_ppe() {
+        assert_eq!(DebugInfo::path_for(Path::new("/bbs/GAME.PPE")), PathBuf::from("/bbs/GAME.DBG"));
+        assert_eq!(DebugInfo::path_for(Path::new("game.ppe")), PathBuf::from("game.dbg"));
+    }
+}
@@ -154,6 +154,11 @@ impl PPEDeserializer {
                 self.offset += 1;
                 Ok(Some(PPECommand::Goto(label)))
             }
+            OpCode::TryBegin => {
+                let label = executable.script_buffer[self.offset] as usize;
+                self.offset += 1;
+                Ok(Some(PPECommand::TryBegin(label)))
+            }
             OpCode::OnError => {
                 let mode = executable.script_buffer[self.offset];
                 let target = executable.script_buffer[self.offset + 1] as usize;
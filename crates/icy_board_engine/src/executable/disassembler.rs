@@ -341,6 +341,16 @@ impl PPEVisitor<()> for DisassembleVisitor<'_> {
         );
     }
 
+    fn visit_try_begin(&mut self, label: &usize) {
+        Self::output_op_code(OpCode::TryBegin);
+        let _ = execute!(
+            stdout(),
+            SetForegroundColor(Color::Cyan),
+            Print(format!(" {{{label:04X}}}")),
+            SetAttribute(Attribute::Reset),
+        );
+    }
+
     fn visit_on_error(&mut self, target: &crate::executable::OnErrorTarget) {
         Self::output_op_code(OpCode::OnError);
         let (mode, value) = target.encode();
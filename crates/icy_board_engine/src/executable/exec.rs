@@ -11,7 +11,7 @@ use crate::{
     parser::{FIRST_USER_TYPE_ID, MAX_TYPE_FIELDS, MAX_USER_TYPES, is_user_declared_type},
 };
 
-use super::{FIRST_TYPE_TABLE_RUNTIME, LAST_PPE_RUNTIME, VariableTable, VariableType};
+use super::{CollectionKind, FIRST_COLLECTION_RUNTIME, FIRST_TYPE_TABLE_RUNTIME, LAST_PPE_RUNTIME, VariableTable, VariableType};
 
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum ExecutableError {
@@ -65,15 +65,33 @@ pub enum ExecutableError {
 
     #[error("Variable refers to type {0}, which is not in the type table")]
     MissingTypeDefinition(u8),
+
+    #[error("Type {0} is a collection, which needs runtime {1}")]
+    CollectionsNotSupported(usize, u16),
+
+    #[error("Type {0} is a collection of unknown kind {1}")]
+    InvalidCollectionKind(usize, u8),
+
+    #[error("Type {0} is a collection of {1}, which cannot be an item")]
+    InvalidCollectionElement(usize, VariableType),
+}
+
+/// What a type in the type table is made of.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeLayout {
+    /// The field types of a record declared with TYPE/ENDTYPE.
+    Record(Vec<VariableType>),
+    /// A `LIST OF` or `MAP OF STRING TO` the given item type.
+    Collection(CollectionKind, VariableType),
 }
 
 #[derive(Clone)]
 pub struct Executable {
     pub runtime: u16,
     pub variable_table: VariableTable,
-    /// Field types of the records the program declared, indexed by type id minus
-    /// `FIRST_USER_TYPE_ID`. Only written for runtime 401 and above.
-    pub user_types: Vec<Vec<VariableType>>,
+    /// The types the program declared, indexed by type id minus `FIRST_USER_TYPE_ID`.
+    /// Only written for runtime 401 and above, collections from 403 on.
+    pub user_types: Vec<TypeLayout>,
     pub script_buffer: Vec<i16>,
 }
 
@@ -82,15 +100,26 @@ static PREAMBLE: &[u8] = b"PCBoard Programming Language Executable";
 const HEADER_SIZE: usize = 48;
 
 impl Executable {
-    fn validate_user_types(user_types: &[Vec<VariableType>]) -> Result<(), ExecutableError> {
+    fn validate_user_types(user_types: &[TypeLayout]) -> Result<(), ExecutableError> {
         if user_types.len() > MAX_USER_TYPES {
             return Err(ExecutableError::TypeCountExceedsMaximum(user_types.len(), MAX_USER_TYPES));
         }
-        for (index, fields) in user_types.iter().enumerate() {
+        for (index, layout) in user_types.iter().enumerate() {
             let type_id = FIRST_USER_TYPE_ID + index;
-            if fields.is_empty() || fields.len() > MAX_TYPE_FIELDS {
-                return Err(ExecutableError::InvalidTypeFieldCount(type_id, fields.len()));
-            }
+            let fields = match layout {
+                TypeLayout::Record(fields) => {
+                    if fields.is_empty() || fields.len() > MAX_TYPE_FIELDS {
+                        return Err(ExecutableError::InvalidTypeFieldCount(type_id, fields.len()));
+                    }
+                    fields.as_slice()
+                }
+                TypeLayout::Collection(_, element) => {
+                    if matches!(element, VariableType::None | VariableType::Function | VariableType::Procedure) {
+                        return Err(ExecutableError::InvalidCollectionElement(type_id, *element));
+                    }
+                    std::slice::from_ref(element)
+                }
+            };
             for field in fields {
                 if let VariableType::UserData(field_type_id) = field {
                     if !is_user_declared_type(*field_type_id) {
@@ -105,7 +134,7 @@ impl Executable {
         Ok(())
     }
 
-    fn validate_variable_types(variable_table: &VariableTable, user_types: &[Vec<VariableType>]) -> Result<(), ExecutableError> {
+    fn validate_variable_types(variable_table: &VariableTable, user_types: &[TypeLayout]) -> Result<(), ExecutableError> {
         for entry in variable_table.get_entries() {
             if let VariableType::UserData(type_id) = entry.header.variable_type
                 && is_user_declared_type(type_id)
@@ -165,8 +194,21 @@ impl Executable {
                 };
                 let field_count = field_count as usize;
                 i += 1;
+                let type_id = FIRST_USER_TYPE_ID + user_types.len();
                 if field_count == 0 {
-                    return Err(Box::new(ExecutableError::InvalidTypeFieldCount(FIRST_USER_TYPE_ID + user_types.len(), 0)));
+                    // A record has at least one field, so from 403 on a zero count introduces a collection.
+                    if version < FIRST_COLLECTION_RUNTIME {
+                        return Err(Box::new(ExecutableError::InvalidTypeFieldCount(type_id, 0)));
+                    }
+                    let Some(&[kind, element]) = buffer.get(i..i + 2) else {
+                        return Err(Box::new(ExecutableError::BufferTooShort(buffer.len())));
+                    };
+                    let Some(kind) = CollectionKind::from_byte(kind) else {
+                        return Err(Box::new(ExecutableError::InvalidCollectionKind(type_id, kind)));
+                    };
+                    i += 2;
+                    user_types.push(TypeLayout::Collection(kind, VariableType::from(element)));
+                    continue;
                 }
                 let Some(field_bytes) = buffer.get(i..i + field_count) else {
                     return Err(Box::new(ExecutableError::BufferTooShort(buffer.len())));
@@ -176,7 +218,7 @@ impl Executable {
                     fields.push(VariableType::from(*field));
                 }
                 i += field_count;
-                user_types.push(fields);
+                user_types.push(TypeLayout::Record(fields));
             }
             Self::validate_user_types(&user_types)?;
             Self::validate_variable_types(&variable_table, &user_types)?;
@@ -260,6 +302,11 @@ impl Executable {
         if !self.user_types.is_empty() && self.runtime < FIRST_TYPE_TABLE_RUNTIME {
             return Err(ExecutableError::CustomTypesNotSupported(FIRST_TYPE_TABLE_RUNTIME));
         }
+        if self.runtime < FIRST_COLLECTION_RUNTIME
+            && let Some(index) = self.user_types.iter().position(|layout| matches!(layout, TypeLayout::Collection(..)))
+        {
+            return Err(ExecutableError::CollectionsNotSupported(FIRST_USER_TYPE_ID + index, FIRST_COLLECTION_RUNTIME));
+        }
         Self::validate_user_types(&self.user_types)?;
         Self::validate_variable_types(&self.variable_table, &self.user_types)?;
         let mut buffer = Vec::new();
@@ -278,10 +325,19 @@ impl Executable {
         if self.runtime >= FIRST_TYPE_TABLE_RUNTIME {
             // Ids run 100..=255, and a record is capped at 255 fields, so a byte holds both counts.
             buffer.push(self.user_types.len() as u8);
-            for fields in &self.user_types {
-                buffer.push(fields.len() as u8);
-                for field in fields {
-                    buffer.push((*field).into());
+            for layout in &self.user_types {
+                match layout {
+                    TypeLayout::Record(fields) => {
+                        buffer.push(fields.len() as u8);
+                        for field in fields {
+                            buffer.push((*field).into());
+                        }
+                    }
+                    TypeLayout::Collection(kind, element) => {
+                        buffer.push(0);
+                        buffer.push(*kind as u8);
+                        buffer.push((*element).into());
+                    }
                 }
             }
         }
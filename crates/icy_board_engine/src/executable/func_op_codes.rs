@@ -329,9 +329,13 @@ pub enum FuncOpCode {
     TermState = -315,
     Err = -316,
     TermInfo = -317,
+    EachItem = -318,
+    JsonParse = -319,
+    JsonStr = -320,
+    JsonText = -321,
 }
 
-pub const LAST_FUNC: i16 = -317;
+pub const LAST_FUNC: i16 = -321;
 
 impl FuncOpCode {
     pub fn get_definition(self) -> &'static FunctionDefinition {
@@ -340,7 +344,13 @@ impl FuncOpCode {
     }
 
     pub fn minimum_runtime(self) -> u16 {
-        if (self as i16) <= FuncOpCode::GfxBackend as i16 { 402 } else { 100 }
+        if (self as i16) <= FuncOpCode::EachItem as i16 {
+            403
+        } else if (self as i16) <= FuncOpCode::GfxBackend as i16 {
+            402
+        } else {
+            100
+        }
     }
 }
 
@@ -402,7 +412,7 @@ impl FunctionDefinition {
         }
     }
 }
-pub static FUNCTION_DEFINITIONS: std::sync::LazyLock<[FunctionDefinition; 329]> = std::sync::LazyLock::new(|| {
+pub static FUNCTION_DEFINITIONS: std::sync::LazyLock<[FunctionDefinition; 333]> = std::sync::LazyLock::new(|| {
     [
         FunctionDefinition {
             name: "END",
@@ -3128,6 +3138,40 @@ pub static FUNCTION_DEFINITIONS: std::sync::LazyLock<[FunctionDefinition; 329]>
             args: None,
             signature: FunctionSignature::FixedParameters(0),
         },
+        FunctionDefinition {
+            // What FOR EACH reads its loop variable with: item n of a list, key n of a map.
+            name: "<each item>",
+            version: 400,
+            opcode: FuncOpCode::EachItem,
+            return_type: VariableType::None,
+            args: None,
+            signature: FunctionSignature::FixedParameters(2),
+        },
+        FunctionDefinition {
+            name: "JsonParse",
+            version: 400,
+            opcode: FuncOpCode::JsonParse,
+            return_type: VariableType::UserData(41),
+            args: Some(vec![ArgumentDefinition::new("text", VariableType::BigStr)]),
+            signature: FunctionSignature::FixedParameters(1),
+        },
+        FunctionDefinition {
+            name: "JsonStr",
+            version: 400,
+            opcode: FuncOpCode::JsonStr,
+            return_type: VariableType::BigStr,
+            args: Some(vec![ArgumentDefinition::new("value", VariableType::None)]),
+            signature: FunctionSignature::FixedParameters(1),
+        },
+        FunctionDefinition {
+            // What JsonStr compiles to: the value and the field names of its type.
+            name: "<json text>",
+            version: 400,
+            opcode: FuncOpCode::JsonText,
+            return_type: VariableType::BigStr,
+            args: None,
+            signature: FunctionSignature::FixedParameters(2),
+        },
         // ALIASES (need to be last in the list)
         FunctionDefinition {
             name: "ToString",
@@ -5,6 +5,9 @@ pub use deserializer::*;
 pub mod commands;
 pub use commands::*;
 
+pub mod debug_info;
+pub use debug_info::*;
+
 pub mod compat;
 pub use compat::*;
 
@@ -21,6 +24,9 @@ pub use variable_value::*;
 pub mod variable_table;
 pub use variable_table::*;
 
+pub mod collection;
+pub use collection::*;
+
 pub mod exec;
 pub use exec::*;
 
@@ -32,16 +38,28 @@ pub mod expr_tests;
 #[cfg(test)]
 pub mod stmt_tests;
 
-pub const LAST_PPE_RUNTIME: u16 = 402;
+pub const LAST_PPE_RUNTIME: u16 = 404;
 
-pub const LAST_PPL_LANGUAGE_VERSION: u16 = 400;
+pub const LAST_PPL_LANGUAGE_VERSION: u16 = 404;
 
-pub const SUPPORTED_PPE_VERSIONS: &[u16] = &[100, 200, 300, 310, 320, 330, 340, 400, 401, 402];
+pub const SUPPORTED_PPE_VERSIONS: &[u16] = &[100, 200, 300, 310, 320, 330, 340, 400, 401, 402, 403, 404];
 
-pub const SUPPORTED_PPL_LANGUAGE_VERSIONS: &[u16] = &[100, 200, 300, 310, 320, 330, 340, 350, 400];
+pub const SUPPORTED_PPL_LANGUAGE_VERSIONS: &[u16] = &[100, 200, 300, 310, 320, 330, 340, 350, 400, 404];
 
 pub const PPL_LANG_VERSION_ENV: &str = "PPL_LANG_VERSION";
 
+/// The newest language a runtime can run, which is what a source gets when
+/// nothing names a language version. A runtime that only changed the format
+/// keeps the language before it.
+pub fn language_version_for_runtime(runtime: u16) -> u16 {
+    SUPPORTED_PPL_LANGUAGE_VERSIONS
+        .iter()
+        .copied()
+        .filter(|version| *version <= runtime)
+        .max()
+        .unwrap_or(SUPPORTED_PPL_LANGUAGE_VERSIONS[0])
+}
+
 /// The user's default language version for loose sources and decompilation.
 pub fn language_version_from_env() -> Result<Option<u16>, String> {
     let value = match std::env::var(PPL_LANG_VERSION_ENV) {
@@ -67,6 +85,12 @@ pub const FIRST_ROUTINE_REFERENCE_RUNTIME: u16 = 401;
 
 pub const FIRST_RECORD_LITERAL_RUNTIME: u16 = 401;
 
+/// The first runtime whose type table can hold `LIST OF` and `MAP OF` types.
+pub const FIRST_COLLECTION_RUNTIME: u16 = 403;
+
+/// The first runtime that knows `<try>` and `<end try>`, what `TRY` is lowered to.
+pub const FIRST_TRY_RUNTIME: u16 = 404;
+
 #[derive(Clone, Debug, Default)]
 pub struct VariableNameGenerator {
     _version: u16,
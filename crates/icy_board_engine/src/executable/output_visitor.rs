@@ -152,6 +152,11 @@ impl PPEVisitor<()> for PPEOutputVisitor {
         self.output_function(*label);
     }
 
+    fn visit_try_begin(&mut self, label: &usize) {
+        self.output_keyword("<try> ");
+        self.output_function(*label);
+    }
+
     fn visit_on_error(&mut self, target: &crate::executable::OnErrorTarget) {
         self.output_keyword("ON ERROR ");
         match target {
@@ -288,8 +288,15 @@ pub enum OpCode {
     SetPaletteColorRgb = 248,
     ResetPaletteColor = 249,
     ResetPalette = 250,
+    Watchdog = 251,
+
+    JsonRead = 252,
+    JsonReadFields = 253,
+
+    TryBegin = 254,
+    TryEnd = 255,
 }
-pub const LAST_STMT: i16 = OpCode::ResetPalette as i16;
+pub const LAST_STMT: i16 = OpCode::TryEnd as i16;
 
 impl OpCode {
     pub fn get_definition(self) -> &'static StatementDefinition {
@@ -297,7 +304,15 @@ impl OpCode {
     }
 
     pub fn minimum_runtime(self) -> u16 {
-        if (self as i16) >= OpCode::GfxInit as i16 { 402 } else { 100 }
+        if (self as i16) >= OpCode::TryBegin as i16 {
+            404
+        } else if (self as i16) >= OpCode::JsonRead as i16 {
+            403
+        } else if (self as i16) >= OpCode::GfxInit as i16 {
+            402
+        } else {
+            100
+        }
     }
 }
 
@@ -625,7 +640,7 @@ pub fn format_argument_type_last(arg: &ArgumentDefinition) -> String {
 // "WAIT FOR" == "WAITFOR"
 // "GO SUB"
 // " GO TO"
-pub static STATEMENT_DEFINITIONS: std::sync::LazyLock<[StatementDefinition; 256]> = std::sync::LazyLock::new(|| {
+pub static STATEMENT_DEFINITIONS: std::sync::LazyLock<[StatementDefinition; 261]> = std::sync::LazyLock::new(|| {
     [
         StatementDefinition {
             // helps to map opcode to array index.
@@ -2767,6 +2782,47 @@ pub static STATEMENT_DEFINITIONS: std::sync::LazyLock<[StatementDefinition; 256]
             args: None,
             sig: StatementSignature::ArgumentsWithVariable(0, 0),
         },
+        StatementDefinition {
+            name: "Watchdog",
+            version: 400,
+            opcode: OpCode::Watchdog,
+            args: Some(vec![ArgumentDefinition::new("armed", VariableType::Boolean)]),
+            sig: StatementSignature::ArgumentsWithVariable(0, 1),
+        },
+        StatementDefinition {
+            name: "JsonRead",
+            version: 400,
+            opcode: OpCode::JsonRead,
+            args: Some(vec![
+                ArgumentDefinition::new("text", VariableType::BigStr),
+                ArgumentDefinition::new("var", VariableType::None),
+            ]),
+            sig: StatementSignature::ArgumentsWithVariable(2, 2),
+        },
+        StatementDefinition {
+            // What JsonRead compiles to: the text, the variable and the field names of its type.
+            name: "<json read>",
+            version: 400,
+            opcode: OpCode::JsonReadFields,
+            args: None,
+            sig: StatementSignature::ArgumentsWithVariable(2, 3),
+        },
+        StatementDefinition {
+            // Arms the CATCH part of a TRY, the label is stored like a GOTO target.
+            name: "<try>",
+            version: 404,
+            opcode: OpCode::TryBegin,
+            args: None,
+            sig: StatementSignature::Invalid,
+        },
+        StatementDefinition {
+            // Disarms the innermost TRY where its block ends.
+            name: "<end try>",
+            version: 404,
+            opcode: OpCode::TryEnd,
+            args: None,
+            sig: StatementSignature::ArgumentsWithVariable(0, 0),
+        },
         // Alias section
         // Moving to the end, so that the opcode <--> index mapping is not broken
         StatementDefinition {
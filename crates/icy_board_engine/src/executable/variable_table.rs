@@ -14,7 +14,10 @@ use crate::{
     crypt::{decrypt_chunks, encrypt_chunks},
 };
 
-use super::{ExecutableError, GenericVariableData, LAST_PPE_RUNTIME, PPEExpr, PPEScript, VariableData, VariableNameGenerator, VariableType, VariableValue};
+use super::{
+    CollectionKind, DebugInfo, ExecutableError, GenericVariableData, LAST_PPE_RUNTIME, PPEExpr, PPEScript, PPLList, PPLMap, TypeLayout, VariableData,
+    VariableNameGenerator, VariableType, VariableValue,
+};
 
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct VarHeader {
@@ -109,7 +112,8 @@ impl VarHeader {
 
 /// A record value with every field set up, so a field that is itself a record gets
 /// its own fields too. A type can only name types declared before it, so this ends.
-pub fn create_record_value(type_id: u8, user_types: &[Vec<VariableType>]) -> Option<VariableValue> {
+/// A collection type gives an empty collection that knows what its items start as.
+pub fn create_record_value(type_id: u8, user_types: &[TypeLayout]) -> Option<VariableValue> {
     let built_in_fields = match type_id as usize {
         crate::parser::CONTACT_ID => Some(vec![VariableType::String, VariableType::String]),
         _ => None,
@@ -117,18 +121,23 @@ pub fn create_record_value(type_id: u8, user_types: &[Vec<VariableType>]) -> Opt
     let fields = if let Some(fields) = built_in_fields.as_ref() {
         fields
     } else {
-        user_types.get(type_id as usize - crate::parser::FIRST_USER_TYPE_ID)?
-    };
-    let mut values = Vec::with_capacity(fields.len());
-    for field in fields {
-        let value = match field {
-            VariableType::UserData(id) if crate::parser::is_user_declared_type(*id) => {
-                create_record_value(*id, user_types).unwrap_or_else(|| field.create_empty_value())
+        match user_types.get(type_id as usize - crate::parser::FIRST_USER_TYPE_ID)? {
+            TypeLayout::Record(fields) => fields,
+            TypeLayout::Collection(kind, element) => {
+                let element = create_field_value(*element, user_types);
+                let generic_data = match kind {
+                    CollectionKind::List => GenericVariableData::List(PPLList::new(element)),
+                    CollectionKind::Map => GenericVariableData::Map(PPLMap::new(element)),
+                };
+                return Some(VariableValue {
+                    vtype: VariableType::UserData(type_id),
+                    data: crate::executable::VariableData::default(),
+                    generic_data,
+                });
             }
-            _ => field.create_empty_value(),
-        };
-        values.push(value);
-    }
+        }
+    };
+    let values = fields.iter().map(|field| create_field_value(*field, user_types)).collect();
     Some(VariableValue {
         vtype: VariableType::UserData(type_id),
         data: crate::executable::VariableData::default(),
@@ -136,6 +145,15 @@ pub fn create_record_value(type_id: u8, user_types: &[Vec<VariableType>]) -> Opt
     })
 }
 
+fn create_field_value(field: VariableType, user_types: &[TypeLayout]) -> VariableValue {
+    match field {
+        VariableType::UserData(id) if crate::parser::is_user_declared_type(id) => {
+            create_record_value(id, user_types).unwrap_or_else(|| field.create_empty_value())
+        }
+        _ => field.create_empty_value(),
+    }
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct FunctionValue {
     pub parameters: u8,
@@ -637,6 +655,28 @@ impl VariableTable {
         }
     }
 
+    /// Puts back the names the source used, as far as the debug information knows them.
+    /// Call it after `generate_names`, it leaves the rest of the made up names alone.
+    pub fn restore_names(&mut self, debug_info: &DebugInfo) {
+        for var in &debug_info.variables {
+            if let Some(entry) = self.try_get_entry_mut(var.id)
+                && !matches!(entry.get_type(), EntryType::Constant | EntryType::UserVariable)
+            {
+                // A module member is named `Module.Member`, the module itself is gone.
+                entry.set_name(var.name.replace('.', "_"));
+            }
+        }
+        for i in 0..self.entries.len() {
+            if self.entries[i].header.variable_type == VariableType::Function {
+                let id = unsafe { self.entries[i].value.data.function_value.return_var as usize };
+                let name = self.entries[i].get_name().clone();
+                if let Some(entry) = self.try_get_entry_mut(id) {
+                    entry.set_name(name);
+                }
+            }
+        }
+    }
+
     pub fn analyze_usage(&mut self, script: &PPEScript) {
         for stmt in &script.statements {
             self.analyze_statement(&stmt.command);
@@ -921,10 +961,10 @@ impl VariableTable {
         &self.entries
     }
 
-    /// Gives every variable of a program declared type the fields its record has.
-    /// The layout is not part of a variable's own entry, so it is filled in once the
-    /// type table has been read.
-    pub fn fill_in_records(&mut self, user_types: &[Vec<VariableType>]) {
+    /// Gives every variable of a program declared type the fields its record has, or
+    /// its empty collection. The layout is not part of a variable's own entry, so it is
+    /// filled in once the type table has been read.
+    pub fn fill_in_records(&mut self, user_types: &[TypeLayout]) {
         for entry in &mut self.entries {
             let VariableType::UserData(type_id) = entry.header.variable_type else {
                 continue;
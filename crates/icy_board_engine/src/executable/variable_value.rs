@@ -292,6 +292,12 @@ pub enum GenericVariableData {
     /// The fields of a value whose type the program declared with TYPE/ENDTYPE.
     Record(Vec<VariableValue>),
 
+    /// A `LIST OF` value.
+    List(super::PPLList),
+
+    /// A `MAP OF STRING TO` value.
+    Map(super::PPLMap),
+
     /// The object a member expression reads, kept alive by the values that name it.
     UserData(std::sync::Arc<dyn crate::compiler::user_data::UserDataValue>),
 }
@@ -308,6 +314,8 @@ impl fmt::Debug for GenericVariableData {
             // A secret has no business in a log line.
             GenericVariableData::Password(_) => write!(f, "Password(******)"),
             GenericVariableData::Record(fields) => write!(f, "Record({fields:?})"),
+            GenericVariableData::List(list) => write!(f, "List({:?})", list.items),
+            GenericVariableData::Map(map) => write!(f, "Map({:?})", map.entries),
             GenericVariableData::UserData(_) => write!(f, "UserData"),
         }
     }
@@ -411,6 +419,8 @@ impl PartialEq for VariableValue {
             }
             return match (&self.generic_data, &other.generic_data) {
                 (GenericVariableData::Record(left), GenericVariableData::Record(right)) => left == right,
+                (GenericVariableData::List(left), GenericVariableData::List(right)) => left.items == right.items,
+                (GenericVariableData::Map(left), GenericVariableData::Map(right)) => left.entries == right.entries,
                 _ => false,
             };
         }
@@ -960,6 +970,16 @@ impl VariableValue {
                         .collect(),
                 ),
             },
+            GenericVariableData::List(list) => VariableValue {
+                vtype: self.vtype,
+                data: VariableData::default(),
+                generic_data: GenericVariableData::List(super::PPLList::new((*list.element).clone())),
+            },
+            GenericVariableData::Map(map) => VariableValue {
+                vtype: self.vtype,
+                data: VariableData::default(),
+                generic_data: GenericVariableData::Map(super::PPLMap::new((*map.element).clone())),
+            },
             _ => self.vtype.create_empty_value(),
         }
     }
@@ -1,9 +1,9 @@
 use crate::ast::{
     ArrayInitializerExpression, Ast, AstNode, AstVisitor, BinaryExpression, BlockStatement, ConstDeclarationStatement, EnumDeclarationAstNode, Expression,
-    ForStatement, FunctionCallExpression, FunctionDeclarationAstNode, FunctionImplementation, IfThenStatement, IndexerExpression, LetStatement, LoopStatement,
-    MemberReferenceExpression, ParameterSpecifier, PredefinedCallStatement, ProcedureCallStatement, ProcedureDeclarationAstNode, ProcedureImplementation,
-    RecordLiteralExpression, RepeatUntilStatement, SelectStatement, Statement, TypeDeclarationAstNode, UnaryExpression, VariableDeclarationStatement,
-    WhileDoStatement, walk_binary_expression,
+    ForEachStatement, ForStatement, FunctionCallExpression, FunctionDeclarationAstNode, FunctionImplementation, IfThenStatement, ImportAstNode,
+    IndexerExpression, LetStatement, LoopStatement, MemberReferenceExpression, ModuleAstNode, ParameterSpecifier, PredefinedCallStatement,
+    ProcedureCallStatement, ProcedureDeclarationAstNode, ProcedureImplementation, RecordLiteralExpression, RepeatUntilStatement, SelectStatement, Statement,
+    TryStatement, TypeDeclarationAstNode, UnaryExpression, VariableDeclarationStatement, WhileDoStatement, walk_binary_expression,
 };
 
 pub mod options;
@@ -24,6 +24,22 @@ fn parameter_start(parameter: &ParameterSpecifier) -> usize {
     }
 }
 
+/// Where a top level node starts and ends, `None` for a main program without `BEGIN`.
+fn node_span(node: &AstNode) -> Option<core::ops::Range<usize>> {
+    match node {
+        AstNode::TopLevelStatement(statement) => Some(statement.get_span()),
+        AstNode::Function(function) => Some(function.get_function_token().span.start..function.get_endfunc_token().span.end),
+        AstNode::Procedure(procedure) => Some(procedure.get_procedure_token().span.start..procedure.get_endproc_token().span.end),
+        AstNode::FunctionDeclaration(function) => Some(function.get_declare_token().span.clone()),
+        AstNode::ProcedureDeclaration(procedure) => Some(procedure.get_declare_token().span.clone()),
+        AstNode::TypeDeclaration(declaration) => Some(declaration.get_type_token().span.start..declaration.get_endtype_token().span.end),
+        AstNode::EnumDeclaration(declaration) => Some(declaration.get_enum_token().span.start..declaration.get_endenum_token().span.end),
+        AstNode::Module(module) => Some(module.get_module_token().span.start..module.get_endmodule_token().span.end),
+        AstNode::Import(import) => Some(import.get_import_token().span.start..import.get_identifier_token().span.end),
+        AstNode::Main(main) => main.get_begin_token().map(|begin_token| begin_token.span.clone()),
+    }
+}
+
 pub struct FormattingVisitor<'a> {
     pub backend: &'a mut dyn FormattingBackend,
     pub options: &'a FormattingOptions,
@@ -142,17 +158,7 @@ impl<'a> FormattingVisitor<'a> {
     /// Formats a whole file, which is where the top level starts at column zero.
     pub fn format(&mut self, ast: &Ast) {
         for node in &ast.nodes {
-            let span = match node {
-                AstNode::TopLevelStatement(statement) => Some(statement.get_span()),
-                AstNode::Function(function) => Some(function.get_function_token().span.start..function.get_endfunc_token().span.end),
-                AstNode::Procedure(procedure) => Some(procedure.get_procedure_token().span.start..procedure.get_endproc_token().span.end),
-                AstNode::FunctionDeclaration(function) => Some(function.get_declare_token().span.clone()),
-                AstNode::ProcedureDeclaration(procedure) => Some(procedure.get_declare_token().span.clone()),
-                AstNode::TypeDeclaration(declaration) => Some(declaration.get_type_token().span.start..declaration.get_endtype_token().span.end),
-                AstNode::EnumDeclaration(declaration) => Some(declaration.get_enum_token().span.start..declaration.get_endenum_token().span.end),
-                AstNode::Main(main) => main.get_begin_token().map(|begin_token| begin_token.span.clone()),
-            };
-            if let Some(span) = &span {
+            if let Some(span) = node_span(node) {
                 self.backend.limit_blank_lines(span.start, self.options.max_blank_lines);
                 self.indent(span.start..span.start);
             }
@@ -213,6 +219,29 @@ impl AstVisitor<()> for FormattingVisitor<'_> {
         }
     }
 
+    fn visit_module(&mut self, module: &ModuleAstNode) {
+        self.ensure_space_before(module.get_identifier_token().span.start);
+        self.inc_indent();
+        for member in module.get_members() {
+            let node_start = node_span(member.get_node()).map(|span| span.start);
+            let start = member.get_visibility_token().map(|visibility| visibility.span.start).or(node_start);
+            if let Some(start) = start {
+                self.backend.limit_blank_lines(start, self.options.max_blank_lines);
+                self.indent(start..start);
+            }
+            if let (Some(_), Some(node_start)) = (member.get_visibility_token(), node_start) {
+                self.ensure_space_before(node_start);
+            }
+            member.get_node().visit(self);
+        }
+        self.dec_indent();
+        self.indent(module.get_endmodule_token().span.clone());
+    }
+
+    fn visit_import(&mut self, import: &ImportAstNode) {
+        self.ensure_space_before(import.get_identifier_token().span.start);
+    }
+
     fn visit_let_statement(&mut self, let_stmt: &LetStatement) {
         // `p . X = 1` names its members with tokens rather than an expression.
         let mut left = let_stmt.get_identifier_token().span.end;
@@ -391,6 +420,14 @@ impl AstVisitor<()> for FormattingVisitor<'_> {
         self.indent(for_stmt.get_next_token().span.clone());
     }
 
+    fn visit_for_each_statement(&mut self, for_each_stmt: &ForEachStatement) {
+        for_each_stmt.get_collection_expr().visit(self);
+        self.inc_indent();
+        self.format_block(for_each_stmt.get_statements());
+        self.dec_indent();
+        self.indent(for_each_stmt.get_next_token().span.clone());
+    }
+
     fn visit_while_do_statement(&mut self, while_do_stmt: &WhileDoStatement) {
         while_do_stmt.get_condition().visit(self);
         self.inc_indent();
@@ -414,6 +451,20 @@ impl AstVisitor<()> for FormattingVisitor<'_> {
         self.indent(loop_stmt.get_endloop_token().span.clone());
     }
 
+    fn visit_try_statement(&mut self, try_stmt: &TryStatement) {
+        self.inc_indent();
+        self.format_block(try_stmt.get_statements());
+        self.dec_indent();
+        self.indent(try_stmt.get_catch_token().span.clone());
+        if let Some(id) = try_stmt.get_catch_identifier_token() {
+            self.ensure_space_before(id.span.start);
+        }
+        self.inc_indent();
+        self.format_block(try_stmt.get_catch_statements());
+        self.dec_indent();
+        self.indent(try_stmt.get_endtry_token().span.clone());
+    }
+
     fn visit_function_implementation(&mut self, function: &FunctionImplementation) {
         self.format_parameters(function.get_parameters());
         self.inc_indent();
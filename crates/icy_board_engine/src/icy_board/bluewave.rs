@@ -0,0 +1,1069 @@
+//! Blue Wave offline mail packets (format revision 3).
+//!
+//! A mail packet holds `ID.INF` (board and area list), `ID.MIX` (one record per
+//! area with messages), `ID.FTI` (one header per message) and `ID.DAT` (the
+//! message texts). The reader answers with `ID.NEW`, which carries `ID.UPL`
+//! (one record per reply, the text in a file of its own), `ID.REQ` (file
+//! requests) and, from older readers, `ID.NET` (netmail).
+
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Write},
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use thiserror::Error;
+
+type Res<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+pub const PACKET_VERSION: u8 = 3;
+
+/// `INF_AREA_INFO.area_flags`
+pub mod area_flags {
+    pub const SCANNING: u16 = 0x0001;
+    pub const ALIAS_NAME: u16 = 0x0002;
+    pub const ANY_NAME: u16 = 0x0004;
+    pub const ECHO: u16 = 0x0008;
+    pub const NETMAIL: u16 = 0x0010;
+    pub const POST: u16 = 0x0020;
+    pub const NO_PRIVATE: u16 = 0x0040;
+    pub const NO_PUBLIC: u16 = 0x0080;
+}
+
+/// `FTI_REC.flags`, the fidonet message attributes.
+pub mod message_flags {
+    pub const PRIVATE: u16 = 0x0001;
+    pub const READ: u16 = 0x0004;
+    pub const LOCAL: u16 = 0x0100;
+}
+
+/// `UPL_REC.msg_attr`
+pub mod reply_flags {
+    pub const INACTIVE: u16 = 0x0001;
+    pub const PRIVATE: u16 = 0x0002;
+    pub const NO_ECHO: u16 = 0x0004;
+    pub const HAS_FILE: u16 = 0x0008;
+    pub const NETMAIL: u16 = 0x0010;
+    pub const IS_REPLY: u16 = 0x0020;
+}
+
+#[derive(Error, Debug)]
+pub enum BlueWaveError {
+    #[error("Blue Wave {0} ends in the middle of a record")]
+    Truncated(&'static str),
+
+    #[error("Blue Wave {0} declares records of {1} bytes, at least {2} are needed")]
+    RecordTooShort(&'static str, usize, usize),
+
+    #[error("Reply packet has no {0}")]
+    MissingFile(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InfHeader {
+    /// Up to five files in the packet the reader shows on its own, bulletins and the like.
+    pub reader_files: Vec<String>,
+    pub login_name: String,
+    pub alias_name: String,
+    /// Packet password, only used when `password_type` isn't zero.
+    pub password: String,
+    pub password_type: u8,
+    pub zone: u16,
+    pub net: u16,
+    pub node: u16,
+    pub point: u16,
+    pub sysop: String,
+    pub system_name: String,
+    pub max_freqs: u8,
+    /// Longest name the board takes for from and to, zero means the record size.
+    pub from_to_len: u8,
+    pub subject_len: u8,
+    pub packet_id: String,
+}
+
+impl InfHeader {
+    /// What the board writes, the reserved tail of `INF_HEADER` included.
+    pub const SIZE: usize = 1230;
+    /// The fields up to `packet_id`, the rest of the header is reserved.
+    const KNOWN: usize = 993;
+    const READER_FILES: usize = 5;
+
+    fn read(input: &mut impl Read) -> Res<(Self, usize, usize, usize)> {
+        let mut bytes = vec![0u8; Self::KNOWN];
+        input.read_exact(&mut bytes).map_err(|_| BlueWaveError::Truncated("INF header"))?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        let _version = cursor.read_u8()?;
+        let mut reader_files = Vec::new();
+        for _ in 0..Self::READER_FILES {
+            let file = read_fixed(&mut cursor, 13)?;
+            if !file.is_empty() {
+                reader_files.push(file);
+            }
+        }
+        let _registration = read_fixed(&mut cursor, 9)?;
+        let _mash_type = cursor.read_u8()?;
+        let login_name = read_fixed(&mut cursor, 43)?;
+        let alias_name = read_fixed(&mut cursor, 43)?;
+        let password = read_fixed(&mut cursor, 21)?;
+        let password_type = cursor.read_u8()?;
+        let zone = cursor.read_u16::<LittleEndian>()?;
+        let net = cursor.read_u16::<LittleEndian>()?;
+        let node = cursor.read_u16::<LittleEndian>()?;
+        let point = cursor.read_u16::<LittleEndian>()?;
+        let sysop = read_fixed(&mut cursor, 41)?;
+        let _ctrl_flags = cursor.read_u16::<LittleEndian>()?;
+        let system_name = read_fixed(&mut cursor, 65)?;
+        let max_freqs = cursor.read_u8()?;
+        // obsolete QWK flag, obsolete byte, user flags, keywords, filters, macros
+        skip(&mut cursor, 2 + 1 + 2 + 10 * 21 + 10 * 21 + 3 * 80)?;
+        // netmail flags, credits, debits, can forward
+        skip(&mut cursor, 2 + 2 + 2 + 1)?;
+        let header_len = cursor.read_u16::<LittleEndian>()? as usize;
+        let area_len = cursor.read_u16::<LittleEndian>()? as usize;
+        let mix_len = cursor.read_u16::<LittleEndian>()? as usize;
+        let fti_len = cursor.read_u16::<LittleEndian>()? as usize;
+        let _uses_upl_file = cursor.read_u8()?;
+        let from_to_len = cursor.read_u8()?;
+        let subject_len = cursor.read_u8()?;
+        let packet_id = read_fixed(&mut cursor, 9)?;
+
+        if header_len < Self::KNOWN {
+            return Err(BlueWaveError::RecordTooShort("INF header", header_len, Self::KNOWN).into());
+        }
+        skip(input, header_len - Self::KNOWN)?;
+        Ok((
+            Self {
+                reader_files,
+                login_name,
+                alias_name,
+                password,
+                password_type,
+                zone,
+                net,
+                node,
+                point,
+                sysop,
+                system_name,
+                max_freqs,
+                from_to_len,
+                subject_len,
+                packet_id,
+            },
+            area_len,
+            mix_len,
+            fti_len,
+        ))
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.write_u8(PACKET_VERSION)?;
+        for i in 0..Self::READER_FILES {
+            write_fixed(&mut bytes, self.reader_files.get(i).map_or("", String::as_str), 13)?;
+        }
+        write_fixed(&mut bytes, "", 9)?;
+        bytes.write_u8(0)?;
+        write_fixed(&mut bytes, &self.login_name, 43)?;
+        write_fixed(&mut bytes, &self.alias_name, 43)?;
+        write_fixed(&mut bytes, &self.password, 21)?;
+        bytes.write_u8(self.password_type)?;
+        bytes.write_u16::<LittleEndian>(self.zone)?;
+        bytes.write_u16::<LittleEndian>(self.net)?;
+        bytes.write_u16::<LittleEndian>(self.node)?;
+        bytes.write_u16::<LittleEndian>(self.point)?;
+        write_fixed(&mut bytes, &self.sysop, 41)?;
+        bytes.write_u16::<LittleEndian>(0)?;
+        write_fixed(&mut bytes, &self.system_name, 65)?;
+        bytes.write_u8(self.max_freqs)?;
+        bytes.resize(bytes.len() + 2 + 1 + 2 + 10 * 21 + 10 * 21 + 3 * 80 + 2 + 2 + 2 + 1, 0);
+        bytes.write_u16::<LittleEndian>(Self::SIZE as u16)?;
+        bytes.write_u16::<LittleEndian>(InfArea::SIZE as u16)?;
+        bytes.write_u16::<LittleEndian>(MixRecord::SIZE as u16)?;
+        bytes.write_u16::<LittleEndian>(FtiRecord::SIZE as u16)?;
+        // Replies come back as UPL records, not in the old NET/MSG layout.
+        bytes.write_u8(1)?;
+        bytes.write_u8(self.from_to_len)?;
+        bytes.write_u8(self.subject_len)?;
+        write_fixed(&mut bytes, &self.packet_id, 9)?;
+        debug_assert_eq!(bytes.len(), Self::KNOWN);
+        bytes.resize(Self::SIZE, 0);
+        output.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InfArea {
+    /// The number the reader shows, the board uses the QWK conference number.
+    pub area_number: String,
+    pub echo_tag: String,
+    pub title: String,
+    pub area_flags: u16,
+    pub network_type: u8,
+}
+
+impl InfArea {
+    pub const SIZE: usize = 80;
+
+    fn read(input: &mut impl Read, len: usize) -> Res<Self> {
+        let bytes = read_record(input, "INF area", len, Self::SIZE)?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        Ok(Self {
+            area_number: read_fixed(&mut cursor, 6)?,
+            echo_tag: read_fixed(&mut cursor, 21)?,
+            title: read_fixed(&mut cursor, 50)?,
+            area_flags: cursor.read_u16::<LittleEndian>()?,
+            network_type: cursor.read_u8()?,
+        })
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        write_fixed(output, &self.area_number, 6)?;
+        write_fixed(output, &self.echo_tag, 21)?;
+        write_fixed(output, &self.title, 50)?;
+        output.write_u16::<LittleEndian>(self.area_flags)?;
+        output.write_u8(self.network_type)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MixRecord {
+    pub area_number: String,
+    pub total_messages: u16,
+    pub personal_messages: u16,
+    /// Offset of the area's first record in `ID.FTI`.
+    pub fti_offset: u32,
+}
+
+impl MixRecord {
+    pub const SIZE: usize = 14;
+
+    fn read(input: &mut impl Read, len: usize) -> Res<Self> {
+        let bytes = read_record(input, "MIX record", len, Self::SIZE)?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        Ok(Self {
+            area_number: read_fixed(&mut cursor, 6)?,
+            total_messages: cursor.read_u16::<LittleEndian>()?,
+            personal_messages: cursor.read_u16::<LittleEndian>()?,
+            fti_offset: cursor.read_u32::<LittleEndian>()?,
+        })
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        write_fixed(output, &self.area_number, 6)?;
+        output.write_u16::<LittleEndian>(self.total_messages)?;
+        output.write_u16::<LittleEndian>(self.personal_messages)?;
+        output.write_u32::<LittleEndian>(self.fti_offset)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FtiRecord {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    /// Fidonet style, "01 Jan 86  02:34:56".
+    pub date: String,
+    pub msg_number: u16,
+    pub reply_to: u16,
+    pub reply_at: u16,
+    /// Offset of the text in `ID.DAT`, pointing at the space every text starts with.
+    pub msg_offset: u32,
+    pub msg_length: u32,
+    pub flags: u16,
+    pub orig_zone: u16,
+    pub orig_net: u16,
+    pub orig_node: u16,
+}
+
+impl FtiRecord {
+    pub const SIZE: usize = 186;
+
+    fn read(input: &mut impl Read, len: usize) -> Res<Self> {
+        let bytes = read_record(input, "FTI record", len, Self::SIZE)?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        Ok(Self {
+            from: read_fixed(&mut cursor, 36)?,
+            to: read_fixed(&mut cursor, 36)?,
+            subject: read_fixed(&mut cursor, 72)?,
+            date: read_fixed(&mut cursor, 20)?,
+            msg_number: cursor.read_u16::<LittleEndian>()?,
+            reply_to: cursor.read_u16::<LittleEndian>()?,
+            reply_at: cursor.read_u16::<LittleEndian>()?,
+            msg_offset: cursor.read_u32::<LittleEndian>()?,
+            msg_length: cursor.read_u32::<LittleEndian>()?,
+            flags: cursor.read_u16::<LittleEndian>()?,
+            orig_zone: cursor.read_u16::<LittleEndian>()?,
+            orig_net: cursor.read_u16::<LittleEndian>()?,
+            orig_node: cursor.read_u16::<LittleEndian>()?,
+        })
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        write_fixed(output, &self.from, 36)?;
+        write_fixed(output, &self.to, 36)?;
+        write_fixed(output, &self.subject, 72)?;
+        write_fixed(output, &self.date, 20)?;
+        output.write_u16::<LittleEndian>(self.msg_number)?;
+        output.write_u16::<LittleEndian>(self.reply_to)?;
+        output.write_u16::<LittleEndian>(self.reply_at)?;
+        output.write_u32::<LittleEndian>(self.msg_offset)?;
+        output.write_u32::<LittleEndian>(self.msg_length)?;
+        output.write_u16::<LittleEndian>(self.flags)?;
+        output.write_u16::<LittleEndian>(self.orig_zone)?;
+        output.write_u16::<LittleEndian>(self.orig_net)?;
+        output.write_u16::<LittleEndian>(self.orig_node)?;
+        Ok(())
+    }
+}
+
+/// A message on its way into a mail packet; the offsets are filled in when the
+/// packet is written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PacketMessage {
+    pub header: FtiRecord,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PacketArea {
+    pub info: InfArea,
+    pub messages: Vec<PacketMessage>,
+}
+
+/// The four files of a mail packet, before they go into the archive.
+pub struct MailPacketFiles {
+    pub inf: Vec<u8>,
+    pub mix: Vec<u8>,
+    pub fti: Vec<u8>,
+    pub dat: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MailPacket {
+    pub header: InfHeader,
+    /// Every area the caller may read, the ones without messages included.
+    pub areas: Vec<PacketArea>,
+}
+
+impl MailPacket {
+    pub fn message_count(&self) -> usize {
+        self.areas.iter().map(|area| area.messages.len()).sum()
+    }
+
+    pub fn to_files(&self) -> Res<MailPacketFiles> {
+        let mut inf = Vec::new();
+        self.header.write(&mut inf)?;
+        for area in &self.areas {
+            area.info.write(&mut inf)?;
+        }
+
+        let mut mix = Vec::new();
+        let mut fti = Vec::new();
+        let mut dat = Vec::new();
+        for area in &self.areas {
+            if area.messages.is_empty() {
+                continue;
+            }
+            let mix_record = MixRecord {
+                area_number: area.info.area_number.clone(),
+                total_messages: area.messages.len().min(u16::MAX as usize) as u16,
+                personal_messages: area
+                    .messages
+                    .iter()
+                    .filter(|msg| msg.header.to.eq_ignore_ascii_case(&self.header.login_name) || msg.header.to.eq_ignore_ascii_case(&self.header.alias_name))
+                    .count()
+                    .min(u16::MAX as usize) as u16,
+                fti_offset: fti.len() as u32,
+            };
+            mix_record.write(&mut mix)?;
+
+            for msg in &area.messages {
+                let mut text = vec![b' '];
+                text.extend(to_cp437(&msg.text));
+                let mut header = msg.header.clone();
+                header.msg_offset = dat.len() as u32;
+                header.msg_length = text.len() as u32;
+                header.write(&mut fti)?;
+                dat.extend(text);
+            }
+        }
+        Ok(MailPacketFiles { inf, mix, fti, dat })
+    }
+
+    pub fn from_files(files: &MailPacketFiles) -> Res<Self> {
+        let mut input = Cursor::new(&files.inf[..]);
+        let (header, area_len, mix_len, fti_len) = InfHeader::read(&mut input)?;
+        let mut areas = Vec::new();
+        while (input.position() as usize) < files.inf.len() {
+            areas.push(PacketArea {
+                info: InfArea::read(&mut input, area_len)?,
+                messages: Vec::new(),
+            });
+        }
+
+        let mut mix = Cursor::new(&files.mix[..]);
+        while (mix.position() as usize) < files.mix.len() {
+            let record = MixRecord::read(&mut mix, mix_len)?;
+            let Some(area) = areas.iter_mut().find(|area| area.info.area_number == record.area_number) else {
+                continue;
+            };
+            let mut fti = Cursor::new(&files.fti[..]);
+            fti.set_position(record.fti_offset as u64);
+            for _ in 0..record.total_messages {
+                let header = FtiRecord::read(&mut fti, fti_len)?;
+                let start = header.msg_offset as usize;
+                let end = start + header.msg_length as usize;
+                if end > files.dat.len() {
+                    return Err(BlueWaveError::Truncated("DAT file").into());
+                }
+                let bytes = &files.dat[start..end];
+                let bytes = bytes.strip_prefix(b" ").unwrap_or(bytes);
+                area.messages.push(PacketMessage {
+                    text: from_cp437(bytes),
+                    header,
+                });
+            }
+        }
+        Ok(Self { header, areas })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UplHeader {
+    pub reader_name: String,
+    pub reader_version: String,
+    pub reader_tear: String,
+    pub login_name: String,
+    pub alias_name: String,
+}
+
+impl UplHeader {
+    /// What the board writes, the reserved tail of `UPL_HEADER` included.
+    pub const SIZE: usize = 256;
+    /// `upl_header_len` and `upl_rec_len` are known once this much is read.
+    const LENGTHS: usize = 116;
+    /// The fields up to `reader_tear`, the rest of the header is flags and padding.
+    const KNOWN: usize = 218;
+
+    fn read(input: &mut impl Read) -> Res<(Self, usize)> {
+        let mut bytes = vec![0u8; Self::LENGTHS];
+        input.read_exact(&mut bytes).map_err(|_| BlueWaveError::Truncated("UPL header"))?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        let _registration = read_fixed(&mut cursor, 10)?;
+        let reader_version = read_fixed(&mut cursor, 20)?;
+        let _major = cursor.read_u8()?;
+        let _minor = cursor.read_u8()?;
+        let reader_name = read_fixed(&mut cursor, 80)?;
+        let header_len = cursor.read_u16::<LittleEndian>()? as usize;
+        let record_len = cursor.read_u16::<LittleEndian>()? as usize;
+
+        let rest = read_record(input, "UPL header", header_len.saturating_sub(Self::LENGTHS), Self::KNOWN - Self::LENGTHS)?;
+        let mut cursor = Cursor::new(&rest[..]);
+        let login_name = read_fixed(&mut cursor, 43)?;
+        let alias_name = read_fixed(&mut cursor, 43)?;
+        let reader_tear = read_fixed(&mut cursor, 16)?;
+        Ok((
+            Self {
+                reader_name,
+                reader_version,
+                reader_tear,
+                login_name,
+                alias_name,
+            },
+            record_len,
+        ))
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        write_fixed(&mut bytes, "", 10)?;
+        write_fixed(&mut bytes, &self.reader_version, 20)?;
+        bytes.write_u8(0)?;
+        bytes.write_u8(0)?;
+        write_fixed(&mut bytes, &self.reader_name, 80)?;
+        bytes.write_u16::<LittleEndian>(Self::SIZE as u16)?;
+        bytes.write_u16::<LittleEndian>(UplRecord::SIZE as u16)?;
+        write_fixed(&mut bytes, &self.login_name, 43)?;
+        write_fixed(&mut bytes, &self.alias_name, 43)?;
+        write_fixed(&mut bytes, &self.reader_tear, 16)?;
+        debug_assert_eq!(bytes.len(), Self::KNOWN);
+        bytes.resize(Self::SIZE, 0);
+        output.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UplRecord {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub dest_zone: u16,
+    pub dest_net: u16,
+    pub dest_node: u16,
+    pub dest_point: u16,
+    pub msg_attr: u16,
+    pub netmail_attr: u16,
+    pub unix_date: u32,
+    /// The board's number of the message this one answers, zero if none.
+    pub reply_to: u32,
+    /// Name of the file in the reply packet that holds the text.
+    pub file_name: String,
+    pub echo_tag: String,
+    pub area_flags: u16,
+    pub attached_file: String,
+    pub user_area: String,
+    pub network_type: u8,
+    pub net_dest: String,
+}
+
+impl UplRecord {
+    pub const SIZE: usize = 320;
+
+    fn read(input: &mut impl Read, len: usize) -> Res<Self> {
+        let bytes = read_record(input, "UPL record", len, Self::SIZE)?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        Ok(Self {
+            from: read_fixed(&mut cursor, 36)?,
+            to: read_fixed(&mut cursor, 36)?,
+            subject: read_fixed(&mut cursor, 72)?,
+            dest_zone: cursor.read_u16::<LittleEndian>()?,
+            dest_net: cursor.read_u16::<LittleEndian>()?,
+            dest_node: cursor.read_u16::<LittleEndian>()?,
+            dest_point: cursor.read_u16::<LittleEndian>()?,
+            msg_attr: cursor.read_u16::<LittleEndian>()?,
+            netmail_attr: cursor.read_u16::<LittleEndian>()?,
+            unix_date: cursor.read_u32::<LittleEndian>()?,
+            reply_to: cursor.read_u32::<LittleEndian>()?,
+            file_name: read_fixed(&mut cursor, 13)?,
+            echo_tag: read_fixed(&mut cursor, 21)?,
+            area_flags: cursor.read_u16::<LittleEndian>()?,
+            attached_file: read_fixed(&mut cursor, 13)?,
+            user_area: read_fixed(&mut cursor, 6)?,
+            network_type: cursor.read_u8()?,
+            net_dest: read_fixed(&mut cursor, 100)?,
+        })
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        write_fixed(output, &self.from, 36)?;
+        write_fixed(output, &self.to, 36)?;
+        write_fixed(output, &self.subject, 72)?;
+        output.write_u16::<LittleEndian>(self.dest_zone)?;
+        output.write_u16::<LittleEndian>(self.dest_net)?;
+        output.write_u16::<LittleEndian>(self.dest_node)?;
+        output.write_u16::<LittleEndian>(self.dest_point)?;
+        output.write_u16::<LittleEndian>(self.msg_attr)?;
+        output.write_u16::<LittleEndian>(self.netmail_attr)?;
+        output.write_u32::<LittleEndian>(self.unix_date)?;
+        output.write_u32::<LittleEndian>(self.reply_to)?;
+        write_fixed(output, &self.file_name, 13)?;
+        write_fixed(output, &self.echo_tag, 21)?;
+        output.write_u16::<LittleEndian>(self.area_flags)?;
+        write_fixed(output, &self.attached_file, 13)?;
+        write_fixed(output, &self.user_area, 6)?;
+        output.write_u8(self.network_type)?;
+        write_fixed(output, &self.net_dest, 100)?;
+        Ok(())
+    }
+}
+
+/// `NET_REC` - how readers before `ID.UPL` sent netmail: a stored fidonet
+/// message header followed by the name of the text file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetRecord {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub date: String,
+    pub dest_zone: u16,
+    pub dest_net: u16,
+    pub dest_node: u16,
+    pub dest_point: u16,
+    pub attributes: u16,
+    pub file_name: String,
+}
+
+impl NetRecord {
+    pub const SIZE: usize = 190 + 13;
+
+    fn read(input: &mut impl Read) -> Res<Self> {
+        let bytes = read_record(input, "NET record", Self::SIZE, Self::SIZE)?;
+        let mut cursor = Cursor::new(&bytes[..]);
+        let from = read_fixed(&mut cursor, 36)?;
+        let to = read_fixed(&mut cursor, 36)?;
+        let subject = read_fixed(&mut cursor, 72)?;
+        let date = read_fixed(&mut cursor, 20)?;
+        let _times_read = cursor.read_u16::<LittleEndian>()?;
+        let dest_node = cursor.read_u16::<LittleEndian>()?;
+        let _orig_node = cursor.read_u16::<LittleEndian>()?;
+        let _cost = cursor.read_u16::<LittleEndian>()?;
+        let _orig_net = cursor.read_u16::<LittleEndian>()?;
+        let dest_net = cursor.read_u16::<LittleEndian>()?;
+        let dest_zone = cursor.read_u16::<LittleEndian>()?;
+        let _orig_zone = cursor.read_u16::<LittleEndian>()?;
+        let dest_point = cursor.read_u16::<LittleEndian>()?;
+        let _orig_point = cursor.read_u16::<LittleEndian>()?;
+        let _reply_to = cursor.read_u16::<LittleEndian>()?;
+        let attributes = cursor.read_u16::<LittleEndian>()?;
+        let _next_reply = cursor.read_u16::<LittleEndian>()?;
+        let file_name = read_fixed(&mut cursor, 13)?;
+        Ok(Self {
+            from,
+            to,
+            subject,
+            date,
+            dest_zone,
+            dest_net,
+            dest_node,
+            dest_point,
+            attributes,
+            file_name,
+        })
+    }
+
+    fn write(&self, output: &mut impl Write) -> Res<()> {
+        write_fixed(output, &self.from, 36)?;
+        write_fixed(output, &self.to, 36)?;
+        write_fixed(output, &self.subject, 72)?;
+        write_fixed(output, &self.date, 20)?;
+        for value in [
+            0,
+            self.dest_node,
+            0,
+            0,
+            0,
+            self.dest_net,
+            self.dest_zone,
+            0,
+            self.dest_point,
+            0,
+            0,
+            self.attributes,
+            0,
+        ] {
+            output.write_u16::<LittleEndian>(value)?;
+        }
+        write_fixed(output, &self.file_name, 13)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplyMessage {
+    pub header: UplRecord,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetReply {
+    pub header: NetRecord,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplyPacket {
+    pub header: UplHeader,
+    pub messages: Vec<ReplyMessage>,
+    pub netmail: Vec<NetReply>,
+    /// File names or wildcards from `ID.REQ`.
+    pub file_requests: Vec<String>,
+}
+
+impl ReplyPacket {
+    /// Reads a reply packet from the files of the unpacked `ID.NEW` archive,
+    /// the keys being upper case file names.
+    pub fn from_files(packet_id: &str, files: &HashMap<String, Vec<u8>>) -> Res<Self> {
+        let packet_id = packet_id.to_ascii_uppercase();
+        let mut result = Self::default();
+
+        if let Some(upl) = files.get(&format!("{packet_id}.UPL")) {
+            let mut input = Cursor::new(&upl[..]);
+            let (header, record_len) = UplHeader::read(&mut input)?;
+            result.header = header;
+            while (input.position() as usize) < upl.len() {
+                let header = UplRecord::read(&mut input, record_len)?;
+                if header.msg_attr & reply_flags::INACTIVE != 0 {
+                    continue;
+                }
+                let text = reply_text(files, &header.file_name)?;
+                result.messages.push(ReplyMessage { header, text });
+            }
+        }
+
+        if let Some(net) = files.get(&format!("{packet_id}.NET")) {
+            let mut input = Cursor::new(&net[..]);
+            while (input.position() as usize) < net.len() {
+                let header = NetRecord::read(&mut input)?;
+                let text = reply_text(files, &header.file_name)?;
+                result.netmail.push(NetReply { header, text });
+            }
+        }
+
+        if let Some(req) = files.get(&format!("{packet_id}.REQ")) {
+            result.file_requests = req.chunks(13).map(read_string).filter(|name| !name.trim().is_empty()).collect();
+        }
+        Ok(result)
+    }
+
+    pub fn to_files(&self, packet_id: &str) -> Res<HashMap<String, Vec<u8>>> {
+        let packet_id = packet_id.to_ascii_uppercase();
+        let mut files = HashMap::new();
+        let mut upl = Vec::new();
+        self.header.write(&mut upl)?;
+        for msg in &self.messages {
+            msg.header.write(&mut upl)?;
+            files.insert(msg.header.file_name.to_ascii_uppercase(), to_cp437(&msg.text));
+        }
+        files.insert(format!("{packet_id}.UPL"), upl);
+
+        if !self.netmail.is_empty() {
+            let mut net = Vec::new();
+            for msg in &self.netmail {
+                msg.header.write(&mut net)?;
+                files.insert(msg.header.file_name.to_ascii_uppercase(), to_cp437(&msg.text));
+            }
+            files.insert(format!("{packet_id}.NET"), net);
+        }
+
+        if !self.file_requests.is_empty() {
+            let mut req = Vec::new();
+            for name in &self.file_requests {
+                write_fixed(&mut req, name, 13)?;
+            }
+            files.insert(format!("{packet_id}.REQ"), req);
+        }
+        Ok(files)
+    }
+}
+
+fn reply_text(files: &HashMap<String, Vec<u8>>, file_name: &str) -> Res<String> {
+    let Some(bytes) = files.get(&file_name.to_ascii_uppercase()) else {
+        return Err(BlueWaveError::MissingFile(file_name.to_string()).into());
+    };
+    let end = bytes.iter().position(|b| *b == 0x1A).unwrap_or(bytes.len());
+    Ok(from_cp437(&bytes[..end]))
+}
+
+/// Reads a record of the length the file declares and hands back at least the
+/// length this reader knows, so that shorter foreign records are refused and
+/// longer ones are cut down.
+fn read_record(input: &mut impl Read, what: &'static str, len: usize, known: usize) -> Res<Vec<u8>> {
+    if len < known {
+        return Err(BlueWaveError::RecordTooShort(what, len, known).into());
+    }
+    let mut bytes = vec![0u8; len];
+    input.read_exact(&mut bytes).map_err(|_| BlueWaveError::Truncated(what))?;
+    bytes.truncate(known);
+    Ok(bytes)
+}
+
+fn skip(input: &mut impl Read, len: usize) -> Res<()> {
+    let mut bytes = vec![0u8; len];
+    input.read_exact(&mut bytes).map_err(|_| BlueWaveError::Truncated("record"))?;
+    Ok(())
+}
+
+fn read_fixed(input: &mut impl Read, len: usize) -> Res<String> {
+    let mut bytes = vec![0u8; len];
+    input.read_exact(&mut bytes)?;
+    Ok(read_string(&bytes))
+}
+
+fn read_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    from_cp437(&bytes[..end])
+}
+
+/// Fixed fields are NUL padded and always keep room for the terminating NUL.
+fn write_fixed(output: &mut impl Write, text: &str, len: usize) -> Res<()> {
+    let mut bytes = to_cp437(text);
+    bytes.truncate(len - 1);
+    bytes.resize(len, 0);
+    output.write_all(&bytes)?;
+    Ok(())
+}
+
+fn from_cp437(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| {
+            if *byte < 0x80 {
+                *byte as char
+            } else {
+                codepages::tables::CP437_TO_UNICODE[*byte as usize]
+            }
+        })
+        .collect()
+}
+
+fn to_cp437(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|ch| {
+            if (ch as u32) < 0x80 {
+                ch as u8
+            } else {
+                *codepages::tables::UNICODE_TO_CP437.get(&ch).unwrap_or(&b'.')
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mail_packet() -> MailPacket {
+        MailPacket {
+            header: InfHeader {
+                login_name: "JOE CALLER".to_string(),
+                alias_name: "JOE".to_string(),
+                sysop: "SYSOP".to_string(),
+                system_name: "IcyBoard".to_string(),
+                packet_id: "ICY".to_string(),
+                from_to_len: 35,
+                subject_len: 71,
+                ..Default::default()
+            },
+            areas: vec![
+                PacketArea {
+                    info: InfArea {
+                        area_number: "1".to_string(),
+                        echo_tag: "GENERAL".to_string(),
+                        title: "General Chatter".to_string(),
+                        area_flags: area_flags::SCANNING | area_flags::POST,
+                        network_type: 0,
+                    },
+                    messages: vec![
+                        PacketMessage {
+                            header: FtiRecord {
+                                from: "SYSOP".to_string(),
+                                to: "JOE CALLER".to_string(),
+                                subject: "Welcome".to_string(),
+                                date: "09 Aug 26  14:30:05".to_string(),
+                                msg_number: 12,
+                                ..Default::default()
+                            },
+                            text: "Hello Joe\rHave fun.\r".to_string(),
+                        },
+                        PacketMessage {
+                            header: FtiRecord {
+                                from: "JOE CALLER".to_string(),
+                                to: "ALL".to_string(),
+                                subject: "Re: Welcome".to_string(),
+                                msg_number: 13,
+                                reply_to: 12,
+                                ..Default::default()
+                            },
+                            text: "Thanks, Grüße\r".to_string(),
+                        },
+                    ],
+                },
+                PacketArea {
+                    info: InfArea {
+                        area_number: "2".to_string(),
+                        echo_tag: "EMPTY".to_string(),
+                        title: "Nothing New".to_string(),
+                        ..Default::default()
+                    },
+                    messages: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_a_mail_packet_survives_a_round_trip() {
+        let packet = mail_packet();
+        let files = packet.to_files().unwrap();
+        let read = MailPacket::from_files(&files).unwrap();
+
+        assert_eq!(read.header, packet.header);
+        assert_eq!(read.areas.len(), 2);
+        assert_eq!(read.areas[0].info, packet.areas[0].info);
+        assert_eq!(read.areas[0].messages.len(), 2);
+        assert_eq!(read.areas[0].messages[1].text, "Thanks, Grüße\r");
+        assert_eq!(read.areas[0].messages[1].header.reply_to, 12);
+        assert!(read.areas[1].messages.is_empty());
+    }
+
+    #[test]
+    fn test_records_have_the_sizes_the_inf_header_announces() {
+        let files = mail_packet().to_files().unwrap();
+
+        assert_eq!(files.inf.len(), InfHeader::SIZE + 2 * InfArea::SIZE);
+        assert_eq!(files.mix.len(), MixRecord::SIZE);
+        assert_eq!(files.fti.len(), 2 * FtiRecord::SIZE);
+        assert_eq!(files.inf[0], PACKET_VERSION);
+    }
+
+    fn put(bytes: &mut [u8], offset: usize, data: &[u8]) {
+        bytes[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    /// An `ID.INF` header laid out field by field at the offsets of BWAVE.H.
+    fn spec_inf_header() -> Vec<u8> {
+        let mut bytes = vec![0u8; InfHeader::SIZE];
+        bytes[0] = PACKET_VERSION;
+        put(&mut bytes, 1, b"WELCOME.BBS");
+        put(&mut bytes, 14, b"NEWS.TXT");
+        put(&mut bytes, 76, b"JOE CALLER");
+        put(&mut bytes, 119, b"JOE");
+        put(&mut bytes, 162, b"SECRET");
+        bytes[183] = 1;
+        put(&mut bytes, 184, &2u16.to_le_bytes());
+        put(&mut bytes, 186, &280u16.to_le_bytes());
+        put(&mut bytes, 188, &5u16.to_le_bytes());
+        put(&mut bytes, 192, b"SYSOP");
+        put(&mut bytes, 235, b"IcyBoard");
+        bytes[300] = 10;
+        put(&mut bytes, 973, &(InfHeader::SIZE as u16).to_le_bytes());
+        put(&mut bytes, 975, &(InfArea::SIZE as u16).to_le_bytes());
+        put(&mut bytes, 977, &(MixRecord::SIZE as u16).to_le_bytes());
+        put(&mut bytes, 979, &(FtiRecord::SIZE as u16).to_le_bytes());
+        bytes[981] = 1;
+        bytes[982] = 35;
+        bytes[983] = 71;
+        put(&mut bytes, 984, b"ICY");
+        bytes
+    }
+
+    #[test]
+    fn test_the_inf_header_follows_the_spec_layout() {
+        let bytes = spec_inf_header();
+        let (header, area_len, mix_len, fti_len) = InfHeader::read(&mut Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(header.reader_files, vec!["WELCOME.BBS", "NEWS.TXT"]);
+        assert_eq!((header.login_name.as_str(), header.alias_name.as_str()), ("JOE CALLER", "JOE"));
+        assert_eq!((header.password.as_str(), header.password_type), ("SECRET", 1));
+        assert_eq!((header.zone, header.net, header.node, header.point), (2, 280, 5, 0));
+        assert_eq!((header.sysop.as_str(), header.system_name.as_str()), ("SYSOP", "IcyBoard"));
+        assert_eq!(header.max_freqs, 10);
+        assert_eq!((area_len, mix_len, fti_len), (InfArea::SIZE, MixRecord::SIZE, FtiRecord::SIZE));
+        assert_eq!((header.from_to_len, header.subject_len, header.packet_id.as_str()), (35, 71, "ICY"));
+
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn test_the_upl_header_is_read_with_the_length_it_announces() {
+        // A reader with a shorter header than the board writes.
+        let mut bytes = vec![0u8; 254];
+        put(&mut bytes, 10, b"2.30");
+        put(&mut bytes, 32, b"Blue Wave/DOS");
+        put(&mut bytes, 112, &254u16.to_le_bytes());
+        put(&mut bytes, 114, &(UplRecord::SIZE as u16).to_le_bytes());
+        put(&mut bytes, 116, b"JOE CALLER");
+        put(&mut bytes, 159, b"JOE");
+        put(&mut bytes, 202, b"BWAVE");
+        bytes.extend_from_slice(b"next");
+
+        let mut input = Cursor::new(&bytes);
+        let (header, record_len) = UplHeader::read(&mut input).unwrap();
+        assert_eq!(header.reader_version, "2.30");
+        assert_eq!(header.reader_name, "Blue Wave/DOS");
+        assert_eq!((header.login_name.as_str(), header.alias_name.as_str()), ("JOE CALLER", "JOE"));
+        assert_eq!(header.reader_tear, "BWAVE");
+        assert_eq!(record_len, UplRecord::SIZE);
+        assert_eq!(input.position(), 254);
+
+        let mut written = Vec::new();
+        header.write(&mut written).unwrap();
+        assert_eq!(&written[..112], &bytes[..112]);
+        assert_eq!(&written[112..114], &(UplHeader::SIZE as u16).to_le_bytes());
+        assert_eq!(&written[114..UplHeader::KNOWN], &bytes[114..UplHeader::KNOWN]);
+    }
+
+    #[test]
+    fn test_message_offsets_point_at_the_leading_space() {
+        let files = mail_packet().to_files().unwrap();
+        let read = MailPacket::from_files(&files).unwrap();
+        let second = &read.areas[0].messages[1].header;
+
+        assert_eq!(files.dat[second.msg_offset as usize], b' ');
+        assert_eq!(read.areas[0].messages[0].header.msg_offset, 0);
+    }
+
+    #[test]
+    fn test_a_reply_packet_survives_a_round_trip() {
+        let packet = ReplyPacket {
+            header: UplHeader {
+                reader_name: "Blue Wave/DOS".to_string(),
+                reader_tear: "BWAVE".to_string(),
+                login_name: "JOE CALLER".to_string(),
+                ..Default::default()
+            },
+            messages: vec![ReplyMessage {
+                header: UplRecord {
+                    from: "JOE CALLER".to_string(),
+                    to: "SYSOP".to_string(),
+                    subject: "Re: Welcome".to_string(),
+                    msg_attr: reply_flags::PRIVATE | reply_flags::IS_REPLY,
+                    reply_to: 12,
+                    unix_date: 1_786_278_605,
+                    file_name: "ICY.001".to_string(),
+                    echo_tag: "GENERAL".to_string(),
+                    ..Default::default()
+                },
+                text: "Thanks!\r".to_string(),
+            }],
+            netmail: vec![NetReply {
+                header: NetRecord {
+                    from: "JOE CALLER".to_string(),
+                    to: "Somebody".to_string(),
+                    subject: "Hi".to_string(),
+                    dest_zone: 2,
+                    dest_net: 280,
+                    dest_node: 1,
+                    file_name: "ICY.002".to_string(),
+                    ..Default::default()
+                },
+                text: "Netmail\r".to_string(),
+            }],
+            file_requests: vec!["FILES.ZIP".to_string(), "*.TXT".to_string()],
+        };
+        let files = packet.to_files("icy").unwrap();
+        assert_eq!(files["ICY.UPL"].len(), UplHeader::SIZE + UplRecord::SIZE);
+
+        let read = ReplyPacket::from_files("icy", &files).unwrap();
+        assert_eq!(read, packet);
+    }
+
+    #[test]
+    fn test_inactive_replies_are_skipped() {
+        let mut packet = ReplyPacket::default();
+        packet.messages.push(ReplyMessage {
+            header: UplRecord {
+                msg_attr: reply_flags::INACTIVE,
+                file_name: "ICY.001".to_string(),
+                ..Default::default()
+            },
+            text: "deleted".to_string(),
+        });
+        let read = ReplyPacket::from_files("ICY", &packet.to_files("ICY").unwrap()).unwrap();
+        assert!(read.messages.is_empty());
+    }
+
+    #[test]
+    fn test_a_missing_text_file_is_an_error() {
+        let mut files = ReplyPacket {
+            messages: vec![ReplyMessage {
+                header: UplRecord {
+                    file_name: "ICY.001".to_string(),
+                    ..Default::default()
+                },
+                text: String::new(),
+            }],
+            ..Default::default()
+        }
+        .to_files("ICY")
+        .unwrap();
+        files.remove("ICY.001");
+        assert!(ReplyPacket::from_files("ICY", &files).is_err());
+    }
+}
@@ -4,7 +4,7 @@ use std::{
     str::FromStr,
 };
 
-use crate::Res;
+use crate::{Res, vm::Permissions};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_with::{DisplayFromStr, serde_as};
 
@@ -257,6 +257,9 @@ pub enum CommandType {
     /// SYSOP '16' command
     DirCommand,
 
+    /// SYSOP '17' command
+    PendingUploads,
+
     // '@'
     ReadEmail,
 
@@ -363,6 +366,7 @@ impl Display for CommandType {
             CommandType::HeaderScan => write!(f, "HeaderScan"),
             CommandType::LogoffNode => write!(f, "LogoffNode"),
             CommandType::DirCommand => write!(f, "DirCommand"),
+            CommandType::PendingUploads => write!(f, "PendingUploads"),
             CommandType::ReadEmail => write!(f, "ReadEmail"),
             CommandType::WriteEmail => write!(f, "WriteEmail"),
             CommandType::RunPPE => write!(f, "RunPPE"),
@@ -452,6 +456,7 @@ impl Display for CommandType {
             CommandType::HeaderScan => write!(f, "(5)\tHeaderScan"),
             CommandType::LogoffNode => write!(f, "(12)\tLogoffNode"),
             CommandType::DirCommand => write!(f, "(16)\tDirCommand"),
+            CommandType::PendingUploads => write!(f, "(17)\tPendingUploads"),
             CommandType::ReadEmail => write!(f, "(@)\tReadEmail"),
             CommandType::WriteEmail => write!(f, "(@W)\tWriteEmail"),
             CommandType::RunPPE => write!(f, "(PPE)\tRunPPE"),
@@ -542,6 +547,7 @@ impl FromStr for CommandType {
             "headerscan" => Ok(CommandType::HeaderScan),
             "logoffnode" => Ok(CommandType::LogoffNode),
             "dircommand" => Ok(CommandType::DirCommand),
+            "pendinguploads" => Ok(CommandType::PendingUploads),
             "reademail" => Ok(CommandType::ReadEmail),
             "writeemail" => Ok(CommandType::WriteEmail),
             "runppe" => Ok(CommandType::RunPPE),
@@ -635,6 +641,7 @@ impl CommandType {
             CommandType::HeaderScan,
             CommandType::LogoffNode,
             CommandType::DirCommand,
+            CommandType::PendingUploads,
             CommandType::ReadEmail,
             CommandType::WriteEmail,
             CommandType::RunPPE,
@@ -705,6 +712,7 @@ impl CommandType {
             CommandType::HeaderScan => "hlp5",
             CommandType::LogoffNode => "hlp12",
             CommandType::DirCommand => "hlp16",
+            CommandType::PendingUploads => "hlp17",
             CommandType::ReadEmail => "hlp@",
             CommandType::WriteEmail => "hlp@w",
             CommandType::RunPPE => "hlpppe",
@@ -872,6 +880,10 @@ pub struct CommandAction {
     #[serde(default)]
     #[serde(skip_serializing_if = "ActionTrigger::is_default")]
     pub trigger: ActionTrigger,
+
+    /// What the PPE a `RunPPE` action starts may do. Without it the PPE gets what it asks for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -954,6 +966,7 @@ impl PCBoardRecordImporter<Command> for CommandList {
                 command_type,
                 parameter,
                 trigger: ActionTrigger::Activation,
+                permissions: None,
             }],
             security: SecurityExpression::from_req_security(security),
         })
@@ -9,6 +9,7 @@ use crate::{
     compiler::user_data::{UserData, UserDataMemberRegistry, UserDataValue},
     executable::{VariableType, VariableValue},
     parser::load_with_encoding,
+    vm::Permissions,
 };
 
 use super::{IcyBoardSerializer, security_expr::SecurityExpression};
@@ -129,6 +130,10 @@ pub struct Door {
 
     #[serde(default)]
     pub drop_file: DropFile,
+
+    /// What a PPE run as this door may do. Without it the PPE gets what it asks for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
 }
 impl Door {
     pub async fn create_drop_file(&self, state: &super::state::IcyBoardState, path: &std::path::Path, door_number: usize) -> Res<()> {
@@ -256,6 +261,7 @@ impl DoorList {
                 } else {
                     DropFile::None
                 },
+                permissions: None,
             };
             result.doors.push(door);
         }
@@ -12,10 +12,11 @@ use crate::{
     tables::export_cp437_string,
 };
 use async_trait::async_trait;
+use dizbase::file_base_scanner::advertise::Advertisement;
 use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
 
-use super::{IcyBoardError, IcyBoardSerializer, PCBoardRecordImporter, is_false, security_expr::SecurityExpression, user_base::Password};
+use super::{IcyBoardError, IcyBoardSerializer, PCBoardRecordImporter, is_false, path_is_empty, security_expr::SecurityExpression, user_base::Password};
 
 #[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq)]
 pub enum SortOrder {
@@ -54,6 +55,32 @@ pub enum SortDirection {
     Descending,
 }
 
+/// When the board's advertisement goes into the ZIP archives of an area.
+#[derive(Default, Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Advertise {
+    #[default]
+    #[serde(rename = "off")]
+    Off,
+
+    /// The stored archive is changed once, right after the upload was checked.
+    #[serde(rename = "upload")]
+    Upload,
+
+    /// Every download gets a copy with the ad, the stored archive stays as uploaded.
+    #[serde(rename = "download")]
+    Download,
+}
+
+impl Advertise {
+    pub fn iter() -> impl Iterator<Item = Advertise> {
+        [Advertise::Off, Advertise::Upload, Advertise::Download].iter().copied()
+    }
+
+    pub fn is_off(&self) -> bool {
+        *self == Advertise::Off
+    }
+}
+
 /// A survey is a question and answer pair.
 /// `PCBoard` calles them "Questionnairies" but we call them surveys.
 #[serde_as]
@@ -88,6 +115,46 @@ pub struct FileDirectory {
     #[serde(skip_serializing_if = "SecurityExpression::is_empty")]
     #[serde_as(as = "DisplayFromStr")]
     pub download_security: SecurityExpression,
+
+    /// The file echo a fidonet technology network distributes into this area,
+    /// empty when the area is local.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub ftn_area_tag: String,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Advertise::is_off")]
+    pub advertise: Advertise,
+
+    /// The file added to the archives, usually a short text or ANSI about the board.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "path_is_empty")]
+    pub ad_file: PathBuf,
+
+    /// Set as the archive comment, which most unzippers show on opening.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub ad_comment: String,
+
+    /// Added below the `FILE_ID.DIZ` of an archive that carries one.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub ad_file_id_line: String,
+}
+
+impl FileDirectory {
+    /// What goes into the archives of this area at the given moment, if anything.
+    pub fn advertisement(&self, at: Advertise) -> Option<Advertisement> {
+        if self.advertise != at || at.is_off() {
+            return None;
+        }
+        let ad = Advertisement {
+            file: self.ad_file.clone(),
+            comment: self.ad_comment.clone(),
+            file_id_line: self.ad_file_id_line.clone(),
+        };
+        if ad.is_empty() { None } else { Some(ad) }
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq)]
@@ -183,6 +250,11 @@ impl PCBoardRecordImporter<FileDirectory> for DirectoryList {
             is_free: false,
             list_security: SecurityExpression::default(),
             download_security: SecurityExpression::default(),
+            ftn_area_tag: String::new(),
+            advertise: Advertise::Off,
+            ad_file: PathBuf::new(),
+            ad_comment: String::new(),
+            ad_file_id_line: String::new(),
         })
     }
 }
@@ -0,0 +1,290 @@
+//! File requests, FTS-0006's FREQ. A link asks for a file by name, by pattern
+//! or by one of the magic names the sysop set up, and gets it in the session
+//! that carried the request. Only the file bases of the directories listed in
+//! `[freq]` are searched, so a request cannot reach anything the sysop did not
+//! publish, nor an upload still waiting for the sysop.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use dizbase::file_base::{
+    FileBase,
+    pattern::{MatchOptions, Pattern},
+};
+use icy_net::binkp::{FileRequest, FileRequests};
+use serde::{Deserialize, Serialize};
+
+use super::FtnLink;
+
+/// A name that stands for a fixed file or directory, like `FILES` for the
+/// board's file list, instead of being looked up in the areas.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct FreqMagic {
+    pub name: String,
+    /// A directory answers with every file in it.
+    pub path: PathBuf,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct FreqConfig {
+    /// The directories whose files can be requested. Nothing is, as long as
+    /// this and `magic` are both empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub areas: Vec<PathBuf>,
+
+    /// How many files a session may take, zero for no limit. A link can be
+    /// given limits of its own.
+    pub max_files: u32,
+
+    /// How many bytes a session may take, zero for no limit.
+    pub max_bytes: u64,
+
+    // Toml demands that the tables come last.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub magic: Vec<FreqMagic>,
+}
+
+impl FreqConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.areas.is_empty() || !self.magic.is_empty()
+    }
+}
+
+/// Answers the requests of one session, keeping count of what it handed out
+/// so far against the limits of the link that asked.
+pub struct FreqResolver {
+    config: FreqConfig,
+    metadata_paths: HashMap<PathBuf, PathBuf>,
+    max_files: u32,
+    max_bytes: u64,
+    sent: Vec<PathBuf>,
+    bytes: u64,
+}
+
+impl FreqResolver {
+    pub fn new(config: &FreqConfig, link: &FtnLink) -> Self {
+        Self {
+            config: config.clone(),
+            metadata_paths: HashMap::new(),
+            max_files: if link.freq_max_files > 0 { link.freq_max_files } else { config.max_files },
+            max_bytes: if link.freq_max_bytes > 0 { link.freq_max_bytes } else { config.max_bytes },
+            sent: Vec::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Where the file bases of the areas keep their data, for those that don't
+    /// use the `dir` next to the files.
+    pub fn with_metadata_paths(mut self, metadata_paths: HashMap<PathBuf, PathBuf>) -> Self {
+        self.metadata_paths = metadata_paths;
+        self
+    }
+
+    /// Everything that was handed out, for the log.
+    pub fn sent(&self) -> &[PathBuf] {
+        &self.sent
+    }
+
+    fn candidates(&self, request: &FileRequest) -> Vec<PathBuf> {
+        if let Some(magic) = self.config.magic.iter().find(|magic| magic.name.eq_ignore_ascii_case(&request.name)) {
+            if !magic.password.is_empty() && !magic.password.eq_ignore_ascii_case(&request.password) {
+                log::warn!("freq: wrong password for {}", magic.name);
+                return Vec::new();
+            }
+            if magic.path.is_dir() {
+                return files_in(&magic.path);
+            }
+            return vec![magic.path.clone()];
+        }
+
+        // A request names a file, never where it is.
+        if request.name.starts_with('.') || request.name.contains(['/', '\\', ':']) {
+            return Vec::new();
+        }
+        let Ok(pattern) = Pattern::new(&request.name) else {
+            return Vec::new();
+        };
+        self.config.areas.iter().flat_map(|area| self.listed_in(area, &pattern)).collect()
+    }
+
+    /// The files the base of `area` lists that match, held uploads left out.
+    fn listed_in(&self, area: &Path, pattern: &Pattern) -> Vec<PathBuf> {
+        let metadata_path = self.metadata_paths.get(area).cloned().unwrap_or_else(|| area.join("dir"));
+        let base = match FileBase::open(area, metadata_path) {
+            Ok(base) => base,
+            Err(err) => {
+                log::error!("freq: can't open the file base of {}: {err}", area.display());
+                return Vec::new();
+            }
+        };
+        let options = MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        let mut files: Vec<PathBuf> = base
+            .iter()
+            .filter(|header| !header.is_held() && pattern.matches_with(&header.name, &options))
+            .map(|header| base.full_path(header))
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+impl FileRequests for FreqResolver {
+    fn resolve(&mut self, request: &FileRequest) -> Vec<PathBuf> {
+        let mut answers = Vec::new();
+        for path in self.candidates(request) {
+            if self.sent.contains(&path) {
+                continue;
+            }
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            if let Some(newer_than) = request.newer_than
+                && modified_secs(&metadata) <= newer_than
+            {
+                continue;
+            }
+            if self.max_files > 0 && self.sent.len() >= self.max_files as usize {
+                log::warn!("freq: {} file(s) per session reached, {} not sent", self.max_files, request.name);
+                break;
+            }
+            if self.max_bytes > 0 && self.bytes + metadata.len() > self.max_bytes {
+                log::warn!("freq: {} would pass {} bytes per session, not sent", path.display(), self.max_bytes);
+                continue;
+            }
+            self.bytes += metadata.len();
+            self.sent.push(path.clone());
+            answers.push(path);
+        }
+        if answers.is_empty() {
+            log::info!("freq: nothing found for {}", request.name);
+        }
+        answers
+    }
+}
+
+/// Everything in a magic directory, which is published as it is.
+fn files_in(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn area(root: &TempDir, files: &[(&str, &[u8])]) -> PathBuf {
+        let dir = root.path().join("files");
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn request(line: &str) -> FileRequest {
+        FileRequest::parse(line).unwrap()
+    }
+
+    #[test]
+    fn test_a_pattern_is_matched_in_the_public_areas_ignoring_case() {
+        let root = TempDir::new().unwrap();
+        let config = FreqConfig {
+            areas: vec![area(&root, &[("DOOM.ZIP", b"doom"), ("QUAKE.ZIP", b"quake"), ("README.TXT", b"read")])],
+            ..Default::default()
+        };
+        let mut resolver = FreqResolver::new(&config, &FtnLink::default());
+
+        let names: Vec<String> = resolver
+            .resolve(&request("*.zip"))
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["DOOM.ZIP", "QUAKE.ZIP"]);
+        // What went out once is not sent again in the same session.
+        assert!(resolver.resolve(&request("DOOM.ZIP")).is_empty());
+        assert!(resolver.resolve(&request("../files/README.TXT")).is_empty());
+    }
+
+    #[test]
+    fn test_a_held_upload_can_not_be_requested() {
+        let root = TempDir::new().unwrap();
+        let dir = area(&root, &[("DOOM.ZIP", b"doom")]);
+        let mut base = FileBase::open(&dir, dir.join("dir")).unwrap();
+        fs::write(dir.join("HELD.ZIP"), b"held").unwrap();
+        base.hold_file(&dir.join("HELD.ZIP"), Vec::new()).unwrap();
+        drop(base);
+
+        let config = FreqConfig {
+            areas: vec![dir.clone()],
+            ..Default::default()
+        };
+        let mut resolver = FreqResolver::new(&config, &FtnLink::default());
+        assert_eq!(resolver.resolve(&request("*.ZIP")), vec![dir.join("DOOM.ZIP")]);
+        assert!(resolver.resolve(&request("HELD.ZIP")).is_empty());
+    }
+
+    #[test]
+    fn test_a_magic_name_wants_its_password() {
+        let root = TempDir::new().unwrap();
+        let dir = area(&root, &[("ALLFILES.TXT", b"list")]);
+        let config = FreqConfig {
+            magic: vec![FreqMagic {
+                name: "FILES".to_string(),
+                path: dir.join("ALLFILES.TXT"),
+                password: "secret".to_string(),
+            }],
+            ..Default::default()
+        };
+        let mut resolver = FreqResolver::new(&config, &FtnLink::default());
+
+        assert!(resolver.resolve(&request("FILES")).is_empty());
+        assert_eq!(resolver.resolve(&request("files !SECRET")), vec![dir.join("ALLFILES.TXT")]);
+    }
+
+    #[test]
+    fn test_the_limits_of_the_link_come_before_those_of_the_board() {
+        let root = TempDir::new().unwrap();
+        let config = FreqConfig {
+            areas: vec![area(&root, &[("A.ZIP", b"aaaa"), ("B.ZIP", b"bbbb"), ("C.ZIP", b"cccc")])],
+            max_files: 1,
+            ..Default::default()
+        };
+        assert_eq!(FreqResolver::new(&config, &FtnLink::default()).resolve(&request("*")).len(), 1);
+
+        let link = FtnLink {
+            freq_max_files: 5,
+            freq_max_bytes: 8,
+            ..Default::default()
+        };
+        assert_eq!(FreqResolver::new(&config, &link).resolve(&request("*")).len(), 2);
+    }
+}
@@ -4,11 +4,14 @@ use jamjam::util::echomail::EchomailAddress;
 use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
 
-use super::IcyBoardSerializer;
+use super::{IcyBoardSerializer, is_null_32, is_null_64};
+use freq::FreqConfig;
 
 pub mod bundle;
+pub mod freq;
 pub mod packet;
 pub mod queue;
+pub mod tic;
 pub mod toss;
 
 /// The port fidonet technology networks reserved for binkp.
@@ -69,6 +72,24 @@ pub struct FtnLink {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub areas: Vec<String>,
+
+    /// The file echo tags this link carries. A file arriving with a `.TIC` for
+    /// one of them is passed on to every link that asked, along with a new TIC.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub file_areas: Vec<String>,
+
+    /// How many files this link may request in one session, zero for the
+    /// limit in `[freq]`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_null_32")]
+    pub freq_max_files: u32,
+
+    /// How many bytes this link may request in one session, zero for the
+    /// limit in `[freq]`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_null_64")]
+    pub freq_max_bytes: u64,
 }
 
 impl FtnLink {
@@ -86,6 +107,10 @@ impl FtnLink {
     pub fn carries(&self, tag: &str) -> bool {
         self.areas.iter().any(|area| area.eq_ignore_ascii_case(tag))
     }
+
+    pub fn carries_file_echo(&self, tag: &str) -> bool {
+        self.file_areas.iter().any(|area| area.eq_ignore_ascii_case(tag))
+    }
 }
 
 impl Default for FtnLink {
@@ -98,6 +123,9 @@ impl Default for FtnLink {
             password: String::new(),
             poll_minutes: 0,
             areas: Vec::new(),
+            file_areas: Vec::new(),
+            freq_max_files: 0,
+            freq_max_bytes: 0,
         }
     }
 }
@@ -215,6 +243,10 @@ pub struct FtnConfig {
     #[serde(default)]
     pub options: FtnOptions,
 
+    /// What links may request from this board.
+    #[serde(default)]
+    pub freq: FreqConfig,
+
     #[serde(rename = "aka", default)]
     pub akas: Vec<FtnAka>,
 
@@ -278,6 +310,7 @@ impl Default for FtnConfig {
             new_areas: Self::default_new_areas(),
             origin: String::new(),
             options: FtnOptions::default(),
+            freq: FreqConfig::default(),
         }
     }
 }
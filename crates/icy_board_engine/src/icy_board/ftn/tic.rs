@@ -0,0 +1,562 @@
+//! File echos. A file distributed through the network travels next to a `.TIC`
+//! file (FSC-0087) that names the echo, describes the file and carries a
+//! password, a crc and the trail of systems it has passed. The processor
+//! checks all that against the link it came from, puts the file into the
+//! directory carrying the echo and hands it on to the links that asked for it.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::NaiveDateTime;
+use dizbase::{
+    file_base::{
+        FileBase,
+        metadata::{MetadataHeader, MetadataType},
+    },
+    file_base_scanner::scan_file,
+};
+use icy_net::crc::get_crc32;
+use jamjam::util::echomail::EchomailAddress;
+use std::fmt::Write as _;
+
+use super::{FtnAka, FtnConfig, FtnLink, toss::product};
+
+type Res<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// One file area of the board as the processor sees it: the echo tag, the
+/// directory the files go into and the metadata path of its file base.
+pub type FileAreaMap = [(String, PathBuf, PathBuf)];
+
+/// The lines of a `.TIC` file the processor acts on. Anything else is kept as
+/// it came and passed on unchanged.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tic {
+    pub area: String,
+    pub area_desc: String,
+    pub origin: String,
+    pub from: String,
+    pub to: String,
+    pub file: String,
+    pub replaces: String,
+    pub desc: String,
+    /// The long description, one entry per line. Wins over `desc` when present.
+    pub ldesc: Vec<String>,
+    pub size: Option<u64>,
+    pub crc: Option<u32>,
+    pub path: Vec<String>,
+    pub seenby: Vec<String>,
+    pub pw: String,
+    pub other: Vec<String>,
+}
+
+impl Tic {
+    pub fn parse(text: &str) -> Self {
+        let mut tic = Tic::default();
+        for line in text.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (keyword, value) = line.split_once([' ', '\t']).unwrap_or((line, ""));
+            let value = value.trim();
+            match keyword.to_ascii_lowercase().as_str() {
+                "area" => tic.area = value.to_string(),
+                "areadesc" => tic.area_desc = value.to_string(),
+                "origin" => tic.origin = value.to_string(),
+                "from" => tic.from = value.to_string(),
+                "to" => tic.to = value.to_string(),
+                "file" => tic.file = value.to_string(),
+                "replaces" => tic.replaces = value.to_string(),
+                "desc" => tic.desc = value.to_string(),
+                "ldesc" => tic.ldesc.push(value.to_string()),
+                "size" => tic.size = value.parse().ok(),
+                "crc" => tic.crc = u32::from_str_radix(value, 16).ok(),
+                "path" => tic.path.push(value.to_string()),
+                "seenby" => tic.seenby.push(value.to_string()),
+                "pw" => tic.pw = value.to_string(),
+                // Written fresh by whoever sends the file on.
+                "created" => {}
+                _ => tic.other.push(line.to_string()),
+            }
+        }
+        tic
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut line = |keyword: &str, value: &str| {
+            if !value.is_empty() {
+                let _ = write!(text, "{keyword} {value}\r\n");
+            }
+        };
+        line("Area", &self.area);
+        line("Areadesc", &self.area_desc);
+        line("Origin", &self.origin);
+        line("From", &self.from);
+        line("To", &self.to);
+        line("File", &self.file);
+        line("Replaces", &self.replaces);
+        line("Desc", &self.desc);
+        for ldesc in &self.ldesc {
+            line("Ldesc", ldesc);
+        }
+        if let Some(size) = self.size {
+            line("Size", &size.to_string());
+        }
+        if let Some(crc) = self.crc {
+            line("Crc", &format!("{crc:08X}"));
+        }
+        line("Created by", &product());
+        for other in &self.other {
+            let _ = write!(text, "{other}\r\n");
+        }
+        for path in &self.path {
+            let _ = write!(text, "Path {path}\r\n");
+        }
+        for seenby in &self.seenby {
+            let _ = write!(text, "Seenby {seenby}\r\n");
+        }
+        let _ = write!(text, "Pw {}\r\n", self.pw);
+        text
+    }
+
+    pub fn description(&self) -> String {
+        if self.ldesc.is_empty() { self.desc.clone() } else { self.ldesc.join("\n") }
+    }
+
+    /// Whether the trail already names a system, in which case it has the file.
+    fn seen_by(&self, address: &EchomailAddress) -> bool {
+        self.seenby.iter().chain(self.path.iter()).any(|entry| {
+            entry
+                .split_whitespace()
+                .next()
+                .and_then(|first| EchomailAddress::parse(strip_domain(first)))
+                .is_some_and(|seen| seen == *address)
+        })
+    }
+}
+
+/// A 5D address names the network after an '@', which the address itself does not carry.
+fn strip_domain(address: &str) -> &str {
+    address.split('@').next().unwrap_or(address)
+}
+
+/// What one run over the inbound left behind.
+#[derive(Debug, Default)]
+pub struct TicReport {
+    pub imported: usize,
+    pub replaced: usize,
+
+    /// Files handed on to downlinks, counted once per link.
+    pub forwarded: usize,
+
+    /// Tics whose file has not arrived yet. They wait for the next run.
+    pub waiting: usize,
+
+    /// Echo tags no file area carries, and how many files came for each.
+    pub unknown: BTreeMap<String, usize>,
+
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Places every file announced by a tic in the inbound. A tic that fails a
+/// check stays where it is, with its file, for the sysop to look at.
+pub fn process_tics(config: &FtnConfig, areas: &FileAreaMap, now: &NaiveDateTime) -> Res<TicReport> {
+    let mut report = TicReport::default();
+    if !config.options.process_in || !config.inbound.is_dir() {
+        return Ok(report);
+    }
+    let mut tics = Vec::new();
+    for entry in fs::read_dir(&config.inbound)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("tic")) {
+            tics.push(path);
+        }
+    }
+    tics.sort();
+
+    for tic_path in tics {
+        match process_tic(config, areas, &tic_path, now, &mut report) {
+            Ok(true) => fs::remove_file(&tic_path)?,
+            Ok(false) => {}
+            Err(err) => report.failed.push((tic_path, err.to_string())),
+        }
+    }
+    Ok(report)
+}
+
+/// A name without a directory in it. Dots are fine as long as they aren't all
+/// there is, `v1..2.zip` is a file while `..` is the parent directory.
+fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    !name.contains(['/', '\\']) && matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+/// Answers whether the tic is done with and can go.
+fn process_tic(config: &FtnConfig, areas: &FileAreaMap, tic_path: &Path, now: &NaiveDateTime, report: &mut TicReport) -> Res<bool> {
+    let tic = Tic::parse(&String::from_utf8_lossy(&fs::read(tic_path)?));
+    let Some(from) = EchomailAddress::parse(strip_domain(&tic.from)) else {
+        return Err(format!("'{}' is not an address", tic.from).into());
+    };
+    let Some(sender) = config.links.iter().find(|link| link.address == from) else {
+        return Err(format!("{from} is not a configured link").into());
+    };
+    if !sender.password.is_empty() && !sender.password.eq_ignore_ascii_case(&tic.pw) {
+        return Err(format!("wrong password from {from}").into());
+    }
+    // The name is all a tic gets to say about where the file goes.
+    if !is_file_name(&tic.file) {
+        return Err(format!("'{}' is not a file name", tic.file).into());
+    }
+    let Some(file) = find_file(&config.inbound, &tic.file)? else {
+        report.waiting += 1;
+        return Ok(false);
+    };
+    let data = fs::read(&file)?;
+    if let Some(size) = tic.size
+        && size != data.len() as u64
+    {
+        return Err(format!("{} is {} bytes, the tic says {}", tic.file, data.len(), size).into());
+    }
+    if let Some(crc) = tic.crc
+        && crc != get_crc32(&data)
+    {
+        return Err(format!("{} does not match the crc {:08X}", tic.file, crc).into());
+    }
+
+    let area = areas.iter().find(|(tag, _, _)| tag.eq_ignore_ascii_case(&tic.area));
+    match area {
+        Some((_, path, metadata_path)) => {
+            import(&tic, &file, path, metadata_path, report)?;
+            report.imported += 1;
+        }
+        None if config.options.pass_thru && config.links.iter().any(|link| link.carries_file_echo(&tic.area)) => {}
+        None => {
+            *report.unknown.entry(tic.area.to_uppercase()).or_default() += 1;
+            return Ok(false);
+        }
+    }
+    report.forwarded += forward(config, &tic, &file, sender, now)?;
+    fs::remove_file(&file)?;
+    Ok(true)
+}
+
+/// Links upload under whatever case their system prefers, and the tic does not always agree.
+fn find_file(directory: &Path, name: &str) -> Res<Option<PathBuf>> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path
+                .file_name()
+                .and_then(|file| file.to_str())
+                .is_some_and(|file| file.eq_ignore_ascii_case(name))
+        {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+fn import(tic: &Tic, file: &Path, directory: &Path, metadata_path: &Path, report: &mut TicReport) -> Res<()> {
+    fs::create_dir_all(directory)?;
+    let mut base = FileBase::open(directory, metadata_path)?;
+    // A file sent again under the same name is a new version of it.
+    for name in [&tic.replaces, &tic.file] {
+        if name.is_empty() {
+            continue;
+        }
+        let Some(old) = base
+            .iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| base.full_path(header))
+        else {
+            continue;
+        };
+        base.remove_file(&old)?;
+        if old.exists() {
+            fs::remove_file(&old)?;
+        }
+        report.replaced += 1;
+    }
+
+    let dest = directory.join(&tic.file);
+    fs::copy(file, &dest)?;
+    let mut metadata = scan_file(&dest)?;
+    let description = tic.description();
+    if !description.is_empty() {
+        metadata.retain(|header| header.get_type() != MetadataType::FileID);
+        metadata.push(MetadataHeader::new(MetadataType::FileID, description.into_bytes()));
+    }
+    let uploader = if tic.origin.is_empty() { &tic.from } else { &tic.origin };
+    metadata.push(MetadataHeader::new(MetadataType::Uploader, uploader.as_bytes().to_vec()));
+    base.add_file(&dest, metadata)?;
+    Ok(())
+}
+
+/// Puts the file and a tic of its own into the outbound of every link that
+/// carries the echo and has not had it yet. Returns how many links got it.
+fn forward(config: &FtnConfig, tic: &Tic, file: &Path, sender: &FtnLink, now: &NaiveDateTime) -> Res<usize> {
+    let takers: Vec<&FtnLink> = config
+        .links
+        .iter()
+        .filter(|link| link.carries_file_echo(&tic.area) && link.address != sender.address && !tic.seen_by(&link.address))
+        .collect();
+    let Some(first) = takers.first() else {
+        return Ok(0);
+    };
+    let Some(aka) = config.aka_for(first) else {
+        return Ok(0);
+    };
+
+    let mut seenby = tic.seenby.clone();
+    for address in std::iter::once(aka.address).chain(takers.iter().map(|link| link.address)) {
+        if !tic.seen_by(&address) {
+            seenby.push(address.to_string());
+        }
+    }
+    let mut path = tic.path.clone();
+    path.push(path_line(aka, now));
+
+    for link in &takers {
+        let directory = config.outbound_for(link);
+        fs::create_dir_all(&directory)?;
+        fs::copy(file, directory.join(&tic.file))?;
+        let outgoing = Tic {
+            from: config.aka_for(link).unwrap_or(aka).to_5d(),
+            to: link.to_5d(),
+            pw: link.password.clone(),
+            path: path.clone(),
+            seenby: seenby.clone(),
+            origin: if tic.origin.is_empty() { tic.from.clone() } else { tic.origin.clone() },
+            ..tic.clone()
+        };
+        fs::write(next_tic_name(&directory, now), outgoing.to_text())?;
+    }
+    Ok(takers.len())
+}
+
+/// FSC-0087 wants the address, the unix time and the time written out.
+fn path_line(aka: &FtnAka, now: &NaiveDateTime) -> String {
+    format!("{} {} {} UTC", aka.address, now.and_utc().timestamp(), now.format("%a %b %d %H:%M:%S %Y"))
+}
+
+/// Tics are named after the moment they were written, like packets, and
+/// counted on when several are written in the same second.
+fn next_tic_name(directory: &Path, now: &NaiveDateTime) -> PathBuf {
+    let mut stamp = now.and_utc().timestamp() as u32;
+    loop {
+        let candidate = directory.join(format!("{stamp:08x}.tic"));
+        if !candidate.exists() {
+            return candidate;
+        }
+        stamp = stamp.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn address(text: &str) -> EchomailAddress {
+        EchomailAddress::parse(text).unwrap()
+    }
+
+    fn link(text: &str, password: &str, file_areas: &[&str]) -> FtnLink {
+        FtnLink {
+            address: address(text),
+            domain: "fsxnet".to_string(),
+            password: password.to_string(),
+            file_areas: file_areas.iter().map(|area| area.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn config(directory: &Path) -> FtnConfig {
+        FtnConfig {
+            inbound: directory.join("inbound"),
+            outbound: directory.join("outbound"),
+            akas: vec![FtnAka {
+                address: address("21:1/100"),
+                domain: "fsxnet".to_string(),
+            }],
+            links: vec![link("21:1/1", "secret", &["FSX_NFO"]), link("21:1/101", "down", &["FSX_NFO"])],
+            ..Default::default()
+        }
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn areas(directory: &Path) -> Vec<(String, PathBuf, PathBuf)> {
+        let files = directory.join("files/nfo");
+        vec![("FSX_NFO".to_string(), files.clone(), files.join("dir"))]
+    }
+
+    fn arrive(config: &FtnConfig, name: &str, contents: &[u8], password: &str) {
+        fs::create_dir_all(&config.inbound).unwrap();
+        fs::write(config.inbound.join(name), contents).unwrap();
+        let tic = Tic {
+            area: "FSX_NFO".to_string(),
+            from: "21:1/1@fsxnet".to_string(),
+            to: "21:1/100@fsxnet".to_string(),
+            origin: "21:1/1@fsxnet".to_string(),
+            file: name.to_string(),
+            desc: "The fsxNet nodelist".to_string(),
+            size: Some(contents.len() as u64),
+            crc: Some(get_crc32(contents)),
+            path: vec!["21:1/1 1709294400".to_string()],
+            seenby: vec!["21:1/1".to_string()],
+            pw: password.to_string(),
+            ..Default::default()
+        };
+        fs::write(config.inbound.join(format!("{}.tic", name.replace('.', "_"))), tic.to_text()).unwrap();
+    }
+
+    fn tics_in(directory: &Path) -> Vec<Tic> {
+        let mut tics = Vec::new();
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("tic")) {
+                tics.push(Tic::parse(&fs::read_to_string(path).unwrap()));
+            }
+        }
+        tics
+    }
+
+    #[test]
+    fn test_the_crc_is_the_one_every_tic_processor_uses() {
+        assert_eq!(get_crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_only_a_path_component_of_dots_is_refused() {
+        assert!(is_file_name("FSXNET.ZIP"));
+        assert!(is_file_name("v1..2.zip"));
+        assert!(is_file_name("..hidden"));
+        assert!(!is_file_name(""));
+        assert!(!is_file_name(".."));
+        assert!(!is_file_name("."));
+        assert!(!is_file_name("../FSXNET.ZIP"));
+        assert!(!is_file_name("/etc/passwd"));
+        assert!(!is_file_name("..\\FSXNET.ZIP"));
+    }
+
+    #[test]
+    fn test_a_tic_survives_writing_it_out() {
+        let tic = Tic {
+            area: "FSX_NFO".to_string(),
+            file: "FSXNET.ZIP".to_string(),
+            ldesc: vec!["first".to_string(), "second".to_string()],
+            size: Some(12),
+            crc: Some(0xDEAD_BEEF),
+            path: vec!["21:1/1 1709294400".to_string()],
+            pw: "secret".to_string(),
+            other: vec!["Magic NODELIST".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(Tic::parse(&tic.to_text()), tic);
+        assert_eq!(tic.description(), "first\nsecond");
+    }
+
+    #[test]
+    fn test_a_file_lands_in_its_area_with_the_tic_description() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = config(directory.path());
+        arrive(&config, "FSXNET.ZIP", b"nodelist", "SECRET");
+
+        let report = process_tics(&config, &areas(directory.path()), &now()).unwrap();
+        assert_eq!(report.imported, 1);
+        assert!(report.failed.is_empty());
+        assert!(fs::read_dir(&config.inbound).unwrap().next().is_none());
+
+        let files = directory.path().join("files/nfo");
+        let mut base = FileBase::open(&files, files.join("dir")).unwrap();
+        assert_eq!(base.len(), 1);
+        assert_eq!(base.description(&files.join("FSXNET.ZIP")).unwrap().as_deref(), Some("The fsxNet nodelist"));
+    }
+
+    #[test]
+    fn test_a_wrong_password_leaves_the_file_in_the_inbound() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = config(directory.path());
+        arrive(&config, "FSXNET.ZIP", b"nodelist", "guessed");
+
+        let report = process_tics(&config, &areas(directory.path()), &now()).unwrap();
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.failed.len(), 1);
+        assert!(config.inbound.join("FSXNET.ZIP").exists());
+    }
+
+    #[test]
+    fn test_a_damaged_file_is_refused() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = config(directory.path());
+        arrive(&config, "FSXNET.ZIP", b"nodelist", "secret");
+        fs::write(config.inbound.join("FSXNET.ZIP"), b"nodelisx").unwrap();
+
+        let report = process_tics(&config, &areas(directory.path()), &now()).unwrap();
+        assert!(report.failed[0].1.contains("crc"));
+    }
+
+    #[test]
+    fn test_a_tic_waits_for_its_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = config(directory.path());
+        arrive(&config, "FSXNET.ZIP", b"nodelist", "secret");
+        fs::remove_file(config.inbound.join("FSXNET.ZIP")).unwrap();
+
+        let report = process_tics(&config, &areas(directory.path()), &now()).unwrap();
+        assert_eq!(report.waiting, 1);
+        assert!(config.inbound.join("FSXNET_ZIP.tic").exists());
+    }
+
+    #[test]
+    fn test_replaces_takes_the_old_version_out() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = config(directory.path());
+        let files = directory.path().join("files/nfo");
+        fs::create_dir_all(&files).unwrap();
+        fs::write(files.join("FSXNET.001"), b"old").unwrap();
+
+        arrive(&config, "FSXNET.002", b"new", "secret");
+        let tic_path = config.inbound.join("FSXNET_002.tic");
+        let mut tic = Tic::parse(&fs::read_to_string(&tic_path).unwrap());
+        tic.replaces = "fsxnet.001".to_string();
+        fs::write(&tic_path, tic.to_text()).unwrap();
+
+        let report = process_tics(&config, &areas(directory.path()), &now()).unwrap();
+        assert_eq!(report.replaced, 1);
+        assert!(!files.join("FSXNET.001").exists());
+        let base = FileBase::open(&files, files.join("dir")).unwrap();
+        let names: Vec<&str> = base.iter().map(|header| header.name.as_str()).collect();
+        assert_eq!(names, vec!["FSXNET.002"]);
+    }
+
+    #[test]
+    fn test_a_file_is_handed_on_to_the_downlinks_with_a_tic_of_its_own() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = config(directory.path());
+        arrive(&config, "FSXNET.ZIP", b"nodelist", "secret");
+
+        let report = process_tics(&config, &areas(directory.path()), &now()).unwrap();
+        assert_eq!(report.forwarded, 1);
+
+        // The file came from 21:1/1, so only the downlink gets it.
+        assert!(!config.outbound_for(&config.links[0]).exists());
+        let outbound = config.outbound_for(&config.links[1]);
+        assert_eq!(fs::read(outbound.join("FSXNET.ZIP")).unwrap(), b"nodelist");
+        let tics = tics_in(&outbound);
+        assert_eq!(tics.len(), 1);
+        assert_eq!(tics[0].from, "21:1/100@fsxnet");
+        assert_eq!(tics[0].to, "21:1/101@fsxnet");
+        assert_eq!(tics[0].pw, "down");
+        assert_eq!(tics[0].path.len(), 2);
+        assert!(tics[0].seen_by(&address("21:1/101")));
+        assert_eq!(tics[0].crc, Some(get_crc32(b"nodelist")));
+    }
+}
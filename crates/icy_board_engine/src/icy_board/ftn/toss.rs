@@ -28,7 +28,7 @@ type Res<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
 const NO_MSGID: u32 = 0xffff_ffff;
 
 /// What every message this board sends out says it was written with.
-fn product() -> String {
+pub(crate) fn product() -> String {
     format!("IcyBoard/{}", env!("CARGO_PKG_VERSION"))
 }
 
@@ -333,23 +333,23 @@ fn to_jam(message: &PackedMessage, kludges: &Kludges, echo: bool) -> JamMessage
 
 /// Opening a base means reading the message ids it already holds, so a run
 /// that meets the same area in packet after packet pays for it once.
-struct OpenBases {
+pub(crate) struct OpenBases {
     /// How far back the duplicate check looks, zero for the whole base.
     track: u32,
     bases: HashMap<PathBuf, OpenBase>,
 }
 
-struct OpenBase {
-    base: JamMessageBase,
-    seen: HashSet<u32>,
+pub(crate) struct OpenBase {
+    pub(crate) base: JamMessageBase,
+    pub(crate) seen: HashSet<u32>,
 }
 
 impl OpenBases {
-    fn new(track: u32) -> Self {
+    pub(crate) fn new(track: u32) -> Self {
         Self { track, bases: HashMap::new() }
     }
 
-    fn get(&mut self, path: &Path) -> Res<&mut OpenBase> {
+    pub(crate) fn get(&mut self, path: &Path) -> Res<&mut OpenBase> {
         if !self.bases.contains_key(path) {
             let base = open_base(path)?;
             let mut ids: Vec<u32> = base
@@ -368,7 +368,7 @@ impl OpenBases {
     }
 }
 
-fn open_base(path: &Path) -> Res<JamMessageBase> {
+pub(crate) fn open_base(path: &Path) -> Res<JamMessageBase> {
     if path.with_extension("jhr").exists() {
         return Ok(JamMessageBase::open(path)?);
     }
@@ -593,7 +593,7 @@ fn deliver(config: &FtnConfig, link: &FtnLink, aka: &FtnAka, mut messages: Vec<P
     Ok(name)
 }
 
-fn subfield(header: &jamjam::jam::msg_header::JamMessageHeader, kind: SubfieldType) -> Option<String> {
+pub(crate) fn subfield(header: &jamjam::jam::msg_header::JamMessageHeader, kind: SubfieldType) -> Option<String> {
     header
         .sub_fields
         .iter()
@@ -5,8 +5,8 @@ use icy_engine::Color;
 use serde::{Deserialize, Serialize};
 
 use super::{
-    IcyBoardSerializer, accounting_cfg::AccountingConfig, commands::CommandType, is_false, is_null_8, is_null_16, is_null_32, login_server::LoginServer,
-    security_expr::SecurityExpression, user_base::Password,
+    IcyBoardSerializer, accounting_cfg::AccountingConfig, commands::CommandType, doors::DropFile, is_false, is_null_8, is_null_16, is_null_32,
+    login_server::LoginServer, path_is_empty, qwknet::QwkHub, security_expr::SecurityExpression, upload_check::UploadChecker, user_base::Password,
 };
 
 #[derive(Clone, Default, Serialize, Deserialize)]
@@ -62,6 +62,7 @@ impl SysopCommandLevels {
             CommandType::NodeList => Some(self.sec_11_view_other_nodes.clone()),
             CommandType::LogoffNode => Some(self.sec_12_logoff_alt_node.clone()),
             CommandType::NodeCallerLog => Some(self.sec_13_view_alt_node_callers.clone()),
+            CommandType::PendingUploads => Some(self.view_private_uploads.clone()),
             _ => None,
         }
     }
@@ -643,6 +644,31 @@ pub struct MessageOptions {
     /// Reading a message moves the last read pointer along.
     #[serde(default = "default_true")]
     pub update_last_read_pointer: bool,
+
+    /// Command line of an external message editor. It runs like a door on the
+    /// caller's connection, `%1` is replaced by the draft and `%2` by the header
+    /// file. Empty keeps the built-in editor.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub external_editor: String,
+
+    /// Header file the external editor expects next to the draft.
+    #[serde(default)]
+    pub external_editor_info: EditorInfoFile,
+
+    /// Drop file written for editors that are door programs.
+    #[serde(default)]
+    pub external_editor_drop_file: DropFile,
+}
+
+/// The message header an external editor reads before it starts.
+#[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Debug)]
+pub enum EditorInfoFile {
+    /// `MSGINF` - QuickBBS/RemoteAccess, read by QEdit-style editors.
+    #[default]
+    MsgInf,
+    /// `EDITOR.INF` - WWIV.
+    EditorInf,
 }
 
 fn default_true() -> bool {
@@ -666,8 +692,58 @@ pub struct FileTransferOptions {
     #[serde(default)]
     pub strip_colors_in_descriptions: bool,
 
+    /// Draws a thumbnail below pictures and SAUCE tagged ANSI in the file listings,
+    /// for callers whose terminal shows sixel or JPEG XL.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub show_thumbnails: bool,
+
+    /// Asks uploaders for tags after the description, for `L` to search by.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ask_upload_tags: bool,
+
     pub disable_drive_size_check: bool,
     pub stop_uploads_free_space: u32,
+
+    /// Programs every upload has to pass after the test extraction, e.g. a virus scanner.
+    #[serde(default, rename = "upload_checker", skip_serializing_if = "Vec::is_empty")]
+    pub upload_checkers: Vec<UploadChecker>,
+
+    /// An upload that fails its checks is moved here for the sysop to review.
+    /// Empty deletes it instead.
+    #[serde(default, skip_serializing_if = "path_is_empty")]
+    pub quarantine_path: PathBuf,
+
+    /// New uploads wait for a sysop to approve them (sysop command 17) before
+    /// anybody else gets to see them.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub hold_uploads: bool,
+
+    /// What to do with an upload whose name or contents are already in one of the
+    /// board's file areas.
+    #[serde(default, skip_serializing_if = "DuplicateUploads::is_allow")]
+    pub duplicate_uploads: DuplicateUploads,
+}
+
+#[derive(Default, Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum DuplicateUploads {
+    /// Uploads are taken as they come, nothing is checked.
+    #[default]
+    #[serde(rename = "allow")]
+    Allow,
+
+    /// The upload is accepted but held for the sysop, with the original noted.
+    #[serde(rename = "flag")]
+    Flag,
+
+    /// The upload is turned down, as is one whose name is taken in the upload directory.
+    #[serde(rename = "reject")]
+    Reject,
+}
+
+impl DuplicateUploads {
+    pub fn is_allow(&self) -> bool {
+        *self == DuplicateUploads::Allow
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -784,6 +860,16 @@ pub struct LimitOptions {
     #[serde(default)]
     #[serde(skip_serializing_if = "IcbTime::is_empty")]
     pub sysop_stop: IcbTime,
+
+    /// Statements a PPE may run before the watchdog stops it (0=no limit)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_null_32")]
+    pub ppe_instruction_budget: u32,
+
+    /// Seconds a PPE may run without output or input (0=no limit)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_null_16")]
+    pub ppe_idle_timeout: u16,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -1141,6 +1227,9 @@ impl IcbConfig {
                 default_scan_all_selected_confs_at_login: true,
                 force_comments_to_main: false,
                 update_last_read_pointer: true,
+                external_editor: String::new(),
+                external_editor_info: EditorInfoFile::default(),
+                external_editor_drop_file: DropFile::None,
             },
             file_transfer: FileTransferOptions {
                 display_uploader: false,
@@ -1151,8 +1240,14 @@ impl IcbConfig {
                 upload_credit_bytes: 0,
                 verify_files_uploaded: true,
                 strip_colors_in_descriptions: false,
+                show_thumbnails: false,
+                ask_upload_tags: false,
                 disable_drive_size_check: false,
                 stop_uploads_free_space: 1024,
+                upload_checkers: Vec::new(),
+                quarantine_path: PathBuf::new(),
+                hold_uploads: false,
+                duplicate_uploads: DuplicateUploads::Allow,
             },
             system_control: SystemControlOptions {
                 disable_ns_logon: false,
@@ -1190,6 +1285,8 @@ impl IcbConfig {
                 sysop_start: IcbTime::default(),
                 sysop_stop: IcbTime::default(),
                 max_number_upload_descr_lines: 20,
+                ppe_instruction_budget: 0,
+                ppe_idle_timeout: 0,
             },
             options: BoardOptions {
                 give_user_password_to_doors: false,
@@ -1258,6 +1355,11 @@ pub struct QwkSettings {
     /// The same ceiling per conference, `PCBoard`'s `MaxConfMsgs`.
     #[serde(default = "QwkSettings::default_max_msgs_per_conf")]
     pub max_msgs_per_conf: u16,
+
+    /// The QWK networks this board is a node of, exchanged by `icbmailer qwk`.
+    #[serde(rename = "hub", default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hubs: Vec<QwkHub>,
 }
 
 impl QwkSettings {
@@ -1283,6 +1385,7 @@ impl Default for QwkSettings {
             news_sceen: PathBuf::new(),
             max_msgs: Self::default_max_msgs(),
             max_msgs_per_conf: Self::default_max_msgs_per_conf(),
+            hubs: Vec::new(),
         }
     }
 }
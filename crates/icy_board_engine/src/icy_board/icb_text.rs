@@ -1375,7 +1375,7 @@ pub enum IceText {
     CreatingQWK = 676,
     /// `Error compressing file.`
     ErrorCompressing = 677,
-    /// `QWK Commands: (D)ownload, (U)pload, (S)canned bases`
+    /// `QWK Commands: (D)ownload, (U)pload, (S)canned bases, (B)lue Wave`
     QWKCommands = 678,
     /// `Extracting messages, please wait...`
     ExtractingMessages = 679,
@@ -1609,9 +1609,36 @@ pub enum IceText {
 
     /// `Reading Bulletins`
     ReadingBulletins = 780,
+
+    /// `(A)pprove, (D)elete, (M)ove, (S)kip, (Q)uit`
+    PendingUploadCommands = 781,
+
+    /// `Move to @OPTEXT@`
+    MoveUploadTo = 782,
+
+    /// `Uploaded by @OPTEXT@`
+    UploadedBy = 783,
+
+    /// `Duplicate of @OPTEXT@`
+    DuplicateOf = 784,
+
+    /// `@OPTEXT@ approved.`
+    UploadApproved = 785,
+
+    /// `@OPTEXT@ deleted.`
+    UploadDeleted = 786,
+
+    /// `@OPTEXT@ moved.`
+    UploadMoved = 787,
+
+    /// `(V)iew, (E)xtract member @OPTEXT@, (L)ist, (Enter)=done`
+    ArchiveMemberCommands = 788,
+
+    /// `Tags (e.g. #demo #1995)`
+    UploadTags = 789,
 }
 
-const LAST_ENTRY: usize = 780;
+const LAST_ENTRY: usize = 789;
 
 impl IceText {
     /// A number a file or a PPE names. Anything past the last message has no
@@ -2004,7 +2031,7 @@ mod tests {
     #[test]
     fn text_numbers_are_checked_before_they_become_an_enum() {
         assert_eq!(IceText::try_from_number(0), Some(IceText::UnusedStatusLine));
-        assert_eq!(IceText::try_from_number(LAST_ENTRY), Some(IceText::ReadingBulletins));
+        assert_eq!(IceText::try_from_number(LAST_ENTRY), Some(IceText::UploadTags));
         assert_eq!(IceText::try_from_number(LAST_ENTRY + 1), None);
         assert_eq!(IceText::try_from_number(usize::MAX), None);
     }
@@ -166,6 +166,7 @@ impl Menu {
                     command_type: cmd_type,
                     parameter,
                     trigger: ActionTrigger::default(),
+                    permissions: None,
                 }],
             });
         }
@@ -8,6 +8,7 @@ use crate::Res;
 use accounting_cfg::AccountingConfig;
 use bulletins::BullettinList;
 use codepages::tables::write_utf8_with_bom;
+use dizbase::file_base::hash_index::HashIndex;
 use surveys::SurveyList;
 use thiserror::Error;
 
@@ -34,6 +35,7 @@ use self::{
 };
 
 pub mod bbs;
+pub mod bluewave;
 pub mod bulletins;
 pub mod commands;
 pub mod conferences;
@@ -53,12 +55,15 @@ pub mod menu;
 pub mod message_area;
 pub mod path_check;
 pub mod pcb;
+pub mod pending_uploads;
+pub mod qwknet;
 pub mod sec_levels;
 pub mod security_expr;
 pub mod state;
 pub mod statistics;
 pub mod subscription;
 pub mod surveys;
+pub mod upload_check;
 pub mod user_base;
 pub mod user_maintenance;
 pub mod xfer_protocols;
@@ -128,6 +133,10 @@ pub struct IcyBoard {
     pub commands: CommandList,
     pub ftn: FtnConfig,
     pub events: EventList,
+
+    /// What uploads are checked against for duplicates. Built by the first upload
+    /// that needs it and kept up to date as files come in.
+    pub upload_index: Option<HashIndex>,
 }
 
 impl IcyBoard {
@@ -149,6 +158,7 @@ impl IcyBoard {
             groups: GroupList::default(),
             ftn: FtnConfig::default(),
             events: EventList::default(),
+            upload_index: None,
         }
     }
 
@@ -197,6 +207,19 @@ impl IcyBoard {
         self.ftn.netmail = get_path(&self.root_path, &self.ftn.netmail);
         self.ftn.bad_netmail = get_path(&self.root_path, &self.ftn.bad_netmail);
         self.ftn.new_areas = get_path(&self.root_path, &self.ftn.new_areas);
+        for area in &mut self.ftn.freq.areas {
+            *area = get_path(&self.root_path, &*area);
+        }
+        for magic in &mut self.ftn.freq.magic {
+            magic.path = get_path(&self.root_path, &magic.path);
+        }
+
+        // QWK network spool
+        for hub in &mut self.config.qwk_settings.hubs {
+            hub.directory = get_path(&self.root_path, &hub.directory);
+        }
+
+        self.config.file_transfer.quarantine_path = get_path(&self.root_path, &self.config.file_transfer.quarantine_path);
 
         // Trashcan files
         self.config.paths.trashcan_upload_files = get_path(&self.root_path, &self.config.paths.trashcan_upload_files);
@@ -245,6 +268,7 @@ impl IcyBoard {
                 for dir in directories.iter_mut() {
                     dir.path = get_path(&self.root_path, &dir.path);
                     dir.metadata_path = get_path(&self.root_path, &dir.metadata_path);
+                    dir.ad_file = get_path(&self.root_path, &dir.ad_file);
                 }
             }
 
@@ -426,6 +450,7 @@ impl IcyBoard {
             groups,
             ftn,
             events,
+            upload_index: None,
         };
 
         for conf in board.conferences.iter_mut() {
@@ -0,0 +1,257 @@
+//! Uploads waiting for a sysop. With `hold_uploads` set a new file goes into its
+//! file base held (see `FileBase::hold_file`) and stays out of every listing until
+//! it is approved, moved to another location or deleted - from sysop command 17,
+//! `icbfile approve` or the admin API.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use dizbase::file_base::{
+    FileBase, FileBaseError,
+    metadata::{MetadataHeader, MetadataType},
+};
+
+use crate::Res;
+
+use super::IcyBoard;
+
+/// A directory uploads can end up in: a conference's upload locations and its file areas.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UploadLocation {
+    pub conference: usize,
+    pub name: String,
+    pub path: PathBuf,
+    pub metadata_path: PathBuf,
+}
+
+impl UploadLocation {
+    fn new(conference: usize, name: String, path: &Path, metadata_path: &Path) -> Self {
+        // The upload locations of an imported board don't name an index of their own.
+        let metadata_path = if metadata_path.as_os_str().is_empty() {
+            path.join("dir")
+        } else {
+            metadata_path.to_path_buf()
+        };
+        Self {
+            conference,
+            name,
+            path: path.to_path_buf(),
+            metadata_path,
+        }
+    }
+
+    pub fn open(&self) -> Res<FileBase> {
+        FileBase::open(&self.path, &self.metadata_path)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PendingUpload {
+    pub location: UploadLocation,
+    pub name: String,
+    pub size: u64,
+    pub uploader: String,
+    pub uploaded: Option<DateTime<Utc>>,
+    pub description: String,
+    pub duplicate_of: Option<String>,
+}
+
+impl PendingUpload {
+    fn from_metadata(location: &UploadLocation, name: &str, size: u64, metadata: &[MetadataHeader]) -> Self {
+        let text = |metadata_type: MetadataType| {
+            metadata
+                .iter()
+                .find(|header| header.get_type() == metadata_type)
+                .map(|header| String::from_utf8_lossy(&header.data).to_string())
+                .unwrap_or_default()
+        };
+        Self {
+            location: location.clone(),
+            name: name.to_string(),
+            size,
+            uploader: text(MetadataType::Uploader),
+            uploaded: metadata.iter().find_map(|header| header.upload_time()),
+            description: text(MetadataType::FileID),
+            duplicate_of: Some(text(MetadataType::Duplicate)).filter(|original| !original.is_empty()),
+        }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.location.path.join(&self.name)
+    }
+
+    pub fn approve(&self) -> Res<()> {
+        self.location.open()?.approve(&self.name)
+    }
+
+    pub fn delete(&self) -> Res<()> {
+        self.location.open()?.take_held(&self.name)?;
+        let path = self.path();
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Moves the file to `target` and lists it there; an upload a sysop bothered
+    /// to put in the right place is an approved one.
+    pub fn move_to(&self, target: &UploadLocation) -> Res<()> {
+        if target.path == self.location.path {
+            return self.approve();
+        }
+        let dest = target.path.join(&self.name);
+        if dest.exists() {
+            return Err(FileBaseError::FileAlreadyExists(self.name.clone()).into());
+        }
+        let mut target_base = target.open()?;
+        let metadata = self.location.open()?.take_held(&self.name)?;
+        std::fs::copy(self.path(), &dest)?;
+        std::fs::remove_file(self.path())?;
+        target_base.add_file(&dest, metadata)?;
+        Ok(())
+    }
+}
+
+/// Every location of the board once, in conference order.
+pub fn upload_locations(board: &IcyBoard) -> Vec<UploadLocation> {
+    let mut seen = HashSet::new();
+    let mut locations = Vec::new();
+    for (number, conference) in board.conferences.iter().enumerate() {
+        let mut candidates = vec![
+            UploadLocation::new(
+                number,
+                format!("{} - Uploads", conference.name),
+                &conference.pub_upload_location,
+                &conference.pub_upload_metadata,
+            ),
+            UploadLocation::new(
+                number,
+                format!("{} - Private uploads", conference.name),
+                &conference.private_upload_location,
+                &conference.private_upload_metadata,
+            ),
+        ];
+        if let Some(directories) = &conference.directories {
+            for directory in directories.iter() {
+                candidates.push(UploadLocation::new(
+                    number,
+                    format!("{} - {}", conference.name, directory.name),
+                    &directory.path,
+                    &directory.metadata_path,
+                ));
+            }
+        }
+        for location in candidates {
+            if !location.path.as_os_str().is_empty() && seen.insert(location.path.clone()) {
+                locations.push(location);
+            }
+        }
+    }
+    locations
+}
+
+pub fn pending_in(location: &UploadLocation) -> Res<Vec<PendingUpload>> {
+    let mut base = location.open()?;
+    let held: Vec<(String, u64)> = base.held().iter().map(|header| (header.name.clone(), header.size)).collect();
+    let mut pending = Vec::with_capacity(held.len());
+    for (name, size) in held {
+        let metadata = base.held_metadata(&name)?;
+        pending.push(PendingUpload::from_metadata(location, &name, size, &metadata));
+    }
+    Ok(pending)
+}
+
+/// The queue across all conferences, oldest upload first. A location that can't be
+/// opened is logged and skipped rather than hiding the rest of the queue.
+pub fn pending_uploads(board: &IcyBoard) -> Vec<PendingUpload> {
+    let mut pending = Vec::new();
+    for location in upload_locations(board) {
+        if !location.path.is_dir() {
+            continue;
+        }
+        match pending_in(&location) {
+            Ok(uploads) => pending.extend(uploads),
+            Err(err) => log::error!("Can't read the uploads held in {}: {err}", location.path.display()),
+        }
+    }
+    pending.sort_by_key(|upload| upload.uploaded);
+    pending
+}
+
+/// Looks an upload up by where it is and what it is called, so that nothing outside
+/// the queue can be approved or deleted through it.
+pub fn find_pending(board: &IcyBoard, path: &Path, name: &str) -> Option<PendingUpload> {
+    pending_uploads(board)
+        .into_iter()
+        .find(|upload| upload.location.path == path && upload.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(dir: &Path, name: &str) -> UploadLocation {
+        let path = dir.join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        UploadLocation::new(0, name.to_string(), &path, Path::new(""))
+    }
+
+    fn hold(location: &UploadLocation, name: &str) {
+        let path = location.path.join(name);
+        std::fs::write(&path, b"upload").unwrap();
+        let mut base = location.open().unwrap();
+        base.remove_file(&path).unwrap();
+        base.hold_file(&path, vec![MetadataHeader::new(MetadataType::Uploader, b"Joe Caller".to_vec())])
+            .unwrap();
+    }
+
+    fn listed(location: &UploadLocation) -> Vec<String> {
+        location.open().unwrap().iter().map(|header| header.name.clone()).collect()
+    }
+
+    #[test]
+    fn a_held_upload_is_queued_with_its_uploader() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploads = location(dir.path(), "uploads");
+        hold(&uploads, "NEW.ZIP");
+
+        let pending = pending_in(&uploads).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].uploader, "Joe Caller");
+        assert!(pending[0].uploaded.is_some());
+        assert!(listed(&uploads).is_empty());
+
+        pending[0].approve().unwrap();
+        assert!(pending_in(&uploads).unwrap().is_empty());
+        assert_eq!(listed(&uploads), vec!["NEW.ZIP"]);
+    }
+
+    #[test]
+    fn a_moved_upload_is_listed_at_its_new_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploads = location(dir.path(), "uploads");
+        let games = location(dir.path(), "games");
+        hold(&uploads, "GAME.ZIP");
+
+        let pending = pending_in(&uploads).unwrap().remove(0);
+        pending.move_to(&games).unwrap();
+        assert!(pending_in(&uploads).unwrap().is_empty());
+        assert!(!uploads.path.join("GAME.ZIP").exists());
+        assert_eq!(listed(&games), vec!["GAME.ZIP"]);
+        assert!(listed(&uploads).is_empty());
+    }
+
+    #[test]
+    fn a_deleted_upload_is_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let uploads = location(dir.path(), "uploads");
+        hold(&uploads, "JUNK.EXE");
+
+        pending_in(&uploads).unwrap()[0].delete().unwrap();
+        assert!(pending_in(&uploads).unwrap().is_empty());
+        assert!(!uploads.path.join("JUNK.EXE").exists());
+    }
+}
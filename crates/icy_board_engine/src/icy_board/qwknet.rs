@@ -0,0 +1,557 @@
+//! The board as a node of a QWK network. A hub hands its nodes a QWK packet
+//! the way it hands one to a caller, and takes a REP back. What makes it a
+//! network are the `@MSGID`, `@REPLY` and `@VIA` lines QWKnet puts in front of
+//! the message text.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use bstr::BString;
+use chrono::{NaiveDateTime, Utc};
+use jamjam::{
+    jam::{
+        JamMessage, JamMessageBase, attributes,
+        msg_header::{MessageSubfield, SubfieldType},
+        raw,
+    },
+    qwk::qwk_message::QwkMessage,
+};
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+
+use super::ftn::toss::{OpenBases, subfield};
+
+type Res<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// The active flag QWK gives a message that was not killed.
+const ACTIVE: u8 = 225;
+
+/// A QWK hub this board is a node of.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct QwkHub {
+    /// The id the hub names its packets with, `HUB.QWK` comes in and
+    /// `HUB.REP` goes back.
+    pub name: String,
+
+    /// Where the hub is fetched from. Empty means somebody else puts the
+    /// packet into `directory` and takes the reply from there.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host: String,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub user: String,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub password: String,
+
+    /// Where the packet is dropped, the reply is left and the scanner keeps
+    /// track of what it already sent.
+    #[serde(default = "QwkHub::default_directory")]
+    pub directory: PathBuf,
+
+    /// The QWK conference numbers the hub carries for this board. Empty means
+    /// every area that has a QWK number.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conferences: Vec<u16>,
+}
+
+impl QwkHub {
+    fn default_directory() -> PathBuf {
+        PathBuf::from("qwknet")
+    }
+
+    pub fn carries(&self, conference: u16) -> bool {
+        self.conferences.is_empty() || self.conferences.contains(&conference)
+    }
+
+    pub fn packet_path(&self) -> PathBuf {
+        self.directory.join(format!("{}.QWK", self.name.to_ascii_uppercase()))
+    }
+
+    pub fn reply_path(&self) -> PathBuf {
+        self.directory.join(format!("{}.REP", self.name.to_ascii_uppercase()))
+    }
+
+    /// The packet waiting for the tosser. A hub that is reached over the
+    /// network would be called here, only the file drop is there yet.
+    pub fn fetch(&self) -> Res<Option<PathBuf>> {
+        if !self.host.is_empty() {
+            return Err(format!(
+                "{}: fetching from {} is not supported yet, drop {} into {} instead",
+                self.name,
+                self.host,
+                self.packet_path().file_name().unwrap_or_default().to_string_lossy(),
+                self.directory.display()
+            )
+            .into());
+        }
+        if !self.directory.is_dir() {
+            return Ok(None);
+        }
+        // Whoever dropped the packet may not have cared about the case.
+        let wanted = self.packet_path().file_name().unwrap_or_default().to_string_lossy().to_string();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() && entry.file_name().to_string_lossy().eq_ignore_ascii_case(&wanted) {
+                return Ok(Some(entry.path()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Default for QwkHub {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            host: String::new(),
+            user: String::new(),
+            password: String::new(),
+            directory: Self::default_directory(),
+            conferences: Vec::new(),
+        }
+    }
+}
+
+/// One area of the board as the QWK network sees it: its conference number
+/// and the message base it is stored in.
+pub type AreaMap = [(u16, PathBuf)];
+
+/// What tossing one hub packet left behind.
+#[derive(Debug, Default)]
+pub struct QwkTossReport {
+    pub imported: usize,
+    pub duplicates: usize,
+
+    /// Messages whose `@VIA` already named this board.
+    pub looped: usize,
+
+    /// Conference numbers no area here has, and how many messages came for each.
+    pub unknown: BTreeMap<u16, usize>,
+}
+
+/// Reads a packet from the hub into the areas that carry its conferences.
+pub fn toss_packet(packet: &Path, areas: &AreaMap, node_id: &str) -> Res<QwkTossReport> {
+    let mut report = QwkTossReport::default();
+    let mut archive = zip::ZipArchive::new(fs::File::open(packet)?)?;
+    let Some(name) = archive.file_names().find(|name| name.eq_ignore_ascii_case("messages.dat")).map(str::to_string) else {
+        return Err(format!("{} holds no messages.dat", packet.display()).into());
+    };
+    let mut data = Vec::new();
+    archive.by_name(&name)?.read_to_end(&mut data)?;
+
+    let mut bases = OpenBases::new(0);
+    let mut cursor = Cursor::new(data);
+    // The first block only says who produced the packet.
+    cursor.seek(SeekFrom::Start(128))?;
+    while let Ok(message) = QwkMessage::read(&mut cursor, true) {
+        if message.active_flag != ACTIVE {
+            continue;
+        }
+        let Some((_, path)) = areas.iter().find(|(number, _)| *number == message.conference_number) else {
+            *report.unknown.entry(message.conference_number).or_default() += 1;
+            continue;
+        };
+        let kludges = QwkKludges::split(&message.text);
+        if kludges.via.iter().any(|node| node.eq_ignore_ascii_case(node_id)) {
+            report.looped += 1;
+            continue;
+        }
+        let base = bases.get(path)?;
+        if let Some(id) = &kludges.msgid
+            && !base.seen.insert(JamMessageBase::crc(&BString::from(id.as_str())))
+        {
+            report.duplicates += 1;
+            continue;
+        }
+        base.base.write_message(&to_jam(&message, &kludges))?;
+        base.base.write_jhr_header()?;
+        report.imported += 1;
+    }
+    Ok(report)
+}
+
+fn to_jam(message: &QwkMessage, kludges: &QwkKludges) -> JamMessage {
+    // The echo flag is what keeps the scanner from sending it back.
+    let mut flags = attributes::MSG_TYPEECHO;
+    if message.status == b'*' || message.status == b'+' {
+        flags |= attributes::MSG_PRIVATE;
+    }
+    let written = NaiveDateTime::parse_from_str(&message.date_time.to_string(), "%m-%d-%y%H:%M")
+        .map(|date| date.and_utc())
+        .unwrap_or(Utc::now());
+    let mut jam = JamMessage::default()
+        .with_from(message.from.clone())
+        .with_to(message.to.clone())
+        .with_subject(message.subj.clone())
+        .with_date_time(written)
+        .with_attributes(flags)
+        .with_text(BString::from(kludges.body.as_str()));
+    if let Some(id) = &kludges.msgid {
+        jam = jam.with_msg_id(BString::from(id.as_str()));
+    }
+    if let Some(id) = &kludges.reply {
+        jam = jam.with_reply_id(BString::from(id.as_str()));
+    }
+    if !kludges.via.is_empty() {
+        jam = jam.with_sub_field(MessageSubfield::new(
+            SubfieldType::FTSKludge,
+            BString::from(format!("VIA: {}", kludges.via.join("/"))),
+        ));
+    }
+    jam
+}
+
+/// The `@` lines QWKnet puts at the top of a message.
+#[derive(Debug, Default, PartialEq)]
+struct QwkKludges {
+    msgid: Option<String>,
+    reply: Option<String>,
+    /// The nodes the message passed, the one that wrote it first.
+    via: Vec<String>,
+    body: String,
+}
+
+impl QwkKludges {
+    fn split(text: &BString) -> Self {
+        let mut result = QwkKludges::default();
+        // QWK ends a line with 0xE3, the readers that wrote the text may not have.
+        let text: Vec<u8> = text.iter().map(|byte| if *byte == 0xE3 { b'\n' } else { *byte }).collect();
+        let text = String::from_utf8_lossy(&text).replace("\r\n", "\n").replace('\r', "\n");
+        let mut lines = text.trim_end_matches('\n').split('\n').peekable();
+        while let Some(line) = lines.peek() {
+            let Some(rest) = line.strip_prefix('@') else {
+                break;
+            };
+            let Some((name, value)) = rest.split_once(':') else {
+                break;
+            };
+            let value = value.trim().to_string();
+            match name.to_ascii_uppercase().as_str() {
+                "MSGID" => result.msgid = Some(value),
+                "REPLY" => result.reply = Some(value),
+                "VIA" => result.via = value.split('/').map(|node| node.trim().to_string()).filter(|node| !node.is_empty()).collect(),
+                // @TZ and the rest say nothing the board keeps.
+                _ => {}
+            }
+            lines.next();
+        }
+        result.body = lines.collect::<Vec<_>>().join("\n");
+        result
+    }
+}
+
+/// What the last scan already put into a reply, kept beside the hub's packets.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScanState {
+    #[serde(default)]
+    serial: u32,
+
+    /// Toml wants its keys as strings, so the conference number is written as one.
+    #[serde(default)]
+    exported: BTreeMap<String, u32>,
+}
+
+impl ScanState {
+    fn path(hub: &QwkHub) -> PathBuf {
+        hub.directory.join("scan.toml")
+    }
+
+    fn load(hub: &QwkHub) -> Res<Self> {
+        let path = Self::path(hub);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self, hub: &QwkHub) -> Res<()> {
+        fs::create_dir_all(&hub.directory)?;
+        fs::write(Self::path(hub), toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// What one scan put into the reply for the hub.
+#[derive(Debug, Default)]
+pub struct QwkScanReport {
+    pub exported: usize,
+    pub reply: Option<PathBuf>,
+
+    /// The last reply was not picked up yet, so nothing new was added.
+    pub waiting: bool,
+}
+
+/// Packs what was written here since the last scan into a REP for the hub.
+pub fn scan_reply(hub: &QwkHub, areas: &AreaMap, node_id: &str) -> Res<QwkScanReport> {
+    let mut report = QwkScanReport::default();
+    let reply_path = hub.reply_path();
+    if reply_path.exists() {
+        report.waiting = true;
+        return Ok(report);
+    }
+    let mut state = ScanState::load(hub)?;
+
+    let mut messages = format!("{:<128}", hub.name.to_ascii_uppercase()).into_bytes();
+    messages.truncate(128);
+    for (conference, path) in areas {
+        if !hub.carries(*conference) || !path.with_extension("jhr").exists() {
+            continue;
+        }
+        let mut base = JamMessageBase::open(path)?;
+        let high = base.highest_message_number();
+        let Some(last) = state.exported.get(&conference.to_string()).copied() else {
+            // A hub that was just joined does not need the history of the area.
+            state.exported.insert(conference.to_string(), high);
+            continue;
+        };
+        for number in (last + 1)..=high {
+            let Ok(mut header) = base.read_header(number) else {
+                continue;
+            };
+            if header.attributes & attributes::MSG_TYPEECHO != 0 || header.is_deleted() {
+                continue;
+            }
+            let msgid = if let Some(id) = subfield(&header, SubfieldType::MsgID) {
+                id
+            } else {
+                state.serial = state.serial.wrapping_add(1);
+                let id = format!("<{:08x}.{}@{}>", state.serial, conference, node_id);
+                header.msgid_crc = JamMessageBase::crc(&BString::from(id.as_str()));
+                header.sub_fields.push(MessageSubfield::new(SubfieldType::MsgID, BString::from(id.as_str())));
+                raw::update_header(&mut base, number, &header)?;
+                id
+            };
+            let text = base.read_message_text(&header)?;
+            let private = header.attributes & attributes::MSG_PRIVATE != 0;
+            let written = chrono::DateTime::from_timestamp(header.date_written as i64, 0).unwrap_or_default();
+            let message = QwkMessage {
+                // A reply names the conference where a packet has the message number.
+                msg_number: *conference as u32,
+                from: header.from().cloned().unwrap_or_default(),
+                to: header.to().cloned().unwrap_or_default(),
+                subj: header.subject().cloned().unwrap_or_default(),
+                date_time: written.format("%m-%d-%y%H:%M").to_string().into(),
+                text: BString::from(reply_text(
+                    &msgid,
+                    subfield(&header, SubfieldType::ReplyID).as_deref(),
+                    node_id,
+                    &text.to_string(),
+                )),
+                status: if private { b'*' } else { b' ' },
+                password: BString::from(""),
+                ref_msg_number: 0,
+                logical_message_number: report.exported as u16,
+                active_flag: ACTIVE,
+                conference_number: *conference,
+                net_tag: b' ',
+            };
+            message.write(&mut messages, true)?;
+            report.exported += 1;
+        }
+        state.exported.insert(conference.to_string(), high);
+    }
+
+    if report.exported > 0 {
+        fs::create_dir_all(&hub.directory)?;
+        let mut zip = zip::ZipWriter::new(fs::File::create(&reply_path)?);
+        zip.start_file(format!("{}.MSG", hub.name.to_ascii_uppercase()), SimpleFileOptions::default())?;
+        zip.write_all(&messages)?;
+        zip.finish()?;
+        report.reply = Some(reply_path);
+    }
+    state.save(hub)?;
+    Ok(report)
+}
+
+/// The kludges go in front of the text, which is where a QWKnet hub looks.
+fn reply_text(msgid: &str, reply: Option<&str>, node_id: &str, body: &str) -> String {
+    let mut text = format!("@MSGID: {msgid}\n");
+    if let Some(reply) = reply {
+        let _ = writeln!(text, "@REPLY: {reply}");
+    }
+    let _ = writeln!(text, "@VIA: {node_id}");
+    text.push_str(&body.replace("\r\n", "\n").replace('\r', "\n"));
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icy_board::ftn::toss::open_base;
+
+    fn hub(directory: &Path) -> QwkHub {
+        QwkHub {
+            name: "HUB".to_string(),
+            directory: directory.join("qwknet"),
+            ..Default::default()
+        }
+    }
+
+    fn qwk_message(conference: u16, text: &str) -> QwkMessage {
+        QwkMessage {
+            msg_number: 1,
+            from: BString::from("Someone"),
+            to: BString::from("All"),
+            subj: BString::from("Hello"),
+            date_time: BString::from("03-04-2512:00"),
+            text: BString::from(text),
+            status: b' ',
+            password: BString::from(""),
+            ref_msg_number: 0,
+            logical_message_number: 0,
+            active_flag: ACTIVE,
+            conference_number: conference,
+            net_tag: b' ',
+        }
+    }
+
+    fn drop_packet(hub: &QwkHub, messages: &[QwkMessage]) -> PathBuf {
+        let mut data = format!("{:<128}", "Produced by a hub").into_bytes();
+        for message in messages {
+            message.write(&mut data, true).unwrap();
+        }
+        fs::create_dir_all(&hub.directory).unwrap();
+        let path = hub.directory.join("hub.qwk");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        zip.start_file("MESSAGES.DAT", SimpleFileOptions::default()).unwrap();
+        zip.write_all(&data).unwrap();
+        zip.finish().unwrap();
+        path
+    }
+
+    fn read_reply(path: &Path) -> Vec<QwkMessage> {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        let mut data = Vec::new();
+        archive.by_name("HUB.MSG").unwrap().read_to_end(&mut data).unwrap();
+        assert!(data.starts_with(b"HUB "));
+        let mut cursor = Cursor::new(data);
+        cursor.seek(SeekFrom::Start(128)).unwrap();
+        let mut messages = Vec::new();
+        while let Ok(message) = QwkMessage::read(&mut cursor, true) {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[test]
+    fn test_a_dropped_packet_is_found_whatever_its_case() {
+        let directory = tempfile::tempdir().unwrap();
+        let hub = hub(directory.path());
+        assert_eq!(hub.fetch().unwrap(), None);
+        let path = drop_packet(&hub, &[]);
+        assert_eq!(hub.fetch().unwrap(), Some(path));
+    }
+
+    #[test]
+    fn test_a_hub_on_the_network_is_not_fetched_yet() {
+        let hub = QwkHub {
+            name: "HUB".to_string(),
+            host: "hub.example.org".to_string(),
+            ..Default::default()
+        };
+        assert!(hub.fetch().is_err());
+    }
+
+    #[test]
+    fn test_kludges_are_taken_from_the_top_of_the_text() {
+        let kludges = QwkKludges::split(&BString::from(
+            &b"@MSGID: <1.2@HUB>\xe3@REPLY: <0.2@HUB>\xe3@VIA: HUB/OTHER\xe3Hello\xe3@not a kludge\xe3"[..],
+        ));
+        assert_eq!(kludges.msgid.as_deref(), Some("<1.2@HUB>"));
+        assert_eq!(kludges.reply.as_deref(), Some("<0.2@HUB>"));
+        assert_eq!(kludges.via, vec!["HUB".to_string(), "OTHER".to_string()]);
+        assert_eq!(kludges.body, "Hello\n@not a kludge");
+    }
+
+    #[test]
+    fn test_a_packet_is_tossed_by_conference_number() {
+        let directory = tempfile::tempdir().unwrap();
+        let hub = hub(directory.path());
+        let areas = vec![(7, directory.path().join("bases/general"))];
+        let packet = drop_packet(
+            &hub,
+            &[
+                qwk_message(7, "@MSGID: <1.7@HUB>\nBody\n"),
+                qwk_message(7, "@MSGID: <1.7@HUB>\nBody\n"),
+                qwk_message(7, "@VIA: HUB/MYBBS\nBeen here\n"),
+                qwk_message(9, "Nobody carries this\n"),
+            ],
+        );
+
+        let report = toss_packet(&packet, &areas, "MYBBS").unwrap();
+
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.looped, 1);
+        assert_eq!(report.unknown.get(&9), Some(&1));
+        let base = JamMessageBase::open(&areas[0].1).unwrap();
+        let header = base.read_header(1).unwrap();
+        assert_eq!(base.read_message_text(&header).unwrap().to_string().trim_end(), "Body");
+        assert_eq!(subfield(&header, SubfieldType::MsgID).unwrap(), "<1.7@HUB>");
+    }
+
+    #[test]
+    fn test_mail_written_here_leaves_as_a_reply_with_msgid_and_via() {
+        let directory = tempfile::tempdir().unwrap();
+        let hub = hub(directory.path());
+        let path = directory.path().join("bases/general");
+        let areas = vec![(7, path.clone())];
+        let mut base = open_base(&path).unwrap();
+        base.write_message(&JamMessage::default().with_text(BString::from("old"))).unwrap();
+        base.write_jhr_header().unwrap();
+        assert_eq!(scan_reply(&hub, &areas, "MYBBS").unwrap().exported, 0);
+
+        base.write_message(
+            &JamMessage::default()
+                .with_from(BString::from("Sysop"))
+                .with_to(BString::from("All"))
+                .with_subject(BString::from("Hi"))
+                .with_text(BString::from("Body")),
+        )
+        .unwrap();
+        base.write_jhr_header().unwrap();
+        let report = scan_reply(&hub, &areas, "MYBBS").unwrap();
+
+        assert_eq!(report.exported, 1);
+        let messages = read_reply(report.reply.as_ref().unwrap());
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].conference_number, 7);
+        let kludges = QwkKludges::split(&messages[0].text);
+        assert!(kludges.msgid.unwrap().ends_with(".7@MYBBS>"));
+        assert_eq!(kludges.via, vec!["MYBBS".to_string()]);
+
+        // The reply has not been picked up, so the next scan leaves it alone.
+        assert!(scan_reply(&hub, &areas, "MYBBS").unwrap().waiting);
+    }
+
+    #[test]
+    fn test_what_came_from_the_hub_is_not_sent_back() {
+        let directory = tempfile::tempdir().unwrap();
+        let hub = hub(directory.path());
+        let path = directory.path().join("bases/general");
+        let areas = vec![(7, path.clone())];
+        {
+            let mut base = open_base(&path).unwrap();
+            base.write_message(&JamMessage::default().with_text(BString::from("old"))).unwrap();
+            base.write_jhr_header().unwrap();
+        }
+        scan_reply(&hub, &areas, "MYBBS").unwrap();
+
+        let packet = drop_packet(&hub, &[qwk_message(7, "@MSGID: <1.7@HUB>\nBody\n")]);
+        assert_eq!(toss_packet(&packet, &areas, "MYBBS").unwrap().imported, 1);
+
+        let report = scan_reply(&hub, &areas, "MYBBS").unwrap();
+        assert_eq!(report.exported, 0);
+        assert!(report.reply.is_none());
+    }
+}
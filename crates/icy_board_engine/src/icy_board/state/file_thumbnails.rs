@@ -0,0 +1,362 @@
+//! Small previews of pictures and ANSI art, drawn into file listings for callers
+//! whose terminal can show graphics. A thumbnail is rendered once per file
+//! content and kept below the work directory under the content hash, so a file
+//! listed by every caller is still only decoded once.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use icy_engine::{IceMode, TextPane};
+use icy_parser_core::CommandParser;
+
+use super::{
+    IcyBoardState,
+    ppl_graphics::{self, GFX_BACKEND_AUTO, GFX_BACKEND_JXL, GFX_BACKEND_NONE, GfxSurface},
+};
+use crate::Res;
+
+pub const THUMBNAIL_WIDTH: u32 = 160;
+pub const THUMBNAIL_HEIGHT: u32 = 96;
+
+/// Anything bigger is not decoded for a preview.
+const MAX_SOURCE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// How much of an ANSI is rendered, two screens is what a thumbnail can still show.
+const MAX_ANSI_ROWS: i32 = 50;
+
+/// Every text cell becomes this many pixels, enough to keep half blocks apart.
+const CELL_WIDTH: u32 = 2;
+const CELL_HEIGHT: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThumbnailKind {
+    Picture,
+    /// Only ANSI that carries a SAUCE record is rendered, an untagged `.ANS`
+    /// is as likely to be a text file with colours.
+    Ansi,
+}
+
+pub fn thumbnail_kind(name: &str) -> Option<ThumbnailKind> {
+    let extension = Path::new(name).extension()?.to_string_lossy().to_ascii_uppercase();
+    match extension.as_str() {
+        "GIF" | "PNG" | "JPG" | "JPEG" => Some(ThumbnailKind::Picture),
+        "ANS" | "ICE" | "ASC" | "NFO" => Some(ThumbnailKind::Ansi),
+        _ => None,
+    }
+}
+
+pub fn is_sauce_tagged(data: &[u8]) -> bool {
+    data.len() >= 128 && data[data.len() - 128..].starts_with(b"SAUCE00")
+}
+
+/// Where the contents of a file come from when its thumbnail is not cached yet.
+pub enum ThumbnailSource<'a> {
+    File(&'a Path),
+    Bytes(&'a [u8]),
+}
+
+impl ThumbnailSource<'_> {
+    fn read(&self) -> Option<Vec<u8>> {
+        match self {
+            ThumbnailSource::File(path) => {
+                if fs::metadata(path).ok()?.len() > MAX_SOURCE_BYTES {
+                    return None;
+                }
+                fs::read(path).ok()
+            }
+            ThumbnailSource::Bytes(data) => (data.len() as u64 <= MAX_SOURCE_BYTES).then(|| data.to_vec()),
+        }
+    }
+}
+
+pub fn render_thumbnail(kind: ThumbnailKind, data: &[u8]) -> Option<image::RgbaImage> {
+    let image = match kind {
+        ThumbnailKind::Picture => {
+            let mut limits = image::Limits::default();
+            limits.max_image_width = Some(4096);
+            limits.max_image_height = Some(4096);
+            limits.max_alloc = Some(64 * 1024 * 1024);
+            let mut reader = image::ImageReader::new(std::io::Cursor::new(data));
+            reader.limits(limits);
+            reader.with_guessed_format().ok()?.decode().ok()?
+        }
+        ThumbnailKind::Ansi => image::DynamicImage::ImageRgba8(render_ansi(data)?),
+    };
+    if image.width() <= THUMBNAIL_WIDTH && image.height() <= THUMBNAIL_HEIGHT {
+        return Some(image.to_rgba8());
+    }
+    Some(image.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT).to_rgba8())
+}
+
+const DOS_PALETTE: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0x00, 0x00, 0xAA],
+    [0x00, 0xAA, 0x00],
+    [0x00, 0xAA, 0xAA],
+    [0xAA, 0x00, 0x00],
+    [0xAA, 0x00, 0xAA],
+    [0xAA, 0x55, 0x00],
+    [0xAA, 0xAA, 0xAA],
+    [0x55, 0x55, 0x55],
+    [0x55, 0x55, 0xFF],
+    [0x55, 0xFF, 0x55],
+    [0x55, 0xFF, 0xFF],
+    [0xFF, 0x55, 0x55],
+    [0xFF, 0x55, 0xFF],
+    [0xFF, 0xFF, 0x55],
+    [0xFF, 0xFF, 0xFF],
+];
+
+/// Renders the art as coloured blocks instead of glyphs. At thumbnail size a
+/// letter is a smudge anyway, what has to survive is where the colours are.
+fn render_ansi(data: &[u8]) -> Option<image::RgbaImage> {
+    if !is_sauce_tagged(data) {
+        return None;
+    }
+    let end = data.iter().position(|&byte| byte == 0x1A).unwrap_or(data.len() - 128);
+    let mut screen = icy_engine::TextScreen::new((80, MAX_ANSI_ROWS));
+    let mut parser = icy_parser_core::AnsiParser::default();
+    parser.parse(&data[..end], &mut icy_engine::ScreenSink::new(&mut screen));
+
+    let columns = screen.width().max(1) as u32;
+    let rows = screen.height().clamp(1, MAX_ANSI_ROWS) as u32;
+    let mut image = image::RgbaImage::new(columns * CELL_WIDTH, rows * CELL_HEIGHT);
+    for y in 0..rows {
+        for x in 0..columns {
+            let ch = screen.char_at((x as i32, y as i32).into());
+            let attribute = ch.attribute.as_u8(IceMode::Blink);
+            let foreground = DOS_PALETTE[(attribute & 0x0F) as usize];
+            let background = DOS_PALETTE[((attribute >> 4) & 0x07) as usize];
+            for py in 0..CELL_HEIGHT {
+                for px in 0..CELL_WIDTH {
+                    let weight = foreground_weight(ch.ch, px, py);
+                    let mix = |channel: usize| ((foreground[channel] as u32 * weight + background[channel] as u32 * (255 - weight)) / 255) as u8;
+                    image.put_pixel(x * CELL_WIDTH + px, y * CELL_HEIGHT + py, image::Rgba([mix(0), mix(1), mix(2), 0xFF]));
+                }
+            }
+        }
+    }
+    Some(image)
+}
+
+/// How much of a pixel of the cell the glyph covers. The block characters are
+/// drawn as they are, everything else is a shade of its foreground.
+fn foreground_weight(ch: char, x: u32, y: u32) -> u32 {
+    match ch {
+        ' ' | '\0' | '\u{FF}' => 0,
+        '█' | '\u{DB}' => 255,
+        '▀' | '\u{DF}' => {
+            if y < CELL_HEIGHT / 2 {
+                255
+            } else {
+                0
+            }
+        }
+        '▄' | '\u{DC}' => {
+            if y >= CELL_HEIGHT / 2 {
+                255
+            } else {
+                0
+            }
+        }
+        '▌' | '\u{DD}' => {
+            if x < CELL_WIDTH / 2 {
+                255
+            } else {
+                0
+            }
+        }
+        '▐' | '\u{DE}' => {
+            if x >= CELL_WIDTH / 2 {
+                255
+            } else {
+                0
+            }
+        }
+        '░' | '\u{B0}' => 64,
+        '▒' | '\u{B1}' => 128,
+        '▓' | '\u{B2}' => 192,
+        _ => 100,
+    }
+}
+
+/// The rendered thumbnails of one board, named after the content hash of the
+/// file they show. A file that cannot be rendered leaves an empty marker, so it
+/// is not tried again on every listing.
+pub struct ThumbnailCache {
+    directory: PathBuf,
+}
+
+impl ThumbnailCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into() }
+    }
+
+    pub fn get(&self, hash: u64, kind: ThumbnailKind, source: &ThumbnailSource<'_>) -> Option<GfxSurface> {
+        let path = self.directory.join(format!("{hash:016x}.png"));
+        let failed = self.directory.join(format!("{hash:016x}.none"));
+        if path.exists() {
+            let image = image::open(&path).ok()?.to_rgba8();
+            return GfxSurface::from_rgba(image.width() as usize, image.height() as usize, image.into_raw());
+        }
+        if failed.exists() {
+            return None;
+        }
+
+        let thumbnail = source.read().and_then(|data| render_thumbnail(kind, &data));
+        if let Err(err) = fs::create_dir_all(&self.directory) {
+            log::error!("Can't create thumbnail directory {}: {err}", self.directory.display());
+        } else if let Some(thumbnail) = &thumbnail {
+            if let Err(err) = thumbnail.save_with_format(&path, image::ImageFormat::Png) {
+                log::error!("Can't write thumbnail {}: {err}", path.display());
+            }
+        } else {
+            let _ = fs::write(&failed, []);
+        }
+        let thumbnail = thumbnail?;
+        GfxSurface::from_rgba(thumbnail.width() as usize, thumbnail.height() as usize, thumbnail.into_raw())
+    }
+}
+
+impl IcyBoardState {
+    /// Draws the thumbnail of a listed file on the lines below it, starting at
+    /// `column`. Answers whether there was anything to draw: the board has to
+    /// show thumbnails, the caller's terminal has to draw sixel or JPEG XL, and
+    /// the file has to be a picture or tagged ANSI.
+    pub async fn show_thumbnail(&mut self, name: &str, hash: u64, source: ThumbnailSource<'_>, column: usize) -> Res<bool> {
+        let Some(kind) = thumbnail_kind(name) else {
+            return Ok(false);
+        };
+        let directory = {
+            let board = self.get_board().await;
+            if !board.config.file_transfer.show_thumbnails {
+                return Ok(false);
+            }
+            board.resolve_file(&board.config.paths.tmp_work_path).join("thumbnails")
+        };
+        let capabilities = self.query_gfx_capabilities().await?;
+        let backend = ppl_graphics::resolve_backend(&capabilities, GFX_BACKEND_AUTO);
+        if backend == GFX_BACKEND_NONE {
+            return Ok(false);
+        }
+        let Some(surface) = ThumbnailCache::new(directory).get(hash, kind, &source) else {
+            return Ok(false);
+        };
+
+        // The lines are made first, so a MORE prompt or the screen scrolling
+        // happens before the picture is there to be torn.
+        let cell_height = capabilities.cell_height.max(1) as usize;
+        let rows = surface.height.div_ceil(cell_height);
+        for _ in 0..rows {
+            self.new_line().await?;
+            if self.session.disp_options.abort_printout {
+                return Ok(false);
+            }
+        }
+        self.connection.send(format!("\x1b7\x1b[{rows}A\x1b[{}G", column + 1).as_bytes()).await?;
+        if backend == GFX_BACKEND_JXL {
+            self.draw_jxl_thumbnail(&surface, hash, capabilities.cell_width, capabilities.cell_height)
+                .await?;
+        } else if let Some(sixel) = surface.encode_sixel() {
+            self.connection.send(sixel.as_bytes()).await?;
+        }
+        self.connection.send(b"\x1b8").await?;
+        Ok(true)
+    }
+
+    /// The image APC places by pixel, so the terminal is asked where the
+    /// cursor is. The thumbnail goes into the caller's cache under the file's
+    /// hash and is uploaded once, however often it is listed.
+    async fn draw_jxl_thumbnail(&mut self, surface: &GfxSurface, hash: u64, cell_width: i32, cell_height: i32) -> Res<()> {
+        use base64::{Engine as _, engine::general_purpose};
+
+        let position = OnceLock::new();
+        self.query_terminal_csi(b"\x1b[6n", |reply| {
+            let body = reply.strip_prefix("\x1b[")?.strip_suffix('R')?;
+            let (row, column) = body.split_once(';')?;
+            let _ = position.set((row.parse::<i32>().ok()?, column.parse::<i32>().ok()?));
+            Some(true)
+        })
+        .await?;
+        let Some((row, column)) = position.get().copied() else {
+            return Ok(());
+        };
+
+        let name = format!("{}t{hash:016x}.jxl", ppl_graphics::CACHE_PREFIX);
+        if !self.gfx_cache.contains(&name) {
+            let Some(encoded) = surface.encode_jxl() else {
+                return Ok(());
+            };
+            if !self.reserve_media_upload(encoded.len()) {
+                return Ok(());
+            }
+            let payload = general_purpose::STANDARD.encode(&encoded);
+            self.connection.send(format!("\x1b_SyncTERM:C;S;{name};{payload}\x1b\\").as_bytes()).await?;
+            self.gfx_cache.insert(name.clone());
+            self.acknowledge_upload(payload.len()).await?;
+        }
+        let x = (column - 1).max(0) * cell_width;
+        let y = (row - 1).max(0) * cell_height;
+        self.connection
+            .send(format!("\x1b_SyncTERM:C;DrawJXL;DX={x};DY={y};{name}\x1b\\").as_bytes())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sauce(mut data: Vec<u8>) -> Vec<u8> {
+        data.push(0x1A);
+        let mut record = b"SAUCE00".to_vec();
+        record.resize(128, b' ');
+        data.extend(record);
+        data
+    }
+
+    #[test]
+    fn test_only_tagged_ansi_is_rendered() {
+        assert_eq!(thumbnail_kind("LOGO.ANS"), Some(ThumbnailKind::Ansi));
+        assert_eq!(thumbnail_kind("photo.jpeg"), Some(ThumbnailKind::Picture));
+        assert_eq!(thumbnail_kind("GAME.ZIP"), None);
+
+        assert!(render_thumbnail(ThumbnailKind::Ansi, b"\x1b[31mred").is_none());
+        let image = render_thumbnail(ThumbnailKind::Ansi, &sauce(b"\x1b[0;31m\xDB".to_vec())).unwrap();
+        assert!(image.width() <= THUMBNAIL_WIDTH && image.height() <= THUMBNAIL_HEIGHT);
+        let [red, green, _, _] = image.get_pixel(0, 0).0;
+        assert!(red > 0x80 && green < 0x20, "the block should come out red");
+    }
+
+    #[test]
+    fn test_a_picture_is_shrunk_to_fit() {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(640, 240, image::Rgba([1, 2, 3, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let image = render_thumbnail(ThumbnailKind::Picture, &png).unwrap();
+        assert_eq!((image.width(), image.height()), (THUMBNAIL_WIDTH, 60));
+    }
+
+    #[test]
+    fn test_a_thumbnail_is_rendered_once_per_hash() {
+        let directory = tempfile::TempDir::new().unwrap();
+        let cache = ThumbnailCache::new(directory.path());
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(4, 4, image::Rgba([9, 9, 9, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        assert!(cache.get(7, ThumbnailKind::Picture, &ThumbnailSource::Bytes(&png)).is_some());
+        // Served from the cache, the source is not looked at again.
+        let surface = cache.get(7, ThumbnailKind::Picture, &ThumbnailSource::Bytes(b"garbage")).unwrap();
+        assert_eq!((surface.width, surface.height), (4, 4));
+
+        assert!(cache.get(8, ThumbnailKind::Picture, &ThumbnailSource::Bytes(b"garbage")).is_none());
+        assert!(directory.path().join(format!("{:016x}.none", 8)).exists());
+    }
+}
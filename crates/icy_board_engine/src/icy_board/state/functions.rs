@@ -195,7 +195,7 @@ impl IcyBoardState {
     pub async fn display_line(&mut self, txt: &str) -> Res<()> {
         if !txt.is_empty() {
             if let Some(call) = PPECall::try_parse_line(txt) {
-                let file = self.get_board().await.resolve_file(&dos_path(&call.file));
+                let file = self.resolve_call_file(&call).await;
                 let found = match call.call_type {
                     PPECallType::Menu => file.exists() || self.get_board().await.resolve_file(&file.with_extension("ppe")).exists(),
                     _ => file.exists(),
@@ -206,21 +206,26 @@ impl IcyBoardState {
                     self.print(TerminalTarget::Both, txt).await?;
                     return Ok(());
                 }
+                // Shown for a restricted PPE, a line can't start or show more than the PPE could.
+                if let Some(sandbox) = &self.ppe_sandbox {
+                    if !sandbox.allows_read(&file) {
+                        log::warn!("not running the line '{txt}', the PPE is not allowed to read {}", file.display());
+                        return Ok(());
+                    }
+                    self.ppe_grant = Some(sandbox.grant_for_callee());
+                }
                 for sc in call.arguments {
                     self.session.tokens.push_back(sc.clone());
                 }
-                match call.call_type {
-                    PPECallType::PPE => {
-                        self.run_ppe(&file, None).await?;
-                    }
-                    PPECallType::Menu => {
-                        let _ = self.display_menu(&file).await?;
-                    }
-                    PPECallType::File => {
-                        let _ = self.display_file(&file).await?;
-                    }
+                let result = match call.call_type {
+                    PPECallType::PPE => self.run_ppe(&file, None).await.map(|_| ()),
+                    PPECallType::Menu => self.display_menu(&file).await.map(|_| ()),
+                    PPECallType::File => self.display_file(&file).await.map(|_| ()),
+                };
+                if self.ppe_sandbox.is_some() {
+                    self.ppe_grant = None;
                 }
-                return Ok(());
+                return result;
             }
             // display text
             self.print(TerminalTarget::Both, txt).await?;
@@ -228,6 +233,11 @@ impl IcyBoardState {
         Ok(())
     }
 
+    /// Where the file a `!`, `$` or `%` line names is.
+    pub async fn resolve_call_file(&self, call: &PPECall) -> PathBuf {
+        self.get_board().await.resolve_file(&dos_path(&call.file))
+    }
+
     pub async fn display_menu<P: AsRef<Path>>(&mut self, file_name: &P) -> Res<bool> {
         let resolved_name_ppe = self.get_board().await.resolve_file(&(file_name.as_ref().with_extension("ppe")));
         let path = resolved_name_ppe;
@@ -263,18 +273,7 @@ impl IcyBoardState {
             }
             return Ok(false);
         };
-        let converted_content = if content.starts_with(&UTF8_BOM) {
-            String::from_utf8_lossy(&content[3..]).to_string()
-        } else {
-            let mut s: String = String::new();
-            for byte in content {
-                if byte == 0x1A {
-                    break;
-                }
-                s.push(CP437_TO_UNICODE[byte as usize]);
-            }
-            s
-        };
+        let converted_content = decode_display_file(&content);
 
         self.displayed_files.push(resolved_name);
         let result = self.display_file_content(&converted_content).await;
@@ -283,6 +282,13 @@ impl IcyBoardState {
         Ok(true)
     }
 
+    /// Shows text that does not come from a file of the board, like a member
+    /// of an archive, the same way a display file is shown.
+    pub async fn display_bytes(&mut self, content: &[u8]) -> Res<()> {
+        self.session.disp_options.abort_printout = false;
+        self.display_file_content(&decode_display_file(content)).await
+    }
+
     async fn display_file_content(&mut self, converted_content: &str) -> Res<()> {
         for (i, line) in converted_content.lines().enumerate() {
             if i > 0 {
@@ -682,6 +688,21 @@ impl IcyBoardState {
     }
 }
 
+/// Display files are CP437 up to the DOS end of file, unless a BOM says UTF-8.
+fn decode_display_file(content: &[u8]) -> String {
+    if content.starts_with(&UTF8_BOM) {
+        return String::from_utf8_lossy(&content[3..]).to_string();
+    }
+    let mut s: String = String::new();
+    for byte in content {
+        if *byte == 0x1A {
+            break;
+        }
+        s.push(CP437_TO_UNICODE[*byte as usize]);
+    }
+    s
+}
+
 fn transfer_log_line(upload: bool, user_name: &str, time: DateTime<Local>, file_name: &str, protocol: &str, errors: usize, cps: usize) -> String {
     format!(
         "({}),{},{},{},{},{},{},{}\r\n",
@@ -555,6 +555,14 @@ impl IcyBoardState {
                 // 16
                 self.dir_command().await?;
             }
+            CommandType::PendingUploads => {
+                let sec = self.session.sysop_command_level.view_private_uploads.clone();
+                if check_security && !self.check_sec("17", &sec).await? {
+                    return Ok(());
+                }
+                // 17
+                self.review_pending_uploads().await?;
+            }
             CommandType::ReadEmail => {
                 let sec = self.session.user_command_level.cmd_r.clone();
                 if check_security && !self.check_sec("@", &sec).await? {
@@ -580,7 +588,10 @@ impl IcyBoardState {
                 if !cmd_action.parameter.is_empty() {
                     self.session.push_tokens(&cmd_action.parameter);
                 }
-                self.ppe_run().await?;
+                self.ppe_grant = cmd_action.permissions.clone();
+                let result = self.ppe_run().await;
+                self.ppe_grant = None;
+                result?;
             }
             CommandType::TextSearch => {
                 let sec = self.session.user_command_level.cmd_r.clone();
@@ -30,14 +30,16 @@ use tokio::{sync::Mutex, time::sleep};
 
 use crate::{
     icy_board::IcyBoardError,
-    vm::{DiskIO, TerminalTarget, run},
+    vm::{DiskIO, Permissions, PpeDebugger, Profiler, Sandbox, TerminalTarget, VMError, run},
 };
+pub mod file_thumbnails;
 pub mod functions;
 pub mod menu_runner;
 pub mod ppl_audio;
 pub mod ppl_error;
 pub mod ppl_events;
 pub mod ppl_graphics;
+pub mod ppl_json;
 pub mod ppl_keys;
 pub mod ppl_mouse;
 pub mod ppl_surface;
@@ -136,6 +138,8 @@ pub struct PPEExecute {
     pub user_name: Option<String>,
     pub password: Option<String>,
     pub args: Vec<String>,
+    pub debugger: Option<PpeDebugger>,
+    pub profiler: Option<Profiler>,
 }
 
 impl DisplayOptions {
@@ -252,6 +256,9 @@ pub struct Session {
     /// After it's elapsed logoff the user for inactivity.
     pub keyboard_timer_check: bool,
     pub keyboard_timer_started: Instant,
+    /// When the node last sent output or looked for input. The PPE watchdog measures
+    /// how long a program has sat idle from here.
+    pub last_io: Instant,
 
     pub tokens: VecDeque<String>,
 
@@ -340,6 +347,7 @@ impl Session {
             time_adjusted_for_event: false,
             keyboard_timer_check: true,
             keyboard_timer_started: Instant::now(),
+            last_io: Instant::now(),
             request_logoff: false,
             tokens: VecDeque::new(),
             last_password: String::new(),
@@ -644,6 +652,16 @@ pub struct IcyBoardState {
     displayed_files: Vec<PathBuf>,
     /// How many PPEs are running on top of each other.
     ppe_nesting: usize,
+    /// Taken by the next PPE that runs, which then stops where the debugger says.
+    pub ppe_debugger: Option<PpeDebugger>,
+    /// Taken by the next PPE that runs: what the door or menu entry starting it allows.
+    pub ppe_grant: Option<Permissions>,
+    /// The sandbox of the PPE running right now, which what it displays is held to as well.
+    pub ppe_sandbox: Option<Sandbox>,
+    /// Left here for every PPE that runs to add its counts to, until it is taken back.
+    pub ppe_profiler: Option<Profiler>,
+    /// Every PPE that runs from now on is stopped when it is still running by then.
+    pub ppe_deadline: Option<Instant>,
 
     /// Where `OPENCAP` is teeing everything the caller sees, until `CLOSECAP`.
     capture_file: Option<std::fs::File>,
@@ -763,6 +781,11 @@ impl IcyBoardState {
             file_bases: HashMap::new(),
             displayed_files: Vec::new(),
             ppe_nesting: 0,
+            ppe_debugger: None,
+            ppe_grant: None,
+            ppe_sandbox: None,
+            ppe_profiler: None,
+            ppe_deadline: None,
             capture_file: None,
             sound_cache: HashSet::new(),
             media_upload_bytes: 0,
@@ -1158,6 +1181,11 @@ impl IcyBoardState {
             Ok(keep_answers) => Ok(keep_answers),
             Err(err) => {
                 log::error!("Error executing PPE {}: {}", canonicalized_path.display(), err);
+                if let Some(VMError::Located(_, _, call_stack)) = err.downcast_ref::<VMError>() {
+                    for call in call_stack {
+                        log::error!("    {}", call);
+                    }
+                }
                 self.session.op_text = format!("{err}");
                 self.display_text(IceText::ErrorExecPPE, display_flags::LFBEFORE | display_flags::LFAFTER)
                     .await?;
@@ -1327,6 +1355,7 @@ impl IcyBoardState {
             "12" => convert_cmd(CommandType::LogoffNode),
             "13" => convert_cmd(CommandType::NodeCallerLog),
             "16" => convert_cmd(CommandType::DirCommand),
+            "17" => convert_cmd(CommandType::PendingUploads),
             "@" => convert_cmd(CommandType::ReadEmail),
             "@W" => convert_cmd(CommandType::WriteEmail),
             _ => {
@@ -2212,6 +2241,7 @@ impl IcyBoardState {
     }
 
     async fn write_chars_internal(&mut self, target: TerminalTarget, user_bytes: &[u8], sysop_bytes: &[u8]) -> Res<()> {
+        self.session.last_io = Instant::now();
         if (target != TerminalTarget::Sysop || self.session.is_sysop) && !user_bytes.is_empty() {
             // A capture sees what the caller sees, whether or not the display is on.
             if let Some(file) = &mut self.capture_file {
@@ -2813,6 +2843,7 @@ impl IcyBoardState {
 
     /// # Errors
     pub async fn get_char(&mut self, target: TerminalTarget) -> Res<Option<KeyChar>> {
+        self.session.last_io = Instant::now();
         self.drain_raw_input();
         self.drain_stale_protocol_input();
         let stale = self.ppl_mouse.take_stale_keyboard();
@@ -3726,6 +3757,7 @@ fn convert_cmd(cmd_type: CommandType) -> Option<Command> {
             command_type: cmd_type,
             parameter: String::new(),
             trigger: crate::icy_board::commands::ActionTrigger::default(),
+            permissions: None,
         }],
         security: SecurityExpression::from_req_security(0),
     })
@@ -13,6 +13,9 @@ pub const ERR_KIND_STACK: i32 = 3;
 pub const ERR_KIND_GFX: i32 = 4;
 pub const ERR_KIND_FONT: i32 = 5;
 pub const ERR_KIND_SOUND: i32 = 6;
+pub const ERR_KIND_SANDBOX: i32 = 7;
+pub const ERR_KIND_JSON: i32 = 8;
+pub const ERR_KIND_WEB: i32 = 9;
 
 /// What went wrong. The same codes describe every subsystem, so one piece of
 /// code can handle a file, a font, a sound or a picture going wrong.
@@ -24,6 +27,7 @@ pub const ERR_FORMAT: i32 = 4;
 pub const ERR_LIMIT: i32 = 5;
 pub const ERR_UNSUPPORTED: i32 = 6;
 pub const ERR_STACK: i32 = 7;
+pub const ERR_DENIED: i32 = 8;
 
 /// What `Channel` answers for an error that is not bound to one.
 pub const NO_CHANNEL: i32 = -1;
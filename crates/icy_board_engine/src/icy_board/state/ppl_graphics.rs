@@ -222,6 +222,34 @@ impl GfxSurface {
         }
         Self::from_rgba(output_width, output_height, pixels)
     }
+
+    pub fn encode_sixel(&self) -> Option<String> {
+        let options = icy_sixel::EncodeOptions::default();
+        match icy_sixel::sixel_encode(&self.pixels, self.width, self.height, &options) {
+            Ok(encoded) => Some(encoded),
+            Err(err) => {
+                log::warn!("sixel encode failed: {err}");
+                None
+            }
+        }
+    }
+
+    /// A composed image is opaque, so the alpha channel is dropped rather than encoded.
+    pub fn encode_jxl(&self) -> Option<Vec<u8>> {
+        use zune_core::{bit_depth::BitDepth, colorspace::ColorSpace, options::EncoderOptions};
+        use zune_jpegxl::JxlSimpleEncoder;
+
+        let rgb: Vec<u8> = self.pixels.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+        let options = EncoderOptions::new(self.width, self.height, ColorSpace::RGB, BitDepth::Eight);
+        let mut encoded = Vec::new();
+        match JxlSimpleEncoder::new(&rgb, options).encode(&mut encoded) {
+            Ok(_) => Some(encoded),
+            Err(err) => {
+                log::warn!("JPEG XL encode failed: {err:?}");
+                None
+            }
+        }
+    }
 }
 
 pub struct PplGraphicsState {
@@ -0,0 +1,357 @@
+use std::fmt;
+
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+};
+use serde_json::Number;
+
+use crate::{
+    compiler::user_data::{UserData, UserDataMemberRegistry, UserDataValue, user_data_value},
+    executable::{VariableType, VariableValue},
+    parser::JSON_ID,
+};
+
+macro_rules! member_name {
+    ($vis:vis $name:ident, $value:literal) => {
+        $vis static $name: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new($value.to_string()));
+    };
+}
+
+member_name!(KIND, "Kind");
+member_name!(COUNT, "Count");
+member_name!(pub(crate) TEXT, "Text");
+member_name!(AS_STRING, "AsString");
+member_name!(AS_INTEGER, "AsInteger");
+member_name!(AS_DOUBLE, "AsDouble");
+member_name!(AS_BOOLEAN, "AsBoolean");
+member_name!(GET, "Get");
+member_name!(ITEM, "Item");
+member_name!(KEY, "Key");
+member_name!(HAS, "Has");
+
+/// What `Kind` answers, the `JSON_*` constants of a PPE.
+pub const JSON_NULL: i32 = 0;
+pub const JSON_BOOLEAN: i32 = 1;
+pub const JSON_NUMBER: i32 = 2;
+pub const JSON_STRING: i32 = 3;
+pub const JSON_ARRAY: i32 = 4;
+pub const JSON_OBJECT: i32 = 5;
+
+/// A JSON document that keeps an object's keys in the order they came in, so a
+/// record reads back the way it was declared. `serde_json::Value` only does that
+/// with its `preserve_order` feature, which would switch it on for every crate
+/// of the workspace.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Array(Vec<Value>),
+    Object(IndexMap<String, Value>),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+/// The compact text, the way `serde_json` writes it.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&serde_json::to_string(self).map_err(|_| fmt::Error)?)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(value) => serializer.serialize_bool(*value),
+            Value::Number(number) => number.serialize(serializer),
+            Value::String(text) => serializer.serialize_str(text),
+            Value::Array(items) => items.serialize(serializer),
+            Value::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    /// A key that comes twice keeps its first place and its last value.
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = IndexMap::new();
+        while let Some((key, value)) = map.next_entry::<String, Value>()? {
+            entries.insert(key, value);
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+/// A JSON value whose shape the program does not know up front. Asking for a key
+/// or an item that is not there answers with null, so a lookup can be chained
+/// without checking every step.
+#[derive(Clone, Debug, Default)]
+pub struct PplJson {
+    value: Value,
+}
+
+impl PplJson {
+    pub fn new(value: Value) -> Self {
+        Self { value }
+    }
+
+    pub fn value(self) -> VariableValue {
+        user_data_value(self, JSON_ID)
+    }
+
+    pub fn kind(&self) -> i32 {
+        match &self.value {
+            Value::Null => JSON_NULL,
+            Value::Bool(_) => JSON_BOOLEAN,
+            Value::Number(_) => JSON_NUMBER,
+            Value::String(_) => JSON_STRING,
+            Value::Array(_) => JSON_ARRAY,
+            Value::Object(_) => JSON_OBJECT,
+        }
+    }
+
+    fn count(&self) -> i32 {
+        let len = match &self.value {
+            Value::Array(items) => items.len(),
+            Value::Object(entries) => entries.len(),
+            _ => 0,
+        };
+        i32::try_from(len).unwrap_or(i32::MAX)
+    }
+
+    /// A key is looked up the way PPL looks up a name, so `Get("name")` finds `"Name"`.
+    fn get(&self, key: &str) -> Value {
+        let Value::Object(entries) = &self.value else {
+            return Value::Null;
+        };
+        entries
+            .get(key)
+            .or_else(|| entries.iter().find_map(|(name, value)| name.eq_ignore_ascii_case(key).then_some(value)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn has(&self, key: &str) -> bool {
+        let Value::Object(entries) = &self.value else {
+            return false;
+        };
+        entries.keys().any(|name| name.eq_ignore_ascii_case(key))
+    }
+
+    fn item(&self, index: i32) -> Value {
+        let Value::Array(items) = &self.value else {
+            return Value::Null;
+        };
+        usize::try_from(index).ok().and_then(|index| items.get(index)).cloned().unwrap_or_default()
+    }
+
+    fn key(&self, index: i32) -> String {
+        let Value::Object(entries) = &self.value else {
+            return String::new();
+        };
+        usize::try_from(index)
+            .ok()
+            .and_then(|index| entries.get_index(index))
+            .map(|(key, _)| key.clone())
+            .unwrap_or_default()
+    }
+
+    fn as_string(&self) -> String {
+        match &self.value {
+            Value::Null => String::new(),
+            Value::String(text) => text.clone(),
+            value => value.to_string(),
+        }
+    }
+
+    fn as_double(&self) -> f64 {
+        match &self.value {
+            Value::Bool(value) => f64::from(u8::from(*value)),
+            Value::Number(number) => number.as_f64().unwrap_or_default(),
+            Value::String(text) => text.trim().parse().unwrap_or_default(),
+            _ => 0.0,
+        }
+    }
+
+    fn as_integer(&self) -> i32 {
+        match &self.value {
+            Value::Number(number) => match number.as_i64() {
+                Some(value) => value.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32,
+                None => self.as_double() as i32,
+            },
+            Value::String(text) => text.trim().parse().unwrap_or_else(|_| self.as_double() as i32),
+            _ => self.as_double() as i32,
+        }
+    }
+
+    fn as_boolean(&self) -> bool {
+        match &self.value {
+            Value::Bool(value) => *value,
+            Value::Number(_) => self.as_double() != 0.0,
+            Value::String(text) => text.eq_ignore_ascii_case("true"),
+            _ => false,
+        }
+    }
+}
+
+impl UserData for PplJson {
+    const TYPE_NAME: &'static str = "Json";
+
+    fn register_members<F: UserDataMemberRegistry>(registry: &mut F) {
+        let json = VariableType::UserData(JSON_ID as u8);
+        registry.add_property(KIND.clone(), VariableType::Integer, false);
+        registry.add_property(COUNT.clone(), VariableType::Integer, false);
+        registry.add_property(TEXT.clone(), VariableType::BigStr, false);
+        registry.add_property(AS_STRING.clone(), VariableType::BigStr, false);
+        registry.add_property(AS_INTEGER.clone(), VariableType::Integer, false);
+        registry.add_property(AS_DOUBLE.clone(), VariableType::Double, false);
+        registry.add_property(AS_BOOLEAN.clone(), VariableType::Boolean, false);
+        registry.add_function(GET.clone(), vec![VariableType::String], json);
+        registry.add_function(ITEM.clone(), vec![VariableType::Integer], json);
+        registry.add_function(KEY.clone(), vec![VariableType::Integer], VariableType::String);
+        registry.add_function(HAS.clone(), vec![VariableType::String], VariableType::Boolean);
+    }
+}
+
+#[async_trait]
+impl UserDataValue for PplJson {
+    fn get_property_value(&self, _vm: &crate::vm::VirtualMachine, name: &unicase::Ascii<String>) -> crate::Res<VariableValue> {
+        let value = if *name == *KIND {
+            VariableValue::new_int(self.kind())
+        } else if *name == *COUNT {
+            VariableValue::new_int(self.count())
+        } else if *name == *TEXT {
+            VariableValue::new_string(self.value.to_string())
+        } else if *name == *AS_STRING {
+            VariableValue::new_string(self.as_string())
+        } else if *name == *AS_INTEGER {
+            VariableValue::new_int(self.as_integer())
+        } else if *name == *AS_DOUBLE {
+            VariableValue::new_double(self.as_double())
+        } else if *name == *AS_BOOLEAN {
+            VariableValue::new_bool(self.as_boolean())
+        } else {
+            return Err(format!("Unknown JSON property {name}").into());
+        };
+        Ok(value)
+    }
+
+    fn set_property_value(&mut self, _vm: &mut crate::vm::VirtualMachine<'_>, _name: &unicase::Ascii<String>, _val: VariableValue) -> crate::Res<()> {
+        Ok(())
+    }
+
+    async fn call_function(
+        &self,
+        _vm: &mut crate::vm::VirtualMachine<'_>,
+        name: &unicase::Ascii<String>,
+        arguments: &[VariableValue],
+    ) -> crate::Res<VariableValue> {
+        let Some(argument) = arguments.first() else {
+            return Err(format!("JSON function {name} needs an argument").into());
+        };
+        if *name == *GET {
+            return Ok(PplJson::new(self.get(&argument.as_string())).value());
+        }
+        if *name == *ITEM {
+            return Ok(PplJson::new(self.item(argument.as_int())).value());
+        }
+        if *name == *KEY {
+            return Ok(VariableValue::new_string(self.key(argument.as_int())));
+        }
+        if *name == *HAS {
+            return Ok(VariableValue::new_bool(self.has(&argument.as_string())));
+        }
+        Err(format!("Unknown JSON function {name}").into())
+    }
+
+    async fn call_method(&mut self, _vm: &mut crate::vm::VirtualMachine<'_>, name: &unicase::Ascii<String>, _arguments: &[VariableValue]) -> crate::Res<()> {
+        Err(format!("Unknown JSON method {name}").into())
+    }
+}
@@ -0,0 +1,289 @@
+//! Files that travel with a message. The upload is kept below the conference's
+//! attachment location and the message names it in a JAM enclosed file subfield.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use bstr::BString;
+use chrono::Utc;
+use jamjam::jam::{
+    JamMessageBase,
+    msg_header::{JamMessageHeader, MessageSubfield, SubfieldType},
+};
+
+use crate::{
+    Res,
+    icy_board::{
+        icb_text::IceText,
+        state::{IcyBoardState, functions::display_flags, user_commands::pcb::u_upload_file::create_protocol},
+    },
+    vm::TerminalTarget,
+};
+
+/// The file attached to a message, if it has one.
+pub fn attachment(header: &JamMessageHeader) -> Option<PathBuf> {
+    header
+        .sub_fields
+        .iter()
+        .find(|field| field.field_type() == SubfieldType::EnclosedFile)
+        .map(|field| PathBuf::from(field.content().to_string()))
+}
+
+pub fn attachment_subfield(path: &Path) -> MessageSubfield {
+    MessageSubfield::new(SubfieldType::EnclosedFile, BString::from(path.display().to_string()))
+}
+
+/// Every attachment the messages of a base still point to.
+pub fn referenced_attachments(message_base: &JamMessageBase) -> HashSet<PathBuf> {
+    message_base
+        .messages()
+        .flatten()
+        .filter(|header| !header.is_deleted())
+        .filter_map(|header| attachment(&header))
+        .collect()
+}
+
+/// Copies an upload into its own directory below `location`, so two callers
+/// attaching `README.TXT` don't overwrite each other and the download keeps the name.
+pub fn store_attachment(location: &Path, name: &str, upload: &Path) -> Res<PathBuf> {
+    let name = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "ATTACH.DAT".to_string());
+    let stamp = Utc::now().format("%Y%m%d%H%M%S");
+    let mut n = 0;
+    let dir = loop {
+        let dir = location.join(format!("{stamp}-{n}"));
+        if !dir.exists() {
+            break dir;
+        }
+        n += 1;
+    };
+    std::fs::create_dir_all(&dir)?;
+    let dest = dir.join(name);
+    std::fs::copy(upload, &dest)?;
+    Ok(dest)
+}
+
+/// The attachment a message names, as long as it really is below `location`.
+/// The subfield is just text in the message base, so it isn't trusted to point
+/// at a file the board stored.
+pub fn stored_attachment(location: &Path, path: &Path) -> Option<PathBuf> {
+    if location.as_os_str().is_empty() {
+        log::warn!("Ignoring attachment {}, the conference has no attachment location", path.display());
+        return None;
+    }
+    let inside = match (location.canonicalize(), path.canonicalize()) {
+        (Ok(location), Ok(path)) if path != location && path.starts_with(&location) => Some(path),
+        _ => None,
+    };
+    if inside.is_none() {
+        log::warn!("Ignoring attachment {}, it isn't below {}", path.display(), location.display());
+    }
+    inside
+}
+
+/// Deletes the attachments below `location` only removed messages pointed to. Returns how many went.
+pub fn purge_attachments(location: &Path, before: &HashSet<PathBuf>, after: &HashSet<PathBuf>) -> usize {
+    let mut purged = 0;
+    for path in before.difference(after) {
+        let Some(path) = stored_attachment(location, path) else {
+            continue;
+        };
+        match std::fs::remove_file(&path) {
+            Ok(()) => {
+                purged += 1;
+                // The directory store_attachment made for it; fails harmlessly when not empty.
+                if let Some(dir) = path.parent() {
+                    let _ = std::fs::remove_dir(dir);
+                }
+            }
+            Err(err) => log::error!("Can't remove attachment {}: {err}", path.display()),
+        }
+    }
+    purged
+}
+
+impl IcyBoardState {
+    /// Asks for the file to attach to a message about to be saved in `conf`/`area`,
+    /// a negative conference being the recipient's mailbox. Mail keeps its attachments
+    /// with the main conference, so they are found whatever conference it is read from.
+    pub async fn receive_attachment(&mut self, conf: i32, area: i32) -> Res<Option<PathBuf>> {
+        let (allowed, location) = {
+            let board = self.get_board().await;
+            let conference = &board.conferences[conf.max(0) as usize];
+            let mut allowed = conference.sec_attachments.session_can_access(&self.session);
+            if let Some(area) = conference.areas.as_ref().and_then(|areas| areas.get(area as usize)).filter(|_| conf >= 0) {
+                allowed &= area.req_level_to_save_attach.session_can_access(&self.session);
+            }
+            (
+                allowed && !conference.attachment_location.as_os_str().is_empty(),
+                conference.attachment_location.clone(),
+            )
+        };
+        if !allowed {
+            self.display_text(IceText::AttachNotAllOWed, display_flags::NEWLINE | display_flags::LFBEFORE)
+                .await?;
+            return Ok(None);
+        }
+        let location = self.resolve_path(&location);
+
+        let cur_protocol = if let Some(user) = &self.session.current_user {
+            user.protocol.clone()
+        } else {
+            String::new()
+        };
+        let prot_str = self.ask_protocols(&cur_protocol).await?;
+        if prot_str.is_empty() {
+            return Ok(None);
+        }
+        let Some(protocol) = self.get_protocol(prot_str).await else {
+            return Ok(None);
+        };
+        let Some(mut prot) = create_protocol(&protocol) else {
+            self.display_text(IceText::TransferAborted, display_flags::NEWLINE).await?;
+            return Ok(None);
+        };
+
+        let mut stored = None;
+        match prot.initiate_recv(&mut *self.connection).await {
+            Ok(mut state) => {
+                while !state.is_finished {
+                    if let Err(e) = prot.update_transfer(&mut *self.connection, &mut state).await {
+                        log::error!("Error while updating file transfer with {protocol:?} : {e}");
+                        self.display_text(IceText::TransferAborted, display_flags::NEWLINE).await?;
+                        break;
+                    }
+                }
+                // A message carries one file; anything else sent along is dropped.
+                for (name, path) in state.recieve_state.finished_files {
+                    if stored.is_none() {
+                        stored = Some(store_attachment(&location, &name, &path)?);
+                    }
+                    std::fs::remove_file(&path)?;
+                }
+                if let Some(path) = &stored {
+                    self.display_text(IceText::TransferSuccessful, display_flags::NEWLINE | display_flags::LFBEFORE)
+                        .await?;
+                    self.session.op_text = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                    self.display_text(IceText::Attachment, display_flags::NEWLINE).await?;
+                }
+            }
+            Err(e) => {
+                log::error!("Error while initiating file transfer with {protocol:?} : {e}");
+                self.println(TerminalTarget::Both, &format!("Error: {e}")).await?;
+            }
+        }
+        Ok(stored)
+    }
+
+    /// Where the attachments of messages in `conf` are kept, empty when it takes none.
+    /// The mailbox, a negative conference, keeps them with the main conference.
+    pub(crate) async fn attachment_location(&self, conf: i32) -> PathBuf {
+        let location = self
+            .get_board()
+            .await
+            .conferences
+            .get(conf.max(0) as usize)
+            .map(|conference| conference.attachment_location.clone())
+            .unwrap_or_default();
+        if location.as_os_str().is_empty() {
+            return PathBuf::new();
+        }
+        self.resolve_path(&location)
+    }
+
+    /// FLAG in the reader: queue the attachment of a message in `conf` and offer the download.
+    pub(crate) async fn download_attachment(&mut self, conf: i32, path: &Path) -> Res<()> {
+        let location = self.attachment_location(conf).await;
+        let Some(path) = stored_attachment(&location, path) else {
+            self.display_text(IceText::AttachmentMissing, display_flags::NEWLINE | display_flags::LFBEFORE)
+                .await?;
+            return Ok(());
+        };
+        self.add_flagged_file(&path, false, true).await?;
+        self.download(true).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use jamjam::jam::msg_header::JamMessageHeader;
+
+    use super::{attachment, attachment_subfield, purge_attachments, store_attachment, stored_attachment};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("attachments-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn the_subfield_names_the_attachment() {
+        let path = std::path::Path::new("/bbs/attach/1/README.TXT");
+        let header = JamMessageHeader {
+            sub_fields: vec![attachment_subfield(path)],
+            ..Default::default()
+        };
+        assert_eq!(attachment(&header).as_deref(), Some(path));
+        assert_eq!(attachment(&JamMessageHeader::default()), None);
+    }
+
+    #[test]
+    fn uploads_with_the_same_name_do_not_collide() {
+        let dir = temp_dir("store");
+        let upload = dir.join("upload.tmp");
+        std::fs::write(&upload, b"data").unwrap();
+        let first = store_attachment(&dir.join("attach"), "README.TXT", &upload).unwrap();
+        let second = store_attachment(&dir.join("attach"), "../README.TXT", &upload).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.file_name().unwrap(), "README.TXT");
+        assert_eq!(second.file_name().unwrap(), "README.TXT");
+        assert!(second.starts_with(dir.join("attach")));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn only_attachments_no_message_points_to_are_purged() {
+        let dir = temp_dir("purge");
+        let upload = dir.join("upload.tmp");
+        std::fs::write(&upload, b"data").unwrap();
+        let kept = store_attachment(&dir, "KEEP.ZIP", &upload).unwrap();
+        let gone = store_attachment(&dir, "GONE.ZIP", &upload).unwrap();
+        let before: HashSet<_> = [kept.clone(), gone.clone()].into_iter().collect();
+        let after: HashSet<_> = [kept.clone()].into_iter().collect();
+        assert_eq!(purge_attachments(&dir, &before, &after), 1);
+        assert!(kept.exists());
+        assert!(!gone.exists());
+        assert!(!gone.parent().unwrap().exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn an_attachment_outside_the_location_is_left_alone() {
+        let dir = temp_dir("outside");
+        let location = dir.join("attach");
+        std::fs::create_dir_all(&location).unwrap();
+        let upload = dir.join("upload.tmp");
+        std::fs::write(&upload, b"data").unwrap();
+        let stored = store_attachment(&location, "KEEP.ZIP", &upload).unwrap();
+        let escaped = location.join("..").join("upload.tmp");
+
+        assert_eq!(stored_attachment(&location, &stored), Some(stored.canonicalize().unwrap()));
+        assert_eq!(stored_attachment(&location, &upload), None);
+        assert_eq!(stored_attachment(&location, &escaped), None);
+        assert_eq!(stored_attachment(&location, &location), None);
+        assert_eq!(stored_attachment(std::path::Path::new(""), &stored), None);
+
+        let before: HashSet<_> = [upload.clone(), escaped].into_iter().collect();
+        assert_eq!(purge_attachments(&location, &before, &HashSet::new()), 0);
+        assert!(upload.exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt::Write as _, path::Path};
+
+use crate::{
+    Res,
+    icy_board::{
+        doors::Door,
+        icb_config::EditorInfoFile,
+        state::{IcyBoardState, NodeStatus},
+    },
+    parser::{Encoding, load_with_encoding},
+};
+
+use super::{EditResult, EditState};
+
+/// The draft file QEdit-style editors read and write back.
+const DRAFT_FILE: &str = "MSGTMP";
+
+/// What the board knows about the message when the external editor starts.
+pub struct EditorInfo<'a> {
+    pub area: &'a str,
+    pub private: bool,
+    pub user_number: i32,
+    pub real_name: &'a str,
+    pub security: u8,
+    pub screen_lines: u16,
+}
+
+impl EditorInfoFile {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            EditorInfoFile::MsgInf => "MSGINF",
+            EditorInfoFile::EditorInf => "EDITOR.INF",
+        }
+    }
+
+    pub fn contents(&self, editor: &EditState, info: &EditorInfo<'_>) -> String {
+        let mut contents = String::new();
+        match self {
+            EditorInfoFile::MsgInf => {
+                let _ = write!(contents, "{}\r\n", editor.from);
+                let _ = write!(contents, "{}\r\n", editor.to);
+                let _ = write!(contents, "{}\r\n", editor.subj);
+                contents.push_str("0\r\n"); // Message number, assigned when the message is saved
+                let _ = write!(contents, "{}\r\n", info.area);
+                let _ = write!(contents, "{}\r\n", if info.private { "YES" } else { "NO" });
+            }
+            EditorInfoFile::EditorInf => {
+                let _ = write!(contents, "{}\r\n", editor.subj); // Title
+                let _ = write!(contents, "{}\r\n", info.area); // Destination
+                let _ = write!(contents, "{}\r\n", info.user_number);
+                let _ = write!(contents, "{}\r\n", editor.from); // Name the message is written under
+                let _ = write!(contents, "{}\r\n", info.real_name);
+                let _ = write!(contents, "{}\r\n", info.security);
+                contents.push_str("0\r\n"); // Flags, 1 = anonymous
+                let _ = write!(contents, "{}\r\n", info.screen_lines);
+                let _ = write!(contents, "{}\r\n", editor.max_line_length + 1); // Screen columns
+            }
+        }
+        contents
+    }
+}
+
+/// Reads the draft the editor left behind. DOS editors end the file with ^Z.
+fn read_draft(path: &Path, max_lines: usize) -> Res<Vec<String>> {
+    let text = load_with_encoding(&path, Encoding::Detect)?;
+    let text = text.trim_end_matches(['\u{1a}', '\r', '\n']);
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(text.lines().take(max_lines).map(|line| line.trim_end_matches('\r').to_string()).collect())
+}
+
+fn command_line(command: &str, draft: &Path, info_file: &Path, node: usize) -> String {
+    command
+        .replace("%1", &draft.display().to_string())
+        .replace("%2", &info_file.display().to_string())
+        .replace("%N", &(node + 1).to_string())
+}
+
+impl EditState {
+    /// Hands the message to the configured external editor. Returns `None` when the
+    /// editor couldn't run, the caller then gets the built-in editor instead.
+    pub(crate) async fn external_edit(&mut self, state: &mut IcyBoardState, info: &EditorInfo<'_>) -> Res<Option<EditResult>> {
+        let (command, info_file, drop_file) = {
+            let board = state.get_board().await;
+            (
+                board.config.message.external_editor.clone(),
+                board.config.message.external_editor_info,
+                board.config.message.external_editor_drop_file.clone(),
+            )
+        };
+        if command.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let work_dir = temp_file::empty().path().to_path_buf();
+        std::fs::create_dir_all(&work_dir)?;
+        let result = self.run_external_editor(state, info, &command, info_file, drop_file, &work_dir).await;
+        let _ = std::fs::remove_dir_all(&work_dir);
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(err) => {
+                log::error!("external editor '{command}' failed: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn run_external_editor(
+        &mut self,
+        state: &mut IcyBoardState,
+        info: &EditorInfo<'_>,
+        command: &str,
+        info_file: EditorInfoFile,
+        drop_file: crate::icy_board::doors::DropFile,
+        work_dir: &Path,
+    ) -> Res<Option<EditResult>> {
+        let draft = work_dir.join(DRAFT_FILE);
+        let mut text = self.msg.join("\r\n");
+        if !text.is_empty() {
+            text.push_str("\r\n");
+        }
+        std::fs::write(&draft, text)?;
+        let info_path = work_dir.join(info_file.file_name());
+        std::fs::write(&info_path, info_file.contents(self, info))?;
+        let door = Door {
+            drop_file,
+            ..Default::default()
+        };
+        door.create_drop_file(state, work_dir, 0).await?;
+
+        state.set_activity(NodeStatus::EnterMessage).await;
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(command_line(command, &draft, &info_path, state.node)).current_dir(work_dir);
+        let status = state.run_door_process_in_terminal(&mut cmd).await?;
+        state.session.disp_options.force_count_lines();
+        if !status.success() {
+            log::error!("external editor '{command}' exited with {status}");
+            return Ok(None);
+        }
+
+        // Editors delete the draft when the caller aborts.
+        if !draft.exists() {
+            return Ok(Some(EditResult::Abort));
+        }
+        let msg = read_draft(&draft, self.max_lines)?;
+        if msg.is_empty() {
+            return Ok(Some(EditResult::Abort));
+        }
+        self.msg = msg;
+        Ok(Some(EditResult::SendMessage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{EditorInfo, command_line, read_draft};
+    use crate::icy_board::{icb_config::EditorInfoFile, state::user_commands::mods::editor::EditState};
+
+    fn info() -> EditorInfo<'static> {
+        EditorInfo {
+            area: "General",
+            private: true,
+            user_number: 7,
+            real_name: "Joe Caller",
+            security: 20,
+            screen_lines: 24,
+        }
+    }
+
+    fn editor() -> EditState {
+        EditState {
+            from: "JOE".to_string(),
+            to: "SYSOP".to_string(),
+            subj: "Hello".to_string(),
+            max_line_length: 79,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn msginf_carries_the_header_fields() {
+        assert_eq!(
+            EditorInfoFile::MsgInf.contents(&editor(), &info()),
+            "JOE\r\nSYSOP\r\nHello\r\n0\r\nGeneral\r\nYES\r\n"
+        );
+    }
+
+    #[test]
+    fn editor_inf_follows_the_wwiv_layout() {
+        let contents = EditorInfoFile::EditorInf.contents(&editor(), &info());
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines, vec!["Hello", "General", "7", "JOE", "Joe Caller", "20", "0", "24", "80"]);
+    }
+
+    #[test]
+    fn draft_is_read_back_without_the_dos_eof() {
+        let dir = std::env::temp_dir().join(format!("ext-editor-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let draft = dir.join("MSGTMP");
+        std::fs::write(&draft, b"First\r\nSecond\r\nThird\r\n\x1a").unwrap();
+        assert_eq!(read_draft(&draft, 100).unwrap(), vec!["First", "Second", "Third"]);
+        assert_eq!(read_draft(&draft, 2).unwrap(), vec!["First", "Second"]);
+        std::fs::write(&draft, b"\r\n\x1a").unwrap();
+        assert!(read_draft(&draft, 100).unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn command_line_substitutes_draft_info_and_node() {
+        assert_eq!(
+            command_line("qedit %1 %2 /n%N", Path::new("/tmp/MSGTMP"), Path::new("/tmp/MSGINF"), 2),
+            "qedit /tmp/MSGTMP /tmp/MSGINF /n3"
+        );
+    }
+}
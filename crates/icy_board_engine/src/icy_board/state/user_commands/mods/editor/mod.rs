@@ -14,6 +14,9 @@ use crate::{
 use chrono::Local;
 use icy_engine::Position;
 
+mod external;
+pub use external::EditorInfo;
+
 #[cfg(test)]
 mod tests;
 
@@ -39,6 +42,8 @@ pub struct EditState {
 pub enum EditResult {
     Abort,
     SendMessage,
+    /// `SA`: save once the caller has uploaded a file to go with the message.
+    SendWithAttachment,
     CarbonCopy,
 }
 
@@ -190,6 +195,10 @@ impl EditState {
                         state.session.disp_options.force_count_lines();
                         return Ok(EditResult::SendMessage);
                     }
+                    "SA" => {
+                        state.session.disp_options.force_count_lines();
+                        return Ok(EditResult::SendWithAttachment);
+                    }
                     "SC" => {
                         state.session.disp_options.force_count_lines();
                         return Ok(EditResult::CarbonCopy);
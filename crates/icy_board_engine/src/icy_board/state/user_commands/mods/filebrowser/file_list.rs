@@ -1,16 +1,22 @@
 use std::{path::PathBuf, sync::Arc};
 
-use dizbase::file_base::{
-    FileBase,
-    file_header::FileHeader,
-    metadata::{MetadataHeader, MetadataType},
+use dizbase::{
+    file_base::{
+        FileBase,
+        file_header::FileHeader,
+        metadata::{MetadataHeader, MetadataType},
+    },
+    file_base_scanner::verify::Verdict,
 };
 use humanize_bytes::humanize_bytes_decimal;
 use tokio::sync::Mutex;
 
 use crate::{
     Res,
-    icy_board::{icb_text::IceText, state::IcyBoardState},
+    icy_board::{
+        icb_text::IceText,
+        state::{IcyBoardState, file_thumbnails::ThumbnailSource},
+    },
     vm::TerminalTarget,
 };
 use std::fmt::Write as _;
@@ -343,9 +349,25 @@ impl FileList {
                     cmd.new_line().await?;
                 }
             }
+            // Only the quarantine lists files that failed their upload check.
+            if let Some(Verdict::Failed(reason)) = Verdict::from_metadata(&meta_data) {
+                if !first_line || show_uploader {
+                    cmd.print(TerminalTarget::Both, &format!("{:33}", " ")).await?;
+                }
+                let failed = cmd.get_display_text(IceText::Failed).unwrap_or_default();
+                cmd.set_color(TerminalTarget::Both, colors.file_offline.clone()).await?;
+                cmd.print(TerminalTarget::Both, &format!("{} {reason}", failed.trim())).await?;
+                cmd.reset_color(TerminalTarget::Both).await?;
+                cmd.new_line().await?;
+                printed_lines = true;
+            }
             if !printed_lines {
                 cmd.new_line().await?;
             }
+            // A file that was never scanned has no hash yet, and a listing is no place to read it in full.
+            if exists && let Some(hash) = meta_data.iter().find_map(MetadataHeader::hash) {
+                cmd.show_thumbnail(name, hash, ThumbnailSource::File(&full_path), DESCRIPTION_COLUMN).await?;
+            }
         }
         cmd.session.disp_options.in_file_list = None;
         Ok(())
@@ -1,14 +1,53 @@
 use std::fs;
+use std::path::{Path, PathBuf};
 
+use dizbase::FileInfo;
+use dizbase::file_base::FileBase;
 use dizbase::file_base::pattern::{MatchOptions, Pattern};
 use humanize_bytes::humanize_bytes_decimal;
 
 use crate::icy_board::icb_config::IcbColor;
+use crate::icy_board::state::file_thumbnails::{ThumbnailKind, ThumbnailSource, thumbnail_kind};
 use crate::icy_board::state::functions::MASK_ASCII;
 use crate::icy_board::{icb_text::IceText, state::functions::display_flags};
 use crate::vm::TerminalTarget;
 use crate::{Res, icy_board::state::IcyBoardState};
 
+/// A member bigger than this is not text anybody pages through on a terminal.
+const MAX_VIEW_SIZE: u64 = 256 * 1024;
+
+/// What the caller asked for at the member prompt.
+#[derive(Debug, PartialEq)]
+enum MemberCommand {
+    View(usize),
+    Extract(usize),
+    List,
+    Done,
+}
+
+/// `V 3`, `V3` and a bare `3` view the third member, `E 3` extracts it. The
+/// number is one based, the way the listing shows it.
+fn parse_member_command(answer: &str) -> Option<MemberCommand> {
+    let answer = answer.trim();
+    if answer.is_empty() || answer.eq_ignore_ascii_case("Q") {
+        return Some(MemberCommand::Done);
+    }
+    if answer.eq_ignore_ascii_case("L") {
+        return Some(MemberCommand::List);
+    }
+    let (action, number) = match answer.chars().next()? {
+        'V' | 'v' => ('V', answer[1..].trim()),
+        'E' | 'e' => ('E', answer[1..].trim()),
+        _ => ('V', answer),
+    };
+    let index = number.parse::<usize>().ok()?.checked_sub(1)?;
+    Some(if action == 'E' {
+        MemberCommand::Extract(index)
+    } else {
+        MemberCommand::View(index)
+    })
+}
+
 impl IcyBoardState {
     pub async fn view_file(&mut self) -> Res<()> {
         let Some(cur_dir_path) = self.session.disp_options.in_file_list.clone() else {
@@ -64,46 +103,10 @@ impl IcyBoardState {
             }
             if let Ok(file_content) = dizbase::scan_file_contents(file) {
                 let sav = self.session.disp_options.in_file_list.take();
-                self.session.disp_options.abort_printout = false;
-                let mut len = 0;
-                let colors: crate::icy_board::icb_config::ColorConfiguration = self.get_board().await.config.color_configuration.clone();
-                self.set_color(TerminalTarget::Both, colors.file_head.clone()).await?;
-                self.println(TerminalTarget::Both, &format!(" Archive: {}", file.file_name().unwrap().to_string_lossy()))
-                    .await?;
-                self.println(TerminalTarget::Both, "  Length      Date    Time   Name").await?;
-                self.println(TerminalTarget::Both, " ========  ========== ===== ======").await?;
-                self.set_color(TerminalTarget::Both, IcbColor::dos_light_cyan()).await?;
-                for info in &file_content {
-                    if self.session.disp_options.abort_printout {
-                        break;
-                    }
-                    self.set_color(TerminalTarget::Both, colors.file_size.clone()).await?;
-                    self.print(TerminalTarget::Both, &format!("{:>9}  ", humanize_bytes_decimal!(info.size).to_string()))
-                        .await?;
-                    self.set_color(TerminalTarget::Both, colors.file_date.clone()).await?;
-                    self.print(
-                        TerminalTarget::Both,
-                        &format!("{:04}-{:02}-{:02} ", info.date.year() % 10000, info.date.month(), info.date.day()),
-                    )
-                    .await?;
-                    self.print(TerminalTarget::Both, &format!("{:02}:{:02} ", info.date.hour(), info.date.minute()))
-                        .await?;
-                    self.set_color(TerminalTarget::Both, colors.file_name.clone()).await?;
-                    self.println(TerminalTarget::Both, &info.name).await?;
-                    len += info.size;
+                self.list_archive(file, &file_content).await?;
+                if !file_content.is_empty() {
+                    self.browse_archive(file, &file_content).await?;
                 }
-                self.set_color(TerminalTarget::Both, IcbColor::dos_yellow()).await?;
-                self.set_color(TerminalTarget::Both, colors.file_head.clone()).await?;
-                self.println(TerminalTarget::Both, "---------                   ------").await?;
-                self.set_color(TerminalTarget::Both, colors.file_size).await?;
-                self.set_color(TerminalTarget::Both, IcbColor::dos_white()).await?;
-                self.println(
-                    TerminalTarget::Both,
-                    &format!("{:>9}                   {} files", humanize_bytes_decimal!(len).to_string(), file_content.len()),
-                )
-                .await?;
-                self.reset_color(TerminalTarget::Both).await?;
-                self.new_line().await?;
                 self.session.disp_options.in_file_list = sav;
             } else {
                 self.session.op_text = file.file_name().unwrap().to_string_lossy().to_string();
@@ -113,4 +116,188 @@ impl IcyBoardState {
         }
         Ok(())
     }
+
+    async fn list_archive(&mut self, file: &Path, file_content: &[FileInfo]) -> Res<()> {
+        self.session.disp_options.abort_printout = false;
+        let mut len = 0;
+        let colors: crate::icy_board::icb_config::ColorConfiguration = self.get_board().await.config.color_configuration.clone();
+        self.set_color(TerminalTarget::Both, colors.file_head.clone()).await?;
+        self.println(TerminalTarget::Both, &format!(" Archive: {}", file.file_name().unwrap().to_string_lossy()))
+            .await?;
+        self.println(TerminalTarget::Both, "   #    Length Ratio     Date    Time   Name").await?;
+        self.println(TerminalTarget::Both, " ===  ======== ===== ========== ===== ======").await?;
+        self.set_color(TerminalTarget::Both, IcbColor::dos_light_cyan()).await?;
+        for (number, info) in file_content.iter().enumerate() {
+            if self.session.disp_options.abort_printout {
+                break;
+            }
+            self.set_color(TerminalTarget::Both, IcbColor::dos_light_cyan()).await?;
+            self.print(TerminalTarget::Both, &format!("{:>4}  ", number + 1)).await?;
+            self.set_color(TerminalTarget::Both, colors.file_size.clone()).await?;
+            self.print(
+                TerminalTarget::Both,
+                &format!("{:>8} {:>4}% ", humanize_bytes_decimal!(info.size).to_string(), info.ratio()),
+            )
+            .await?;
+            self.set_color(TerminalTarget::Both, colors.file_date.clone()).await?;
+            self.print(
+                TerminalTarget::Both,
+                &format!("{:04}-{:02}-{:02} ", info.date.year() % 10000, info.date.month(), info.date.day()),
+            )
+            .await?;
+            self.print(TerminalTarget::Both, &format!("{:02}:{:02} ", info.date.hour(), info.date.minute()))
+                .await?;
+            self.set_color(TerminalTarget::Both, colors.file_name.clone()).await?;
+            self.println(TerminalTarget::Both, &info.name).await?;
+            len += info.size;
+        }
+        self.set_color(TerminalTarget::Both, colors.file_head.clone()).await?;
+        self.println(TerminalTarget::Both, "      --------                        ------").await?;
+        self.set_color(TerminalTarget::Both, IcbColor::dos_white()).await?;
+        self.println(
+            TerminalTarget::Both,
+            &format!(
+                "      {:>8}                        {} files",
+                humanize_bytes_decimal!(len).to_string(),
+                file_content.len()
+            ),
+        )
+        .await?;
+        self.reset_color(TerminalTarget::Both).await?;
+        Ok(())
+    }
+
+    /// Lets the caller read a text member without downloading the archive, or
+    /// take a single member instead of all of it.
+    async fn browse_archive(&mut self, file: &Path, members: &[FileInfo]) -> Res<()> {
+        loop {
+            self.session.op_text = format!("(1-{})", members.len());
+            let answer = self
+                .input_field(
+                    IceText::ArchiveMemberCommands,
+                    6,
+                    &MASK_ASCII,
+                    "",
+                    None,
+                    display_flags::NEWLINE | display_flags::LFBEFORE | display_flags::UPCASE | display_flags::FIELDLEN,
+                )
+                .await?;
+            let Some(command) = parse_member_command(&answer) else {
+                continue;
+            };
+            match command {
+                MemberCommand::Done => break,
+                MemberCommand::List => self.list_archive(file, members).await?,
+                MemberCommand::View(index) => {
+                    if let Some(member) = members.get(index) {
+                        self.view_member(file, member).await?;
+                    }
+                }
+                MemberCommand::Extract(index) => {
+                    if let Some(member) = members.get(index) {
+                        self.extract_member(file, member).await?;
+                    }
+                }
+            }
+        }
+        self.new_line().await?;
+        Ok(())
+    }
+
+    async fn view_member(&mut self, file: &Path, member: &FileInfo) -> Res<()> {
+        self.session.op_text.clone_from(&member.name);
+        if thumbnail_kind(&member.name) == Some(ThumbnailKind::Picture)
+            && let Ok(Some(content)) = dizbase::read_member(file, &member.name, dizbase::MAX_EXTRACT_SIZE)
+            && self
+                .show_thumbnail(&member.name, FileBase::hash_bytes(&content), ThumbnailSource::Bytes(&content), 0)
+                .await?
+        {
+            return Ok(());
+        }
+        if !dizbase::is_text_member(&member.name) || member.size > MAX_VIEW_SIZE {
+            self.display_text(IceText::ErrorViewingFile, display_flags::NEWLINE | display_flags::LFBEFORE)
+                .await?;
+            return Ok(());
+        }
+        let content = match dizbase::read_member(file, &member.name, MAX_VIEW_SIZE) {
+            Ok(Some(content)) => content,
+            Ok(None) => Vec::new(),
+            Err(err) => {
+                log::error!("Could not read {} from {}: {}", member.name, file.display(), err);
+                self.display_text(IceText::ErrorViewingFile, display_flags::NEWLINE | display_flags::LFBEFORE)
+                    .await?;
+                return Ok(());
+            }
+        };
+        self.new_line().await?;
+        self.session.disp_options.num_lines_printed = 0;
+        self.display_bytes(&content).await?;
+        self.reset_color(TerminalTarget::Both).await?;
+        self.new_line().await?;
+        Ok(())
+    }
+
+    /// Unpacks a member into this node's work directory and flags it, so it
+    /// goes out with the next download like any file of the area. The download
+    /// cleans up after it with `remove_extracted_members`.
+    async fn extract_member(&mut self, file: &Path, member: &FileInfo) -> Res<()> {
+        let Some(name) = Path::new(&member.name.replace('\\', "/"))
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+        else {
+            return Ok(());
+        };
+        let content = match dizbase::read_member(file, &member.name, dizbase::MAX_EXTRACT_SIZE) {
+            Ok(Some(content)) => content,
+            result => {
+                if let Err(err) = result {
+                    log::error!("Could not extract {} from {}: {}", member.name, file.display(), err);
+                }
+                self.session.op_text.clone_from(&member.name);
+                self.display_text(IceText::ErrorViewingFile, display_flags::NEWLINE | display_flags::LFBEFORE)
+                    .await?;
+                return Ok(());
+            }
+        };
+        let directory = self.extract_directory().await;
+        fs::create_dir_all(&directory)?;
+        let target = directory.join(name);
+        fs::write(&target, content)?;
+        self.add_flagged_file(target, false, true).await
+    }
+
+    async fn extract_directory(&self) -> PathBuf {
+        let board = self.get_board().await;
+        board
+            .resolve_file(&board.config.paths.tmp_work_path)
+            .join(format!("node{}", self.node))
+            .join("extract")
+    }
+
+    /// Once a transfer took the flagged files, the members unpacked for it can go.
+    pub(crate) async fn remove_extracted_members(&self) {
+        let directory = self.extract_directory().await;
+        if directory.exists()
+            && let Err(err) = fs::remove_dir_all(&directory)
+        {
+            log::error!("Could not remove {}: {}", directory.display(), err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_member_is_picked_by_its_listed_number() {
+        assert_eq!(parse_member_command("V 3"), Some(MemberCommand::View(2)));
+        assert_eq!(parse_member_command("v3"), Some(MemberCommand::View(2)));
+        assert_eq!(parse_member_command("3"), Some(MemberCommand::View(2)));
+        assert_eq!(parse_member_command("E 1"), Some(MemberCommand::Extract(0)));
+        assert_eq!(parse_member_command(""), Some(MemberCommand::Done));
+        assert_eq!(parse_member_command("L"), Some(MemberCommand::List));
+        assert_eq!(parse_member_command("V 0"), None);
+        assert_eq!(parse_member_command("X"), None);
+    }
 }
@@ -127,7 +127,7 @@ fn field(value: Option<&bstr::BString>) -> String {
     value.map(|v| v.to_string().trim().to_ascii_uppercase()).unwrap_or_default()
 }
 
-fn strip_re(subject: &str) -> &str {
+pub(super) fn strip_re(subject: &str) -> &str {
     let subject = subject.trim();
     if subject.len() >= 4 && subject[..4].eq_ignore_ascii_case("re: ") {
         subject[4..].trim_start()
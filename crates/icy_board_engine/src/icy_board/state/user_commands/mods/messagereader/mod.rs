@@ -10,13 +10,17 @@ use crate::{
 };
 use jamjam::jam::{JamMessageBase, msg_header::JamMessageHeader};
 
+use super::attachments::attachment;
+
 pub mod message_filter;
 pub mod read_actions;
 pub mod read_command;
+pub mod thread;
 
 use message_filter::MessageFilter;
 use read_actions::AfterAction;
 use read_command::{MsgFunc, ParseContext, ReadCommand, ReadLoop, user_search};
+use thread::ThreadTree;
 
 /// The next message number in the direction the range runs, if the range has one.
 fn next_in_range(number: u32, first: u32, last: u32) -> Option<u32> {
@@ -29,6 +33,14 @@ fn next_in_range(number: u32, first: u32, last: u32) -> Option<u32> {
     }
 }
 
+/// The message after this one: in thread order when reading a thread, else along the range.
+fn next_message(thread: Option<&ThreadTree>, number: u32, first: u32, last: u32) -> Option<u32> {
+    match thread {
+        Some(tree) => tree.next(number),
+        None => next_in_range(number, first, last),
+    }
+}
+
 /// What to do with the command line once its missing pieces have been asked for.
 enum Resolution {
     Run,
@@ -218,6 +230,11 @@ impl MessageViewer {
         if state.session.disp_options.count_lines {
             state.session.disp_options.num_lines_printed += 5;
         }
+        if let Some(path) = attachment(header) {
+            state.session.op_text = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            state.display_text(IceText::AttachedFile, display_flags::NEWLINE).await?;
+            state.display_text(IceText::ShowAttachCommands, display_flags::NEWLINE).await?;
+        }
         Ok(())
     }
 
@@ -242,7 +259,8 @@ impl MessageViewer {
 }
 
 impl IcyBoardState {
-    pub async fn read_msgs_from_base(&mut self, mut message_base: JamMessageBase, only_personal: bool) -> Res<()> {
+    /// `conference` is where the messages belong, negative for the caller's mailbox.
+    pub async fn read_msgs_from_base(&mut self, mut message_base: JamMessageBase, conference: i32, only_personal: bool) -> Res<()> {
         let viewer = MessageViewer::load(&self.display_text)?;
         let mut low_number = message_base.lowest_message_number();
         let mut high_number = message_base.highest_message_number();
@@ -335,12 +353,30 @@ impl IcyBoardState {
             }
             let filter = MessageFilter::new(&cmd, &self.session);
 
+            if cmd.thread_view {
+                let tree = ThreadTree::read(&message_base);
+                let last_read = self.last_read_pointer(&mut message_base)?;
+                self.show_thread_index(&message_base, &tree, last_read).await?;
+                let start = tree
+                    .index()
+                    .iter()
+                    .map(|(number, _)| *number)
+                    .find(|number| *number > last_read)
+                    .or_else(|| tree.index().first().map(|(number, _)| *number));
+                if let Some(start) = start {
+                    self.read_message_number(&mut message_base, conference, &viewer, start, high_number, true, &filter, Some(&tree))
+                        .await?;
+                }
+                self.stop_search();
+                continue;
+            }
+
             for range in cmd.numbers.clone() {
                 let (first, last) = self.clamp_range(range, low_number, high_number);
                 if first == 0 {
                     continue;
                 }
-                self.read_message_number(&mut message_base, &viewer, first, last, cmd.keep_going, &filter)
+                self.read_message_number(&mut message_base, conference, &viewer, first, last, cmd.keep_going, &filter, None)
                     .await?;
             }
             self.stop_search();
@@ -348,6 +384,27 @@ impl IcyBoardState {
         Ok(())
     }
 
+    /// One line per message, replies indented below what they answer and a `*`
+    /// in front of what the caller hasn't read yet.
+    async fn show_thread_index(&mut self, message_base: &JamMessageBase, tree: &ThreadTree, last_read: u32) -> Res<()> {
+        self.new_line().await?;
+        for (number, depth) in tree.index() {
+            let Ok(header) = message_base.read_header(*number) else {
+                continue;
+            };
+            let subject = header.subject().map(|subject| subject.to_string()).unwrap_or_default();
+            let from = header.from().map(|from| from.to_string()).unwrap_or_default();
+            let marker = if *number > last_read { '*' } else { ' ' };
+            let indent = "  ".repeat((*depth).min(10));
+            self.println(TerminalTarget::Both, &format!("{marker}{number:>6} {indent}{subject} ({from})"))
+                .await?;
+            if self.session.disp_options.abort_printout {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     /// Where this message base left the current user's last-read pointer.
     fn last_read_pointer(&mut self, message_base: &mut JamMessageBase) -> Res<u32> {
         unsafe {
@@ -518,7 +575,7 @@ impl IcyBoardState {
             }
         }
 
-        if cmd.func == MsgFunc::None && cmd.numbers.is_empty() && !cmd.all_conf && !cmd.since && !cmd.new_msgs {
+        if cmd.func == MsgFunc::None && cmd.numbers.is_empty() && !cmd.all_conf && !cmd.since && !cmd.new_msgs && !cmd.thread_view {
             if !cmd.valid_cmd {
                 self.display_text(IceText::InvalidEntry, display_flags::NEWLINE | display_flags::LFBEFORE)
                     .await?;
@@ -532,11 +589,13 @@ impl IcyBoardState {
     pub async fn read_message_number(
         &mut self,
         message_base: &mut JamMessageBase,
+        conference: i32,
         viewer: &MessageViewer,
         mut first: u32,
         mut last: u32,
         mut keep_going: bool,
         filter: &MessageFilter,
+        mut thread: Option<&ThreadTree>,
     ) -> Res<()> {
         let mut number = first;
         if number == 0 {
@@ -582,7 +641,7 @@ impl IcyBoardState {
                             log::error!("Error reading message header: {err}");
                         }
                     }
-                    match next_in_range(number, first, last) {
+                    match next_message(thread, number, first, last) {
                         Some(next) => number = next,
                         None => break None,
                     }
@@ -664,10 +723,45 @@ impl IcyBoardState {
                         display_msg = true;
                         continue;
                     }
+                    MsgFunc::ThreadIndex | MsgFunc::ThreadParent | MsgFunc::ThreadFirstReply | MsgFunc::ThreadNextSibling => {
+                        let built;
+                        let tree = match thread {
+                            Some(tree) => tree,
+                            None => {
+                                built = ThreadTree::read(message_base);
+                                &built
+                            }
+                        };
+                        let target = match cmd.func {
+                            MsgFunc::ThreadParent => tree.parent(number),
+                            MsgFunc::ThreadFirstReply => tree.first_reply(number),
+                            MsgFunc::ThreadNextSibling => tree.next_sibling(number),
+                            _ => {
+                                self.show_thread_index(message_base, tree, last_read).await?;
+                                continue;
+                            }
+                        };
+                        match target {
+                            Some(target) => {
+                                // Outside a thread view the jump starts a new range reading upwards.
+                                if thread.is_none() {
+                                    first = target;
+                                    last = self.session.high_msg_num;
+                                }
+                                number = target;
+                                display_msg = true;
+                            }
+                            None => {
+                                self.display_text(IceText::NoMailFound, display_flags::NEWLINE | display_flags::LFBEFORE)
+                                    .await?;
+                            }
+                        }
+                        continue;
+                    }
                     _ => {}
                 }
 
-                match self.run_read_action(&cmd, message_base, number).await? {
+                match self.run_read_action(&cmd, message_base, conference, number).await? {
                     AfterAction::Prompt => continue,
                     AfterAction::Redisplay => {
                         display_msg = true;
@@ -695,6 +789,8 @@ impl IcyBoardState {
                     if lo == 0 {
                         break;
                     }
+                    // An explicit number leaves the thread order behind.
+                    thread = None;
                     number = lo;
                     first = lo;
                     last = hi;
@@ -703,7 +799,7 @@ impl IcyBoardState {
                 }
             }
             if keep_going {
-                match next_in_range(number, first, last) {
+                match next_message(thread, number, first, last) {
                     Some(next) => number = next,
                     None => break,
                 }
@@ -8,6 +8,7 @@ use crate::Res;
 use crate::icy_board::icb_text::IceText;
 use crate::icy_board::state::IcyBoardState;
 use crate::icy_board::state::functions::{MASK_ASCII, MASK_NUM, display_flags};
+use crate::icy_board::state::user_commands::mods::attachments::attachment;
 use crate::icy_board::state::user_commands::pcb::select_conferences::SelectMode;
 use crate::vm::TerminalTarget;
 
@@ -34,7 +35,7 @@ pub(super) enum AfterAction {
 }
 
 impl IcyBoardState {
-    pub(super) async fn run_read_action(&mut self, cmd: &ReadCommand, message_base: &mut JamMessageBase, number: u32) -> Res<AfterAction> {
+    pub(super) async fn run_read_action(&mut self, cmd: &ReadCommand, message_base: &mut JamMessageBase, conference: i32, number: u32) -> Res<AfterAction> {
         match cmd.func {
             MsgFunc::Kill => {
                 self.new_line().await?;
@@ -142,7 +143,11 @@ impl IcyBoardState {
                 Ok(AfterAction::Redisplay)
             }
             MsgFunc::FlagFile => {
-                self.flag_files_cmd(true).await?;
+                let attached = message_base.read_header(number).ok().and_then(|header| attachment(&header));
+                match attached {
+                    Some(path) => self.download_attachment(conference, &path).await?,
+                    None => self.flag_files_cmd(true).await?,
+                }
                 Ok(AfterAction::Redisplay)
             }
             // PCBoard answers the sender or recipient of the message in front of
@@ -38,6 +38,10 @@ pub enum MsgFunc {
     ReplyOther,
     Skip,
     Who,
+    ThreadParent,
+    ThreadFirstReply,
+    ThreadNextSibling,
+    ThreadIndex,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -106,6 +110,8 @@ pub struct ReadCommand {
     pub keep_going: bool,
     pub threading: bool,
     pub thread_forward: bool,
+    /// `TH` at the read prompt walks the base thread by thread.
+    pub thread_view: bool,
     pub any_msgs: bool,
     pub unread_only: bool,
     pub stay_in_conf: bool,
@@ -163,6 +169,7 @@ impl Default for ReadCommand {
             keep_going: false,
             threading: false,
             thread_forward: true,
+            thread_view: false,
             any_msgs: true,
             unread_only: false,
             stay_in_conf: false,
@@ -197,10 +204,10 @@ impl Default for ReadCommand {
     }
 }
 
-const OPTIONS: [&str; 42] = [
+const OPTIONS: [&str; 46] = [
     "ALL", "ALIAS", "BYE", "CHAT", "COPY", "DESELECT", "EDIT", "FF", "FT", "FLAG", "FORWARD", "FROM", "GB", "HELP", "JUMP", "KILL", "LONG", "MOVE", "NEXT",
-    "NET", "PREV", "QWK", "REPLY", "RM", "RM+", "RM-", "RO", "RR", "RR+", "RR-", "SELECT", "SET", "SHORT", "SKIP", "T+", "T-", "TO", "TS", "USER", "WAIT",
-    "WHO", "YA",
+    "NET", "PREV", "QWK", "REPLY", "RM", "RM+", "RM-", "RO", "RR", "RR+", "RR-", "SELECT", "SET", "SHORT", "SKIP", "T+", "T-", "TH", "TN", "TO", "TP", "TR",
+    "TS", "USER", "WAIT", "WHO", "YA",
 ];
 
 const O_ALL: usize = 0;
@@ -239,12 +246,16 @@ const O_SHORT: usize = 32;
 const O_SKIP: usize = 33;
 const O_TF: usize = 34;
 const O_TB: usize = 35;
-const O_TO: usize = 36;
-const O_TS: usize = 37;
-const O_USER: usize = 38;
-const O_WAIT: usize = 39;
-const O_WHO: usize = 40;
-const O_YA: usize = 41;
+const O_TH: usize = 36;
+const O_TN: usize = 37;
+const O_TO: usize = 38;
+const O_TP: usize = 39;
+const O_TR: usize = 40;
+const O_TS: usize = 41;
+const O_USER: usize = 42;
+const O_WAIT: usize = 43;
+const O_WHO: usize = 44;
+const O_YA: usize = 45;
 
 /// Keyword lookup: first prefix match wins, and two characters are
 /// enough for any word of three or more.
@@ -717,6 +728,24 @@ pub fn parse(tokens: &[String], flag: ReadLoop, ctx: &ParseContext) -> ReadComma
                     cmd.valid_cmd = true;
                 }
             }
+            O_TH => {
+                if flag == ReadLoop::Inside {
+                    cmd.func = MsgFunc::ThreadIndex;
+                } else {
+                    cmd.thread_view = true;
+                }
+                cmd.valid_cmd = true;
+            }
+            O_TP | O_TR | O_TN => {
+                if flag == ReadLoop::Inside {
+                    cmd.func = match opt {
+                        O_TP => MsgFunc::ThreadParent,
+                        O_TR => MsgFunc::ThreadFirstReply,
+                        _ => MsgFunc::ThreadNextSibling,
+                    };
+                    cmd.valid_cmd = true;
+                }
+            }
             O_TS => {
                 cmd.do_text_search = true;
                 last_search_cmd = Some(O_TS);
@@ -1034,6 +1063,26 @@ mod tests {
         assert!(parse_inside("T").threading);
     }
 
+    #[test]
+    fn th_opens_the_thread_view_outside_and_lists_the_thread_inside() {
+        let cmd = parse_outside("TH");
+        assert!(cmd.thread_view);
+        assert!(cmd.numbers.is_empty());
+        let cmd = parse_inside("TH");
+        assert!(!cmd.thread_view);
+        assert_eq!(cmd.func, MsgFunc::ThreadIndex);
+    }
+
+    #[test]
+    fn thread_jumps_only_apply_inside_the_read_loop() {
+        assert_eq!(parse_inside("TP").func, MsgFunc::ThreadParent);
+        assert_eq!(parse_inside("TR").func, MsgFunc::ThreadFirstReply);
+        assert_eq!(parse_inside("TN").func, MsgFunc::ThreadNextSibling);
+        assert!(!parse_outside("TP").valid_cmd);
+        assert_eq!(option("TO"), Some(O_TO));
+        assert_eq!(option("TS"), Some(O_TS));
+    }
+
     #[test]
     fn a_needs_join_access() {
         let mut context = ctx();
@@ -0,0 +1,239 @@
+//! The reply tree of a message base. JAM links a reply to what it answers and
+//! a message to its first reply and the next one after that; a message written
+//! by a reader that set none of them still joins the thread its subject names.
+
+use std::collections::{BTreeMap, HashMap};
+
+use jamjam::jam::{JamMessageBase, msg_header::JamMessageHeader};
+
+use super::message_filter::strip_re;
+
+/// The part of a header the tree is built from.
+#[derive(Clone, Debug, Default)]
+pub struct ThreadEntry {
+    pub number: u32,
+    pub reply_to: u32,
+    pub reply_first: u32,
+    pub reply_next: u32,
+    pub subject: String,
+}
+
+impl ThreadEntry {
+    pub fn from_header(header: &JamMessageHeader) -> Self {
+        Self {
+            number: header.message_number,
+            reply_to: header.reply_to,
+            reply_first: header.reply_first,
+            reply_next: header.reply_next,
+            subject: header.subject().map(|subject| subject.to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ThreadTree {
+    parents: HashMap<u32, u32>,
+    children: BTreeMap<u32, Vec<u32>>,
+    /// Every message once, a reply right below what it answers, with its depth.
+    order: Vec<(u32, usize)>,
+    /// Where a message is in `order`, walking a thread asks for it at every step.
+    positions: HashMap<u32, usize>,
+    next_siblings: HashMap<u32, u32>,
+}
+
+impl ThreadTree {
+    pub fn read(message_base: &JamMessageBase) -> Self {
+        let entries: Vec<ThreadEntry> = message_base
+            .messages()
+            .flatten()
+            .filter(|header| !header.is_deleted())
+            .map(|header| ThreadEntry::from_header(&header))
+            .collect();
+        Self::build(&entries)
+    }
+
+    pub fn build(entries: &[ThreadEntry]) -> Self {
+        let by_number: HashMap<u32, &ThreadEntry> = entries.iter().map(|entry| (entry.number, entry)).collect();
+        let mut parents = HashMap::new();
+        // A reply is always written after what it answers, which keeps a broken
+        // link from tying a thread into a loop.
+        let mut link = |parents: &mut HashMap<u32, u32>, child: u32, parent: u32| {
+            if parent < child && by_number.contains_key(&parent) && by_number.contains_key(&child) {
+                parents.entry(child).or_insert(parent);
+            }
+        };
+
+        for entry in entries {
+            if entry.reply_to != 0 {
+                link(&mut parents, entry.number, entry.reply_to);
+            }
+        }
+        for entry in entries {
+            let mut reply = entry.reply_first;
+            let mut steps = 0;
+            while reply != 0 && steps < entries.len() {
+                link(&mut parents, reply, entry.number);
+                reply = by_number.get(&reply).map(|next| next.reply_next).unwrap_or(0);
+                steps += 1;
+            }
+        }
+
+        // What no link claims is answered by the first message on its subject,
+        // as long as its own subject says it is a reply.
+        let mut first_on_subject: HashMap<String, u32> = HashMap::new();
+        let mut sorted: Vec<&ThreadEntry> = entries.iter().collect();
+        sorted.sort_by_key(|entry| entry.number);
+        for entry in &sorted {
+            let stripped = strip_re(&entry.subject).to_ascii_uppercase();
+            if stripped.is_empty() {
+                continue;
+            }
+            match first_on_subject.get(&stripped) {
+                Some(first) if !parents.contains_key(&entry.number) && stripped.len() < entry.subject.trim().len() => {
+                    parents.insert(entry.number, *first);
+                }
+                Some(_) => {}
+                None => {
+                    first_on_subject.insert(stripped, entry.number);
+                }
+            }
+        }
+
+        let mut children: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        let mut roots = Vec::new();
+        for entry in &sorted {
+            match parents.get(&entry.number) {
+                Some(parent) => children.entry(*parent).or_default().push(entry.number),
+                None => roots.push(entry.number),
+            }
+        }
+
+        let mut order = Vec::with_capacity(entries.len());
+        let mut stack: Vec<(u32, usize)> = roots.iter().rev().map(|root| (*root, 0)).collect();
+        while let Some((number, depth)) = stack.pop() {
+            order.push((number, depth));
+            if let Some(replies) = children.get(&number) {
+                stack.extend(replies.iter().rev().map(|reply| (*reply, depth + 1)));
+            }
+        }
+        let positions = order.iter().enumerate().map(|(index, (number, _))| (*number, index)).collect();
+        let next_siblings = children
+            .values()
+            .chain(std::iter::once(&roots))
+            .flat_map(|siblings| siblings.windows(2).map(|pair| (pair[0], pair[1])))
+            .collect();
+        Self {
+            parents,
+            children,
+            order,
+            positions,
+            next_siblings,
+        }
+    }
+
+    pub fn parent(&self, number: u32) -> Option<u32> {
+        self.parents.get(&number).copied()
+    }
+
+    pub fn first_reply(&self, number: u32) -> Option<u32> {
+        self.children.get(&number).and_then(|replies| replies.first()).copied()
+    }
+
+    pub fn next_sibling(&self, number: u32) -> Option<u32> {
+        self.next_siblings.get(&number).copied()
+    }
+
+    /// The message after this one when the whole base is read thread by thread.
+    pub fn next(&self, number: u32) -> Option<u32> {
+        let index = self.positions.get(&number)?;
+        self.order.get(index + 1).map(|(entry, _)| *entry)
+    }
+
+    pub fn index(&self) -> &[(u32, usize)] {
+        &self.order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(number: u32, reply_to: u32, subject: &str) -> ThreadEntry {
+        ThreadEntry {
+            number,
+            reply_to,
+            subject: subject.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn replies_hang_below_what_they_answer() {
+        let tree = ThreadTree::build(&[
+            entry(1, 0, "Hello"),
+            entry(2, 0, "Other"),
+            entry(3, 1, "Re: Hello"),
+            entry(4, 3, "Re: Hello"),
+            entry(5, 1, "Re: Hello"),
+        ]);
+        assert_eq!(tree.index(), &[(1, 0), (3, 1), (4, 2), (5, 1), (2, 0)]);
+        assert_eq!(tree.parent(4), Some(3));
+        assert_eq!(tree.first_reply(1), Some(3));
+        assert_eq!(tree.next_sibling(3), Some(5));
+        assert_eq!(tree.next_sibling(5), None);
+        assert_eq!(tree.next_sibling(1), Some(2));
+        assert_eq!(tree.next(5), Some(2));
+    }
+
+    #[test]
+    fn next_walks_the_index_from_start_to_end() {
+        let entries: Vec<ThreadEntry> = (1..=200).map(|number| entry(number, number / 2, "Re: Deep")).collect();
+        let tree = ThreadTree::build(&entries);
+
+        let mut walked = vec![tree.index()[0].0];
+        while let Some(next) = tree.next(*walked.last().unwrap()) {
+            walked.push(next);
+        }
+        assert_eq!(walked, tree.index().iter().map(|(number, _)| *number).collect::<Vec<_>>());
+        assert_eq!(tree.next_sibling(2), Some(3));
+        assert_eq!(tree.next_sibling(3), None);
+    }
+
+    #[test]
+    fn the_reply_chain_of_the_parent_links_replies_as_well() {
+        let tree = ThreadTree::build(&[
+            ThreadEntry {
+                number: 1,
+                reply_first: 2,
+                subject: "Hello".to_string(),
+                ..Default::default()
+            },
+            ThreadEntry {
+                number: 2,
+                reply_next: 3,
+                subject: "Hi".to_string(),
+                ..Default::default()
+            },
+            entry(3, 0, "Hey"),
+        ]);
+        assert_eq!(tree.parent(2), Some(1));
+        assert_eq!(tree.parent(3), Some(1));
+    }
+
+    #[test]
+    fn a_reply_without_links_joins_the_thread_by_subject() {
+        let tree = ThreadTree::build(&[entry(1, 0, "Hello"), entry(2, 0, "Re: hello"), entry(3, 0, "Hello")]);
+        assert_eq!(tree.parent(2), Some(1));
+        // The same subject without a Re: is a new thread.
+        assert_eq!(tree.parent(3), None);
+    }
+
+    #[test]
+    fn a_link_to_a_later_or_missing_message_is_ignored() {
+        let tree = ThreadTree::build(&[entry(1, 2, "A"), entry(2, 1, "B"), entry(3, 9, "C")]);
+        assert_eq!(tree.parent(1), None);
+        assert_eq!(tree.parent(2), Some(1));
+        assert_eq!(tree.parent(3), None);
+        assert_eq!(tree.index().len(), 3);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod attachments;
 pub mod editor;
 pub mod filebrowser;
 pub mod fileview;
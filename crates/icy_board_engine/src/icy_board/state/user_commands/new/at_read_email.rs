@@ -4,7 +4,7 @@ impl IcyBoardState {
     pub async fn read_email(&mut self) -> Res<()> {
         let name = self.session.user_name.clone();
         let msg_base = self.get_email_msgbase(&name).await?;
-        self.read_msgs_from_base(msg_base, true).await?;
+        self.read_msgs_from_base(msg_base, -1, true).await?;
         Ok(())
     }
 }
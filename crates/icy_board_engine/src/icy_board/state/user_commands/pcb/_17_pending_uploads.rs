@@ -0,0 +1,114 @@
+use crate::icy_board::commands::CommandType;
+use crate::icy_board::icb_config::IcbColor;
+use crate::icy_board::pending_uploads::{PendingUpload, UploadLocation, pending_uploads, upload_locations};
+use crate::{Res, icy_board::state::IcyBoardState};
+use crate::{
+    icy_board::{
+        icb_text::IceText,
+        state::functions::{MASK_NUM, display_flags},
+    },
+    vm::TerminalTarget,
+};
+
+impl IcyBoardState {
+    /// Sysop command 17 - go through the uploads `hold_uploads` kept back, oldest first.
+    pub async fn review_pending_uploads(&mut self) -> Res<()> {
+        let (pending, locations) = {
+            let board = self.get_board().await;
+            (pending_uploads(&board), upload_locations(&board))
+        };
+        if pending.is_empty() {
+            self.display_text(IceText::NoFilesFound, display_flags::NEWLINE | display_flags::LFBEFORE)
+                .await?;
+            return Ok(());
+        }
+
+        let total = pending.len();
+        for (number, upload) in pending.iter().enumerate() {
+            self.show_pending_upload(number + 1, total, upload).await?;
+            let answer = self
+                .input_field(
+                    IceText::PendingUploadCommands,
+                    1,
+                    "ADMSQ",
+                    CommandType::PendingUploads.get_help(),
+                    None,
+                    display_flags::NEWLINE | display_flags::LFBEFORE | display_flags::UPCASE | display_flags::FIELDLEN,
+                )
+                .await?;
+            let result = match answer.as_str() {
+                "A" => upload.approve().map(|_| IceText::UploadApproved),
+                "D" => upload.delete().map(|_| IceText::UploadDeleted),
+                "M" => match self.ask_upload_location(&locations).await? {
+                    Some(target) => upload.move_to(target).map(|_| IceText::UploadMoved),
+                    None => continue,
+                },
+                "Q" => break,
+                _ => continue,
+            };
+            match result {
+                Ok(done) => {
+                    log::info!("{} in {}: {done}", upload.name, upload.location.path.display());
+                    self.session.op_text = upload.name.clone();
+                    self.display_text(done, display_flags::NEWLINE).await?;
+                }
+                Err(err) => {
+                    log::error!("Pending upload {}: {err}", upload.path().display());
+                    self.println(TerminalTarget::Both, &format!("{}: {err}", upload.name)).await?;
+                }
+            }
+        }
+        // The bases this session keeps open still list what they read before, and the
+        // duplicate index still has the moved and deleted files where they were.
+        self.file_bases.clear();
+        self.get_board().await.upload_index = None;
+        Ok(())
+    }
+
+    async fn show_pending_upload(&mut self, number: usize, total: usize, upload: &PendingUpload) -> Res<()> {
+        self.new_line().await?;
+        self.set_color(TerminalTarget::Both, IcbColor::dos_light_green()).await?;
+        self.println(
+            TerminalTarget::Both,
+            &format!("({number}/{total}) {:<13} {:>10}  {}", upload.name, upload.size, upload.location.name),
+        )
+        .await?;
+        self.session.op_text = match upload.uploaded {
+            Some(date) => format!("{} {} {}", upload.uploader, self.format_date(date), self.format_time(date)),
+            None => upload.uploader.clone(),
+        };
+        self.display_text(IceText::UploadedBy, display_flags::NEWLINE).await?;
+        if let Some(original) = &upload.duplicate_of {
+            self.session.op_text = original.clone();
+            self.display_text(IceText::DuplicateOf, display_flags::NEWLINE).await?;
+        }
+        self.set_color(TerminalTarget::Both, IcbColor::dos_white()).await?;
+        for line in upload.description.lines() {
+            self.println(TerminalTarget::Both, &format!("  {line}")).await?;
+        }
+        Ok(())
+    }
+
+    async fn ask_upload_location<'a>(&mut self, locations: &'a [UploadLocation]) -> Res<Option<&'a UploadLocation>> {
+        self.new_line().await?;
+        for (number, location) in locations.iter().enumerate() {
+            self.println(TerminalTarget::Both, &format!("{:>3}) {}", number + 1, location.name)).await?;
+        }
+        self.session.op_text = format!("(1-{})", locations.len());
+        let answer = self
+            .input_field(
+                IceText::MoveUploadTo,
+                4,
+                &MASK_NUM,
+                CommandType::PendingUploads.get_help(),
+                None,
+                display_flags::NEWLINE | display_flags::LFBEFORE | display_flags::FIELDLEN,
+            )
+            .await?;
+        Ok(answer
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|index| locations.get(index)))
+    }
+}
@@ -1,6 +1,7 @@
 use jamjam::jam::{JamMessageBase, pack::PackOptions};
 
 use crate::icy_board::commands::CommandType;
+use crate::icy_board::state::user_commands::mods::attachments::{purge_attachments, referenced_attachments};
 use crate::{Res, datetime::IcbDate, icy_board::state::IcyBoardState};
 use crate::{
     icy_board::{
@@ -66,12 +67,21 @@ impl IcyBoardState {
         }
 
         self.new_line().await?;
+        let location = self.attachment_location(self.session.current_conference_number as i32).await;
         let mut removed = 0;
         let mut failed = false;
         for area in areas.iter() {
+            let attached = JamMessageBase::open(&area.path)
+                .map(|message_base| referenced_attachments(&message_base))
+                .unwrap_or_default();
             match JamMessageBase::open(&area.path) {
                 Ok(mut message_base) => match message_base.pack(&options) {
-                    Ok(report) => removed += report.removed,
+                    Ok(report) => {
+                        removed += report.removed;
+                        // What the pack dropped can't be downloaded any more.
+                        let after = referenced_attachments(&message_base);
+                        purge_attachments(&location, &attached, &after);
+                    }
                     Err(err) => {
                         log::error!("Error packing message base {}: {}", area.path.display(), err);
                         failed = true;
@@ -0,0 +1,326 @@
+use std::{collections::HashMap, io::Read, io::Write, path::Path};
+
+use bstr::BString;
+use chrono::{DateTime, Datelike, Local, Utc};
+use jamjam::jam::{JamMessage, JamMessageBase, attributes};
+use zip::write::SimpleFileOptions;
+
+use crate::{
+    Res,
+    icy_board::{
+        IcyBoard,
+        bluewave::{FtiRecord, InfArea, InfHeader, MailPacket, PacketArea, PacketMessage, ReplyPacket, area_flags, message_flags, reply_flags},
+        icb_text::IceText,
+        message_area::MessageArea,
+        state::{IcyBoardState, functions::display_flags},
+    },
+};
+
+/// Blue Wave names every file after the packet id, which is at most eight characters.
+pub fn bluewave_packet_id(board: &IcyBoard) -> String {
+    let id = if board.config.qwk_settings.bbs_id.is_empty() {
+        &board.config.board.name
+    } else {
+        &board.config.qwk_settings.bbs_id
+    };
+    let id: String = id.chars().filter(char::is_ascii_alphanumeric).take(8).collect::<String>().to_ascii_uppercase();
+    if id.is_empty() { "ICY".to_string() } else { id }
+}
+
+/// The archive is named `ID.<day><n>`, e.g. `ICY.MO1` for a packet made on a monday.
+fn mail_packet_name(packet_id: &str, now: DateTime<Local>) -> String {
+    const DAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+    format!("{}.{}1", packet_id, DAYS[now.weekday().num_days_from_monday() as usize])
+}
+
+/// The echo tag a reply names to find its area again.
+fn echo_tag(area: &MessageArea) -> String {
+    let tag = if !area.ftn_area_tag.is_empty() {
+        &area.ftn_area_tag
+    } else if !area.qwk_name.is_empty() {
+        &area.qwk_name
+    } else {
+        &area.name
+    };
+    tag.chars()
+        .map(|ch| if ch == ' ' { '_' } else { ch })
+        .take(20)
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+fn fti_date(date_written: u32) -> String {
+    let date_time = DateTime::from_timestamp(date_written as i64, 0).unwrap_or(Utc::now());
+    date_time.format("%d %b %y  %H:%M:%S").to_string()
+}
+
+impl IcyBoardState {
+    pub(super) async fn create_bluewave_packet(&mut self) -> Res<()> {
+        let (_number_to_msgid, msgid_to_number) = self.get_number_to_msgid().await;
+
+        let (packet_id, mut packet, conferences) = {
+            let board = self.board.lock().await;
+            let packet_id = bluewave_packet_id(&board);
+            let header = InfHeader {
+                login_name: self.session.user_name.clone(),
+                alias_name: self.session.alias_name.clone(),
+                sysop: if board.config.qwk_settings.bbs_sysop_name.is_empty() {
+                    board.config.board.operator.clone()
+                } else {
+                    board.config.qwk_settings.bbs_sysop_name.clone()
+                },
+                system_name: if board.config.qwk_settings.bbs_name.is_empty() {
+                    board.config.board.name.clone()
+                } else {
+                    board.config.qwk_settings.bbs_name.clone()
+                },
+                from_to_len: 35,
+                subject_len: 71,
+                packet_id: packet_id.clone(),
+                ..Default::default()
+            };
+            (packet_id, MailPacket { header, areas: Vec::new() }, board.conferences.clone())
+        };
+
+        let Some(user) = &self.session.current_user else {
+            return Ok(());
+        };
+        let max_msgs = user.qwk_config.as_ref().map(|config| config.max_msgs_per_conf).unwrap_or_default();
+        let mut pointers = Vec::new();
+        for (i, conf) in conferences.iter().enumerate() {
+            let Some(areas) = &conf.areas else {
+                continue;
+            };
+            for (j, area) in areas.iter().enumerate() {
+                let ptr = user.lastread_ptr_flags.get(&(i, j)).copied().unwrap_or_default();
+                if !ptr.include_qwk {
+                    continue;
+                }
+                let Some(conference_number) = msgid_to_number.get(&(i, j)) else {
+                    continue;
+                };
+                let mut flags = area_flags::SCANNING;
+                if !area.is_read_only {
+                    flags |= area_flags::POST;
+                }
+                if area.allow_aliases {
+                    flags |= area_flags::ALIAS_NAME;
+                }
+                if !area.ftn_area_tag.is_empty() {
+                    flags |= area_flags::ECHO;
+                }
+                let mut packet_area = PacketArea {
+                    info: InfArea {
+                        area_number: conference_number.to_string(),
+                        echo_tag: echo_tag(area),
+                        title: area.name.clone(),
+                        area_flags: flags,
+                        network_type: 0,
+                    },
+                    messages: Vec::new(),
+                };
+
+                match JamMessageBase::open(&area.path) {
+                    Ok(message_base) => {
+                        let highest = message_base.highest_message_number();
+                        let mut last = ptr.highest_msg_read as u32;
+                        for number in (ptr.highest_msg_read as u32 + 1)..=highest {
+                            if max_msgs > 0 && packet_area.messages.len() >= max_msgs as usize {
+                                break;
+                            }
+                            let Ok(header) = message_base.read_header(number) else {
+                                continue;
+                            };
+                            last = number;
+                            if header.is_deleted() {
+                                continue;
+                            }
+                            let Ok(text) = message_base.read_message_text(&header) else {
+                                continue;
+                            };
+                            let mut flags = message_flags::LOCAL;
+                            if header.attributes & attributes::MSG_PRIVATE != 0 {
+                                flags |= message_flags::PRIVATE;
+                            }
+                            packet_area.messages.push(PacketMessage {
+                                header: FtiRecord {
+                                    from: header.from().map(|s| s.to_string()).unwrap_or_default(),
+                                    to: header.to().map(|s| s.to_string()).unwrap_or_default(),
+                                    subject: header.subject().map(|s| s.to_string()).unwrap_or_default(),
+                                    date: fti_date(header.date_written),
+                                    msg_number: header.message_number.min(u16::MAX as u32) as u16,
+                                    reply_to: header.reply_to.min(u16::MAX as u32) as u16,
+                                    reply_at: header.reply_first.min(u16::MAX as u32) as u16,
+                                    flags,
+                                    ..Default::default()
+                                },
+                                // Blue Wave readers break lines at a carriage return.
+                                text: text.to_string().replace("\r\n", "\r").replace('\n', "\r"),
+                            });
+                        }
+                        if last > ptr.highest_msg_read as u32 {
+                            pointers.push(((i, j), last as usize));
+                        }
+                    }
+                    Err(err) => {
+                        log::error!("Blue Wave: can't open message base {}: {err}", area.path.display());
+                    }
+                }
+                packet.areas.push(packet_area);
+            }
+        }
+
+        // Goes away with everything in it however the download ends.
+        let output_dir = tempfile::tempdir()?;
+        let package = output_dir.path().join(mail_packet_name(&packet_id, Local::now()));
+        {
+            let files = packet.to_files()?;
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&package)?);
+            for (ext, data) in [("INF", &files.inf), ("MIX", &files.mix), ("FTI", &files.fti), ("DAT", &files.dat)] {
+                zip.start_file(format!("{packet_id}.{ext}"), SimpleFileOptions::default())?;
+                zip.write_all(data)?;
+            }
+            zip.finish()?;
+        }
+
+        self.transfer_statistics.downloaded_files = 0;
+        self.add_flagged_file(&package, true, false).await?;
+        self.download(false).await?;
+        self.session.flagged_files.retain(|f| f != &package);
+        if self.transfer_statistics.downloaded_files > 0 {
+            self.advance_offline_pointers(&pointers);
+        }
+        Ok(())
+    }
+
+    /// Reads a Blue Wave `ID.NEW` reply archive. Returns false when the archive
+    /// holds no Blue Wave reply files.
+    pub(super) async fn import_bluewave_reply(&mut self, path: &Path, packet_id: &str) -> Res<bool> {
+        let mut files = HashMap::new();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path)?)?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            files.insert(entry.name().to_ascii_uppercase(), data);
+        }
+        if !["UPL", "NET", "REQ"].iter().any(|ext| files.contains_key(&format!("{packet_id}.{ext}"))) {
+            return Ok(false);
+        }
+
+        let reply = match ReplyPacket::from_files(packet_id, &files) {
+            Ok(reply) => reply,
+            Err(err) => {
+                log::error!("Blue Wave reply {}: {err}", path.display());
+                self.display_text(IceText::ErrorExtracting, display_flags::NEWLINE).await?;
+                return Ok(true);
+            }
+        };
+
+        let mut tag_to_area = HashMap::new();
+        for (i, conf) in self.board.lock().await.conferences.iter().enumerate() {
+            if let Some(areas) = &conf.areas {
+                for (j, area) in areas.iter().enumerate() {
+                    tag_to_area.insert(echo_tag(area), (i, j));
+                }
+            }
+        }
+
+        for msg in reply.messages {
+            let header = msg.header;
+            let target = if header.msg_attr & reply_flags::NETMAIL != 0 {
+                None
+            } else {
+                tag_to_area.get(&header.echo_tag.to_ascii_uppercase()).copied()
+            };
+            let Some((conf, area)) = target else {
+                self.display_text(IceText::ReplyFailed, display_flags::NEWLINE).await?;
+                continue;
+            };
+            let mut msg_attributes = 0;
+            if header.msg_attr & reply_flags::PRIVATE != 0 {
+                msg_attributes |= attributes::MSG_PRIVATE;
+            }
+            let date_time = DateTime::from_timestamp(header.unix_date as i64, 0).unwrap_or(Utc::now());
+            let mut jam_msg = JamMessage::default()
+                .with_from(BString::from(self.session.user_name.clone()))
+                .with_to(BString::from(header.to))
+                .with_subject(BString::from(header.subject))
+                .with_date_time(date_time)
+                .with_attributes(msg_attributes)
+                .with_text(BString::from(msg.text.replace('\r', "\n")));
+            if header.reply_to != 0 {
+                jam_msg = jam_msg.with_reply_to(header.reply_to);
+            }
+            self.send_message(conf as i32, area as i32, jam_msg, IceText::ReplySuccessful).await?;
+        }
+
+        // The board has no netmail area of its own, a netmail reply ends up as
+        // private mail for the addressee on this board.
+        for msg in reply.netmail {
+            let jam_msg = JamMessage::default()
+                .with_from(BString::from(self.session.user_name.clone()))
+                .with_to(BString::from(msg.header.to))
+                .with_subject(BString::from(msg.header.subject))
+                .with_date_time(Utc::now())
+                .with_attributes(attributes::MSG_PRIVATE)
+                .with_text(BString::from(msg.text.replace('\r', "\n")));
+            self.send_message(-1, 0, jam_msg, IceText::ReplySuccessful).await?;
+        }
+
+        for request in reply.file_requests {
+            self.session.tokens.push_back(request);
+            self.flag_files_cmd(false).await?;
+        }
+        Ok(true)
+    }
+
+    /// Moves the pointers of the areas that went into an offline packet past
+    /// the last message packed.
+    fn advance_offline_pointers(&mut self, pointers: &[((usize, usize), usize)]) {
+        let Some(user) = &mut self.session.current_user else {
+            return;
+        };
+        for (area, last) in pointers {
+            let ptr = user.lastread_ptr_flags.entry(*area).or_default();
+            ptr.highest_msg_read = ptr.highest_msg_read.max(*last);
+            ptr.last_read = ptr.last_read.max(*last);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::{bluewave_packet_id, echo_tag, mail_packet_name};
+    use crate::icy_board::{IcyBoard, message_area::MessageArea};
+
+    #[test]
+    fn packet_id_is_eight_upper_case_characters() {
+        let mut board = IcyBoard::new();
+        board.config.board.name = "Icy Board Test System".to_string();
+        assert_eq!(bluewave_packet_id(&board), "ICYBOARD");
+        board.config.qwk_settings.bbs_id = "cool".to_string();
+        assert_eq!(bluewave_packet_id(&board), "COOL");
+    }
+
+    #[test]
+    fn packet_is_named_after_the_weekday() {
+        let monday = Local.with_ymd_and_hms(2026, 10, 12, 12, 0, 0).unwrap();
+        assert_eq!(mail_packet_name("ICY", monday), "ICY.MO1");
+    }
+
+    #[test]
+    fn echo_tag_prefers_the_network_tag() {
+        let mut area = MessageArea {
+            name: "General Chatter".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(echo_tag(&area), "GENERAL_CHATTER");
+        area.qwk_name = "General".to_string();
+        assert_eq!(echo_tag(&area), "GENERAL");
+        area.ftn_area_tag = "FSX_GEN".to_string();
+        assert_eq!(echo_tag(&area), "FSX_GEN");
+    }
+}
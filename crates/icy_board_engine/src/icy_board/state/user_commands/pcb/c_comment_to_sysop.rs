@@ -1,4 +1,7 @@
-use crate::icy_board::state::user_commands::mods::editor::{EditResult, EditState};
+use crate::icy_board::state::user_commands::mods::{
+    attachments::attachment_subfield,
+    editor::{EditResult, EditState, EditorInfo},
+};
 use crate::{Res, icy_board::state::IcyBoardState};
 
 use crate::icy_board::user_base::FSEMode;
@@ -152,31 +155,72 @@ impl IcyBoardState {
     ) -> Res<()> {
         self.displaycmdfile("preedit").await?;
 
-        // PCBoard asks whether to use the full screen editor unless the user has a
-        // fixed preference (msgeditor()/TXT_USEFULLSCREEN). Skipping this prompt made
-        // stuffed answers (e.g. from a PPE) leak into the message body.
-        let use_fse = match self.session.fse_mode {
-            FSEMode::Yes => true,
-            FSEMode::No => false,
-            FSEMode::Ask => self.prompt_use_fse().await?,
-        };
-
         let mut editor = EditState {
             from: self.session.user_name.clone(),
             to: to.to_string(),
             subj: subj.to_string(),
             msg: Vec::new(),
             cursor: Position::new(0, 0),
-            use_fse,
-            insert_mode: use_fse,
+            use_fse: false,
+            insert_mode: false,
             top_line: 0,
             max_line_length: 79,
             max_lines: self.get_board().await.config.message.max_msg_lines.max(1) as usize,
         };
 
-        match editor.edit_message(self).await? {
+        let area_name = if conf < 0 {
+            "E-Mail".to_string()
+        } else {
+            self.get_board()
+                .await
+                .conferences
+                .get(conf as usize)
+                .and_then(|conference| conference.areas.as_ref()?.get(area as usize))
+                .map(|area| area.name.clone())
+                .unwrap_or_default()
+        };
+        // The name the message goes out under may be an alias, the editor is told both.
+        let real_name = self
+            .session
+            .current_user
+            .as_ref()
+            .map_or_else(|| self.session.user_name.clone(), |user| user.get_name().to_string());
+        let info = EditorInfo {
+            area: &area_name,
+            private: attributes & jamjam::jam::attributes::MSG_PRIVATE != 0,
+            user_number: self.session.cur_user_id,
+            real_name: &real_name,
+            security: self.session.cur_security,
+            screen_lines: self.session.page_len,
+        };
+        let result = match editor.external_edit(self, &info).await? {
+            Some(result) => result,
+            None => {
+                // PCBoard asks whether to use the full screen editor unless the user has a
+                // fixed preference (msgeditor()/TXT_USEFULLSCREEN). Skipping this prompt made
+                // stuffed answers (e.g. from a PPE) leak into the message body.
+                let use_fse = match self.session.fse_mode {
+                    FSEMode::Yes => true,
+                    FSEMode::No => false,
+                    FSEMode::Ask => self.prompt_use_fse().await?,
+                };
+                editor.use_fse = use_fse;
+                editor.insert_mode = use_fse;
+                editor.edit_message(self).await?
+            }
+        };
+
+        match result {
             EditResult::Abort => {}
-            result @ (EditResult::SendMessage | EditResult::CarbonCopy) => {
+            result @ (EditResult::SendMessage | EditResult::SendWithAttachment | EditResult::CarbonCopy) => {
+                let mut attributes = attributes;
+                let mut sub_fields = sub_fields;
+                if matches!(result, EditResult::SendWithAttachment)
+                    && let Some(path) = self.receive_attachment(conf, area).await?
+                {
+                    attributes |= jamjam::jam::attributes::MSG_FILEATTACH;
+                    sub_fields.push(attachment_subfield(&path));
+                }
                 let msg = message_text(&editor.msg, self.get_board().await.config.message.allow_esc_codes);
                 let original = make_message(&editor, &editor.to, &msg, attributes, &password, packout_date, &sub_fields);
                 self.send_message(conf, area, original, text).await?;
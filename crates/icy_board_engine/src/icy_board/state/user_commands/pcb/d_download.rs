@@ -3,8 +3,10 @@ use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use async_recursion::async_recursion;
+use dizbase::file_base_scanner::advertise::{Advertisement, advertise_zip};
 use humanize_bytes::humanize_bytes_decimal;
 
+use crate::icy_board::file_directory::Advertise;
 use crate::icy_board::icb_config::IcbColor;
 use crate::icy_board::limits::{self, BatchSoFar, LimitVerdict, TransferHistory};
 use crate::icy_board::state::functions::{MASK_NUM, transfer_cps};
@@ -160,7 +162,9 @@ impl IcyBoardState {
                 if files.is_empty() {
                     return Ok(());
                 }
-                match prot.initiate_send(&mut *self.connection, &files).await {
+                // Kept alive until the transfer is over, it holds the copies carrying the ad.
+                let (sending, _advertised) = self.advertise_downloads(&files).await;
+                match prot.initiate_send(&mut *self.connection, &sending).await {
                     Ok(mut state) => {
                         let started = Instant::now();
                         while !state.is_finished {
@@ -175,6 +179,12 @@ impl IcyBoardState {
                         self.transfer_statistics.downloaded_files = state.send_state.finished_files.len();
                         self.display_text(IceText::BatchSend, display_flags::LFBEFORE).await?;
 
+                        // What was sent is charged and counted as the stored file, not the copy.
+                        for (_, path) in &mut state.send_state.finished_files {
+                            if let Some(index) = sending.iter().position(|copy| copy == path) {
+                                *path = files[index].clone();
+                            }
+                        }
                         let sent: Vec<String> = state.send_state.finished_files.iter().map(|(name, _)| name.clone()).collect();
                         let cps = transfer_cps(state.send_state.total_bytes_transfered, started);
                         self.log_transfer(false, &sent, &protocol_str, state.send_state.errors, cps).await?;
@@ -196,6 +206,7 @@ impl IcyBoardState {
                         self.println(TerminalTarget::Both, &format!("Error: {e}")).await?;
                     }
                 }
+                self.remove_extracted_members().await;
             } else {
                 self.println(TerminalTarget::Both, "Protocol not found.").await?;
             }
@@ -235,6 +246,38 @@ impl IcyBoardState {
         }
     }
 
+    /// The advertisement of the area in this conference that `dir` belongs to.
+    pub(crate) fn area_advertisement(&self, dir: &Path, at: Advertise) -> Option<Advertisement> {
+        let directories = self.session.current_conference.directories.as_ref()?;
+        directories.iter().find(|area| area.path == dir)?.advertisement(at)
+    }
+
+    /// Copies the files of areas that advertise on download into a scratch
+    /// directory with the ad added, under their own names. The stored archive,
+    /// and the hash the duplicate check keeps for it, are left as they are.
+    /// A file that cannot be copied goes out as it is.
+    async fn advertise_downloads(&self, files: &[PathBuf]) -> (Vec<PathBuf>, Option<tempfile::TempDir>) {
+        let files: Vec<(PathBuf, Option<Advertisement>)> = files
+            .iter()
+            .map(|path| {
+                let ad = path.parent().and_then(|dir| self.area_advertisement(dir, Advertise::Download));
+                (path.clone(), ad)
+            })
+            .collect();
+        if files.iter().all(|(_, ad)| ad.is_none()) {
+            return (files.into_iter().map(|(path, _)| path).collect(), None);
+        }
+        let unchanged: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+        // Repacking rewrites whole archives, the other nodes go on meanwhile.
+        match tokio::task::spawn_blocking(move || advertise_copies(files)).await {
+            Ok(advertised) => advertised,
+            Err(err) => {
+                log::error!("Could not add the board ad to the download: {err}");
+                (unchanged, None)
+            }
+        }
+    }
+
     fn charged_downloads(&mut self, finished: &[(String, PathBuf)]) -> (u64, u64) {
         let free_areas = self.free_download_areas();
         finished
@@ -468,6 +511,37 @@ fn downloads_per_area(offered: &[PathBuf], sent: &[String]) -> HashMap<PathBuf,
     per_area
 }
 
+/// The copying half of `advertise_downloads`, run on a blocking thread.
+fn advertise_copies(files: Vec<(PathBuf, Option<Advertisement>)>) -> (Vec<PathBuf>, Option<tempfile::TempDir>) {
+    let mut scratch: Option<tempfile::TempDir> = None;
+    let mut sending = Vec::with_capacity(files.len());
+    for (path, ad) in files {
+        let (Some(ad), Some(name)) = (ad, path.file_name().map(ToOwned::to_owned)) else {
+            sending.push(path);
+            continue;
+        };
+        if scratch.is_none() {
+            scratch = tempfile::tempdir()
+                .map_err(|err| log::error!("No scratch directory for download ads: {err}"))
+                .ok();
+        }
+        let Some(directory) = &scratch else {
+            sending.push(path);
+            continue;
+        };
+        let copy = directory.path().join(name);
+        match advertise_zip(&path, &copy, &ad) {
+            Ok(true) => sending.push(copy),
+            Ok(false) => sending.push(path),
+            Err(err) => {
+                log::error!("Could not add the board ad to {}: {}", path.display(), err);
+                sending.push(path);
+            }
+        }
+    }
+    (sending, scratch)
+}
+
 #[cfg(test)]
 mod download_counter_tests {
     use super::downloads_per_area;
@@ -1,5 +1,10 @@
+use std::path::PathBuf;
+
 use crate::icy_board::state::user_commands::mods::filebrowser::FileFilter;
-use dizbase::file_base::pattern::{MatchOptions, Pattern};
+use dizbase::file_base::{
+    pattern::{MatchOptions, Pattern},
+    tags::normalize_tag,
+};
 
 use crate::{
     Res,
@@ -17,6 +22,32 @@ use crate::{
     vm::TerminalTarget,
 };
 
+/// What `L` was asked for: `#demo #1995` looks for files with both tags, and
+/// anything that does not start with `#` is the file name as before.
+#[derive(Debug, PartialEq)]
+struct FileSearch {
+    pattern: String,
+    tags: Vec<String>,
+}
+
+impl FileSearch {
+    fn parse(input: &str) -> Self {
+        let mut names = Vec::new();
+        let mut tags = Vec::new();
+        for word in input.split_whitespace() {
+            if word.starts_with('#') {
+                if let Some(tag) = normalize_tag(word) {
+                    tags.push(tag);
+                }
+            } else {
+                names.push(word);
+            }
+        }
+        let pattern = if names.is_empty() { "*".to_string() } else { names.join(" ") };
+        Self { pattern, tags }
+    }
+}
+
 impl IcyBoardState {
     pub async fn find_files_cmd(&mut self) -> Res<()> {
         if self.session.current_conference.directories.is_none() || self.session.current_conference.directories.as_ref().unwrap().is_empty() {
@@ -45,6 +76,9 @@ impl IcyBoardState {
         if search_pattern.is_empty() {
             return Ok(());
         }
+        // A lone '#' asks which tags there are to search for.
+        let show_tag_cloud = search_pattern.trim() == "#";
+        let FileSearch { pattern: search_pattern, tags } = FileSearch::parse(&search_pattern);
 
         // The prompt promises DOS wildcards, so match like PCBoard did: '*'/'?' globbing
         // against the whole name, case insensitively.
@@ -91,6 +125,14 @@ impl IcyBoardState {
                 self.session.disp_options.no_change();
 
                 for (num, desc, path, metadata) in dir_numbers.numbers {
+                    if show_tag_cloud {
+                        self.display_tag_cloud(num, &desc, &path, &metadata).await?;
+                        if self.session.disp_options.abort_printout {
+                            break;
+                        }
+                        continue;
+                    }
+
                     self.display_text(IceText::ScanningDirectory, display_flags::DEFAULT).await?;
                     self.print(TerminalTarget::Both, &format!(" {num}")).await?;
                     if !desc.is_empty() {
@@ -99,6 +141,18 @@ impl IcyBoardState {
                     }
                     self.new_line().await?;
                     self.reset_color(TerminalTarget::Both).await?;
+                    let tagged = if tags.is_empty() {
+                        None
+                    } else {
+                        let Ok(base) = self.get_filebase(&path, &metadata).await else {
+                            continue;
+                        };
+                        let tagged = base.lock().await.tagged(&tags)?;
+                        if tagged.is_empty() {
+                            continue;
+                        }
+                        Some(tagged)
+                    };
                     let r = search_pattern.clone();
                     self.display_file_area(
                         &path,
@@ -109,6 +163,11 @@ impl IcyBoardState {
                             {
                                 return false;
                             }
+                            if let Some(tagged) = &tagged
+                                && !tagged.contains(&p.id)
+                            {
+                                return false;
+                            }
                             r.matches_with(p.name(), &match_options)
                         }),
                     )
@@ -120,4 +179,67 @@ impl IcyBoardState {
             }
         }
     }
+
+    /// Lists the tags of an area the way a tag cloud would, the most used ones
+    /// brightest.
+    async fn display_tag_cloud(&mut self, num: usize, desc: &str, path: &PathBuf, metadata: &PathBuf) -> Res<()> {
+        let Ok(base) = self.get_filebase(path, metadata).await else {
+            return Ok(());
+        };
+        let cloud = base.lock().await.tag_cloud()?;
+        if cloud.is_empty() {
+            return Ok(());
+        }
+        self.set_color(TerminalTarget::Both, IcbColor::dos_yellow()).await?;
+        self.print(TerminalTarget::Both, &format!("{num}")).await?;
+        if !desc.is_empty() {
+            self.set_color(TerminalTarget::Both, IcbColor::dos_light_green()).await?;
+            self.print(TerminalTarget::Both, &format!(" ({desc})")).await?;
+        }
+        self.new_line().await?;
+
+        let most = cloud[0].1;
+        let mut column = 0;
+        for (tag, count) in cloud {
+            let word = format!("#{tag}({count}) ");
+            if column > 0 && column + word.len() > 79 {
+                self.new_line().await?;
+                column = 0;
+            }
+            let color = if count == most {
+                IcbColor::dos_white()
+            } else if count * 2 >= most {
+                IcbColor::dos_light_cyan()
+            } else {
+                IcbColor::dos_gray()
+            };
+            self.set_color(TerminalTarget::Both, color).await?;
+            self.print(TerminalTarget::Both, &word).await?;
+            column += word.len();
+            if self.session.disp_options.abort_printout {
+                break;
+            }
+        }
+        self.new_line().await?;
+        self.reset_color(TerminalTarget::Both).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags_and_the_file_name_are_told_apart() {
+        assert_eq!(
+            FileSearch::parse("#DEMO *.ZIP #1995"),
+            FileSearch {
+                pattern: "*.ZIP".to_string(),
+                tags: vec!["demo".to_string(), "1995".to_string()],
+            }
+        );
+        assert_eq!(FileSearch::parse("#demo").pattern, "*");
+        assert!(FileSearch::parse("#").tags.is_empty());
+    }
 }
@@ -1,6 +1,7 @@
 pub mod _11_node_list;
 pub mod _12_logoff_node;
 pub mod _16_dir_command;
+pub mod _17_pending_uploads;
 pub mod _1_view_caller_log;
 pub mod _2_view_user_file;
 pub mod _3_pack_message_base;
@@ -12,6 +13,7 @@ pub mod a_abandon_conference;
 pub mod alias_toggle_alias;
 pub mod b_show_bulletins;
 pub mod bd_batch_download;
+pub mod bluewave;
 pub mod br_broadcast;
 pub mod bu_batch_upload;
 pub mod c_comment_to_sysop;
@@ -170,31 +170,82 @@ impl IcyBoardState {
     async fn run_local_door(&mut self, door: &crate::icy_board::doors::Door, door_number: usize) -> Res<()> {
         let file_name = self.resolve_path(&door.path);
         if door.path.ends_with("ppe") {
-            self.run_ppe(&file_name, None).await?;
+            self.ppe_grant = door.permissions.clone();
+            let result = self.run_ppe(&file_name, None).await;
+            self.ppe_grant = None;
+            result?;
             return Ok(());
         }
         let working_directory = file_name.parent().unwrap();
         door.create_drop_file(self, working_directory, door_number).await?;
         let mut cmd = if door.use_shell_execute {
-            tokio::process::Command::new("sh")
-                .arg("-c")
-                .arg(format!("{}", file_name.display()))
-                .current_dir(working_directory)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .spawn()?
+            let mut cmd = tokio::process::Command::new("sh");
+            cmd.arg("-c").arg(format!("{}", file_name.display()));
+            cmd
         } else {
             tokio::process::Command::new(&file_name)
-                .current_dir(working_directory)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .spawn()?
         };
+        cmd.current_dir(working_directory);
+        self.run_door_process(&mut cmd).await?;
+        log::info!("door exited.");
+
+        Ok(())
+    }
+
+    /// Runs a door program on the caller's connection: its stdout goes to the
+    /// caller (and a watching sysop), the caller's keys go to its stdin.
+    pub(crate) async fn run_door_process(&mut self, cmd: &mut tokio::process::Command) -> Res<std::process::ExitStatus> {
+        let mut cmd = cmd.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let stdin = cmd.stdin.take().unwrap();
+        let stdout = cmd.stdout.take().unwrap();
+        self.pump_door_io(&mut cmd, stdout, stdin).await
+    }
+
+    /// The same for a program that draws on the screen itself, like a full screen
+    /// editor: it gets a terminal of the caller's size instead of pipes.
+    #[cfg(unix)]
+    pub(crate) async fn run_door_process_in_terminal(&mut self, cmd: &mut tokio::process::Command) -> Res<std::process::ExitStatus> {
+        let rows = self.session.page_len.max(1);
+        // A terminal that never said how wide it is gets the usual 80 columns.
+        let columns = match self.session.term_caps.term_size.0 {
+            0 => 80,
+            columns => columns,
+        };
+        let (master, slave) = open_terminal(columns, rows)?;
+        cmd.env("TERM", "ansi")
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // SAFETY: only async-signal-safe calls between fork and exec.
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = cmd.spawn()?;
+        // Once the program is gone the board's side reads an error instead of waiting
+        // for output forever, as long as no other copy of the program's side is open.
+        cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        let output = tokio::fs::File::from_std(master.try_clone()?);
+        let input = tokio::fs::File::from_std(master);
+        self.pump_door_io(&mut child, output, input).await
+    }
 
+    #[cfg(not(unix))]
+    pub(crate) async fn run_door_process_in_terminal(&mut self, cmd: &mut tokio::process::Command) -> Res<std::process::ExitStatus> {
+        self.run_door_process(cmd).await
+    }
+
+    async fn pump_door_io<R, W>(&mut self, cmd: &mut tokio::process::Child, mut stdout: R, mut stidn: W) -> Res<std::process::ExitStatus>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        W: tokio::io::AsyncWrite + Unpin,
+    {
         let mut write_buf = vec![0; 32 * 1024];
         let mut read_buf = vec![0; 128 * 1024];
-        let mut stidn = cmd.stdin.take().unwrap();
-        let mut stdout = cmd.stdout.take().unwrap();
 
         loop {
             tokio::select! {
@@ -226,7 +277,7 @@ impl IcyBoardState {
                 read_data = self.connection.read(&mut write_buf) => {
                     match read_data {
                         Ok(size) => {
-                            if stidn.write_all(&write_buf[0..size]).await.is_err() {
+                            if stidn.write_all(&write_buf[0..size]).await.is_err() || stidn.flush().await.is_err() {
                                 break;
                             }
                         }
@@ -237,14 +288,14 @@ impl IcyBoardState {
                 }
             };
 
-            if cmd.try_wait()?.is_some() {
-                break;
+            if let Some(status) = cmd.try_wait()? {
+                return Ok(status);
             }
             std::thread::sleep(Duration::from_millis(25));
         }
-        log::info!("door exited.");
-
-        Ok(())
+        // The caller is gone, don't leave the door running on the node.
+        let _ = cmd.kill().await;
+        Ok(cmd.wait().await?)
     }
 
     pub async fn run_bbslink_door(&mut self, bbslink: &BBSLink, door: &Door) -> Res<()> {
@@ -424,6 +475,29 @@ pub enum BBSLinkError {
     UnknownError(usize),
 }
 
+/// Opens a pseudo terminal of the given size. The first file is the board's side,
+/// the second the program's.
+#[cfg(unix)]
+fn open_terminal(columns: u16, rows: u16) -> std::io::Result<(std::fs::File, std::fs::File)> {
+    use std::os::fd::FromRawFd;
+
+    let mut master = 0;
+    let mut slave = 0;
+    let mut size = libc::winsize {
+        ws_row: rows,
+        ws_col: columns,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: openpty fills in two descriptors that nothing else owns.
+    unsafe {
+        if libc::openpty(&mut master, &mut slave, std::ptr::null_mut(), std::ptr::null_mut(), &mut size) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok((std::fs::File::from_raw_fd(master), std::fs::File::from_raw_fd(slave)))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::icy_board::state::user_commands::pcb::open_door::BBSLinkError;
@@ -172,7 +172,8 @@ impl IcyBoardState {
             // A header scan lists and stops; it does not walk into the messages.
             return Ok(());
         }
-        self.read_msgs_from_base(message_base, false).await
+        self.read_msgs_from_base(message_base, self.session.current_conference_number as i32, false)
+            .await
     }
 }
 
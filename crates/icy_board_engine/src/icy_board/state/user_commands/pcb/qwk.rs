@@ -28,7 +28,7 @@ use crate::{
     vm::TerminalTarget,
 };
 
-use super::u_upload_file::create_protocol;
+use super::{bluewave::bluewave_packet_id, u_upload_file::create_protocol};
 
 const MASK_CONFNUMBERS: &str = "0123456789-SDL?";
 
@@ -99,7 +99,7 @@ impl IcyBoardState {
                     .input_field(
                         IceText::QWKCommands,
                         2,
-                        "DSU",
+                        "BDSU",
                         CommandType::QWK.get_help(),
                         None,
                         display_flags::UPCASE | display_flags::STACKED | display_flags::NEWLINE | display_flags::LFBEFORE,
@@ -117,6 +117,10 @@ impl IcyBoardState {
                         self.create_qwk_packet().await?;
                         break;
                     }
+                    "B" => {
+                        self.create_bluewave_packet().await?;
+                        break;
+                    }
                     "U" => {
                         self.upload_qwk_reply().await?;
                         break;
@@ -133,7 +137,7 @@ impl IcyBoardState {
         Ok(())
     }
 
-    async fn get_number_to_msgid(&self) -> (HashMap<u16, (usize, usize)>, HashMap<(usize, usize), u16>) {
+    pub(super) async fn get_number_to_msgid(&self) -> (HashMap<u16, (usize, usize)>, HashMap<(usize, usize), u16>) {
         let conferences = &self.board.lock().await.conferences;
         let mut number_to_msgid = HashMap::new();
         for (i, conf) in conferences.iter().enumerate() {
@@ -528,13 +532,14 @@ impl IcyBoardState {
             self.display_text(IceText::TransferAborted, display_flags::NEWLINE).await?;
             return Ok(());
         };
-        let bbs_id = {
+        let (bbs_id, packet_id) = {
             let board = self.board.lock().await;
-            if board.config.qwk_settings.bbs_id.is_empty() {
+            let bbs_id = if board.config.qwk_settings.bbs_id.is_empty() {
                 board.config.board.name.clone()
             } else {
                 board.config.qwk_settings.bbs_id.clone()
-            }
+            };
+            (bbs_id, bluewave_packet_id(&board))
         };
         match prot.initiate_recv(&mut *self.connection).await {
             Ok(mut state) => {
@@ -582,7 +587,7 @@ impl IcyBoardState {
                                 self.display_text(IceText::ReplyFailed, display_flags::NEWLINE).await?;
                             }
                         }
-                    } else {
+                    } else if !self.import_bluewave_reply(&path, &packet_id).await? {
                         self.display_text(IceText::ErrorExtracting, display_flags::NEWLINE).await?;
                     }
 
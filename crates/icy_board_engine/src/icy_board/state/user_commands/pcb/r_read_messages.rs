@@ -26,7 +26,8 @@ impl IcyBoardState {
             let message_base_file = message_base_file.clone();
             match JamMessageBase::open(&message_base_file) {
                 Ok(message_base) => {
-                    self.read_msgs_from_base(message_base, false).await?;
+                    self.read_msgs_from_base(message_base, self.session.current_conference_number as i32, false)
+                        .await?;
                     return Ok(());
                 }
                 Err(err) => {
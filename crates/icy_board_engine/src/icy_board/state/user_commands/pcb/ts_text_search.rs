@@ -98,7 +98,8 @@ impl IcyBoardState {
                     let viewer = MessageViewer::load(&self.display_text)?;
                     match JamMessageBase::open(path) {
                         Ok(mut message_base) => {
-                            self.read_message_number(&mut message_base, &viewer, 1, 1, false, &MessageFilter::default())
+                            let conference = self.session.current_conference_number as i32;
+                            self.read_message_number(&mut message_base, conference, &viewer, 1, 1, false, &MessageFilter::default(), None)
                                 .await?;
                             return Ok(());
                         }
@@ -1,5 +1,7 @@
 use crate::icy_board::commands::CommandType;
-use crate::icy_board::icb_config::IcbColor;
+use crate::icy_board::file_directory::Advertise;
+use crate::icy_board::icb_config::{DuplicateUploads, IcbColor};
+use crate::icy_board::upload_check::verify_upload;
 use crate::{Res, icy_board::state::IcyBoardState};
 use crate::{
     icy_board::{
@@ -11,10 +13,16 @@ use crate::{
     },
     vm::TerminalTarget,
 };
-use dizbase::file_base::metadata::{MetadataHeader, MetadataType};
-use dizbase::file_base_scanner::scan_file;
+use dizbase::file_base::{
+    FileBase,
+    hash_index::IndexedFile,
+    metadata::{MetadataHeader, MetadataType},
+    tags::parse_tags,
+};
+use dizbase::file_base_scanner::{advertise::advertise_in_place, scan_file};
 use fs4::available_space;
 use icy_net::protocol::{Protocol, TransferProtocolType, XYModemVariant, XYmodem, Zmodem};
+use std::path::Path;
 use std::time::Instant;
 
 fn has_upload_space(path: &std::path::Path, minimum_kib: u32) -> std::io::Result<bool> {
@@ -100,6 +108,43 @@ impl IcyBoardState {
         Ok(Some((lines, private)))
     }
 
+    /// Tags are asked for after the description when the sysop wants them, and
+    /// are what `L` finds with `#demo #1995`. An empty answer leaves the file untagged.
+    pub async fn ask_upload_tags(&mut self) -> Res<Vec<String>> {
+        if !self.get_board().await.config.file_transfer.ask_upload_tags {
+            return Ok(Vec::new());
+        }
+        let answer = self
+            .input_field(
+                IceText::UploadTags,
+                60,
+                &MASK_ASCII,
+                "",
+                None,
+                display_flags::NEWLINE | display_flags::LFBEFORE | display_flags::FIELDLEN,
+            )
+            .await?;
+        Ok(parse_tags(&answer))
+    }
+
+    async fn upload_name_taken(&self, upload_location: &Path, file_name: &str) -> bool {
+        if upload_location.join(file_name).exists() {
+            return true;
+        }
+        self.load_upload_index().await;
+        let board = self.get_board().await;
+        board.upload_index.as_ref().and_then(|index| index.find_name(file_name)).is_some()
+    }
+
+    /// What an upload duplicates, as `MetadataType::Duplicate` names it. An original
+    /// removed from disk since the index was built doesn't count.
+    async fn find_duplicate_upload(&self, name: &str, size: u64, hash: u64) -> Option<String> {
+        self.load_upload_index().await;
+        let board = self.get_board().await;
+        let original = board.upload_index.as_ref()?.find(name, size, hash)?.original();
+        original.path.exists().then(|| original.describe())
+    }
+
     pub async fn upload_file(&mut self) -> Res<()> {
         if let Some(window) = self.event_window().await
             && window.uploads_blocked(&chrono::Local::now())
@@ -123,9 +168,11 @@ impl IcyBoardState {
             return Ok(());
         }
 
+        let duplicate_uploads = self.get_board().await.config.file_transfer.duplicate_uploads;
+
         // PCBoard asks for a name until the answer is empty or a file is accepted, so an
         // abandoned description comes back here rather than ending the command.
-        let (description, private_upload, had_token) = loop {
+        let (description, private_upload, tags, had_token) = loop {
             let stacked_name = self.session.tokens.pop_front();
             let had_token = stacked_name.is_some();
             let file_name = if let Some(token) = stacked_name {
@@ -145,9 +192,22 @@ impl IcyBoardState {
             if file_name.is_empty() {
                 return Ok(());
             }
+            // Whatever is done with duplicates, a name already in the upload directory is never overwritten.
+            let name_taken = if duplicate_uploads == DuplicateUploads::Reject {
+                self.upload_name_taken(&upload_location, &file_name).await
+            } else {
+                upload_location.join(&file_name).exists()
+            };
+            if name_taken {
+                self.session.op_text = file_name;
+                self.display_text(IceText::DuplicateFile, display_flags::NEWLINE | display_flags::LFBEFORE)
+                    .await?;
+                continue;
+            }
 
             if let Some((description, private_upload)) = self.ask_upload_description(&file_name).await? {
-                break (description, private_upload, had_token);
+                let tags = self.ask_upload_tags().await?;
+                break (description, private_upload, tags, had_token);
             }
         };
 
@@ -179,7 +239,11 @@ impl IcyBoardState {
 
         self.display_text(IceText::UploadStatus, display_flags::DEFAULT).await?;
         self.display_text(
-            if private_upload { IceText::ScreenEditor } else { IceText::PostedImmediately },
+            if private_upload || file_transfer.hold_uploads {
+                IceText::ScreenEditor
+            } else {
+                IceText::PostedImmediately
+            },
             display_flags::NEWLINE,
         )
         .await?;
@@ -281,11 +345,43 @@ impl IcyBoardState {
                     self.board.lock().await.statistics.add_upload(&state);
                     self.board.lock().await.save_statistics()?;
 
+                    let file_base = self.get_filebase(&upload_location, &upload_metadata).await?;
                     for (x, path) in state.recieve_state.finished_files {
-                        let dest = upload_location.join(x);
+                        let dest = upload_location.join(&x);
+                        // A batch can bring a name the first prompt never saw, and
+                        // copying over it would lose the file listed under it.
+                        if dest.exists() || file_base.lock().await.contains_name(&x) {
+                            self.session.op_text = x.clone();
+                            self.display_text(IceText::DuplicateFile, display_flags::NEWLINE).await?;
+                            std::fs::remove_file(&path)?;
+                            continue;
+                        }
+                        let size = std::fs::metadata(&path)?.len();
+                        let hash = FileBase::get_hash(&path)?;
+                        let duplicate = if duplicate_uploads.is_allow() {
+                            None
+                        } else {
+                            self.find_duplicate_upload(&x, size, hash).await
+                        };
+                        if let Some(original) = &duplicate {
+                            log::warn!("upload {x} duplicates {original}");
+                        }
+                        if duplicate_uploads == DuplicateUploads::Reject && duplicate.is_some() {
+                            self.session.op_text = x.clone();
+                            self.display_text(IceText::DuplicateFile, display_flags::NEWLINE).await?;
+                            std::fs::remove_file(&path)?;
+                            continue;
+                        }
                         std::fs::copy(&path, &dest)?;
 
-                        let file_base = self.get_filebase(&upload_location, &upload_metadata).await?;
+                        let verdict = verify_upload(&dest, file_transfer.verify_files_uploaded, &file_transfer.upload_checkers).await;
+                        if let Some(verdict) = &verdict {
+                            self.session.op_text = x.clone();
+                            self.display_text(IceText::VerifyingFile, display_flags::DEFAULT).await?;
+                            self.display_text(if verdict.is_passed() { IceText::Passed } else { IceText::Failed }, display_flags::NEWLINE)
+                                .await?;
+                        }
+
                         let mut metadata = scan_file(&dest)?;
                         metadata.push(MetadataHeader {
                             data: self.session.get_username_or_alias().as_bytes().to_vec(),
@@ -298,7 +394,51 @@ impl IcyBoardState {
                                 metadata_type: MetadataType::FileID,
                             });
                         }
-                        file_base.lock().await.add_file(&dest, metadata.clone())?;
+                        if !tags.is_empty() {
+                            metadata.push(MetadataHeader::tags(&tags));
+                        }
+                        if let Some(verdict) = verdict {
+                            let failed = !verdict.is_passed();
+                            metadata.push(verdict.to_metadata());
+                            if failed {
+                                self.quarantine_upload(&dest, metadata, &file_transfer.quarantine_path).await?;
+                                std::fs::remove_file(&path)?;
+                                continue;
+                            }
+                        }
+                        // The scan above hashed the upload as it came in, and that hash is what
+                        // a later duplicate is compared with - not the archive with our ad.
+                        if let Some(ad) = self.area_advertisement(&upload_location, Advertise::Upload)
+                            && let Err(err) = advertise_in_place(&dest, &ad)
+                        {
+                            log::error!("Could not add the board ad to {}: {}", dest.display(), err);
+                        }
+                        // A flagged duplicate waits for the sysop like a held upload.
+                        let hold = file_transfer.hold_uploads || duplicate.is_some();
+                        if let Some(original) = duplicate {
+                            metadata.push(MetadataHeader::new(MetadataType::Duplicate, original.into_bytes()));
+                        }
+                        let listed = if hold {
+                            file_base.lock().await.hold_file(&dest, metadata)
+                        } else {
+                            file_base.lock().await.add_file(&dest, metadata)
+                        };
+                        // One file that can't be listed doesn't cost the caller the rest of the batch.
+                        if let Err(err) = listed {
+                            log::error!("Can't list upload {} in {}: {err}", x, upload_location.display());
+                            std::fs::remove_file(&dest)?;
+                            std::fs::remove_file(&path)?;
+                            continue;
+                        }
+                        if let Some(index) = &mut self.get_board().await.upload_index {
+                            index.add(IndexedFile {
+                                area: self.session.current_conference.name.clone(),
+                                path: dest.clone(),
+                                name: x.clone(),
+                                size,
+                                hash,
+                            });
+                        }
 
                         std::fs::remove_file(&path)?;
                     }
@@ -315,6 +455,23 @@ impl IcyBoardState {
         Ok(())
     }
 
+    /// Takes a failed upload out of the caller's reach. With a quarantine directory it
+    /// is listed there, verdict and all, for the sysop to look at; without one it is deleted.
+    async fn quarantine_upload(&mut self, file: &Path, metadata: Vec<MetadataHeader>, quarantine: &Path) -> Res<()> {
+        if quarantine.as_os_str().is_empty() {
+            std::fs::remove_file(file)?;
+            return Ok(());
+        }
+        std::fs::create_dir_all(quarantine)?;
+        let dest = quarantine.join(file.file_name().unwrap_or_default());
+        std::fs::copy(file, &dest)?;
+        std::fs::remove_file(file)?;
+        let quarantine = quarantine.to_path_buf();
+        let file_base = self.get_filebase(&quarantine, &quarantine.join("dir")).await?;
+        file_base.lock().await.add_file(&dest, metadata)?;
+        Ok(())
+    }
+
     pub async fn get_protocol(&mut self, protocol_str: String) -> Option<TransferProtocolType> {
         let mut protocol = None;
         for p in self.get_board().await.protocols.iter() {
@@ -1,4 +1,4 @@
-use icy_engine::TextScreen;
+use icy_engine::{EditableScreen, TextPane, TextScreen};
 
 use crate::Res;
 
@@ -27,4 +27,19 @@ impl VirtualScreen {
         self.parser.parse(&[c as u8], &mut sink);
         Ok(())
     }
+
+    /// What the screen shows, line by line, without colours and without the
+    /// blanks that pad each line and the page.
+    pub fn text(&self) -> String {
+        let top = self.buffer.first_visible_line();
+        let mut lines = Vec::new();
+        for y in 0..self.buffer.height() {
+            let line: String = (0..self.buffer.width()).map(|x| self.buffer.char_at((x, top + y).into()).ch).collect();
+            lines.push(line.trim_end().to_string());
+        }
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
 }
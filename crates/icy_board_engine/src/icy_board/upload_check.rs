@@ -0,0 +1,186 @@
+//! What an upload goes through before it is listed: the duplicate check
+//! `duplicate_uploads` asks for, the test extraction `verify_files_uploaded`
+//! asks for, then every configured checker in turn.
+
+use std::{path::Path, time::Duration};
+
+use dizbase::{
+    file_base::hash_index::HashIndex,
+    file_base_scanner::verify::{Verdict, test_extract},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    pending_uploads::{UploadLocation, upload_locations},
+    state::IcyBoardState,
+};
+
+/// An external program that has to accept an upload, e.g. `clamscan --no-summary %1`.
+/// It runs through the shell with `%1` standing for the file; exit code 0 means clean.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct UploadChecker {
+    pub name: String,
+    pub command: String,
+    /// Seconds the checker may take before the upload counts as failed.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    60
+}
+
+impl UploadChecker {
+    /// The caller picked the file name, so it never becomes part of the script:
+    /// `%1` reads the first positional parameter the name is passed in.
+    fn command_line(&self) -> String {
+        self.command.replace("%1", "\"$1\"")
+    }
+
+    async fn check(&self, file: &Path) -> Result<(), String> {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c").arg(self.command_line()).arg("sh").arg(file).kill_on_drop(true);
+        if let Some(dir) = file.parent() {
+            cmd.current_dir(dir);
+        }
+        match tokio::time::timeout(Duration::from_secs(self.timeout.max(1)), cmd.output()).await {
+            Err(_) => Err(format!("{}: timed out", self.name)),
+            Ok(Err(err)) => Err(format!("{}: {err}", self.name)),
+            Ok(Ok(output)) if output.status.success() => Ok(()),
+            Ok(Ok(output)) => {
+                // Scanners name what they found on the last line they print.
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let found = stdout.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+                if found.is_empty() {
+                    Err(format!("{}: rejected ({})", self.name, output.status))
+                } else {
+                    Err(format!("{}: {found}", self.name))
+                }
+            }
+        }
+    }
+}
+
+/// Puts the locations, usually all of `upload_locations`, in one index. One that
+/// can't be read is logged and left out.
+pub fn hash_index(locations: &[UploadLocation]) -> HashIndex {
+    let mut index = HashIndex::default();
+    for location in locations {
+        if !location.path.is_dir() {
+            continue;
+        }
+        let result = location.open().and_then(|mut base| index.add_base(&location.name, &mut base));
+        if let Err(err) = result {
+            log::error!("Can't index {} for duplicates: {err}", location.path.display());
+        }
+    }
+    index
+}
+
+impl IcyBoardState {
+    /// Builds `IcyBoard::upload_index` unless it is there already. The bases are
+    /// read and hashed on a blocking thread without the board locked, other
+    /// nodes go on meanwhile.
+    pub async fn load_upload_index(&self) {
+        let locations = {
+            let board = self.get_board().await;
+            if board.upload_index.is_some() {
+                return;
+            }
+            upload_locations(&board)
+        };
+        let index = match tokio::task::spawn_blocking(move || hash_index(&locations)).await {
+            Ok(index) => index,
+            Err(err) => {
+                log::error!("Can't index the file bases for duplicates: {err}");
+                return;
+            }
+        };
+        self.get_board().await.upload_index.get_or_insert(index);
+    }
+}
+
+/// Runs the checks in order and stops at the first one that turns the file down.
+/// Returns `None` when there was nothing to check.
+pub async fn verify_upload(file: &Path, test_extraction: bool, checkers: &[UploadChecker]) -> Option<Verdict> {
+    if !test_extraction && checkers.is_empty() {
+        return None;
+    }
+    if test_extraction {
+        // Unpacking a big archive takes a while, the other nodes go on meanwhile.
+        let path = file.to_path_buf();
+        let tested = tokio::task::spawn_blocking(move || test_extract(&path, dizbase::MAX_EXTRACT_SIZE))
+            .await
+            .unwrap_or_else(|err| Err(err.into()));
+        if let Err(err) = tested {
+            return Some(Verdict::Failed(format!("test extraction: {err}")));
+        }
+    }
+    for checker in checkers {
+        if let Err(reason) = checker.check(file).await {
+            log::warn!("upload {} failed {}", file.display(), reason);
+            return Some(Verdict::Failed(reason));
+        }
+    }
+    Some(Verdict::Passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checker(command: &str, timeout: u64) -> UploadChecker {
+        UploadChecker {
+            name: "test".to_string(),
+            command: command.to_string(),
+            timeout,
+        }
+    }
+
+    fn verify(file: &Path, test_extraction: bool, checkers: &[UploadChecker]) -> Option<Verdict> {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(verify_upload(file, test_extraction, checkers))
+    }
+
+    #[test]
+    fn nothing_configured_checks_nothing() {
+        assert_eq!(verify(Path::new("/nowhere/FILE.ZIP"), false, &[]), None);
+    }
+
+    #[test]
+    fn a_checker_sees_the_file_and_its_exit_code_decides() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("CLEAN.TXT");
+        std::fs::write(&file, b"clean").unwrap();
+        assert_eq!(verify(&file, true, &[checker("test -f %1", 5)]), Some(Verdict::Passed));
+        assert_eq!(
+            verify(&file, false, &[checker("true", 5), checker("echo 'Eicar-Signature FOUND'; exit 1", 5)]),
+            Some(Verdict::Failed("test: Eicar-Signature FOUND".to_string()))
+        );
+    }
+
+    #[test]
+    fn a_checker_that_hangs_fails_the_upload() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("SLOW.TXT");
+        std::fs::write(&file, b"slow").unwrap();
+        assert_eq!(
+            verify(&file, false, &[checker("sleep 10", 1)]),
+            Some(Verdict::Failed("test: timed out".to_string()))
+        );
+    }
+
+    #[test]
+    fn a_hostile_file_name_is_not_run() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["$(touch PWNED).zip", "`touch PWNED`.zip", "\"; touch PWNED; \".zip", "'$(touch PWNED)'.zip"] {
+            let file = dir.path().join(name);
+            std::fs::write(&file, b"data").unwrap();
+            assert_eq!(verify(&file, false, &[checker("test -f %1", 5)]), Some(Verdict::Passed), "{name}");
+        }
+        assert!(!dir.path().join("PWNED").exists());
+    }
+}
@@ -183,6 +183,40 @@ fn test_function() {
     assert_eq!(1, prg.function_implementations.len());
 }*/
 
+#[test]
+fn test_module_declaration() {
+    let AstNode::Module(module) = parse_ast_node("MODULE Counter\n  PUBLIC INTEGER count\n  PROCEDURE Reset()\n  ENDPROC\nEND MODULE", true) else {
+        panic!("not a module");
+    };
+    assert_eq!(module.get_identifier().as_str(), "Counter");
+    assert_eq!(module.get_members().len(), 2);
+    assert!(module.get_members()[0].is_public());
+    assert!(matches!(module.get_members()[0].get_node(), AstNode::TopLevelStatement(_)));
+    assert!(!module.get_members()[1].is_public());
+    assert!(matches!(module.get_members()[1].get_node(), AstNode::Procedure(_)));
+}
+
+/// A dependency shares its modules and types, its own program is left out.
+#[test]
+fn a_dependency_keeps_only_what_it_shares() {
+    let registry = UserTypeRegistry::default();
+    let errors = Arc::new(Mutex::new(ErrorReporter::default()));
+    let workspace = Workspace::default();
+    let source = "TYPE Roll\n  INTEGER Value\nENDTYPE\nINTEGER seed\nPRINTLN \"Hello, World!\"\nMODULE Dice\n  PROCEDURE Shake()\n  ENDPROC\nEND MODULE\n";
+    preparse_type_declarations(PathBuf::from("dice.pps"), errors.clone(), source, &registry, Encoding::Utf8, &workspace);
+    let mut ast = parse_ast_with_predeclared_types(PathBuf::from("dice.pps"), errors, source, &registry, Encoding::Utf8, &workspace);
+
+    let unshared: Vec<&str> = ast.unshared_spans().into_iter().map(|span| &source[span]).collect();
+    assert_eq!(unshared.len(), 2, "{unshared:?}");
+    assert!(unshared[0].contains("seed"));
+    assert!(unshared[1].contains("PRINTLN"));
+
+    ast.retain_shared();
+    assert_eq!(ast.nodes.len(), 2);
+    assert!(matches!(ast.nodes[0], AstNode::TypeDeclaration(_)));
+    assert!(matches!(ast.nodes[1], AstNode::Module(_)));
+}
+
 /// The id of a board object is stored in every PPE that names its type, so the
 /// list may only ever grow at the end.
 #[test]
@@ -219,6 +219,16 @@ pub enum Token {
     /// The keyword, as opposed to `Const` which carries a value.
     ConstDecl,
     Begin,
+
+    // New in 404
+    Try,
+    Catch,
+    EndTry,
+    Module,
+    EndModule,
+    Import,
+    Public,
+    Private,
 }
 
 impl Token {
@@ -344,6 +354,14 @@ impl fmt::Display for Token {
             Token::EndEnum => write!(f, "ENDENUM"),
             Token::Begin => write!(f, "BEGIN"),
             Token::ConstDecl => write!(f, "CONST"),
+            Token::Try => write!(f, "TRY"),
+            Token::Catch => write!(f, "CATCH"),
+            Token::EndTry => write!(f, "ENDTRY"),
+            Token::Module => write!(f, "MODULE"),
+            Token::EndModule => write!(f, "ENDMODULE"),
+            Token::Import => write!(f, "IMPORT"),
+            Token::Public => write!(f, "PUBLIC"),
+            Token::Private => write!(f, "PRIVATE"),
         }
     }
 }
@@ -491,6 +509,15 @@ pub const KEYWORDS: &[Keyword] = &[
     Keyword { name: "endtype",   token: Token::EndType,   since: 400 },
     Keyword { name: "begin",     token: Token::Begin,     since: 400 },
     Keyword { name: "onerror",   token: Token::OnError,   since: 400 },
+
+    Keyword { name: "try",       token: Token::Try,       since: 404 },
+    Keyword { name: "catch",     token: Token::Catch,     since: 404 },
+    Keyword { name: "endtry",    token: Token::EndTry,    since: 404 },
+    Keyword { name: "module",    token: Token::Module,    since: 404 },
+    Keyword { name: "endmodule", token: Token::EndModule, since: 404 },
+    Keyword { name: "import",    token: Token::Import,    since: 404 },
+    Keyword { name: "public",    token: Token::Public,    since: 404 },
+    Keyword { name: "private",   token: Token::Private,   since: 404 },
 ];
 
 /// One table per version that reserves a word, in ascending order.
@@ -8,14 +8,15 @@ use std::{
 use crate::{
     ast::{
         Ast, AstNode, BlockStatement, CommentAstNode, Constant, DimensionSpecifier, EnumDeclarationAstNode, EnumVariantSpecifier, FunctionDeclarationAstNode,
-        FunctionImplementation, FunctionParameterSpecifier, ParameterSpecifier, ProcedureDeclarationAstNode, ProcedureImplementation,
-        ProcedureParameterSpecifier, Statement, TypeDeclarationAstNode, TypeFieldSpecifier, VariableParameterSpecifier, VariableSpecifier, const_value,
+        FunctionImplementation, FunctionParameterSpecifier, ImportAstNode, ModuleAstNode, ModuleMember, ParameterSpecifier, ProcedureDeclarationAstNode,
+        ProcedureImplementation, ProcedureParameterSpecifier, Statement, TypeDeclarationAstNode, TypeFieldSpecifier, VariableParameterSpecifier,
+        VariableSpecifier, const_value,
     },
     compiler::{
         user_data::{UserData, UserDataRegistry},
         workspace::Workspace,
     },
-    executable::{FuncOpCode, FunctionDefinition, OpCode, StatementDefinition, VariableType},
+    executable::{CollectionKind, FuncOpCode, FunctionDefinition, OpCode, StatementDefinition, VariableType},
     icy_board::{conferences::Conference, doors::Door, file_directory::FileDirectory, message_area::MessageArea},
 };
 
@@ -205,6 +206,39 @@ pub enum ParserErrorType {
 
     #[error("'TYPE' needs runtime {0}, an older PPE has nowhere to store the layout")]
     TypeNeedsNewerRuntime(u16),
+
+    #[error("Expected a type for the collection to hold, found '{0}'")]
+    CollectionElementExpected(Token),
+
+    #[error("Board object {0} cannot be held in a collection")]
+    CollectionOfBoardObject(VariableType),
+
+    #[error("A map is keyed by STRING, write 'MAP OF STRING TO <type>'")]
+    MapKeyMustBeString,
+
+    #[error("Expected 'IN' ({0})")]
+    InExpected(Token),
+
+    #[error("Expected 'CATCH' ({0})")]
+    CatchExpected(Token),
+
+    #[error("'ENDMODULE' expected before the end of the file")]
+    EndModuleExpected,
+
+    #[error("Only FUNCTION, PROCEDURE, DECLARE, CONST and variables can go in a MODULE")]
+    ModuleMemberExpected,
+
+    #[error("Module variable '{0}' can't have an initializer, set it from a routine of the module")]
+    ModuleVariableInitializer(unicase::Ascii<String>),
+
+    #[error("'{0}' can only be used inside a MODULE")]
+    VisibilityOutsideModule(Token),
+
+    #[error("IMPORT has to come before the first statement")]
+    ImportAfterStatements,
+
+    #[error("A dependency only shares MODULE, TYPE and ENUM, this would not be compiled in")]
+    NotSharedByDependency,
 }
 
 #[derive(Error, Debug, Clone, PartialEq)]
@@ -226,12 +260,14 @@ pub enum ParserWarningType {
     FunctionClosedWithEndProc,
 }
 
-/// A record a program declared with `TYPE ... ENDTYPE`.
+/// A record a program declared with `TYPE ... ENDTYPE`, or a `LIST OF`/`MAP OF`
+/// type it used. A collection has no fields and is named after what it holds.
 #[derive(Debug, Clone, PartialEq)]
 pub struct UserTypeDefinition {
     pub id: usize,
     pub name: unicase::Ascii<String>,
     pub fields: Vec<(unicase::Ascii<String>, VariableType)>,
+    pub collection: Option<(CollectionKind, VariableType)>,
 }
 
 /// An integer-backed type that exists in source only.
@@ -286,10 +322,17 @@ pub const AUDIO_ID: usize = 37;
 pub const TERM_STATE_ID: usize = 38;
 pub const ERROR_ID: usize = 39;
 pub const TERM_INFO_ID: usize = 40;
+pub const JSON_ID: usize = 41;
 
 /// The board objects are ours, so no `PCBoard` language knows their names.
 pub const FIRST_BOARD_OBJECT_LANGUAGE_VERSION: u16 = 400;
 
+/// `LIST OF` and `MAP OF` came with the 4.00 language.
+pub const FIRST_COLLECTION_LANGUAGE_VERSION: u16 = 400;
+
+/// `TRY ... CATCH ... ENDTRY` came with the 4.04 language.
+pub const FIRST_TRY_LANGUAGE_VERSION: u16 = 404;
+
 /// Types a program declares itself start here, so the board can keep adding
 /// objects of its own below without ever running into them.
 pub const FIRST_USER_TYPE_ID: usize = 100;
@@ -326,6 +369,7 @@ impl UserTypeRegistry {
         reg.register::<crate::icy_board::state::ppl_terminal_state::PplTerminalState>(TERM_STATE_ID);
         reg.register::<crate::icy_board::state::ppl_error::PplError>(ERROR_ID);
         reg.register::<crate::icy_board::state::ppl_terminal_info::PplTerminalInfo>(TERM_INFO_ID);
+        reg.register::<crate::icy_board::state::ppl_json::PplJson>(JSON_ID);
 
         reg
     }
@@ -364,7 +408,15 @@ impl UserTypeRegistry {
     }
 
     pub fn is_record_type(&self, id: u8) -> bool {
-        self.built_in_records.contains_key(&id) || is_user_declared_type(id)
+        self.built_in_records.contains_key(&id) || self.get_user_type_from_id(id).is_some_and(|def| def.collection.is_none())
+    }
+
+    /// What kind of collection type `id` is and what it holds, if it is one.
+    pub fn get_collection(&self, variable_type: VariableType) -> Option<(CollectionKind, VariableType)> {
+        let VariableType::UserData(id) = variable_type else {
+            return None;
+        };
+        self.get_user_type_from_id(id)?.collection
     }
 
     pub fn user_types(&self) -> Vec<UserTypeDefinition> {
@@ -406,6 +458,27 @@ impl UserTypeRegistry {
         self.get_record_type_from_id(id)?.field_index(field)
     }
 
+    /// The field names `JsonStr` and `JsonRead` go by. A PPE only keeps the field types, so
+    /// the compiler hands the names along: `{Name,Address{Street,City}}` for a record, what
+    /// one item looks like for a collection or an array, and nothing for a plain value.
+    pub fn json_fields(&self, variable_type: VariableType) -> String {
+        if let Some((_, element)) = self.get_collection(variable_type) {
+            return self.json_fields(element);
+        }
+        let VariableType::UserData(id) = variable_type else {
+            return String::new();
+        };
+        let Some(definition) = self.get_record_type_from_id(id) else {
+            return String::new();
+        };
+        let fields: Vec<String> = definition
+            .fields
+            .iter()
+            .map(|(name, field_type)| format!("{name}{}", self.json_fields(*field_type)))
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+
     /// Adds a record and hands back its type id, or `None` when the id space is full.
     pub fn declare_user_type(&self, name: unicase::Ascii<String>, fields: Vec<(unicase::Ascii<String>, VariableType)>) -> Option<usize> {
         let mut user_types = self.user_types.write().unwrap();
@@ -414,10 +487,50 @@ impl UserTypeRegistry {
         if id >= lowest_enum {
             return None;
         }
-        user_types.push(UserTypeDefinition { id, name, fields });
+        user_types.push(UserTypeDefinition {
+            id,
+            name,
+            fields,
+            collection: None,
+        });
+        Some(id)
+    }
+
+    /// The type id of a collection of `element`. Every use of the same collection
+    /// shares one id, so two `LIST OF INTEGER` variables can be assigned to each other.
+    /// `None` when the element has no name a program could write or the id space is full.
+    pub fn declare_collection(&self, kind: CollectionKind, element: VariableType) -> Option<usize> {
+        let name = unicase::Ascii::new(kind.type_name(&self.type_keyword(element)?));
+        let mut user_types = self.user_types.write().unwrap();
+        if let Some(def) = user_types.iter().find(|def| def.name == name) {
+            return Some(def.id);
+        }
+        let id = FIRST_USER_TYPE_ID + user_types.len();
+        let lowest_enum = self.enums.read().unwrap().last().map_or(u8::MAX as usize + 1, |def| def.id as usize);
+        if id >= lowest_enum {
+            return None;
+        }
+        user_types.push(UserTypeDefinition {
+            id,
+            name,
+            fields: Vec::new(),
+            collection: Some((kind, element)),
+        });
         Some(id)
     }
 
+    /// How a program writes `variable_type`. Board objects are left out, a collection can't hold them.
+    pub fn type_keyword(&self, variable_type: VariableType) -> Option<String> {
+        match variable_type {
+            VariableType::UserData(id) if is_user_declared_type(id) => self
+                .get_user_type_from_id(id)
+                .map(|def| def.name.to_string())
+                .or_else(|| self.get_enum_from_id(id).map(|def| def.name.to_string())),
+            VariableType::UserData(_) => None,
+            _ => built_in_type_name(variable_type).map(str::to_string),
+        }
+    }
+
     /// Every PPE stores the type id, so an id that is taken can never move.
     fn claim_id(&self, id: usize, name: &str) {
         assert!(
@@ -449,6 +562,7 @@ impl UserTypeRegistry {
                 id,
                 name: unicase::Ascii::new(name.to_string()),
                 fields,
+                collection: None,
             },
         );
     }
@@ -482,6 +596,9 @@ static PROC_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::Lazy
 static FUNC_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new("FUNC".to_string()));
 static ON_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new("ON".to_string()));
 static ERROR_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new("ERROR".to_string()));
+static LIST_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new("LIST".to_string()));
+static MAP_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new("MAP".to_string()));
+static OF_TOKEN: std::sync::LazyLock<unicase::Ascii<String>> = std::sync::LazyLock::new(|| unicase::Ascii::new("OF".to_string()));
 
 impl<'a> Parser<'a> {
     pub fn new(
@@ -591,6 +708,12 @@ impl<'a> Parser<'a> {
                         Token::For => {
                             self.cur_token = Some(Spanned::new(Token::Next, start..self.lex.span().end));
                         }
+                        Token::Try => {
+                            self.cur_token = Some(Spanned::new(Token::EndTry, start..self.lex.span().end));
+                        }
+                        Token::Module => {
+                            self.cur_token = Some(Spanned::new(Token::EndModule, start..self.lex.span().end));
+                        }
                         _ => {
                             let set_lookahad = if let Token::Identifier(id) = &lookahed {
                                 if *id == *PROC_TOKEN {
@@ -693,6 +816,20 @@ impl<'a> Parser<'a> {
                     return Some(AstNode::EnumDeclaration(decl));
                 }
             }
+            Token::Module => {
+                if let Some(module) = self.parse_module() {
+                    self.got_funcs = true;
+                    return Some(AstNode::Module(module));
+                }
+            }
+            Token::Import => {
+                if let Some(import) = self.parse_import() {
+                    return Some(AstNode::Import(import));
+                }
+            }
+            Token::Public | Token::Private => {
+                self.report_error(cur_token.span.clone(), ParserErrorType::VisibilityOutsideModule(cur_token.token.clone()));
+            }
             Token::Begin => {
                 if self.parsed_block {
                     self.report_error(cur_token.span.clone(), ParserErrorType::BlockAlreadyDefined);
@@ -750,7 +887,7 @@ impl<'a> Parser<'a> {
                     if !self.got_statement && !matches!(stmt, Statement::VariableDeclaration(_) | Statement::ConstDeclaration(_) | Statement::Comment(_)) {
                         let mut main_block = vec![stmt];
                         while let Some(cur_token) = &self.cur_token {
-                            if cur_token.token == Token::Function || cur_token.token == Token::Procedure {
+                            if matches!(cur_token.token, Token::Function | Token::Procedure | Token::Module | Token::Import) {
                                 break;
                             }
                             if let Some(stmt) = self.parse_statement() {
@@ -986,6 +1123,103 @@ impl<'a> Parser<'a> {
         Some(EnumDeclarationAstNode::new(enum_token, identifier_token, variants, endenum_token))
     }
 
+    /// Parses `MODULE <name> ... ENDMODULE`. Only declarations go inside, the
+    /// compiler turns them into ordinary globals and routines.
+    fn parse_module(&mut self) -> Option<ModuleAstNode> {
+        let module_token = self.save_spanned_token();
+        self.next_token();
+
+        let Some(Token::Identifier(_)) = self.get_cur_token() else {
+            self.report_error(self.lex.span(), ParserErrorType::IdentifierExpected(self.save_token()));
+            return None;
+        };
+        let identifier_token = self.save_spanned_token();
+        self.next_token();
+
+        let mut members = Vec::new();
+        let endmodule_token = loop {
+            while matches!(self.get_cur_token(), Some(Token::Eol)) {
+                self.next_token();
+            }
+            let visibility_token = match self.get_cur_token() {
+                Some(Token::EndModule) => {
+                    let token = self.save_spanned_token();
+                    self.next_token();
+                    break token;
+                }
+                None => {
+                    self.report_error(self.lex.span(), ParserErrorType::EndModuleExpected);
+                    return None;
+                }
+                Some(Token::Public | Token::Private) => {
+                    let token = self.save_spanned_token();
+                    self.next_token();
+                    Some(token)
+                }
+                _ => None,
+            };
+
+            let node = match self.get_cur_token() {
+                Some(Token::Function) => self.parse_function().map(AstNode::Function),
+                Some(Token::Procedure) => self.parse_procedure().map(AstNode::Procedure),
+                Some(Token::Declare) => self.parse_declaration(),
+                Some(Token::Type | Token::Enum | Token::Module | Token::Import | Token::Begin) => {
+                    self.report_error(self.save_token_span(), ParserErrorType::ModuleMemberExpected);
+                    return None;
+                }
+                None => {
+                    self.report_error(self.lex.span(), ParserErrorType::EndModuleExpected);
+                    return None;
+                }
+                _ => match self.parse_statement() {
+                    Some(Statement::Comment(comment)) if visibility_token.is_none() => Some(AstNode::TopLevelStatement(Statement::Comment(comment))),
+                    Some(Statement::VariableDeclaration(declaration)) => {
+                        for variable in declaration.get_variables() {
+                            if variable.get_initalizer().is_some() {
+                                self.error_reporter.lock().unwrap().report_error(
+                                    variable.get_identifier_token().span.clone(),
+                                    ParserErrorType::ModuleVariableInitializer(variable.get_identifier().clone()),
+                                );
+                            }
+                        }
+                        Some(AstNode::TopLevelStatement(Statement::VariableDeclaration(declaration)))
+                    }
+                    Some(Statement::ConstDeclaration(declaration)) => Some(AstNode::TopLevelStatement(Statement::ConstDeclaration(declaration))),
+                    Some(stmt) => {
+                        self.error_reporter
+                            .lock()
+                            .unwrap()
+                            .report_error(stmt.get_span(), ParserErrorType::ModuleMemberExpected);
+                        None
+                    }
+                    None => None,
+                },
+            };
+            if let Some(node) = node {
+                members.push(ModuleMember::new(visibility_token, node));
+            }
+        };
+
+        Some(ModuleAstNode::new(module_token, identifier_token, members, endmodule_token))
+    }
+
+    fn parse_import(&mut self) -> Option<ImportAstNode> {
+        let import_token = self.save_spanned_token();
+        if self.got_statement {
+            self.report_error(import_token.span.clone(), ParserErrorType::ImportAfterStatements);
+            return None;
+        }
+        self.next_token();
+
+        let Some(Token::Identifier(_)) = self.get_cur_token() else {
+            self.report_error(self.lex.span(), ParserErrorType::IdentifierExpected(self.save_token()));
+            return None;
+        };
+        let identifier_token = self.save_spanned_token();
+        self.next_token();
+        Some(ImportAstNode::new(import_token, identifier_token))
+    }
+
     fn parse_function_parameter_specifier(&mut self) -> ParameterSpecifier {
         let func_token = self.save_spanned_token();
         self.next_token();
@@ -1199,6 +1433,11 @@ pub fn built_in_type(name: &unicase::Ascii<String>, lang_version: u16) -> Option
         .map(|(variable_type, _)| *variable_type)
 }
 
+/// The name a built-in type is written as, the first of them where there are several.
+pub fn built_in_type_name(variable_type: VariableType) -> Option<&'static str> {
+    BUILT_IN_TYPES.iter().find(|(_, vt, _)| *vt == variable_type).map(|(name, _, _)| *name)
+}
+
 /// The type names a program written for that language version may use.
 pub fn built_in_type_names(lang_version: u16) -> Vec<&'static str> {
     BUILT_IN_TYPES
@@ -1209,7 +1448,10 @@ pub fn built_in_type_names(lang_version: u16) -> Vec<&'static str> {
 }
 
 impl Parser<'_> {
-    pub fn get_variable_type(&self) -> Option<VariableType> {
+    /// The type the current token names. `LIST OF <type>` and `MAP OF STRING TO <type>`
+    /// are read whole and left as one token carrying the collection's name, so a caller
+    /// can treat them like any other type name.
+    pub fn get_variable_type(&mut self) -> Option<VariableType> {
         if let Some(token) = &self.cur_token {
             if let Token::Identifier(id) = &token.token {
                 if let Some(vt) = built_in_type(id, self.lang_version) {
@@ -1224,6 +1466,16 @@ impl Parser<'_> {
                 if let Some(vt) = self.type_registry.get_declared_type(id) {
                     return Some(vt);
                 }
+                if self.lang_version >= FIRST_COLLECTION_LANGUAGE_VERSION {
+                    let kind = if *id == *LIST_TOKEN {
+                        CollectionKind::List
+                    } else if *id == *MAP_TOKEN {
+                        CollectionKind::Map
+                    } else {
+                        return None;
+                    };
+                    return self.parse_collection_type(kind);
+                }
                 None
             } else {
                 None
@@ -1233,6 +1485,54 @@ impl Parser<'_> {
         }
     }
 
+    /// Neither word is reserved, so `LIST` only starts a type when `OF` follows it.
+    /// The token after the current one, read ahead without moving on.
+    pub(crate) fn peek_token(&mut self) -> Option<&Token> {
+        if self.lookahead_token.is_none()
+            && let Some(token) = self.lex.next_token()
+        {
+            self.lookahead_token = Some(Spanned::new(token, self.lex.span()));
+        }
+        self.lookahead_token.as_ref().map(|token| &token.token)
+    }
+
+    fn parse_collection_type(&mut self, kind: CollectionKind) -> Option<VariableType> {
+        if !matches!(self.peek_token(), Some(Token::Identifier(of)) if *of == *OF_TOKEN) {
+            return None;
+        }
+        let start = self.save_token_span().start;
+        self.next_token();
+        self.next_token();
+        if kind == CollectionKind::Map {
+            if !matches!(self.get_cur_token(), Some(Token::Identifier(key)) if built_in_type(&key, self.lang_version) == Some(VariableType::String)) {
+                self.report_error(self.save_token_span(), ParserErrorType::MapKeyMustBeString);
+                return None;
+            }
+            self.next_token();
+            if !matches!(self.get_cur_token(), Some(Token::Identifier(to)) if to == "TO") {
+                self.report_error(self.save_token_span(), ParserErrorType::MapKeyMustBeString);
+                return None;
+            }
+            self.next_token();
+        }
+        let Some(element) = self.get_variable_type() else {
+            self.report_error(self.save_token_span(), ParserErrorType::CollectionElementExpected(self.save_token()));
+            return None;
+        };
+        let end = self.save_token_span().end;
+        if matches!(element, VariableType::UserData(id) if !is_user_declared_type(id)) {
+            self.report_error(start..end, ParserErrorType::CollectionOfBoardObject(element));
+            return None;
+        }
+        let Some(id) = self.type_registry.declare_collection(kind, element) else {
+            self.report_error(start..end, ParserErrorType::TooManyTypes(MAX_USER_TYPES));
+            return None;
+        };
+        let name = self.type_registry.get_user_type_from_id(id as u8)?.name;
+        self.cur_token = Some(Spanned::new(Token::Identifier(name), start..end));
+        Some(VariableType::UserData(id as u8))
+    }
+
     /// Returns the parse var info of this [`Tokenizer`].
     ///
     /// # Panics
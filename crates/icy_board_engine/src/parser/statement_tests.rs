@@ -6,9 +6,9 @@ use std::{
 use crate::{
     ast::{
         Ast, AstNode, BlockStatement, BreakStatement, CaseBlock, CaseSpecifier, CommentAstNode, Constant, ConstantExpression, ContinueStatement, ElseBlock,
-        ElseIfBlock, ForStatement, GosubStatement, GotoStatement, IdentifierExpression, IfStatement, IfThenStatement, LabelStatement, LetStatement,
-        LoopStatement, ParensExpression, PredefinedCallStatement, RepeatUntilStatement, ReturnStatement, SelectStatement, Statement, UnaryExpression, UnaryOp,
-        VariableDeclarationStatement, VariableSpecifier, WhileDoStatement, WhileStatement, constant::NumberFormat,
+        ElseIfBlock, ForEachStatement, ForStatement, GosubStatement, GotoStatement, IdentifierExpression, IfStatement, IfThenStatement, LabelStatement,
+        LetStatement, LoopStatement, ParensExpression, PredefinedCallStatement, RepeatUntilStatement, ReturnStatement, SelectStatement, Statement,
+        TryStatement, UnaryExpression, UnaryOp, VariableDeclarationStatement, VariableSpecifier, WhileDoStatement, WhileStatement, constant::NumberFormat,
     },
     compiler::workspace::{CompilerData, Workspace},
     executable::{OpCode, VariableType},
@@ -604,6 +604,32 @@ END FOR",
     );
 }
 
+#[test]
+fn test_for_each_statement() {
+    check_statement(
+        r"FOR EACH name IN names
+NEXT",
+        &ForEachStatement::create_empty_statement(
+            unicase::Ascii::new("name".to_string()),
+            IdentifierExpression::create_empty_expression(unicase::Ascii::new("names".to_string())),
+            vec![],
+        ),
+    );
+
+    // EACH is no keyword, a counter may still be called that.
+    check_statement(
+        r"FOR EACH = 0 TO 5
+NEXT",
+        &ForStatement::create_empty_statement(
+            unicase::Ascii::new("EACH".to_string()),
+            ConstantExpression::create_empty_expression(Constant::Integer(0, NumberFormat::Default)),
+            ConstantExpression::create_empty_expression(Constant::Integer(5, NumberFormat::Default)),
+            None,
+            vec![],
+        ),
+    );
+}
+
 #[test]
 fn test_for_step_statement() {
     check_statement(
@@ -1070,6 +1096,28 @@ fn test_loop_statement() {
     );
 }
 
+#[test]
+fn test_try_statement() {
+    check_statement(
+        r"TRY
+        CATCH
+        ENDTRY",
+        &TryStatement::create_empty_statement(vec![], None, vec![]),
+    );
+    check_statement(
+        r"TRY
+            BREAK
+        CATCH e
+            CONTINUE
+        END TRY",
+        &TryStatement::create_empty_statement(
+            vec![BreakStatement::create_empty_statement()],
+            Some(unicase::Ascii::new("e".to_string())),
+            vec![ContinueStatement::create_empty_statement()],
+        ),
+    );
+}
+
 #[test]
 fn test_variable_declaration_initalizer() {
     check_statement(
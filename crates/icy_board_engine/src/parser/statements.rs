@@ -1,17 +1,17 @@
 use crate::{
     ast::{
         BlockStatement, BreakStatement, CaseBlock, CaseSpecifier, CommentAstNode, ConstDeclarationStatement, Constant, ContinueStatement, ElseBlock,
-        ElseIfBlock, Expression, ForStatement, FunctionCallExpression, GosubStatement, GotoStatement, IdentifierExpression, IfStatement, IfThenStatement,
-        LabelStatement, LetStatement, LoopStatement, MemberCallStatement, MemberReferenceExpression, OnErrorMode, OnErrorStatement, PredefinedCallStatement,
-        ProcedureCallStatement, RepeatUntilStatement, ReturnStatement, SelectStatement, Statement, VariableDeclarationStatement, WhileDoStatement,
-        WhileStatement,
+        ElseIfBlock, Expression, ForEachStatement, ForStatement, FunctionCallExpression, GosubStatement, GotoStatement, IdentifierExpression, IfStatement,
+        IfThenStatement, LabelStatement, LetStatement, LoopStatement, MemberCallStatement, MemberReferenceExpression, OnErrorMode, OnErrorStatement,
+        PredefinedCallStatement, ProcedureCallStatement, RepeatUntilStatement, ReturnStatement, SelectStatement, Statement, TryStatement,
+        VariableDeclarationStatement, WhileDoStatement, WhileStatement,
     },
     executable::{OpCode, StatementDefinition},
     parser::ParserErrorType,
 };
 
 use super::{
-    Parser, ParserWarningType,
+    FIRST_COLLECTION_LANGUAGE_VERSION, Parser, ParserWarningType,
     lexer::{Spanned, Token},
 };
 
@@ -223,9 +223,71 @@ impl Parser<'_> {
         )))
     }
 
+    fn parse_try(&mut self) -> Option<Statement> {
+        let try_token = self.save_spanned_token();
+        self.next_token();
+
+        let mut statements = Vec::new();
+        self.skip_eol();
+        while self.get_cur_token() != Some(Token::Catch) {
+            match self.get_cur_token() {
+                None => {
+                    self.report_error(self.lex.span(), ParserErrorType::EndExpected);
+                    return None;
+                }
+                Some(Token::EndTry) => {
+                    self.report_error(self.save_token_span(), ParserErrorType::CatchExpected(self.save_token()));
+                    self.next_token();
+                    return None;
+                }
+                _ => {}
+            }
+            statements.push(self.parse_statement());
+            self.skip_eol();
+        }
+        let catch_token = self.save_spanned_token();
+        self.next_token(); // skip CATCH
+
+        let catch_identifier_token = if let Some(Token::Identifier(_)) = self.get_cur_token() {
+            let t = self.save_spanned_token();
+            self.next_token();
+            Some(t)
+        } else {
+            None
+        };
+
+        let mut catch_statements = Vec::new();
+        self.skip_eol();
+        while self.get_cur_token() != Some(Token::EndTry) {
+            if self.get_cur_token().is_none() {
+                self.report_error(self.lex.span(), ParserErrorType::EndExpected);
+                return None;
+            }
+            catch_statements.push(self.parse_statement());
+            self.skip_eol();
+        }
+        let endtry_token = self.save_spanned_token();
+        self.next_token(); // skip ENDTRY
+
+        Some(Statement::Try(TryStatement::new(
+            try_token,
+            statements.into_iter().flatten().collect(),
+            catch_token,
+            catch_identifier_token,
+            catch_statements.into_iter().flatten().collect(),
+            endtry_token,
+        )))
+    }
+
     fn parse_for(&mut self) -> Option<Statement> {
         let for_token = self.save_spanned_token();
         self.next_token();
+        if self.lang_version >= FIRST_COLLECTION_LANGUAGE_VERSION
+            && matches!(self.get_cur_token(), Some(Token::Identifier(id)) if id == "EACH")
+            && matches!(self.peek_token(), Some(Token::Identifier(_)))
+        {
+            return self.parse_for_each(for_token);
+        }
         let identifier_token = self.save_spanned_token();
 
         let _var = if let Some(Token::Identifier(id)) = self.get_cur_token() {
@@ -317,6 +379,64 @@ impl Parser<'_> {
         )))
     }
 
+    fn parse_for_each(&mut self, for_token: Spanned<Token>) -> Option<Statement> {
+        let each_token = self.save_spanned_token();
+        self.next_token();
+        let identifier_token = self.save_spanned_token();
+        self.next_token();
+
+        if !matches!(self.get_cur_token(), Some(Token::Identifier(id)) if id == "IN") {
+            self.report_error(self.lex.span(), ParserErrorType::InExpected(self.save_token()));
+            return None;
+        }
+        let in_token = self.save_spanned_token();
+        self.next_token();
+        let Some(collection_expr) = self.parse_expression() else {
+            self.report_error(self.lex.span(), ParserErrorType::ExpressionExpected(self.save_token()));
+            return None;
+        };
+
+        let mut statements = Vec::new();
+        self.skip_eol();
+        while self.get_cur_token() != Some(Token::Next) {
+            if self.get_cur_token().is_none() {
+                self.report_error(self.lex.span(), ParserErrorType::EndExpected);
+                return None;
+            }
+            statements.push(self.parse_statement());
+            self.skip_eol();
+        }
+        let next_token = self.save_spanned_token();
+        self.next_token();
+
+        let next_identifier_token = if let Some(Token::Identifier(next_id)) = &self.get_cur_token() {
+            let start_id = identifier_token.token.get_identifier();
+            if *next_id != start_id {
+                self.error_reporter
+                    .lock()
+                    .unwrap()
+                    .report_warning(self.lex.span(), ParserWarningType::NextIdentifierInvalid(start_id, self.save_token()));
+                return None;
+            }
+            let t = self.save_spanned_token();
+            self.next_token();
+            Some(t)
+        } else {
+            None
+        };
+
+        Some(Statement::ForEach(ForEachStatement::new(
+            for_token,
+            each_token,
+            identifier_token,
+            in_token,
+            collection_expr,
+            statements.into_iter().flatten().collect(),
+            next_token,
+            next_identifier_token,
+        )))
+    }
+
     fn parse_if(&mut self) -> Option<Statement> {
         let if_token = self.save_spanned_token();
         self.next_token();
@@ -612,6 +732,7 @@ impl Parser<'_> {
             Some(Token::While) => self.parse_while(),
             Some(Token::Repeat) => self.parse_repeat_until(),
             Some(Token::Loop) => self.parse_loop(),
+            Some(Token::Try) => self.parse_try(),
             Some(Token::Select) => self.parse_select(),
             Some(Token::If) => self.parse_if(),
             Some(Token::For) => self.parse_for(),
@@ -821,6 +942,10 @@ impl Parser<'_> {
                     }
                     return Some(Statement::VariableDeclaration(VariableDeclarationStatement::new(type_token, var_type, vars)));
                 }
+                // A collection type that went wrong half way has been reported already.
+                if !matches!(self.get_cur_token(), Some(Token::Identifier(_))) {
+                    return None;
+                }
 
                 if let Some(value) = self.parse_call(&id) {
                     return Some(value);
@@ -834,7 +959,7 @@ impl Parser<'_> {
                 self.next_token();
                 Some(Statement::Label(LabelStatement::new(label_token)))
             }
-            Some(Token::EndIf | Token::EndWhile | Token::Next | Token::EndSelect) => {
+            Some(Token::EndIf | Token::EndWhile | Token::Next | Token::EndSelect | Token::Catch | Token::EndTry) => {
                 self.report_error(self.save_token_span(), ParserErrorType::BlockEndBeforeBlockStart);
                 self.next_token();
                 None
@@ -10,14 +10,14 @@ use crate::{
         AstVisitor, CommentAstNode, ConstDeclarationStatement, Constant, ConstantExpression, EnumDeclarationAstNode, Expression, FunctionCallExpression,
         FunctionDeclarationAstNode, FunctionImplementation, GosubStatement, GotoStatement, IdentifierExpression, LabelStatement, LetStatement, OnErrorMode,
         OnErrorStatement, ParameterSpecifier, PredefinedCallStatement, ProcedureCallStatement, ProcedureDeclarationAstNode, ProcedureImplementation,
-        TypeDeclarationAstNode, VariableDeclarationStatement, VariableParameterSpecifier, const_value_with_members, walk_function_implementation,
-        walk_indexer_expression, walk_predefined_call_statement, walk_procedure_call_statement, walk_procedure_implementation,
+        TryMarkerStatement, TryStatement, TypeDeclarationAstNode, VariableDeclarationStatement, VariableParameterSpecifier, const_value_with_members,
+        walk_function_implementation, walk_indexer_expression, walk_procedure_call_statement, walk_procedure_implementation, walk_try_stmt,
     },
-    compiler::{CompilationErrorType, CompilationWarningType, user_data::UserDataMemberRegistry, workspace::Workspace},
+    compiler::{CompilationErrorType, CompilationWarningType, ast_transform::EACH_COLLECTION_PREFIX, user_data::UserDataMemberRegistry, workspace::Workspace},
     executable::{
-        EntryType, FIRST_RECORD_LITERAL_RUNTIME, FIRST_ROUTINE_REFERENCE_RUNTIME, FIRST_TYPE_TABLE_RUNTIME, FUNCTION_DEFINITIONS, FuncOpCode,
-        FunctionDefinition, FunctionValue, GenericVariableData, OpCode, ProcedureValue, TableEntry, USER_VARIABLES, VarHeader, VariableData, VariableTable,
-        VariableType, VariableValue,
+        CollectionKind, CollectionMember, EntryType, FIRST_COLLECTION_RUNTIME, FIRST_RECORD_LITERAL_RUNTIME, FIRST_ROUTINE_REFERENCE_RUNTIME,
+        FIRST_TRY_RUNTIME, FIRST_TYPE_TABLE_RUNTIME, FUNCTION_DEFINITIONS, FuncOpCode, FunctionDefinition, FunctionValue, GenericVariableData, OpCode,
+        ProcedureValue, TableEntry, USER_VARIABLES, VarHeader, VariableData, VariableTable, VariableType, VariableValue,
     },
     parser::{
         self, ErrorReporter, ParserErrorType, UserTypeRegistry,
@@ -229,6 +229,9 @@ pub struct SemanticVisitor {
     /// Maps member references -> user type IDs
     pub user_type_lookup: HashMap<usize, u8>,
 
+    /// Maps the name of a `JsonStr`/`JsonRead` -> the field names of the value it works on.
+    pub json_fields: HashMap<usize, String>,
+
     pub function_type_lookup: HashMap<u64, SemanticInfo>,
 
     pub require_user_variables: bool,
@@ -415,9 +418,42 @@ impl SemanticVisitor {
         {
             return definition.name.to_string();
         }
+        if self.type_registry.get_collection(variable_type).is_some()
+            && let Some(name) = self.type_registry.type_keyword(variable_type)
+        {
+            return name;
+        }
         variable_type.to_string()
     }
 
+    /// A collection only reaches the PPE from `FIRST_COLLECTION_RUNTIME` on.
+    fn check_collection_runtime(&mut self, variable_type: VariableType, span: &core::ops::Range<usize>) {
+        if self.runtime < FIRST_COLLECTION_RUNTIME && self.type_registry.get_collection(variable_type).is_some() {
+            self.errors
+                .lock()
+                .unwrap()
+                .report_error(span.clone(), CompilationErrorType::CollectionNeedsRuntime(FIRST_COLLECTION_RUNTIME));
+        }
+    }
+
+    /// `<try>` and `<end try>` only reach the PPE from `FIRST_TRY_RUNTIME` on.
+    fn check_try_runtime(&mut self, span: &core::ops::Range<usize>) {
+        if self.runtime < FIRST_TRY_RUNTIME {
+            self.errors
+                .lock()
+                .unwrap()
+                .report_error(span.clone(), CompilationErrorType::TryNeedsRuntime(FIRST_TRY_RUNTIME));
+        }
+    }
+
+    /// What indexing a variable of `variable_type` with a single key gives, if it is a collection.
+    fn collection_item_type(&self, variable_type: VariableType, dim: usize, arg_count: usize) -> Option<VariableType> {
+        if dim != 0 || arg_count != 1 {
+            return None;
+        }
+        self.type_registry.get_collection(variable_type).map(|(_, element)| element)
+    }
+
     pub fn set_loop_counters(&mut self, loop_counters: HashSet<usize>) {
         self.loop_counters = loop_counters;
     }
@@ -461,6 +497,7 @@ impl SemanticVisitor {
             label_count: 0,
             label_lookup_table: HashMap::new(),
             user_type_lookup: HashMap::new(),
+            json_fields: HashMap::new(),
             function_type_lookup: HashMap::new(),
 
             global_lookup: VariableLookups::default(),
@@ -733,6 +770,13 @@ impl SemanticVisitor {
         }
     }
 
+    /// A PPE has no field names, so `JsonStr` and `JsonRead` are handed them as a constant.
+    fn add_json_fields(&mut self, name_start: usize, variable_type: VariableType) {
+        let fields = self.type_registry.json_fields(variable_type);
+        self.add_constant(&Constant::String(fields.clone()));
+        self.json_fields.insert(name_start, fields);
+    }
+
     fn add_declaration(&mut self, variable_type: VariableType, identifier_token: &Spanned<parser::lexer::Token>) -> usize {
         let id = self.references.len();
 
@@ -1555,6 +1599,20 @@ impl AstVisitor<VariableType> for SemanticVisitor {
         }
         let t = member_reference_expression.get_expression().visit(self);
         if let VariableType::UserData(d) = t {
+            if let Some((kind, _)) = self.type_registry.get_collection(t) {
+                let span = &member_reference_expression.get_identifier_token().span;
+                if let Some(member) = CollectionMember::from_name(member_reference_expression.get_identifier())
+                    && member.parameter_count(kind).is_some()
+                {
+                    self.user_type_lookup.insert(span.start, d);
+                    return member.return_type();
+                }
+                self.errors.lock().unwrap().report_error(
+                    span.clone(),
+                    CompilationErrorType::CollectionMemberNotFound(self.source_type_name(t), member_reference_expression.get_identifier().to_string()),
+                );
+                return VariableType::None;
+            }
             if self.type_registry.is_record_type(d) {
                 return self.resolve_record_field(
                     d,
@@ -1623,7 +1681,12 @@ impl AstVisitor<VariableType> for SemanticVisitor {
 
     fn visit_predefined_call_statement(&mut self, call_stmt: &PredefinedCallStatement) -> VariableType {
         let def = call_stmt.get_func();
-        walk_predefined_call_statement(self, call_stmt);
+        let argument_types: Vec<VariableType> = call_stmt.get_arguments().iter().map(|argument| argument.visit(self)).collect();
+        if def.opcode == OpCode::JsonRead
+            && let Some(variable_type) = argument_types.get(1)
+        {
+            self.add_json_fields(call_stmt.get_identifier_token().span.start, *variable_type);
+        }
 
         let minimum_runtime = def.opcode.minimum_runtime();
         if self.runtime < minimum_runtime {
@@ -1752,6 +1815,17 @@ impl AstVisitor<VariableType> for SemanticVisitor {
                 }
                 if let Expression::MemberReference(member) = call.get_expression() {
                     if let Some(user_type) = self.user_type_lookup.get(&member.get_identifier_token().span.start) {
+                        if let Some((kind, _)) = self.type_registry.get_collection(VariableType::UserData(*user_type)) {
+                            // The member reference only records a member the collection has.
+                            let Some(collection_member) = CollectionMember::from_name(member.get_identifier()) else {
+                                return res;
+                            };
+                            let parameters = collection_member.parameter_count(kind).unwrap_or_default();
+                            self.check_expr_arg_count(parameters, call.get_arguments().len(), call.get_expression());
+                            self.function_type_lookup
+                                .insert(call.id, SemanticInfo::MemberFunctionCall(collection_member as usize));
+                            return collection_member.return_type();
+                        }
                         if let Some(registry) = self.type_registry.get_type_from_id(*user_type) {
                             for (name, function) in &registry.functions {
                                 if name == member.get_identifier() {
@@ -1787,12 +1861,13 @@ impl AstVisitor<VariableType> for SemanticVisitor {
                     0
                 };
                 res = r.variable_type;
+                if let Some(item_type) = self.collection_item_type(res, arg_count, call.get_arguments().len()) {
+                    return item_type;
+                }
                 self.check_expr_arg_count(arg_count, call.get_arguments().len(), call.get_expression());
             }
             Some(SemanticInfo::PredefFunctionGroup(funcs)) => {
-                for argument in call.get_arguments() {
-                    argument.visit(self);
-                }
+                let argument_types: Vec<VariableType> = call.get_arguments().iter().map(|argument| argument.visit(self)).collect();
                 for func in &funcs {
                     let def = &FUNCTION_DEFINITIONS[*func];
                     if def.parameter_count() == call.get_arguments().len() {
@@ -1815,6 +1890,23 @@ impl AstVisitor<VariableType> for SemanticVisitor {
                         if let Expression::Identifier(id) = call.get_expression() {
                             self.add_reference(ReferenceType::PredefinedFunc(def.opcode), VariableType::Function, id.get_identifier_token());
                         }
+                        if def.opcode == FuncOpCode::JsonStr {
+                            self.add_json_fields(call.get_expression().get_span().start, argument_types[0]);
+                        }
+                        // FOR EACH hands its loop variable whatever the collection holds.
+                        if def.opcode == FuncOpCode::EachItem {
+                            return match self.type_registry.get_collection(argument_types[0]) {
+                                Some((CollectionKind::List, element)) => element,
+                                Some((CollectionKind::Map, _)) => VariableType::String,
+                                None => {
+                                    self.errors.lock().unwrap().report_error(
+                                        call.get_arguments()[0].get_span(),
+                                        CompilationErrorType::CollectionExpected(self.source_type_name(argument_types[0])),
+                                    );
+                                    VariableType::None
+                                }
+                            };
+                        }
                         return def.return_type;
                     }
                 }
@@ -1885,7 +1977,9 @@ impl AstVisitor<VariableType> for SemanticVisitor {
             0
         };
 
-        if found {
+        if let Some(item_type) = self.collection_item_type(res, arg_count, indexer.get_arguments().len()) {
+            res = item_type;
+        } else if found {
             self.check_arg_count(arg_count, indexer.get_arguments().len(), indexer.get_identifier_token());
         } else {
             self.errors.lock().unwrap().report_error(
@@ -1958,6 +2052,42 @@ impl AstVisitor<VariableType> for SemanticVisitor {
         VariableType::None
     }
 
+    fn visit_try_statement(&mut self, try_stmt: &TryStatement) -> VariableType {
+        self.check_try_runtime(&try_stmt.get_try_token().span);
+        if let (Some(identifier), Some(identifier_token)) = (try_stmt.get_catch_identifier(), try_stmt.get_catch_identifier_token()) {
+            if let Some(idx) = self.lookup_variable(identifier) {
+                let (_rt, r) = &mut self.references[idx];
+                r.usages.push((
+                    self.errors.lock().unwrap().file_name().to_path_buf(),
+                    Spanned::new(identifier_token.token.to_string(), identifier_token.span.clone()),
+                ));
+                let error_type = VariableType::UserData(parser::ERROR_ID as u8);
+                if r.variable_type != error_type {
+                    let variable_type = r.variable_type;
+                    self.errors.lock().unwrap().report_error(
+                        identifier_token.span.clone(),
+                        CompilationErrorType::AssignmentTypeMismatch(variable_type, error_type),
+                    );
+                }
+            } else {
+                self.errors
+                    .lock()
+                    .unwrap()
+                    .report_error(identifier_token.span.clone(), CompilationErrorType::VariableNotFound(identifier.to_string()));
+            }
+        }
+        walk_try_stmt(self, try_stmt);
+        VariableType::None
+    }
+
+    fn visit_try_marker_statement(&mut self, marker: &TryMarkerStatement) -> VariableType {
+        if let Some(label_token) = marker.get_catch_label_token() {
+            self.check_try_runtime(&marker.get_span());
+            self.add_label_usage(label_token);
+        }
+        VariableType::None
+    }
+
     fn visit_label_statement(&mut self, label: &LabelStatement) -> VariableType {
         self.set_label_declaration(label.get_label_token());
         VariableType::None
@@ -1991,7 +2121,12 @@ impl AstVisitor<VariableType> for SemanticVisitor {
             } else {
                 target_type = self.references[idx].1.variable_type;
                 if let Some(header) = &self.references[idx].1.header {
-                    self.check_arg_count(header.dim as usize, let_stmt.get_arguments().len(), let_stmt.get_identifier_token());
+                    let dim = header.dim as usize;
+                    if let Some(item_type) = self.collection_item_type(target_type, dim, let_stmt.get_arguments().len()) {
+                        target_type = item_type;
+                    } else {
+                        self.check_arg_count(dim, let_stmt.get_arguments().len(), let_stmt.get_identifier_token());
+                    }
                 } else {
                     self.errors
                         .lock()
@@ -2032,6 +2167,15 @@ impl AstVisitor<VariableType> for SemanticVisitor {
             arg.visit(self);
         }
         let value_type = let_stmt.get_value_expression().visit(self);
+        if let_stmt.get_identifier().starts_with(EACH_COLLECTION_PREFIX)
+            && let Some(idx) = self.lookup_variable(let_stmt.get_identifier())
+        {
+            self.references[idx].1.variable_type = value_type;
+            if let Some(header) = &mut self.references[idx].1.header {
+                header.variable_type = value_type;
+            }
+            target_type = value_type;
+        }
         if (self.type_registry.is_enum_type(target_type) || self.type_registry.is_enum_type(value_type)) && target_type != value_type {
             self.errors.lock().unwrap().report_error(
                 let_stmt.get_eq_token().span.clone(),
@@ -2066,6 +2210,51 @@ impl AstVisitor<VariableType> for SemanticVisitor {
         VariableType::None
     }
 
+    /// Only reached for an untransformed tree, as the LSP has it; the compiler sees the
+    /// loop lowered onto `<each item>`.
+    fn visit_for_each_statement(&mut self, for_each_stmt: &crate::ast::ForEachStatement) -> VariableType {
+        let loop_type = if let Some(idx) = self.lookup_variable(for_each_stmt.get_identifier()) {
+            let (_rt, r) = &mut self.references[idx];
+            let identifier = for_each_stmt.get_identifier_token();
+            r.usages.push((
+                self.errors.lock().unwrap().file_name().to_path_buf(),
+                Spanned::new(identifier.token.to_string(), identifier.span.clone()),
+            ));
+            Some(r.variable_type)
+        } else {
+            self.errors.lock().unwrap().report_error(
+                for_each_stmt.get_identifier_token().span.clone(),
+                CompilationErrorType::VariableNotFound(for_each_stmt.get_identifier().to_string()),
+            );
+            None
+        };
+        let collection_type = for_each_stmt.get_collection_expr().visit(self);
+        let item_type = match self.type_registry.get_collection(collection_type) {
+            Some((CollectionKind::List, element)) => Some(element),
+            Some((CollectionKind::Map, _)) => Some(VariableType::String),
+            None => {
+                self.errors.lock().unwrap().report_error(
+                    for_each_stmt.get_collection_expr().get_span(),
+                    CompilationErrorType::CollectionExpected(self.source_type_name(collection_type)),
+                );
+                None
+            }
+        };
+        if let (Some(loop_type), Some(item_type)) = (loop_type, item_type)
+            && loop_type != item_type
+            && (matches!(loop_type, VariableType::UserData(_)) || matches!(item_type, VariableType::UserData(_)))
+        {
+            self.errors.lock().unwrap().report_error(
+                for_each_stmt.get_in_token().span.clone(),
+                CompilationErrorType::AssignmentTypeMismatch(loop_type, item_type),
+            );
+        }
+        for stmt in for_each_stmt.get_statements() {
+            stmt.visit(self);
+        }
+        VariableType::None
+    }
+
     fn visit_const_declaration_statement(&mut self, const_decl: &ConstDeclarationStatement) -> VariableType {
         // The value is never read at runtime, so walking it would put literals nobody
         // uses into the table.
@@ -2123,6 +2312,7 @@ impl AstVisitor<VariableType> for SemanticVisitor {
     }
 
     fn visit_variable_declaration_statement(&mut self, var_decl: &VariableDeclarationStatement) -> VariableType {
+        self.check_collection_runtime(var_decl.get_variable_type(), &var_decl.get_type_token().span);
         for v in var_decl.get_variables() {
             if self.has_variable_defined(v.get_identifier()) {
                 self.errors.lock().unwrap().report_error(
@@ -2506,6 +2696,9 @@ impl AstVisitor<VariableType> for SemanticVisitor {
                 ParserErrorType::TypeNeedsNewerRuntime(FIRST_TYPE_TABLE_RUNTIME),
             );
         }
+        for field in type_decl.get_fields() {
+            self.check_collection_runtime(field.get_variable_type(), &field.get_type_token().span);
+        }
         VariableType::None
     }
 
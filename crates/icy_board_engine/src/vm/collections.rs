@@ -0,0 +1,174 @@
+//! `LIST OF` and `MAP OF` at run time.
+//!
+//! Reading an expression hands out a copy, but an item has to be changed where it
+//! lives. So a target is first resolved to a place, the variable plus the steps
+//! into it, and only then walked into mutably.
+
+use crate::{
+    Res,
+    executable::{CollectionMember, GenericVariableData, PPEExpr, VariableType, VariableValue},
+};
+
+use super::{VMError, VirtualMachine};
+
+/// One step from a value into a part of it.
+pub enum PlaceStep {
+    Field(usize),
+    Index(usize, usize, usize),
+    /// An item of a collection, by list index or map key.
+    Item(VariableValue),
+}
+
+impl VirtualMachine<'_> {
+    pub(crate) fn is_collection_variable(&self, id: usize) -> bool {
+        matches!(
+            self.variable_table.get_value(id).generic_data,
+            GenericVariableData::List(_) | GenericVariableData::Map(_)
+        )
+    }
+
+    /// The variable `expr` is stored in and the way into it, or `None` when `expr`
+    /// computes a value rather than naming one. Index expressions are evaluated here,
+    /// once.
+    pub(crate) async fn resolve_place(&mut self, expr: &PPEExpr) -> Res<Option<(usize, Vec<PlaceStep>)>> {
+        let mut fields = Vec::new();
+        let mut base = expr;
+        while let PPEExpr::Member(inner, member_id) = base {
+            fields.push(*member_id);
+            base = inner.as_ref();
+        }
+        let mut steps = Vec::new();
+        let id = match base {
+            PPEExpr::Value(id) => *id,
+            PPEExpr::Dim(id, dims) if dims.len() == 1 && self.is_collection_variable(*id) => {
+                steps.push(PlaceStep::Item(self.eval_expr(&dims[0]).await?));
+                *id
+            }
+            PPEExpr::Dim(id, dims) => {
+                let dim_1 = self.eval_expr(&dims[0]).await?.as_int() as usize;
+                let dim_2 = if dims.len() >= 2 {
+                    self.eval_expr(&dims[1]).await?.as_int() as usize
+                } else {
+                    0
+                };
+                let dim_3 = if dims.len() >= 3 {
+                    self.eval_expr(&dims[2]).await?.as_int() as usize
+                } else {
+                    0
+                };
+                steps.push(PlaceStep::Index(dim_1, dim_2, dim_3));
+                *id
+            }
+            _ => return Ok(None),
+        };
+        steps.extend(fields.into_iter().rev().map(PlaceStep::Field));
+        Ok(Some((id, steps)))
+    }
+
+    /// Walks into a place. A map makes the entry for a key it does not have yet.
+    pub(crate) fn place_mut(&mut self, id: usize, steps: &[PlaceStep]) -> Res<&mut VariableValue> {
+        let mut target = &mut self.variable_table.get_var_entry_mut(id).value;
+        for step in steps {
+            target = match step {
+                PlaceStep::Field(member_id) => {
+                    let vtype = target.vtype;
+                    let GenericVariableData::Record(fields) = &mut target.generic_data else {
+                        return Err(VMError::NoUserTypeBase.into());
+                    };
+                    let Some(field) = fields.get_mut(*member_id) else {
+                        let VariableType::UserData(type_id) = vtype else {
+                            return Err(VMError::NoUserTypeBase.into());
+                        };
+                        return Err(VMError::InvalidMemberId(type_id, *member_id).into());
+                    };
+                    field
+                }
+                PlaceStep::Index(dim_1, dim_2, dim_3) => target.get_array_value_mut(*dim_1, *dim_2, *dim_3).ok_or(VMError::InternalVMError)?,
+                PlaceStep::Item(key) => match &mut target.generic_data {
+                    GenericVariableData::List(list) => {
+                        let index = key.as_int();
+                        let len = list.len();
+                        list.get_mut(index).ok_or(VMError::CollectionIndexOutOfRange(index, len))?
+                    }
+                    GenericVariableData::Map(map) => map.entry(&key.to_string()),
+                    _ => return Err(VMError::InternalVMError.into()),
+                },
+            };
+        }
+        Ok(target)
+    }
+
+    /// Item `key` of collection variable `id`. A map key that was never set reads as
+    /// an empty item, like an array element nobody wrote to.
+    pub(crate) async fn read_collection_item(&mut self, id: usize, key: &PPEExpr) -> Res<VariableValue> {
+        let key = self.eval_expr(key).await?;
+        match &self.variable_table.get_value(id).generic_data {
+            GenericVariableData::List(list) => {
+                let index = key.as_int();
+                list.get(index)
+                    .cloned()
+                    .ok_or_else(|| VMError::CollectionIndexOutOfRange(index, list.len()).into())
+            }
+            GenericVariableData::Map(map) => Ok(map.get(&key.to_string()).cloned().unwrap_or_else(|| (*map.element).clone())),
+            _ => Err(VMError::InternalVMError.into()),
+        }
+    }
+
+    pub(crate) fn call_collection_member(target: &mut VariableValue, member_id: usize, args: Vec<VariableValue>) -> Res<VariableValue> {
+        let VariableType::UserData(type_id) = target.vtype else {
+            return Err(VMError::NoUserTypeBase.into());
+        };
+        let Some(member) = CollectionMember::from_id(member_id) else {
+            return Err(VMError::InvalidMemberId(type_id, member_id).into());
+        };
+        let mut args = args.into_iter();
+        let mut arg = || args.next().ok_or(VMError::InvalidMemberArgumentCount(type_id, member_id, 1, 0));
+        let result = match &mut target.generic_data {
+            GenericVariableData::List(list) => match member {
+                CollectionMember::Add => {
+                    list.add(arg()?);
+                    None
+                }
+                CollectionMember::Insert => {
+                    let index = arg()?.as_int();
+                    if !list.insert(index, arg()?) {
+                        return Err(VMError::CollectionIndexOutOfRange(index, list.len()).into());
+                    }
+                    None
+                }
+                CollectionMember::Remove => {
+                    let index = arg()?.as_int();
+                    if !list.remove(index) {
+                        return Err(VMError::CollectionIndexOutOfRange(index, list.len()).into());
+                    }
+                    None
+                }
+                CollectionMember::Contains => Some(list.contains(&arg()?)),
+                CollectionMember::Clear => {
+                    list.clear();
+                    None
+                }
+            },
+            GenericVariableData::Map(map) => match member {
+                CollectionMember::Add => return Err(VMError::InvalidMemberFunction(type_id, member_id).into()),
+                CollectionMember::Insert => {
+                    let key = arg()?.to_string();
+                    map.insert(&key, arg()?);
+                    None
+                }
+                // Taking out a key that is not there leaves nothing to do.
+                CollectionMember::Remove => {
+                    map.remove(&arg()?.to_string());
+                    None
+                }
+                CollectionMember::Contains => Some(map.contains(&arg()?.to_string())),
+                CollectionMember::Clear => {
+                    map.clear();
+                    None
+                }
+            },
+            _ => return Err(VMError::NoUserTypeBase.into()),
+        };
+        Ok(result.map_or_else(VariableValue::default, VariableValue::new_bool))
+    }
+}
@@ -55,6 +55,9 @@ pub async fn dcreate(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<bool>
     }
 
     let path = vm.resolve_file(&table_path(&name)).await;
+    if !vm.permit_write(&path) {
+        return Ok(true);
+    }
     Ok(vm.dbase.create(channel, &path, &fields))
 }
 
@@ -62,6 +65,10 @@ pub async fn dopen(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<bool> {
     let channel = channel(vm, args, 0).await?;
     let name = text(vm, args, 1).await?;
     let path = vm.resolve_file(&table_path(&name)).await;
+    // Tables are opened for writing.
+    if !vm.permit_write(&path) {
+        return Ok(true);
+    }
     // PCBoard reports success even when the table is not there, so a miss only shows up
     // on the first access.
     vm.dbase.open(channel, &path);
@@ -273,6 +280,9 @@ pub async fn dncreate(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<bool
     let name = text(vm, args, 1).await?;
     let expression = text(vm, args, 2).await?;
     let path = index_file(vm, &name).await;
+    if !vm.permit_write(&path) {
+        return Ok(true);
+    }
     Ok(vm.dbase.create_index(channel, &name, path, &expression))
 }
 
@@ -280,6 +290,9 @@ pub async fn dnopen(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<bool>
     let channel = channel(vm, args, 0).await?;
     let name = text(vm, args, 1).await?;
     let path = index_file(vm, &name).await;
+    if !vm.permit_write(&path) {
+        return Ok(true);
+    }
     Ok(vm.dbase.open_index(channel, &name, path))
 }
 
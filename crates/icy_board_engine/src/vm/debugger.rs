@@ -0,0 +1,837 @@
+//! Lets a debugger stop a running PPE, step through it and look at its variables.
+//! This is the VM's end: requests come in over a channel and queries are answered
+//! on the oneshot they bring. `icboard --debug-ppe` speaks DAP on the other end.
+//!
+//! Stepping goes by source line, so it needs the debug information `pplc --debug`
+//! writes. Without it every statement counts as a line of its own.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    executable::{DebugInfo, EntryType, GenericVariableData, TypeLayout, VariableType, VariableValue},
+    parser::FIRST_USER_TYPE_ID,
+};
+
+use super::VirtualMachine;
+
+/// Arrays are handed out in pages, a debugger asks for more when it shows them.
+const MAX_CHILDREN: usize = 1000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopReason {
+    Entry,
+    Breakpoint,
+    Step,
+    Pause,
+    /// The PPE is about to die of this error.
+    Exception(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct DebugFrame {
+    /// The call depth, 0 is the main program.
+    pub id: usize,
+    pub name: String,
+    pub file: Option<PathBuf>,
+    pub line: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct DebugScope {
+    pub name: String,
+    pub reference: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DebugVariable {
+    pub name: String,
+    pub value: String,
+    pub type_name: String,
+    /// Where the children are, 0 when there are none.
+    pub reference: usize,
+    /// How many elements an array has, for a debugger that pages through them.
+    pub indexed: usize,
+}
+
+pub enum DebugRequest {
+    /// Replaces the breakpoints of a file. The answer has the line each one
+    /// really stops at, `None` for one that has no code.
+    SetBreakpoints {
+        file: PathBuf,
+        lines: Vec<usize>,
+        reply: oneshot::Sender<Vec<Option<usize>>>,
+    },
+    /// The PPE waits for this before it runs its first statement.
+    ConfigurationDone {
+        stop_on_entry: bool,
+    },
+    Continue,
+    Pause,
+    StepIn,
+    StepOver,
+    StepOut,
+    StackTrace(oneshot::Sender<Vec<DebugFrame>>),
+    Scopes {
+        frame: usize,
+        reply: oneshot::Sender<Vec<DebugScope>>,
+    },
+    Variables {
+        reference: usize,
+        start: usize,
+        count: usize,
+        reply: oneshot::Sender<Vec<DebugVariable>>,
+    },
+    Evaluate {
+        expression: String,
+        frame: usize,
+        reply: oneshot::Sender<Result<DebugVariable, String>>,
+    },
+    /// Ends the PPE.
+    Disconnect,
+}
+
+pub enum DebugEvent {
+    Stopped(StopReason),
+    Exited,
+}
+
+/// The debugger's end of the channels.
+pub struct DebugClient {
+    pub requests: mpsc::UnboundedSender<DebugRequest>,
+    pub events: mpsc::UnboundedReceiver<DebugEvent>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StepMode {
+    Run,
+    Pause,
+    In,
+    /// Until a line at this call depth or above.
+    Over(usize),
+    /// Until a line above this call depth.
+    Out(usize),
+}
+
+enum Flow {
+    Stay,
+    Resume,
+    Disconnect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Root {
+    /// The parameters and locals of the routine with this id.
+    Locals(usize),
+    Globals,
+    Variable(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Step {
+    Element(usize, usize, usize),
+    Field(usize),
+    /// The n-th item of a list, or the n-th entry of a map.
+    Item(usize),
+}
+
+/// What a variables reference stands for, so it can be read again when asked.
+#[derive(Clone, Debug, PartialEq)]
+struct ValuePath {
+    root: Root,
+    steps: Vec<Step>,
+}
+
+/// The VM's end of the channels, handed to the VM through `IcyBoardState::ppe_debugger`.
+pub struct PpeDebugger {
+    requests: mpsc::UnboundedReceiver<DebugRequest>,
+    events: mpsc::UnboundedSender<DebugEvent>,
+    configured: bool,
+    stop_on_entry: bool,
+    breakpoints: HashMap<PathBuf, Vec<usize>>,
+    breakpoint_offsets: HashSet<usize>,
+    mode: StepMode,
+    /// An error is shown once, not again by every call it passes on its way out.
+    error_shown: bool,
+    /// Only valid while stopped, a reference is its index plus one.
+    references: Vec<ValuePath>,
+}
+
+pub fn debug_channel() -> (PpeDebugger, DebugClient) {
+    let (request_tx, request_rx) = mpsc::unbounded_channel();
+    let (event_tx, event_rx) = mpsc::unbounded_channel();
+    (
+        PpeDebugger {
+            requests: request_rx,
+            events: event_tx,
+            configured: false,
+            stop_on_entry: false,
+            breakpoints: HashMap::new(),
+            breakpoint_offsets: HashSet::new(),
+            mode: StepMode::Run,
+            error_shown: false,
+            references: Vec::new(),
+        },
+        DebugClient {
+            requests: request_tx,
+            events: event_rx,
+        },
+    )
+}
+
+impl PpeDebugger {
+    pub fn exited(&self) {
+        let _ = self.events.send(DebugEvent::Exited);
+    }
+
+    /// Runs before every statement. Answers `false` once the debugger wants the PPE gone.
+    async fn before_statement(&mut self, vm: &VirtualMachine<'_>, ptr: usize) -> bool {
+        while let Ok(request) = self.requests.try_recv() {
+            if matches!(self.handle_running(vm, request), Flow::Disconnect) {
+                return false;
+            }
+        }
+        if !self.configured {
+            while !self.configured {
+                let Some(request) = self.requests.recv().await else {
+                    self.configured = true;
+                    return true;
+                };
+                if matches!(self.handle_running(vm, request), Flow::Disconnect) {
+                    return false;
+                }
+            }
+            if self.stop_on_entry {
+                return self.stop(vm, ptr, StopReason::Entry).await;
+            }
+        }
+
+        let offset = vm.script.statements[ptr].span.start * 2;
+        let line_start = vm.debug_info.as_ref().is_none_or(|info| info.is_line_start(offset));
+        let depth = vm.return_addresses.len();
+        let reason = if self.mode == StepMode::Pause {
+            Some(StopReason::Pause)
+        } else if line_start && self.breakpoint_offsets.contains(&offset) {
+            Some(StopReason::Breakpoint)
+        } else if line_start
+            && match self.mode {
+                StepMode::In => true,
+                StepMode::Over(from) => depth <= from,
+                StepMode::Out(from) => depth < from,
+                StepMode::Run | StepMode::Pause => false,
+            }
+        {
+            Some(StopReason::Step)
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => self.stop(vm, ptr, reason).await,
+            None => true,
+        }
+    }
+
+    /// Waits for the debugger to let the PPE go on. Answers `false` if it should not.
+    async fn stop(&mut self, vm: &VirtualMachine<'_>, ptr: usize, reason: StopReason) -> bool {
+        self.mode = StepMode::Run;
+        self.references.clear();
+        if self.events.send(DebugEvent::Stopped(reason)).is_err() {
+            return true;
+        }
+        while let Some(request) = self.requests.recv().await {
+            match self.handle_stopped(vm, ptr, request) {
+                Flow::Stay => {}
+                Flow::Resume => return true,
+                Flow::Disconnect => return false,
+            }
+        }
+        // The debugger went away, the PPE runs on without it.
+        true
+    }
+
+    fn handle_running(&mut self, vm: &VirtualMachine<'_>, request: DebugRequest) -> Flow {
+        match request {
+            DebugRequest::SetBreakpoints { file, lines, reply } => {
+                let _ = reply.send(self.set_breakpoints(vm.debug_info.as_ref(), file, lines));
+            }
+            DebugRequest::ConfigurationDone { stop_on_entry } => {
+                self.configured = true;
+                self.stop_on_entry = stop_on_entry;
+            }
+            DebugRequest::Pause => self.mode = StepMode::Pause,
+            DebugRequest::Disconnect => return Flow::Disconnect,
+            // Nothing to look at while the PPE runs.
+            DebugRequest::StackTrace(reply) => {
+                let _ = reply.send(Vec::new());
+            }
+            DebugRequest::Scopes { reply, .. } => {
+                let _ = reply.send(Vec::new());
+            }
+            DebugRequest::Variables { reply, .. } => {
+                let _ = reply.send(Vec::new());
+            }
+            DebugRequest::Evaluate { reply, .. } => {
+                let _ = reply.send(Err("the PPE is running".to_string()));
+            }
+            DebugRequest::Continue | DebugRequest::StepIn | DebugRequest::StepOver | DebugRequest::StepOut => {}
+        }
+        Flow::Stay
+    }
+
+    fn handle_stopped(&mut self, vm: &VirtualMachine<'_>, ptr: usize, request: DebugRequest) -> Flow {
+        let depth = vm.return_addresses.len();
+        match request {
+            DebugRequest::Continue => self.mode = StepMode::Run,
+            DebugRequest::StepIn => self.mode = StepMode::In,
+            DebugRequest::StepOver => self.mode = StepMode::Over(depth),
+            DebugRequest::StepOut => self.mode = StepMode::Out(depth),
+            DebugRequest::StackTrace(reply) => {
+                let _ = reply.send(self.frames(vm, ptr));
+                return Flow::Stay;
+            }
+            DebugRequest::Scopes { frame, reply } => {
+                let _ = reply.send(self.scopes(vm, frame));
+                return Flow::Stay;
+            }
+            DebugRequest::Variables {
+                reference,
+                start,
+                count,
+                reply,
+            } => {
+                let _ = reply.send(self.variables(vm, reference, start, count));
+                return Flow::Stay;
+            }
+            DebugRequest::Evaluate { expression, frame, reply } => {
+                let _ = reply.send(self.evaluate(vm, &expression, frame));
+                return Flow::Stay;
+            }
+            request => return self.handle_running(vm, request),
+        }
+        Flow::Resume
+    }
+
+    /// Answers, for each of the file's lines, the line the breakpoint ended up on.
+    fn set_breakpoints(&mut self, debug_info: Option<&DebugInfo>, file: PathBuf, lines: Vec<usize>) -> Vec<Option<usize>> {
+        self.breakpoints.insert(file.clone(), lines);
+        self.breakpoint_offsets.clear();
+        let Some(debug_info) = debug_info else {
+            return vec![None; self.breakpoints[&file].len()];
+        };
+        let mut result = Vec::new();
+        for (breakpoint_file, lines) in &self.breakpoints {
+            let file_index = debug_info.file_index(breakpoint_file);
+            for line in lines {
+                // A line without code stops at the next one that has some.
+                let target = file_index.and_then(|idx| {
+                    debug_info
+                        .lines
+                        .iter()
+                        .filter(|mapping| mapping.file() == idx && mapping.line() >= *line)
+                        .map(|mapping| mapping.line())
+                        .min()
+                });
+                if let (Some(idx), Some(target)) = (file_index, target) {
+                    self.breakpoint_offsets.extend(
+                        debug_info
+                            .lines
+                            .iter()
+                            .filter(|mapping| mapping.file() == idx && mapping.line() == target)
+                            .map(|mapping| mapping.offset()),
+                    );
+                }
+                if *breakpoint_file == file {
+                    result.push(target);
+                }
+            }
+        }
+        result
+    }
+
+    fn frames(&self, vm: &VirtualMachine<'_>, ptr: usize) -> Vec<DebugFrame> {
+        let depth = vm.return_addresses.len();
+        let mut frames = Vec::new();
+        for id in (0..=depth).rev() {
+            // The innermost frame is where the PPE stopped, every other one where it made the call.
+            let at = if id == depth {
+                ptr
+            } else {
+                vm.return_addresses[id].get_ptr().saturating_sub(1)
+            };
+            let offset = vm.script.statements.get(at).map_or(0, |stmt| stmt.span.start * 2);
+            let location = vm.debug_info.as_ref().and_then(|info| info.location(offset));
+            frames.push(DebugFrame {
+                id,
+                name: routine_name(vm, id),
+                file: location.map(|(file, _)| file.to_path_buf()),
+                line: location.map_or(0, |(_, line)| line),
+            });
+        }
+        frames
+    }
+
+    fn scopes(&mut self, vm: &VirtualMachine<'_>, frame: usize) -> Vec<DebugScope> {
+        // The id comes from the client, and a stale one names a frame that has returned since.
+        let Some(deeper_calls) = vm.return_addresses.get(frame..) else {
+            return Vec::new();
+        };
+        let mut scopes = Vec::new();
+        if let Some(routine) = frame.checked_sub(1).and_then(|idx| vm.return_addresses.get(idx))
+            && !routine.is_gosub()
+            // A routine that was called again further in has its locals replaced by the later call's.
+            && !deeper_calls.iter().any(|deeper| deeper.get_id() == routine.get_id())
+        {
+            scopes.push(DebugScope {
+                name: "Locals".to_string(),
+                reference: self.reference(ValuePath {
+                    root: Root::Locals(routine.get_id()),
+                    steps: Vec::new(),
+                }),
+            });
+        }
+        scopes.push(DebugScope {
+            name: "Globals".to_string(),
+            reference: self.reference(ValuePath {
+                root: Root::Globals,
+                steps: Vec::new(),
+            }),
+        });
+        scopes
+    }
+
+    fn variables(&mut self, vm: &VirtualMachine<'_>, reference: usize, start: usize, count: usize) -> Vec<DebugVariable> {
+        let Some(path) = reference.checked_sub(1).and_then(|idx| self.references.get(idx)).cloned() else {
+            return Vec::new();
+        };
+        let count = if count == 0 { MAX_CHILDREN } else { count.min(MAX_CHILDREN) };
+        match path.root {
+            Root::Locals(routine) if path.steps.is_empty() => {
+                let ids = routine_variables(vm, routine);
+                ids.into_iter().map(|id| self.describe_variable(vm, id)).collect()
+            }
+            Root::Globals => {
+                let mut locals = HashSet::new();
+                for entry in vm.variable_table.get_entries() {
+                    if matches!(entry.header.variable_type, VariableType::Function | VariableType::Procedure) {
+                        locals.extend(routine_variables(vm, entry.header.id));
+                    }
+                }
+                vm.variable_table
+                    .get_entries()
+                    .iter()
+                    .filter(|entry| {
+                        entry.get_type() != EntryType::Constant
+                            && !matches!(entry.header.variable_type, VariableType::Function | VariableType::Procedure)
+                            && !locals.contains(&entry.header.id)
+                    })
+                    .map(|entry| entry.header.id)
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|id| self.describe_variable(vm, id))
+                    .collect()
+            }
+            _ => {
+                let Some(value) = resolve(vm, &path) else {
+                    return Vec::new();
+                };
+                self.children(vm, &path, &value, start, count)
+            }
+        }
+    }
+
+    fn children(&mut self, vm: &VirtualMachine<'_>, path: &ValuePath, value: &VariableValue, start: usize, count: usize) -> Vec<DebugVariable> {
+        let mut result = Vec::new();
+        match &value.generic_data {
+            GenericVariableData::Record(fields) => {
+                let names = field_names(vm, value.vtype, fields.len());
+                for (idx, field) in fields.iter().enumerate() {
+                    result.push(self.describe(vm, path, Step::Field(idx), names[idx].clone(), field));
+                }
+            }
+            GenericVariableData::Dim1(_) | GenericVariableData::Dim2(_) | GenericVariableData::Dim3(_) => {
+                for n in start..element_count(value).min(start.saturating_add(count)) {
+                    let (i, j, k) = element_index(value, n);
+                    let Some(element) = element(value, i, j, k) else {
+                        continue;
+                    };
+                    let name = match value.get_dimensions() {
+                        1 => format!("({i})"),
+                        2 => format!("({i},{j})"),
+                        _ => format!("({i},{j},{k})"),
+                    };
+                    result.push(self.describe(vm, path, Step::Element(i, j, k), name, element));
+                }
+            }
+            GenericVariableData::List(list) => {
+                for (n, item) in list.items.iter().enumerate().skip(start).take(count) {
+                    result.push(self.describe(vm, path, Step::Item(n), format!("({n})"), item));
+                }
+            }
+            GenericVariableData::Map(map) => {
+                for (n, (key, item)) in map.entries.iter().enumerate().skip(start).take(count) {
+                    result.push(self.describe(vm, path, Step::Item(n), format!("({key:?})"), item));
+                }
+            }
+            _ => {}
+        }
+        result
+    }
+
+    fn describe_variable(&mut self, vm: &VirtualMachine<'_>, id: usize) -> DebugVariable {
+        let path = ValuePath {
+            root: Root::Variable(id),
+            steps: Vec::new(),
+        };
+        let value = vm.variable_table.get_value(id).clone();
+        self.describe_value(vm, path, variable_name(vm, id), &value)
+    }
+
+    fn describe(&mut self, vm: &VirtualMachine<'_>, parent: &ValuePath, step: Step, name: String, value: &VariableValue) -> DebugVariable {
+        let mut path = parent.clone();
+        path.steps.push(step);
+        self.describe_value(vm, path, name, value)
+    }
+
+    fn describe_value(&mut self, vm: &VirtualMachine<'_>, path: ValuePath, name: String, value: &VariableValue) -> DebugVariable {
+        let type_name = type_name(vm, value.vtype);
+        match &value.generic_data {
+            GenericVariableData::Dim1(_) | GenericVariableData::Dim2(_) | GenericVariableData::Dim3(_) => {
+                let sizes = match value.get_dimensions() {
+                    1 => format!("{}", value.get_vector_size()),
+                    2 => format!("{},{}", value.get_vector_size(), value.get_matrix_size()),
+                    _ => format!("{},{},{}", value.get_vector_size(), value.get_matrix_size(), value.get_cube_size()),
+                };
+                DebugVariable {
+                    name,
+                    value: format!("{type_name}({sizes})"),
+                    type_name: format!("{type_name}({sizes})"),
+                    indexed: element_count(value),
+                    reference: self.reference(path),
+                }
+            }
+            GenericVariableData::Record(_) => DebugVariable {
+                name,
+                value: type_name.clone(),
+                type_name,
+                reference: self.reference(path),
+                indexed: 0,
+            },
+            GenericVariableData::List(_) | GenericVariableData::Map(_) => {
+                let len = item_count(value);
+                DebugVariable {
+                    name,
+                    value: format!("{type_name} ({len} items)"),
+                    type_name,
+                    reference: self.reference(path),
+                    indexed: len,
+                }
+            }
+            _ => DebugVariable {
+                name,
+                value: if matches!(value.vtype, VariableType::String | VariableType::BigStr) {
+                    format!("{:?}", value.to_string())
+                } else {
+                    value.to_string()
+                },
+                type_name,
+                reference: 0,
+                indexed: 0,
+            },
+        }
+    }
+
+    fn reference(&mut self, path: ValuePath) -> usize {
+        if let Some(idx) = self.references.iter().position(|known| *known == path) {
+            return idx + 1;
+        }
+        self.references.push(path);
+        self.references.len()
+    }
+
+    /// Watches and hovers: a variable, optionally followed by array indices and
+    /// record fields, such as `SCORES(3)` or `PLAYER.NAME`.
+    fn evaluate(&mut self, vm: &VirtualMachine<'_>, expression: &str, frame: usize) -> Result<DebugVariable, String> {
+        let expression = expression.trim();
+        let name_end = expression.find(['(', '[', '.']).unwrap_or(expression.len());
+        let name = expression[..name_end].trim();
+        let routine = frame
+            .checked_sub(1)
+            .and_then(|idx| vm.return_addresses.get(idx))
+            .filter(|address| !address.is_gosub())
+            .map(|address| address.get_id());
+        let id = find_variable(vm, routine, name).ok_or_else(|| format!("{name} is not a variable"))?;
+
+        let mut path = ValuePath {
+            root: Root::Variable(id),
+            steps: Vec::new(),
+        };
+        let mut value = vm.variable_table.get_value(id).clone();
+        let mut rest = expression[name_end..].trim_start();
+        while !rest.is_empty() {
+            let step = if let Some(field) = rest.strip_prefix('.') {
+                let end = field.find(['(', '[', '.']).unwrap_or(field.len());
+                let GenericVariableData::Record(fields) = &value.generic_data else {
+                    return Err(format!("{name} is not a record"));
+                };
+                let names = field_names(vm, value.vtype, fields.len());
+                let field_name = field[..end].trim();
+                let idx = names
+                    .iter()
+                    .position(|known| known.eq_ignore_ascii_case(field_name))
+                    .ok_or_else(|| format!("{name} has no field {field_name}"))?;
+                rest = field[end..].trim_start();
+                Step::Field(idx)
+            } else if let GenericVariableData::Map(map) = &value.generic_data {
+                let close = if rest.starts_with('(') { ')' } else { ']' };
+                let end = rest.find(close).ok_or_else(|| format!("missing '{close}'"))?;
+                let key = rest[1..end].trim().trim_matches('"');
+                let idx = map
+                    .entries
+                    .iter()
+                    .position(|(known, _)| known == key)
+                    .ok_or_else(|| format!("{name} has no key {key:?}"))?;
+                rest = rest[end + 1..].trim_start();
+                Step::Item(idx)
+            } else {
+                let close = if rest.starts_with('(') { ')' } else { ']' };
+                let end = rest.find(close).ok_or_else(|| format!("missing '{close}'"))?;
+                let indices = rest[1..end]
+                    .split(',')
+                    .map(|idx| idx.trim().parse::<usize>().map_err(|_| format!("'{}' is not an index", idx.trim())))
+                    .collect::<Result<Vec<_>, _>>()?;
+                rest = rest[end + 1..].trim_start();
+                if matches!(value.generic_data, GenericVariableData::List(_)) {
+                    Step::Item(indices.first().copied().unwrap_or(0))
+                } else {
+                    Step::Element(
+                        indices.first().copied().unwrap_or(0),
+                        indices.get(1).copied().unwrap_or(0),
+                        indices.get(2).copied().unwrap_or(0),
+                    )
+                }
+            };
+            value = apply(&value, step).ok_or_else(|| format!("{expression} is out of bounds"))?;
+            path.steps.push(step);
+        }
+        Ok(self.describe_value(vm, path, expression.to_string(), &value))
+    }
+}
+
+impl VirtualMachine<'_> {
+    /// Lets the debugger look at the PPE before the statement at `ptr` runs.
+    pub(super) async fn debug_statement(&mut self, ptr: usize) {
+        let Some(mut debugger) = self.debugger.take() else {
+            return;
+        };
+        if debugger.before_statement(self, ptr).await {
+            self.debugger = Some(debugger);
+        } else {
+            self.is_running = false;
+            debugger.exited();
+        }
+    }
+
+    /// Shows the debugger the error the PPE is about to end with, while its variables are still there.
+    pub(super) async fn debug_error(&mut self, ptr: usize, error: &str) {
+        let Some(mut debugger) = self.debugger.take() else {
+            return;
+        };
+        if !debugger.error_shown {
+            debugger.error_shown = true;
+            debugger.stop(self, ptr, StopReason::Exception(error.to_string())).await;
+        }
+        self.debugger = Some(debugger);
+    }
+}
+
+fn routine_name(vm: &VirtualMachine<'_>, frame: usize) -> String {
+    let Some(address) = frame.checked_sub(1).map(|idx| &vm.return_addresses[idx]) else {
+        return "main".to_string();
+    };
+    if address.is_gosub() {
+        return "GOSUB".to_string();
+    }
+    variable_name(vm, address.get_id())
+}
+
+fn variable_name(vm: &VirtualMachine<'_>, id: usize) -> String {
+    vm.debug_info
+        .as_ref()
+        .and_then(|info| info.name_of(id))
+        .map(str::to_string)
+        .or_else(|| vm.variable_table.try_get_entry(id).map(|entry| entry.get_name().clone()))
+        .unwrap_or_else(|| format!("#{id}"))
+}
+
+/// The ids of a routine's parameters and locals, a function's result last.
+fn routine_variables(vm: &VirtualMachine<'_>, routine: usize) -> Vec<usize> {
+    let Some(entry) = vm.variable_table.try_get_entry(routine) else {
+        return Vec::new();
+    };
+    let (first, count) = unsafe {
+        match entry.header.variable_type {
+            VariableType::Function => (
+                entry.value.data.function_value.first_var_id as usize,
+                entry.value.data.function_value.parameters as usize + entry.value.data.function_value.local_variables as usize,
+            ),
+            VariableType::Procedure => (
+                entry.value.data.procedure_value.first_var_id as usize,
+                entry.value.data.procedure_value.parameters as usize + entry.value.data.procedure_value.local_variables as usize,
+            ),
+            _ => return Vec::new(),
+        }
+    };
+    (first + 1..=first + count)
+        .filter(|id| vm.variable_table.try_get_entry(*id).is_some())
+        .collect()
+}
+
+/// Locals of the routine the frame is in first, then globals. Names are compared
+/// the way PPL compares them, without regard to case.
+fn find_variable(vm: &VirtualMachine<'_>, routine: Option<usize>, name: &str) -> Option<usize> {
+    if let Some(routine) = routine
+        && let Some(id) = routine_variables(vm, routine)
+            .into_iter()
+            .find(|id| variable_name(vm, *id).eq_ignore_ascii_case(name))
+    {
+        return Some(id);
+    }
+    vm.variable_table
+        .get_entries()
+        .iter()
+        .filter(|entry| entry.get_type() != EntryType::Constant)
+        .map(|entry| entry.header.id)
+        .find(|id| variable_name(vm, *id).eq_ignore_ascii_case(name))
+}
+
+fn type_name(vm: &VirtualMachine<'_>, vtype: VariableType) -> String {
+    if let VariableType::UserData(id) = vtype {
+        if let Some(definition) = vm.debug_info.as_ref().and_then(|info| info.type_name(id)) {
+            return definition.name.clone();
+        }
+        if let Some(definition) = vm.type_registry.get_record_type_from_id(id) {
+            return definition.name.to_string();
+        }
+        if let Some(TypeLayout::Collection(kind, element)) = (id as usize).checked_sub(FIRST_USER_TYPE_ID).and_then(|idx| vm.user_types.get(idx)) {
+            return kind.type_name(&type_name(vm, *element));
+        }
+    }
+    vtype.to_string()
+}
+
+fn field_names(vm: &VirtualMachine<'_>, vtype: VariableType, count: usize) -> Vec<String> {
+    let mut names = match vtype {
+        VariableType::UserData(id) => vm
+            .debug_info
+            .as_ref()
+            .and_then(|info| info.type_name(id))
+            .map(|definition| definition.fields.clone())
+            .or_else(|| {
+                vm.type_registry
+                    .get_record_type_from_id(id)
+                    .map(|definition| definition.fields.iter().map(|(name, _)| name.to_string()).collect())
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    names.truncate(count);
+    while names.len() < count {
+        names.push(format!("FIELD{}", names.len() + 1));
+    }
+    names
+}
+
+fn resolve(vm: &VirtualMachine<'_>, path: &ValuePath) -> Option<VariableValue> {
+    let Root::Variable(id) = path.root else {
+        return None;
+    };
+    let mut value = vm.variable_table.try_get_entry(id)?.value.clone();
+    for step in &path.steps {
+        value = apply(&value, *step)?;
+    }
+    Some(value)
+}
+
+fn apply(value: &VariableValue, step: Step) -> Option<VariableValue> {
+    match step {
+        Step::Element(i, j, k) => element(value, i, j, k).cloned(),
+        Step::Field(idx) => match &value.generic_data {
+            GenericVariableData::Record(fields) => fields.get(idx).cloned(),
+            _ => None,
+        },
+        Step::Item(idx) => match &value.generic_data {
+            GenericVariableData::List(list) => list.items.get(idx).cloned(),
+            GenericVariableData::Map(map) => map.entries.get_index(idx).map(|(_, item)| item.clone()),
+            _ => None,
+        },
+    }
+}
+
+fn item_count(value: &VariableValue) -> usize {
+    match &value.generic_data {
+        GenericVariableData::List(list) => list.len(),
+        GenericVariableData::Map(map) => map.len(),
+        _ => 0,
+    }
+}
+
+fn element(value: &VariableValue, i: usize, j: usize, k: usize) -> Option<&VariableValue> {
+    match &value.generic_data {
+        GenericVariableData::Dim1(data) => data.get(i),
+        GenericVariableData::Dim2(data) => data.get(i)?.get(j),
+        GenericVariableData::Dim3(data) => data.get(i)?.get(j)?.get(k),
+        _ => None,
+    }
+}
+
+fn array_sizes(value: &VariableValue) -> (usize, usize, usize) {
+    match value.get_dimensions() {
+        1 => (value.get_vector_size() + 1, 1, 1),
+        2 => (value.get_vector_size() + 1, value.get_matrix_size() + 1, 1),
+        3 => (value.get_vector_size() + 1, value.get_matrix_size() + 1, value.get_cube_size() + 1),
+        _ => (0, 0, 0),
+    }
+}
+
+/// How many elements an array has. PPL arrays run from 0 to their size.
+fn element_count(value: &VariableValue) -> usize {
+    let (v, m, c) = array_sizes(value);
+    v * m * c
+}
+
+/// The indices of the `n`th element, in the order a debugger lists them.
+fn element_index(value: &VariableValue, n: usize) -> (usize, usize, usize) {
+    let (_, m, c) = array_sizes(value);
+    (n / (m * c), n / c % m, n % c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_breakpoint_moves_to_the_next_line_with_code() {
+        let mut info = DebugInfo {
+            files: vec![PathBuf::from("main.pps"), PathBuf::from("lib.pps")],
+            ..Default::default()
+        };
+        info.add_line(0, 0, 2);
+        info.add_line(8, 0, 5);
+        info.add_line(16, 1, 1);
+        info.add_line(24, 0, 5);
+
+        let (mut debugger, _client) = debug_channel();
+        assert_eq!(debugger.set_breakpoints(Some(&info), PathBuf::from("lib.pps"), vec![1]), vec![Some(1)]);
+        assert_eq!(
+            debugger.set_breakpoints(Some(&info), PathBuf::from("main.pps"), vec![3, 9]),
+            vec![Some(5), None]
+        );
+        let mut offsets: Vec<_> = debugger.breakpoint_offsets.iter().copied().collect();
+        offsets.sort();
+        assert_eq!(offsets, vec![8, 16, 24]);
+    }
+}
@@ -307,6 +307,10 @@ pub async fn run_function(opcode: FuncOpCode, arg: &mut VirtualMachine<'_>, argu
         FuncOpCode::TermState => predefined_functions::termstate(arg, arguments).await,
         FuncOpCode::Err => predefined_functions::err(arg, arguments).await,
         FuncOpCode::TermInfo => predefined_functions::terminfo(arg, arguments).await,
+        FuncOpCode::EachItem => predefined_functions::each_item(arg, arguments).await,
+        FuncOpCode::JsonParse => predefined_functions::json_parse(arg, arguments).await,
+        FuncOpCode::JsonStr => predefined_functions::json_str(arg, arguments).await,
+        FuncOpCode::JsonText => predefined_functions::json_text(arg, arguments).await,
         FuncOpCode::END
         | FuncOpCode::CPAR
         | FuncOpCode::UPLUS
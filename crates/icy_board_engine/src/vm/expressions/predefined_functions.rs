@@ -18,10 +18,14 @@ use crate::icy_board::read_with_encoding_detection;
 use crate::icy_board::security_expr::SecurityExpression;
 use crate::icy_board::state::GraphicsMode;
 use crate::icy_board::state::functions::{MASK_ALNUM, MASK_ALPHA, MASK_ASCII, MASK_FILE, MASK_MESSAGE, MASK_NUM, MASK_PATH, MASK_PWD};
+use crate::icy_board::state::ppl_error::{ERR_IO, ERR_KIND_WEB, ERR_UNAVAILABLE, PplError};
+use crate::icy_board::state::ppl_json::PplJson;
 use crate::icy_board::user_base::{ConferenceFlags, Password};
 use crate::icy_board::user_inf::{BankUserInf, QwkConfigUserInf};
 use crate::parser::CONFERENCE_ID;
-use crate::vm::{TerminalTarget, VirtualMachine, dbase, get_file_channel};
+use crate::vm::json::{self, FieldNames};
+use crate::vm::sandbox::host_matches;
+use crate::vm::{TerminalTarget, VMError, VirtualMachine, dbase, get_file_channel};
 use base64::{Engine as _, engine::general_purpose};
 use bstr::BString;
 use chrono::{DateTime, Datelike, Timelike, Utc};
@@ -87,6 +91,8 @@ pub async fn len(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<VariableV
         GenericVariableData::Dim1(items) => items.len() - 1,
         GenericVariableData::Dim2(items) => items.len() - 1,
         GenericVariableData::Dim3(items) => items.len() - 1,
+        GenericVariableData::List(list) => list.len(),
+        GenericVariableData::Map(map) => map.len(),
         GenericVariableData::Password(p) => {
             match p {
                 Password::PlainText(s) => s.chars().count(),
@@ -741,6 +747,9 @@ pub async fn readline(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<Vari
     let file_name = vm.eval_expr(&args[0]).await?.as_string();
     let line = vm.eval_expr(&args[1]).await?.as_int();
     let file_name = vm.resolve_file(&file_name).await;
+    if !vm.permit_read(&file_name) {
+        return Ok(VariableValue::new_string(String::new()));
+    }
 
     if let Ok(file) = read_with_encoding_detection(&file_name) {
         let file = file.replace("\r\n", "\n");
@@ -1753,6 +1762,9 @@ pub async fn crc32(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<Variabl
 
     if use_file {
         let file = vm.resolve_file(&param).await;
+        if !vm.permit_read(&file) {
+            return Ok(VariableValue::new_unsigned(0));
+        }
         let buffer = fs::read(file)?;
         let crc = calc_crc32(&buffer);
         Ok(VariableValue::new_unsigned(crc as u64))
@@ -2724,13 +2736,25 @@ pub fn fix_casing(param: String) -> String {
 
 pub async fn web_request(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<VariableValue> {
     let url = vm.eval_expr(&args[0]).await?.as_string();
-    let Some(response) = http_get(&url).await else {
+    if !vm.permit_url(&url) {
         return Ok(VariableValue::new_string(String::new()));
+    }
+    let hosts = vm.sandbox.as_ref().map(|sandbox| sandbox.hosts().to_vec());
+    let response = match http_get(&url, hosts).await {
+        Ok(response) => response,
+        Err(error) => {
+            vm.set_error(error);
+            return Ok(VariableValue::new_string(String::new()));
+        }
     };
     match response.text().await {
-        Ok(text) => Ok(VariableValue::new_string(text)),
+        Ok(text) => {
+            vm.operation_succeeded();
+            Ok(VariableValue::new_string(text))
+        }
         Err(err) => {
             log::error!("WEBREQUEST {url}: {err}");
+            vm.set_error(PplError::new(ERR_KIND_WEB, ERR_IO, format!("reading {url} failed: {err}")));
             Ok(VariableValue::new_string(String::new()))
         }
     }
@@ -2866,24 +2890,86 @@ pub async fn terminfo(vm: &mut VirtualMachine<'_>, _args: &[PPEExpr]) -> Res<Var
     Ok(crate::icy_board::state::ppl_terminal_info::PplTerminalInfo::from(&vm.icy_board_state.session.term_caps).value())
 }
 
+/// Item `n` of a list or key `n` of a map, for the loop variable of a FOR EACH.
+pub async fn each_item(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<VariableValue> {
+    let collection = vm.eval_expr(&args[0]).await?;
+    let index = vm.eval_expr(&args[1]).await?.as_int();
+    match &collection.generic_data {
+        GenericVariableData::List(list) => list
+            .get(index)
+            .cloned()
+            .ok_or_else(|| VMError::CollectionIndexOutOfRange(index, list.len()).into()),
+        GenericVariableData::Map(map) => Ok(VariableValue::new_string(map.key_at(index).unwrap_or_default().to_string())),
+        _ => {
+            log::warn!("each_item: called on invalid type: '{}'.", collection.vtype);
+            Ok(VariableValue::default())
+        }
+    }
+}
+
+/// A document that doesn't parse reads as null and leaves the reason in `ERR()`.
+pub async fn json_parse(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<VariableValue> {
+    let text = vm.eval_expr(&args[0]).await?.as_string();
+    match json::parse(&text) {
+        Ok(value) => {
+            vm.operation_succeeded();
+            Ok(PplJson::new(value).value())
+        }
+        Err(error) => {
+            vm.set_error(error);
+            Ok(PplJson::default().value())
+        }
+    }
+}
+
+/// `JsonStr` as the compiler leaves it only when it has no field names to hand over.
+pub async fn json_str(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<VariableValue> {
+    let value = vm.eval_expr(&args[0]).await?;
+    Ok(VariableValue::new_string(json::to_json(vm, &value, &FieldNames::default()).to_string()))
+}
+
+pub async fn json_text(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<VariableValue> {
+    let value = vm.eval_expr(&args[0]).await?;
+    let names = FieldNames::parse(&vm.eval_expr(&args[1]).await?.as_string());
+    Ok(VariableValue::new_string(json::to_json(vm, &value, &names).to_string()))
+}
+
 /// A request the caller's node waits on, so it needs an end: a host that never
 /// answers would hold the node until the caller gives up. A failed request is
-/// logged and answered empty rather than stopping the PPE, the way the rest of
-/// the runtime treats a call it cannot carry out.
-pub(crate) async fn http_get(url: &str) -> Option<reqwest::Response> {
+/// logged and handed back as an `ERR_KIND_WEB` error rather than stopping the PPE,
+/// the way the rest of the runtime treats a call it cannot carry out.
+///
+/// `hosts` are the ones a sandboxed PPE may reach, a redirect doesn't lead anywhere else.
+pub(crate) async fn http_get(url: &str, hosts: Option<Vec<String>>) -> Result<reqwest::Response, PplError> {
     const WEB_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
-    let client = match reqwest::Client::builder().timeout(WEB_REQUEST_TIMEOUT).build() {
+    let redirect = match hosts {
+        Some(hosts) => reqwest::redirect::Policy::custom(move |attempt| {
+            let allowed = attempt
+                .url()
+                .host_str()
+                .is_some_and(|host| hosts.iter().any(|pattern| host_matches(pattern, host)));
+            if !allowed {
+                attempt.error(format!("redirect to {} is not allowed", attempt.url()))
+            } else if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else {
+                attempt.follow()
+            }
+        }),
+        None => reqwest::redirect::Policy::default(),
+    };
+    let client = match reqwest::Client::builder().timeout(WEB_REQUEST_TIMEOUT).redirect(redirect).build() {
         Ok(client) => client,
         Err(err) => {
             log::error!("WEBREQUEST: can't build the http client: {err}");
-            return None;
+            return Err(PplError::new(ERR_KIND_WEB, ERR_UNAVAILABLE, format!("no http client: {err}")));
         }
     };
     match client.get(url).send().await {
-        Ok(response) => Some(response),
+        Ok(response) => Ok(response),
         Err(err) => {
             log::error!("WEBREQUEST {url}: {err}");
-            None
+            Err(PplError::new(ERR_KIND_WEB, ERR_UNAVAILABLE, format!("request to {url} failed: {err}")))
         }
     }
 }
@@ -10,7 +10,7 @@ use crate::{Res, executable::PPEExpr, icy_board::read_data_with_encoding_detecti
 
 use crate::vm::VMError;
 
-const O_RD: i32 = 0;
+pub(crate) const O_RD: i32 = 0;
 const O_WR: i32 = 1;
 /// Not a PPL access mode; FAPPEND is a statement of its own in `PCBoard`.
 const O_APPEND: i32 = 4;
@@ -0,0 +1,238 @@
+//! `JsonStr` and `JsonRead`: a PPL value to JSON text and back.
+//!
+//! A PPE keeps the types of record fields but not their names, so the compiler
+//! hands them along as a string, `{Name,Address{Street,City}}` for a record.
+//! Without names a record is written as an array of its fields.
+
+use indexmap::IndexMap;
+use serde_json::Number;
+
+use crate::{
+    executable::{GenericVariableData, PPLList, PPLMap, VariableType, VariableValue},
+    icy_board::state::{
+        ppl_error::{ERR_FORMAT, ERR_INVALID, ERR_KIND_JSON, PplError},
+        ppl_json::{PplJson, TEXT, Value},
+    },
+    parser::JSON_ID,
+};
+
+use super::VirtualMachine;
+
+/// The field names of a record, each with the names of its own fields.
+#[derive(Debug, Default)]
+pub struct FieldNames(Vec<(String, FieldNames)>);
+
+impl FieldNames {
+    pub fn parse(text: &str) -> Self {
+        Self::parse_record(&mut text.chars().peekable())
+    }
+
+    fn parse_record(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Self {
+        if chars.next_if_eq(&'{').is_none() {
+            return Self::default();
+        }
+        let mut fields = Vec::new();
+        loop {
+            let mut name = String::new();
+            while let Some(ch) = chars.next_if(|ch| !matches!(*ch, ',' | '{' | '}')) {
+                name.push(ch);
+            }
+            let nested = Self::parse_record(chars);
+            if !name.is_empty() {
+                fields.push((name, nested));
+            }
+            if chars.next() != Some(',') {
+                break;
+            }
+        }
+        Self(fields)
+    }
+
+    /// The names for a record with `count` fields, `None` when they don't belong to it.
+    fn for_record(&self, count: usize) -> Option<&[(String, FieldNames)]> {
+        (self.0.len() == count).then_some(self.0.as_slice())
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, PplError> {
+    serde_json::from_str(text).map_err(|err| PplError::new(ERR_KIND_JSON, ERR_FORMAT, format!("invalid JSON: {err}")))
+}
+
+pub fn to_json(vm: &VirtualMachine<'_>, value: &VariableValue, names: &FieldNames) -> Value {
+    match &value.generic_data {
+        GenericVariableData::UserData(object) if value.vtype == VariableType::UserData(JSON_ID as u8) => object
+            .get_property_value(vm, &TEXT)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text.as_string()).ok())
+            .unwrap_or_default(),
+        GenericVariableData::Record(fields) => match names.for_record(fields.len()) {
+            Some(names) => Value::Object(
+                fields
+                    .iter()
+                    .zip(names)
+                    .map(|(field, (name, nested))| (name.clone(), to_json(vm, field, nested)))
+                    .collect(),
+            ),
+            None => Value::Array(fields.iter().map(|field| to_json(vm, field, &FieldNames::default())).collect()),
+        },
+        GenericVariableData::List(list) => Value::Array(list.items.iter().map(|item| to_json(vm, item, names)).collect()),
+        GenericVariableData::Map(map) => Value::Object(map.entries.iter().map(|(key, item)| (key.clone(), to_json(vm, item, names))).collect()),
+        GenericVariableData::Dim1(items) => Value::Array(items.iter().map(|item| to_json(vm, item, names)).collect()),
+        GenericVariableData::Dim2(rows) => Value::Array(
+            rows.iter()
+                .map(|row| Value::Array(row.iter().map(|item| to_json(vm, item, names)).collect()))
+                .collect(),
+        ),
+        GenericVariableData::Dim3(planes) => Value::Array(
+            planes
+                .iter()
+                .map(|plane| {
+                    Value::Array(
+                        plane
+                            .iter()
+                            .map(|row| Value::Array(row.iter().map(|item| to_json(vm, item, names)).collect()))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        ),
+        _ => scalar_to_json(value),
+    }
+}
+
+fn scalar_to_json(value: &VariableValue) -> Value {
+    match value.vtype {
+        VariableType::Boolean => Value::Bool(value.as_bool()),
+        VariableType::Integer | VariableType::Byte | VariableType::SByte | VariableType::Word | VariableType::SWord => Value::from(value.as_int()),
+        VariableType::Unsigned => Value::from(value.as_unsigned()),
+        VariableType::Float | VariableType::Double => Number::from_f64(value.as_double()).map_or(Value::Null, Value::Number),
+        _ => Value::String(value.as_string()),
+    }
+}
+
+/// Reads `json` into a value shaped like `template`. A key the JSON doesn't have
+/// keeps what the template holds, a key the template doesn't have is skipped and
+/// null reads as empty. Anything else that doesn't fit is an error.
+pub fn from_json(template: &VariableValue, json: &Value, names: &FieldNames) -> Result<VariableValue, PplError> {
+    if template.vtype == VariableType::UserData(JSON_ID as u8) {
+        return Ok(PplJson::new(json.clone()).value());
+    }
+    if json.is_null() {
+        return Ok(empty(template));
+    }
+    let mut value = template.clone();
+    match &mut value.generic_data {
+        GenericVariableData::Record(fields) => match json {
+            Value::Object(entries) => {
+                let Some(names) = names.for_record(fields.len()) else {
+                    return Err(mismatch("a record", json));
+                };
+                for (field, (name, nested)) in fields.iter_mut().zip(names) {
+                    if let Some(item) = lookup(entries, name) {
+                        *field = from_json(field, item, nested)?;
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (field, item) in fields.iter_mut().zip(items) {
+                    *field = from_json(field, item, &FieldNames::default())?;
+                }
+            }
+            _ => return Err(mismatch("a record", json)),
+        },
+        GenericVariableData::List(list) => {
+            let Value::Array(items) = json else {
+                return Err(mismatch("a list", json));
+            };
+            let mut result = PPLList::new((*list.element).clone());
+            for item in items {
+                result.add(from_json(&list.element, item, names)?);
+            }
+            *list = result;
+        }
+        GenericVariableData::Map(map) => {
+            let Value::Object(entries) = json else {
+                return Err(mismatch("a map", json));
+            };
+            let mut result = PPLMap::new((*map.element).clone());
+            for (key, item) in entries {
+                result.insert(key, from_json(&map.element, item, names)?);
+            }
+            *map = result;
+        }
+        GenericVariableData::Dim1(items) => read_array(items, json, |item, json| from_json(item, json, names))?,
+        GenericVariableData::Dim2(rows) => read_array(rows, json, |row, json| {
+            let mut row = row.clone();
+            read_array(&mut row, json, |item, json| from_json(item, json, names))?;
+            Ok(row)
+        })?,
+        GenericVariableData::Dim3(planes) => read_array(planes, json, |plane, json| {
+            let mut plane = plane.clone();
+            read_array(&mut plane, json, |row, json| {
+                let mut row = row.clone();
+                read_array(&mut row, json, |item, json| from_json(item, json, names))?;
+                Ok(row)
+            })?;
+            Ok(plane)
+        })?,
+        _ => return read_scalar(template.vtype, json),
+    }
+    Ok(value)
+}
+
+/// Fills the slots of an array from the front; items past its end are dropped.
+fn read_array<T>(slots: &mut [T], json: &Value, read: impl Fn(&T, &Value) -> Result<T, PplError>) -> Result<(), PplError> {
+    let Value::Array(items) = json else {
+        return Err(mismatch("an array", json));
+    };
+    for (slot, item) in slots.iter_mut().zip(items) {
+        *slot = read(slot, item)?;
+    }
+    Ok(())
+}
+
+fn read_scalar(vtype: VariableType, json: &Value) -> Result<VariableValue, PplError> {
+    let value = match json {
+        Value::Bool(value) => VariableValue::new_bool(*value),
+        Value::Number(number) => match number.as_i64().and_then(|value| i32::try_from(value).ok()) {
+            Some(value) => VariableValue::new_int(value),
+            None => VariableValue::new_double(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(text) => VariableValue::new_string(text.clone()),
+        _ => return Err(mismatch("a single value", json)),
+    };
+    Ok(value.convert_to(vtype))
+}
+
+fn empty(template: &VariableValue) -> VariableValue {
+    let mut value = template.clone();
+    match &mut value.generic_data {
+        GenericVariableData::Record(fields) => fields.iter_mut().for_each(|field| *field = empty(field)),
+        GenericVariableData::List(list) => list.clear(),
+        GenericVariableData::Map(map) => map.clear(),
+        GenericVariableData::Dim1(items) => items.iter_mut().for_each(|item| *item = empty(item)),
+        GenericVariableData::Dim2(rows) => rows.iter_mut().flatten().for_each(|item| *item = empty(item)),
+        GenericVariableData::Dim3(planes) => planes.iter_mut().flatten().flatten().for_each(|item| *item = empty(item)),
+        _ => return template.vtype.create_empty_value(),
+    }
+    value
+}
+
+/// Keys are matched the way PPL matches names, so `"name"` fills `Name`.
+fn lookup<'a>(entries: &'a IndexMap<String, Value>, name: &str) -> Option<&'a Value> {
+    entries
+        .get(name)
+        .or_else(|| entries.iter().find_map(|(key, value)| key.eq_ignore_ascii_case(name).then_some(value)))
+}
+
+fn mismatch(expected: &str, json: &Value) -> PplError {
+    let found = match json {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    };
+    PplError::new(ERR_KIND_JSON, ERR_INVALID, format!("expected {expected}, found {found}"))
+}
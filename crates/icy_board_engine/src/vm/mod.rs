@@ -4,6 +4,7 @@ use crate::ast::Statement;
 use crate::ast::UnaryOp;
 use crate::ast::constant::STACK_LIMIT;
 use crate::datetime::IcbDate;
+use crate::executable::DebugInfo;
 use crate::executable::Executable;
 use crate::executable::GenericVariableData;
 use crate::executable::OnErrorTarget;
@@ -29,6 +30,7 @@ use std::collections::HashMap;
 use std::collections::VecDeque;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use thiserror::Error;
 
 pub mod expressions;
@@ -50,6 +52,22 @@ pub use self::io::*;
 pub mod errors;
 mod tests;
 
+pub mod debugger;
+pub use self::debugger::*;
+
+pub mod sandbox;
+pub use self::sandbox::{Permissions, Sandbox};
+
+pub mod watchdog;
+pub use self::watchdog::Watchdog;
+
+pub mod collections;
+
+pub mod json;
+
+pub mod profiler;
+pub use self::profiler::{ProfileMode, ProfileRun, Profiler};
+
 #[derive(Error, Debug, Clone)]
 pub enum VMError {
     #[error("Internal VM error")]
@@ -58,6 +76,11 @@ pub enum VMError {
     #[error("Label not found (0x{0:X})")]
     LabelNotFound(usize),
 
+    /// A function failed and a TRY outside of it takes the error, so the statement that
+    /// called the function goes no further. Never leaves the VM.
+    #[error("Statement aborted")]
+    StatementAborted,
+
     #[error("Tried to pop from empty value stack.")]
     PushPopStackEmpty,
 
@@ -100,8 +123,19 @@ pub enum VMError {
     #[error("Member {1} of user type {0} expected {2} arguments, got {3}")]
     InvalidMemberArgumentCount(u8, usize, usize, usize),
 
+    #[error("Index {0} is outside the list, which holds {1} items")]
+    CollectionIndexOutOfRange(i32, usize),
+
     #[error("PPE call stack exhausted")]
     StackOverflow,
+
+    #[error("PPE stopped by the watchdog: {0}")]
+    Watchdog(String),
+
+    /// An error with the source line it happened on and the calls that led
+    /// there, innermost first. Only made when the PPE has debug information.
+    #[error("{0} at {1}")]
+    Located(#[source] Arc<dyn std::error::Error + Send + Sync>, String, Vec<String>),
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -129,6 +163,13 @@ pub enum ErrorHandler {
     Procedure(usize),
 }
 
+/// An open `TRY` block: where its CATCH part starts and the call depth it was opened at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TryFrame {
+    pub catch: usize,
+    pub depth: usize,
+}
+
 /// What the graphics codes mean, so `ERR().Message` reads like the other subsystems'.
 fn gfx_error_message(code: i32) -> &'static str {
     match code {
@@ -201,9 +242,19 @@ pub struct VirtualMachine<'a> {
     pub return_addresses: Vec<ReturnAddress>,
     pub call_local_value_stack: Vec<VariableValue>,
     pub write_back_stack: Vec<PPEExpr>,
-    pub user_types: Vec<Vec<VariableType>>,
+    pub user_types: Vec<crate::executable::TypeLayout>,
 
     pub label_table: HashMap<usize, usize>,
+    /// Read from the file `pplc --debug` wrote next to the PPE.
+    pub debug_info: Option<DebugInfo>,
+    /// Set when the PPE runs under `icboard --debug-ppe`.
+    pub debugger: Option<PpeDebugger>,
+    /// What the PPE is allowed to do, `None` when it is not restricted.
+    pub sandbox: Option<Sandbox>,
+    /// Stops a program that runs away or sits without output.
+    pub watchdog: Watchdog,
+    /// Counts (and times) statements while a profiler is set on the board state.
+    pub profile: Option<ProfileRun>,
     pub push_pop_stack: Vec<VariableValue>,
 
     pub stored_screen: Option<TextBuffer>,
@@ -234,6 +285,10 @@ pub struct VirtualMachine<'a> {
     /// The call depth the handler returns to, or `None` for a `GOTO` handler that never does.
     pub handler_depth: Option<usize>,
 
+    /// The `TRY` blocks that are open, innermost last. A failure goes to the innermost
+    /// one before `ON ERROR` gets to see it.
+    pub try_frames: Vec<TryFrame>,
+
     pub dbase: dbase::DbaseState,
 }
 
@@ -488,6 +543,7 @@ impl VirtualMachine<'_> {
                 let right_value = self.eval_expr_sync(right)?;
                 Some(Self::apply_bin_op(*op, left_value, right_value))
             }
+            PPEExpr::Dim(id, _) if self.is_collection_variable(*id) => None,
             PPEExpr::Dim(id, dims) => {
                 let dim_1 = self.eval_expr_sync(&dims[0])?.as_int() as usize;
                 let dim_2 = if dims.len() >= 2 {
@@ -595,7 +651,26 @@ impl VirtualMachine<'_> {
             }
 
             PPEExpr::MemberFunctionCall(base_expr, arguments, id) => {
-                let val = self.eval_expr(base_expr).await?;
+                // A collection is changed where it lives, so its place is resolved instead of
+                // reading a copy. The target is only evaluated once either way.
+                let place = match base_expr.as_ref() {
+                    PPEExpr::Member(target, _) => self.resolve_place(target).await?,
+                    _ => None,
+                };
+                let val = if let Some((var_id, steps)) = &place {
+                    let target = self.place_mut(*var_id, steps)?;
+                    if matches!(target.generic_data, GenericVariableData::List(_) | GenericVariableData::Map(_)) {
+                        let mut args = Vec::new();
+                        for arg in arguments {
+                            args.push(self.eval_expr(arg).await?);
+                        }
+                        let target = self.place_mut(*var_id, steps)?;
+                        return Self::call_collection_member(target, *id, args);
+                    }
+                    target.clone()
+                } else {
+                    self.eval_expr(base_expr).await?
+                };
                 let VariableType::UserData(type_id) = val.get_type() else {
                     log::error!("No user type base for value: {val:?} on expr {base_expr:?}");
                     return Err(VMError::NoUserTypeBase.into());
@@ -639,6 +714,7 @@ impl VirtualMachine<'_> {
                 let right_value = self.eval_expr(right).await?;
                 Ok(Self::apply_bin_op(*op, left_value, right_value))
             }
+            PPEExpr::Dim(id, dims) if dims.len() == 1 && self.is_collection_variable(*id) => self.read_collection_item(*id, &dims[0]).await,
             PPEExpr::Dim(id, dims) => {
                 let dim_1 = self.eval_expr(&dims[0]).await?.as_int() as usize;
                 let dim_2 = if dims.len() >= 2 {
@@ -654,10 +730,16 @@ impl VirtualMachine<'_> {
                 Ok(self.variable_table.get_value(*id).get_array_value(dim_1, dim_2, dim_3))
             }
 
-            PPEExpr::PredefinedFunctionCall(func, arguments) => match run_function(func.opcode, self, arguments).await {
-                Ok(val) => Ok(val),
-                Err(e) => Err(VMError::ErrorInFunctionCall(func.name.to_string(), e.to_string()).into()),
-            },
+            PPEExpr::PredefinedFunctionCall(func, arguments) => {
+                let started = self.profile_clock();
+                let result = run_function(func.opcode, self, arguments).await;
+                self.profile_predefined(func.name, started);
+                match result {
+                    Ok(val) => Ok(val),
+                    Err(e) if matches!(e.downcast_ref::<VMError>(), Some(VMError::StatementAborted)) => Err(e),
+                    Err(e) => Err(VMError::ErrorInFunctionCall(func.name.to_string(), e.to_string()).into()),
+                }
+            }
 
             PPEExpr::FunctionCall(func_id, arguments) => {
                 let proc_offset;
@@ -681,9 +763,15 @@ impl VirtualMachine<'_> {
                 self.prepare_call(locals, parameters, first, arguments, 0).await?;
 
                 self.return_addresses.push(ReturnAddress::func_call(self.cur_ptr, *func_id));
+                if let Some(profile) = &mut self.profile {
+                    profile.call(*func_id);
+                }
                 self.goto(proc_offset)?;
                 self.run().await?;
                 self.fpclear = false;
+                if self.error_pending && !self.try_frames.is_empty() {
+                    return Err(VMError::StatementAborted.into());
+                }
                 Ok(self.variable_table.get_value(return_var_id).clone())
             }
         }
@@ -697,12 +785,75 @@ impl VirtualMachine<'_> {
             self.cur_ptr += 1;
             let c = self.script.statements[p].command.clone();
             // log::info!("{p}: {c}");
-            self.execute_statement(&c).await?;
-            self.check_error_trap()?;
+            if let Some(profile) = &mut self.profile {
+                profile.enter(p, &self.return_addresses);
+            }
+            if self.debugger.is_some() {
+                self.debug_statement(p).await;
+                if !self.is_running {
+                    break;
+                }
+            } else if let Some(reason) = self.watchdog.tick(self.icy_board_state.session.last_io) {
+                log::warn!(
+                    "PPE {} stopped by the watchdog at {}: {reason}",
+                    self.file_name.display(),
+                    self.describe_location(p)
+                );
+                return Err(self.locate_error(p, Box::new(VMError::Watchdog(reason))));
+            }
+            if let Err(err) = self.execute_statement(&c).await
+                && !matches!(err.downcast_ref::<VMError>(), Some(VMError::StatementAborted))
+            {
+                self.debug_error(p, &err.to_string()).await;
+                return Err(self.locate_error(p, err));
+            }
+            self.check_error_trap().await?;
         }
         Ok(())
     }
 
+    /// Where a statement is in the source, or in the PPE when there is no debug information.
+    fn describe_location(&self, ptr: usize) -> String {
+        let offset = self.script.statements.get(ptr).map_or(0, |stmt| stmt.span.start * 2);
+        match &self.debug_info {
+            Some(debug_info) => debug_info.describe(offset),
+            None => format!("offset {offset:05X}"),
+        }
+    }
+
+    /// Adds the source line and the call stack to an error, when the debug
+    /// information knows them. An error on its way out of a call has them already.
+    fn locate_error(&self, ptr: usize, err: Box<dyn std::error::Error + Send + Sync>) -> Box<dyn std::error::Error + Send + Sync> {
+        let Some(debug_info) = &self.debug_info else {
+            return err;
+        };
+        if matches!(err.downcast_ref::<VMError>(), Some(VMError::Located(..))) {
+            return err;
+        }
+        let offset_of = |ptr: usize| self.script.statements.get(ptr).map_or(0, |stmt| stmt.span.start * 2);
+        let call_stack = self
+            .return_addresses
+            .iter()
+            .rev()
+            .map(|address| {
+                let routine = if address.is_gosub() {
+                    "GOSUB".to_string()
+                } else {
+                    debug_info
+                        .name_of(address.get_id())
+                        .map_or_else(|| format!("#{}", address.get_id()), str::to_string)
+                };
+                // The return address is the statement after the call.
+                format!(
+                    "{} called from {}",
+                    routine,
+                    debug_info.describe(offset_of(address.get_ptr().saturating_sub(1)))
+                )
+            })
+            .collect();
+        Box::new(VMError::Located(Arc::from(err), debug_info.describe(offset_of(ptr)), call_stack))
+    }
+
     /// Records what an operation failed with. `ON ERROR` acts on it once the statement is over,
     /// so the operation itself always runs to its end first.
     pub fn set_error(&mut self, error: PplError) {
@@ -758,8 +909,9 @@ impl VirtualMachine<'_> {
         self.publish_io_error();
     }
 
-    /// Hands the program to its `ON ERROR` handler, if it has one and is not already in it.
-    fn check_error_trap(&mut self) -> Res<()> {
+    /// Hands the program to the innermost `TRY`, or else to its `ON ERROR` handler if it
+    /// has one and is not already in it.
+    async fn check_error_trap(&mut self) -> Res<()> {
         self.publish_operation_result();
         if self.in_handler
             && let Some(depth) = self.handler_depth
@@ -772,6 +924,9 @@ impl VirtualMachine<'_> {
         if !self.error_pending {
             return Ok(());
         }
+        if let Some(frame) = self.try_frames.last().copied() {
+            return self.raise_into_try(frame).await;
+        }
         self.error_pending = false;
         if self.in_handler {
             return Ok(());
@@ -801,6 +956,22 @@ impl VirtualMachine<'_> {
         Ok(())
     }
 
+    /// Leaves the calls made since the `TRY` was opened and goes on in its CATCH part.
+    /// A function is left through the `run` that called it, so the error stays pending and
+    /// the statement that called the function is cut short before unwinding goes on.
+    async fn raise_into_try(&mut self, frame: TryFrame) -> Res<()> {
+        while self.return_addresses.len() > frame.depth {
+            self.return_from_call().await?;
+            if self.fpclear {
+                return Ok(());
+            }
+        }
+        self.error_pending = false;
+        self.try_frames.pop();
+        self.goto(frame.catch)?;
+        Ok(())
+    }
+
     /// Calls the `ON ERROR` procedure, handing it the error when it takes one.
     fn call_error_handler(&mut self, proc_id: usize) -> Res<()> {
         let proc_offset;
@@ -833,6 +1004,9 @@ impl VirtualMachine<'_> {
         self.in_handler = true;
         self.handler_depth = Some(depth);
         if self.push_return_address(ReturnAddress::func_call(self.cur_ptr, proc_id))? {
+            if let Some(profile) = &mut self.profile {
+                profile.call(proc_id);
+            }
             self.goto(proc_offset)?;
         } else {
             self.in_handler = false;
@@ -846,7 +1020,7 @@ impl VirtualMachine<'_> {
             PPEExpr::Value(id) => {
                 self.variable_table.set_value(*id, value);
             }
-            PPEExpr::Dim(id, dims) => {
+            PPEExpr::Dim(id, dims) if !(dims.len() == 1 && self.is_collection_variable(*id)) => {
                 let dim_1 = self.eval_expr(&dims[0]).await?.as_int() as usize;
                 let dim_2 = if dims.len() >= 2 {
                     self.eval_expr(&dims[1]).await?.as_int() as usize
@@ -860,52 +1034,13 @@ impl VirtualMachine<'_> {
                 };
                 self.variable_table.get_var_entry_mut(*id).value.set_array_value(dim_1, dim_2, dim_3, value)?;
             }
-            PPEExpr::Member(_, _) => {
-                // The base has to be reached as a place, not as the copy eval_expr hands out,
+            PPEExpr::Dim(_, _) | PPEExpr::Member(_, _) => {
+                // The target has to be reached as a place, not as the copy eval_expr hands out,
                 // so the slot it lives in is resolved first and then walked into.
-                let mut path = Vec::new();
-                let mut base = variable;
-                while let PPEExpr::Member(inner, member_id) = base {
-                    path.push(*member_id);
-                    base = inner.as_ref();
-                }
-                let (id, indices) = match base {
-                    PPEExpr::Value(id) => (*id, None),
-                    PPEExpr::Dim(id, dimensions) => {
-                        let dim_1 = self.eval_expr(&dimensions[0]).await?.as_int() as usize;
-                        let dim_2 = if dimensions.len() >= 2 {
-                            self.eval_expr(&dimensions[1]).await?.as_int() as usize
-                        } else {
-                            0
-                        };
-                        let dim_3 = if dimensions.len() >= 3 {
-                            self.eval_expr(&dimensions[2]).await?.as_int() as usize
-                        } else {
-                            0
-                        };
-                        (*id, Some((dim_1, dim_2, dim_3)))
-                    }
-                    _ => return Err(VMError::InternalVMError.into()),
-                };
-                let root = &mut self.variable_table.get_var_entry_mut(id).value;
-                let mut target = if let Some((dim_1, dim_2, dim_3)) = indices {
-                    root.get_array_value_mut(dim_1, dim_2, dim_3).ok_or(VMError::InternalVMError)?
-                } else {
-                    root
+                let Some((id, steps)) = self.resolve_place(variable).await? else {
+                    return Err(VMError::InternalVMError.into());
                 };
-                for member_id in path.iter().rev() {
-                    let vtype = target.vtype;
-                    let GenericVariableData::Record(fields) = &mut target.generic_data else {
-                        return Err(VMError::NoUserTypeBase.into());
-                    };
-                    let Some(field) = fields.get_mut(*member_id) else {
-                        let VariableType::UserData(type_id) = vtype else {
-                            return Err(VMError::NoUserTypeBase.into());
-                        };
-                        return Err(VMError::InvalidMemberId(type_id, *member_id).into());
-                    };
-                    target = field;
-                }
+                let target = self.place_mut(id, &steps)?;
                 let field_type = target.vtype;
                 *target = value.convert_to(field_type);
             }
@@ -932,79 +1067,7 @@ impl VirtualMachine<'_> {
             }
 
             PPECommand::EndFunc | PPECommand::EndProc | PPECommand::Return => {
-                if let Some(addr) = self.return_addresses.pop() {
-                    self.cur_ptr = addr.get_ptr();
-                    let proc_id = addr.get_id();
-                    if proc_id > 0 {
-                        let locals;
-                        let first;
-                        let parameters;
-                        let return_var_id;
-                        let pass_flags;
-                        let is_func;
-                        unsafe {
-                            let proc = &self.variable_table.get_var_entry(proc_id);
-                            first = (proc.value.data.procedure_value.first_var_id + 1) as usize;
-                            locals = proc.value.data.procedure_value.local_variables as usize;
-                            parameters = proc.value.data.procedure_value.parameters as usize;
-                            if proc.header.variable_type == VariableType::Function {
-                                is_func = true;
-                                return_var_id = proc.value.data.function_value.return_var as usize;
-                                pass_flags = 0;
-                            } else {
-                                is_func = false;
-                                return_var_id = 0;
-                                pass_flags = proc.value.data.procedure_value.pass_flags;
-                            }
-                        }
-
-                        // get write back values
-                        let mut pass_values = Vec::new();
-                        if pass_flags > 0 {
-                            for i in 0..parameters {
-                                if (1 << i) & pass_flags != 0 {
-                                    let id = first + i;
-                                    let val = self.variable_table.get_value(id).clone();
-                                    pass_values.push(val);
-                                }
-                            }
-                        }
-
-                        // write back locals + parameters
-                        for i in (0..(locals + parameters)).rev() {
-                            let id = first + i;
-                            if self.variable_table.get_var_entry(id).header.flags & 0x1 == 0x0 {
-                                let Some(value) = self.call_local_value_stack.pop() else {
-                                    return Err(VMError::PushPopStackEmpty.into());
-                                };
-                                if id != return_var_id {
-                                    self.variable_table.set_value(id, value);
-                                }
-                            }
-                        }
-
-                        if pass_flags > 0 {
-                            for i in (0..parameters).rev() {
-                                if (1 << i) & pass_flags != 0 {
-                                    let Some(val) = pass_values.pop() else {
-                                        return Err(VMError::PassValueStackEmpty.into());
-                                    };
-                                    if let Some(argument_expr) = self.write_back_stack.pop() {
-                                        self.set_variable(&argument_expr, val).await?;
-                                    } else {
-                                        return Err(VMError::WriteBackStackEmpty.into());
-                                    }
-                                }
-                            }
-                        }
-
-                        if is_func {
-                            self.fpclear = true;
-                        }
-                    }
-                } else {
-                    self.is_running = false;
-                }
+                self.return_from_call().await?;
             }
 
             PPECommand::IfNot(expr, label) => {
@@ -1035,12 +1098,18 @@ impl VirtualMachine<'_> {
                 self.prepare_call(locals, parameters, first, arguments, pass_flags).await?;
 
                 if self.push_return_address(ReturnAddress::func_call(self.cur_ptr, *proc_id))? {
+                    if let Some(profile) = &mut self.profile {
+                        profile.call(*proc_id);
+                    }
                     self.goto(proc_offset)?;
                 }
             }
 
             PPECommand::PredefinedCall(proc, arguments) => {
-                run_predefined_statement(proc.opcode, self, arguments).await?;
+                let started = self.profile_clock();
+                let result = run_predefined_statement(proc.opcode, self, arguments).await;
+                self.profile_predefined(proc.name, started);
+                result?;
             }
 
             PPECommand::Goto(label) => {
@@ -1051,6 +1120,12 @@ impl VirtualMachine<'_> {
                     self.goto(*label)?;
                 }
             }
+            PPECommand::TryBegin(label) => {
+                self.try_frames.push(TryFrame {
+                    catch: *label,
+                    depth: self.return_addresses.len(),
+                });
+            }
             PPECommand::OnError(target) => {
                 self.error_handler = match target {
                     OnErrorTarget::Off => ErrorHandler::Off,
@@ -1071,6 +1146,89 @@ impl VirtualMachine<'_> {
         Ok(())
     }
 
+    /// Goes back to where the innermost `GOSUB` or call came from. The `TRY` blocks that
+    /// were opened since end with it.
+    async fn return_from_call(&mut self) -> Res<()> {
+        let Some(addr) = self.return_addresses.pop() else {
+            self.is_running = false;
+            return Ok(());
+        };
+        let depth = self.return_addresses.len();
+        while self.try_frames.last().is_some_and(|frame| frame.depth > depth) {
+            self.try_frames.pop();
+        }
+        self.cur_ptr = addr.get_ptr();
+        let proc_id = addr.get_id();
+        if proc_id > 0 {
+            let locals;
+            let first;
+            let parameters;
+            let return_var_id;
+            let pass_flags;
+            let is_func;
+            unsafe {
+                let proc = &self.variable_table.get_var_entry(proc_id);
+                first = (proc.value.data.procedure_value.first_var_id + 1) as usize;
+                locals = proc.value.data.procedure_value.local_variables as usize;
+                parameters = proc.value.data.procedure_value.parameters as usize;
+                if proc.header.variable_type == VariableType::Function {
+                    is_func = true;
+                    return_var_id = proc.value.data.function_value.return_var as usize;
+                    pass_flags = 0;
+                } else {
+                    is_func = false;
+                    return_var_id = 0;
+                    pass_flags = proc.value.data.procedure_value.pass_flags;
+                }
+            }
+
+            // get write back values
+            let mut pass_values = Vec::new();
+            if pass_flags > 0 {
+                for i in 0..parameters {
+                    if (1 << i) & pass_flags != 0 {
+                        let id = first + i;
+                        let val = self.variable_table.get_value(id).clone();
+                        pass_values.push(val);
+                    }
+                }
+            }
+
+            // write back locals + parameters
+            for i in (0..(locals + parameters)).rev() {
+                let id = first + i;
+                if self.variable_table.get_var_entry(id).header.flags & 0x1 == 0x0 {
+                    let Some(value) = self.call_local_value_stack.pop() else {
+                        return Err(VMError::PushPopStackEmpty.into());
+                    };
+                    if id != return_var_id {
+                        self.variable_table.set_value(id, value);
+                    }
+                }
+            }
+
+            if pass_flags > 0 {
+                for i in (0..parameters).rev() {
+                    if (1 << i) & pass_flags != 0 {
+                        let Some(val) = pass_values.pop() else {
+                            return Err(VMError::PassValueStackEmpty.into());
+                        };
+                        if let Some(argument_expr) = self.write_back_stack.pop() {
+                            self.set_variable(&argument_expr, val).await?;
+                        } else {
+                            return Err(VMError::WriteBackStackEmpty.into());
+                        }
+                    }
+                }
+            }
+
+            if is_func {
+                self.fpclear = true;
+            }
+        }
+        Ok(())
+    }
+
     #[allow(clippy::needless_range_loop)]
     async fn prepare_call(&mut self, locals: usize, parameters: usize, first: usize, arguments: &[PPEExpr], pass_flags: u16) -> Res<()> {
         self.save_call_frame(locals, parameters, first);
@@ -1210,7 +1368,7 @@ impl VirtualMachine<'_> {
             return Ok(true);
         }
         // A handler is given the chance to clean up, which aborting would take away.
-        if self.error_handler != ErrorHandler::Off && !self.in_handler {
+        if (self.error_handler != ErrorHandler::Off && !self.in_handler) || !self.try_frames.is_empty() {
             self.set_error(PplError::new(ERR_KIND_STACK, ERR_STACK, "PPE call stack exhausted"));
             return Ok(false);
         }
@@ -1287,6 +1445,20 @@ pub async fn run<P: AsRef<Path>>(file_name: &P, prg: &Executable, io: &mut dyn P
             let file_name = file_name.as_ref().to_path_buf();
             let reg: UserTypeRegistry = UserTypeRegistry::icy_board_registry();
             log::info!("Run PPE {}", file_name.display());
+            let debug_info = DebugInfo::load_for(&file_name, prg);
+            let debugger = icy_board_state.ppe_debugger.take();
+            let (board_root, watchdog) = {
+                let board = icy_board_state.get_board().await;
+                let limits = &board.config.limits;
+                let watchdog = Watchdog::new(limits.ppe_instruction_budget, limits.ppe_idle_timeout).with_deadline(icy_board_state.ppe_deadline);
+                (board.root_path.clone(), watchdog)
+            };
+            let sandbox = Sandbox::new(&file_name, Permissions::load_for(&file_name), icy_board_state.ppe_grant.take(), &board_root);
+            let outer_sandbox = std::mem::replace(&mut icy_board_state.ppe_sandbox, sandbox.clone());
+            let profile = icy_board_state
+                .ppe_profiler
+                .as_ref()
+                .map(|profiler| ProfileRun::new(profiler.mode, script.statements.len()));
 
             let mut vm = VirtualMachine {
                 file_name,
@@ -1302,6 +1474,11 @@ pub async fn run<P: AsRef<Path>>(file_name: &P, prg: &Executable, io: &mut dyn P
                 variable_table: prg.variable_table.clone(),
                 cur_ptr: 0,
                 label_table,
+                debug_info,
+                debugger,
+                sandbox,
+                watchdog,
+                profile,
                 call_local_value_stack: Vec::new(),
                 write_back_stack: Vec::new(),
                 user_types: prg.user_types.clone(),
@@ -1319,10 +1496,23 @@ pub async fn run<P: AsRef<Path>>(file_name: &P, prg: &Executable, io: &mut dyn P
                 error_handler: ErrorHandler::Off,
                 in_handler: false,
                 handler_depth: None,
+                try_frames: Vec::new(),
                 dbase: dbase::DbaseState::default(),
             };
 
-            vm.run().await?;
+            let result = vm.run().await;
+            vm.icy_board_state.ppe_sandbox = outer_sandbox;
+            if let Some(debugger) = vm.debugger.take() {
+                debugger.exited();
+            }
+            if let Some(mut profile) = vm.profile.take() {
+                profile.pause();
+                if let Some(profiler) = &mut vm.icy_board_state.ppe_profiler {
+                    let offsets: Vec<usize> = vm.script.statements.iter().map(|stmt| stmt.span.start * 2).collect();
+                    profiler.add_run(profile, &vm.file_name, &offsets, vm.debug_info.as_ref());
+                }
+            }
+            result?;
             Ok(!vm.aborted)
         }
         Err(e) => {
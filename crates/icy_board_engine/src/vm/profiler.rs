@@ -0,0 +1,341 @@
+//! Where a PPE spends its time, and which of its lines ran at all.
+//!
+//! Profiling is opt-in: a `Profiler` left in `IcyBoardState::ppe_profiler` makes
+//! every PPE that runs count its statements, and in `ProfileMode::Profile` time
+//! them as well. A run's counts are added up by source line when it ends, so one
+//! profiler can cover a whole session or a whole test suite. The source lines and
+//! routine names come from the debug information `pplc --debug` writes.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{Res, executable::DebugInfo, icy_board::write_atomic};
+
+use super::{ReturnAddress, VirtualMachine};
+
+/// `GAME.PPE` gets its flat profile in `GAME.profile`.
+pub const PROFILE_EXTENSION: &str = "profile";
+/// And the collapsed stacks `flamegraph.pl` and inferno read in `GAME.folded`.
+pub const FOLDED_EXTENSION: &str = "folded";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileMode {
+    /// Counts and times statements, routines and predefined calls.
+    Profile,
+    /// Only counts statements, which is all coverage needs and costs next to nothing.
+    Coverage,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sample {
+    pub count: u64,
+    pub time: Duration,
+}
+
+impl Sample {
+    fn add(&mut self, count: u64, time: Duration) {
+        self.count += count;
+        self.time += time;
+    }
+}
+
+pub struct Profiler {
+    pub mode: ProfileMode,
+    /// Statement executions and their own time, by `file:line`. A PPE without
+    /// debug information is told apart by its offsets.
+    pub statements: HashMap<String, Sample>,
+    /// Calls and the time spent inside, callees included. A PPE's main body goes
+    /// by the PPE's name.
+    pub routines: HashMap<String, Sample>,
+    /// Calls of statements and functions the runtime provides, and their time.
+    pub predefined: HashMap<&'static str, Sample>,
+    /// Own time by call stack, outermost first.
+    pub stacks: HashMap<Vec<String>, Duration>,
+    /// How often each line with code ran, by source file. Lines that never ran
+    /// are here with 0, so only PPEs with debug information show up.
+    pub lines: BTreeMap<PathBuf, BTreeMap<usize, u64>>,
+}
+
+impl Profiler {
+    pub fn new(mode: ProfileMode) -> Self {
+        Self {
+            mode,
+            statements: HashMap::new(),
+            routines: HashMap::new(),
+            predefined: HashMap::new(),
+            stacks: HashMap::new(),
+            lines: BTreeMap::new(),
+        }
+    }
+
+    /// Adds what a run of `ppe_file` collected. `offsets` has the byte offset of
+    /// every statement of the PPE, in order.
+    pub fn add_run(&mut self, run: ProfileRun, ppe_file: &Path, offsets: &[usize], debug_info: Option<&DebugInfo>) {
+        let ppe_name = ppe_file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let ppe_display = ppe_file.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let mut line_hits: BTreeMap<(&Path, usize), u64> = BTreeMap::new();
+        for (ptr, &offset) in offsets.iter().enumerate() {
+            let hits = run.hits.get(ptr).copied().unwrap_or(0);
+            let time = run.times.get(ptr).copied().unwrap_or_default();
+            if let Some((file, line)) = debug_info.and_then(|info| info.location(offset)) {
+                // A line with several statements ran as often as its busiest one.
+                let entry = line_hits.entry((file, line)).or_default();
+                *entry = (*entry).max(hits);
+            }
+            if hits == 0 {
+                continue;
+            }
+            let location = match debug_info {
+                Some(info) => info.describe(offset),
+                None => format!("{ppe_display} {offset:05X}"),
+            };
+            self.statements.entry(location).or_default().add(hits, time);
+        }
+        for ((file, line), hits) in line_hits {
+            *self.lines.entry(file.to_path_buf()).or_default().entry(line).or_default() += hits;
+        }
+
+        let name_of = |id: usize| debug_info.and_then(|info| info.name_of(id)).map_or_else(|| format!("#{id}"), str::to_string);
+        self.routines.entry(ppe_name.clone()).or_default().add(1, run.stacks.values().sum());
+        for (id, calls) in &run.calls {
+            self.routines.entry(name_of(*id)).or_default().add(*calls, Duration::ZERO);
+        }
+        for (stack, time) in &run.stacks {
+            let mut names = vec![ppe_name.clone()];
+            names.extend(stack.iter().map(|id| name_of(*id)));
+            // A recursive routine is only inside itself once.
+            let mut seen: Vec<&String> = Vec::new();
+            for name in &names[1..] {
+                if !seen.contains(&name) {
+                    self.routines.entry(name.clone()).or_default().time += *time;
+                    seen.push(name);
+                }
+            }
+            *self.stacks.entry(names).or_default() += *time;
+        }
+        for (name, sample) in run.predefined {
+            self.predefined.entry(name).or_default().add(sample.count, sample.time);
+        }
+    }
+
+    /// The flat profile: statements by their own time, then routines and
+    /// predefined calls by the time spent inside them.
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+        table(
+            &mut out,
+            "Statements",
+            "self ms",
+            self.statements.iter().map(|(name, sample)| (name.as_str(), sample)),
+        );
+        table(
+            &mut out,
+            "Routines",
+            "total ms",
+            self.routines.iter().map(|(name, sample)| (name.as_str(), sample)),
+        );
+        table(
+            &mut out,
+            "Predefined calls",
+            "total ms",
+            self.predefined.iter().map(|(name, sample)| (*name, sample)),
+        );
+        out
+    }
+
+    /// One line per call stack with its own time in microseconds, the format
+    /// flame graph tools take.
+    pub fn folded(&self) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .filter(|(_, time)| !time.is_zero())
+            .map(|(stack, time)| format!("{} {}", stack.join(";"), time.as_micros()))
+            .collect();
+        lines.sort();
+        lines.iter().map(|line| format!("{line}\n")).collect()
+    }
+
+    /// The line coverage as an lcov tracefile.
+    pub fn lcov(&self) -> String {
+        let mut out = String::new();
+        for (file, lines) in &self.lines {
+            let _ = writeln!(out, "TN:");
+            let _ = writeln!(out, "SF:{}", file.display());
+            for (line, hits) in lines {
+                let _ = writeln!(out, "DA:{line},{hits}");
+            }
+            let _ = writeln!(out, "LF:{}", lines.len());
+            let _ = writeln!(out, "LH:{}", lines.values().filter(|hits| **hits > 0).count());
+            let _ = writeln!(out, "end_of_record");
+        }
+        out
+    }
+
+    /// Writes the flat profile and the collapsed stacks next to the PPE.
+    pub fn save_for(&self, ppe_file: &Path) -> Res<()> {
+        write_atomic(&ppe_file.with_extension(PROFILE_EXTENSION), self.report().as_bytes())?;
+        write_atomic(&ppe_file.with_extension(FOLDED_EXTENSION), self.folded().as_bytes())?;
+        Ok(())
+    }
+}
+
+fn table<'a>(out: &mut String, title: &str, time_heading: &str, rows: impl Iterator<Item = (&'a str, &'a Sample)>) {
+    let mut rows: Vec<_> = rows.collect();
+    if rows.is_empty() {
+        return;
+    }
+    rows.sort_by(|a, b| b.1.time.cmp(&a.1.time).then(b.1.count.cmp(&a.1.count)).then(a.0.cmp(b.0)));
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    let _ = writeln!(out, "{title}");
+    let _ = writeln!(out, "{:>12} {:>12}  name", "count", time_heading);
+    for (name, sample) in rows {
+        let _ = writeln!(out, "{:>12} {:>12.3}  {name}", sample.count, sample.time.as_secs_f64() * 1000.0);
+    }
+}
+
+/// What one PPE collects while it runs, before it is mapped back to the source.
+pub struct ProfileRun {
+    timing: bool,
+    hits: Vec<u64>,
+    times: Vec<Duration>,
+    /// Calls by the variable id of the routine.
+    calls: HashMap<usize, u64>,
+    stacks: HashMap<Vec<usize>, Duration>,
+    predefined: HashMap<&'static str, Sample>,
+    stack: Vec<usize>,
+    /// The statement the clock runs for, and since when.
+    current: Option<usize>,
+    mark: Instant,
+}
+
+impl ProfileRun {
+    pub fn new(mode: ProfileMode, statements: usize) -> Self {
+        let timing = mode == ProfileMode::Profile;
+        Self {
+            timing,
+            hits: vec![0; statements],
+            times: if timing { vec![Duration::ZERO; statements] } else { Vec::new() },
+            calls: HashMap::new(),
+            stacks: HashMap::new(),
+            predefined: HashMap::new(),
+            stack: Vec::new(),
+            current: None,
+            mark: Instant::now(),
+        }
+    }
+
+    /// Notes that the statement at `ptr` starts. The time since the last one
+    /// started is that one's own, whatever it called is charged as it runs.
+    pub(crate) fn enter(&mut self, ptr: usize, return_addresses: &[ReturnAddress]) {
+        if let Some(hits) = self.hits.get_mut(ptr) {
+            *hits += 1;
+        }
+        if !self.timing {
+            return;
+        }
+        self.charge();
+        let frames = return_addresses.iter().filter(|address| !address.is_gosub()).map(ReturnAddress::get_id);
+        if !frames.clone().eq(self.stack.iter().copied()) {
+            self.stack = frames.collect();
+        }
+        self.current = Some(ptr);
+    }
+
+    /// Counts a call of the routine with the variable id `id`.
+    pub(crate) fn call(&mut self, id: usize) {
+        *self.calls.entry(id).or_default() += 1;
+    }
+
+    /// Stops the clock until the next statement, for time that is not this PPE's.
+    pub(crate) fn pause(&mut self) {
+        if self.timing {
+            self.charge();
+            self.current = None;
+        }
+    }
+
+    fn charge(&mut self) {
+        let now = Instant::now();
+        if let Some(ptr) = self.current {
+            let elapsed = now - self.mark;
+            self.times[ptr] += elapsed;
+            match self.stacks.get_mut(&self.stack) {
+                Some(time) => *time += elapsed,
+                None => {
+                    self.stacks.insert(self.stack.clone(), elapsed);
+                }
+            }
+        }
+        self.mark = now;
+    }
+}
+
+impl VirtualMachine<'_> {
+    /// When a predefined call starts, if it is timed.
+    pub(crate) fn profile_clock(&self) -> Option<Instant> {
+        self.profile.as_ref().filter(|run| run.timing).map(|_| Instant::now())
+    }
+
+    pub(crate) fn profile_predefined(&mut self, name: &'static str, started: Option<Instant>) {
+        if let (Some(run), Some(started)) = (&mut self.profile, started) {
+            run.predefined.entry(name).or_default().add(1, started.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debug_info() -> DebugInfo {
+        let mut info = DebugInfo {
+            files: vec![PathBuf::from("main.pps")],
+            ..Default::default()
+        };
+        info.add_line(0, 0, 1);
+        info.add_line(4, 0, 2);
+        info.add_line(8, 0, 4);
+        info
+    }
+
+    #[test]
+    fn coverage_counts_lines_and_keeps_the_ones_that_never_ran() {
+        let mut run = ProfileRun::new(ProfileMode::Coverage, 3);
+        run.enter(0, &[]);
+        run.enter(1, &[]);
+        run.enter(1, &[]);
+
+        let mut profiler = Profiler::new(ProfileMode::Coverage);
+        profiler.add_run(run, Path::new("main.ppe"), &[0, 4, 8], Some(&debug_info()));
+        assert_eq!(profiler.lcov(), "TN:\nSF:main.pps\nDA:1,1\nDA:2,2\nDA:4,0\nLF:3\nLH:2\nend_of_record\n");
+    }
+
+    #[test]
+    fn calls_and_statements_are_counted_by_name() {
+        let mut run = ProfileRun::new(ProfileMode::Profile, 3);
+        for _ in 0..2 {
+            run.enter(0, &[]);
+            run.call(7);
+            run.enter(1, &[ReturnAddress::func_call(1, 7)]);
+            run.enter(2, &[ReturnAddress::func_call(1, 7), ReturnAddress::gosub(2)]);
+        }
+        run.pause();
+
+        let mut profiler = Profiler::new(ProfileMode::Profile);
+        profiler.add_run(run, Path::new("game.ppe"), &[0, 4, 8], None);
+        assert_eq!(profiler.routines["#7"].count, 2);
+        assert_eq!(profiler.routines["game"].count, 1);
+        assert_eq!(profiler.statements["game.ppe 00000"].count, 2);
+        assert_eq!(profiler.statements["game.ppe 00004"].count, 2);
+        assert!(profiler.stacks.contains_key(&vec!["game".to_string(), "#7".to_string()]));
+        assert!(profiler.lines.is_empty());
+    }
+}
@@ -0,0 +1,436 @@
+//! What a PPE may do outside its own screen: run commands, go on the network,
+//! touch files and change the user or message bases.
+//!
+//! A package asks for what it needs in the `[permissions]` of its `ppl.toml`,
+//! `pplc` writes that next to the PPE. The sysop grants permissions on the door
+//! or menu entry that starts the PPE. A PPE runs with what it asked for and was
+//! granted; one that neither asks nor is restricted runs the way PPEs always did.
+//!
+//! A denied statement does nothing and reports `ERR_KIND_SANDBOX`/`ERR_DENIED`.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Res,
+    icy_board::{
+        is_false,
+        state::ppl_error::{ERR_DENIED, ERR_KIND_SANDBOX, PplError},
+    },
+};
+
+use super::VirtualMachine;
+
+pub const PERMISSIONS_EXTENSION: &str = "prm";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Permissions {
+    /// `SHELL`.
+    #[serde(skip_serializing_if = "is_false")]
+    pub shell: bool,
+    /// Hosts `WEBREQUEST` may fetch from and FTN addresses `FDOQADD` may queue
+    /// files for. `*.example.com` stands for the subdomains of `example.com`, `*`
+    /// for every host.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub network: Vec<String>,
+    /// Directories (or files) the PPE may read below.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub read: Vec<PathBuf>,
+    /// Directories (or files) the PPE may create, change and delete below. They can be read, too.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub write: Vec<PathBuf>,
+    /// `PUTUSER`, `ADDUSER` and `DELUSER`.
+    #[serde(skip_serializing_if = "is_false")]
+    pub users: bool,
+    /// Writing, moving and deleting messages.
+    #[serde(skip_serializing_if = "is_false")]
+    pub messages: bool,
+    /// `WATCHDOG FALSE`. Only the sysop's grant counts, asking for it is not enough.
+    #[serde(skip_serializing_if = "is_false")]
+    pub watchdog: bool,
+}
+
+impl Permissions {
+    /// `GAME.PPE` keeps what it asks for in `GAME.PRM`.
+    pub fn path_for(ppe_file: &Path) -> PathBuf {
+        let upper = ppe_file
+            .extension()
+            .is_some_and(|ext| ext.to_string_lossy().chars().all(|ch| ch.is_ascii_uppercase()));
+        ppe_file.with_extension(if upper {
+            PERMISSIONS_EXTENSION.to_ascii_uppercase()
+        } else {
+            PERMISSIONS_EXTENSION.to_string()
+        })
+    }
+
+    /// What a PPE asks for. A file that can't be read asks for nothing rather
+    /// than for everything.
+    pub fn load_for(ppe_file: &Path) -> Option<Self> {
+        let path = Self::path_for(ppe_file);
+        if !path.exists() {
+            return None;
+        }
+        match Self::load(&path) {
+            Ok(permissions) => Some(permissions),
+            Err(err) => {
+                log::warn!("Can't read permissions {}: {}", path.display(), err);
+                Some(Self::default())
+            }
+        }
+    }
+
+    pub fn load(path: &Path) -> Res<Self> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Res<()> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// The same permissions with relative roots taken from `base`.
+    pub fn resolved(&self, base: &Path) -> Self {
+        let resolve = |roots: &[PathBuf]| roots.iter().map(|root| normalize(&base.join(root))).collect();
+        Self {
+            read: resolve(&self.read),
+            write: resolve(&self.write),
+            ..self.clone()
+        }
+    }
+
+    /// What both allow. Roots have to be resolved already.
+    pub fn narrow(&self, other: &Self) -> Self {
+        Self {
+            shell: self.shell && other.shell,
+            network: intersect(&self.network, &other.network, |outer, inner| host_covers(outer, inner)),
+            read: intersect(&self.read, &other.read, |outer, inner| inner.starts_with(outer)),
+            write: intersect(&self.write, &other.write, |outer, inner| inner.starts_with(outer)),
+            users: self.users && other.users,
+            messages: self.messages && other.messages,
+            watchdog: self.watchdog && other.watchdog,
+        }
+    }
+}
+
+/// The entries of either list that the other one covers.
+fn intersect<T: Clone + PartialEq>(a: &[T], b: &[T], covers: impl Fn(&T, &T) -> bool) -> Vec<T> {
+    let mut result: Vec<T> = Vec::new();
+    for (from, other) in [(a, b), (b, a)] {
+        for entry in from {
+            if other.iter().any(|outer| covers(outer, entry)) && !result.contains(entry) {
+                result.push(entry.clone());
+            }
+        }
+    }
+    result
+}
+
+/// Whether `host` is one of the hosts `pattern` in a `network` list stands for.
+pub fn host_matches(pattern: &str, host: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.len() > domain.len() + 1 && host.to_ascii_lowercase().ends_with(&format!(".{}", domain.to_ascii_lowercase())),
+        None => pattern.eq_ignore_ascii_case(host),
+    }
+}
+
+/// Whether every host `inner` allows is allowed by `outer` as well.
+fn host_covers(outer: &str, inner: &str) -> bool {
+    match inner.strip_prefix('*') {
+        Some(subdomains) => {
+            outer == "*"
+                || outer
+                    .strip_prefix('*')
+                    .is_some_and(|outer| subdomains.to_ascii_lowercase().ends_with(&outer.to_ascii_lowercase()))
+        }
+        None => host_matches(outer, inner),
+    }
+}
+
+/// An absolute path without `.` and `..`, with symbolic links followed as far as
+/// the path exists, so neither can lead out of a root.
+fn normalize(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    let mut lexical = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                lexical.pop();
+            }
+            _ => lexical.push(component),
+        }
+    }
+    let mut existing = lexical.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+}
+
+/// The permissions a running PPE has.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sandbox {
+    permissions: Permissions,
+    own_directory: PathBuf,
+    /// The PPE and its permissions file. A PPE that could change either would
+    /// run with whatever it liked the next time.
+    own_files: [PathBuf; 2],
+}
+
+impl Sandbox {
+    /// `declared` is what the PPE asked for, with roots relative to its directory.
+    /// `grant` is what the board allows, with roots relative to the board. `None`
+    /// when there is neither, the PPE is not restricted then.
+    pub fn new(ppe_file: &Path, declared: Option<Permissions>, grant: Option<Permissions>, board_root: &Path) -> Option<Self> {
+        let own_directory = normalize(ppe_file.parent().unwrap_or(Path::new(".")));
+        let own_files = [normalize(ppe_file), normalize(&Permissions::path_for(ppe_file))];
+        let declared = declared.map(|permissions| permissions.resolved(&own_directory));
+        let grant = grant.map(|permissions| permissions.resolved(board_root));
+        let watchdog = grant.as_ref().is_some_and(|grant| grant.watchdog);
+        let mut permissions = match (declared, grant) {
+            (Some(declared), Some(grant)) => declared.narrow(&grant),
+            (Some(permissions), None) | (None, Some(permissions)) => permissions,
+            (None, None) => return None,
+        };
+        permissions.watchdog = watchdog;
+        Some(Self {
+            permissions,
+            own_directory,
+            own_files,
+        })
+    }
+
+    /// What a PPE this one calls is granted, so it can't do more than its caller.
+    pub fn grant_for_callee(&self) -> Permissions {
+        let mut permissions = self.permissions.clone();
+        permissions.write.push(self.own_directory.clone());
+        permissions
+    }
+
+    pub fn allows_shell(&self) -> bool {
+        self.permissions.shell
+    }
+
+    pub fn allows_url(&self, url: &str) -> bool {
+        reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| self.allows_host(host)))
+            .unwrap_or(false)
+    }
+
+    pub fn allows_host(&self, host: &str) -> bool {
+        self.permissions.network.iter().any(|pattern| host_matches(pattern, host))
+    }
+
+    pub fn hosts(&self) -> &[String] {
+        &self.permissions.network
+    }
+
+    /// A PPE can always read and write the directory it is in.
+    pub fn allows_read(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        path.starts_with(&self.own_directory) || self.permissions.read.iter().chain(&self.permissions.write).any(|root| path.starts_with(root))
+    }
+
+    /// Except for the PPE itself and any permissions file, the one of a PPE it
+    /// calls included. Neither they nor a directory holding them can be written.
+    pub fn allows_write(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        let is_permissions = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case(PERMISSIONS_EXTENSION));
+        if is_permissions || self.own_files.iter().any(|file| file.starts_with(&path)) {
+            return false;
+        }
+        path.starts_with(&self.own_directory) || self.permissions.write.iter().any(|root| path.starts_with(root))
+    }
+
+    pub fn allows_users(&self) -> bool {
+        self.permissions.users
+    }
+
+    pub fn allows_messages(&self) -> bool {
+        self.permissions.messages
+    }
+
+    pub fn allows_watchdog(&self) -> bool {
+        self.permissions.watchdog
+    }
+}
+
+impl VirtualMachine<'_> {
+    /// Reports a statement the sandbox stops. The statement does nothing.
+    fn deny(&mut self, what: String) -> bool {
+        log::warn!("PPE {} is not allowed to {what}", self.file_name.display());
+        self.set_error(PplError::new(ERR_KIND_SANDBOX, ERR_DENIED, format!("not allowed to {what}")));
+        false
+    }
+
+    pub fn permit_shell(&mut self, command: &str) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_shell() => self.deny(format!("run '{command}'")),
+            _ => true,
+        }
+    }
+
+    pub fn permit_url(&mut self, url: &str) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_url(url) => self.deny(format!("fetch {url}")),
+            _ => true,
+        }
+    }
+
+    /// Queuing `file` for the FTN node `address` reads the file and sends it out.
+    pub fn permit_send(&mut self, address: &str, file: &Path) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_host(address) => self.deny(format!("send files to {address}")),
+            _ => self.permit_read(file),
+        }
+    }
+
+    pub fn permit_read(&mut self, path: &Path) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_read(path) => self.deny(format!("read {}", path.display())),
+            _ => true,
+        }
+    }
+
+    pub fn permit_write(&mut self, path: &Path) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_write(path) => self.deny(format!("write {}", path.display())),
+            _ => true,
+        }
+    }
+
+    pub fn permit_user_write(&mut self) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_users() => self.deny("change the user base".to_string()),
+            _ => true,
+        }
+    }
+
+    pub fn permit_message_write(&mut self) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if !sandbox.allows_messages() => self.deny("change the message base".to_string()),
+            _ => true,
+        }
+    }
+
+    /// Unlike everything else, a PPE nobody restricted may not do this either.
+    pub fn permit_watchdog_off(&mut self) -> bool {
+        match &self.sandbox {
+            Some(sandbox) if sandbox.allows_watchdog() => true,
+            _ => self.deny("turn the watchdog off".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(declared: Option<Permissions>, grant: Option<Permissions>) -> Sandbox {
+        Sandbox::new(Path::new("/bbs/ppe/game/game.ppe"), declared, grant, Path::new("/bbs")).unwrap()
+    }
+
+    #[test]
+    fn test_nothing_declared_or_granted_is_not_restricted() {
+        assert!(Sandbox::new(Path::new("/bbs/ppe/game.ppe"), None, None, Path::new("/bbs")).is_none());
+    }
+
+    #[test]
+    fn test_the_grant_narrows_what_is_declared() {
+        let declared = Permissions {
+            shell: true,
+            network: vec!["api.example.com".to_string(), "evil.org".to_string()],
+            write: vec![PathBuf::from("scores"), PathBuf::from("/bbs/users")],
+            ..Default::default()
+        };
+        let grant = Permissions {
+            network: vec!["*.example.com".to_string()],
+            write: vec![PathBuf::from("ppe")],
+            ..Default::default()
+        };
+        let sandbox = sandbox(Some(declared), Some(grant));
+        assert!(!sandbox.allows_shell());
+        assert!(sandbox.allows_url("https://api.example.com/score?x=1"));
+        assert!(!sandbox.allows_url("https://evil.org/"));
+        assert!(!sandbox.allows_url("https://example.com/"));
+        assert!(sandbox.allows_write(Path::new("/bbs/ppe/game/scores/top.dat")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/users/users.toml")));
+    }
+
+    #[test]
+    fn test_only_the_grant_allows_the_watchdog_off() {
+        let asks = Permissions {
+            watchdog: true,
+            ..Default::default()
+        };
+        assert!(!sandbox(Some(asks.clone()), None).allows_watchdog());
+        assert!(sandbox(None, Some(asks.clone())).allows_watchdog());
+        assert!(!sandbox(Some(asks), Some(Permissions::default())).allows_watchdog());
+    }
+
+    #[test]
+    fn test_paths_cant_leave_a_root() {
+        let sandbox = sandbox(
+            Some(Permissions {
+                read: vec![PathBuf::from("/bbs/art")],
+                ..Default::default()
+            }),
+            None,
+        );
+        assert!(sandbox.allows_read(Path::new("/bbs/art/logo.ans")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/art/logo.ans")));
+        assert!(!sandbox.allows_read(Path::new("/bbs/art/../main/users.toml")));
+        assert!(!sandbox.allows_read(Path::new("/bbs/artwork/logo.ans")));
+        assert!(sandbox.allows_write(Path::new("/bbs/ppe/game/game.cfg")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/ppe/game/../other/other.cfg")));
+    }
+
+    #[test]
+    fn test_a_ppe_cant_change_what_it_may_do() {
+        let sandbox = sandbox(
+            Some(Permissions {
+                write: vec![PathBuf::from("/bbs/ppe")],
+                ..Default::default()
+            }),
+            None,
+        );
+        assert!(sandbox.allows_read(Path::new("/bbs/ppe/game/game.prm")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/ppe/game/game.prm")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/ppe/game/game.ppe")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/ppe/game")));
+        assert!(!sandbox.allows_write(Path::new("/bbs/ppe/other/OTHER.PRM")));
+        assert!(sandbox.allows_write(Path::new("/bbs/ppe/other/other.ppe")));
+    }
+
+    #[test]
+    fn test_wildcard_hosts() {
+        assert!(host_matches("*.example.com", "a.b.Example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "badexample.com"));
+        assert!(host_covers("*.com", "*.example.com"));
+        assert!(!host_covers("*.example.com", "*.com"));
+        assert!(host_covers("*", "*.com"));
+    }
+}
@@ -36,6 +36,7 @@ pub async fn run_predefined_statement(opcode: OpCode, arg: &mut VirtualMachine<'
         | OpCode::BEGIN
         | OpCode::FEND
         | OpCode::OnError
+        | OpCode::TryBegin
         | OpCode::STATIC => predefined_procedures::invalid(arg, arguments).await,
         OpCode::ErrClr => predefined_procedures::errclr(arg, arguments).await,
         OpCode::PRINT => predefined_procedures::print(arg, arguments).await,
@@ -266,5 +267,9 @@ pub async fn run_predefined_statement(opcode: OpCode, arg: &mut VirtualMachine<'
         OpCode::SetPaletteColor | OpCode::SetPaletteColorRgb => predefined_procedures::set_palette_color(arg, arguments).await,
         OpCode::ResetPaletteColor => predefined_procedures::reset_palette_color(arg, arguments).await,
         OpCode::ResetPalette => predefined_procedures::reset_palette(arg, arguments).await,
+        OpCode::Watchdog => predefined_procedures::watchdog(arg, arguments).await,
+        OpCode::JsonRead => predefined_procedures::json_read(arg, arguments).await,
+        OpCode::JsonReadFields => predefined_procedures::json_read_fields(arg, arguments).await,
+        OpCode::TryEnd => predefined_procedures::try_end(arg, arguments).await,
     }
 }
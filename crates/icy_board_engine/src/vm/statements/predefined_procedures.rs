@@ -22,7 +22,7 @@ use crate::{
         user_base::ConferenceFlags,
         user_inf::{BankUserInf, QwkConfigUserInf},
     },
-    vm::{MAX_FILE_CHANNELS, dbase, get_file_channel},
+    vm::{MAX_FILE_CHANNELS, O_RD, dbase, get_file_channel},
 };
 use bstr::BString;
 use chrono::{DateTime, Utc};
@@ -33,8 +33,13 @@ use jamjam::jam::{JamMessage, JamMessageBase, attributes as jam_attributes, msg_
 
 use crate::{
     icy_board::icb_text::IceText,
-    icy_board::state::ppl_error::{ERR_FORMAT, ERR_INVALID, ERR_IO, ERR_KIND_FONT, ERR_KIND_GFX, ERR_KIND_SOUND, ERR_LIMIT, ERR_UNAVAILABLE, PplError},
-    vm::{TerminalTarget, VMError, VirtualMachine},
+    icy_board::state::ppl_error::{
+        ERR_FORMAT, ERR_INVALID, ERR_IO, ERR_KIND_FILE, ERR_KIND_FONT, ERR_KIND_GFX, ERR_KIND_SOUND, ERR_KIND_WEB, ERR_LIMIT, ERR_UNAVAILABLE, PplError,
+    },
+    vm::{
+        TerminalTarget, VMError, VirtualMachine,
+        json::{self, FieldNames},
+    },
 };
 
 use super::super::errors::IcyError;
@@ -153,6 +158,9 @@ pub async fn confunflag(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()
 pub async fn dispfile(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file_name = &vm.eval_expr(&args[0]).await?.as_string();
     let file_name = vm.resolve_file(&file_name).await;
+    if !vm.permit_read(&file_name) {
+        return Ok(());
+    }
     vm.icy_board_state.display_file(&file_name).await?;
     Ok(())
 }
@@ -162,8 +170,11 @@ pub async fn fcreate(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file = vm.eval_expr(&args[1]).await?.as_string();
     let am = vm.eval_expr(&args[2]).await?.as_int();
     let sm = vm.eval_expr(&args[3]).await?.as_int();
-    let file = vm.resolve_file(&file).await.to_string_lossy().to_string();
-    vm.io.fcreate(channel, &file, am, sm);
+    let path = vm.resolve_file(&file).await;
+    if !vm.permit_write(&path) {
+        return Ok(());
+    }
+    vm.io.fcreate(channel, &path.to_string_lossy(), am, sm);
     Ok(())
 }
 
@@ -172,8 +183,12 @@ pub async fn fopen(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file = vm.eval_expr(&args[1]).await?.as_string();
     let am = vm.eval_expr(&args[2]).await?.as_int();
     let sm = vm.eval_expr(&args[3]).await?.as_int();
-    let file = vm.resolve_file(&file).await.to_string_lossy().to_string();
-    vm.io.fopen(channel, &file, am, sm)?;
+    let path = vm.resolve_file(&file).await;
+    let permitted = if am & 0x03 == O_RD { vm.permit_read(&path) } else { vm.permit_write(&path) };
+    if !permitted {
+        return Ok(());
+    }
+    vm.io.fopen(channel, &path.to_string_lossy(), am, sm)?;
     Ok(())
 }
 
@@ -182,8 +197,11 @@ pub async fn fappend(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file = vm.eval_expr(&args[1]).await?.as_string();
     let am = vm.eval_expr(&args[2]).await?.as_int();
     let sm = vm.eval_expr(&args[3]).await?.as_int();
-    let file = vm.resolve_file(&file).await.to_string_lossy().to_string();
-    vm.io.fappend(channel, &file);
+    let path = vm.resolve_file(&file).await;
+    if !vm.permit_write(&path) {
+        return Ok(());
+    }
+    vm.io.fappend(channel, &path.to_string_lossy());
     Ok(())
 }
 
@@ -301,6 +319,9 @@ pub async fn getuser(vm: &mut VirtualMachine<'_>, _args: &[PPEExpr]) -> Res<()>
 /// # Errors
 /// Errors if the variable is not found.
 pub async fn putuser(vm: &mut VirtualMachine<'_>, _args: &[PPEExpr]) -> Res<()> {
+    if !vm.permit_user_write() {
+        return Ok(());
+    }
     if let Some(mut user) = vm.icy_board_state.session.current_user.take() {
         vm.put_user_variables(&mut user).await;
         vm.icy_board_state.session.current_user = Some(user);
@@ -314,7 +335,11 @@ pub async fn defcolor(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
 
 pub async fn delete(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file = &vm.eval_expr(&args[0]).await?.as_string();
-    let file = vm.resolve_file(&file).await.to_string_lossy().to_string();
+    let path = vm.resolve_file(&file).await;
+    if !vm.permit_write(&path) {
+        return Ok(());
+    }
+    let file = path.to_string_lossy().to_string();
     if let Err(err) = vm.io.delete(&file)
         && err.kind() != std::io::ErrorKind::NotFound
     {
@@ -324,6 +349,9 @@ pub async fn delete(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 }
 
 pub async fn deluser(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
+    if !vm.permit_user_write() {
+        return Ok(());
+    }
     vm.user.flags.delete_flag = true;
     Ok(())
 }
@@ -637,6 +665,10 @@ pub async fn shell(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let mut cmd = vm.eval_expr(&args[2]).await?.as_string();
     let arguments = vm.eval_expr(&args[3]).await?.as_string();
     let mut exit_code = 1;
+    if !vm.permit_shell(cmd.trim()) {
+        vm.set_variable(&args[1], VariableValue::new_int(exit_code)).await?;
+        return Ok(());
+    }
 
     let mut command_args = Vec::new();
     if use_shell {
@@ -713,7 +745,18 @@ pub async fn pop(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 
 pub async fn call(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file_name = vm.eval_expr(&args[0]).await?.as_string();
-    vm.icy_board_state.run_ppe(&file_name, None).await?;
+    if !vm.permit_read(std::path::Path::new(&file_name)) {
+        return Ok(());
+    }
+    // A restricted PPE can't get around its sandbox by calling another one.
+    vm.icy_board_state.ppe_grant = vm.sandbox.as_ref().map(|sandbox| sandbox.grant_for_callee());
+    // The called PPE keeps its own profile, its time is not this statement's.
+    if let Some(profile) = &mut vm.profile {
+        profile.pause();
+    }
+    let result = vm.icy_board_state.run_ppe(&file_name, None).await;
+    vm.icy_board_state.ppe_grant = None;
+    result?;
     Ok(())
 }
 
@@ -765,6 +808,9 @@ pub async fn kbdstring(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
 pub async fn kbdfile(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file_name = vm.eval_expr(&args[0]).await?.as_string();
     let file_name = vm.resolve_file(&file_name).await;
+    if !vm.permit_read(&file_name) {
+        return Ok(());
+    }
     let contents = fs::read_to_string(file_name)?;
     vm.icy_board_state.stuff_keyboard_buffer_from(&contents, KeySource::StuffedFile)?;
 
@@ -870,7 +916,11 @@ pub async fn optext(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 pub async fn dispstr(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let value = vm.eval_expr(&args[0]).await?.as_string();
     // PCBoard looks for a file spec only after stripping the padding, but prints the string as it came.
-    if PPECall::try_parse_line(value.trim_end()).is_some() {
+    if let Some(call) = PPECall::try_parse_line(value.trim_end()) {
+        let file = vm.icy_board_state.resolve_call_file(&call).await;
+        if !vm.permit_read(&file) {
+            return Ok(());
+        }
         vm.icy_board_state.display_line(value.trim_end()).await
     } else {
         vm.icy_board_state.print(TerminalTarget::Both, &value).await
@@ -1141,6 +1191,9 @@ pub async fn load_font(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
     }
 
     let path = vm.resolve_file(&file_name).await;
+    if !vm.permit_read(&path) {
+        return Ok(());
+    }
     let data = match fs::metadata(&path).and_then(|meta| {
         if meta.len() > MAX_FONT_FILE_BYTES {
             Err(std::io::Error::other("file too large"))
@@ -1241,6 +1294,10 @@ async fn user_sys_dir(vm: &mut VirtualMachine<'_>) -> std::path::PathBuf {
 }
 pub async fn newpwd(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let new_pwd = vm.eval_expr(&args[0]).await?.as_string();
+    if !vm.permit_user_write() {
+        vm.set_variable(&args[1], VariableValue::new_bool(false)).await?;
+        return Ok(());
+    }
     let was_changed = vm.icy_board_state.change_password(&new_pwd).await?;
     vm.set_variable(&args[1], VariableValue::new_bool(was_changed)).await?;
     Ok(())
@@ -1252,7 +1309,7 @@ pub async fn newpwd(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 pub async fn opencap(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file_name = vm.eval_expr(&args[0]).await?.as_string();
     let path = vm.resolve_file(&file_name).await;
-    let opened = vm.icy_board_state.open_capture(&path);
+    let opened = vm.permit_write(&path) && vm.icy_board_state.open_capture(&path);
     vm.set_variable(&args[1], VariableValue::new_bool(opened)).await?;
     Ok(())
 }
@@ -1274,6 +1331,9 @@ pub async fn message(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let echo = vm.eval_expr(&args[7]).await?.as_bool();
     let file = vm.eval_expr(&args[8]).await?.as_string();
     let file = vm.resolve_file(&file).await;
+    if !vm.permit_message_write() || !vm.permit_read(&file) {
+        return Ok(());
+    }
     // PCBoard's entermessagefromfile() returns quietly when the body file is missing.
     if !file.exists() {
         log::error!("PPE function 'message': message text file not found {}", file.display());
@@ -1403,10 +1463,12 @@ pub async fn mprintln(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
 pub async fn rename(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let old = &vm.eval_expr(&args[0]).await?.as_string();
     let new = &vm.eval_expr(&args[1]).await?.as_string();
-    let old = vm.resolve_file(&old).await.to_string_lossy().to_string();
-    let new = vm.resolve_file(&new).await.to_string_lossy().to_string();
-
-    if let Err(err) = vm.io.rename(&old, &new) {
+    let old = vm.resolve_file(&old).await;
+    let new = vm.resolve_file(&new).await;
+    if !vm.permit_write(&old) || !vm.permit_write(&new) {
+        return Ok(());
+    }
+    if let Err(err) = vm.io.rename(&old.to_string_lossy(), &new.to_string_lossy()) {
         log::error!("Error renaming file: {err}");
     }
     Ok(())
@@ -1637,16 +1699,24 @@ pub async fn redim(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 pub async fn append(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let channel = get_file_channel(vm, args).await?;
     let file = vm.eval_expr(&args[1]).await?.as_string();
-    let file = vm.resolve_file(&file).await.to_string_lossy().to_string();
-    vm.io.fappend(channel, &file);
+    let path = vm.resolve_file(&file).await;
+    if !vm.permit_write(&path) {
+        return Ok(());
+    }
+    vm.io.fappend(channel, &path.to_string_lossy());
     Ok(())
 }
 
 pub async fn copy(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let old = &vm.eval_expr(&args[0]).await?.as_string();
     let new = &vm.eval_expr(&args[1]).await?.as_string();
-    if let Err(err) = vm.io.copy(old, new) {
-        log::error!("Error renaming file: {err}");
+    let old = vm.resolve_file(&old).await;
+    let new = vm.resolve_file(&new).await;
+    if !vm.permit_read(&old) || !vm.permit_write(&new) {
+        return Ok(());
+    }
+    if let Err(err) = vm.io.copy(&old.to_string_lossy(), &new.to_string_lossy()) {
+        log::error!("Error copying file: {err}");
     }
     Ok(())
 }
@@ -2035,6 +2105,51 @@ pub async fn stackabort(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()
     vm.abort_on_stack_error = vm.eval_expr(&args[0]).await?.as_bool();
     Ok(())
 }
+/// `WATCHDOG armed`
+///
+/// Lets a program that runs long on purpose, like a game server loop, switch off
+/// the instruction budget and the idle timeout, and switch them on again.
+pub async fn watchdog(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
+    let armed = vm.eval_expr(&args[0]).await?.as_bool();
+    if !armed && vm.watchdog.armed {
+        if !vm.permit_watchdog_off() {
+            return Ok(());
+        }
+        log::info!("PPE {} turned the watchdog off", vm.file_name.display());
+    }
+    vm.watchdog.armed = armed;
+    Ok(())
+}
+/// `JsonRead` as the compiler leaves it only when it has no field names to hand over.
+pub async fn json_read(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
+    read_json(vm, &args[0], &args[1], &FieldNames::default()).await
+}
+
+pub async fn json_read_fields(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
+    let names = FieldNames::parse(&vm.eval_expr(&args[2]).await?.as_string());
+    read_json(vm, &args[0], &args[1], &names).await
+}
+
+/// The text is read into a copy, so a variable it doesn't fit keeps what it held.
+async fn read_json(vm: &mut VirtualMachine<'_>, text: &PPEExpr, target: &PPEExpr, names: &FieldNames) -> Res<()> {
+    let text = vm.eval_expr(text).await?.as_string();
+    let template = vm.eval_expr(target).await?;
+    match json::parse(&text).and_then(|value| json::from_json(&template, &value, names)) {
+        Ok(value) => {
+            vm.set_variable(target, value).await?;
+            vm.operation_succeeded();
+        }
+        Err(error) => vm.set_error(error),
+    }
+    Ok(())
+}
+
+/// `<end try>`, where a `TRY` block got through or is left by a `BREAK` or `CONTINUE`.
+pub async fn try_end(vm: &mut VirtualMachine<'_>, _args: &[PPEExpr]) -> Res<()> {
+    vm.try_frames.pop();
+    Ok(())
+}
+
 pub async fn dcreate(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     dbase::ops::dcreate(vm, args).await?;
     Ok(())
@@ -2425,6 +2540,9 @@ pub async fn msgtofile(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
     msg.push_str(&body);
 
     let file_name = vm.resolve_file(&file_name).await;
+    if !vm.permit_write(&file_name) {
+        return Ok(());
+    }
     if let Err(err) = append_utf8_with_bom(&file_name, &msg) {
         log::error!("MSGTOFILE can't write message text {msg_number} in area {area}: {err}");
     }
@@ -2581,6 +2699,9 @@ pub async fn adduser(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     // ADDUSER(STRING username, BOOLEAN keepAltVars)
     let username = vm.eval_expr(&args[0]).await?.as_string();
     let keep_alt_vars = vm.eval_expr(&args[1]).await?.as_bool();
+    if !vm.permit_user_write() {
+        return Ok(());
+    }
 
     let trimmed = username.trim();
     if trimmed.is_empty() {
@@ -2654,6 +2775,9 @@ pub async fn adduser(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 pub async fn killmsg(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let (conference, area) = vm.eval_expr(&args[0]).await?.as_msg_id();
     let number = vm.eval_expr(&args[1]).await?.as_int() as u32;
+    if !vm.permit_message_write() {
+        return Ok(());
+    }
 
     let Some(msg_base) = vm.message_base_path(conference, area).await else {
         log::error!("KILLMSG: no message base {conference}:{area}");
@@ -2687,6 +2811,9 @@ pub async fn chdir(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 pub async fn mkdir(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let dir = vm.eval_expr(&args[0]).await?.as_string();
     let path = vm.resolve_file(&dir).await;
+    if !vm.permit_write(&path) {
+        return Ok(());
+    }
     if let Err(err) = fs::create_dir_all(&path) {
         log::error!("MKDIR  {} error : {}", path.display(), err);
     }
@@ -2695,6 +2822,9 @@ pub async fn mkdir(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
 pub async fn rmdir(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let dir = vm.eval_expr(&args[0]).await?.as_string();
     let path = vm.resolve_file(&dir).await;
+    if !vm.permit_write(&path) {
+        return Ok(());
+    }
     if path.is_dir() {
         if let Err(err) = fs::remove_dir(&path) {
             log::error!("RMDIR {} error: {}", path.display(), err);
@@ -2722,6 +2852,9 @@ pub async fn fdoqmod(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     let file = vm.eval_expr(&args[2]).await?.as_string();
     let _flags = vm.eval_expr(&args[3]).await?.as_int();
     let file = vm.resolve_file(&file).await;
+    if !vm.permit_send(&address, &file) {
+        return Ok(());
+    }
     let ftn = vm.icy_board_state.get_board().await.ftn.clone();
 
     if let Err(err) = queue::remove(&ftn, record.max(0) as usize) {
@@ -2740,6 +2873,9 @@ pub async fn fdoqadd(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
     // turn, so what is queued waits for the next call either way.
     let _flags = vm.eval_expr(&args[2]).await?.as_int();
     let file = vm.resolve_file(&file).await;
+    if !vm.permit_send(&address, &file) {
+        return Ok(());
+    }
     let ftn = vm.icy_board_state.get_board().await.ftn.clone();
 
     if let Err(err) = queue::add(&ftn, &address, &file) {
@@ -2778,6 +2914,9 @@ pub async fn move_msg(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
     let (to_conf, to_area) = vm.eval_expr(&args[0]).await?.as_msg_id();
     let number = vm.eval_expr(&args[1]).await?.as_int() as u32;
     let moving = vm.eval_expr(&args[2]).await?.as_bool();
+    if !vm.permit_message_write() {
+        return Ok(());
+    }
 
     let from_conf = vm.icy_board_state.session.current_conference_number as i32;
     let from_area = vm.icy_board_state.session.current_message_area as i32;
@@ -2899,19 +3038,31 @@ pub async fn web_request(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<(
     // writes, so a DOS path lands where the rest of them do instead of wherever
     // the daemon happens to have been started.
     let path = vm.resolve_file(&file).await;
-    let Some(response) = http_get(&url).await else {
+    if !vm.permit_url(&url) || !vm.permit_write(&path) {
         return Ok(());
+    }
+    let hosts = vm.sandbox.as_ref().map(|sandbox| sandbox.hosts().to_vec());
+    let response = match http_get(&url, hosts).await {
+        Ok(response) => response,
+        Err(error) => {
+            vm.set_error(error);
+            return Ok(());
+        }
     };
     let bytes = match response.bytes().await {
         Ok(bytes) => bytes,
         Err(err) => {
             log::error!("WEBREQUEST {url}: {err}");
+            vm.set_error(PplError::new(ERR_KIND_WEB, ERR_IO, format!("reading {url} failed: {err}")));
             return Ok(());
         }
     };
     if let Err(err) = fs::write(&path, &bytes) {
         log::error!("WEBREQUEST can't write {}: {err}", path.display());
+        vm.set_error(PplError::new(ERR_KIND_FILE, ERR_IO, format!("can't write {}: {err}", path.display())));
+        return Ok(());
     }
+    vm.operation_succeeded();
     Ok(())
 }
 
@@ -2987,6 +3138,9 @@ async fn sndcache_store(vm: &mut VirtualMachine<'_>, file_name: &str) -> Res<Res
     use sha2::{Digest, Sha256};
 
     let path = vm.resolve_file(&file_name).await;
+    if !vm.permit_read(&path) {
+        return Ok(Err(vm.last_error.clone()));
+    }
 
     let data = match fs::metadata(&path).and_then(|meta| {
         if meta.len() > MAX_SOUND_FILE_BYTES {
@@ -3309,7 +3463,7 @@ async fn gfx_pin_handle(vm: &mut VirtualMachine<'_>, handle: i32, enabled: bool)
         vm.icy_board_state.gfx_error = 5;
         return Ok(false);
     };
-    let Some(encoded) = gfx_jxl_encode(&surface) else {
+    let Some(encoded) = surface.encode_jxl() else {
         vm.icy_board_state.gfx_error = 4;
         return Ok(false);
     };
@@ -3534,6 +3688,9 @@ pub async fn gfxcreate(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()>
 /// Reads an image file into a surface, reporting through `ERR()` on the way.
 pub(crate) async fn gfx_decode_image(vm: &mut VirtualMachine<'_>, file_name: &str) -> Option<crate::icy_board::state::ppl_graphics::GfxSurface> {
     let path = vm.resolve_file(&file_name).await;
+    if !vm.permit_read(&path) {
+        return None;
+    }
     let bytes = match fs::metadata(&path).and_then(|metadata| {
         if metadata.len() > MAX_GFX_SOURCE_BYTES {
             Err(std::io::Error::other("file too large"))
@@ -3713,37 +3870,13 @@ pub async fn gfxblitrect(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<(
 }
 
 fn gfx_sixel_output(surface: &crate::icy_board::state::ppl_graphics::GfxSurface) -> Option<Vec<u8>> {
-    let options = icy_sixel::EncodeOptions::default();
-    let encoded = match icy_sixel::sixel_encode(&surface.pixels, surface.width, surface.height, &options) {
-        Ok(encoded) => encoded,
-        Err(err) => {
-            log::warn!("GFXPRESENT sixel encode failed: {err}");
-            return None;
-        }
-    };
+    let encoded = surface.encode_sixel()?;
     let mut output = Vec::with_capacity(encoded.len() + 3);
     output.extend_from_slice(b"\x1b[H");
     output.extend_from_slice(encoded.as_bytes());
     Some(output)
 }
 
-/// A composed image is opaque, so the alpha channel is dropped rather than encoded.
-fn gfx_jxl_encode(surface: &crate::icy_board::state::ppl_graphics::GfxSurface) -> Option<Vec<u8>> {
-    use zune_core::{bit_depth::BitDepth, colorspace::ColorSpace, options::EncoderOptions};
-    use zune_jpegxl::JxlSimpleEncoder;
-
-    let rgb: Vec<u8> = surface.pixels.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
-    let options = EncoderOptions::new(surface.width, surface.height, ColorSpace::RGB, BitDepth::Eight);
-    let mut encoded = Vec::new();
-    match JxlSimpleEncoder::new(&rgb, options).encode(&mut encoded) {
-        Ok(_) => Some(encoded),
-        Err(err) => {
-            log::warn!("GFXPRESENT JPEG XL encode failed: {err:?}");
-            None
-        }
-    }
-}
-
 /// The client side transforms the image APC applies while drawing: an exact
 /// destination size (`DW`/`DH`) and the mirror flags (`FX`/`FY`). Scaling on the
 /// client keeps the encoded frame at source size, so only the small image travels.
@@ -3817,7 +3950,7 @@ async fn gfx_present_surface(
     }
     let cacheable = surface.cacheable;
 
-    let Some(encoded) = gfx_jxl_encode(&surface) else {
+    let Some(encoded) = surface.encode_jxl() else {
         vm.icy_board_state.gfx_error = 4;
         return Ok(());
     };
@@ -4090,7 +4223,7 @@ pub async fn gfxpin(vm: &mut VirtualMachine<'_>, args: &[PPEExpr]) -> Res<()> {
         vm.icy_board_state.gfx_error = 5;
         return Ok(());
     };
-    let Some(encoded) = gfx_jxl_encode(&surface) else {
+    let Some(encoded) = surface.encode_jxl() else {
         vm.icy_board_state.gfx_error = 4;
         return Ok(());
     };
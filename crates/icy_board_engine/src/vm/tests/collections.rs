@@ -0,0 +1,187 @@
+use super::{compile_errors, compile_errors_with_runtime, run_ppl};
+
+#[test]
+fn a_list_grows_as_items_are_added() {
+    assert_eq!(
+        "3|a,c,b|",
+        run_ppl(
+            r#"
+LIST OF STRING names
+names.Add("a")
+names.Add("b")
+names.Insert(1, "c")
+PRINT Len(names), "|", names(0), ",", names(1), ",", names(2), "|"
+"#
+        )
+    );
+}
+
+#[test]
+fn a_list_item_can_be_assigned_and_removed() {
+    assert_eq!(
+        "2|9|1|0",
+        run_ppl(
+            r#"
+LIST OF INTEGER numbers
+numbers.Add(1)
+numbers.Add(2)
+numbers.Add(3)
+numbers(1) = 9
+numbers.Remove(0)
+PRINT Len(numbers), "|", numbers(0), "|", numbers.Contains(3), "|", numbers.Contains(1)
+"#
+        )
+    );
+}
+
+#[test]
+fn a_map_reads_a_missing_key_as_empty() {
+    assert_eq!(
+        "[5][0]2|1",
+        run_ppl(
+            r#"
+MAP OF STRING TO INTEGER visits
+visits("sysop") = 5
+visits.Insert("guest", 1)
+PRINT "[", visits("sysop"), "][", visits("nobody"), "]", Len(visits), "|", visits.Contains("guest")
+"#
+        )
+    );
+}
+
+#[test]
+fn for_each_walks_a_list_in_order() {
+    assert_eq!(
+        "a b c ",
+        run_ppl(
+            r#"
+LIST OF STRING names
+STRING name
+names.Add("a")
+names.Add("b")
+names.Add("c")
+FOR EACH name IN names
+    PRINT name, " "
+NEXT
+"#
+        )
+    );
+}
+
+#[test]
+fn for_each_walks_the_keys_of_a_map_in_the_order_they_were_set() {
+    assert_eq!(
+        "b=2 a=1 ",
+        run_ppl(
+            r#"
+MAP OF STRING TO INTEGER scores
+STRING key
+scores("b") = 2
+scores("a") = 1
+scores("b") = 2
+FOR EACH key IN scores
+    PRINT key, "=", scores(key), " "
+NEXT
+"#
+        )
+    );
+}
+
+#[test]
+fn break_and_continue_work_in_for_each() {
+    assert_eq!(
+        "1 3 ",
+        run_ppl(
+            r#"
+LIST OF INTEGER numbers
+INTEGER n
+numbers.Add(1)
+numbers.Add(2)
+numbers.Add(3)
+numbers.Add(4)
+FOR EACH n IN numbers
+    IF (n = 2) CONTINUE
+    IF (n = 4) BREAK
+    PRINT n, " "
+NEXT
+"#
+        )
+    );
+}
+
+#[test]
+fn for_each_walks_the_items_that_were_there_when_it_started() {
+    assert_eq!(
+        "1 2 |4",
+        run_ppl(
+            r#"
+LIST OF INTEGER numbers
+INTEGER n
+numbers.Add(1)
+numbers.Add(2)
+FOR EACH n IN numbers
+    numbers.Add(n * 10)
+    PRINT n, " "
+NEXT
+PRINT "|", Len(numbers)
+"#
+        )
+    );
+}
+
+#[test]
+fn a_list_of_records_is_changed_in_place() {
+    assert_eq!(
+        "Bob 42",
+        run_ppl(
+            r#"
+TYPE Person
+    STRING Name
+    INTEGER Age
+ENDTYPE
+LIST OF Person people
+Person p
+p.Name = "Bob"
+people.Add(p)
+people(0).Age = 42
+PRINT people(0).Name, " ", people(0).Age
+"#
+        )
+    );
+}
+
+#[test]
+fn a_copy_does_not_follow_the_original() {
+    assert_eq!(
+        "1 0",
+        run_ppl(
+            r#"
+LIST OF INTEGER a
+LIST OF INTEGER b
+a.Add(1)
+b = a
+a.Clear()
+PRINT Len(b), " ", Len(a)
+"#
+        )
+    );
+}
+
+#[test]
+fn a_collection_needs_runtime_403() {
+    let errors = compile_errors_with_runtime("LIST OF INTEGER numbers\nnumbers.Add(1)", 402);
+    assert!(errors.iter().any(|error| error.contains("403")), "{errors:?}");
+    assert!(compile_errors_with_runtime("LIST OF INTEGER numbers\nnumbers.Add(1)", 403).is_empty());
+}
+
+#[test]
+fn a_map_has_no_add() {
+    let errors = compile_errors("MAP OF STRING TO INTEGER m\nm.Add(1)");
+    assert!(errors.iter().any(|error| error.contains("Add")), "{errors:?}");
+}
+
+#[test]
+fn for_each_needs_a_collection() {
+    let errors = compile_errors("INTEGER i\nINTEGER n\nFOR EACH n IN i\nNEXT");
+    assert!(errors.iter().any(|error| error.contains("FOR EACH")), "{errors:?}");
+}
@@ -0,0 +1,155 @@
+use super::{compile_errors_with_runtime, run_ppl};
+
+const PERSON: &str = r#"
+TYPE Address
+    STRING City
+ENDTYPE
+TYPE Person
+    STRING Name
+    INTEGER Age
+    Address Home
+ENDTYPE
+"#;
+
+#[test]
+fn a_record_is_written_under_its_field_names() {
+    assert_eq!(
+        r#"{"Name":"Bob","Age":42,"Home":{"City":"Oslo"}}"#,
+        run_ppl(&format!(
+            r#"{PERSON}
+Person p
+p.Name = "Bob"
+p.Age = 42
+p.Home.City = "Oslo"
+PRINT JsonStr(p)
+"#
+        ))
+    );
+}
+
+#[test]
+fn a_record_reads_back_what_it_wrote() {
+    assert_eq!(
+        "Bob 42 Oslo",
+        run_ppl(&format!(
+            r#"{PERSON}
+Person p
+Person q
+p.Name = "Bob"
+p.Age = 42
+p.Home.City = "Oslo"
+JSONREAD JsonStr(p), q
+PRINT q.Name, " ", q.Age, " ", q.Home.City
+"#
+        ))
+    );
+}
+
+#[test]
+fn a_missing_key_keeps_the_field_and_keys_ignore_case() {
+    assert_eq!(
+        "Ann 7",
+        run_ppl(&format!(
+            r#"{PERSON}
+Person p
+p.Age = 7
+JSONREAD "{{""name"": ""Ann"", ""unknown"": true}}", p
+PRINT p.Name, " ", p.Age
+"#
+        ))
+    );
+}
+
+#[test]
+fn a_list_of_records_is_an_array_of_objects() {
+    assert_eq!(
+        r#"2 b|[{"Name":"a","Age":1,"Home":{"City":""}},{"Name":"b","Age":3,"Home":{"City":""}}]"#,
+        run_ppl(&format!(
+            r#"{PERSON}
+LIST OF Person people
+JSONREAD "[{{""Name"": ""a"", ""Age"": 1}}, {{""Name"": ""b"", ""Age"": 2}}]", people
+people(1).Age = 3
+PRINT Len(people), " ", people(1).Name, "|", JsonStr(people)
+"#
+        ))
+    );
+}
+
+#[test]
+fn a_map_keeps_the_order_of_its_keys() {
+    assert_eq!(
+        r#"5|{"b":2,"a":5}"#,
+        run_ppl(
+            r#"
+MAP OF STRING TO INTEGER scores
+JSONREAD "{""b"": 2, ""a"": 1}", scores
+scores("a") = 5
+PRINT scores("a"), "|", JsonStr(scores)
+"#
+        )
+    );
+}
+
+#[test]
+fn a_parsed_document_can_be_walked() {
+    assert_eq!(
+        "x|2|1|1|a",
+        run_ppl(
+            r#"
+JSON doc = JsonParse("{""a"": [1, {""b"": ""x""}]}")
+PRINT doc.Get("A").Item(1).Get("b").AsString, "|", doc.Get("a").Count, "|"
+PRINT (doc.Get("missing").Item(3).Kind = JSON_NULL), "|", doc.Has("a"), "|", doc.Key(0)
+"#
+        )
+    );
+}
+
+#[test]
+fn a_parsed_document_keeps_the_order_of_its_keys() {
+    assert_eq!(
+        r#"z|{"z":1,"a":{"y":true,"b":null}}"#,
+        run_ppl(
+            r#"
+JSON doc = JsonParse("{""z"": 1, ""a"": {""y"": true, ""b"": null}}")
+PRINT doc.Key(0), "|", doc.Text
+"#
+        )
+    );
+}
+
+#[test]
+fn text_that_is_not_json_leaves_the_variable_alone() {
+    assert_eq!(
+        "1|1|1|keep",
+        run_ppl(
+            r#"
+STRING s = "keep"
+JSON doc = JsonParse("{oops")
+PRINT (ERR().Kind = ERR_KIND_JSON), "|", (ERR().Code = ERR_FORMAT), "|", (doc.Kind = JSON_NULL), "|"
+JSONREAD "{", s
+PRINT s
+"#
+        )
+    );
+}
+
+#[test]
+fn text_of_the_wrong_shape_is_invalid() {
+    assert_eq!(
+        "1|0",
+        run_ppl(
+            r#"
+MAP OF STRING TO INTEGER scores
+JSONREAD "[1, 2]", scores
+PRINT (ERR().Code = ERR_INVALID), "|", Len(scores)
+"#
+        )
+    );
+}
+
+#[test]
+fn json_needs_runtime_403() {
+    let errors = compile_errors_with_runtime("PRINT JsonStr(1)", 402);
+    assert!(errors.iter().any(|error| error.contains("403")), "{errors:?}");
+    assert!(compile_errors_with_runtime("PRINT JsonStr(1)", 403).is_empty());
+}
@@ -7,6 +7,7 @@
 
 #![cfg(test)]
 
+mod collections;
 mod contacts;
 mod control_flow;
 mod dbase;
@@ -19,9 +20,11 @@ mod fonts;
 mod forward_calls;
 mod graphics;
 mod hyperlinks;
+mod json;
 mod margins;
 mod masks;
 mod message_base;
+mod modules;
 mod mouse;
 mod nested_records;
 mod palette;
@@ -29,10 +32,13 @@ mod ppe_paths;
 mod record_literals;
 mod records;
 mod routine_parameters;
+mod sandbox;
 mod scalars;
 mod sound;
 mod terminal_info;
 mod tpa;
+mod try_catch;
+mod watchdog;
 
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -48,7 +54,7 @@ use crate::icy_board::user_base::User;
 use crate::icy_board::{IcyBoard, message_area::AreaList, message_area::MessageArea};
 use crate::parser::{Encoding, ErrorReporter, UserTypeRegistry, parse_ast};
 use crate::vm::io::DiskIO;
-use crate::vm::run;
+use crate::vm::{Permissions, run};
 
 /// The diagnostics a snippet produces, for the cases where not compiling is the point.
 pub fn compile_errors(source: &str) -> Vec<String> {
@@ -121,11 +127,16 @@ pub fn run_ppl_in_ppe_dir(source: &str, ppe_dir: &str, files: &[(&str, &[u8])])
 }
 
 pub fn run_ppl_with_input(source: &str, input: &[u8]) -> String {
-    run_ppl_collecting(source, |_| {}, &[], None, input).1
+    run_ppl_collecting(source, |_| {}, &[], None, input, None).1
 }
 
 pub fn run_ppl_with_files_and_input(source: &str, files: &[(&str, &[u8])], input: &[u8]) -> String {
-    run_ppl_collecting(source, |_| {}, files, None, input).1
+    run_ppl_collecting(source, |_| {}, files, None, input, None).1
+}
+
+/// The same, started from a door or menu entry that grants `grant`.
+pub fn run_ppl_granted<P: Fn(&mut IcyBoard)>(source: &str, grant: Permissions, init_fn: P) -> String {
+    run_ppl_collecting(source, init_fn, &[], None, &[], Some(grant)).1
 }
 
 fn run_ppl_seeded<P: Fn(&mut IcyBoard)>(source: &str, init_fn: P, files: &[(&str, &[u8])]) -> String {
@@ -133,16 +144,23 @@ fn run_ppl_seeded<P: Fn(&mut IcyBoard)>(source: &str, init_fn: P, files: &[(&str
 }
 
 fn run_ppl_seeded_in<P: Fn(&mut IcyBoard)>(source: &str, init_fn: P, files: &[(&str, &[u8])], ppe_dir: Option<&str>) -> String {
-    run_ppl_collecting(source, init_fn, files, ppe_dir, &[]).1
+    run_ppl_collecting(source, init_fn, files, ppe_dir, &[], None).1
 }
 
 /// True when the program ran to its end rather than giving up with STOP, which is what
 /// decides whether a script questionnaire keeps the answers it collected.
 pub fn ppl_keeps_script_answers(source: &str) -> bool {
-    run_ppl_collecting(source, |_| {}, &[], None, &[]).0
+    run_ppl_collecting(source, |_| {}, &[], None, &[], None).0
 }
 
-fn run_ppl_collecting<P: Fn(&mut IcyBoard)>(source: &str, init_fn: P, files: &[(&str, &[u8])], ppe_dir: Option<&str>, input: &[u8]) -> (bool, String) {
+fn run_ppl_collecting<P: Fn(&mut IcyBoard)>(
+    source: &str,
+    init_fn: P,
+    files: &[(&str, &[u8])],
+    ppe_dir: Option<&str>,
+    input: &[u8],
+    grant: Option<Permissions>,
+) -> (bool, String) {
     let executable = compile(source);
     let work_dir = scratch_dir("run");
     for (name, bytes) in files {
@@ -188,6 +206,7 @@ fn run_ppl_collecting<P: Fn(&mut IcyBoard)>(source: &str, init_fn: P, files: &[(
         let sysop = state.get_board().await.users[0].clone();
         state.session.current_user = Some(sysop);
         state.session.cur_user_id = 0;
+        state.ppe_grant = grant;
 
         // The peer end is drained on its own thread, so a snippet that writes
         // more than one channel buffer cannot deadlock against us.
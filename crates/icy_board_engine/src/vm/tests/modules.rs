@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use super::{compile_errors, run_ppl};
+use crate::compiler::PPECompiler;
+use crate::compiler::workspace::Workspace;
+use crate::parser::{Encoding, ErrorReporter, UserTypeRegistry, parse_ast};
+
+/// The diagnostics of a package made of several files, the first one holds the program.
+fn compile_errors_in_files(files: &[(&str, &str)]) -> Vec<String> {
+    let errors = Arc::new(Mutex::new(ErrorReporter::default()));
+    let reg = UserTypeRegistry::icy_board_registry();
+    let mut workspace = Workspace::default();
+    workspace.hard_coded_files = Some(files.iter().map(|(name, _)| PathBuf::from(name)).collect());
+
+    let asts: Vec<_> = files
+        .iter()
+        .map(|(name, source)| parse_ast(PathBuf::from(name), errors.clone(), source, &reg, Encoding::Utf8, &workspace))
+        .collect();
+    let mut compiler = PPECompiler::new(&workspace, reg, errors.clone());
+    compiler.compile(&asts.iter().collect::<Vec<_>>());
+
+    let reporter = errors.lock().unwrap();
+    reporter.errors.iter().map(|e| e.error.to_string()).collect()
+}
+
+#[test]
+fn a_public_routine_is_called_through_its_module() {
+    let output = run_ppl(
+        r#"
+        IMPORT Greeter
+        Greeter.Hello("Sysop")
+        PrintLn Greeter.Shout("hi")
+
+        MODULE Greeter
+            PUBLIC PROCEDURE Hello(STRING name)
+                PrintLn "Hello ", name
+            ENDPROC
+
+            PUBLIC FUNCTION Shout(STRING text) STRING
+                RETURN Upper(text) + "!"
+            ENDFUNC
+        ENDMODULE
+        "#,
+    );
+
+    assert_eq!(output, "Hello Sysop\nHI!\n");
+}
+
+#[test]
+fn a_module_variable_keeps_its_value_between_calls() {
+    let output = run_ppl(
+        r#"
+        IMPORT Counter
+        Counter.Bump()
+        Counter.Bump()
+        PrintLn Counter.count
+        Counter.count = 10
+        Counter.Bump()
+        PrintLn Counter.count
+
+        MODULE Counter
+            PUBLIC INTEGER count
+            PUBLIC PROCEDURE Bump()
+                count = count + 1
+            ENDPROC
+        ENDMODULE
+        "#,
+    );
+
+    assert_eq!(output, "2\n11\n");
+}
+
+#[test]
+fn members_reach_each_other_and_parameters_hide_them() {
+    let output = run_ppl(
+        r#"
+        IMPORT Math
+        PrintLn Math.Scaled(5)
+
+        MODULE Math
+            INTEGER scale
+
+            PROCEDURE Setup()
+                scale = 3
+            ENDPROC
+
+            PUBLIC FUNCTION Scaled(INTEGER scale) INTEGER
+                Setup()
+                RETURN scale * Math.Current()
+            ENDFUNC
+
+            PUBLIC FUNCTION Current() INTEGER
+                RETURN scale
+            ENDFUNC
+        ENDMODULE
+        "#,
+    );
+
+    assert_eq!(output, "15\n");
+}
+
+#[test]
+fn a_member_does_not_clash_with_a_program_name() {
+    let output = run_ppl(
+        r#"
+        IMPORT Tally
+        INTEGER count = 7
+        Tally.Add()
+        Tally.Add()
+        PrintLn count, " ", Tally.count
+
+        MODULE Tally
+            PUBLIC INTEGER count
+            PUBLIC PROCEDURE Add()
+                count = count + 1
+            ENDPROC
+        ENDMODULE
+        "#,
+    );
+
+    assert_eq!(output, "7 2\n");
+}
+
+#[test]
+fn a_record_field_keeps_its_name_inside_a_module() {
+    let output = run_ppl(
+        r#"
+        TYPE Point
+            INTEGER x
+        ENDTYPE
+        IMPORT Geo
+        PrintLn Geo.Shift(4)
+
+        MODULE Geo
+            INTEGER x
+            PUBLIC FUNCTION Shift(INTEGER by) INTEGER
+                Point p
+                x = 10
+                p.x = by
+                RETURN p.x + x
+            ENDFUNC
+        ENDMODULE
+        "#,
+    );
+
+    assert_eq!(output, "14\n");
+}
+
+#[test]
+fn a_private_member_is_out_of_reach() {
+    let errors = compile_errors("IMPORT Vault\nPrintLn Vault.secret\nMODULE Vault\n  STRING secret\nENDMODULE");
+
+    assert!(errors.iter().any(|error| error == "secret is private to module Vault"), "{errors:?}");
+}
+
+#[test]
+fn a_module_has_to_be_imported() {
+    let errors = compile_errors("PrintLn Tools.Twice(2)\nMODULE Tools\n  PUBLIC FUNCTION Twice(INTEGER n) INTEGER\n    RETURN n * 2\n  ENDFUNC\nENDMODULE");
+
+    assert!(errors.iter().any(|error| error == "Module Tools is used without 'IMPORT Tools'"), "{errors:?}");
+}
+
+#[test]
+fn an_unknown_module_or_member_is_an_error() {
+    let errors = compile_errors("IMPORT Nowhere\nIMPORT Tools\nPrintLn Tools.Thrice(2)\nMODULE Tools\n  PUBLIC INTEGER n\nENDMODULE");
+
+    assert!(errors.iter().any(|error| error == "Module Nowhere not found"), "{errors:?}");
+    assert!(errors.iter().any(|error| error == "Module Tools has no member named Thrice"), "{errors:?}");
+}
+
+#[test]
+fn only_declarations_go_in_a_module() {
+    let errors = compile_errors("MODULE M\n  PrintLn \"x\"\nENDMODULE");
+    assert!(
+        errors
+            .iter()
+            .any(|error| error == "Only FUNCTION, PROCEDURE, DECLARE, CONST and variables can go in a MODULE"),
+        "{errors:?}"
+    );
+
+    let errors = compile_errors("MODULE M\n  INTEGER n = 1\nENDMODULE");
+    assert!(
+        errors
+            .iter()
+            .any(|error| error == "Module variable 'n' can't have an initializer, set it from a routine of the module"),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn module_is_a_name_before_404() {
+    let errors = compile_errors(";$LANGVERSION 400\nINTEGER module, import, public\nmodule = 1\nPrintLn module, import, public");
+
+    assert!(errors.is_empty(), "{errors:?}");
+}
+
+#[test]
+fn a_module_is_shared_between_files() {
+    let library = "MODULE Strings\n  PUBLIC FUNCTION Twice(STRING s) STRING\n    RETURN s + s\n  ENDFUNC\n  STRING scratch\nENDMODULE";
+
+    let errors = compile_errors_in_files(&[("main.pps", "IMPORT Strings\nPrintLn Strings.Twice(\"ab\")"), ("strings.pps", library)]);
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let errors = compile_errors_in_files(&[("main.pps", "IMPORT Strings\nStrings.scratch = \"x\""), ("strings.pps", library)]);
+    assert!(errors.iter().any(|error| error == "scratch is private to module Strings"), "{errors:?}");
+
+    let errors = compile_errors_in_files(&[("main.pps", "PrintLn 1\nMODULE Strings\nENDMODULE"), ("strings.pps", library)]);
+    assert!(errors.iter().any(|error| error == "Module Strings is already defined"), "{errors:?}");
+}
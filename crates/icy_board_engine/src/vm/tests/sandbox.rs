@@ -0,0 +1,128 @@
+//! A PPE that asks for permissions gets those and nothing else.
+//!
+//! The snippets run as `ppe/game/test.ppe` with a `test.prm` beside it, the file
+//! `pplc` writes from the package's `[permissions]`.
+
+use super::run_ppl_in_ppe_dir;
+
+#[test]
+fn a_file_outside_the_granted_roots_is_not_written() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        FCREATE 1, "scores.dat", O_WR, S_DN
+        PRINTLN "kind=", ERR().Kind = ERR_KIND_SANDBOX, " code=", ERR().Code = ERR_DENIED
+        PRINTLN "exist=", EXIST("scores.dat")
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"read = [\"../../art\"]\n")],
+    );
+    assert_eq!(output, "kind=1 code=1\nexist=0\n");
+}
+
+#[test]
+fn the_ppe_directory_and_granted_roots_can_be_used() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        FCREATE 1, "ppe/game/scores.dat", O_WR, S_DN
+        FPUTLN 1, "100"
+        FCLOSE 1
+        PRINTLN "ok=", ERR().OK, " exist=", EXIST("ppe/game/scores.dat")
+        PRINTLN READLINE("art/logo.txt", 1)
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"read = [\"../../art\"]\n"), ("art/logo.txt", b"LOGO\n")],
+    );
+    assert_eq!(output, "ok=1 exist=1\nLOGO\n");
+}
+
+#[test]
+fn shell_needs_its_permission() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        INTEGER rc
+        SHELL TRUE, rc, "echo", "hello"
+        PRINTLN "rc=", rc, " denied=", ERR().Code = ERR_DENIED
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"users = true\n")],
+    );
+    assert_eq!(output, "rc=1 denied=1\n");
+}
+
+#[test]
+fn a_ppe_without_permissions_is_not_restricted() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        FCREATE 1, "scores.dat", O_WR, S_DN
+        FCLOSE 1
+        PRINTLN "ok=", ERR().OK, " exist=", EXIST("scores.dat")
+    "#,
+        "ppe/game",
+        &[],
+    );
+    assert_eq!(output, "ok=1 exist=1\n");
+}
+
+#[test]
+fn a_ppe_started_by_a_displayed_line_gets_no_more_than_its_caller() {
+    let other = super::compile(
+        r#"
+        FCREATE 1, "scores.dat", O_WR, S_DN
+        PRINTLN "callee denied=", ERR().Code = ERR_DENIED
+    "#,
+    )
+    .to_buffer()
+    .unwrap();
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        DISPSTR "!ppe/game/other.ppe"
+        PRINTLN "exist=", EXIST("scores.dat")
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"read = [\"../../art\"]\n"), ("ppe/game/other.ppe", other.as_slice())],
+    );
+    assert_eq!(output, "callee denied=1\nexist=0\n");
+}
+
+#[test]
+fn a_displayed_line_does_not_show_a_file_outside_the_roots() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        DISPSTR "%secret.txt"
+        PRINTLN "kind=", ERR().Kind = ERR_KIND_SANDBOX, " code=", ERR().Code = ERR_DENIED
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"read = [\"../../art\"]\n"), ("secret.txt", b"SECRET\n")],
+    );
+    assert_eq!(output, "kind=1 code=1\n");
+}
+
+#[test]
+fn the_password_is_part_of_the_user_base() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        BOOLEAN changed
+        NEWPWD "SECRET", changed
+        PRINTLN "changed=", changed, " denied=", ERR().Code = ERR_DENIED
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"shell = true\n")],
+    );
+    assert_eq!(output, "changed=0 denied=1\n");
+}
+
+#[test]
+fn a_ppe_cant_rewrite_or_delete_its_permissions() {
+    let output = run_ppl_in_ppe_dir(
+        r#"
+        FCREATE 1, "ppe/game/test.prm", O_WR, S_DN
+        PRINTLN "write denied=", ERR().Code = ERR_DENIED
+        DELETE "ppe/game/test.prm"
+        PRINTLN "delete denied=", ERR().Code = ERR_DENIED
+        PRINTLN READLINE("ppe/game/test.prm", 1)
+    "#,
+        "ppe/game",
+        &[("ppe/game/test.prm", b"read = [\"../../art\"]\n")],
+    );
+    assert_eq!(output, "write denied=1\ndelete denied=1\nread = [\"../../art\"]\n");
+}
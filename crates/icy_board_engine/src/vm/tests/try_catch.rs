@@ -0,0 +1,311 @@
+use super::{compile_errors_with_runtime, run_ppl};
+
+#[test]
+fn try_requires_runtime_404() {
+    let source = ";$LANGVERSION 404\nTRY\n  PrintLn \"x\"\nCATCH\nEND TRY";
+
+    let errors = compile_errors_with_runtime(source, 403);
+    assert!(errors.iter().any(|error| error == "TRY needs runtime 404"), "{errors:?}");
+    assert!(compile_errors_with_runtime(source, 404).is_empty());
+}
+
+#[test]
+fn try_is_a_name_before_404() {
+    let errors = compile_errors_with_runtime(";$LANGVERSION 400\nINTEGER try = 1\nPrintLn try", 404);
+
+    assert!(errors.is_empty(), "{errors:?}");
+}
+
+#[test]
+fn a_failure_skips_to_the_catch_part() {
+    let output = run_ppl(
+        r#"
+        ERROR e
+        TRY
+            PrintLn "before"
+            LoadFont 43, "nope.fnt"
+            PrintLn "not reached"
+        CATCH e
+            PrintLn "caught kind=", e.Kind, " code=", e.Code
+        END TRY
+        PrintLn "after"
+        "#,
+    );
+
+    assert_eq!(output, "before\ncaught kind=5 code=3\nafter\n");
+}
+
+#[test]
+fn a_block_that_gets_through_skips_the_catch_part() {
+    let output = run_ppl(
+        r#"
+        ERROR e
+        TRY
+            PrintLn "body"
+        CATCH e
+            PrintLn "not reached"
+        ENDTRY
+        PrintLn "after"
+        "#,
+    );
+
+    assert_eq!(output, "body\nafter\n");
+}
+
+#[test]
+fn the_catch_variable_is_optional() {
+    let output = run_ppl(
+        r#"
+        TRY
+            FOPEN 1, "nope.txt", O_RD, S_DN
+        CATCH
+            PrintLn "caught ", ERR().Kind = ERR_KIND_FILE, " ", ERR().Code = ERR_IO
+        END TRY
+        "#,
+    );
+
+    assert_eq!(output, "caught 1 1\n");
+}
+
+#[test]
+fn the_catch_variable_must_be_an_error() {
+    let errors = compile_errors_with_runtime("INTEGER e\nTRY\nCATCH e\nEND TRY", 404);
+
+    assert!(!errors.is_empty());
+}
+
+#[test]
+fn the_innermost_block_catches() {
+    let output = run_ppl(
+        r#"
+        ERROR e
+        TRY
+            TRY
+                LoadFont 43, "nope.fnt"
+            CATCH e
+                PrintLn "inner"
+            END TRY
+            PrintLn "outer body goes on"
+            LoadFont 43, "nope.fnt"
+        CATCH e
+            PrintLn "outer"
+        END TRY
+        "#,
+    );
+
+    assert_eq!(output, "inner\nouter body goes on\nouter\n");
+}
+
+#[test]
+fn a_failure_in_the_catch_part_goes_to_the_enclosing_block() {
+    let output = run_ppl(
+        r#"
+        ERROR e
+        TRY
+            TRY
+                LoadFont 43, "nope.fnt"
+            CATCH e
+                PrintLn "inner"
+                FOPEN 1, "nope.txt", O_RD, S_DN
+                PrintLn "not reached"
+            END TRY
+        CATCH e
+            PrintLn "outer ", e.Kind
+        END TRY
+        "#,
+    );
+
+    assert_eq!(output, "inner\nouter 1\n");
+}
+
+#[test]
+fn a_failure_in_a_called_procedure_is_caught() {
+    let output = run_ppl(
+        r#"
+        DECLARE PROCEDURE Load()
+        ERROR e
+        TRY
+            Load()
+            PrintLn "not reached"
+        CATCH e
+            PrintLn "caught ", e.Code
+        END TRY
+        PrintLn "after"
+        EXIT
+
+        PROCEDURE Load()
+            LoadFont 43, "nope.fnt"
+            PrintLn "not reached in the procedure"
+        ENDPROC
+        "#,
+    );
+
+    assert_eq!(output, "caught 3\nafter\n");
+}
+
+#[test]
+fn a_failure_in_a_function_ends_the_calling_statement() {
+    let output = run_ppl(
+        r#"
+        DECLARE FUNCTION Load() INTEGER
+        ERROR e
+        TRY
+            PrintLn "value ", Load()
+            PrintLn "not reached"
+        CATCH e
+            PrintLn "caught ", e.Code
+        END TRY
+        EXIT
+
+        FUNCTION Load() INTEGER
+            LoadFont 43, "nope.fnt"
+            PrintLn "not reached in the function"
+            RETURN 1
+        ENDFUNC
+        "#,
+    );
+
+    assert_eq!(output, "caught 3\n");
+}
+
+#[test]
+fn a_block_wins_over_on_error() {
+    let output = run_ppl(
+        r#"
+        ON ERROR GOSUB Report
+        TRY
+            LoadFont 43, "nope.fnt"
+        CATCH
+            PrintLn "caught"
+        END TRY
+        LoadFont 43, "nope.fnt"
+        PrintLn "done"
+        EXIT
+        :Report
+        PrintLn "reported"
+        RETURN
+        "#,
+    );
+
+    assert_eq!(output, "caught\nreported\ndone\n");
+}
+
+#[test]
+fn break_and_continue_disarm_the_block_they_leave() {
+    let output = run_ppl(
+        r#"
+        ON ERROR GOSUB Report
+        INTEGER i
+        WHILE (i < 5) DO
+            i = i + 1
+            TRY
+                IF (i = 1) CONTINUE
+                IF (i = 2) BREAK
+            CATCH
+                PrintLn "stale catch"
+            END TRY
+        ENDWHILE
+        LoadFont 43, "nope.fnt"
+        PrintLn "done ", i
+        EXIT
+        :Report
+        PrintLn "reported"
+        RETURN
+        "#,
+    );
+
+    assert_eq!(output, "reported\ndone 2\n");
+}
+
+#[test]
+fn return_disarms_the_block_it_leaves() {
+    let output = run_ppl(
+        r#"
+        DECLARE PROCEDURE Leave()
+        ON ERROR GOSUB Report
+        Leave()
+        LoadFont 43, "nope.fnt"
+        PrintLn "done"
+        EXIT
+        :Report
+        PrintLn "reported"
+        RETURN
+
+        PROCEDURE Leave()
+            TRY
+                RETURN
+            CATCH
+                PrintLn "stale catch"
+            END TRY
+        ENDPROC
+        "#,
+    );
+
+    assert_eq!(output, "reported\ndone\n");
+}
+
+#[test]
+fn goto_disarms_the_blocks_it_leaves() {
+    let output = run_ppl(
+        r#"
+        ON ERROR GOSUB Report
+        TRY
+            TRY
+                GOTO Out
+            CATCH
+                PrintLn "stale inner catch"
+            END TRY
+        CATCH
+            PrintLn "stale outer catch"
+        END TRY
+        :Out
+        LoadFont 43, "nope.fnt"
+        PrintLn "done"
+        EXIT
+        :Report
+        PrintLn "reported"
+        RETURN
+        "#,
+    );
+
+    assert_eq!(output, "reported\ndone\n");
+}
+
+#[test]
+fn goto_from_a_block_into_its_catch_part_disarms_it() {
+    let output = run_ppl(
+        r#"
+        ON ERROR GOSUB Report
+        INTEGER i
+        TRY
+            IF (i = 0) GOTO Handle
+        CATCH
+            :Handle
+            PrintLn "handling"
+            LoadFont 43, "nope.fnt"
+        END TRY
+        PrintLn "done"
+        EXIT
+        :Report
+        PrintLn "reported"
+        RETURN
+        "#,
+    );
+
+    assert_eq!(output, "handling\nreported\ndone\n");
+}
+
+#[test]
+fn a_jump_into_a_block_is_rejected() {
+    let source = "GOTO Inside\nTRY\n  :Inside\n  PrintLn \"x\"\nCATCH\nEND TRY\nGOSUB Inside";
+
+    let errors = compile_errors_with_runtime(source, 404);
+    assert_eq!(
+        errors
+            .iter()
+            .filter(|error| *error == "Can't jump into a TRY block from outside of it (Inside)")
+            .count(),
+        2,
+        "{errors:?}"
+    );
+}
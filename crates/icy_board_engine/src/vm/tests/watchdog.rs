@@ -0,0 +1,49 @@
+//! The watchdog stops a PPE that would otherwise hold the node forever.
+
+use super::{run_ppl_granted, run_ppl_on};
+use crate::vm::Permissions;
+
+#[test]
+#[should_panic(expected = "instruction budget of 1000 statements used up")]
+fn a_runaway_loop_is_stopped() {
+    run_ppl_on(
+        r#"
+        INTEGER i
+        LOOP
+            i = i + 1
+        ENDLOOP
+    "#,
+        |board| board.config.limits.ppe_instruction_budget = 1000,
+    );
+}
+
+#[test]
+fn a_program_can_declare_that_it_runs_long() {
+    let output = run_ppl_granted(
+        r#"
+        INTEGER i
+        WATCHDOG FALSE
+        WHILE (i < 5000) i = i + 1
+        WATCHDOG TRUE
+        PRINT i
+    "#,
+        Permissions {
+            watchdog: true,
+            ..Default::default()
+        },
+        |board| board.config.limits.ppe_instruction_budget = 1000,
+    );
+    assert_eq!(output, "5000");
+}
+
+#[test]
+fn only_the_sysop_can_switch_it_off() {
+    let output = run_ppl_on(
+        r#"
+        WATCHDOG FALSE
+        PRINTLN "kind=", ERR().Kind = ERR_KIND_SANDBOX, " code=", ERR().Code = ERR_DENIED
+    "#,
+        |_| {},
+    );
+    assert_eq!(output, "kind=1 code=1\n");
+}
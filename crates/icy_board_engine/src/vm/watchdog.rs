@@ -0,0 +1,108 @@
+//! Keeps a PPE from holding a node forever.
+//!
+//! A program gets a budget of statements and has to show signs of life, output or
+//! a look at the keyboard, every so often. Which limits apply is set in the board's
+//! `[limits]`; a program that legitimately runs long, like a game server loop,
+//! switches the watchdog off with `WATCHDOG FALSE` when the sysop granted it that.
+
+use std::time::{Duration, Instant};
+
+/// How many statements run between two looks at the clock.
+const IDLE_CHECK_INTERVAL: u64 = 1024;
+
+pub struct Watchdog {
+    /// Statements a run may take, 0 for no limit.
+    budget: u64,
+    /// How long a run may go without output or input, `None` for no limit.
+    idle_timeout: Option<Duration>,
+    steps: u64,
+    /// When the run has to be over, set by whoever runs the PPE. `WATCHDOG FALSE`
+    /// does not lift it.
+    deadline: Option<Instant>,
+    ticks: u64,
+    /// Cleared by `WATCHDOG FALSE`.
+    pub armed: bool,
+}
+
+impl Watchdog {
+    pub fn new(budget: u32, idle_timeout: u16) -> Self {
+        Self {
+            budget: budget as u64,
+            idle_timeout: (idle_timeout > 0).then(|| Duration::from_secs(idle_timeout as u64)),
+            steps: 0,
+            deadline: None,
+            ticks: 0,
+            armed: true,
+        }
+    }
+
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Counts a statement and answers why the program has to stop, if it has to.
+    /// `last_io` is when the node last wrote output or looked for input.
+    pub fn tick(&mut self, last_io: Instant) -> Option<String> {
+        self.ticks += 1;
+        if let Some(deadline) = self.deadline
+            && self.ticks % IDLE_CHECK_INTERVAL == 0
+            && Instant::now() >= deadline
+        {
+            return Some("out of time".to_string());
+        }
+        if !self.armed {
+            return None;
+        }
+        self.steps += 1;
+        if self.budget > 0 && self.steps > self.budget {
+            return Some(format!("instruction budget of {} statements used up", self.budget));
+        }
+        if let Some(timeout) = self.idle_timeout
+            && self.steps % IDLE_CHECK_INTERVAL == 0
+            && last_io.elapsed() >= timeout
+        {
+            return Some(format!("no output or input for {} seconds", timeout.as_secs()));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_budget_counts_statements() {
+        let mut watchdog = Watchdog::new(3, 0);
+        let now = Instant::now();
+        assert!((0..3).all(|_| watchdog.tick(now).is_none()));
+        assert!(watchdog.tick(now).is_some());
+    }
+
+    #[test]
+    fn an_idle_program_is_stopped() {
+        let mut watchdog = Watchdog::new(0, 1);
+        let long_ago = Instant::now() - Duration::from_secs(2);
+        assert!((1..IDLE_CHECK_INTERVAL).all(|_| watchdog.tick(long_ago).is_none()));
+        assert!(watchdog.tick(long_ago).is_some());
+        assert!(Watchdog::new(0, 1).tick(Instant::now()).is_none());
+    }
+
+    #[test]
+    fn a_disarmed_watchdog_lets_everything_run() {
+        let mut watchdog = Watchdog::new(1, 1);
+        watchdog.armed = false;
+        let long_ago = Instant::now() - Duration::from_secs(2);
+        assert!((0..IDLE_CHECK_INTERVAL * 2).all(|_| watchdog.tick(long_ago).is_none()));
+    }
+
+    #[test]
+    fn the_deadline_holds_when_disarmed() {
+        let mut watchdog = Watchdog::new(0, 0).with_deadline(Some(Instant::now()));
+        watchdog.armed = false;
+        let now = Instant::now();
+        assert!((1..IDLE_CHECK_INTERVAL).all(|_| watchdog.tick(now).is_none()));
+        assert!(watchdog.tick(now).is_some());
+    }
+}
@@ -72,6 +72,7 @@ fn released_runtimes_keep_their_user_variable_prefix_lengths() {
         (400, 29),
         (401, 29),
         (402, 30),
+        (403, 30),
     ];
     let actual: Vec<_> = SUPPORTED_PPE_VERSIONS
         .iter()
@@ -5,7 +5,7 @@ use std::{
 
 use icy_board_engine::{
     compiler::{PPECompiler, workspace::Workspace},
-    executable::{Executable, ExecutableError, FIRST_TYPE_TABLE_RUNTIME, GenericVariableData, TableEntry, VariableType},
+    executable::{Executable, ExecutableError, FIRST_TYPE_TABLE_RUNTIME, GenericVariableData, TableEntry, TypeLayout, VariableType},
     parser::{Encoding, ErrorReporter, MAX_TYPE_FIELDS, MAX_USER_TYPES, UserTypeRegistry, parse_ast},
 };
 use std::fmt::Write as _;
@@ -53,8 +53,8 @@ fn custom_type_layouts_survive_the_ppe_round_trip() {
     let executable = compile("TYPE Inner\n  INTEGER Number\n  STRING Text\nENDTYPE\nTYPE Outer\n  Inner Value\n  BOOLEAN Flag\nENDTYPE\nOuter item\n");
     assert_eq!(
         vec![
-            vec![VariableType::Integer, VariableType::String],
-            vec![VariableType::UserData(100), VariableType::Boolean]
+            TypeLayout::Record(vec![VariableType::Integer, VariableType::String]),
+            TypeLayout::Record(vec![VariableType::UserData(100), VariableType::Boolean])
         ],
         executable.user_types
     );
@@ -146,7 +146,7 @@ fn a_record_filled_to_the_byte_limit_round_trips() {
     source.push_str("ENDTYPE\nWide item\n");
 
     let executable = compile(&source);
-    assert_eq!(MAX_TYPE_FIELDS, executable.user_types[0].len());
+    assert!(matches!(&executable.user_types[0], TypeLayout::Record(fields) if fields.len() == MAX_TYPE_FIELDS));
 
     let mut bytes = executable.to_buffer().unwrap();
     let loaded = Executable::from_buffer(&mut bytes, false).unwrap();
@@ -172,7 +172,7 @@ fn a_record_past_the_byte_limit_is_rejected() {
 fn the_serializer_rejects_custom_types_before_runtime_401() {
     let executable = Executable {
         runtime: 400,
-        user_types: vec![vec![VariableType::Integer]],
+        user_types: vec![TypeLayout::Record(vec![VariableType::Integer])],
         ..Executable::default()
     };
     assert_eq!(
@@ -184,7 +184,7 @@ fn the_serializer_rejects_custom_types_before_runtime_401() {
 #[test]
 fn the_serializer_rejects_counts_that_do_not_fit_the_format() {
     let too_many_types = Executable {
-        user_types: vec![vec![VariableType::Integer]; MAX_USER_TYPES + 1],
+        user_types: vec![TypeLayout::Record(vec![VariableType::Integer]); MAX_USER_TYPES + 1],
         ..Executable::default()
     };
     assert_eq!(
@@ -193,7 +193,7 @@ fn the_serializer_rejects_counts_that_do_not_fit_the_format() {
     );
 
     let too_many_fields = Executable {
-        user_types: vec![vec![VariableType::Integer; MAX_TYPE_FIELDS + 1]],
+        user_types: vec![TypeLayout::Record(vec![VariableType::Integer; MAX_TYPE_FIELDS + 1])],
         ..Executable::default()
     };
     assert_eq!(
@@ -205,13 +205,16 @@ fn the_serializer_rejects_counts_that_do_not_fit_the_format() {
 #[test]
 fn the_serializer_rejects_recursive_or_forward_type_references() {
     let self_reference = Executable {
-        user_types: vec![vec![VariableType::UserData(100)]],
+        user_types: vec![TypeLayout::Record(vec![VariableType::UserData(100)])],
         ..Executable::default()
     };
     assert_eq!(ExecutableError::InvalidTypeReference(100, 100), self_reference.to_buffer().unwrap_err());
 
     let forward_reference = Executable {
-        user_types: vec![vec![VariableType::UserData(101)], vec![VariableType::Integer]],
+        user_types: vec![
+            TypeLayout::Record(vec![VariableType::UserData(101)]),
+            TypeLayout::Record(vec![VariableType::Integer]),
+        ],
         ..Executable::default()
     };
     assert_eq!(ExecutableError::InvalidTypeReference(100, 101), forward_reference.to_buffer().unwrap_err());
@@ -220,7 +223,7 @@ fn the_serializer_rejects_recursive_or_forward_type_references() {
 #[test]
 fn the_serializer_rejects_a_board_object_field() {
     let executable = Executable {
-        user_types: vec![vec![VariableType::UserData(30)]],
+        user_types: vec![TypeLayout::Record(vec![VariableType::UserData(30)])],
         ..Executable::default()
     };
     assert_eq!(ExecutableError::BoardObjectTypeField(100, 30), executable.to_buffer().unwrap_err());
@@ -240,7 +243,7 @@ fn the_serializer_rejects_a_variable_whose_type_is_missing() {
 #[test]
 fn the_loader_rejects_a_recursive_type_table() {
     let executable = Executable {
-        user_types: vec![vec![VariableType::Integer]],
+        user_types: vec![TypeLayout::Record(vec![VariableType::Integer])],
         ..Executable::default()
     };
     let mut bytes = executable.to_buffer().unwrap();
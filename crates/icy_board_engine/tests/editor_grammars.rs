@@ -9,6 +9,9 @@ use icy_board_engine::{
 
 /// Names the grammar spells as a keyword or that only exist as an opcode.
 const STATEMENTS_NOT_IN_GRAMMAR: &[&str] = &[
+    "<END TRY>",
+    "<JSON READ>",
+    "<TRY>",
     "BEGIN",
     "DECLARE",
     "END",
@@ -120,7 +123,7 @@ fn list_from_textmate(rule: &str) -> Vec<String> {
 fn vscode_grammar_knows_every_built_in() {
     let statements: Vec<String> = STATEMENT_DEFINITIONS
         .iter()
-        .filter(|def| def.sig != StatementSignature::Invalid)
+        .filter(|def| def.sig != StatementSignature::Invalid && !def.name.starts_with('<'))
         .map(|def| def.name.to_ascii_uppercase())
         .collect();
     let in_grammar = list_from_textmate("builtin-statements");
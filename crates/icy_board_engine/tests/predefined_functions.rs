@@ -7,7 +7,7 @@ use icy_board_engine::{
     executable::{PPEExpr, PPEScript, TableEntry, VariableTable, VariableValue},
     icy_board::{bbs::BBS, commands::CommandList, state::IcyBoardState, user_base::User, xfer_protocols::SupportedProtocols},
     parser::UserTypeRegistry,
-    vm::{DiskIO, VirtualMachine},
+    vm::{DiskIO, VirtualMachine, Watchdog},
 };
 use icy_net::{ConnectionType, channel::ChannelConnection};
 
@@ -54,6 +54,11 @@ async fn test_fmtreal(value: f64, field_width: i32, decimal_places: i32) -> Stri
         variable_table: VariableTable::default(),
         cur_ptr: 0,
         label_table: HashMap::new(),
+        debug_info: None,
+        debugger: None,
+        sandbox: None,
+        watchdog: Watchdog::new(0, 0),
+        profile: None,
         call_local_value_stack: Vec::new(),
         write_back_stack: Vec::new(),
         user_types: Vec::new(),
@@ -71,6 +76,7 @@ async fn test_fmtreal(value: f64, field_width: i32, decimal_places: i32) -> Stri
         error_handler: Default::default(),
         in_handler: false,
         handler_depth: None,
+        try_frames: Vec::new(),
         dbase: Default::default(),
     };
 
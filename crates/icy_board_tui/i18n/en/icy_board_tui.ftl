@@ -1346,6 +1346,21 @@ update_last_read_pointer=Reading Moves the Last Read Pointer
 update_last_read_pointer-status=Reading a message moves the last read pointer along
 update_last_read_pointer-help=Decides what the next new message scan will show.
 
+message_external_editor=External Message Editor
+message_external_editor-status=Command line of a message editor run like a door, empty = built-in
+message_external_editor-help=
+    # External Message Editor
+
+    Runs an external editor instead of the built-in one when a caller writes a
+    message, the way QEdit-style editors ran as doors under PCBoard. `%1` is
+    replaced by the draft file (MSGTMP), `%2` by the MSGINF or EDITOR.INF header
+    and `%N` by the node number; the editor runs in a work directory that also
+    holds the configured drop file.
+
+    Whatever the editor leaves in the draft becomes the message. If the editor
+    can't be started or exits with an error, the caller gets the built-in
+    editor instead.
+
 keyboard_timeout=Keyboard Timeout (in min)
 keyboard_timeout-status=0=disable
 keyboard_timeout-help=
@@ -1378,6 +1393,25 @@ password_expire_warn_days-help=
     How many days ahead of the forced change the caller is warned, so the new
     password does not have to be invented on the spot. 0 turns the warning off.
 
+ppe_instruction_budget=PPE Instruction Budget (statements)
+ppe_instruction_budget-status=0=no limit
+ppe_instruction_budget-help=
+    # PPE Instruction Budget
+
+    How many statements a PPE may run before the watchdog stops it and logs
+    where it was. A few million leaves room for any honest program; 0 lets
+    PPEs run as long as they like.
+
+ppe_idle_timeout=PPE Idle Timeout (in sec)
+ppe_idle_timeout-status=0=no limit
+ppe_idle_timeout-help=
+    # PPE Idle Timeout
+
+    How many seconds a PPE may keep running without sending output or looking
+    at the keyboard. One stuck in a loop is stopped, logged and the caller
+    goes back to the menu. A PPE that runs long on purpose can turn the
+    watchdog off with `WATCHDOG FALSE`. 0 turns the check off.
+
 min_pwd_length=Minimum Password Length
 min_pwd_length-status=Shortest password a caller may choose
 min_pwd_length-help=
@@ -1436,6 +1470,27 @@ strip_colors_in_descriptions=Strip Colors in Descriptions
 strip_colors_in_descriptions-status=Drop the colors a FILE_ID.DIZ brings with it.
 strip_colors_in_descriptions-help=A FILE_ID.DIZ carries whatever colors its author chose, and a reset in one of them puts the caller back to the terminal default rather than the color this board lists files in. Turn this on to keep a listing in the board's own colors. Spacing and line art are left alone either way.
 
+show_thumbnails=Show Thumbnails
+show_thumbnails-status=Preview pictures and ANSI art in the file listings.
+show_thumbnails-help=
+    # Show Thumbnails
+
+    Draws a small preview below every GIF, PNG and JPEG and every SAUCE tagged
+    ANSI in the file listings and the archive viewer. Only callers whose terminal
+    shows sixel or JPEG XL get them, everybody else sees the usual listing.
+    Thumbnails are rendered once per file and kept in the thumbnails directory
+    below the temporary work path.
+
+ask_upload_tags=Ask for Upload Tags
+ask_upload_tags-status=Ask uploaders for tags after the description.
+ask_upload_tags-help=
+    # Ask for Upload Tags
+
+    After the description an uploader is asked for tags like #demo #1995.
+    Callers find tagged files with L by entering the tags instead of a file
+    name, and a lone # shows the tags used in each area. Sysops can change the
+    tags of any file with icbfile set.
+
 verify_files_uploaded=Verify Files Uploaded
 verify_files_uploaded-status=Verify files uploaded after upload.
 verify_files_uploaded-help=
@@ -1444,6 +1499,25 @@ verify_files_uploaded-help=
     Checks an upload before it is offered to anybody, which is where PCBoard ran
     a virus scanner or an archive test.
 
+hold_uploads=Hold Uploads for Approval
+hold_uploads-status=New uploads stay hidden until a sysop approves them.
+hold_uploads-help=
+    # Hold Uploads for Approval
+
+    Keeps every new upload out of the file listings until a sysop has looked at
+    it. Sysop command 17 goes through the waiting files and approves, moves or
+    deletes them; icbfile and the admin API can do the same from outside.
+
+duplicate_uploads=Duplicate Uploads
+duplicate_uploads-status=What happens to an upload that is already on the board.
+duplicate_uploads-help=
+    # Duplicate Uploads
+
+    Every upload is compared with the files in all conferences, by name and by
+    contents. "Allow" takes uploads without checking, "Flag" accepts the file but
+    holds it for a sysop with the original noted, and "Reject" turns it down.
+    `icbfile dupes` lists what is already duplicated.
+
 disable_drive_size_check=Disable Drive Size Check
 disable_drive_size_check-status=That disables the message as well.
 disable_drive_size_check-help=
@@ -3208,6 +3282,41 @@ dirs_download_sec-help=
     The security needed to take a file out of this directory, which can be higher
     than the security needed to look at the listing.
 
+dirs_edit_advertise=Advertise
+dirs_edit_advertise-status=When the board ad goes into ZIP archives
+dirs_edit_advertise-help=
+    # Advertise
+
+    Adds the ad file, comment and FILE_ID line below to the ZIP archives of
+    this directory. Upload changes the stored archive once, after the upload
+    was checked. Download leaves the stored archive alone and sends a copy
+    with the ad. Either way a new upload of the original is still caught as a
+    duplicate.
+
+dirs_edit_ad_file=Ad File
+dirs_edit_ad_file-status=The file added to the archives
+dirs_edit_ad_file-help=
+    # Ad File
+
+    Added to an archive under its own name, unless the archive already holds
+    a file by that name. Usually a short text or ANSI about the board.
+
+dirs_edit_ad_comment=Ad Comment
+dirs_edit_ad_comment-status=Replaces the archive comment
+dirs_edit_ad_comment-help=
+    # Ad Comment
+
+    Replaces the archive comment, which most unzippers print when the archive
+    is opened. Leave it empty to keep the comment the archive came with.
+
+dirs_edit_ad_file_id_line=FILE_ID Line
+dirs_edit_ad_file_id_line-status=Added below the FILE_ID.DIZ
+dirs_edit_ad_file_id_line-help=
+    # FILE_ID Line
+
+    Added below the FILE_ID.DIZ of an archive that carries one. An archive
+    without a description does not get one.
+
 area_editor_title=AREA.LST Editor - { $conference }
 area_editor_edit_title=Edit Area
 
@@ -44,12 +44,6 @@ pub const UNREAD_OPTIONS: &[UnreadOption] = &[
         kind: Unread::NotReadYet,
         note: "uploading earns byte credit, but not time credit",
     },
-    UnreadOption {
-        section: "file_transfer",
-        option: "verify_files_uploaded",
-        kind: Unread::NotReadYet,
-        note: "uploads are never test-extracted",
-    },
     UnreadOption {
         section: "system_control",
         option: "disable_full_record_updating",
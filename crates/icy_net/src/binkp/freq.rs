@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+/// One line of a WaZOO `.REQ` file, FTS-0006 section 4: a name or a pattern,
+/// optionally followed by `!password` and a `+time` asking only for something
+/// newer than what the remote already has.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FileRequest {
+    pub name: String,
+    pub password: String,
+    /// Seconds since the epoch, only files modified after it are wanted.
+    pub newer_than: Option<u64>,
+}
+
+impl FileRequest {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let name = fields.next()?;
+        // Comments are not in the spec, but more than one mailer writes them.
+        if name.starts_with(';') {
+            return None;
+        }
+        let mut request = FileRequest {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        for field in fields {
+            if let Some(password) = field.strip_prefix('!') {
+                request.password = password.to_string();
+            } else if let Some(time) = field.strip_prefix('+') {
+                request.newer_than = time.parse().ok();
+            }
+        }
+        Some(request)
+    }
+
+    pub fn parse_list(text: &str) -> Vec<Self> {
+        text.lines().filter_map(FileRequest::parse).collect()
+    }
+}
+
+/// Decides which files answer a request. The session only carries them; what
+/// may be requested, and how much, is up to whoever runs the mailer.
+pub trait FileRequests: Send {
+    fn resolve(&mut self, request: &FileRequest) -> Vec<PathBuf>;
+}
+
+/// Answers every request with nothing, which is what a mailer without public
+/// file areas does.
+pub struct NoFileRequests;
+
+impl FileRequests for NoFileRequests {
+    fn resolve(&mut self, _request: &FileRequest) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Both the classic `xxxxyyyy.REQ` and a longer name count, only the extension matters.
+pub fn is_request_file(name: &str) -> bool {
+    name.len() > 4 && name.is_char_boundary(name.len() - 4) && name[name.len() - 4..].eq_ignore_ascii_case(".req")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_request_line_carries_its_password_and_time() {
+        assert_eq!(
+            FileRequest::parse("NODELIST.ZIP !Secret +1700000000"),
+            Some(FileRequest {
+                name: "NODELIST.ZIP".to_string(),
+                password: "Secret".to_string(),
+                newer_than: Some(1700000000),
+            })
+        );
+        assert_eq!(FileRequest::parse("FILES").unwrap().password, "");
+        assert_eq!(FileRequest::parse("   "), None);
+        assert_eq!(FileRequest::parse_list("FILES\r\n; comment\r\n*.ZIP\r\n").len(), 2);
+    }
+
+    #[test]
+    fn test_only_the_extension_makes_a_request_file() {
+        assert!(is_request_file("00150064.REQ"));
+        assert!(is_request_file("0001fffe.req"));
+        assert!(!is_request_file("00150064.PKT"));
+        assert!(!is_request_file(".req"));
+    }
+}
@@ -1,10 +1,12 @@
 pub mod cram;
 mod frame;
+mod freq;
 mod poll;
 mod session;
 mod transfer;
 
 pub use frame::*;
+pub use freq::*;
 pub use poll::*;
 pub use session::*;
 pub use transfer::*;
@@ -2,7 +2,7 @@ use std::{path::PathBuf, time::Duration};
 
 use crate::{Connection, raw::RawConnection};
 
-use super::{BatchResult, BinkpIdentity, OutboundFile, RemoteInfo, originate_session, transfer_batch};
+use super::{BatchResult, BinkpIdentity, FileRequests, NoFileRequests, OutboundFile, RemoteInfo, originate_session, transfer_batch_with_requests};
 
 /// The port fidonet technology networks reserved for binkp.
 pub const DEFAULT_PORT: u16 = 24554;
@@ -50,19 +50,24 @@ pub struct PollResult {
 
 /// Calls a system and exchanges one batch of mail with it.
 pub async fn poll(request: &PollRequest) -> crate::Result<PollResult> {
+    poll_with_requests(request, &mut NoFileRequests).await
+}
+
+/// A poll that also answers what the called system requests from us.
+pub async fn poll_with_requests(request: &PollRequest, requests: &mut dyn FileRequests) -> crate::Result<PollResult> {
     let mut connection = RawConnection::open(&(request.host.as_str(), request.port), request.connect_timeout).await?;
-    poll_over(&mut connection, request).await
+    poll_over(&mut connection, request, requests).await
 }
 
 /// The part of a poll that does not care how the connection was obtained.
-pub async fn poll_over(connection: &mut dyn Connection, request: &PollRequest) -> crate::Result<PollResult> {
+pub async fn poll_over(connection: &mut dyn Connection, request: &PollRequest, requests: &mut dyn FileRequests) -> crate::Result<PollResult> {
     let remote = originate_session(connection, &request.identity, &request.called, &request.password).await?;
 
     let mut files = Vec::with_capacity(request.outbound.len());
     for path in &request.outbound {
         files.push(OutboundFile::open(path).await?);
     }
-    let batch = transfer_batch(connection, files, &request.inbound, request.session_timeout).await?;
+    let batch = transfer_batch_with_requests(connection, files, &request.inbound, request.session_timeout, requests).await?;
 
     // The mail is already across, so a refused goodbye is not worth a failed poll.
     let _ = connection.shutdown().await;
@@ -100,7 +105,7 @@ mod tests {
             session_timeout: Duration::from_secs(5),
             ..Default::default()
         };
-        let result = poll_over(&mut ours, &request).await.unwrap();
+        let result = poll_over(&mut ours, &request, &mut NoFileRequests).await.unwrap();
 
         assert_eq!(result.remote.addresses, vec!["21:1/1@fsxnet".to_string()]);
         assert!(result.remote.secure);
@@ -1,4 +1,5 @@
 use std::{
+    collections::VecDeque,
     path::{Path, PathBuf},
     time::Duration,
 };
@@ -11,7 +12,7 @@ use tokio::{
 
 use crate::{
     Connection, NetError,
-    binkp::{BinkpCommand, Frame, FrameReader},
+    binkp::{BinkpCommand, FileRequest, FileRequests, Frame, FrameReader, NoFileRequests, is_request_file},
 };
 use std::fmt::Write as _;
 
@@ -82,6 +83,9 @@ pub struct BatchResult {
     /// Files the remote asked us to offer again next time.
     pub skipped: Vec<PathBuf>,
     pub received: Vec<PathBuf>,
+    /// Files the remote requested and got. They belong to a file area, so
+    /// unlike `sent` they stay where they are.
+    pub requested: Vec<PathBuf>,
 }
 
 struct Sending {
@@ -100,6 +104,19 @@ struct Receiving {
 /// Runs the file transfer stage of FTS-1026 6.2 until both sides have sent
 /// their end of batch and every file has been accounted for.
 pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<OutboundFile>, inbound: &Path, timeout: Duration) -> crate::Result<BatchResult> {
+    transfer_batch_with_requests(connection, outbound, inbound, timeout, &mut NoFileRequests).await
+}
+
+/// A batch that also answers file requests, whether they arrive as a `.REQ`
+/// file or as an M_GET for a file that was never offered. What they resolve to
+/// is sent after the outbound, in the same session.
+pub async fn transfer_batch_with_requests(
+    connection: &mut dyn Connection,
+    outbound: Vec<OutboundFile>,
+    inbound: &Path,
+    timeout: Duration,
+    requests: &mut dyn FileRequests,
+) -> crate::Result<BatchResult> {
     tokio::fs::create_dir_all(inbound).await?;
 
     let mut reader = FrameReader::new();
@@ -108,6 +125,8 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
     let mut sending: Option<Sending> = None;
     let mut unacknowledged: Vec<OutboundFile> = Vec::new();
     let mut receiving: Option<Receiving> = None;
+    let mut requested: VecDeque<OutboundFile> = VecDeque::new();
+    let mut requested_paths: Vec<PathBuf> = Vec::new();
     let mut sent_eob = false;
     let mut got_eob = false;
     let mut deadline = Instant::now() + timeout;
@@ -129,8 +148,15 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
                         if current.written >= current.info.size {
                             current.handle.flush().await?;
                             Frame::command(BinkpCommand::Got, current.info.to_argument(None)).send(connection).await?;
-                            result.received.push(current.path.clone());
+                            let path = current.path.clone();
+                            let is_request = is_request_file(&current.info.name);
                             receiving = None;
+                            if is_request {
+                                let answers = answer_request_file(&path, requests).await?;
+                                queue_requested(answers, &mut requested, &mut requested_paths, &mut sent_eob, &mut got_eob);
+                            } else {
+                                result.received.push(path);
+                            }
                         }
                     }
                 }
@@ -154,7 +180,12 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
                     let handle = File::create(&path).await?;
                     if info.size == 0 {
                         Frame::command(BinkpCommand::Got, info.to_argument(None)).send(connection).await?;
-                        result.received.push(path);
+                        // An empty request asks for nothing, but it is not mail either.
+                        if is_request_file(&name) {
+                            let _ = tokio::fs::remove_file(&path).await;
+                        } else {
+                            result.received.push(path);
+                        }
                     } else {
                         receiving = Some(Receiving {
                             info,
@@ -170,11 +201,20 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
                         return abort(connection, NetError::BinkpBadArgument("M_GOT".to_string(), argument)).await;
                     };
                     // Arriving mid file this is a destructive skip, so stop sending either way.
-                    if sending.as_ref().is_some_and(|current| current.file.info == info) {
-                        let current = sending.take().unwrap();
-                        result.sent.push(current.file.path);
-                    } else if let Some(index) = unacknowledged.iter().position(|file| file.info == info) {
-                        result.sent.push(unacknowledged.remove(index).path);
+                    let delivered = if sending.as_ref().is_some_and(|current| current.file.info == info) {
+                        sending.take().map(|current| current.file.path)
+                    } else {
+                        unacknowledged
+                            .iter()
+                            .position(|file| file.info == info)
+                            .map(|index| unacknowledged.remove(index).path)
+                    };
+                    if let Some(path) = delivered {
+                        if requested_paths.contains(&path) {
+                            result.requested.push(path);
+                        } else {
+                            result.sent.push(path);
+                        }
                     }
                 }
 
@@ -182,11 +222,19 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
                     let Some((info, _)) = FileInfo::parse(&argument) else {
                         return abort(connection, NetError::BinkpBadArgument("M_SKIP".to_string(), argument)).await;
                     };
-                    if sending.as_ref().is_some_and(|current| current.file.info == info) {
-                        let current = sending.take().unwrap();
-                        result.skipped.push(current.file.path);
-                    } else if let Some(index) = unacknowledged.iter().position(|file| file.info == info) {
-                        result.skipped.push(unacknowledged.remove(index).path);
+                    let refused = if sending.as_ref().is_some_and(|current| current.file.info == info) {
+                        sending.take().map(|current| current.file.path)
+                    } else {
+                        unacknowledged
+                            .iter()
+                            .position(|file| file.info == info)
+                            .map(|index| unacknowledged.remove(index).path)
+                    };
+                    // A requested file that was refused is simply not sent; there is no next time for it.
+                    if let Some(path) = refused
+                        && !requested_paths.contains(&path)
+                    {
+                        result.skipped.push(path);
                     }
                 }
 
@@ -196,11 +244,20 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
                     };
                     if let Some(current) = &mut sending
                         && current.file.info == info
-                        && offset <= info.size
                     {
-                        current.handle.seek(std::io::SeekFrom::Start(offset)).await?;
-                        current.offset = offset;
-                        Frame::command(BinkpCommand::File, info.to_argument(Some(offset))).send(connection).await?;
+                        if offset <= info.size {
+                            current.handle.seek(std::io::SeekFrom::Start(offset)).await?;
+                            current.offset = offset;
+                            Frame::command(BinkpCommand::File, info.to_argument(Some(offset))).send(connection).await?;
+                        }
+                    } else if !unacknowledged.iter().any(|file| file.info.name == info.name) {
+                        // Nothing by that name is on its way, so the remote is asking for it.
+                        let request = FileRequest {
+                            name: info.name,
+                            ..Default::default()
+                        };
+                        let answers = open_answers(requests.resolve(&request)).await;
+                        queue_requested(answers, &mut requested, &mut requested_paths, &mut sent_eob, &mut got_eob);
                     }
                 }
 
@@ -217,7 +274,7 @@ pub async fn transfer_batch(connection: &mut dyn Connection, outbound: Vec<Outbo
         if !sent_eob {
             worked = true;
             if sending.is_none() {
-                match waiting.next() {
+                match waiting.next().or_else(|| requested.pop_front()) {
                     Some(file) => {
                         let handle = File::open(&file.path).await?;
                         Frame::command(BinkpCommand::File, file.info.to_argument(Some(0))).send(connection).await?;
@@ -259,6 +316,52 @@ async fn abort(connection: &mut dyn Connection, error: NetError) -> crate::Resul
     Err(error.into())
 }
 
+/// Reads a `.REQ` that has just arrived and resolves every line of it. The file
+/// has done its job once it is read, so it does not stay in the inbound.
+async fn answer_request_file(path: &Path, requests: &mut dyn FileRequests) -> crate::Result<Vec<OutboundFile>> {
+    let text = String::from_utf8_lossy(&tokio::fs::read(path).await?).to_string();
+    tokio::fs::remove_file(path).await?;
+    let mut paths = Vec::new();
+    for request in FileRequest::parse_list(&text) {
+        paths.extend(requests.resolve(&request));
+    }
+    Ok(open_answers(paths).await)
+}
+
+async fn open_answers(paths: Vec<PathBuf>) -> Vec<OutboundFile> {
+    let mut files = Vec::with_capacity(paths.len());
+    for path in paths {
+        match OutboundFile::open(&path).await {
+            Ok(file) => files.push(file),
+            Err(err) => log::warn!("binkp: can't send requested {}: {}", path.display(), err),
+        }
+    }
+    files
+}
+
+/// Puts what a request resolved to behind the files already waiting. When this
+/// side has already ended its batch, another one is started for them, which
+/// binkp/1.1 allows once files have gone across.
+fn queue_requested(
+    answers: Vec<OutboundFile>,
+    requested: &mut VecDeque<OutboundFile>,
+    requested_paths: &mut Vec<PathBuf>,
+    sent_eob: &mut bool,
+    got_eob: &mut bool,
+) {
+    if answers.is_empty() {
+        return;
+    }
+    if *sent_eob {
+        *sent_eob = false;
+        *got_eob = false;
+    }
+    for file in answers {
+        requested_paths.push(file.path.clone());
+        requested.push_back(file);
+    }
+}
+
 /// Refuses everything a remote could use to write outside the inbound. A binkp
 /// file name has no directory part to begin with, so anything that looks like
 /// one is a reason to refuse rather than to repair.
@@ -469,6 +572,101 @@ mod tests {
         assert!(!directory.path().join("escaped").exists());
     }
 
+    /// Answers a request for "FILES" with one file, and everything else with nothing.
+    struct Answer(PathBuf);
+
+    impl FileRequests for Answer {
+        fn resolve(&mut self, request: &FileRequest) -> Vec<PathBuf> {
+            if request.name.eq_ignore_ascii_case("FILES") {
+                vec![self.0.clone()]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+
+    /// Plays a remote that asks for something and waits until it arrived.
+    fn request_and_collect(mut peer: ChannelConnection, asking: Vec<Frame>) -> tokio::task::JoinHandle<Vec<(String, Vec<u8>)>> {
+        tokio::spawn(async move {
+            for frame in asking {
+                frame.send(&mut peer).await.unwrap();
+            }
+            Frame::command(BinkpCommand::Eob, "").send(&mut peer).await.unwrap();
+            let mut received = Vec::new();
+            let mut current: Option<(FileInfo, Vec<u8>)> = None;
+            loop {
+                match Frame::read(&mut peer).await.unwrap() {
+                    Frame::Command(BinkpCommand::File, argument) => current = Some((FileInfo::parse(&argument).unwrap().0, Vec::new())),
+                    Frame::Data(data) => {
+                        let Some((info, bytes)) = &mut current else { continue };
+                        bytes.extend_from_slice(&data);
+                        if bytes.len() as u64 >= info.size {
+                            Frame::command(BinkpCommand::Got, info.to_argument(None)).send(&mut peer).await.unwrap();
+                            received.push((info.name.clone(), current.take().unwrap().1));
+                        }
+                    }
+                    Frame::Command(BinkpCommand::Eob, _) if !received.is_empty() => return received,
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_a_request_file_is_answered_in_the_same_session() {
+        let directory = tempfile::tempdir().unwrap();
+        let inbound = directory.path().join("in");
+        let listing = outbound_file(directory.path(), "ALLFILES.TXT", b"all the files").await;
+        let request = b"FILES\r\n";
+        let info = FileInfo {
+            name: "0001FFFF.REQ".to_string(),
+            size: request.len() as u64,
+            time: 1234,
+        };
+
+        let (mut ours, peer) = ChannelConnection::create_pair();
+        let peer = request_and_collect(
+            peer,
+            vec![Frame::command(BinkpCommand::File, info.to_argument(Some(0))), Frame::Data(request.to_vec())],
+        );
+        let result = transfer_batch_with_requests(&mut ours, Vec::new(), &inbound, timeout(), &mut Answer(listing.path.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(peer.await.unwrap(), vec![("ALLFILES.TXT".to_string(), b"all the files".to_vec())]);
+        assert_eq!(result.requested, vec![listing.path.clone()]);
+        assert!(result.sent.is_empty());
+        assert!(result.received.is_empty());
+        assert!(!inbound.join("0001FFFF.REQ").exists());
+        assert!(listing.path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_a_get_for_a_file_never_offered_is_a_request() {
+        let directory = tempfile::tempdir().unwrap();
+        let listing = outbound_file(directory.path(), "ALLFILES.TXT", b"all the files").await;
+        let info = FileInfo {
+            name: "FILES".to_string(),
+            size: 0,
+            time: 0,
+        };
+
+        let (mut ours, peer) = ChannelConnection::create_pair();
+        let peer = request_and_collect(peer, vec![Frame::command(BinkpCommand::Get, info.to_argument(Some(0)))]);
+        let result = transfer_batch_with_requests(
+            &mut ours,
+            Vec::new(),
+            &directory.path().join("in"),
+            timeout(),
+            &mut Answer(listing.path.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(peer.await.unwrap().len(), 1);
+        assert_eq!(result.requested, vec![listing.path]);
+    }
+
     #[test]
     fn test_a_space_does_not_survive_unescaped_in_a_name() {
         assert_eq!(escape_filename("abcd e.0f@"), "abcd\\x20e.0f@");
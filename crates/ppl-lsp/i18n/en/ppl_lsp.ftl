@@ -1114,6 +1114,10 @@ hint-function-term-info=Returns an immutable TERMINFO snapshot of cached termina
 hint-function-err=Returns an ERROR describing the last operation that could fail: OK, Kind, Code, Message and Channel.
 hint-statement-errclr=Forgets the last error, so ERR().OK is true again.
 hint-statement-on-error=ON ERROR GOTO label | GOSUB label | Procedure | OFF - where a failed operation sends the program.
+hint-statement-watchdog=Turns the board's instruction budget and idle timeout off (FALSE) or on again (TRUE), for a PPE that runs long on purpose.
+hint-function-jsonparse=Parses @1 into a JSON value to walk with Get, Item, Key and Kind. Text that is not JSON gives null and sets ERR().
+hint-function-jsonstr=Writes a value as JSON text: a record as an object, a list or an array as an array, a map as an object.
+hint-statement-jsonread=Reads JSON text into a variable of any type, records and collections included. Text that does not fit leaves the variable alone and sets ERR().
 hint-statement-gfx-api=Creates, draws, presents or controls Sixel/JPEG XL graphics surfaces.
 hint-statement-sound-api=Preloads, plays, fades, stops or changes SyncTERM audio.
 hint-statement-mouse-api=Enables or disables cell/pixel mouse event reporting.
@@ -19,7 +19,7 @@ pub enum ImCompleteCompletionItem {
 
 /// Words the parser reads by name instead of as a token, with the version that gave
 /// them their meaning. EXIT is the statement END used to be.
-const CONTEXTUAL_WORDS: &[(&str, u16)] = &[("EXIT", 400)];
+const CONTEXTUAL_WORDS: &[(&str, u16)] = &[("EXIT", 400), ("LIST", 400), ("MAP", 400), ("OF", 400), ("EACH", 400), ("IN", 400)];
 
 /// return (need_to_continue_search, founded reference)
 pub fn get_completion(ast: &Ast, semantic_visitor: &SemanticVisitor, line_before_cursor: &str, offset: usize) -> Vec<CompletionItem> {
@@ -75,7 +75,7 @@ pub fn get_completion(ast: &Ast, semantic_visitor: &SemanticVisitor, line_before
         }
 
         for stmt in STATEMENT_DEFINITIONS.iter() {
-            if stmt.sig == StatementSignature::Invalid || stmt.version > ast.language_version {
+            if stmt.sig == StatementSignature::Invalid || stmt.version > ast.language_version || stmt.name.starts_with('<') {
                 continue;
             }
             let content = if let Some(hover) = get_statement_hover(stmt) {
@@ -154,9 +154,15 @@ pub fn get_completion(ast: &Ast, semantic_visitor: &SemanticVisitor, line_before
 }
 
 /// The names of the record types the program declares plus, from the version that
-/// brought them, the board objects.
+/// brought them, the board objects. A collection is spelled out with LIST or MAP.
 fn declared_type_names(visitor: &SemanticVisitor, lang_version: u16) -> Vec<String> {
-    let mut names: Vec<String> = visitor.type_registry.user_types().iter().map(|def| def.name.to_string()).collect();
+    let mut names: Vec<String> = visitor
+        .type_registry
+        .user_types()
+        .iter()
+        .filter(|def| def.collection.is_none())
+        .map(|def| def.name.to_string())
+        .collect();
     if lang_version >= FIRST_BOARD_OBJECT_LANGUAGE_VERSION {
         names.extend(visitor.type_registry.registered_types.keys().map(|name| name.to_string()));
     }
@@ -237,7 +243,7 @@ impl CompletionVisitor {
             });
         }
 
-        for func in FUNCTION_DEFINITIONS.iter() {
+        for func in FUNCTION_DEFINITIONS.iter().filter(|func| !func.name.starts_with('<')) {
             let content = if let Some(hover) = get_function_hover(func) {
                 if let HoverContents::Markup(content) = hover.contents {
                     Some(Documentation::MarkupContent(content))
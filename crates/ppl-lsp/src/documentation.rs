@@ -450,6 +450,8 @@ pub fn get_function_hover(func: &FunctionDefinition) -> Option<Hover> {
         FuncOpCode::TermState => get_sig_hint(sig, fl!(crate::LANGUAGE_LOADER, "hint-function-term-state")),
         FuncOpCode::TermInfo => get_sig_hint(sig, fl!(crate::LANGUAGE_LOADER, "hint-function-term-info")),
         FuncOpCode::Err => get_sig_hint(sig, fl!(crate::LANGUAGE_LOADER, "hint-function-err")),
+        FuncOpCode::JsonParse => get_sig_hint(sig, fl!(crate::LANGUAGE_LOADER, "hint-function-jsonparse")),
+        FuncOpCode::JsonStr => get_sig_hint(sig, fl!(crate::LANGUAGE_LOADER, "hint-function-jsonstr")),
         _ => None,
     }
 }
@@ -689,6 +691,8 @@ pub fn get_statement_hover(stmt: &StatementDefinition) -> Option<Hover> {
         }
         OpCode::ErrClr => get_sig_hint(sig, fl!(LANGUAGE_LOADER, "hint-statement-errclr")),
         OpCode::OnError => get_sig_hint(sig, fl!(LANGUAGE_LOADER, "hint-statement-on-error")),
+        OpCode::Watchdog => get_sig_hint(sig, fl!(LANGUAGE_LOADER, "hint-statement-watchdog")),
+        OpCode::JsonRead => get_sig_hint(sig, fl!(LANGUAGE_LOADER, "hint-statement-jsonread")),
         _ => None,
     }
 }
@@ -699,7 +703,8 @@ mod test {
 
     #[test]
     fn test_function_translations() {
-        for f in FUNCTION_DEFINITIONS.iter() {
+        // Names like `<each item>` are only what the compiler lowers to, nobody hovers them.
+        for f in FUNCTION_DEFINITIONS.iter().filter(|f| !f.name.starts_with('<')) {
             if let FunctionSignature::FixedParameters(_) = f.signature {
                 assert!(super::get_function_hover(f).is_some(), "Function {:?} failed", f.opcode);
             }
@@ -9,13 +9,13 @@ use icy_board_engine::ast::{
     PredefinedCallStatement, ProcedureCallStatement, RecordLiteralExpression, walk_function_call_expression, walk_function_declaration,
     walk_function_implementation, walk_predefined_call_statement, walk_procedure_call_statement, walk_variable_declaration_statement,
 };
-use icy_board_engine::compiler::{CompilationErrorType, CompilationWarningType, workspace::CompilerData, workspace::Workspace};
+use icy_board_engine::compiler::{CompilationErrorType, CompilationWarningType, module_lowering::lower_modules, workspace::CompilerData, workspace::Workspace};
 use icy_board_engine::executable::{FUNCTION_DEFINITIONS, FunctionDefinition, FunctionSignature, LAST_PPL_LANGUAGE_VERSION, OpCode, VariableType};
 use icy_board_engine::formatting::FormattingVisitor;
 use icy_board_engine::icy_board::read_data_with_encoding_detection;
 use icy_board_engine::parser::lexer::{LexingErrorType, Spanned, Token};
 use icy_board_engine::parser::{
-    Encoding, ErrorReporter, ParserErrorType, ParserWarningType, UserTypeRegistry, parse_ast_with_predeclared_types, preparse_type_declarations,
+    Encoding, ErrorContainer, ErrorReporter, ParserErrorType, ParserWarningType, UserTypeRegistry, parse_ast_with_predeclared_types, preparse_type_declarations,
 };
 use icy_board_engine::semantic::{FunctionDeclaration, ReferenceType, SemanticVisitor};
 use ppl_lsp::completion::get_completion;
@@ -66,6 +66,34 @@ fn loose_workspace() -> Workspace {
     workspace
 }
 
+/// The packages the workspace depends on. One that is missing or depends on the
+/// workspace itself is reported on the manifest, and the workspace is looked at without them.
+fn dependency_packages(workspace: &Workspace, errors: &Arc<Mutex<ErrorReporter>>) -> Vec<(Workspace, Vec<PathBuf>)> {
+    match workspace.dependency_packages() {
+        Ok(packages) => packages,
+        Err(error) => {
+            errors.lock().unwrap().errors.push(ErrorContainer {
+                error,
+                span: 0..0,
+                file_name: workspace.file_name.clone(),
+            });
+            Vec::new()
+        }
+    }
+}
+
+/// A dependency adds only its modules and types, anything else in it is
+/// reported and left out the way `pplc` does.
+fn keep_shared(ast: &mut Ast, errors: &Arc<Mutex<ErrorReporter>>) {
+    for span in ast.unshared_spans() {
+        errors
+            .lock()
+            .unwrap()
+            .report_error_file(ast.file_name.clone(), span, ParserErrorType::NotSharedByDependency);
+    }
+    ast.retain_shared();
+}
+
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
@@ -814,26 +842,37 @@ impl Backend {
             let errors = Arc::new(Mutex::new(ErrorReporter::default()));
             let registry = UserTypeRegistry::icy_board_registry();
             let mut sources = Vec::new();
-            for file in ws.files() {
+            // A dependency is parsed the way its own manifest says.
+            let dependencies = dependency_packages(&ws, &errors);
+            let files = ws.files().into_iter().map(|file| (file, &ws, false));
+            let dependency_files = dependencies
+                .iter()
+                .flat_map(|(package, files)| files.iter().map(move |file| (file.clone(), package, true)));
+            for (file, package, is_dependency) in files.chain(dependency_files) {
                 let Ok(data) = std::fs::read(&file) else {
                     continue;
                 };
                 let Ok(content) = read_data_with_encoding_detection(&data) else {
                     continue;
                 };
-                preparse_type_declarations(file.clone(), errors.clone(), &content, &registry, Encoding::Utf8, &ws);
-                sources.push((file, content));
+                preparse_type_declarations(file.clone(), errors.clone(), &content, &registry, Encoding::Utf8, package);
+                sources.push((file, package, is_dependency, content));
             }
 
             let mut asts = Vec::new();
-            for (file, content) in sources {
-                let ast = parse_ast_with_predeclared_types(file.clone(), errors.clone(), &content, &registry, Encoding::Utf8, &ws);
+            for (file, package, is_dependency, content) in sources {
+                let mut ast = parse_ast_with_predeclared_types(file.clone(), errors.clone(), &content, &registry, Encoding::Utf8, package);
+                if is_dependency {
+                    keep_shared(&mut ast, &errors);
+                }
                 asts.push(ast);
             }
 
+            let lowered = lower_modules(&asts.iter().collect::<Vec<_>>(), &errors);
             let mut semantic_visitor = SemanticVisitor::new(&ws, errors, registry);
-            for ast in asts {
-                ast.visit(&mut semantic_visitor);
+            for (ast, lowered) in asts.into_iter().zip(lowered) {
+                semantic_visitor.errors.lock().unwrap().set_file_name(&ast.file_name);
+                lowered.visit(&mut semantic_visitor);
                 if let Ok(uri) = Url::from_file_path(&ast.file_name) {
                     self.workspace_map.insert(uri, ast);
                 }
@@ -871,7 +910,13 @@ impl Backend {
                 let registry = UserTypeRegistry::icy_board_registry();
                 let mut sources = Vec::new();
 
-                for file in workspace.files() {
+                // A dependency is parsed the way its own manifest says.
+                let dependencies = dependency_packages(&workspace, &errors);
+                let files = workspace.files().into_iter().map(|file| (file, &*workspace, false));
+                let dependency_files = dependencies
+                    .iter()
+                    .flat_map(|(package, files)| files.iter().map(move |file| (file.clone(), package, true)));
+                for (file, package, is_dependency) in files.chain(dependency_files) {
                     let Ok(cur_uri) = Url::from_file_path(&file) else {
                         continue;
                     };
@@ -888,20 +933,24 @@ impl Backend {
                         };
                         content
                     };
-                    preparse_type_declarations(file.clone(), errors.clone(), &content, &registry, Encoding::Utf8, &workspace);
-                    sources.push((file, cur_uri, content));
+                    preparse_type_declarations(file.clone(), errors.clone(), &content, &registry, Encoding::Utf8, package);
+                    sources.push((file, cur_uri, package, is_dependency, content));
                 }
 
                 let mut asts = Vec::new();
-                for (file, cur_uri, content) in sources {
-                    let ast = parse_ast_with_predeclared_types(file, errors.clone(), &content, &registry, Encoding::Utf8, &workspace);
+                for (file, cur_uri, package, is_dependency, content) in sources {
+                    let mut ast = parse_ast_with_predeclared_types(file, errors.clone(), &content, &registry, Encoding::Utf8, package);
+                    if is_dependency {
+                        keep_shared(&mut ast, &errors);
+                    }
                     asts.push((cur_uri, ast));
                 }
 
+                let lowered = lower_modules(&asts.iter().map(|(_, ast)| ast).collect::<Vec<_>>(), &errors);
                 let mut semantic_visitor = SemanticVisitor::new(&workspace, errors, registry);
-                for (cur_uri, ast) in asts {
+                for ((cur_uri, ast), lowered) in asts.into_iter().zip(lowered) {
                     semantic_visitor.errors.lock().unwrap().set_file_name(&ast.file_name);
-                    ast.visit(&mut semantic_visitor);
+                    lowered.visit(&mut semantic_visitor);
                     self.workspace_map.insert(cur_uri, ast);
                 }
                 semantic_visitor.finish();
@@ -922,8 +971,11 @@ impl Backend {
             preparse_type_declarations(path.clone(), errors.clone(), &params.text, &reg, Encoding::Utf8, &workspace);
             let ast = parse_ast_with_predeclared_types(path, errors.clone(), &params.text, &reg, Encoding::Utf8, &workspace);
 
+            let lowered = lower_modules(&[&ast], &errors);
             let mut semantic_visitor = SemanticVisitor::new(&workspace, errors, reg);
-            ast.visit(&mut semantic_visitor);
+            for lowered in &lowered {
+                lowered.visit(&mut semantic_visitor);
+            }
             semantic_visitor.finish();
 
             self.add_diagnostics(&semantic_visitor, &uri, params.version).await;
@@ -942,6 +994,10 @@ impl Backend {
         for uri in self.ast_map.lock().unwrap().keys() {
             diagnostics.insert(uri.clone(), Vec::new());
         }
+        // A dependency that could not be loaded is reported on the manifest.
+        if let Ok(uri) = Url::from_file_path(&self.workspace.lock().unwrap().file_name) {
+            diagnostics.insert(uri, Vec::new());
+        }
 
         let mut ropes: HashMap<Url, Rope> = HashMap::new();
         {
@@ -2,7 +2,7 @@
 //! and hover can say what a record or a board object holds.
 
 use icy_board_engine::{
-    executable::{FUNCTION_DEFINITIONS, VariableType},
+    executable::{CollectionMember, FUNCTION_DEFINITIONS, VariableType},
     parser::{UserTypeRegistry, is_user_declared_type},
     semantic::{ReferenceType, SemanticVisitor},
 };
@@ -74,6 +74,11 @@ pub fn type_of_member(registry: &UserTypeRegistry, var_type: VariableType, membe
     };
     let member = unicase::Ascii::new(member.to_string());
 
+    if let Some((kind, _)) = registry.get_collection(var_type) {
+        let member = CollectionMember::from_name(&member)?;
+        member.parameter_count(kind)?;
+        return Some(member.return_type());
+    }
     if is_user_declared_type(id) {
         let def = registry.get_user_type_from_id(id)?;
         return def.field_type(def.field_index(&member)?);
@@ -98,6 +103,18 @@ pub fn type_of_chain(visitor: &SemanticVisitor, path: &[String]) -> Option<Varia
 
 /// Everything that may follow a `.` on a value of this type.
 pub fn members_of(registry: &UserTypeRegistry, var_type: VariableType) -> Vec<Member> {
+    if let Some((kind, element)) = registry.get_collection(var_type) {
+        let element = type_name(registry, element);
+        return CollectionMember::ALL
+            .into_iter()
+            .filter(|member| member.parameter_count(kind).is_some())
+            .map(|member| Member {
+                name: member.name().to_string(),
+                detail: member.signature(kind, &element),
+                kind: MemberKind::Method,
+            })
+            .collect();
+    }
     let VariableType::UserData(id) = var_type else {
         return Vec::new();
     };
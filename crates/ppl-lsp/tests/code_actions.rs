@@ -688,7 +688,7 @@ fn upgrade(server: &mut Server, uri: &str, source: &str) -> (Value, Value) {
 fn upgrade_action<'a>(diagnostics: &Value, actions: &'a Value) -> &'a Value {
     actions
         .as_array()
-        .and_then(|actions| actions.iter().find(|action| action["title"] == "Upgrade file to language version 400"))
+        .and_then(|actions| actions.iter().find(|action| action["title"] == "Upgrade file to language version 404"))
         .unwrap_or_else(|| panic!("diagnostics={diagnostics}, actions={actions}"))
 }
 
@@ -714,7 +714,7 @@ fn an_old_file_can_be_upgraded_to_the_current_language() {
     assert!(texts.contains(&json!("^")), "{texts:?}");
     assert!(texts.contains(&json!("BREAK")), "{texts:?}");
     assert!(texts.contains(&json!("EXIT")), "{texts:?}");
-    assert!(texts.contains(&json!(";$LANGVERSION 400")), "{texts:?}");
+    assert!(texts.contains(&json!(";$LANGVERSION 404")), "{texts:?}");
 }
 
 #[test]
@@ -85,6 +85,16 @@ fn a_board_object_offers_fields_and_methods() {
     assert!(items.contains(&"GetDoor".to_string()), "{items:?}");
 }
 
+#[test]
+fn a_collection_offers_the_members_of_its_kind() {
+    let items = complete("LIST OF STRING names\nnames.");
+    assert_eq!(items, vec!["Add", "Insert", "Remove", "Contains", "Clear"]);
+
+    // A map is only filled under a key.
+    let items = complete("MAP OF STRING TO INTEGER scores\nscores.");
+    assert_eq!(items, vec!["Insert", "Remove", "Contains", "Clear"]);
+}
+
 #[test]
 fn a_function_answering_an_object_offers_its_members() {
     let items = complete("ConfInfo(CurConf()).");
@@ -9,13 +9,16 @@ use icy_board_engine::{
         PPECompiler,
         workspace::{CompilerData, Package, Workspace},
     },
-    executable::{LAST_PPL_LANGUAGE_VERSION, SUPPORTED_PPE_VERSIONS, SUPPORTED_PPL_LANGUAGE_VERSIONS, language_version_from_env},
+    executable::{
+        DebugInfo, LAST_PPL_LANGUAGE_VERSION, SUPPORTED_PPE_VERSIONS, SUPPORTED_PPL_LANGUAGE_VERSIONS, language_version_for_runtime, language_version_from_env,
+    },
     formatting::{FormattingVisitor, StringFormattingBackend},
     icy_board::{read_with_encoding_detection, write_atomic},
     parser::{
-        Encoding, ErrorReporter, UserTypeRegistry, lexer::scan_language_version, load_with_encoding, parse_ast_with_predeclared_types,
+        Encoding, ErrorReporter, ParserErrorType, UserTypeRegistry, lexer::scan_language_version, load_with_encoding, parse_ast_with_predeclared_types,
         preparse_type_declarations,
     },
+    vm::Permissions,
 };
 
 use crossterm::{
@@ -34,6 +37,8 @@ use std::{
     sync::{Arc, Mutex, OnceLock},
 };
 
+mod test_runner;
+
 #[derive(FromArgs)]
 /// PCBoard Programming Language Compiler  
 struct Cli {
@@ -53,11 +58,11 @@ struct Cli {
     #[argh(switch)]
     mono: bool,
 
-    /// version number for the compiled PPE, valid: 100, 200, 300, 310, 320, 330, 340, 400, 401, 402 (default)
+    /// version number for the compiled PPE, valid: 100, 200, 300, 310, 320, 330, 340, 400, 401, 402, 403, 404 (default)
     #[argh(option)]
     runtime: Option<u16>,
 
-    /// language version (defaults to the manifest, PPL_LANG_VERSION, then runtime capped at 400)
+    /// language version (defaults to the manifest, PPL_LANG_VERSION, then the newest language the runtime runs)
     #[argh(option)]
     lang_version: Option<u16>,
 
@@ -81,10 +86,30 @@ struct Cli {
     #[argh(switch)]
     stdout: bool,
 
+    /// write debug information (source lines and names) next to the PPE
+    #[argh(switch, short = 'g')]
+    debug: bool,
+
     /// checks source/package for errors without compiling
     #[argh(switch)]
     check: bool,
 
+    /// builds the package and runs the tests in its tests directory against a scratch board
+    #[argh(switch)]
+    test: bool,
+
+    /// with --test, write the snapshots from what the tests print instead of comparing
+    #[argh(switch)]
+    update: bool,
+
+    /// with --test, also write a JUnit XML report to this file
+    #[argh(option)]
+    junit: Option<PathBuf>,
+
+    /// with --test, write the source lines the tests ran to this lcov file (implies --debug)
+    #[argh(option)]
+    coverage: Option<PathBuf>,
+
     /// prints the effective compiler configuration without compiling
     #[argh(switch)]
     print_config: bool,
@@ -159,7 +184,11 @@ fn print_diff_line(line: usize, sign: char, text: impl std::fmt::Display, color:
 }
 
 fn main() {
-    let arguments: Cli = argh::from_env();
+    let mut arguments: Cli = argh::from_env();
+    // Coverage is mapped back to the source through the debug information.
+    if arguments.coverage.is_some() {
+        arguments.debug = true;
+    }
     let _ = COLOR.set(decide_color(&arguments));
     if arguments.version {
         println!("pplc {}", *VERSION);
@@ -230,6 +259,22 @@ fn main() {
         std::process::exit(1);
     }
 
+    if arguments.test {
+        if file_name.extension().is_none_or(|extension| extension != "toml") {
+            eprintln!("ERROR: --test runs the tests of a package, point it at a ppl.toml.");
+            std::process::exit(2);
+        }
+        match test_runner::run_tests(&file_name, &arguments) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(err) => {
+                eprintln!("ERROR: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     if file_name.extension().is_some_and(|extension| extension == "toml") {
         if arguments.print_config || arguments.print_config_json {
             if let Err(err) = print_config(&file_name, &arguments, Encoding::Detect) {
@@ -430,7 +475,7 @@ fn resolve_config(workspace: &Workspace, arguments: &Cli, encoding: Encoding, pr
     } else if let Some(version) = language_version_from_env()? {
         (version, "environment")
     } else {
-        (runtime.min(LAST_PPL_LANGUAGE_VERSION), "runtime")
+        (language_version_for_runtime(runtime), "runtime")
     };
 
     let sources = workspace.files().into_iter().map(|file| absolute(&file)).collect();
@@ -574,6 +619,15 @@ fn compile_toml(file_name: &PathBuf, arguments: &Cli) -> Res<()> {
 
     let out_file_name = target_path.join(workspace.package.name()).with_extension("ppe");
     compile_files(arguments, encoding, &mut workspace, &out_file_name)?;
+    if !arguments.check && !arguments.disassemble {
+        // A package that stopped asking for permissions must not keep the old ones.
+        let permissions_file = Permissions::path_for(&out_file_name);
+        match &workspace.permissions {
+            Some(permissions) => permissions.save(&permissions_file)?,
+            None if permissions_file.exists() => fs::remove_file(&permissions_file)?,
+            None => {}
+        }
+    }
     println!("Copying data files...");
     if let Some(data) = &workspace.data {
         if let Some(art_files) = &data.art_files {
@@ -650,11 +704,23 @@ fn compile_files(arguments: &Cli, encoding: Encoding, workspace: &mut Workspace,
     }
     let mut exit_code = 0;
 
+    // A dependency is parsed the way its own manifest says, not the way ours does.
+    let mut dependencies = workspace.dependency_packages()?;
+    for (package, _) in &mut dependencies {
+        apply_declared_language_version(package, encoding)?;
+    }
     for src_file in workspace.files() {
         if let Ok(src) = load_with_encoding(&src_file, encoding) {
             preparse_type_declarations(src_file, errors.clone(), &src, &reg, encoding, workspace);
         }
     }
+    for (package, files) in &dependencies {
+        for src_file in files {
+            if let Ok(src) = load_with_encoding(src_file, encoding) {
+                preparse_type_declarations(src_file.clone(), errors.clone(), &src, &reg, encoding, package);
+            }
+        }
+    }
 
     for src_file in workspace.files() {
         match load_with_encoding(&src_file, encoding) {
@@ -735,6 +801,32 @@ fn compile_files(arguments: &Cli, encoding: Encoding, workspace: &mut Workspace,
         return Ok(());
     }
 
+    // The modules of a dependency are compiled in, but its sources are not ours to format.
+    for (package, files) in &dependencies {
+        for src_file in files {
+            match load_with_encoding(src_file, encoding) {
+                Ok(src) => {
+                    let mut ast = parse_ast_with_predeclared_types(src_file.clone(), errors.clone(), &src, &reg, encoding, package);
+                    for span in ast.unshared_spans() {
+                        errors
+                            .lock()
+                            .unwrap()
+                            .report_error_file(src_file.clone(), span, ParserErrorType::NotSharedByDependency);
+                    }
+                    ast.retain_shared();
+                    asts.push((ast, src));
+                    if check_errors(errors.clone(), arguments, &asts) {
+                        std::process::exit(1);
+                    }
+                }
+                Err(err) => {
+                    print_error(err);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
     // --check runs the compiler for its diagnostics, it just keeps the result.
     if !arguments.check {
         println!("Compiling...");
@@ -770,6 +862,11 @@ fn compile_files(arguments: &Cli, encoding: Encoding, workspace: &mut Workspace,
             let bin = executable.to_buffer()?;
             //let len = bin.len();
             write_atomic(out_file_name, &bin)?;
+            // An older one is ignored at runtime, its code hash no longer matches.
+            if arguments.debug {
+                let sources: Vec<&str> = asts.iter().map(|(_, src)| src.as_str()).collect();
+                compiler.create_debug_info(&executable, &sources).save(&DebugInfo::path_for(out_file_name))?;
+            }
             //let lines = src.lines().count();
             //println!("{} lines, {} chars compiled. {} bytes written to {:?}", lines, src.len(), len, &out_file_name);
         }
@@ -0,0 +1,459 @@
+//! `pplc --test`: runs a package's PPEs against a scratch board and compares what
+//! they print with the snapshots kept beside the tests.
+//!
+//! A test is a name in the package's `tests` directory. `NAME.pps` is compiled
+//! and run, without one the package's own PPE is. `NAME.input` is what the
+//! caller types, `NAME.ans` the output as it was sent and `NAME.txt` the screen
+//! the caller is left looking at. `board.toml` there sets up the board.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crossterm::style::Color;
+use icy_board_engine::{
+    Res,
+    compiler::workspace::Workspace,
+    executable::DebugInfo,
+    icy_board::{
+        IcyBoard,
+        bbs::BBS,
+        commands::CommandList,
+        conferences::Conference,
+        icb_config::DisplayNewsBehavior,
+        icb_text::DEFAULT_DISPLAY_TEXT,
+        message_area::{AreaList, MessageArea},
+        state::IcyBoardState,
+        user_base::User,
+        write_atomic,
+        xfer_protocols::SupportedProtocols,
+    },
+    parser::Encoding,
+    vm::{ProfileMode, Profiler},
+};
+use icy_net::{Connection, ConnectionType, channel::ChannelConnection, termcap_detect::TerminalCaps};
+use serde::Deserialize;
+
+use crate::{Cli, colored, compile_files, compile_toml, print_diff_line};
+
+const TEST_DIR: &str = "tests";
+const BOARD_FIXTURE: &str = "board.toml";
+
+/// A PPE that waits for more than its script types would wait forever, one in a
+/// loop would run forever.
+const TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The board a test runs on. Everything not named here is a fresh board's default.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct BoardFixture {
+    /// Who runs the PPE, the first user when not given.
+    caller: Option<String>,
+    users: Vec<FixtureUser>,
+    conferences: Vec<FixtureConference>,
+}
+
+#[derive(Deserialize)]
+struct FixtureUser {
+    name: String,
+    #[serde(default = "default_security_level")]
+    security_level: u8,
+}
+
+fn default_security_level() -> u8 {
+    10
+}
+
+#[derive(Deserialize)]
+struct FixtureConference {
+    name: String,
+}
+
+impl BoardFixture {
+    fn load(test_dir: &Path) -> Res<Self> {
+        let path = test_dir.join(BOARD_FIXTURE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        toml::from_str(&fs::read_to_string(&path)?).map_err(|err| format!("{}: {err}", path.display()).into())
+    }
+
+    fn build(&self, board_dir: &Path) -> IcyBoard {
+        let mut board = IcyBoard::new();
+        // A fresh board points its paths at plain names, which would be wherever pplc was started.
+        board.root_path = board_dir.to_path_buf();
+        board.file_name = board_dir.join("icboard.toml");
+        board.config.paths.statistics_file = PathBuf::from("statistics.toml");
+        board.resolve_paths();
+
+        board.config.switches.display_news_behavior = DisplayNewsBehavior::Never;
+        board.config.switches.scan_new_blt = false;
+        board.commands = CommandList::new();
+        board.protocols = SupportedProtocols::generate_pcboard_defaults();
+        board.default_display_text = DEFAULT_DISPLAY_TEXT.clone();
+
+        if self.users.is_empty() {
+            board.users.new_user(User {
+                name: "SYSOP".to_string(),
+                security_level: 255,
+                protocol: "Z".to_string(),
+                ..Default::default()
+            });
+        }
+        for user in &self.users {
+            board.users.new_user(User {
+                name: user.name.clone(),
+                security_level: user.security_level,
+                protocol: "Z".to_string(),
+                ..Default::default()
+            });
+        }
+
+        let names: Vec<&str> = if self.conferences.is_empty() {
+            vec!["Main Board"]
+        } else {
+            self.conferences.iter().map(|conference| conference.name.as_str()).collect()
+        };
+        for (i, name) in names.into_iter().enumerate() {
+            board.conferences.push(Conference {
+                name: name.to_string(),
+                areas: Some(AreaList::new(vec![MessageArea {
+                    name: "General".to_string(),
+                    path: board_dir.join(format!("msgs{i}")),
+                    ..Default::default()
+                }])),
+                ..Default::default()
+            });
+        }
+        board
+    }
+}
+
+struct TestCase {
+    name: String,
+    /// Compiled by the runner, or the package's PPE when there is none.
+    source: Option<PathBuf>,
+    dir: PathBuf,
+}
+
+impl TestCase {
+    fn file(&self, extension: &str) -> PathBuf {
+        self.dir.join(&self.name).with_extension(extension)
+    }
+}
+
+enum Outcome {
+    Passed,
+    Updated,
+    Failed(String),
+}
+
+struct TestResult {
+    name: String,
+    outcome: Outcome,
+    time: Duration,
+}
+
+/// Runs the tests of the package `manifest` belongs to and answers whether all passed.
+pub fn run_tests(manifest: &Path, arguments: &Cli) -> Res<bool> {
+    let package_dir = manifest.parent().unwrap_or_else(|| Path::new("."));
+    let test_dir = package_dir.join(TEST_DIR);
+    let cases = find_tests(&test_dir)?;
+    if cases.is_empty() {
+        println!("No tests in {}", test_dir.display());
+        return Ok(true);
+    }
+
+    compile_toml(&manifest.to_path_buf(), arguments)?;
+    let workspace = Workspace::load(manifest)?;
+    let target_path = workspace.target_path(arguments.runtime.unwrap_or(workspace.runtime()));
+    let package_ppe = target_path.join(workspace.package.name()).with_extension("ppe");
+
+    let fixture = BoardFixture::load(&test_dir)?;
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    let mut coverage = arguments.coverage.as_ref().map(|_| Profiler::new(ProfileMode::Coverage));
+
+    println!();
+    println!("running {} tests", cases.len());
+    let mut results = Vec::new();
+    for case in &cases {
+        let start = Instant::now();
+        let outcome = match prepare(case, manifest, &target_path, &package_ppe, arguments) {
+            Ok(ppe) => {
+                let result = runtime.block_on(run_case(case, &ppe, &fixture, &mut coverage));
+                if case.source.is_some() {
+                    remove_compiled(&ppe);
+                }
+                match result {
+                    Ok((ansi, screen)) => compare(case, &ansi, &screen, arguments.update)?,
+                    Err(err) => Outcome::Failed(err.to_string()),
+                }
+            }
+            Err(err) => Outcome::Failed(err.to_string()),
+        };
+        let result = TestResult {
+            name: case.name.clone(),
+            outcome,
+            time: start.elapsed(),
+        };
+        report(&result);
+        results.push(result);
+    }
+
+    let failed = results.iter().filter(|result| matches!(result.outcome, Outcome::Failed(_))).count();
+    println!();
+    println!(
+        "test result: {}. {} passed; {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        results.len() - failed,
+        failed
+    );
+    if let Some(junit) = &arguments.junit {
+        write_atomic(junit, junit_report(workspace.package.name(), &results).as_bytes())?;
+    }
+    if let (Some(path), Some(coverage)) = (&arguments.coverage, &coverage) {
+        write_atomic(path, coverage.lcov().as_bytes())?;
+        let lines: Vec<u64> = coverage.lines.values().flat_map(|lines| lines.values().copied()).collect();
+        println!(
+            "coverage: {} of {} lines run, written to {}",
+            lines.iter().filter(|hits| **hits > 0).count(),
+            lines.len(),
+            path.display()
+        );
+    }
+    Ok(failed == 0)
+}
+
+fn find_tests(test_dir: &Path) -> Res<Vec<TestCase>> {
+    let mut cases: Vec<TestCase> = Vec::new();
+    if !test_dir.is_dir() {
+        return Ok(cases);
+    }
+    for entry in fs::read_dir(test_dir)? {
+        let path = entry?.path();
+        let Some(extension) = path.extension().and_then(|ext| ext.to_str()) else {
+            continue;
+        };
+        if !matches!(extension, "pps" | "input") {
+            continue;
+        }
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let source = (extension == "pps").then(|| path.clone());
+        match cases.iter_mut().find(|case| case.name == name) {
+            Some(case) => case.source = case.source.take().or(source),
+            None => cases.push(TestCase {
+                name,
+                source,
+                dir: test_dir.to_path_buf(),
+            }),
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Compiles a test's own PPE with the package's settings. It lands next to the
+/// package's, so the data files it reads are where it expects them.
+fn prepare(case: &TestCase, manifest: &Path, target_path: &Path, package_ppe: &Path, arguments: &Cli) -> Res<PathBuf> {
+    let Some(source) = &case.source else {
+        return Ok(package_ppe.to_path_buf());
+    };
+    let mut workspace = Workspace::load(manifest)?;
+    workspace.hard_coded_files = Some(vec![source.clone()]);
+    let out_file_name = target_path.join(format!("test_{}", case.name)).with_extension("ppe");
+    compile_files(arguments, Encoding::Detect, &mut workspace, &out_file_name)?;
+    Ok(out_file_name)
+}
+
+/// A test's own PPE is only there while it runs.
+fn remove_compiled(ppe: &Path) {
+    let _ = fs::remove_file(ppe);
+    let _ = fs::remove_file(DebugInfo::path_for(ppe));
+}
+
+/// Runs the PPE the way `icboard --ppe` does, minus the terminal: a local
+/// session with the fixture's caller. Answers what was sent and what is on the screen.
+async fn run_case(case: &TestCase, ppe: &Path, fixture: &BoardFixture, coverage: &mut Option<Profiler>) -> Res<(String, String)> {
+    let board_dir = std::env::temp_dir().join(format!("pplc-test-{}-{}", std::process::id(), case.name));
+    fs::create_dir_all(&board_dir)?;
+    let result = run_on_board(case, ppe, fixture, &board_dir, coverage).await;
+    let _ = fs::remove_dir_all(&board_dir);
+    result
+}
+
+async fn run_on_board(case: &TestCase, ppe: &Path, fixture: &BoardFixture, board_dir: &Path, coverage: &mut Option<Profiler>) -> Res<(String, String)> {
+    let board = Arc::new(tokio::sync::Mutex::new(fixture.build(board_dir)));
+    let caller = match &fixture.caller {
+        Some(name) => board
+            .lock()
+            .await
+            .users
+            .find_by_name(name)
+            .ok_or_else(|| format!("{BOARD_FIXTURE}: the caller {name} is not one of its users"))?,
+        None => 0,
+    };
+
+    let bbs = Arc::new(tokio::sync::Mutex::new(BBS::new(1)));
+    let node = bbs.lock().await.create_new_node(ConnectionType::Channel).await;
+    let node_state = bbs.lock().await.open_connections.clone();
+    let (mut caller_end, board_end) = ChannelConnection::create_pair();
+    let mut state = IcyBoardState::new(bbs, board, node_state, node, Box::new(board_end)).await;
+
+    let input_file = case.file("input");
+    if input_file.exists() {
+        // Typed ahead: the board reads it as it asks.
+        let input = fs::read_to_string(&input_file)?.replace("\r\n", "\n");
+        caller_end.send(input.as_bytes()).await?;
+    }
+
+    state.set_current_user(caller, false).await?;
+    let conference = state.session.current_user.as_ref().map_or(0, |user| user.last_conference);
+    if !state.set_current_conference(conference).await? {
+        state.set_current_conference(0).await?;
+    }
+    state.session.disp_options.force_count_lines();
+    state.session.is_local = true;
+    state.session.term_caps = TerminalCaps::LOCAL;
+
+    // Every test adds its lines to the same coverage.
+    state.ppe_profiler = coverage.take();
+    // The timeout stops a PPE waiting for input, the deadline one that never waits.
+    let deadline = Instant::now() + TEST_TIMEOUT;
+    state.ppe_deadline = Some(deadline);
+    let finished = tokio::time::timeout(TEST_TIMEOUT, state.run_ppe(&ppe, None)).await;
+    *coverage = state.ppe_profiler.take();
+    if finished.is_err() || Instant::now() >= deadline {
+        return Err(format!(
+            "still running after {}s, in a loop or waiting for input the script does not have?",
+            TEST_TIMEOUT.as_secs()
+        )
+        .into());
+    }
+    let screen = state.display_screen().text();
+    drop(state);
+
+    let mut sent = Vec::new();
+    let mut buffer = [0; 4096];
+    while let Ok(size) = caller_end.try_read(&mut buffer).await
+        && size > 0
+    {
+        sent.extend_from_slice(&buffer[..size]);
+    }
+    Ok((String::from_utf8_lossy(&sent).replace("\r\n", "\n"), screen))
+}
+
+fn compare(case: &TestCase, ansi: &str, screen: &str, update: bool) -> Res<Outcome> {
+    let snapshots = [(case.file("ans"), ansi), (case.file("txt"), screen)];
+    if update {
+        let mut changed = false;
+        for (path, actual) in &snapshots {
+            if fs::read_to_string(path).ok().as_deref() != Some(*actual) {
+                write_atomic(path, actual.as_bytes())?;
+                changed = true;
+            }
+        }
+        return Ok(if changed { Outcome::Updated } else { Outcome::Passed });
+    }
+
+    let mut failures = String::new();
+    for (path, actual) in &snapshots {
+        match fs::read_to_string(path) {
+            Ok(expected) if expected.replace("\r\n", "\n") == *actual => {}
+            Ok(expected) => {
+                let _ = writeln!(failures, "{} differs", path.display());
+                print_snapshot_diff(&case.name, path, &expected.replace("\r\n", "\n"), actual);
+            }
+            Err(_) => {
+                let _ = writeln!(failures, "{} is missing, run with --update to write it", path.display());
+            }
+        }
+    }
+    Ok(if failures.is_empty() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(failures.trim_end().to_string())
+    })
+}
+
+fn print_snapshot_diff(name: &str, path: &Path, expected: &str, actual: &str) {
+    println!();
+    println!("---- {name}: {} ----", path.display());
+    // Escapes would be executed by the terminal rather than shown.
+    let visible = |line: &str| line.replace('\x1B', "\\e");
+    for (i, line) in diff::lines(expected, actual).iter().enumerate() {
+        match line {
+            diff::Result::Left(l) => print_diff_line(i + 1, '-', visible(l), Color::Red),
+            diff::Result::Right(r) => print_diff_line(i + 1, '+', visible(r), Color::Green),
+            diff::Result::Both(_, _) => {}
+        }
+    }
+}
+
+fn report(result: &TestResult) {
+    let (text, color) = match &result.outcome {
+        Outcome::Passed => ("ok", Color::Green),
+        Outcome::Updated => ("updated", Color::Yellow),
+        Outcome::Failed(_) => ("FAILED", Color::Red),
+    };
+    if colored() {
+        crossterm::execute!(
+            std::io::stdout(),
+            crossterm::style::Print(format!("test {} ... ", result.name)),
+            crossterm::style::SetForegroundColor(color),
+            crossterm::style::Print(text),
+            crossterm::style::SetAttribute(crossterm::style::Attribute::Reset),
+            crossterm::style::Print("\n"),
+        )
+        .unwrap();
+    } else {
+        println!("test {} ... {text}", result.name);
+    }
+    if let Outcome::Failed(message) = &result.outcome {
+        for line in message.lines() {
+            println!("    {line}");
+        }
+    }
+}
+
+fn junit_report(package: &str, results: &[TestResult]) -> String {
+    let failures = results.iter().filter(|result| matches!(result.outcome, Outcome::Failed(_))).count();
+    let total: f64 = results.iter().map(|result| result.time.as_secs_f64()).sum();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites><testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+        xml_escape(package),
+        results.len(),
+        failures,
+        total
+    );
+    for result in results {
+        let _ = write!(
+            xml,
+            "  <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+            xml_escape(package),
+            xml_escape(&result.name),
+            result.time.as_secs_f64()
+        );
+        match &result.outcome {
+            Outcome::Failed(message) => {
+                let _ = writeln!(xml, "><failure message=\"{}\"/></testcase>", xml_escape(message));
+            }
+            _ => xml.push_str("/>\n"),
+        }
+    }
+    xml.push_str("</testsuite></testsuites>\n");
+    xml
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#10;")
+}
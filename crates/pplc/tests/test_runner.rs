@@ -0,0 +1,71 @@
+use std::{fs, path::Path, process::Command};
+
+fn pplc() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_pplc"));
+    command.env_remove("PPL_LANG_VERSION");
+    command
+}
+
+fn package(dir: &Path) -> std::path::PathBuf {
+    let package = dir.join("package");
+    let init = pplc().args(["--init", "--lang-version", "400"]).arg(&package).output().unwrap();
+    assert!(init.status.success(), "{}", String::from_utf8_lossy(&init.stderr));
+    fs::write(
+        package.join("src/main.pps"),
+        "STRING name\nINPUT \"Name\", name\nNEWLINE\nPRINTLN \"Hello \", name\n",
+    )
+    .unwrap();
+    fs::create_dir_all(package.join("tests")).unwrap();
+    fs::write(package.join("tests/greeting.input"), "Bob\n").unwrap();
+    fs::write(package.join("tests/own.pps"), "PRINTLN \"From the test\"\n").unwrap();
+    package
+}
+
+#[test]
+fn snapshots_are_written_and_then_compared() {
+    let dir = tempfile::tempdir().unwrap();
+    let package = package(dir.path());
+
+    let update = pplc().current_dir(&package).args(["--test", "--update"]).output().unwrap();
+    assert!(update.status.success(), "{}", String::from_utf8_lossy(&update.stdout));
+    let screen = fs::read_to_string(package.join("tests/greeting.txt")).unwrap();
+    assert!(screen.contains("Hello Bob"), "{screen}");
+    assert!(fs::read_to_string(package.join("tests/own.txt")).unwrap().contains("From the test"));
+    assert!(package.join("tests/greeting.ans").is_file());
+
+    let compare = pplc().current_dir(&package).arg("--test").output().unwrap();
+    let text = String::from_utf8_lossy(&compare.stdout);
+    assert!(compare.status.success(), "{text}");
+    assert!(text.contains("test greeting ... ok"), "{text}");
+}
+
+#[test]
+fn a_changed_output_fails_and_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let package = package(dir.path());
+    let update = pplc().current_dir(&package).args(["--test", "--update"]).output().unwrap();
+    assert!(update.status.success(), "{}", String::from_utf8_lossy(&update.stdout));
+
+    fs::write(package.join("tests/greeting.input"), "Alice\n").unwrap();
+    let report = dir.path().join("report.xml");
+    let output = pplc().current_dir(&package).arg("--test").arg("--junit").arg(&report).output().unwrap();
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{text}");
+    assert!(text.contains("test greeting ... FAILED"), "{text}");
+    assert!(text.contains("Hello Alice"), "{text}");
+
+    let xml = fs::read_to_string(report).unwrap();
+    assert!(xml.contains("tests=\"2\" failures=\"1\""), "{xml}");
+    assert!(xml.contains("<testcase classname=\"package\" name=\"own\""), "{xml}");
+}
+
+#[test]
+fn a_missing_snapshot_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let package = package(dir.path());
+
+    let output = pplc().current_dir(&package).arg("--test").output().unwrap();
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{text}");
+    assert!(text.contains("run with --update"), "{text}");
+}
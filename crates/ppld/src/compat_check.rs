@@ -114,6 +114,7 @@ fn collect_statement_hits(stmt: &icy_board_engine::executable::PPEStatement, hit
         | PPECommand::Goto(_)
         | PPECommand::Gosub(_)
         | PPECommand::OnError(_)
+        | PPECommand::TryBegin(_)
         | PPECommand::EndFunc
         | PPECommand::EndProc
         | PPECommand::Stop => {}
@@ -8,7 +8,8 @@ use crossterm::style::SetAttribute;
 use crossterm::style::SetForegroundColor;
 use icy_board_engine::ast::OutputFunc;
 use icy_board_engine::ast::output_visitor;
-use icy_board_engine::decompiler::decompile;
+use icy_board_engine::decompiler::decompile_with_debug_info;
+use icy_board_engine::executable::DebugInfo;
 use icy_board_engine::executable::Executable;
 use icy_board_engine::executable::LAST_PPL_LANGUAGE_VERSION;
 use icy_board_engine::executable::PPEScript;
@@ -156,6 +157,9 @@ fn main() {
                 if let Ok(script) = PPEScript::from_ppe_file(&executable) {
                     executable.variable_table.analyze_usage(&script);
                     executable.variable_table.generate_names();
+                    if let Some(debug_info) = DebugInfo::load_for(Path::new(&file_name), &executable) {
+                        executable.variable_table.restore_names(&debug_info);
+                    }
                 }
 
                 executable.print_variable_table();
@@ -166,7 +170,11 @@ fn main() {
             }
 
             let lang_version = arguments.lang_version.or(env_language_version).unwrap_or(LAST_PPL_LANGUAGE_VERSION);
-            match decompile(executable, arguments.raw, lang_version) {
+            let debug_info = DebugInfo::load_for(Path::new(&file_name), &executable);
+            if debug_info.is_some() && !arguments.output {
+                println!("Using the names from {}", DebugInfo::path_for(Path::new(&file_name)).display());
+            }
+            match decompile_with_debug_info(executable, arguments.raw, lang_version, debug_info) {
                 Ok((decompilation, issues)) => {
                     let mut output_visitor: output_visitor::OutputVisitor = output_visitor::OutputVisitor::default();
                     // The source is written for our own pplc, whatever runtime the PPE was built for.
@@ -6,7 +6,7 @@ use std::sync::{Arc, Mutex};
 use icy_board_engine::{
     ast::{Ast, OutputFunc, output_visitor},
     compiler::{PPECompiler, workspace::Workspace},
-    executable::{Executable, LAST_PPE_RUNTIME, LAST_PPL_LANGUAGE_VERSION},
+    executable::{Executable, LAST_PPE_RUNTIME, LAST_PPL_LANGUAGE_VERSION, language_version_for_runtime},
     parser::{Encoding, ErrorReporter, UserTypeRegistry, parse_ast},
 };
 
@@ -154,7 +154,7 @@ fn decompiled_source_settles_after_one_pass() {
         let name = cur_entry.file_name().unwrap().to_string_lossy().to_string();
         let executable = Executable::read_file(&cur_entry.as_os_str(), false).unwrap();
         let runtime = executable.runtime;
-        let lang_version = runtime.min(LAST_PPL_LANGUAGE_VERSION);
+        let lang_version = language_version_for_runtime(runtime);
 
         let mut text = decompile_to_text(executable, lang_version);
         let mut previous = None;
@@ -252,6 +252,31 @@ fn ending_the_program_decompiles_to_exit() {
     assert!(legacy.lines().any(|line| line.trim() == "END"), "no END in:\n{legacy}");
 }
 
+/// A collection keeps its type name, and the loop lowered for FOR EACH is read back
+/// as one.
+#[test]
+fn collections_survive_decompilation() {
+    let source = "LIST OF STRING names\n\
+                  MAP OF STRING TO INTEGER visits\n\
+                  STRING name\n\
+                  names.Add(\"Sysop\")\n\
+                  visits(\"Sysop\") = 1\n\
+                  FOR EACH name IN names\n\
+                  PRINTLN name, visits(name)\n\
+                  NEXT\n";
+
+    let executable = compile_source(source, LAST_PPE_RUNTIME).unwrap();
+    let text = decompile_to_text(executable, LAST_PPL_LANGUAGE_VERSION);
+
+    assert!(text.contains("LIST OF STRING"), "no LIST OF in:\n{text}");
+    assert!(text.contains("MAP OF STRING TO INTEGER"), "no MAP OF in:\n{text}");
+    assert!(text.contains("FOR EACH"), "no FOR EACH in:\n{text}");
+    assert!(!text.contains("<each item>"), "loop left lowered in:\n{text}");
+
+    let rebuilt = compile_source(&text, LAST_PPE_RUNTIME).unwrap_or_else(|e| panic!("does not compile again:\n{text}\n{e}"));
+    assert_eq!(text, decompile_to_text(rebuilt, LAST_PPL_LANGUAGE_VERSION));
+}
+
 /// The source names the language it is written in, so it needs no option to be
 /// compiled again.
 #[test]
@@ -300,6 +325,38 @@ fn repeat_and_loop_survive_decompilation() {
     assert!(!legacy.contains("ENDLOOP"), "LOOP written for a language without it:\n{legacy}");
 }
 
+/// TRY is lowered to a pair of markers around labels and jumps. Leaving the block with
+/// BREAK closes it early, which must not be read back as a block of its own.
+#[test]
+fn try_catch_survives_decompilation() {
+    let source = "ERROR E\n\
+                  INTEGER I\n\
+                  LOOP\n\
+                  I = I + 1\n\
+                  TRY\n\
+                  TRY\n\
+                  IF (I > 3) BREAK\n\
+                  LOADFONT 43, \"nope.fnt\"\n\
+                  CATCH\n\
+                  PRINTLN \"inner\"\n\
+                  ENDTRY\n\
+                  CATCH E\n\
+                  PRINTLN E.Code\n\
+                  ENDTRY\n\
+                  ENDLOOP\n\
+                  PRINTLN I\n";
+
+    let executable = compile_source(source, LAST_PPE_RUNTIME).unwrap();
+    let text = decompile_to_text(executable, LAST_PPL_LANGUAGE_VERSION);
+
+    assert_eq!(text.matches("ENDTRY").count(), 2, "TRY blocks lost in:\n{text}");
+    assert!(text.lines().any(|line| line.trim().starts_with("CATCH ")), "no CATCH variable in:\n{text}");
+    assert!(!text.contains("<try>") && !text.contains("<end try>"), "block left lowered in:\n{text}");
+
+    let rebuilt = compile_source(&text, LAST_PPE_RUNTIME).unwrap_or_else(|e| panic!("does not compile again:\n{text}\n{e}"));
+    assert_eq!(text, decompile_to_text(rebuilt, LAST_PPL_LANGUAGE_VERSION));
+}
+
 /// A record keeps no name in the PPE, so the decompiler invents one. What matters
 /// is that the result still describes the same layout and compiles again.
 #[test]
@@ -3,7 +3,8 @@
  *
  * Covers PPL 1.00 - 4.01 as implemented by IcyBoard: the classic PCBoard
  * statements plus the 3.50 and 4.00 additions (REPEAT/LOOP, brackets, braces,
- * the dot operator, TYPE ... ENDTYPE, record literals and routine parameters).
+ * the dot operator, TYPE ... ENDTYPE, record literals, routine parameters,
+ * LIST and MAP collections, FOR EACH, TRY ... CATCH and MODULE/IMPORT).
  *
  * The language is case insensitive, so every keyword is a case insensitive
  * token. Built-in statements share a single token so that a statement head can
@@ -85,6 +86,7 @@ const BUILTIN_STATEMENTS = [
   'GFXSETPACING', 'MOUSEON', 'MOUSEOFF', 'KEYEVENTS',
   'SETVMARGINS', 'SETHMARGINS', 'RESETVMARGINS', 'RESETHMARGINS', 'RESETMARGINS',
   'SETFONT', 'LOADFONT', 'ERRCLR', 'SETPALETTECOLOR', 'RESETPALETTECOLOR', 'RESETPALETTE',
+  'WATCHDOG', 'JSONREAD',
 ];
 
 // Types that may be written in a declaration, plus the read-only board objects.
@@ -92,7 +94,7 @@ const BUILTIN_TYPES = [
   'BIGSTR', 'BOOLEAN', 'BYTE', 'DATE', 'DDATE', 'DOUBLE', 'DREAL', 'DWORD', 'EDATE', 'FLOAT',
   'INTEGER', 'INT', 'LONG', 'MONEY', 'MSGAREAID', 'REAL', 'SBYTE', 'SDWORD', 'SHORT', 'STRING',
   'SWORD', 'TIME', 'UBYTE', 'UDWORD', 'UNSIGNED', 'UWORD', 'WORD',
-  'AREA', 'CONFERENCE', 'CONTACT', 'DIRECTORY', 'DOOR', 'ERROR', 'EVENT', 'PASSWORD', 'TERMINFO', 'TERMSTATE',
+  'AREA', 'CONFERENCE', 'CONTACT', 'DIRECTORY', 'DOOR', 'ERROR', 'EVENT', 'JSON', 'PASSWORD', 'TERMINFO', 'TERMSTATE',
 ];
 
 // Built-in constants, taken from BUILTIN_CONSTS. TRUE and FALSE are literals of
@@ -109,8 +111,10 @@ const BUILTIN_CONSTANTS = [
   'GFX_CAP_SIXEL', 'GFX_CAP_JXL', 'GFX_CAP_JXL_BLOB', 'GFX_CAP_PIXEL_MOUSE', 'GFX_CAP_CLIENT_BLIT',
   'GFX_CAP_PHYSICAL_KEYS', 'GFX_CAP_AUDIO',
   'ERR_OK', 'ERR_UNAVAILABLE', 'ERR_INVALID', 'ERR_IO', 'ERR_FORMAT', 'ERR_LIMIT',
-  'ERR_UNSUPPORTED', 'ERR_STACK', 'ERR_KIND_NONE', 'ERR_KIND_FILE', 'ERR_KIND_DBASE',
-  'ERR_KIND_STACK', 'ERR_KIND_GFX', 'ERR_KIND_FONT', 'ERR_KIND_SOUND', 'FONT_ALL',
+  'ERR_UNSUPPORTED', 'ERR_STACK', 'ERR_DENIED', 'ERR_KIND_NONE', 'ERR_KIND_FILE', 'ERR_KIND_DBASE',
+  'ERR_KIND_STACK', 'ERR_KIND_GFX', 'ERR_KIND_FONT', 'ERR_KIND_SOUND', 'ERR_KIND_SANDBOX', 'ERR_KIND_JSON',
+  'ERR_KIND_WEB',
+  'JSON_NULL', 'JSON_BOOLEAN', 'JSON_NUMBER', 'JSON_STRING', 'JSON_ARRAY', 'JSON_OBJECT', 'FONT_ALL',
   'GRAPH', 'GUIDE', 'HDR_ACTIVE', 'HDR_BLOCKS',
   'HDR_DATE', 'HDR_ECHO', 'HDR_FROM', 'HDR_MSGNUM', 'HDR_MSGREF', 'HDR_PWD', 'HDR_REPLY',
   'HDR_RPLYDATE', 'HDR_RPLYTIME', 'HDR_STATUS', 'HDR_SUBJ', 'HDR_TIME', 'HDR_TO', 'HIGHASCII',
@@ -166,6 +170,8 @@ module.exports = grammar({
     source_file: $ => repeat($._top_level_item),
 
     _top_level_item: $ => choice(
+      $.module_declaration,
+      $.import_statement,
       $.type_declaration,
       $.enum_declaration,
       $.function_declaration,
@@ -218,6 +224,31 @@ module.exports = grammar({
     substitution: $ => token(seq(';#', /[A-Za-z_][A-Za-z0-9_]*/)),
 
     // ---------- Declarations ----------
+    module_declaration: $ => seq(
+      kw('MODULE'),
+      field('name', $.identifier),
+      repeat($.module_member),
+      endKw('MODULE'),
+    ),
+
+    // A member is private unless it says otherwise.
+    module_member: $ => seq(
+      optional(field('visibility', choice(kw('PUBLIC'), kw('PRIVATE')))),
+      choice(
+        $.function_declaration,
+        $.procedure_declaration,
+        $.function_definition,
+        $.procedure_definition,
+        $.variable_declaration,
+        $.const_declaration,
+      ),
+    ),
+
+    import_statement: $ => seq(
+      kw('IMPORT'),
+      field('module', $.identifier),
+    ),
+
     type_declaration: $ => seq(
       kw('TYPE'),
       field('name', $.identifier),
@@ -300,7 +331,13 @@ module.exports = grammar({
       $.parameter_list,
     ),
 
-    _type: $ => choice($.builtin_type, alias($.identifier, $.type_identifier)),
+    _type: $ => choice($.builtin_type, $.collection_type, alias($.identifier, $.type_identifier)),
+
+    // 4.00: `LIST OF <type>` and `MAP OF STRING TO <type>`.
+    collection_type: $ => choice(
+      seq(kw('LIST'), kw('OF'), field('element', $._type)),
+      seq(kw('MAP'), kw('OF'), field('key', $._type), kw('TO'), field('element', $._type)),
+    ),
 
     builtin_type: $ => choice(...BUILTIN_TYPES.map(t => kw(t))),
 
@@ -316,6 +353,8 @@ module.exports = grammar({
       $.repeat_statement,
       $.loop_statement,
       $.for_statement,
+      $.for_each_statement,
+      $.try_statement,
       $.select_statement,
       $.goto_statement,
       $.gosub_statement,
@@ -439,6 +478,26 @@ module.exports = grammar({
       optional(field('variable_end', $.identifier)),
     )),
 
+    for_each_statement: $ => prec.right(seq(
+      kw('FOR'),
+      kw('EACH'),
+      field('variable', $.identifier),
+      kw('IN'),
+      field('collection', $._expression),
+      field('body', repeat($._statement)),
+      choice(kw('NEXT'), endKw('FOR')),
+      optional(field('variable_end', $.identifier)),
+    )),
+
+    try_statement: $ => seq(
+      kw('TRY'),
+      field('body', repeat($._statement)),
+      kw('CATCH'),
+      optional(field('error', $.identifier)),
+      field('handler', repeat($._statement)),
+      endKw('TRY'),
+    ),
+
     select_statement: $ => seq(
       kw('SELECT'),
       optional(kw('CASE')),
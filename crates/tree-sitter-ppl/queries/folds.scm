@@ -1,6 +1,7 @@
 ; Foldable regions.
 
 [
+  (module_declaration)
   (function_definition)
   (procedure_definition)
   (type_declaration)
@@ -12,6 +13,8 @@
   (repeat_statement)
   (loop_statement)
   (for_statement)
+  (for_each_statement)
+  (try_statement)
   (select_statement)
   (case_clause)
   (default_clause)
@@ -17,9 +17,12 @@
 
 ; ---------- Types ----------
 (builtin_type) @type.builtin
+(collection_type ["LIST" "MAP" "OF" "TO"] @type.builtin)
 (type_identifier) @type
 
 ; ---------- Declarations ----------
+(module_declaration name: (identifier) @module @namespace)
+(import_statement module: (identifier) @module @namespace)
 (type_declaration name: (identifier) @type)
 (enum_declaration name: (identifier) @type)
 (enum_variant name: (identifier) @constant)
@@ -50,6 +53,9 @@
 ; ---------- Loop variables and labels ----------
 (for_statement variable: (identifier) @variable)
 (for_statement variable_end: (identifier) @variable)
+(for_each_statement variable: (identifier) @variable)
+(for_each_statement variable_end: (identifier) @variable)
+(try_statement error: (identifier) @variable)
 (label) @label
 (goto_statement label: (identifier) @label)
 (gosub_statement label: (identifier) @label)
@@ -92,6 +98,8 @@
   "FOR"
   "TO"
   "STEP"
+  "EACH"
+  "IN"
   "NEXT"
   "ENDFOR"
   "BREAK"
@@ -105,6 +113,12 @@
 
 "RETURN" @keyword.return @keyword.control.return
 
+[
+  "TRY"
+  "CATCH"
+  "ENDTRY"
+] @keyword.exception @keyword.control.exception
+
 [
   "DECLARE"
   "FUNCTION"
@@ -122,6 +136,17 @@
   "ENDENUM"
 ] @keyword.type @keyword.storage.type
 
+[
+  "MODULE"
+  "ENDMODULE"
+  "IMPORT"
+] @keyword.import @keyword.control.import
+
+[
+  "PUBLIC"
+  "PRIVATE"
+] @keyword.modifier @keyword.storage.modifier
+
 "CONST" @keyword @keyword.storage.modifier
 
 (const_declaration name: (identifier) @constant)
@@ -4,6 +4,7 @@
 ; @indent.branch, Helix reads @indent/@outdent. Each ignores the other's names.
 
 [
+  (module_declaration)
   (function_definition)
   (procedure_definition)
   (type_declaration)
@@ -15,6 +16,8 @@
   (repeat_statement)
   (loop_statement)
   (for_statement)
+  (for_each_statement)
+  (try_statement)
   (select_statement)
   (case_clause)
   (default_clause)
@@ -27,6 +30,7 @@
   "CASE"
   "DEFAULT"
   "UNTIL"
+  "CATCH"
 ] @indent.branch @outdent
 
 [
@@ -34,9 +38,11 @@
   "ENDWHILE"
   "ENDLOOP"
   "ENDSELECT"
+  "ENDMODULE"
   "ENDTYPE"
   "ENDENUM"
   "ENDFOR"
+  "ENDTRY"
   "ENDFUNC"
   "ENDFUNCTION"
   "ENDPROC"
@@ -11,6 +11,7 @@
 (const_declaration name: (identifier) @local.definition.constant)
 (field_declaration name: (identifier) @local.definition.field)
 
+(module_declaration name: (identifier) @local.definition.namespace)
 (type_declaration name: (identifier) @local.definition.type)
 (enum_declaration name: (identifier) @local.definition.type)
 (enum_variant name: (identifier) @local.definition.constant)
@@ -0,0 +1,92 @@
+import * as vscode from "vscode";
+import * as net from "net";
+import * as path from "path";
+
+import { buildPpe, commandLine, stopPreviousRun } from "./run";
+
+/// The port `icboard --debug-ppe` listens on unless told otherwise.
+const DEFAULT_PORT = 4711;
+
+const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+/// The board opens its port once it has loaded its configuration, which takes
+/// a moment after the terminal appears. It ignores a connection that closes
+/// without saying anything, so knocking does not use up the debugger's.
+async function waitForPort(terminal: vscode.Terminal, port: number): Promise<boolean> {
+  for (let waited = 0; waited < 10000 && terminal.exitStatus === undefined; waited += 200) {
+    const open = await new Promise<boolean>((resolve) => {
+      const socket = net.connect(port, "127.0.0.1");
+      socket.once("connect", () => {
+        socket.destroy();
+        resolve(true);
+      });
+      socket.once("error", () => resolve(false));
+    });
+    if (open) {
+      return true;
+    }
+    await sleep(200);
+  }
+  return false;
+}
+
+/// Fills in a configuration for F5 on a .pps without a launch.json.
+class PplConfigurationProvider implements vscode.DebugConfigurationProvider {
+  resolveDebugConfiguration(_folder: vscode.WorkspaceFolder | undefined, config: vscode.DebugConfiguration): vscode.DebugConfiguration | undefined {
+    if (!config.type && !config.request && !config.name) {
+      const editor = vscode.window.activeTextEditor;
+      if (editor?.document.languageId !== "ppl") {
+        return undefined;
+      }
+      config.type = "ppl";
+      config.name = "Debug PPE";
+      config.request = "launch";
+      config.stopOnEntry = false;
+    }
+    return config;
+  }
+}
+
+/// Builds the program with debug information, starts a board that runs it under
+/// the debugger and hands VS Code the port the board listens on.
+class PplDebugAdapterFactory implements vscode.DebugAdapterDescriptorFactory {
+  constructor(private readonly output: vscode.OutputChannel) {}
+
+  async createDebugAdapterDescriptor(session: vscode.DebugSession): Promise<vscode.DebugAdapterDescriptor | undefined> {
+    const config = session.configuration;
+    const program: string | undefined = config.program || vscode.window.activeTextEditor?.document.fileName;
+    if (!program || !program.toLowerCase().endsWith(".pps")) {
+      throw new Error("PPL: say which .pps to debug with \"program\" in the launch configuration.");
+    }
+    const build = await buildPpe(this.output, { source: program, debug: true });
+    if (!build) {
+      return undefined;
+    }
+
+    const port: number = config.port ?? DEFAULT_PORT;
+    const parts = [build.board, "--debug-ppe", build.ppe, "--debug-port", `${port}`, build.boardConfig];
+
+    const name = "PPL Debug";
+    await stopPreviousRun(name);
+    this.output.appendLine(`Debugging ${commandLine(parts)}`);
+    const terminal = vscode.window.createTerminal({
+      name,
+      cwd: build.workspaceFolder ?? vscode.Uri.file(path.dirname(build.source)),
+      shellPath: parts[0],
+      shellArgs: parts.slice(1),
+    });
+    terminal.show(true);
+
+    if (!(await waitForPort(terminal, port))) {
+      throw new Error(`PPL: the board did not start. See the ${name} terminal.`);
+    }
+    return new vscode.DebugAdapterServer(port);
+  }
+}
+
+export function registerDebugger(context: vscode.ExtensionContext, output: vscode.OutputChannel): void {
+  context.subscriptions.push(
+    vscode.debug.registerDebugConfigurationProvider("ppl", new PplConfigurationProvider()),
+    vscode.debug.registerDebugAdapterDescriptorFactory("ppl", new PplDebugAdapterFactory(output)),
+  );
+}
@@ -5,6 +5,7 @@ import { Executable, LanguageClient, LanguageClientOptions, ServerOptions } from
 
 import { runPpe } from "./run";
 import { binary } from "./binaries";
+import { registerDebugger } from "./debug";
 
 let client: LanguageClient | undefined;
 
@@ -42,6 +43,7 @@ export async function activate(context: vscode.ExtensionContext) {
   context.subscriptions.push(
     vscode.commands.registerCommand("ppl.runFile", () => runPpe(output, { singleFile: true })),
   );
+  registerDebugger(context, output);
 
   const command = serverCommand(context);
   const run: Executable = { command };
@@ -33,10 +33,10 @@ function configOf(compiler: string, target: string): Promise<CompilerConfig> {
   });
 }
 
-function compile(compiler: string, target: string): Promise<string> {
+function compile(compiler: string, target: string, debug: boolean): Promise<string> {
   return new Promise((resolve, reject) => {
     // The output pane shows escapes rather than colour, so ask for plain text.
-    execFile(compiler, ["--mono", target], { cwd: path.dirname(target) }, (error, stdout, stderr) => {
+    execFile(compiler, debug ? ["--mono", "--debug", target] : ["--mono", target], { cwd: path.dirname(target) }, (error, stdout, stderr) => {
       const output = `${stdout}${stderr}`.trim();
       if (error) {
         reject(new Error(output || error.message));
@@ -48,7 +48,7 @@ function compile(compiler: string, target: string): Promise<string> {
 }
 
 /// The terminal takes a command line, so anything with a space needs quoting.
-function commandLine(parts: string[]): string {
+export function commandLine(parts: string[]): string {
   const quote = (part: string) =>
     process.platform === "win32" ? (/[\s&|<>^]/.test(part) ? `"${part}"` : part) : `'${part.replace(/'/g, `'\\''`)}'`;
   return parts.map(quote).join(" ");
@@ -86,7 +86,7 @@ async function waitForExit(pid: number): Promise<void> {
 /// A board holds the lock on its directory for as long as it runs, so the next
 /// run waits for the process itself rather than only for its terminal, which
 /// closes without having reaped it yet.
-async function stopPreviousRun(name: string): Promise<void> {
+export async function stopPreviousRun(name: string): Promise<void> {
   const previous = vscode.window.terminals.filter((terminal) => terminal.name === name);
   // Only a terminal that has not reported an exit still owns its process id; a
   // finished one may name a number the system has given away since.
@@ -100,18 +100,35 @@ async function stopPreviousRun(name: string): Promise<void> {
   }
 }
 
-/// Builds the open source and runs what came out of it.
-///
-/// The board takes over the terminal it runs in - a PPE asks its caller
-/// questions - so this gets a real terminal rather than a task.
-export async function runPpe(output: vscode.OutputChannel, options: { singleFile?: boolean } = {}): Promise<void> {
-  const document = vscode.window.activeTextEditor?.document;
-  if (!document || document.uri.scheme !== "file" || !document.fileName.toLowerCase().endsWith(".pps")) {
-    vscode.window.showErrorMessage("PPL: open the .pps you want to run.");
-    return;
-  }
-  if (!(await document.save())) {
-    return;
+/// A PPE that was built and the board it runs on.
+export interface Build {
+  board: string;
+  boardConfig: string;
+  source: string;
+  ppe: string;
+  workspaceFolder?: vscode.Uri;
+  from: string;
+}
+
+/// Builds the open source, or the project it belongs to, and finds the board to
+/// run the result on. Whatever goes wrong has been reported when this gives up.
+export async function buildPpe(
+  output: vscode.OutputChannel,
+  options: { singleFile?: boolean; debug?: boolean; source?: string } = {},
+): Promise<Build | undefined> {
+  let source = options.source;
+  if (!source) {
+    const document = vscode.window.activeTextEditor?.document;
+    if (!document || document.uri.scheme !== "file" || !document.fileName.toLowerCase().endsWith(".pps")) {
+      vscode.window.showErrorMessage("PPL: open the .pps you want to run.");
+      return undefined;
+    }
+    if (!(await document.save())) {
+      return undefined;
+    }
+    source = document.fileName;
+  } else {
+    await vscode.workspace.saveAll(false);
   }
 
   const compiler = binary("pplc");
@@ -120,15 +137,14 @@ export async function runPpe(output: vscode.OutputChannel, options: { singleFile
   // rather than a shell error nobody reads.
   if (!locate(compiler)) {
     await reportMissing(compiler, "the IcyBoard programs");
-    return;
+    return undefined;
   }
   if (!locate(board)) {
     await reportMissing(board, "the IcyBoard programs");
-    return;
+    return undefined;
   }
 
-  const source = document.fileName;
-  const workspaceFolder = vscode.workspace.getWorkspaceFolder(document.uri)?.uri;
+  const workspaceFolder = vscode.workspace.getWorkspaceFolder(vscode.Uri.file(source))?.uri;
   // A file that belongs to a project is built as part of it, so the manifest's
   // language version, defines and data files apply instead of bare defaults.
   const manifest = options.singleFile ? undefined : findManifest(path.dirname(source), workspaceFolder?.fsPath);
@@ -148,20 +164,20 @@ export async function runPpe(output: vscode.OutputChannel, options: { singleFile
     plan = await configOf(compiler, target);
   } catch (error) {
     await report(error, `the configuration of ${path.basename(target)} could not be read.`);
-    return;
+    return undefined;
   }
 
   if (manifest) {
     output.appendLine(`Building project ${manifest}`);
   }
   try {
-    const built = await compile(compiler, target);
+    const built = await compile(compiler, target, options.debug ?? false);
     if (built) {
       output.appendLine(built);
     }
   } catch (error) {
     await report(error, `${path.basename(target)} did not build.`);
-    return;
+    return undefined;
   }
 
   const from = workspaceFolder?.fsPath ?? path.dirname(source);
@@ -169,17 +185,29 @@ export async function runPpe(output: vscode.OutputChannel, options: { singleFile
   const boardConfig = locateBoardConfig(configured || undefined, from);
   if (!boardConfig) {
     await reportMissingBoard();
+    return undefined;
+  }
+  return { board, boardConfig, source, ppe: plan.output, workspaceFolder, from };
+}
+
+/// Builds the open source and runs what came out of it.
+///
+/// The board takes over the terminal it runs in - a PPE asks its caller
+/// questions - so this gets a real terminal rather than a task.
+export async function runPpe(output: vscode.OutputChannel, options: { singleFile?: boolean } = {}): Promise<void> {
+  const build = await buildPpe(output, options);
+  if (!build) {
     return;
   }
 
   const parts = [
-    board,
+    build.board,
     ...runArguments({
-      ppe: plan.output,
-      source,
-      workspaceFolder: from,
+      ppe: build.ppe,
+      source: build.source,
+      workspaceFolder: build.from,
     }),
-    boardConfig,
+    build.boardConfig,
   ];
 
   const name = "PPL";
@@ -189,7 +217,7 @@ export async function runPpe(output: vscode.OutputChannel, options: { singleFile
   // Started without a shell, so the terminal's process id is the board's own.
   const terminal = vscode.window.createTerminal({
     name,
-    cwd: workspaceFolder ?? vscode.Uri.file(path.dirname(source)),
+    cwd: build.workspaceFolder ?? vscode.Uri.file(path.dirname(build.source)),
     shellPath: parts[0],
     shellArgs: parts.slice(1),
   });